@@ -19,7 +19,6 @@ use unicase::UniCase;
 use hyper::{server, net, Decoder, Encoder, Next, Control};
 use hyper::header;
 use hyper::method::Method;
-use hyper::header::AccessControlAllowOrigin;
 
 use api::types::{App, ApiError};
 use api::response;
@@ -27,19 +26,16 @@ use apps::fetcher::ContentFetcher;
 
 use handlers::extract_url;
 use endpoint::{Endpoint, Endpoints, Handler, EndpointPath};
-use jsonrpc_http_server::cors;
 
 #[derive(Clone)]
 pub struct RestApi {
-	cors_domains: Option<Vec<AccessControlAllowOrigin>>,
 	endpoints: Arc<Endpoints>,
 	fetcher: Arc<ContentFetcher>,
 }
 
 impl RestApi {
-	pub fn new(cors_domains: Vec<String>, endpoints: Arc<Endpoints>, fetcher: Arc<ContentFetcher>) -> Box<Endpoint> {
+	pub fn new(endpoints: Arc<Endpoints>, fetcher: Arc<ContentFetcher>) -> Box<Endpoint> {
 		Box::new(RestApi {
-			cors_domains: Some(cors_domains.into_iter().map(AccessControlAllowOrigin::Value).collect()),
 			endpoints: endpoints,
 			fetcher: fetcher,
 		})
@@ -60,7 +56,6 @@ impl Endpoint for RestApi {
 
 struct RestApiRouter {
 	api: RestApi,
-	origin: Option<String>,
 	path: Option<EndpointPath>,
 	control: Option<Control>,
 	handler: Box<Handler>,
@@ -70,7 +65,6 @@ impl RestApiRouter {
 	fn new(api: RestApi, path: EndpointPath, control: Control) -> Self {
 		RestApiRouter {
 			path: Some(path),
-			origin: None,
 			control: Some(control),
 			api: api,
 			handler: response::as_json_error(&ApiError {
@@ -90,7 +84,8 @@ impl RestApiRouter {
 		}
 	}
 
-	/// Returns basic headers for a response (it may be overwritten by the handler)
+	/// Returns basic headers for a response (it may be overwritten by the handler).
+	/// `Access-Control-Allow-Origin` is set by the server's access control.
 	fn response_headers(&self) -> header::Headers {
 		let mut headers = header::Headers::new();
 		headers.set(header::AccessControlAllowCredentials);
@@ -105,10 +100,6 @@ impl RestApiRouter {
 			UniCase("accept".to_owned()),
 		]));
 
-		if let Some(cors_header) = cors::get_cors_header(&self.api.cors_domains, &self.origin) {
-			headers.set(cors_header);
-		}
-
 		headers
 	}
 }
@@ -116,8 +107,6 @@ impl RestApiRouter {
 impl server::Handler<net::HttpStream> for RestApiRouter {
 
 	fn on_request(&mut self, request: server::Request<net::HttpStream>) -> Next {
-		self.origin = cors::read_origin(&request);
-
 		if let Method::Options = *request.method() {
 			self.handler = response::empty();
 			return Next::write();
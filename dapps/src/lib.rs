@@ -91,7 +91,7 @@ use std::collections::HashMap;
 use hash_fetch::urlhint::ContractClient;
 use jsonrpc_core::{IoHandler, IoDelegate};
 use router::auth::{Authorization, NoAuth, HttpBasicAuth};
-use ethcore_rpc::Extendable;
+use ethcore_rpc::{Extendable, AccessControl, AccessPolicy};
 
 use self::apps::{HOME_PAGE, DAPPS_DOMAIN};
 
@@ -105,13 +105,26 @@ impl<F> SyncStatus for F where F: Fn() -> bool + Send + Sync {
 	fn is_major_importing(&self) -> bool { self() }
 }
 
+/// Notified whenever an authorized request is handled,
+/// so that a dormant node can be woken up.
+pub trait Activity: Send + Sync {
+	/// Called on every authorized request.
+	fn notify(&self);
+}
+
+impl<F> Activity for F where F: Fn() + Send + Sync {
+	fn notify(&self) { self() }
+}
+
 /// Webapps HTTP+RPC server build.
 pub struct ServerBuilder {
 	dapps_path: String,
 	handler: Arc<IoHandler>,
 	registrar: Arc<ContractClient>,
 	sync_status: Arc<SyncStatus>,
+	activity: Arc<Activity>,
 	signer_address: Option<(String, u16)>,
+	access_policy: Arc<AccessPolicy>,
 }
 
 impl Extendable for ServerBuilder {
@@ -128,7 +141,9 @@ impl ServerBuilder {
 			handler: Arc::new(IoHandler::new()),
 			registrar: registrar,
 			sync_status: Arc::new(|| false),
+			activity: Arc::new(|| ()),
 			signer_address: None,
+			access_policy: Arc::new(AccessPolicy::new(None, None)),
 		}
 	}
 
@@ -137,11 +152,21 @@ impl ServerBuilder {
 		self.sync_status = status;
 	}
 
+	/// Set activity notifier.
+	pub fn with_activity(&mut self, activity: Arc<Activity>) {
+		self.activity = activity;
+	}
+
 	/// Change default signer port.
 	pub fn with_signer_address(&mut self, signer_address: Option<(String, u16)>) {
 		self.signer_address = signer_address;
 	}
 
+	/// Share CORS domains of given policy, e.g. the one of the JSON-RPC server.
+	pub fn with_access_policy(&mut self, access_policy: Arc<AccessPolicy>) {
+		self.access_policy = access_policy;
+	}
+
 	/// Asynchronously start server with no authentication,
 	/// returns result with `Server` handle on success or an error.
 	pub fn start_unsecured_http(&self, addr: &SocketAddr, hosts: Option<Vec<String>>) -> Result<Server, ServerError> {
@@ -154,6 +179,8 @@ impl ServerBuilder {
 			self.signer_address.clone(),
 			self.registrar.clone(),
 			self.sync_status.clone(),
+			self.activity.clone(),
+			self.access_policy.clone(),
 		)
 	}
 
@@ -169,6 +196,8 @@ impl ServerBuilder {
 			self.signer_address.clone(),
 			self.registrar.clone(),
 			self.sync_status.clone(),
+			self.activity.clone(),
+			self.access_policy.clone(),
 		)
 	}
 }
@@ -215,6 +244,8 @@ impl Server {
 		signer_address: Option<(String, u16)>,
 		registrar: Arc<ContractClient>,
 		sync_status: Arc<SyncStatus>,
+		activity: Arc<Activity>,
+		access_policy: Arc<AccessPolicy>,
 	) -> Result<Server, ServerError> {
 		let panic_handler = Arc::new(Mutex::new(None));
 		let authorization = Arc::new(authorization);
@@ -228,22 +259,30 @@ impl Server {
 			special.insert(router::SpecialEndpoint::Utils, apps::utils());
 			special.insert(
 				router::SpecialEndpoint::Api,
-				api::RestApi::new(cors_domains, endpoints.clone(), content_fetcher.clone())
+				api::RestApi::new(endpoints.clone(), content_fetcher.clone())
 			);
 			special
 		});
-		let hosts = Self::allowed_hosts(hosts, format!("{}", addr));
+		// dapps are always served on their own domains.
+		let hosts = Self::allowed_hosts(hosts, format!("{}", addr)).map(|mut hosts| {
+			hosts.push(format!("*{}", DAPPS_DOMAIN));
+			hosts
+		});
+		let access_policy = Arc::new(AccessPolicy::inherit(access_policy, hosts)
+			// NOTE [todr] This is required for error pages to be styled properly.
+			.override_hosts(&format!("/{}/", apps::UTILS_PATH), None)
+			.override_cors(&format!("/{}/", apps::API_PATH), Some(cors_domains)));
 
 		try!(hyper::Server::http(addr))
-			.handle(move |ctrl| router::Router::new(
+			.handle(move |ctrl| AccessControl::new(access_policy.clone(), router::Router::new(
 				ctrl,
 				signer_address.clone(),
 				content_fetcher.clone(),
 				endpoints.clone(),
 				special.clone(),
 				authorization.clone(),
-				hosts.clone(),
-			))
+				activity.clone(),
+			)).with_rejection(router::host_invalid_response))
 			.map(|(l, srv)| {
 
 				::std::thread::spawn(move || {
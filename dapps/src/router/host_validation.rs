@@ -15,28 +15,11 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 
-use apps::DAPPS_DOMAIN;
-use hyper::{server, header, StatusCode};
+use hyper::{server, StatusCode};
 use hyper::net::HttpStream;
 
-use jsonrpc_http_server::{is_host_header_valid};
 use handlers::ContentHandler;
 
-pub fn is_valid(request: &server::Request<HttpStream>, allowed_hosts: &[String], endpoints: Vec<String>) -> bool {
-	let mut endpoints = endpoints.iter()
-		.map(|endpoint| format!("{}{}", endpoint, DAPPS_DOMAIN))
-		.collect::<Vec<String>>();
-	endpoints.extend_from_slice(allowed_hosts);
-
-	let header_valid = is_host_header_valid(request, &endpoints);
-
-	match (header_valid, request.headers().get::<header::Host>()) {
-		(true, _) => true,
-		(_, Some(host)) => host.hostname.ends_with(DAPPS_DOMAIN),
-		_ => false,
-	}
-}
-
 pub fn host_invalid_response() -> Box<server::Handler<HttpStream> + Send> {
 	Box::new(ContentHandler::error(StatusCode::Forbidden,
 		"Current Host Is Disallowed",
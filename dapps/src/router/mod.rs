@@ -20,6 +20,8 @@
 pub mod auth;
 mod host_validation;
 
+pub use self::host_validation::host_invalid_response;
+
 use address;
 use std::sync::Arc;
 use std::collections::HashMap;
@@ -31,6 +33,7 @@ use apps::fetcher::ContentFetcher;
 use endpoint::{Endpoint, Endpoints, EndpointPath};
 use handlers::{Redirection, extract_url, ContentHandler};
 use self::auth::{Authorization, Authorized};
+use Activity;
 
 /// Special endpoints are accessible on every domain (every dapp)
 #[derive(Debug, PartialEq, Hash, Eq)]
@@ -48,7 +51,7 @@ pub struct Router<A: Authorization + 'static> {
 	fetch: Arc<ContentFetcher>,
 	special: Arc<HashMap<SpecialEndpoint, Box<Endpoint>>>,
 	authorization: Arc<A>,
-	allowed_hosts: Option<Vec<String>>,
+	activity: Arc<Activity>,
 	handler: Box<server::Handler<HttpStream> + Send>,
 }
 
@@ -59,21 +62,9 @@ impl<A: Authorization + 'static> server::Handler<HttpStream> for Router<A> {
 		// Choose proper handler depending on path / domain
 		let url = extract_url(&req);
 		let endpoint = extract_endpoint(&url);
-		let is_utils = endpoint.1 == SpecialEndpoint::Utils;
 
 		trace!(target: "dapps", "Routing request to {:?}. Details: {:?}", url, req);
 
-		// Validate Host header
-		if let Some(ref hosts) = self.allowed_hosts {
-			trace!(target: "dapps", "Validating host headers against: {:?}", hosts);
-			let is_valid = is_utils || host_validation::is_valid(&req, hosts, self.endpoints.keys().cloned().collect());
-			if !is_valid {
-				debug!(target: "dapps", "Rejecting invalid host header.");
-				self.handler = host_validation::host_invalid_response();
-				return self.handler.on_request(req);
-			}
-		}
-
 		trace!(target: "dapps", "Checking authorization.");
 		// Check authorization
 		let auth = self.authorization.is_authorized(&req);
@@ -83,6 +74,8 @@ impl<A: Authorization + 'static> server::Handler<HttpStream> for Router<A> {
 			return self.handler.on_request(req);
 		}
 
+		self.activity.notify();
+
 		let control = self.control.take().expect("on_request is called only once; control is always defined at start; qed");
 		debug!(target: "dapps", "Handling endpoint request: {:?}", endpoint);
 		self.handler = match endpoint {
@@ -173,7 +166,7 @@ impl<A: Authorization> Router<A> {
 		endpoints: Arc<Endpoints>,
 		special: Arc<HashMap<SpecialEndpoint, Box<Endpoint>>>,
 		authorization: Arc<A>,
-		allowed_hosts: Option<Vec<String>>,
+		activity: Arc<Activity>,
 		) -> Self {
 
 		let handler = special.get(&SpecialEndpoint::Utils)
@@ -186,7 +179,7 @@ impl<A: Authorization> Router<A> {
 			fetch: content_fetcher,
 			special: special,
 			authorization: authorization,
-			allowed_hosts: allowed_hosts,
+			activity: activity,
 			handler: handler,
 		}
 	}
@@ -17,6 +17,7 @@
 use std::env;
 use std::str;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use rustc_serialize::hex::FromHex;
 use env_logger::LogBuilder;
 
@@ -93,6 +94,21 @@ pub fn serve_with_auth(user: &str, pass: &str) -> Server {
 	builder.start_basic_auth_http(&"127.0.0.1:0".parse().unwrap(), None, user, pass).unwrap()
 }
 
+pub fn serve_with_activity(user: &str, pass: &str) -> (Server, Arc<AtomicUsize>) {
+	init_logger();
+	let registrar = Arc::new(FakeRegistrar::new());
+	let mut dapps_path = env::temp_dir();
+	dapps_path.push("non-existent-dir-to-prevent-fs-files-from-loading");
+	let mut builder = ServerBuilder::new(dapps_path.to_str().unwrap().into(), registrar);
+	let activity = Arc::new(AtomicUsize::new(0));
+	let counter = activity.clone();
+	builder.with_activity(Arc::new(move || { counter.fetch_add(1, Ordering::SeqCst); }));
+	(
+		builder.start_basic_auth_http(&"127.0.0.1:0".parse().unwrap(), None, user, pass).unwrap(),
+		activity,
+	)
+}
+
 pub fn serve_hosts(hosts: Option<Vec<String>>) -> Server {
 	init_server(hosts, false).0
 }
@@ -21,13 +21,15 @@
 
 use primal::is_prime;
 use std::cell::Cell;
+use std::cmp::max;
 use std::mem;
 use std::ptr;
 use sha3;
 use std::slice;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::io::{self, Read, Write};
 use std::fs::{self, File};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use parking_lot::Mutex;
 
@@ -95,7 +97,18 @@ pub struct Light {
 impl Light {
 	/// Create a new light cache for a given block number
 	pub fn new(block_number: u64) -> Light {
-		light_new(block_number)
+		light_new(block_number, None)
+	}
+
+	/// Create a new light cache for a given block number, counting processed nodes in `progress`.
+	/// Generation is complete once `progress` reaches `Light::generation_steps(block_number)`.
+	pub fn new_with_progress(block_number: u64, progress: &AtomicUsize) -> Light {
+		light_new(block_number, Some(progress))
+	}
+
+	/// Number of steps it takes to generate the light cache for a given block number.
+	pub fn generation_steps(block_number: u64) -> usize {
+		get_cache_size(block_number) / NODE_BYTES * (1 + ETHASH_CACHE_ROUNDS)
 	}
 
 	/// Calculate the light boundary data
@@ -105,17 +118,23 @@ impl Light {
 		light_compute(self, header_hash, nonce)
 	}
 
-	pub fn file_path(seed_hash: H256) -> PathBuf {
+	/// Default directory to store light caches in.
+	pub fn default_cache_dir() -> PathBuf {
 		let mut home = ::std::env::home_dir().unwrap();
 		home.push(".ethash");
 		home.push("light");
-		home.push(to_hex(&seed_hash));
 		home
 	}
 
-	pub fn from_file(block_number: u64) -> io::Result<Light> {
+	pub fn file_path(cache_dir: &Path, seed_hash: H256) -> PathBuf {
+		let mut path = cache_dir.to_path_buf();
+		path.push(to_hex(&seed_hash));
+		path
+	}
+
+	pub fn from_file(cache_dir: &Path, block_number: u64) -> io::Result<Light> {
 		let seed_compute = SeedHashCompute::new();
-		let path = Light::file_path(seed_compute.get_seedhash(block_number));
+		let path = Light::file_path(cache_dir, seed_compute.get_seedhash(block_number));
 		let mut file = try!(File::open(path));
 
 		let cache_size = get_cache_size(block_number);
@@ -134,19 +153,9 @@ impl Light {
 		})
 	}
 
-	pub fn to_file(&self) -> io::Result<PathBuf> {
+	pub fn to_file(&self, cache_dir: &Path) -> io::Result<PathBuf> {
 		let seed_compute = self.seed_compute.lock();
-		let path = Light::file_path(seed_compute.get_seedhash(self.block_number));
-
-		if self.block_number >= ETHASH_EPOCH_LENGTH * 2 {
-			let deprecated = Light::file_path(
-				seed_compute.get_seedhash(self.block_number - ETHASH_EPOCH_LENGTH * 2));
-
-			if deprecated.exists() {
-				debug!(target: "ethash", "removing: {:?}", &deprecated);
-				try!(fs::remove_file(deprecated));
-			}
-		}
+		let path = Light::file_path(cache_dir, seed_compute.get_seedhash(self.block_number));
 
 		try!(fs::create_dir_all(path.parent().unwrap()));
 		let mut file = try!(File::create(&path));
@@ -156,6 +165,30 @@ impl Light {
 		try!(file.write(buf));
 		Ok(path)
 	}
+
+	/// Epochs up to and including `max_epoch` which have a cache file in `cache_dir`.
+	pub fn cached_epochs(cache_dir: &Path, max_epoch: u64) -> Vec<u64> {
+		let seed_compute = SeedHashCompute::new();
+		(0..max_epoch + 1)
+			.filter(|e| Light::file_path(cache_dir, seed_compute.get_seedhash(e * ETHASH_EPOCH_LENGTH)).exists())
+			.collect()
+	}
+
+	/// Remove cache files of epochs older than the last `keep_epochs` ones up to the epoch of `block_number`.
+	/// At least two epochs are always kept, so pruning after a pregenerated cache never drops the current one.
+	pub fn prune(cache_dir: &Path, block_number: u64, keep_epochs: u64) -> io::Result<()> {
+		let epoch = block_number / ETHASH_EPOCH_LENGTH;
+		let keep_epochs = max(keep_epochs, 2);
+		let seed_compute = SeedHashCompute::new();
+		for e in 0..(epoch + 1).saturating_sub(keep_epochs) {
+			let deprecated = Light::file_path(cache_dir, seed_compute.get_seedhash(e * ETHASH_EPOCH_LENGTH));
+			if deprecated.exists() {
+				debug!(target: "ethash", "removing: {:?}", &deprecated);
+				try!(fs::remove_file(deprecated));
+			}
+		}
+		Ok(())
+	}
 }
 
 pub struct SeedHashCompute {
@@ -341,7 +374,7 @@ fn calculate_dag_item(node_index: u32, cache: &[Node]) -> Node {
 	}
 }
 
-fn light_new(block_number: u64) -> Light {
+fn light_new(block_number: u64, progress: Option<&AtomicUsize>) -> Light {
 
 	let seed_compute = SeedHashCompute::new();
 	let seedhash = seed_compute.get_seedhash(block_number);
@@ -359,6 +392,9 @@ fn light_new(block_number: u64) -> Light {
 		for i in 1..num_nodes {
 			sha3::sha3_512(nodes.get_unchecked_mut(i).bytes.as_mut_ptr(), NODE_BYTES, nodes.get_unchecked(i - 1).bytes.as_ptr(), NODE_BYTES);
 		}
+		if let Some(progress) = progress {
+			progress.fetch_add(num_nodes, Ordering::Relaxed);
+		}
 
 		for _ in 0..ETHASH_CACHE_ROUNDS {
 			for i in 0..num_nodes {
@@ -369,6 +405,9 @@ fn light_new(block_number: u64) -> Light {
 				}
 				sha3_512(&data.bytes, &mut nodes.get_unchecked_mut(i).bytes);
 			}
+			if let Some(progress) = progress {
+				progress.fetch_add(num_nodes, Ordering::Relaxed);
+			}
 		}
 	}
 
@@ -472,15 +511,38 @@ fn test_seed_compute_after_newer() {
 
 #[test]
 fn test_drop_old_data() {
-	let first = Light::new(0).to_file().unwrap();
+	let cache_dir = Light::default_cache_dir();
+	let first = Light::new(0).to_file(&cache_dir).unwrap();
 
-	let second = Light::new(ETHASH_EPOCH_LENGTH).to_file().unwrap();
+	let second = Light::new(ETHASH_EPOCH_LENGTH).to_file(&cache_dir).unwrap();
+	Light::prune(&cache_dir, ETHASH_EPOCH_LENGTH, 2).unwrap();
 	assert!(fs::metadata(&first).is_ok());
 
-	let _ = Light::new(ETHASH_EPOCH_LENGTH * 2).to_file();
+	let _ = Light::new(ETHASH_EPOCH_LENGTH * 2).to_file(&cache_dir);
+	Light::prune(&cache_dir, ETHASH_EPOCH_LENGTH * 2, 2).unwrap();
 	assert!(fs::metadata(&first).is_err());
 	assert!(fs::metadata(&second).is_ok());
 
-	let _ = Light::new(ETHASH_EPOCH_LENGTH * 3).to_file();
+	let _ = Light::new(ETHASH_EPOCH_LENGTH * 3).to_file(&cache_dir);
+	Light::prune(&cache_dir, ETHASH_EPOCH_LENGTH * 3, 2).unwrap();
 	assert!(fs::metadata(&second).is_err());
+	assert!(Light::cached_epochs(&cache_dir, 3).contains(&3));
+}
+
+#[test]
+fn test_prune_keeps_two_epochs() {
+	let cache_dir = Light::default_cache_dir();
+	let current = Light::new(ETHASH_EPOCH_LENGTH * 4).to_file(&cache_dir).unwrap();
+	let next = Light::new(ETHASH_EPOCH_LENGTH * 5).to_file(&cache_dir).unwrap();
+	Light::prune(&cache_dir, ETHASH_EPOCH_LENGTH * 5, 1).unwrap();
+	assert!(fs::metadata(&current).is_ok());
+	assert!(fs::metadata(&next).is_ok());
+}
+
+#[test]
+fn test_generation_progress() {
+	let progress = AtomicUsize::new(0);
+	let light = Light::new_with_progress(0, &progress);
+	assert_eq!(progress.load(Ordering::Relaxed), Light::generation_steps(0));
+	assert_eq!(light.cache.len() * (1 + ETHASH_CACHE_ROUNDS), Light::generation_steps(0));
 }
@@ -25,11 +25,78 @@ extern crate log;
 mod compute;
 
 use std::mem;
+use std::cmp::{min, max};
+use std::path::PathBuf;
+use std::thread;
 use compute::Light;
 pub use compute::{ETHASH_EPOCH_LENGTH, H256, ProofOfWork, SeedHashCompute, quick_get_difficulty, slow_get_seedhash};
 
 use std::sync::Arc;
-use parking_lot::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use parking_lot::{Mutex, RwLock};
+
+/// Light cache storage options.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheOptions {
+	/// Directory to store the light caches in.
+	pub cache_dir: PathBuf,
+	/// Number of most recent epochs to keep the caches of on disk.
+	pub keep_epochs: u64,
+	/// Generate the cache of the next epoch in the background ahead of time.
+	pub pregenerate: bool,
+}
+
+impl Default for CacheOptions {
+	fn default() -> Self {
+		CacheOptions {
+			cache_dir: Light::default_cache_dir(),
+			keep_epochs: 2,
+			pregenerate: false,
+		}
+	}
+}
+
+/// Light cache status.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheStatus {
+	/// Epoch of the most recently used cache.
+	pub current_epoch: Option<u64>,
+	/// Epoch of the cache being generated and the percentage done.
+	pub generating: Option<(u64, u8)>,
+	/// Epochs which have a cache stored on disk.
+	pub cached_epochs: Vec<u64>,
+}
+
+struct Generation {
+	epoch: u64,
+	steps: usize,
+	done: Arc<AtomicUsize>,
+}
+
+/// Load the light cache from disk or generate (and store) it, tracking the progress in `generating`.
+fn load_or_generate(options: &CacheOptions, block_number: u64, generating: &Mutex<Option<Generation>>) -> Light {
+	match Light::from_file(&options.cache_dir, block_number) {
+		Ok(light) => light,
+		Err(e) => {
+			debug!("Light cache file not found for {}:{}", block_number, e);
+			let done = Arc::new(AtomicUsize::new(0));
+			*generating.lock() = Some(Generation {
+				epoch: block_number / ETHASH_EPOCH_LENGTH,
+				steps: Light::generation_steps(block_number),
+				done: done.clone(),
+			});
+			let light = Light::new_with_progress(block_number, &done);
+			*generating.lock() = None;
+			if let Err(e) = light.to_file(&options.cache_dir) {
+				warn!("Light cache file write error: {}", e);
+			}
+			if let Err(e) = Light::prune(&options.cache_dir, block_number, options.keep_epochs) {
+				warn!("Error removing old light cache files: {}", e);
+			}
+			light
+		}
+	}
+}
 
 struct LightCache {
 	recent_epoch: Option<u64>,
@@ -38,9 +105,45 @@ struct LightCache {
 	prev: Option<Arc<Light>>,
 }
 
+impl LightCache {
+	/// Get the cache for given epoch, making it the most recent one.
+	fn get(&mut self, epoch: u64) -> Option<Arc<Light>> {
+		match self.recent_epoch.clone() {
+			Some(ref e) if *e == epoch => self.recent.clone(),
+			_ => match self.prev_epoch.clone() {
+				Some(e) if e == epoch => {
+					// don't swap if recent is newer.
+					if self.recent_epoch > self.prev_epoch {
+						None
+					} else {
+						// swap
+						let t = self.prev_epoch;
+						self.prev_epoch = self.recent_epoch;
+						self.recent_epoch = t;
+						let t = self.prev.clone();
+						self.prev = self.recent.clone();
+						self.recent = t;
+						self.recent.clone()
+					}
+				}
+				_ => None,
+			},
+		}
+	}
+
+	fn insert(&mut self, epoch: u64, light: Arc<Light>) {
+		self.prev_epoch = mem::replace(&mut self.recent_epoch, Some(epoch));
+		self.prev = mem::replace(&mut self.recent, Some(light));
+	}
+}
+
 /// Light/Full cache manager.
 pub struct EthashManager {
 	cache: Mutex<LightCache>,
+	generation: Arc<Mutex<()>>,
+	generating: Arc<Mutex<Option<Generation>>>,
+	options: RwLock<CacheOptions>,
+	pregenerated_epoch: Mutex<Option<u64>>,
 }
 
 impl EthashManager {
@@ -53,6 +156,55 @@ impl EthashManager {
 				prev_epoch: None,
 				prev: None,
 			}),
+			generation: Arc::new(Mutex::new(())),
+			generating: Arc::new(Mutex::new(None)),
+			options: RwLock::new(CacheOptions::default()),
+			pregenerated_epoch: Mutex::new(None),
+		}
+	}
+
+	/// Change the light cache storage options.
+	pub fn set_options(&self, options: CacheOptions) {
+		*self.options.write() = options;
+	}
+
+	/// Current light cache status.
+	pub fn status(&self) -> CacheStatus {
+		let current_epoch = self.cache.lock().recent_epoch;
+		let generating = self.generating.lock().as_ref().map(|g| {
+			(g.epoch, min(100, g.done.load(Ordering::Relaxed) * 100 / max(1, g.steps)) as u8)
+		});
+		let max_epoch = max(current_epoch.unwrap_or(0), generating.map_or(0, |g| g.0)) + 1;
+		CacheStatus {
+			current_epoch: current_epoch,
+			generating: generating,
+			cached_epochs: Light::cached_epochs(&self.options.read().cache_dir, max_epoch),
+		}
+	}
+
+	/// Generate the cache of given epoch in the background unless it was requested before.
+	fn pregenerate(&self, epoch: u64) {
+		{
+			let mut pregenerated = self.pregenerated_epoch.lock();
+			if *pregenerated >= Some(epoch) {
+				return;
+			}
+			*pregenerated = Some(epoch);
+		}
+
+		let options = self.options.read().clone();
+		let generation = self.generation.clone();
+		let generating = self.generating.clone();
+		let spawned = thread::Builder::new().name("Ethash cache".into()).spawn(move || {
+			let _generating = generation.lock();
+			let block_number = epoch * ETHASH_EPOCH_LENGTH;
+			if !Light::file_path(&options.cache_dir, slow_get_seedhash(block_number)).exists() {
+				info!("Pregenerating light cache for epoch {}", epoch);
+				load_or_generate(&options, block_number, &generating);
+			}
+		});
+		if let Err(e) = spawned {
+			warn!("Error spawning light cache generation thread: {}", e);
 		}
 	}
 
@@ -61,51 +213,33 @@ impl EthashManager {
 	/// `light` - The light client handler
 	/// `header_hash` - The header hash to pack into the mix
 	/// `nonce` - The nonce to pack into the mix
+	///
+	/// Safe to call from many verifier threads at once: lookups of cached epochs
+	/// never wait for a cache of another epoch being generated, and each cache
+	/// is generated only once.
 	pub fn compute_light(&self, block_number: u64, header_hash: &H256, nonce: u64) -> ProofOfWork {
 		let epoch = block_number / ETHASH_EPOCH_LENGTH;
-		let light = {
-			let mut lights = self.cache.lock();
-			let light = match lights.recent_epoch.clone() {
-				Some(ref e) if *e == epoch => lights.recent.clone(),
-				_ => match lights.prev_epoch.clone() {
-					Some(e) if e == epoch => {
-						// don't swap if recent is newer.
-						if lights.recent_epoch > lights.prev_epoch {
-							None
-						} else {
-							// swap
-							let t = lights.prev_epoch;
-							lights.prev_epoch = lights.recent_epoch;
-							lights.recent_epoch = t;
-							let t = lights.prev.clone();
-							lights.prev = lights.recent.clone();
-							lights.recent = t;
-							lights.recent.clone()
-						}
+		let cached = self.cache.lock().get(epoch);
+		let light = match cached {
+			Some(light) => light,
+			None => {
+				let _generating = self.generation.lock();
+				// another thread might have generated it while we were waiting.
+				let cached = self.cache.lock().get(epoch);
+				match cached {
+					Some(light) => light,
+					None => {
+						let options = self.options.read().clone();
+						let light = Arc::new(load_or_generate(&options, block_number, &self.generating));
+						self.cache.lock().insert(epoch, light.clone());
+						light
 					}
-					_ => None,
-				},
-			};
-			match light {
-				None => {
-					let light = match Light::from_file(block_number) {
-						Ok(light) => Arc::new(light),
-						Err(e) => {
-							debug!("Light cache file not found for {}:{}", block_number, e);
-							let light = Light::new(block_number);
-							if let Err(e) = light.to_file() {
-								warn!("Light cache file write error: {}", e);
-							}
-							Arc::new(light)
-						}
-					};
-					lights.prev_epoch = mem::replace(&mut lights.recent_epoch, Some(epoch));
-					lights.prev = mem::replace(&mut lights.recent, Some(light.clone()));
-					light
 				}
-				Some(light) => light,
 			}
 		};
+		if self.options.read().pregenerate {
+			self.pregenerate(epoch + 1);
+		}
 		light.compute(header_hash, nonce)
 	}
 }
@@ -125,3 +259,13 @@ fn test_lru() {
 	assert_eq!(ethash.cache.lock().recent_epoch.unwrap(), 2);
 	assert_eq!(ethash.cache.lock().prev_epoch.unwrap(), 0);
 }
+
+#[test]
+fn test_status() {
+	let ethash = EthashManager::new();
+	let hash = [0u8; 32];
+	ethash.compute_light(1, &hash, 1);
+	let status = ethash.status();
+	assert_eq!(status.current_epoch, Some(0));
+	assert_eq!(status.generating, None);
+}
@@ -28,6 +28,7 @@ extern crate fetch;
 
 mod client;
 
+pub mod registrar;
 pub mod urlhint;
 
 pub use client::{HashFetch, Client};
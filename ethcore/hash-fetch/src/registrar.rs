@@ -0,0 +1,68 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Registrar Contract
+
+use std::fmt;
+use std::sync::Arc;
+
+use ethabi::{Interface, Contract, Token};
+use util::{Address, Hashable};
+
+use urlhint::ContractClient;
+
+/// `RegistrarContract` API
+pub struct RegistrarContract {
+	registrar: Contract,
+	client: Arc<ContractClient>,
+}
+
+impl RegistrarContract {
+	/// Creates new `RegistrarContract`
+	pub fn new(client: Arc<ContractClient>) -> Self {
+		let registrar = Interface::load(include_bytes!("../res/registrar.json")).expect("registrar.json is valid ABI");
+
+		RegistrarContract {
+			registrar: Contract::new(registrar),
+			client: client,
+		}
+	}
+
+	/// Contract client used to perform the calls.
+	pub fn client(&self) -> &Arc<ContractClient> {
+		&self.client
+	}
+
+	/// Resolves address registered under given name.
+	pub fn get_address(&self, name: &str) -> Result<Address, String> {
+		let get_address = try!(self.registrar.function("getAddress".into()).map_err(as_string));
+		let params = try!(get_address.encode_call(
+				vec![Token::FixedBytes((*name.sha3()).to_vec()), Token::String("A".into())]
+		).map_err(as_string));
+		let output = try!(self.client.call(try!(self.client.registrar()), params));
+		let result = try!(get_address.decode_output(output).map_err(as_string));
+
+		match result.get(0) {
+			Some(&Token::Address(address)) if address != *Address::default() => Ok(address.into()),
+			Some(&Token::Address(_)) => Err(format!("Contract not found.")),
+			e => Err(format!("Invalid result: {:?}", e)),
+		}
+	}
+}
+
+fn as_string<T: fmt::Debug>(e: T) -> String {
+	format!("{:?}", e)
+}
@@ -16,13 +16,14 @@
 
 //! URLHint Contract
 
-use std::fmt;
 use std::sync::Arc;
 use rustc_serialize::hex::ToHex;
 use mime_guess;
 
 use ethabi::{Interface, Contract, Token};
-use util::{Address, Bytes, Hashable};
+use util::{Address, Bytes};
+
+use registrar::RegistrarContract;
 
 const COMMIT_LEN: usize = 20;
 
@@ -99,7 +100,7 @@ pub trait URLHint {
 /// `URLHintContract` API
 pub struct URLHintContract {
 	urlhint: Contract,
-	registrar: Contract,
+	registrar: RegistrarContract,
 	client: Arc<ContractClient>,
 }
 
@@ -107,32 +108,16 @@ impl URLHintContract {
 	/// Creates new `URLHintContract`
 	pub fn new(client: Arc<ContractClient>) -> Self {
 		let urlhint = Interface::load(include_bytes!("../res/urlhint.json")).expect("urlhint.json is valid ABI");
-		let registrar = Interface::load(include_bytes!("../res/registrar.json")).expect("registrar.json is valid ABI");
 
 		URLHintContract {
 			urlhint: Contract::new(urlhint),
-			registrar: Contract::new(registrar),
+			registrar: RegistrarContract::new(client.clone()),
 			client: client,
 		}
 	}
 
 	fn urlhint_address(&self) -> Option<Address> {
-		let res = || {
-			let get_address = try!(self.registrar.function("getAddress".into()).map_err(as_string));
-			let params = try!(get_address.encode_call(
-					vec![Token::FixedBytes((*"githubhint".sha3()).to_vec()), Token::String("A".into())]
-			).map_err(as_string));
-			let output = try!(self.client.call(try!(self.client.registrar()), params));
-			let result = try!(get_address.decode_output(output).map_err(as_string));
-
-			match result.get(0) {
-				Some(&Token::Address(address)) if address != *Address::default() => Ok(address.into()),
-				Some(&Token::Address(_)) => Err(format!("Contract not found.")),
-				e => Err(format!("Invalid result: {:?}", e)),
-			}
-		};
-
-		match res() {
+		match self.registrar.get_address("githubhint") {
 			Ok(res) => Some(res),
 			Err(e) => {
 				warn!(target: "dapps", "Error while calling registrar: {:?}", e);
@@ -258,10 +243,6 @@ fn guess_mime_type(url: &str) -> Option<String> {
 	})
 }
 
-fn as_string<T: fmt::Debug>(e: T) -> String {
-	format!("{:?}", e)
-}
-
 #[cfg(test)]
 mod tests {
 	use std::sync::Arc;
@@ -0,0 +1,221 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Cache for data fetched from the network.
+//!
+//! Data keyed by hash (headers, bodies, receipts and code) is immutable once
+//! verified and is only bounded by memory. Data which depends on the chain head,
+//! such as the canonical hash of a block number, expires after a fixed time.
+
+use std::collections::HashMap;
+
+use ethcore::header::BlockNumber;
+use ethcore::receipt::Receipt;
+use util::cache::MemoryLruCache;
+use util::{Bytes, H256};
+use time::{Duration, SteadyTime};
+
+/// Maximum number of head-dependent entries kept.
+const MAX_HEAD_DEPENDENT: usize = 4096;
+
+/// Memory limits for each kind of cached item, in bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheSizes {
+	/// Maximum size of cached headers.
+	pub headers: usize,
+	/// Maximum size of cached block bodies.
+	pub bodies: usize,
+	/// Maximum size of cached receipts.
+	pub receipts: usize,
+	/// Maximum size of cached contract code.
+	pub code: usize,
+}
+
+impl Default for CacheSizes {
+	fn default() -> Self {
+		const MB: usize = 1024 * 1024;
+		CacheSizes {
+			headers: 10 * MB,
+			bodies: 20 * MB,
+			receipts: 10 * MB,
+			code: 20 * MB,
+		}
+	}
+}
+
+/// Cache of verified data retrieved on demand.
+///
+/// Callers are expected to insert only data which passed verification.
+pub struct Cache {
+	headers: MemoryLruCache<H256, Bytes>,
+	bodies: MemoryLruCache<H256, Bytes>,
+	receipts: MemoryLruCache<H256, Vec<Receipt>>,
+	code: MemoryLruCache<H256, Bytes>,
+	canon_hashes: HashMap<BlockNumber, (H256, SteadyTime)>,
+	max_age: Duration,
+}
+
+impl Cache {
+	/// Create a new cache with the given size limits, expiring head-dependent
+	/// data after `max_age`.
+	pub fn new(sizes: CacheSizes, max_age: Duration) -> Self {
+		Cache {
+			headers: MemoryLruCache::new(sizes.headers),
+			bodies: MemoryLruCache::new(sizes.bodies),
+			receipts: MemoryLruCache::new(sizes.receipts),
+			code: MemoryLruCache::new(sizes.code),
+			canon_hashes: HashMap::new(),
+			max_age: max_age,
+		}
+	}
+
+	/// Get a cached header by hash.
+	pub fn block_header(&mut self, hash: &H256) -> Option<Bytes> {
+		self.headers.get_mut(hash).map(|x| x.clone())
+	}
+
+	/// Get a cached block body by block hash.
+	pub fn block_body(&mut self, hash: &H256) -> Option<Bytes> {
+		self.bodies.get_mut(hash).map(|x| x.clone())
+	}
+
+	/// Get cached receipts by block hash.
+	pub fn block_receipts(&mut self, hash: &H256) -> Option<Vec<Receipt>> {
+		self.receipts.get_mut(hash).map(|x| x.clone())
+	}
+
+	/// Get cached contract code by code hash.
+	pub fn code(&mut self, code_hash: &H256) -> Option<Bytes> {
+		self.code.get_mut(code_hash).map(|x| x.clone())
+	}
+
+	/// Get the canonical hash of a block number, if known and not expired.
+	pub fn block_hash(&mut self, number: BlockNumber) -> Option<H256> {
+		let now = SteadyTime::now();
+		match self.canon_hashes.get(&number) {
+			Some(&(hash, inserted)) if now - inserted < self.max_age => return Some(hash),
+			Some(_) => {},
+			None => return None,
+		}
+
+		self.canon_hashes.remove(&number);
+		None
+	}
+
+	/// Cache a header.
+	pub fn insert_block_header(&mut self, hash: H256, header: Bytes) {
+		self.headers.insert(hash, header);
+	}
+
+	/// Cache a block body.
+	pub fn insert_block_body(&mut self, hash: H256, body: Bytes) {
+		self.bodies.insert(hash, body);
+	}
+
+	/// Cache the receipts of a block.
+	pub fn insert_block_receipts(&mut self, hash: H256, receipts: Vec<Receipt>) {
+		self.receipts.insert(hash, receipts);
+	}
+
+	/// Cache contract code.
+	pub fn insert_code(&mut self, code_hash: H256, code: Bytes) {
+		self.code.insert(code_hash, code);
+	}
+
+	/// Cache the canonical hash of a block number.
+	pub fn insert_block_hash(&mut self, number: BlockNumber, hash: H256) {
+		if self.canon_hashes.len() >= MAX_HEAD_DEPENDENT {
+			self.prune_expired();
+		}
+
+		// still full: drop the lowest numbers, least likely to be asked for again.
+		while self.canon_hashes.len() >= MAX_HEAD_DEPENDENT {
+			let lowest = *self.canon_hashes.keys().min().expect("map is non-empty; qed");
+			self.canon_hashes.remove(&lowest);
+		}
+
+		self.canon_hashes.insert(number, (hash, SteadyTime::now()));
+	}
+
+	/// Drop all head-dependent data, e.g. after a reorganization.
+	pub fn clear_head_dependent(&mut self) {
+		self.canon_hashes.clear();
+	}
+
+	/// Total memory used by cached items, in bytes.
+	pub fn mem_used(&self) -> usize {
+		self.headers.current_size() + self.bodies.current_size() +
+			self.receipts.current_size() + self.code.current_size()
+	}
+
+	fn prune_expired(&mut self) {
+		let now = SteadyTime::now();
+		let max_age = self.max_age;
+		let expired: Vec<_> = self.canon_hashes.iter()
+			.filter(|&(_, &(_, inserted))| now - inserted >= max_age)
+			.map(|(number, _)| *number)
+			.collect();
+
+		for number in expired {
+			self.canon_hashes.remove(&number);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Cache, CacheSizes};
+	use time::Duration;
+	use util::H256;
+
+	#[test]
+	fn caches_by_hash() {
+		let mut cache = Cache::new(CacheSizes::default(), Duration::hours(1));
+		assert!(cache.block_header(&H256::from(1)).is_none());
+
+		cache.insert_block_header(H256::from(1), vec![1, 2, 3]);
+		cache.insert_code(H256::from(2), vec![0x60, 0x00]);
+		assert_eq!(cache.block_header(&H256::from(1)), Some(vec![1, 2, 3]));
+		assert_eq!(cache.code(&H256::from(2)), Some(vec![0x60, 0x00]));
+		assert!(cache.block_body(&H256::from(1)).is_none());
+		assert!(cache.mem_used() > 0);
+	}
+
+	#[test]
+	fn respects_size_limit() {
+		let sizes = CacheSizes { headers: 1024, ..CacheSizes::default() };
+		let mut cache = Cache::new(sizes, Duration::hours(1));
+
+		for i in 0..100 {
+			cache.insert_block_header(H256::from(i), vec![0; 100]);
+		}
+
+		assert!(cache.mem_used() <= 1024);
+		assert!(cache.block_header(&H256::from(0)).is_none());
+		assert!(cache.block_header(&H256::from(99)).is_some());
+	}
+
+	#[test]
+	fn expires_head_dependent_data() {
+		let mut cache = Cache::new(CacheSizes::default(), Duration::hours(1));
+		cache.insert_block_hash(10, H256::from(10));
+		assert_eq!(cache.block_hash(10), Some(H256::from(10)));
+
+		let mut cache = Cache::new(CacheSizes::default(), Duration::zero());
+		cache.insert_block_hash(10, H256::from(10));
+		assert_eq!(cache.block_hash(10), None);
+	}
+}
@@ -0,0 +1,232 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Canonical hash trie definitions and helper functions.
+//!
+//! Each CHT is a trie mapping the numbers of `SIZE` consecutive blocks to their
+//! canonical hash and total difficulty. Light clients can keep only its root and
+//! request inclusion proofs of the ancient headers they need.
+
+use std::collections::VecDeque;
+
+use util::{Bytes, H256, U256, Mutex, MemoryDB, Trie, TrieMut, TrieDB, TrieDBMut};
+use util::trie::recorder::{Recorder, BasicRecorder};
+use rlp::{self, RlpStream, Stream};
+
+/// The number of blocks covered by each CHT.
+pub const SIZE: u64 = 2048;
+
+// number of built CHTs kept by a `Cache`.
+const CACHE_SIZE: usize = 4;
+
+/// Number of the CHT covering the given block. The genesis block isn't covered by any.
+pub fn block_to_cht_number(block_num: u64) -> Option<u64> {
+	match block_num {
+		0 => None,
+		n => Some((n - 1) / SIZE),
+	}
+}
+
+/// Number of the first block covered by the given CHT.
+pub fn start_number(cht_num: u64) -> u64 {
+	cht_num * SIZE + 1
+}
+
+// build the trie of the given CHT, fetching the canonical hash and total
+// difficulty of every block it covers.
+fn build<F>(cht_num: u64, fetcher: F) -> Option<(MemoryDB, H256)>
+	where F: Fn(u64) -> Option<(H256, U256)>
+{
+	let mut db = MemoryDB::new();
+	let mut root = H256::default();
+
+	{
+		let mut trie = TrieDBMut::new(&mut db, &mut root);
+		for num in start_number(cht_num)..start_number(cht_num + 1) {
+			let (hash, td) = match fetcher(num) {
+				Some(info) => info,
+				None => return None,
+			};
+
+			let mut stream = RlpStream::new_list(2);
+			stream.append(&hash).append(&td);
+			if trie.insert(&rlp::encode(&num), &stream.out()).is_err() {
+				return None;
+			}
+		}
+	}
+
+	Some((db, root))
+}
+
+/// Compute the root of the given CHT. Returns `None` if any block it covers is unknown.
+pub fn compute_root<F>(cht_num: u64, fetcher: F) -> Option<H256>
+	where F: Fn(u64) -> Option<(H256, U256)>
+{
+	build(cht_num, fetcher).map(|(_, root)| root)
+}
+
+/// Prove the inclusion of a block in the given CHT by the trie nodes visited looking
+/// it up. Nodes above `from_level` are omitted. Returns `None` if the block isn't
+/// covered by the CHT or any block it covers is unknown.
+pub fn prove<F>(cht_num: u64, block_num: u64, from_level: u32, fetcher: F) -> Option<Vec<Bytes>>
+	where F: Fn(u64) -> Option<(H256, U256)>
+{
+	if block_to_cht_number(block_num) != Some(cht_num) {
+		return None;
+	}
+
+	build(cht_num, fetcher).and_then(|(db, root)| prove_in(&db, &root, block_num, from_level))
+}
+
+// prove the inclusion of a block in a built CHT.
+fn prove_in(db: &MemoryDB, root: &H256, block_num: u64, from_level: u32) -> Option<Vec<Bytes>> {
+	let mut recorder = BasicRecorder::with_depth(from_level);
+	let trie = match TrieDB::new(db, root) {
+		Ok(trie) => trie,
+		Err(_) => return None,
+	};
+
+	match trie.get_recorded(&rlp::encode(&block_num), &mut recorder) {
+		Ok(Some(_)) => Some(recorder.drain().into_iter().map(|record| record.data).collect()),
+		_ => None,
+	}
+}
+
+/// Recently built CHTs, so that proving headers doesn't rebuild the whole trie
+/// each time. Each is kept along with the hash of the last block it covers: a
+/// reorganization of the blocks covered makes it stale.
+pub struct Cache {
+	tries: Mutex<VecDeque<(u64, H256, MemoryDB, H256)>>,
+}
+
+impl Cache {
+	/// Create an empty cache.
+	pub fn new() -> Self {
+		Cache {
+			tries: Mutex::new(VecDeque::new()),
+		}
+	}
+
+	/// Like `prove`, building the CHT only if it isn't cached.
+	pub fn prove<F>(&self, cht_num: u64, block_num: u64, from_level: u32, fetcher: F) -> Option<Vec<Bytes>>
+		where F: Fn(u64) -> Option<(H256, U256)>
+	{
+		if block_to_cht_number(block_num) != Some(cht_num) {
+			return None;
+		}
+
+		self.with_trie(cht_num, fetcher, |db, root| prove_in(db, root, block_num, from_level))
+	}
+
+	/// Like `compute_root`, building the CHT only if it isn't cached.
+	pub fn root<F>(&self, cht_num: u64, fetcher: F) -> Option<H256>
+		where F: Fn(u64) -> Option<(H256, U256)>
+	{
+		self.with_trie(cht_num, fetcher, |_, root| Some(root.clone()))
+	}
+
+	// the lock is held while building so concurrent requests for a CHT build it once.
+	fn with_trie<F, T, G>(&self, cht_num: u64, fetcher: F, f: G) -> Option<T>
+		where F: Fn(u64) -> Option<(H256, U256)>, G: FnOnce(&MemoryDB, &H256) -> Option<T>
+	{
+		let last_hash = match fetcher(start_number(cht_num + 1) - 1) {
+			Some((hash, _)) => hash,
+			None => return None,
+		};
+
+		let mut tries = self.tries.lock();
+		let pos = match tries.iter().position(|&(num, ref hash, _, _)| num == cht_num && *hash == last_hash) {
+			Some(pos) => pos,
+			None => {
+				let (db, root) = match build(cht_num, fetcher) {
+					Some(trie) => trie,
+					None => return None,
+				};
+				tries.retain(|&(num, _, _, _)| num != cht_num);
+				if tries.len() == CACHE_SIZE {
+					tries.pop_back();
+				}
+				tries.push_front((cht_num, last_hash, db, root));
+				0
+			}
+		};
+
+		let (_, _, ref db, ref root) = tries[pos];
+		f(db, root)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cell::Cell;
+	use util::{H256, U256, MemoryDB, HashDB, Trie, TrieDB};
+	use rlp::{self, Rlp, View};
+
+	fn fetcher(num: u64) -> Option<(H256, U256)> {
+		Some((H256::from(num), U256::from(num) * U256::from(1000)))
+	}
+
+	#[test]
+	fn cht_numbers() {
+		assert_eq!(super::block_to_cht_number(0), None);
+		assert_eq!(super::block_to_cht_number(1), Some(0));
+		assert_eq!(super::block_to_cht_number(super::SIZE), Some(0));
+		assert_eq!(super::block_to_cht_number(super::SIZE + 1), Some(1));
+		assert_eq!(super::start_number(1), super::SIZE + 1);
+	}
+
+	#[test]
+	fn proof_checks_against_root() {
+		let root = super::compute_root(1, fetcher).unwrap();
+		let num = super::SIZE + 100;
+		let proof = super::prove(1, num, 0, fetcher).unwrap();
+
+		let mut db = MemoryDB::new();
+		for node in &proof {
+			db.insert(node);
+		}
+
+		let value = TrieDB::new(&db, &root).unwrap().get(&rlp::encode(&num)).unwrap().unwrap();
+		let hash: H256 = Rlp::new(&value).val_at(0);
+		assert_eq!(hash, H256::from(num));
+
+		assert!(super::prove(0, num, 0, fetcher).is_none());
+		assert!(super::prove(1, num, 0, |n| if n == num { None } else { fetcher(n) }).is_none());
+	}
+
+	#[test]
+	fn cache_builds_each_cht_once() {
+		let built = Cell::new(0);
+		let counting = |num| {
+			if num == super::start_number(1) { built.set(built.get() + 1) }
+			fetcher(num)
+		};
+
+		let cache = super::Cache::new();
+		let num = super::SIZE + 100;
+		assert_eq!(cache.root(1, &counting), super::compute_root(1, fetcher));
+		assert_eq!(cache.prove(1, num, 0, &counting), super::prove(1, num, 0, fetcher));
+		assert_eq!(cache.prove(1, num + 1, 0, &counting), super::prove(1, num + 1, 0, fetcher));
+		assert_eq!(built.get(), 1);
+
+		// a different last block means the blocks covered were reorganized.
+		let last = super::start_number(2) - 1;
+		let reorged = |n| if n == last { Some((H256::from(0), U256::default())) } else { counting(n) };
+		assert!(cache.prove(1, num, 0, &reorged).is_some());
+		assert_eq!(built.get(), 2);
+	}
+}
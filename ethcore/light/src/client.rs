@@ -24,7 +24,8 @@ use ethcore::ids::BlockID;
 use ethcore::service::ClientIoMessage;
 use ethcore::block_import_error::BlockImportError;
 use ethcore::block_status::BlockStatus;
-use ethcore::verification::queue::{HeaderQueue, QueueInfo};
+use ethcore::verification::queue::{self, HeaderQueue, QueueInfo};
+use ethcore::spec::Spec;
 use ethcore::transaction::SignedTransaction;
 use ethcore::blockchain_info::BlockChainInfo;
 
@@ -33,16 +34,49 @@ use util::hash::H256;
 use util::{Bytes, Mutex};
 
 use provider::Provider;
-use request;
+use request::{self, HashOrNumber};
 
 /// Light client implementation.
 pub struct Client {
 	engine: Arc<Engine>,
 	header_queue: HeaderQueue,
 	message_channel: Mutex<IoChannel<ClientIoMessage>>,
+	anchor: Option<(u64, H256)>,
 }
 
 impl Client {
+	/// Create a new light client for the given spec. The header sync starts
+	/// from the latest checkpoint of the spec, if any, rather than from genesis.
+	pub fn new(config: queue::Config, spec: &Spec, io_channel: IoChannel<ClientIoMessage>) -> Self {
+		Client {
+			engine: spec.engine.clone(),
+			header_queue: HeaderQueue::new(config, spec.engine.clone(), io_channel.clone(), true),
+			message_channel: Mutex::new(io_channel),
+			anchor: spec.latest_checkpoint(),
+		}
+	}
+
+	/// Trusted header the sync starts from, as its number and hash.
+	/// Its ancestry is never downloaded nor validated.
+	pub fn anchor(&self) -> Option<(u64, H256)> {
+		self.anchor.clone()
+	}
+
+	/// First headers to request from peers: `max` headers starting at the anchor,
+	/// requested by hash so that a peer on another chain can't answer, or starting
+	/// at genesis when there is no anchor.
+	pub fn sync_start(&self, max: usize) -> request::Headers {
+		request::Headers {
+			block: match self.anchor {
+				Some((_, ref hash)) => HashOrNumber::Hash(hash.clone()),
+				None => HashOrNumber::Number(0),
+			},
+			max: max,
+			skip: 0,
+			reverse: false,
+		}
+	}
+
 	/// Import a header as rlp-encoded bytes.
 	pub fn import_header(&self, bytes: Bytes) -> Result<H256, BlockImportError> {
 		let header = ::rlp::decode(&bytes);
@@ -51,8 +85,8 @@ impl Client {
 	}
 
 	/// Whether the block is already known (but not necessarily part of the canonical chain)
-	pub fn is_known(&self, _id: BlockID) -> bool {
-		false
+	pub fn is_known(&self, id: BlockID) -> bool {
+		self.status(id) == BlockStatus::InChain
 	}
 
 	/// Fetch a vector of all pending transactions.
@@ -60,9 +94,13 @@ impl Client {
 		vec![]
 	}
 
-	/// Inquire about the status of a given block.
-	pub fn status(&self, _id: BlockID) -> BlockStatus {
-		BlockStatus::Unknown
+	/// Inquire about the status of a given block. The anchor is trusted to be in the chain.
+	pub fn status(&self, id: BlockID) -> BlockStatus {
+		match (id, self.anchor.as_ref()) {
+			(BlockID::Hash(ref hash), Some(&(_, ref anchor))) if hash == anchor => BlockStatus::InChain,
+			(BlockID::Number(number), Some(&(anchor, _))) if number == anchor => BlockStatus::InChain,
+			_ => BlockStatus::Unknown,
+		}
 	}
 
 	/// Get the header queue info.
@@ -85,6 +123,10 @@ impl Provider for Client {
 		None
 	}
 
+	fn block_header(&self, _id: BlockID) -> Option<Bytes> {
+		None
+	}
+
 	fn block_headers(&self, _req: request::Headers) -> Vec<Bytes> {
 		Vec::new()
 	}
@@ -112,4 +154,37 @@ impl Provider for Client {
 	fn pending_transactions(&self) -> Vec<SignedTransaction> {
 		Vec::new()
 	}
-}
\ No newline at end of file
+
+	fn queue_transactions(&self, _transactions: Vec<Bytes>) {}
+}
+#[cfg(test)]
+mod tests {
+	use super::Client;
+	use ethcore::block_status::BlockStatus;
+	use ethcore::ethereum;
+	use ethcore::ids::BlockID;
+	use ethcore::spec::Spec;
+	use io::IoChannel;
+	use request::HashOrNumber;
+
+	#[test]
+	fn syncs_from_latest_checkpoint() {
+		let spec = ethereum::new_frontier();
+		let (number, hash) = spec.latest_checkpoint().unwrap();
+		let client = Client::new(Default::default(), &spec, IoChannel::disconnected());
+
+		assert_eq!(client.anchor(), Some((number, hash.clone())));
+		assert_eq!(client.sync_start(64).block, HashOrNumber::Hash(hash.clone()));
+		assert_eq!(client.status(BlockID::Hash(hash)), BlockStatus::InChain);
+		assert!(client.is_known(BlockID::Number(number)));
+		assert_eq!(client.status(BlockID::Number(number - 1)), BlockStatus::Unknown);
+	}
+
+	#[test]
+	fn syncs_from_genesis_without_checkpoints() {
+		let client = Client::new(Default::default(), &Spec::new_test(), IoChannel::disconnected());
+
+		assert_eq!(client.anchor(), None);
+		assert_eq!(client.sync_start(64).block, HashOrNumber::Number(0));
+	}
+}
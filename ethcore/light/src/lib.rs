@@ -31,10 +31,14 @@
 // TODO: remove when integrating with parity.
 #![allow(dead_code)]
 
+pub mod cache;
+pub mod cht;
 pub mod client;
 pub mod net;
+pub mod on_demand;
 pub mod provider;
-pub mod request;
+pub mod verification;
+pub use ethcore::request;
 
 extern crate ethcore_util as util;
 extern crate ethcore_network as network;
@@ -84,6 +84,7 @@ pub struct CostTable {
 	state_proofs: Cost,
 	contract_codes: Cost,
 	header_proofs: Cost,
+	transactions: Cost,
 }
 
 impl Default for CostTable {
@@ -96,6 +97,7 @@ impl Default for CostTable {
 			state_proofs: Cost(250000.into(), 25000.into()),
 			contract_codes: Cost(200000.into(), 20000.into()),
 			header_proofs: Cost(150000.into(), 15000.into()),
+			transactions: Cost(100000.into(), 10000.into()),
 		}
 	}
 }
@@ -109,7 +111,7 @@ impl RlpEncodable for CostTable {
 				.append(&cost.1);
 		}
 
-		s.begin_list(6);
+		s.begin_list(7);
 
 		append_cost(s, packet::GET_BLOCK_HEADERS, &self.headers);
 		append_cost(s, packet::GET_BLOCK_BODIES, &self.bodies);
@@ -117,6 +119,7 @@ impl RlpEncodable for CostTable {
 		append_cost(s, packet::GET_PROOFS, &self.state_proofs);
 		append_cost(s, packet::GET_CONTRACT_CODES, &self.contract_codes);
 		append_cost(s, packet::GET_HEADER_PROOFS, &self.header_proofs);
+		append_cost(s, packet::SEND_TRANSACTIONS, &self.transactions);
 	}
 }
 
@@ -130,6 +133,7 @@ impl RlpDecodable for CostTable {
 		let mut state_proofs = None;
 		let mut contract_codes = None;
 		let mut header_proofs = None;
+		let mut transactions = None;
 
 		for row in rlp.iter() {
 			let msg_id: u8 = try!(row.val_at(0));
@@ -147,6 +151,7 @@ impl RlpDecodable for CostTable {
 				packet::GET_PROOFS => state_proofs = Some(cost),
 				packet::GET_CONTRACT_CODES => contract_codes = Some(cost),
 				packet::GET_HEADER_PROOFS => header_proofs = Some(cost),
+				packet::SEND_TRANSACTIONS => transactions = Some(cost),
 				_ => return Err(DecoderError::Custom("Unrecognized message in cost table")),
 			}
 		}
@@ -158,6 +163,8 @@ impl RlpDecodable for CostTable {
 			state_proofs: try!(state_proofs.ok_or(DecoderError::Custom("No proofs cost specified"))),
 			contract_codes: try!(contract_codes.ok_or(DecoderError::Custom("No contract codes specified"))),
 			header_proofs: try!(header_proofs.ok_or(DecoderError::Custom("No header proofs cost specified"))),
+			// not given by peers which don't relay transactions.
+			transactions: transactions.unwrap_or_else(|| CostTable::default().transactions),
 		})
 	}
 }
@@ -206,6 +213,12 @@ impl FlowParams {
 		cost.0 + (amount * cost.1)
 	}
 
+	/// Compute the cost of relaying the given number of transactions.
+	pub fn compute_transactions_cost(&self, amount: usize) -> U256 {
+		let amount: U256 = amount.into();
+		self.costs.transactions.0 + (amount * self.costs.transactions.1)
+	}
+
 	/// Create initial buffer parameter.
 	pub fn create_buffer(&self) -> Buffer {
 		Buffer {
@@ -233,6 +246,8 @@ impl FlowParams {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use rlp::{RlpStream, Stream, UntrustedRlp, View};
+	use util::U256;
 
 	#[test]
 	fn should_serialize_cost_table() {
@@ -244,6 +259,21 @@ mod tests {
 		assert_eq!(costs, new_costs);
 	}
 
+	#[test]
+	fn transactions_cost_defaults_when_not_given() {
+		let costs = CostTable::default();
+		let mut stream = RlpStream::new_list(6);
+		for row in UntrustedRlp::new(&::rlp::encode(&costs)).iter().take(6) {
+			stream.append_raw(row.as_raw(), 1);
+		}
+
+		let decoded: CostTable = ::rlp::decode(&stream.out());
+		assert_eq!(decoded, costs);
+
+		let flow_params = FlowParams::new(100.into(), decoded, 20.into());
+		assert_eq!(flow_params.compute_transactions_cost(2), U256::from(120000));
+	}
+
 	#[test]
 	fn buffer_mechanism() {
 		use std::thread;
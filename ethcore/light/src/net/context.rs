@@ -0,0 +1,53 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! I/O context of the light protocol.
+
+use network::{NetworkContext, PeerId, PacketId, NetworkError};
+
+/// Sends packets to peers and drops them. Implemented by the network context,
+/// and by test doubles so the protocol can be driven without sockets.
+pub trait IoContext {
+	/// Send a packet to a peer.
+	fn send(&self, peer: PeerId, packet_id: PacketId, packet_body: Vec<u8>) -> Result<(), NetworkError>;
+
+	/// Respond to the peer whose packet is being handled.
+	fn respond(&self, packet_id: PacketId, packet_body: Vec<u8>) -> Result<(), NetworkError>;
+
+	/// Disconnect a peer.
+	fn disconnect_peer(&self, peer: PeerId);
+
+	/// Disconnect a peer and prevent it from connecting again for a while.
+	fn disable_peer(&self, peer: PeerId);
+}
+
+impl<'a> IoContext for NetworkContext<'a> {
+	fn send(&self, peer: PeerId, packet_id: PacketId, packet_body: Vec<u8>) -> Result<(), NetworkError> {
+		NetworkContext::send(self, peer, packet_id, packet_body)
+	}
+
+	fn respond(&self, packet_id: PacketId, packet_body: Vec<u8>) -> Result<(), NetworkError> {
+		NetworkContext::respond(self, packet_id, packet_body)
+	}
+
+	fn disconnect_peer(&self, peer: PeerId) {
+		NetworkContext::disconnect_peer(self, peer);
+	}
+
+	fn disable_peer(&self, peer: PeerId) {
+		NetworkContext::disable_peer(self, peer);
+	}
+}
@@ -19,6 +19,7 @@
 
 use rlp::DecoderError;
 use network::NetworkError;
+use verification;
 
 use std::fmt;
 
@@ -52,6 +53,16 @@ pub enum Error {
 	UnexpectedHandshake,
 	/// Peer on wrong network (wrong NetworkId or genesis hash)
 	WrongNetwork,
+	/// Peer is not connected.
+	UnknownPeer,
+	/// Response to a request we didn't make (or made to another peer).
+	UnsolicitedResponse,
+	/// Response failed verification.
+	BadResponse(verification::Error),
+	/// Peer doesn't advertise the capabilities to serve a request.
+	NotServer,
+	/// No connected peer can serve a request.
+	NoCapablePeers,
 }
 
 impl Error {
@@ -64,6 +75,11 @@ impl Error {
 			Error::UnrecognizedPacket(_) => Punishment::Disconnect,
 			Error::UnexpectedHandshake => Punishment::Disconnect,
 			Error::WrongNetwork => Punishment::Disable,
+			Error::UnknownPeer => Punishment::None,
+			Error::UnsolicitedResponse => Punishment::Disconnect,
+			Error::BadResponse(_) => Punishment::Disable,
+			Error::NotServer => Punishment::None,
+			Error::NoCapablePeers => Punishment::None,
 		}
 	}
 }
@@ -74,6 +90,12 @@ impl From<DecoderError> for Error {
 	}
 }
 
+impl From<verification::Error> for Error {
+	fn from(err: verification::Error) -> Self {
+		Error::BadResponse(err)
+	}
+}
+
 impl From<NetworkError> for Error {
 	fn from(err: NetworkError) -> Self {
 		Error::Network(err)
@@ -89,6 +111,11 @@ impl fmt::Display for Error {
 			Error::UnrecognizedPacket(code) => write!(f, "Unrecognized packet: 0x{:x}", code),
 			Error::UnexpectedHandshake => write!(f, "Unexpected handshake"),
 			Error::WrongNetwork => write!(f, "Wrong network"),
+			Error::UnknownPeer => write!(f, "Unknown peer"),
+			Error::UnsolicitedResponse => write!(f, "Unsolicited response"),
+			Error::BadResponse(ref err) => write!(f, "Bad response: {}", err),
+			Error::NotServer => write!(f, "Peer doesn't serve this request"),
+			Error::NoCapablePeers => write!(f, "No peer can serve this request"),
 		}
 	}
 }
\ No newline at end of file
@@ -19,36 +19,59 @@
 //! This uses a "Provider" to answer requests.
 //! See https://github.com/ethcore/parity/wiki/Light-Ethereum-Subprotocol-(LES)
 
+use ethcore::engines::Engine;
+use ethcore::header::Header;
+use ethcore::ids::BlockID;
+use ethcore::receipt::Receipt;
+use ethcore::transaction::SignedTransaction;
 use io::TimerToken;
 use network::{NetworkProtocolHandler, NetworkContext, NetworkError, PeerId};
-use rlp::{RlpStream, Stream, UntrustedRlp, View};
+use rlp::{DecoderError, RlpStream, Stream, UntrustedRlp, View};
 use util::hash::H256;
-use util::RwLock;
+use util::{Bytes, RwLock, U256};
+use util::metrics::{metrics, DEFAULT_BUCKETS};
 
-use std::collections::{HashMap, HashSet};
-use std::sync::atomic::AtomicUsize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 use provider::Provider;
-use request::{self, Request};
+use request::{self, Request, Response};
+use verification;
 
 use self::buffer_flow::{Buffer, FlowParams};
 use self::error::{Error, Punishment};
 use self::status::{Status, Capabilities};
 
 mod buffer_flow;
+mod context;
 mod error;
 mod status;
 
-pub use self::status::Announcement;
+pub use self::buffer_flow::{CostTable, FlowParams};
+pub use self::context::IoContext;
+pub use self::status::{Announcement, Capabilities, NetworkId};
 
 const TIMEOUT: TimerToken = 0;
 const TIMEOUT_INTERVAL_MS: u64 = 1000;
 
+// maximum number of transactions sent to a peer per timer tick.
+const MAX_TRANSACTIONS_PER_ROUND: usize = 64;
+
 // LPV1
 const PROTOCOL_VERSION: u32 = 1;
 
+/// Protocol versions to register with the network service.
+pub const PROTOCOL_VERSIONS: &'static [u8] = &[1];
+
+/// Subprotocol name of the light protocol.
 // TODO [rob] make configurable.
-const PROTOCOL_ID: [u8; 3] = *b"les";
+pub const PROTOCOL_ID: [u8; 3] = *b"les";
+
+/// Number of packet ids used by the light protocol.
+pub const PACKET_COUNT: u8 = 15;
 
 // packet ID definitions.
 mod packet {
@@ -90,6 +113,7 @@ mod packet {
 // may not have received one for.
 struct PendingPeer {
 	sent_head: H256,
+	sent_at: Instant,
 }
 
 // data about each peer.
@@ -101,6 +125,120 @@ struct Peer {
 	capabilities: Capabilities,
 	remote_flow: FlowParams,
 	sent_head: H256, // last head we've given them.
+	known_transactions: HashSet<H256>, // pending transactions they've been sent or sent us.
+	stats: PeerStats,
+}
+
+// response latency and timeouts of requests made to a peer.
+#[derive(Debug, Default, Clone)]
+struct PeerStats {
+	responses: u32,
+	timeouts: u32,
+	avg_latency_ms: f64, // exponential moving average.
+}
+
+impl PeerStats {
+	fn note_response(&mut self, elapsed: Duration) {
+		let ms = elapsed.as_secs() as f64 * 1000.0 + elapsed.subsec_nanos() as f64 / 1_000_000.0;
+		self.avg_latency_ms = match self.responses {
+			0 => ms,
+			_ => self.avg_latency_ms * 0.8 + ms * 0.2,
+		};
+		self.responses += 1;
+	}
+
+	fn note_timeout(&mut self) {
+		self.timeouts += 1;
+	}
+
+	// whether the peer is too slow or unreliable to keep.
+	fn should_evict(&self, limits: &PeerLimits) -> bool {
+		let total = self.responses + self.timeouts;
+		if total < limits.min_samples { return false }
+
+		self.avg_latency_ms > duration_ms(limits.max_average_latency) as f64 ||
+			self.timeouts as f64 / total as f64 > limits.max_timeout_rate
+	}
+}
+
+fn duration_ms(d: Duration) -> u64 {
+	d.as_secs() * 1000 + d.subsec_nanos() as u64 / 1_000_000
+}
+
+// A request we've made and are awaiting a response for.
+struct Requested {
+	request: Request,
+	peer: PeerId,
+	sent_at: Instant,
+	timed_out: bool,
+}
+
+/// Receives verified responses to requests made through `LightProtocol::request_from`.
+///
+/// All methods have empty default implementations.
+pub trait Handler: Send + Sync {
+	/// Called with verified headers answering request `req_id`.
+	fn on_block_headers(&self, _peer: PeerId, _req_id: usize, _headers: &[Header]) {}
+	/// Called with verified block bodies answering request `req_id`, one per requested hash.
+	fn on_block_bodies(&self, _peer: PeerId, _req_id: usize, _bodies: &[Bytes]) {}
+	/// Called with verified receipts answering request `req_id`, one list per requested hash.
+	fn on_receipts(&self, _peer: PeerId, _req_id: usize, _receipts: &[Vec<Receipt>]) {}
+	/// Called with verified state proofs answering request `req_id`, one per requested proof.
+	fn on_state_proofs(&self, _peer: PeerId, _req_id: usize, _proofs: &[Vec<Bytes>]) {}
+	/// Called with contract codes answering request `req_id`, one per requested account.
+	/// Codes can't be checked without the account, handlers must compare their hashes.
+	fn on_code(&self, _peer: PeerId, _req_id: usize, _codes: &[Bytes]) {}
+	/// Called with headers and their CHT proofs answering request `req_id`, in request order up to
+	/// the first the peer couldn't prove. Proofs can't be checked without the CHT root, handlers must do so.
+	fn on_header_proofs(&self, _peer: PeerId, _req_id: usize, _proofs: &[(Header, Vec<Bytes>)]) {}
+	/// Called when a peer disconnects with requests still unanswered.
+	fn on_disconnect(&self, _peer: PeerId, _unfulfilled: &[usize]) {}
+	/// Called when requests to a peer are given up on, having been unanswered for twice the request timeout.
+	fn on_timeout(&self, _peer: PeerId, _expired: &[usize]) {}
+}
+
+/// Protocol parameters.
+pub struct Params {
+	/// Network id.
+	pub network_id: NetworkId,
+	/// Buffer flow parameters offered to peers.
+	pub flow_params: FlowParams,
+	/// Initial capabilities, unless the provider reports its own.
+	pub capabilities: Capabilities,
+	/// Trusted block hashes by number. Headers conflicting with them are rejected.
+	pub checkpoints: BTreeMap<u64, H256>,
+	/// Handshake deadline and slow-peer eviction thresholds.
+	pub limits: PeerLimits,
+	/// Consensus engine to check the seals of received headers with, e.g. authority
+	/// signatures on proof-of-authority chains. Seals are not checked if `None`.
+	pub engine: Option<Arc<Engine>>,
+}
+
+/// Limits on how long peers may take to complete the handshake and answer requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerLimits {
+	/// Time a peer has to send its status after connecting.
+	pub handshake_timeout: Duration,
+	/// Time after which an unanswered request counts as timed out.
+	pub request_timeout: Duration,
+	/// Average response latency above which a peer is evicted.
+	pub max_average_latency: Duration,
+	/// Fraction of timed out requests above which a peer is evicted.
+	pub max_timeout_rate: f64,
+	/// Number of answered or timed out requests needed before a peer may be evicted.
+	pub min_samples: u32,
+}
+
+impl Default for PeerLimits {
+	fn default() -> Self {
+		PeerLimits {
+			handshake_timeout: Duration::from_secs(10),
+			request_timeout: Duration::from_secs(10),
+			max_average_latency: Duration::from_secs(5),
+			max_timeout_rate: 0.5,
+			min_samples: 10,
+		}
+	}
 }
 
 /// This is an implementation of the light ethereum network protocol, abstracted
@@ -115,16 +253,183 @@ pub struct LightProtocol {
 	network_id: status::NetworkId,
 	pending_peers: RwLock<HashMap<PeerId, PendingPeer>>,
 	peers: RwLock<HashMap<PeerId, Peer>>,
-	pending_requests: RwLock<HashMap<usize, Request>>,
+	pending_requests: RwLock<HashMap<usize, Requested>>,
 	capabilities: RwLock<Capabilities>,
 	flow_params: FlowParams, // assumed static and same for every peer.
+	handlers: Vec<Arc<Handler>>,
+	checkpoints: BTreeMap<u64, H256>,
+	limits: PeerLimits,
+	engine: Option<Arc<Engine>>,
 	req_id: AtomicUsize,
 }
 
 impl LightProtocol {
+	/// Create a new instance of the protocol manager.
+	pub fn new(provider: Box<Provider>, params: Params) -> Self {
+		let genesis_hash = provider.chain_info().genesis_hash;
+		let capabilities = provider.capabilities().unwrap_or(params.capabilities);
+		LightProtocol {
+			provider: provider,
+			genesis_hash: genesis_hash,
+			network_id: params.network_id,
+			pending_peers: RwLock::new(HashMap::new()),
+			peers: RwLock::new(HashMap::new()),
+			pending_requests: RwLock::new(HashMap::new()),
+			capabilities: RwLock::new(capabilities),
+			flow_params: params.flow_params,
+			handlers: Vec::new(),
+			checkpoints: params.checkpoints,
+			limits: params.limits,
+			engine: params.engine,
+			req_id: AtomicUsize::new(0),
+		}
+	}
+
+	/// Add a handler for verified responses. Must be done before registering
+	/// the protocol with the network service.
+	pub fn add_handler(&mut self, handler: Arc<Handler>) {
+		self.handlers.push(handler);
+	}
+
+	/// IDs of all peers which have completed the handshake.
+	pub fn peers(&self) -> Vec<PeerId> {
+		self.peers.read().keys().cloned().collect()
+	}
+
+	/// Estimated heap memory used by the peer and request tables, in bytes.
+	pub fn mem_used(&self) -> usize {
+		let peers = self.peers.read();
+		let peer_sets: usize = peers.values()
+			.map(|p| p.current_asking.capacity() * mem::size_of::<usize>() + p.known_transactions.capacity() * mem::size_of::<H256>())
+			.sum();
+
+		peers.capacity() * (mem::size_of::<PeerId>() + mem::size_of::<Peer>())
+			+ peer_sets
+			+ self.pending_peers.read().capacity() * (mem::size_of::<PeerId>() + mem::size_of::<PendingPeer>())
+			+ self.pending_requests.read().capacity() * (mem::size_of::<usize>() + mem::size_of::<Requested>())
+	}
+
+	// disconnect peers which haven't completed the handshake in time,
+	// and those too slow or unreliable answering our requests.
+	fn evict_peers(&self, io: &IoContext) {
+		let stale: Vec<PeerId> = self.pending_peers.read().iter()
+			.filter(|&(_, pending)| pending.sent_at.elapsed() > self.limits.handshake_timeout)
+			.map(|(peer, _)| *peer)
+			.collect();
+
+		for peer in stale {
+			debug!(target: "les", "Disconnecting peer {}: handshake timed out", peer);
+			self.pending_peers.write().remove(&peer);
+			io.disconnect_peer(peer);
+		}
+
+		// requests still count as pending for another timeout after timing out, so late responses are accepted.
+		let mut expired: HashMap<PeerId, Vec<usize>> = HashMap::new();
+		{
+			let mut peers = self.peers.write();
+			let mut pending_requests = self.pending_requests.write();
+			for (req_id, requested) in pending_requests.iter_mut() {
+				let elapsed = requested.sent_at.elapsed();
+				if elapsed <= self.limits.request_timeout { continue }
+
+				if !requested.timed_out {
+					requested.timed_out = true;
+					if let Some(peer_info) = peers.get_mut(&requested.peer) {
+						peer_info.stats.note_timeout();
+					}
+				}
+				if elapsed > self.limits.request_timeout * 2 {
+					expired.entry(requested.peer).or_insert_with(Vec::new).push(*req_id);
+				}
+			}
+
+			for (peer, req_ids) in &expired {
+				for req_id in req_ids {
+					pending_requests.remove(req_id);
+				}
+				if let Some(peer_info) = peers.get_mut(peer) {
+					for req_id in req_ids {
+						peer_info.current_asking.remove(req_id);
+					}
+				}
+			}
+
+			for (peer, peer_info) in peers.iter() {
+				if peer_info.stats.should_evict(&self.limits) {
+					debug!(target: "les", "Disconnecting slow peer {}: {:?}", peer, peer_info.stats);
+					io.disconnect_peer(*peer);
+				}
+			}
+		}
+
+		for (peer, req_ids) in expired {
+			trace!(target: "les", "Giving up on {} requests to peer {}", req_ids.len(), peer);
+			for handler in &self.handlers {
+				handler.on_timeout(peer, &req_ids);
+			}
+		}
+	}
+
+	/// Periodic maintenance: evicts stale and slow peers, gives up on long unanswered requests
+	/// and propagates transactions.
+	pub fn tick(&self, io: &IoContext) {
+		self.evict_peers(io);
+		self.propagate_transactions(io);
+	}
+
+	/// Send pending transactions to peers which relay them, skipping those each
+	/// peer has already been sent. Relaying is paid for from the peer's buffer like
+	/// a request; peers whose buffer is below half its limit are skipped for this
+	/// round so that our requests take priority.
+	pub fn propagate_transactions(&self, io: &IoContext) {
+		let pending = self.provider.pending_transactions();
+		if pending.is_empty() { return }
+
+		let pending_hashes: HashSet<H256> = pending.iter().map(|tx| tx.hash()).collect();
+		let mut peers = self.peers.write();
+		for (peer_id, peer) in peers.iter_mut() {
+			if !peer.capabilities.tx_relay { continue }
+
+			// forget transactions which are no longer pending.
+			peer.known_transactions = peer.known_transactions.intersection(&pending_hashes).cloned().collect();
+
+			peer.remote_flow.recharge(&mut peer.remote_buffer);
+			if peer.remote_buffer.current() < *peer.remote_flow.limit() / U256::from(2) {
+				trace!(target: "les", "Deferring transactions to peer {}: low buffer", peer_id);
+				continue;
+			}
+
+			let to_send: Vec<_> = pending.iter()
+				.filter(|tx| !peer.known_transactions.contains(&tx.hash()))
+				.take(MAX_TRANSACTIONS_PER_ROUND)
+				.collect();
+
+			if to_send.is_empty() { continue }
+
+			let cost = peer.remote_flow.compute_transactions_cost(to_send.len());
+			if peer.remote_buffer.deduct_cost(cost).is_err() {
+				trace!(target: "les", "Deferring transactions to peer {}: insufficient buffer", peer_id);
+				continue;
+			}
+
+			let mut stream = RlpStream::new_list(to_send.len());
+			for tx in &to_send {
+				stream.append(*tx);
+			}
+
+			match io.send(*peer_id, packet::SEND_TRANSACTIONS, stream.out()) {
+				Ok(()) => {
+					trace!(target: "les", "Sent {} transactions to peer {}", to_send.len(), peer_id);
+					peer.known_transactions.extend(to_send.iter().map(|tx| tx.hash()));
+				}
+				Err(e) => debug!(target: "les", "Error sending transactions to peer {}: {}", peer_id, e),
+			}
+		}
+	}
+
 	/// Make an announcement of new chain head and capabilities to all peers.
 	/// The announcement is expected to be valid.
-	pub fn make_announcement(&self, mut announcement: Announcement, io: &NetworkContext) {
+	pub fn make_announcement(&self, mut announcement: Announcement, io: &IoContext) {
 		let mut reorgs_map = HashMap::new();
 
 		// calculate reorg info and send packets
@@ -151,11 +456,263 @@ impl LightProtocol {
 			}
 		}
 	}
+
+	/// Make a request to a peer, deducting its maximum cost from our buffer.
+	/// Returns the ID of the request; the response is verified against it on arrival.
+	/// Fails with `NotServer` if the peer doesn't advertise the capabilities to serve it.
+	pub fn request_from(&self, io: &IoContext, peer_id: &PeerId, request: Request) -> Result<usize, Error> {
+		let mut peers = self.peers.write();
+		let peer = match peers.get_mut(peer_id) {
+			Some(peer) => peer,
+			None => return Err(Error::UnknownPeer),
+		};
+
+		let block_number = |hash: &H256| self.provider.block_header(BlockID::Hash(*hash)).map(|header| ::rlp::decode::<Header>(&header).number());
+		if !can_serve(&peer.capabilities, peer.status.head_num, &request, block_number) {
+			return Err(Error::NotServer);
+		}
+
+		let max_cost = peer.remote_flow.compute_cost(request.kind(), request_amount(&request));
+		peer.remote_flow.recharge(&mut peer.remote_buffer);
+		try!(peer.remote_buffer.deduct_cost(max_cost));
+
+		let req_id = self.req_id.fetch_add(1, Ordering::SeqCst);
+		let packet = encode_request(&request, req_id);
+		note_issued(request.kind(), packet.len());
+		try!(io.send(*peer_id, request_packet(&request), packet));
+
+		peer.current_asking.insert(req_id);
+		self.pending_requests.write().insert(req_id, Requested {
+			request: request,
+			peer: *peer_id,
+			sent_at: Instant::now(),
+			timed_out: false,
+		});
+
+		Ok(req_id)
+	}
+
+	/// Make a request to the first peer able to serve it.
+	/// Returns the peer and request ID, or `NoCapablePeers` if no peer could take it.
+	pub fn request(&self, io: &IoContext, request: Request) -> Result<(PeerId, usize), Error> {
+		for peer in self.peers() {
+			match self.request_from(io, &peer, request.clone()) {
+				Ok(req_id) => return Ok((peer, req_id)),
+				Err(Error::NotServer) => {}
+				Err(e) => trace!(target: "les", "Failed to make request of peer {}: {}", peer, e),
+			}
+		}
+
+		Err(Error::NoCapablePeers)
+	}
+
+	/// Handle a packet received from a peer.
+	pub fn handle_packet(&self, io: &IoContext, peer: &PeerId, packet_id: u8, data: &[u8]) {
+		let rlp = UntrustedRlp::new(data);
+		let started = Instant::now();
+
+		// handle the packet
+		let res = match packet_id {
+			packet::STATUS => self.status(peer, rlp),
+			packet::ANNOUNCE => self.announcement(peer, rlp),
+
+			packet::GET_BLOCK_HEADERS => self.get_block_headers(peer, io, rlp),
+			packet::BLOCK_HEADERS => self.block_headers(peer, io, rlp),
+
+			packet::GET_BLOCK_BODIES => self.get_block_bodies(peer, io, rlp),
+			packet::BLOCK_BODIES => self.block_bodies(peer, io, rlp),
+
+			packet::GET_RECEIPTS => self.get_receipts(peer, io, rlp),
+			packet::RECEIPTS => self.receipts(peer, io, rlp),
+
+			packet::GET_PROOFS => self.get_proofs(peer, io, rlp),
+			packet::PROOFS => self.proofs(peer, io, rlp),
+
+			packet::GET_CONTRACT_CODES => self.get_contract_code(peer, io, rlp),
+			packet::CONTRACT_CODES => self.contract_code(peer, io, rlp),
+
+			packet::GET_HEADER_PROOFS => self.get_header_proofs(peer, io, rlp),
+			packet::HEADER_PROOFS => self.header_proofs(peer, io, rlp),
+
+			packet::SEND_TRANSACTIONS => self.relay_transactions(peer, io, rlp),
+
+			other => {
+				Err(Error::UnrecognizedPacket(other))
+			}
+		};
+
+		if let (true, Some(kind)) = (res.is_ok(), served_kind(packet_id)) {
+			metrics().histogram("parity_les_serve_duration_seconds", "Time taken to serve LES requests.", &[("kind", kind_label(kind))], DEFAULT_BUCKETS)
+				.observe_duration(started.elapsed());
+		}
+
+		// if something went wrong, figure out how much to punish the peer.
+		if let Err(e) = res {
+			match e.punishment() {
+				Punishment::None => {}
+				Punishment::Disconnect => {
+					debug!(target: "les", "Disconnecting peer {}: {}", peer, e);
+					io.disconnect_peer(*peer)
+				}
+				Punishment::Disable => {
+					debug!(target: "les", "Disabling peer {}: {}", peer, e);
+					io.disable_peer(*peer)
+				}
+			}
+		}
+	}
+}
+
+// label of a request kind in metrics.
+fn kind_label(kind: request::Kind) -> &'static str {
+	match kind {
+		request::Kind::Headers => "headers",
+		request::Kind::Bodies => "bodies",
+		request::Kind::Receipts => "receipts",
+		request::Kind::StateProofs => "proofs",
+		request::Kind::Codes => "code",
+		request::Kind::HeaderProofs => "header_proofs",
+	}
+}
+
+// kind of request a packet asks us to serve, if any.
+fn served_kind(packet_id: u8) -> Option<request::Kind> {
+	match packet_id {
+		packet::GET_BLOCK_HEADERS => Some(request::Kind::Headers),
+		packet::GET_BLOCK_BODIES => Some(request::Kind::Bodies),
+		packet::GET_RECEIPTS => Some(request::Kind::Receipts),
+		packet::GET_PROOFS => Some(request::Kind::StateProofs),
+		packet::GET_CONTRACT_CODES => Some(request::Kind::Codes),
+		packet::GET_HEADER_PROOFS => Some(request::Kind::HeaderProofs),
+		_ => None,
+	}
+}
+
+// send a response to a served request, recording its size.
+fn respond(io: &IoContext, packet_id: u8, kind: request::Kind, data: Vec<u8>) -> Result<(), Error> {
+	let m = metrics();
+	let label = [("kind", kind_label(kind))];
+	m.counter("parity_les_requests_served_total", "Number of LES requests served.", &label).inc();
+	m.counter("parity_les_served_bytes_total", "Size of LES responses served.", &label).add(data.len());
+	io.respond(packet_id, data).map_err(Into::into)
+}
+
+// record a request made to a peer.
+fn note_issued(kind: request::Kind, bytes: usize) {
+	let m = metrics();
+	let label = [("kind", kind_label(kind))];
+	m.counter("parity_les_requests_issued_total", "Number of LES requests made to peers.", &label).inc();
+	m.counter("parity_les_issued_bytes_total", "Size of LES requests made to peers.", &label).add(bytes);
+}
+
+// record a response to a request made to a peer.
+fn note_response(kind: request::Kind, bytes: usize, elapsed: Duration) {
+	let m = metrics();
+	let label = [("kind", kind_label(kind))];
+	m.counter("parity_les_response_bytes_total", "Size of LES responses received from peers.", &label).add(bytes);
+	m.histogram("parity_les_request_duration_seconds", "Round-trip time of LES requests made to peers.", &label, DEFAULT_BUCKETS)
+		.observe_duration(elapsed);
+}
+
+// whether a peer with the given capabilities and head can serve a request.
+// `block_number` gives the number of a block requested by hash; blocks unknown
+// locally are assumed to be within the range the peer serves.
+fn can_serve<F>(capabilities: &Capabilities, head_num: u64, request: &Request, block_number: F) -> bool
+	where F: Fn(&H256) -> Option<u64>
+{
+	let in_range = |since: Option<u64>, num: u64| since.map_or(false, |since| num >= since && num <= head_num);
+	let serves = |since: Option<u64>, hash: &H256| since.is_some() && block_number(hash).map_or(true, |num| in_range(since, num));
+
+	match *request {
+		Request::Headers(ref req) => capabilities.serve_headers && match req.block {
+			request::HashOrNumber::Number(num) => num <= head_num,
+			request::HashOrNumber::Hash(ref hash) => block_number(hash).map_or(true, |num| num <= head_num),
+		},
+		Request::Bodies(ref req) => req.block_hashes.iter().all(|hash| serves(capabilities.serve_chain_since, hash))
+			&& capabilities.serve_chain_since.is_some(),
+		Request::Receipts(ref req) => req.block_hashes.iter().all(|hash| serves(capabilities.serve_chain_since, hash))
+			&& capabilities.serve_chain_since.is_some(),
+		Request::HeaderProofs(ref req) => req.requests.iter().all(|r| in_range(capabilities.serve_chain_since, r.block_number))
+			&& capabilities.serve_chain_since.is_some(),
+		Request::StateProofs(ref req) => req.requests.iter().all(|r| serves(capabilities.serve_state_since, &r.block))
+			&& capabilities.serve_state_since.is_some(),
+		Request::Codes(ref req) => req.code_requests.iter().all(|&(ref hash, _)| serves(capabilities.serve_state_since, hash))
+			&& capabilities.serve_state_since.is_some(),
+	}
+}
+
+// number of items requested, used to compute the cost.
+fn request_amount(request: &Request) -> usize {
+	match *request {
+		Request::Headers(ref req) => req.max,
+		Request::Bodies(ref req) => req.block_hashes.len(),
+		Request::Receipts(ref req) => req.block_hashes.len(),
+		Request::StateProofs(ref req) => req.requests.len(),
+		Request::Codes(ref req) => req.code_requests.len(),
+		Request::HeaderProofs(ref req) => req.requests.len(),
+	}
+}
+
+// packet ID a request is sent with.
+fn request_packet(request: &Request) -> u8 {
+	match *request {
+		Request::Headers(_) => packet::GET_BLOCK_HEADERS,
+		Request::Bodies(_) => packet::GET_BLOCK_BODIES,
+		Request::Receipts(_) => packet::GET_RECEIPTS,
+		Request::StateProofs(_) => packet::GET_PROOFS,
+		Request::Codes(_) => packet::GET_CONTRACT_CODES,
+		Request::HeaderProofs(_) => packet::GET_HEADER_PROOFS,
+	}
+}
+
+// encode a request with the given ID as packet data.
+fn encode_request(request: &Request, req_id: usize) -> Vec<u8> {
+	match *request {
+		Request::Headers(ref req) => {
+			let mut stream = RlpStream::new_list(2);
+			stream.append(&req_id).append(req);
+			stream.out()
+		}
+		Request::Bodies(ref req) => {
+			let mut stream = RlpStream::new_list(2);
+			stream.append(&req_id).append(req);
+			stream.out()
+		}
+		Request::Receipts(ref req) => {
+			let mut stream = RlpStream::new_list(2);
+			stream.append(&req_id).append(req);
+			stream.out()
+		}
+		Request::StateProofs(ref req) => {
+			let mut stream = RlpStream::new_list(req.requests.len() + 1);
+			stream.append(&req_id);
+			for proof_req in &req.requests {
+				stream.append(proof_req);
+			}
+			stream.out()
+		}
+		Request::Codes(ref req) => {
+			let mut stream = RlpStream::new_list(req.code_requests.len() + 1);
+			stream.append(&req_id);
+			for &(ref block, ref account) in &req.code_requests {
+				stream.begin_list(2).append(block).append(account);
+			}
+			stream.out()
+		}
+		Request::HeaderProofs(ref req) => {
+			let mut stream = RlpStream::new_list(req.requests.len() + 1);
+			stream.append(&req_id);
+			for proof_req in &req.requests {
+				stream.append(proof_req);
+			}
+			stream.out()
+		}
+	}
 }
 
 impl LightProtocol {
-	// called when a peer connects.
-	fn on_connect(&self, peer: &PeerId, io: &NetworkContext) {
+	/// Called when a peer connects: sends our status.
+	pub fn on_connect(&self, peer: &PeerId, io: &IoContext) {
 		let peer = *peer;
 
 		match self.send_status(peer, io) {
@@ -169,15 +726,30 @@ impl LightProtocol {
 		}
 	}
 
-	// called when a peer disconnects.
-	fn on_disconnect(&self, peer: PeerId) {
-		// TODO: reassign all requests assigned to this peer.
+	/// Called when a peer disconnects: drops its pending requests.
+	pub fn on_disconnect(&self, peer: PeerId) {
 		self.pending_peers.write().remove(&peer);
-		self.peers.write().remove(&peer);
+		let unfulfilled: Vec<_> = match self.peers.write().remove(&peer) {
+			Some(peer_info) => peer_info.current_asking.into_iter().collect(),
+			None => return,
+		};
+
+		if unfulfilled.is_empty() { return }
+
+		{
+			let mut pending_requests = self.pending_requests.write();
+			for req_id in &unfulfilled {
+				pending_requests.remove(req_id);
+			}
+		}
+
+		for handler in &self.handlers {
+			handler.on_disconnect(peer, &unfulfilled);
+		}
 	}
 
 	// send status to a peer.
-	fn send_status(&self, peer: PeerId, io: &NetworkContext) -> Result<PendingPeer, NetworkError> {
+	fn send_status(&self, peer: PeerId, io: &IoContext) -> Result<PendingPeer, NetworkError> {
 		let chain_info = self.provider.chain_info();
 
 		// TODO: could update capabilities here.
@@ -199,6 +771,7 @@ impl LightProtocol {
 
 		Ok(PendingPeer {
 			sent_head: chain_info.best_block_hash,
+			sent_at: Instant::now(),
 		})
 	}
 
@@ -227,6 +800,8 @@ impl LightProtocol {
 			capabilities: capabilities,
 			remote_flow: flow_params,
 			sent_head: pending.sent_head,
+			known_transactions: HashSet::new(),
+			stats: PeerStats::default(),
 		});
 
 		Ok(())
@@ -273,7 +848,7 @@ impl LightProtocol {
 	}
 
 	// Handle a request for block headers.
-	fn get_block_headers(&self, peer: &PeerId, io: &NetworkContext, data: UntrustedRlp) -> Result<(), Error> {
+	fn get_block_headers(&self, peer: &PeerId, io: &IoContext, data: UntrustedRlp) -> Result<(), Error> {
 		const MAX_HEADERS: usize = 512;
 
 		let mut present_buffer = match self.peers.read().get(peer) {
@@ -287,15 +862,8 @@ impl LightProtocol {
 		self.flow_params.recharge(&mut present_buffer);
 		let req_id: u64 = try!(data.val_at(0));
 
-		let req = request::Headers {
-			block: {
-				let rlp = try!(data.at(1));
-				(try!(rlp.val_at(0)), try!(rlp.val_at(1)))
-			},
-			max: ::std::cmp::min(MAX_HEADERS, try!(data.val_at(2))),
-			skip: try!(data.val_at(3)),
-			reverse: try!(data.val_at(4)),
-		};
+		let mut req: request::Headers = try!(data.val_at(1));
+		req.max = ::std::cmp::min(MAX_HEADERS, req.max);
 
 		let max_cost = self.flow_params.compute_cost(request::Kind::Headers, req.max);
 		try!(present_buffer.deduct_cost(max_cost));
@@ -315,25 +883,61 @@ impl LightProtocol {
 			}
 		};
 
-		io.respond(packet::BLOCK_HEADERS, {
-			let mut stream = RlpStream::new_list(response.len() + 2);
-			stream.append(&req_id).append(&cur_buffer);
+		let response = request::BlockHeaders { headers: response };
+		respond(io, packet::BLOCK_HEADERS, request::Kind::Headers, response.les_encode(req_id, &cur_buffer))
+	}
 
-			for header in response {
-				stream.append_raw(&header, 1);
+	// Match a response to the request it answers, updating the peer's buffer
+	// with the value the response carries.
+	fn pre_verify_response(&self, peer: &PeerId, kind: request::Kind, raw: &UntrustedRlp) -> Result<(usize, Request), Error> {
+		let req_id: usize = try!(raw.val_at(0));
+		let cur_buffer: U256 = try!(raw.val_at(1));
+
+		let requested = {
+			let mut pending_requests = self.pending_requests.write();
+			match pending_requests.get(&req_id) {
+				Some(requested) if requested.peer == *peer && requested.request.kind() == kind => {}
+				_ => return Err(Error::UnsolicitedResponse),
 			}
+			pending_requests.remove(&req_id).expect("presence checked above; qed")
+		};
+		let elapsed = requested.sent_at.elapsed();
+		note_response(kind, raw.as_raw().len(), elapsed);
 
-			stream.out()
-		}).map_err(Into::into)
+		if let Some(peer_info) = self.peers.write().get_mut(peer) {
+			peer_info.current_asking.remove(&req_id);
+			if !requested.timed_out {
+				peer_info.stats.note_response(elapsed);
+			}
+			peer_info.remote_buffer.update_to(cur_buffer);
+		}
+
+		Ok((req_id, requested.request))
 	}
 
 	// Receive a response for block headers.
-	fn block_headers(&self, _: &PeerId, _: &NetworkContext, _: UntrustedRlp) -> Result<(), Error> {
-		unimplemented!()
+	fn block_headers(&self, peer: &PeerId, _: &IoContext, raw: UntrustedRlp) -> Result<(), Error> {
+		let (req_id, req) = match try!(self.pre_verify_response(peer, request::Kind::Headers, &raw)) {
+			(req_id, Request::Headers(req)) => (req_id, req),
+			_ => unreachable!("request kind checked in pre_verify_response; qed"),
+		};
+
+		let headers = try!(request::BlockHeaders::les_decode(&raw).decode());
+		try!(verification::check_headers(&req, &headers));
+		try!(verification::check_checkpoints(&self.checkpoints, &headers));
+		if let Some(ref engine) = self.engine {
+			try!(verification::check_seals(&**engine, &headers));
+		}
+
+		trace!(target: "les", "Verified {} headers from peer {}", headers.len(), peer);
+		for handler in &self.handlers {
+			handler.on_block_headers(*peer, req_id, &headers);
+		}
+		Ok(())
 	}
 
 	// Handle a request for block bodies.
-	fn get_block_bodies(&self, peer: &PeerId, io: &NetworkContext, data: UntrustedRlp) -> Result<(), Error> {
+	fn get_block_bodies(&self, peer: &PeerId, io: &IoContext, data: UntrustedRlp) -> Result<(), Error> {
 		const MAX_BODIES: usize = 256;
 
 		let mut present_buffer = match self.peers.read().get(peer) {
@@ -347,9 +951,8 @@ impl LightProtocol {
 		self.flow_params.recharge(&mut present_buffer);
 		let req_id: u64 = try!(data.val_at(0));
 
-		let req = request::Bodies {
-			block_hashes: try!(data.iter().skip(1).take(MAX_BODIES).map(|x| x.as_val()).collect())
-		};
+		let mut req: request::Bodies = try!(data.val_at(1));
+		req.block_hashes.truncate(MAX_BODIES);
 
 		let max_cost = self.flow_params.compute_cost(request::Kind::Bodies, req.block_hashes.len());
 		try!(present_buffer.deduct_cost(max_cost));
@@ -370,121 +973,353 @@ impl LightProtocol {
 			}
 		};
 
-		io.respond(packet::BLOCK_BODIES, {
-			let mut stream = RlpStream::new_list(response.len() + 2);
-			stream.append(&req_id).append(&cur_buffer);
-
-			for body in response {
-				stream.append_raw(&body, 1);
-			}
-
-			stream.out()
-		}).map_err(Into::into)
+		let response = request::BlockBodies { bodies: response };
+		respond(io, packet::BLOCK_BODIES, request::Kind::Bodies, response.les_encode(req_id, &cur_buffer))
 	}
 
 	// Receive a response for block bodies.
-	fn block_bodies(&self, _: &PeerId, _: &NetworkContext, _: UntrustedRlp) -> Result<(), Error> {
-		unimplemented!()
+	fn block_bodies(&self, peer: &PeerId, _: &IoContext, raw: UntrustedRlp) -> Result<(), Error> {
+		let (req_id, req) = match try!(self.pre_verify_response(peer, request::Kind::Bodies, &raw)) {
+			(req_id, Request::Bodies(req)) => (req_id, req),
+			_ => unreachable!("request kind checked in pre_verify_response; qed"),
+		};
+
+		let mut bodies = request::BlockBodies::les_decode(&raw).bodies;
+		bodies.truncate(req.block_hashes.len());
+		for (hash, body) in req.block_hashes.iter().zip(bodies.iter()) {
+			match self.provider.block_header(BlockID::Hash(*hash)) {
+				Some(header) => try!(verification::check_body(&::rlp::decode(&header), body)),
+				None => trace!(target: "les", "Unable to verify body of unknown block {}", hash),
+			}
+		}
+
+		for handler in &self.handlers {
+			handler.on_block_bodies(*peer, req_id, &bodies);
+		}
+		Ok(())
 	}
 
 	// Handle a request for receipts.
-	fn get_receipts(&self, _: &PeerId, _: &NetworkContext, _: UntrustedRlp) -> Result<(), Error> {
-		unimplemented!()
+	fn get_receipts(&self, peer: &PeerId, io: &IoContext, data: UntrustedRlp) -> Result<(), Error> {
+		const MAX_RECEIPTS: usize = 256;
+
+		let mut present_buffer = match self.peers.read().get(peer) {
+			Some(peer) => peer.local_buffer.clone(),
+			None => {
+				debug!(target: "les", "Ignoring request from unknown peer");
+				return Ok(())
+			}
+		};
+
+		self.flow_params.recharge(&mut present_buffer);
+		let req_id: u64 = try!(data.val_at(0));
+
+		let mut req: request::Receipts = try!(data.val_at(1));
+		req.block_hashes.truncate(MAX_RECEIPTS);
+
+		let max_cost = self.flow_params.compute_cost(request::Kind::Receipts, req.block_hashes.len());
+		try!(present_buffer.deduct_cost(max_cost));
+
+		let response = self.provider.receipts(req);
+		let response_len = response.iter().filter(|x| &x[..] != &::rlp::EMPTY_LIST_RLP).count();
+		let actual_cost = self.flow_params.compute_cost(request::Kind::Receipts, response_len);
+
+		let cur_buffer = match self.peers.write().get_mut(peer) {
+			Some(peer) => {
+				self.flow_params.recharge(&mut peer.local_buffer);
+				try!(peer.local_buffer.deduct_cost(actual_cost));
+				peer.local_buffer.current()
+			}
+			None => {
+				debug!(target: "les", "peer disconnected during serving of request.");
+				return Ok(())
+			}
+		};
+
+		let response = request::BlockReceipts { receipts: response };
+		respond(io, packet::RECEIPTS, request::Kind::Receipts, response.les_encode(req_id, &cur_buffer))
 	}
 
 	// Receive a response for receipts.
-	fn receipts(&self, _: &PeerId, _: &NetworkContext, _: UntrustedRlp) -> Result<(), Error> {
-		unimplemented!()
+	fn receipts(&self, peer: &PeerId, _: &IoContext, raw: UntrustedRlp) -> Result<(), Error> {
+		let (req_id, req) = match try!(self.pre_verify_response(peer, request::Kind::Receipts, &raw)) {
+			(req_id, Request::Receipts(req)) => (req_id, req),
+			_ => unreachable!("request kind checked in pre_verify_response; qed"),
+		};
+
+		let mut all_receipts = Vec::new();
+		for (hash, receipts) in req.block_hashes.iter().zip(request::BlockReceipts::les_decode(&raw).receipts) {
+			let receipts: Vec<Receipt> = try!(UntrustedRlp::new(&receipts).as_val());
+			match self.provider.block_header(BlockID::Hash(*hash)) {
+				Some(header) => try!(verification::check_receipts(&::rlp::decode(&header), &receipts)),
+				None => trace!(target: "les", "Unable to verify receipts of unknown block {}", hash),
+			}
+			all_receipts.push(receipts);
+		}
+
+		for handler in &self.handlers {
+			handler.on_receipts(*peer, req_id, &all_receipts);
+		}
+		Ok(())
 	}
 
 	// Handle a request for proofs.
-	fn get_proofs(&self, _: &PeerId, _: &NetworkContext, _: UntrustedRlp) -> Result<(), Error> {
-		unimplemented!()
+	fn get_proofs(&self, peer: &PeerId, io: &IoContext, data: UntrustedRlp) -> Result<(), Error> {
+		const MAX_PROOFS: usize = 128;
+
+		let mut present_buffer = match self.peers.read().get(peer) {
+			Some(peer) => peer.local_buffer.clone(),
+			None => {
+				debug!(target: "les", "Ignoring request from unknown peer");
+				return Ok(())
+			}
+		};
+
+		self.flow_params.recharge(&mut present_buffer);
+		let req_id: u64 = try!(data.val_at(0));
+
+		let req = request::StateProofs {
+			requests: try!(data.iter().skip(1).take(MAX_PROOFS).map(|x| x.as_val()).collect::<Result<Vec<_>, _>>()),
+		};
+
+		let max_cost = self.flow_params.compute_cost(request::Kind::StateProofs, req.requests.len());
+		try!(present_buffer.deduct_cost(max_cost));
+
+		let response = self.provider.proofs(req);
+		let response_len = response.iter().filter(|x| &x[..] != &::rlp::EMPTY_LIST_RLP).count();
+		let actual_cost = self.flow_params.compute_cost(request::Kind::StateProofs, response_len);
+
+		let cur_buffer = match self.peers.write().get_mut(peer) {
+			Some(peer) => {
+				self.flow_params.recharge(&mut peer.local_buffer);
+				try!(peer.local_buffer.deduct_cost(actual_cost));
+				peer.local_buffer.current()
+			}
+			None => {
+				debug!(target: "les", "peer disconnected during serving of request.");
+				return Ok(())
+			}
+		};
+
+		let mut stream = RlpStream::new_list(response.len() + 2);
+		stream.append(&req_id).append(&cur_buffer);
+		for proof in &response {
+			stream.append_raw(proof, 1);
+		}
+		respond(io, packet::PROOFS, request::Kind::StateProofs, stream.out())
 	}
 
 	// Receive a response for proofs.
-	fn proofs(&self, _: &PeerId, _: &NetworkContext, _: UntrustedRlp) -> Result<(), Error> {
-		unimplemented!()
+	fn proofs(&self, peer: &PeerId, _: &IoContext, raw: UntrustedRlp) -> Result<(), Error> {
+		let (req_id, req) = match try!(self.pre_verify_response(peer, request::Kind::StateProofs, &raw)) {
+			(req_id, Request::StateProofs(req)) => (req_id, req),
+			_ => unreachable!("request kind checked in pre_verify_response; qed"),
+		};
+
+		let mut proofs = Vec::new();
+		for (proof_req, proof) in req.requests.iter().zip(raw.iter().skip(2)) {
+			let nodes: Vec<Bytes> = try!(proof.as_val());
+
+			// nodes above `from_level` may be omitted, so such proofs can't be checked from the root.
+			if proof_req.from_level == 0 {
+				match self.provider.block_header(BlockID::Hash(proof_req.block)) {
+					Some(header) => {
+						let header: Header = ::rlp::decode(&header);
+						try!(verification::check_state_proof(header.state_root(), proof_req, &nodes));
+					}
+					None => trace!(target: "les", "Unable to verify proof against unknown block {}", proof_req.block),
+				}
+			}
+			proofs.push(nodes);
+		}
+
+		for handler in &self.handlers {
+			handler.on_state_proofs(*peer, req_id, &proofs);
+		}
+		Ok(())
 	}
 
 	// Handle a request for contract code.
-	fn get_contract_code(&self, _: &PeerId, _: &NetworkContext, _: UntrustedRlp) -> Result<(), Error> {
-		unimplemented!()
+	fn get_contract_code(&self, peer: &PeerId, io: &IoContext, data: UntrustedRlp) -> Result<(), Error> {
+		const MAX_CODES: usize = 256;
+
+		let mut present_buffer = match self.peers.read().get(peer) {
+			Some(peer) => peer.local_buffer.clone(),
+			None => {
+				debug!(target: "les", "Ignoring request from unknown peer");
+				return Ok(())
+			}
+		};
+
+		self.flow_params.recharge(&mut present_buffer);
+		let req_id: u64 = try!(data.val_at(0));
+
+		let req = request::ContractCodes {
+			code_requests: try!(data.iter().skip(1).take(MAX_CODES)
+				.map(|x| Ok((try!(x.val_at(0)), try!(x.val_at(1)))))
+				.collect::<Result<Vec<_>, DecoderError>>()),
+		};
+
+		let max_cost = self.flow_params.compute_cost(request::Kind::Codes, req.code_requests.len());
+		try!(present_buffer.deduct_cost(max_cost));
+
+		let response = self.provider.code(req);
+		let response_len = response.iter().filter(|x| !x.is_empty()).count();
+		let actual_cost = self.flow_params.compute_cost(request::Kind::Codes, response_len);
+
+		let cur_buffer = match self.peers.write().get_mut(peer) {
+			Some(peer) => {
+				self.flow_params.recharge(&mut peer.local_buffer);
+				try!(peer.local_buffer.deduct_cost(actual_cost));
+				peer.local_buffer.current()
+			}
+			None => {
+				debug!(target: "les", "peer disconnected during serving of request.");
+				return Ok(())
+			}
+		};
+
+		let mut stream = RlpStream::new_list(response.len() + 2);
+		stream.append(&req_id).append(&cur_buffer);
+		for code in &response {
+			stream.append(code);
+		}
+		respond(io, packet::CONTRACT_CODES, request::Kind::Codes, stream.out())
 	}
 
 	// Receive a response for contract code.
-	fn contract_code(&self, _: &PeerId, _: &NetworkContext, _: UntrustedRlp) -> Result<(), Error> {
-		unimplemented!()
+	fn contract_code(&self, peer: &PeerId, _: &IoContext, raw: UntrustedRlp) -> Result<(), Error> {
+		let (req_id, req) = match try!(self.pre_verify_response(peer, request::Kind::Codes, &raw)) {
+			(req_id, Request::Codes(req)) => (req_id, req),
+			_ => unreachable!("request kind checked in pre_verify_response; qed"),
+		};
+
+		let mut codes = Vec::new();
+		for code in raw.iter().skip(2).take(req.code_requests.len()) {
+			codes.push(try!(code.as_val::<Bytes>()));
+		}
+
+		for handler in &self.handlers {
+			handler.on_code(*peer, req_id, &codes);
+		}
+		Ok(())
 	}
 
 	// Handle a request for header proofs
-	fn get_header_proofs(&self, _: &PeerId, _: &NetworkContext, _: UntrustedRlp) -> Result<(), Error> {
-		unimplemented!()
-	}
+	fn get_header_proofs(&self, peer: &PeerId, io: &IoContext, data: UntrustedRlp) -> Result<(), Error> {
+		const MAX_PROOFS: usize = 128;
 
-	// Receive a response for header proofs
-	fn header_proofs(&self, _: &PeerId, _: &NetworkContext, _: UntrustedRlp) -> Result<(), Error> {
-		unimplemented!()
-	}
+		let mut present_buffer = match self.peers.read().get(peer) {
+			Some(peer) => peer.local_buffer.clone(),
+			None => {
+				debug!(target: "les", "Ignoring request from unknown peer");
+				return Ok(())
+			}
+		};
 
-	// Receive a set of transactions to relay.
-	fn relay_transactions(&self, _: &PeerId, _: &NetworkContext, _: UntrustedRlp) -> Result<(), Error> {
-		unimplemented!()
-	}
-}
+		self.flow_params.recharge(&mut present_buffer);
+		let req_id: u64 = try!(data.val_at(0));
 
-impl NetworkProtocolHandler for LightProtocol {
-	fn initialize(&self, io: &NetworkContext) {
-		io.register_timer(TIMEOUT, TIMEOUT_INTERVAL_MS).expect("Error registering sync timer.");
-	}
+		let req = request::HeaderProofs {
+			requests: try!(data.iter().skip(1).take(MAX_PROOFS).map(|x| x.as_val()).collect::<Result<Vec<_>, _>>()),
+		};
 
-	fn read(&self, io: &NetworkContext, peer: &PeerId, packet_id: u8, data: &[u8]) {
-		let rlp = UntrustedRlp::new(data);
+		let max_cost = self.flow_params.compute_cost(request::Kind::HeaderProofs, req.requests.len());
+		try!(present_buffer.deduct_cost(max_cost));
 
-		// handle the packet
-		let res = match packet_id {
-			packet::STATUS => self.status(peer, rlp),
-			packet::ANNOUNCE => self.announcement(peer, rlp),
+		let response = self.provider.header_proofs(req);
+		let response_len = response.iter().filter(|x| &x[..] != &::rlp::EMPTY_LIST_RLP).count();
+		let actual_cost = self.flow_params.compute_cost(request::Kind::HeaderProofs, response_len);
 
-			packet::GET_BLOCK_HEADERS => self.get_block_headers(peer, io, rlp),
-			packet::BLOCK_HEADERS => self.block_headers(peer, io, rlp),
+		let cur_buffer = match self.peers.write().get_mut(peer) {
+			Some(peer) => {
+				self.flow_params.recharge(&mut peer.local_buffer);
+				try!(peer.local_buffer.deduct_cost(actual_cost));
+				peer.local_buffer.current()
+			}
+			None => {
+				debug!(target: "les", "peer disconnected during serving of request.");
+				return Ok(())
+			}
+		};
 
-			packet::GET_BLOCK_BODIES => self.get_block_bodies(peer, io, rlp),
-			packet::BLOCK_BODIES => self.block_bodies(peer, io, rlp),
+		let mut stream = RlpStream::new_list(response.len() + 2);
+		stream.append(&req_id).append(&cur_buffer);
+		for proof in &response {
+			stream.append_raw(proof, 1);
+		}
+		respond(io, packet::HEADER_PROOFS, request::Kind::HeaderProofs, stream.out())
+	}
 
-			packet::GET_RECEIPTS => self.get_receipts(peer, io, rlp),
-			packet::RECEIPTS => self.receipts(peer, io, rlp),
+	// Receive a response for header proofs
+	fn header_proofs(&self, peer: &PeerId, _: &IoContext, raw: UntrustedRlp) -> Result<(), Error> {
+		let (req_id, req) = match try!(self.pre_verify_response(peer, request::Kind::HeaderProofs, &raw)) {
+			(req_id, Request::HeaderProofs(req)) => (req_id, req),
+			_ => unreachable!("request kind checked in pre_verify_response; qed"),
+		};
 
-			packet::GET_PROOFS => self.get_proofs(peer, io, rlp),
-			packet::PROOFS => self.proofs(peer, io, rlp),
+		let mut proofs = Vec::new();
+		for (proof_req, proof) in req.requests.iter().zip(raw.iter().skip(2)) {
+			// an empty list stands for a proof the peer couldn't give.
+			if proof.is_empty() { break }
 
-			packet::GET_CONTRACT_CODES => self.get_contract_code(peer, io, rlp),
-			packet::CONTRACT_CODES => self.contract_code(peer, io, rlp),
+			let header: Header = try!(proof.val_at(0));
+			if header.number() != proof_req.block_number {
+				return Err(verification::Error::WrongStartBlock.into());
+			}
+			proofs.push((header, try!(proof.val_at::<Vec<Bytes>>(1))));
+		}
 
-			packet::GET_HEADER_PROOFS => self.get_header_proofs(peer, io, rlp),
-			packet::HEADER_PROOFS => self.header_proofs(peer, io, rlp),
+		for handler in &self.handlers {
+			handler.on_header_proofs(*peer, req_id, &proofs);
+		}
+		Ok(())
+	}
 
-			packet::SEND_TRANSACTIONS => self.relay_transactions(peer, io, rlp),
+	// Receive a set of transactions to relay, paid for from the peer's buffer.
+	fn relay_transactions(&self, peer: &PeerId, _: &IoContext, data: UntrustedRlp) -> Result<(), Error> {
+		const MAX_TRANSACTIONS: usize = 256;
 
-			other => {
-				Err(Error::UnrecognizedPacket(other))
-			}
-		};
+		if !self.capabilities.read().tx_relay {
+			trace!(target: "les", "Ignoring transactions from peer {}: not relaying", peer);
+			return Ok(())
+		}
 
-		// if something went wrong, figure out how much to punish the peer.
-		if let Err(e) = res {
-			match e.punishment() {
-				Punishment::None => {}
-				Punishment::Disconnect => {
-					debug!(target: "les", "Disconnecting peer {}: {}", peer, e);
-					io.disconnect_peer(*peer)
-				}
-				Punishment::Disable => {
-					debug!(target: "les", "Disabling peer {}: {}", peer, e);
-					io.disable_peer(*peer)
-				}
+		let mut transactions = Vec::new();
+		let mut hashes = Vec::new();
+		for tx in data.iter().take(MAX_TRANSACTIONS) {
+			let signed: SignedTransaction = try!(tx.as_val());
+			hashes.push(signed.hash());
+			transactions.push(tx.as_raw().to_vec());
+		}
+
+		let cost = self.flow_params.compute_transactions_cost(transactions.len());
+		match self.peers.write().get_mut(peer) {
+			Some(peer_info) => {
+				self.flow_params.recharge(&mut peer_info.local_buffer);
+				try!(peer_info.local_buffer.deduct_cost(cost));
+				// don't send them back.
+				peer_info.known_transactions.extend(hashes);
+			}
+			None => {
+				debug!(target: "les", "Ignoring transactions from unknown peer");
+				return Ok(())
 			}
 		}
+
+		trace!(target: "les", "Queueing {} transactions relayed by peer {}", transactions.len(), peer);
+		self.provider.queue_transactions(transactions);
+		Ok(())
+	}
+}
+
+impl NetworkProtocolHandler for LightProtocol {
+	fn initialize(&self, io: &NetworkContext) {
+		io.register_timer(TIMEOUT, TIMEOUT_INTERVAL_MS).expect("Error registering sync timer.");
+	}
+
+	fn read(&self, io: &NetworkContext, peer: &PeerId, packet_id: u8, data: &[u8]) {
+		self.handle_packet(io, peer, packet_id, data);
 	}
 
 	fn connected(&self, io: &NetworkContext, peer: &PeerId) {
@@ -495,12 +1330,98 @@ impl NetworkProtocolHandler for LightProtocol {
 		self.on_disconnect(*peer);
 	}
 
-	fn timeout(&self, _io: &NetworkContext, timer: TimerToken) {
+	fn timeout(&self, io: &NetworkContext, timer: TimerToken) {
 		match timer {
-			TIMEOUT => {
-				// broadcast transactions to peers.
-			}
+			TIMEOUT => self.tick(io),
 			_ => warn!(target: "les", "received timeout on unknown token {}", timer),
 		}
 	}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use super::{can_serve, PeerLimits, PeerStats};
+	use super::status::Capabilities;
+	use request::{self, HashOrNumber, Request};
+	use util::hash::H256;
+	use util::FixedHash;
+
+	#[test]
+	fn routes_by_capabilities() {
+		let headers = |num| Request::Headers(request::Headers { block: HashOrNumber::Number(num), max: 1, skip: 0, reverse: false });
+		let proofs = Request::StateProofs(request::StateProofs { requests: Vec::new() });
+		let bodies = Request::Bodies(request::Bodies { block_hashes: Vec::new() });
+		let unknown = |_: &H256| None;
+
+		let mut caps = Capabilities::default();
+		assert!(can_serve(&caps, 100, &headers(100), &unknown));
+		assert!(!can_serve(&caps, 100, &headers(101), &unknown));
+		assert!(!can_serve(&caps, 100, &proofs, &unknown));
+		assert!(!can_serve(&caps, 100, &bodies, &unknown));
+
+		caps.serve_headers = false;
+		caps.serve_state_since = Some(0);
+		caps.serve_chain_since = Some(0);
+		assert!(!can_serve(&caps, 100, &headers(1), &unknown));
+		assert!(can_serve(&caps, 100, &proofs, &unknown));
+		assert!(can_serve(&caps, 100, &bodies, &unknown));
+	}
+
+	#[test]
+	fn respects_serving_ranges() {
+		let block_number = |hash: &H256| Some(hash.low_u64());
+		let bodies = |num: u64| Request::Bodies(request::Bodies { block_hashes: vec![H256::from(num)] });
+		let proof = |num: u64| Request::StateProofs(request::StateProofs { requests: vec![request::StateProof {
+			block: H256::from(num),
+			key1: H256::new(),
+			key2: None,
+			from_level: 0,
+		}] });
+		let header_proof = |num: u64| Request::HeaderProofs(request::HeaderProofs { requests: vec![request::HeaderProof {
+			cht_number: 0,
+			block_number: num,
+			from_level: 0,
+		}] });
+
+		let mut caps = Capabilities::default();
+		caps.serve_chain_since = Some(50);
+		caps.serve_state_since = Some(90);
+		assert!(can_serve(&caps, 100, &bodies(50), &block_number));
+		assert!(!can_serve(&caps, 100, &bodies(49), &block_number));
+		assert!(!can_serve(&caps, 100, &bodies(101), &block_number));
+		assert!(can_serve(&caps, 100, &header_proof(60), &block_number));
+		assert!(!can_serve(&caps, 100, &header_proof(40), &block_number));
+		assert!(can_serve(&caps, 100, &proof(95), &block_number));
+		assert!(!can_serve(&caps, 100, &proof(60), &block_number));
+		// blocks unknown locally can't be checked.
+		assert!(can_serve(&caps, 100, &proof(60), |_: &H256| None));
+	}
+
+	#[test]
+	fn evicts_slow_and_unreliable_peers() {
+		let limits = PeerLimits::default();
+		let mut stats = PeerStats::default();
+
+		for _ in 0..5 {
+			stats.note_response(Duration::from_secs(30));
+		}
+		assert!(!stats.should_evict(&limits), "too few samples");
+
+		for _ in 0..5 {
+			stats.note_response(Duration::from_secs(30));
+		}
+		assert!(stats.should_evict(&limits));
+
+		let mut stats = PeerStats::default();
+		for _ in 0..5 {
+			stats.note_response(Duration::from_millis(100));
+		}
+		for _ in 0..5 {
+			stats.note_timeout();
+		}
+		assert!(!stats.should_evict(&limits));
+		stats.note_timeout();
+		assert!(stats.should_evict(&limits));
+	}
+}
@@ -93,7 +93,8 @@ pub enum NetworkId {
 }
 
 impl NetworkId {
-	fn from_raw(raw: u32) -> Option<Self> {
+	/// Network ID for the given numeric id, if the light protocol knows it.
+	pub fn from_raw(raw: u32) -> Option<Self> {
 		match raw {
 			0 => Some(NetworkId::Testnet),
 			1 => Some(NetworkId::Mainnet),
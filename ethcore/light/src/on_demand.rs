@@ -0,0 +1,597 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! On-demand retrieval of chain data from LES peers.
+//!
+//! Requests are answered from the cache when possible. Otherwise they are
+//! dispatched to a peer and the result is delivered through a channel once a
+//! verified response arrives; the channel is closed if no peer can serve it.
+//!
+//! Fetched bodies and receipts can be turned into logs and receipts with
+//! `block_logs` and `transaction_receipt`. `OnDemand::logs` and
+//! `OnDemand::transaction_receipt_in` do the whole round trip for the light
+//! `eth_getLogs` and `eth_getTransactionReceipt`, given the headers of the
+//! blocks to search. They block waiting for responses, so they must be called
+//! from RPC threads, never from the network thread.
+//!
+//! Accounts, code and storage can be fetched with `account`, `code` and `storage`
+//! to satisfy the items `ProvedState::execute` reports missing. `OnDemand::execute`
+//! repeats that loop until nothing is missing, for the light `eth_call`; it blocks
+//! as well, so it must not run on the network thread.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+use ethcore::client::{EnvInfo, Executed};
+use ethcore::contract_address;
+use ethcore::engines::Engine;
+use ethcore::executed::ExecutionError;
+use ethcore::filter::Filter;
+use ethcore::header::Header;
+use ethcore::log_entry::LocalizedLogEntry;
+use ethcore::receipt::{Receipt, LocalizedReceipt};
+use ethcore::proved_execution::{ProvedState, ProvedAccount, StateItem, Error as ProvedError};
+use ethcore::transaction::{Action, SignedTransaction};
+use ethcore::views::BodyView;
+use network::{NetworkContext, PeerId};
+use util::{Address, Bytes, H256, Hashable, Mutex, U256, SHA3_NULL_RLP, SHA3_EMPTY_LIST_RLP, SHA3_EMPTY};
+use rlp::{UntrustedRlp, View, DecoderError};
+use util::hash::FixedHash;
+
+use cache::Cache;
+use net::{Handler, LightProtocol};
+use request::{self, Request};
+use verification;
+
+/// How long to wait for a response before giving up on a request.
+const FETCH_TIMEOUT_MS: u64 = 10_000;
+
+/// Errors of fetches waiting for responses.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+	/// No peer could serve a request or the response didn't arrive in time.
+	Unavailable,
+	/// A proved account could not be decoded.
+	Decoder(DecoderError),
+	/// The executed call failed.
+	Execution(ExecutionError),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Error::Unavailable => write!(f, "No peer could provide the requested data"),
+			Error::Decoder(ref e) => write!(f, "Invalid account: {}", e),
+			Error::Execution(ref e) => write!(f, "{}", e),
+		}
+	}
+}
+
+impl From<DecoderError> for Error {
+	fn from(err: DecoderError) -> Self {
+		Error::Decoder(err)
+	}
+}
+
+// wait for the response to a request made with `with_context`.
+fn wait<T>(receiver: Option<Receiver<T>>) -> Result<T, Error> {
+	match receiver.map(|r| r.recv_timeout(Duration::from_millis(FETCH_TIMEOUT_MS))) {
+		Some(Ok(item)) => Ok(item),
+		_ => Err(Error::Unavailable),
+	}
+}
+
+// a request awaiting response, along with the header it's checked against.
+enum Pending {
+	Receipts(Header, Sender<Vec<Receipt>>),
+	Body(Header, Sender<Bytes>),
+	State(Header, request::StateProof, Sender<Option<Bytes>>),
+	Code(H256, Sender<Bytes>),
+}
+
+/// On-demand fetcher of block bodies, receipts and state.
+///
+/// Must be added as a handler to the `LightProtocol` it dispatches requests through.
+pub struct OnDemand {
+	pending: Mutex<HashMap<usize, Pending>>,
+	cache: Arc<Mutex<Cache>>,
+}
+
+impl OnDemand {
+	/// Create a new on-demand fetcher backed by the given cache.
+	pub fn new(cache: Arc<Mutex<Cache>>) -> Self {
+		OnDemand {
+			pending: Mutex::new(HashMap::new()),
+			cache: cache,
+		}
+	}
+
+	/// Fetch the receipts of the block with the given header.
+	pub fn block_receipts(&self, net: &LightProtocol, io: &NetworkContext, header: Header) -> Receiver<Vec<Receipt>> {
+		let (sender, receiver) = mpsc::channel();
+		let hash = header.hash();
+		let cached = self.cache.lock().block_receipts(&hash);
+
+		if *header.receipts_root() == SHA3_NULL_RLP {
+			let _ = sender.send(Vec::new());
+		} else if let Some(receipts) = cached {
+			let _ = sender.send(receipts);
+		} else {
+			let req = Request::Receipts(request::Receipts { block_hashes: vec![hash] });
+			self.dispatch(net, io, req, Pending::Receipts(header, sender));
+		}
+
+		receiver
+	}
+
+	/// Fetch the body of the block with the given header.
+	pub fn block_body(&self, net: &LightProtocol, io: &NetworkContext, header: Header) -> Receiver<Bytes> {
+		let (sender, receiver) = mpsc::channel();
+		let hash = header.hash();
+		let cached = self.cache.lock().block_body(&hash);
+
+		if *header.transactions_root() == SHA3_NULL_RLP && *header.uncles_hash() == SHA3_EMPTY_LIST_RLP {
+			let _ = sender.send(vec![0xc2, 0xc0, 0xc0]);
+		} else if let Some(body) = cached {
+			let _ = sender.send(body);
+		} else {
+			let req = Request::Bodies(request::Bodies { block_hashes: vec![hash] });
+			self.dispatch(net, io, req, Pending::Body(header, sender));
+		}
+
+		receiver
+	}
+
+	/// Fetch the RLP of an account at the block with the given header,
+	/// or `None` if it doesn't exist.
+	pub fn account(&self, net: &LightProtocol, io: &NetworkContext, header: Header, address: &Address) -> Receiver<Option<Bytes>> {
+		self.state_proof(net, io, header, address.sha3(), None)
+	}
+
+	/// Fetch the RLP of a storage item of an account at the block with the given header,
+	/// or `None` if it is empty.
+	pub fn storage(&self, net: &LightProtocol, io: &NetworkContext, header: Header, address: &Address, key: &H256) -> Receiver<Option<Bytes>> {
+		self.state_proof(net, io, header, address.sha3(), Some(key.sha3()))
+	}
+
+	/// Receipt of the transaction with given hash, if it's in the block with given header.
+	///
+	/// Requests are made within `with_context`, which must run the given function with a
+	/// context of the light protocol, e.g. through `NetworkService::with_context`.
+	/// Blocks until the body and receipts of the block arrive.
+	pub fn transaction_receipt_in<C>(&self, net: &LightProtocol, with_context: C, header: Header, tx_hash: &H256) -> Result<Option<LocalizedReceipt>, Error>
+		where C: Fn(&Fn(&NetworkContext))
+	{
+		let (body, receipts) = try!(self.block_data(net, &with_context, header.clone()));
+		Ok(transaction_receipt(&header, &body, &receipts, tx_hash))
+	}
+
+	/// Logs matching the filter in the blocks with given headers, in order.
+	/// Blocks whose bloom rules out a match are skipped without fetching anything.
+	///
+	/// Requests are made within `with_context`, as for `transaction_receipt_in`.
+	/// Blocks until the bodies and receipts of the candidate blocks arrive.
+	pub fn logs<C>(&self, net: &LightProtocol, with_context: C, headers: Vec<Header>, filter: &Filter) -> Result<Vec<LocalizedLogEntry>, Error>
+		where C: Fn(&Fn(&NetworkContext))
+	{
+		let blooms = filter.bloom_possibilities();
+		let mut logs = Vec::new();
+		for header in headers {
+			if !blooms.iter().any(|bloom| header.log_bloom().contains(bloom)) {
+				continue;
+			}
+			let (body, receipts) = try!(self.block_data(net, &with_context, header.clone()));
+			logs.extend(block_logs(filter, &header, &body, &receipts));
+		}
+
+		if let Some(limit) = filter.limit {
+			let skip = logs.len().saturating_sub(limit);
+			logs.drain(..skip);
+		}
+		Ok(logs)
+	}
+
+	// fetch the body and receipts of a block at once and wait for both.
+	fn block_data<C>(&self, net: &LightProtocol, with_context: &C, header: Header) -> Result<(Bytes, Vec<Receipt>), Error>
+		where C: Fn(&Fn(&NetworkContext))
+	{
+		let receivers = RefCell::new(None);
+		with_context(&|io: &NetworkContext| {
+			*receivers.borrow_mut() = Some((self.block_body(net, io, header.clone()), self.block_receipts(net, io, header.clone())));
+		});
+		let (body, receipts) = match receivers.into_inner() {
+			Some((body, receipts)) => (Some(body), Some(receipts)),
+			None => (None, None),
+		};
+		Ok((try!(wait(body)), try!(wait(receipts))))
+	}
+
+	/// Fetch the code of an account at the block with the given header. `code_hash`
+	/// comes from the proved account and is checked against the response.
+	pub fn code(&self, net: &LightProtocol, io: &NetworkContext, header: Header, address: &Address, code_hash: H256) -> Receiver<Bytes> {
+		let (sender, receiver) = mpsc::channel();
+		let cached = self.cache.lock().code(&code_hash);
+
+		if code_hash == SHA3_EMPTY {
+			let _ = sender.send(Vec::new());
+		} else if let Some(code) = cached {
+			let _ = sender.send(code);
+		} else {
+			let req = Request::Codes(request::ContractCodes { code_requests: vec![(header.hash(), address.sha3())] });
+			self.dispatch(net, io, req, Pending::Code(code_hash, sender));
+		}
+
+		receiver
+	}
+
+	/// Execute a transaction against the state at the block with the given header, like
+	/// `eth_call` does, fetching the accounts, code and storage it reads as it goes.
+	///
+	/// Requests are made within `with_context`, as for `transaction_receipt_in`.
+	/// Blocks until the execution completes or an item can't be fetched.
+	pub fn execute<C>(&self, net: &LightProtocol, with_context: C, header: Header, transaction: &SignedTransaction, env_info: &EnvInfo, engine: &Engine) -> Result<Executed, Error>
+		where C: Fn(&Fn(&NetworkContext))
+	{
+		let mut state = ProvedState::new();
+		loop {
+			let missing = match state.execute(transaction, env_info, engine) {
+				Ok(executed) => return Ok(executed),
+				Err(ProvedError::Execution(e)) => return Err(Error::Execution(e)),
+				Err(ProvedError::Missing(missing)) => missing,
+			};
+
+			for item in missing {
+				match item {
+					StateItem::Account(address) => {
+						let account = try!(self.proved_account(net, &with_context, &header, &address));
+						state.insert_account(address, account);
+					}
+					StateItem::Storage(address, key) => {
+						let receiver = RefCell::new(None);
+						with_context(&|io: &NetworkContext| *receiver.borrow_mut() = Some(self.storage(net, io, header.clone(), &address, &key)));
+						let value = match try!(wait(receiver.into_inner())) {
+							Some(rlp) => H256::from(try!(UntrustedRlp::new(&rlp).as_val::<U256>())),
+							None => H256::zero(),
+						};
+						state.insert_storage(address, key, value);
+					}
+				}
+			}
+		}
+	}
+
+	// fetch an account along with its code, or `None` if it doesn't exist.
+	fn proved_account<C>(&self, net: &LightProtocol, with_context: &C, header: &Header, address: &Address) -> Result<Option<ProvedAccount>, Error>
+		where C: Fn(&Fn(&NetworkContext))
+	{
+		let receiver = RefCell::new(None);
+		with_context(&|io: &NetworkContext| *receiver.borrow_mut() = Some(self.account(net, io, header.clone(), address)));
+		let account = match try!(wait(receiver.into_inner())) {
+			Some(account) => account,
+			None => return Ok(None),
+		};
+
+		let rlp = UntrustedRlp::new(&account);
+		let code_hash: H256 = try!(rlp.val_at(3));
+		let receiver = RefCell::new(None);
+		with_context(&|io: &NetworkContext| *receiver.borrow_mut() = Some(self.code(net, io, header.clone(), address, code_hash)));
+
+		Ok(Some(ProvedAccount {
+			nonce: try!(rlp.val_at(0)),
+			balance: try!(rlp.val_at(1)),
+			code: try!(wait(receiver.into_inner())),
+		}))
+	}
+
+	fn state_proof(&self, net: &LightProtocol, io: &NetworkContext, header: Header, key1: H256, key2: Option<H256>) -> Receiver<Option<Bytes>> {
+		let (sender, receiver) = mpsc::channel();
+		let proof_req = request::StateProof {
+			block: header.hash(),
+			key1: key1,
+			key2: key2,
+			from_level: 0,
+		};
+
+		let req = Request::StateProofs(request::StateProofs { requests: vec![proof_req.clone()] });
+		self.dispatch(net, io, req, Pending::State(header, proof_req, sender));
+		receiver
+	}
+
+	// make the request to the first peer able to serve it.
+	// if none can, `pending` is dropped, closing the channel.
+	fn dispatch(&self, net: &LightProtocol, io: &NetworkContext, request: Request, pending: Pending) {
+		// hold the lock so a response can't arrive before the request is recorded.
+		let mut pending_requests = self.pending.lock();
+		let kind = request.kind();
+		match net.request(io, request) {
+			Ok((_, req_id)) => { pending_requests.insert(req_id, pending); }
+			Err(e) => debug!(target: "on_demand", "Unable to dispatch {:?} request: {}", kind, e),
+		}
+	}
+}
+
+impl Handler for OnDemand {
+	fn on_block_bodies(&self, peer: PeerId, req_id: usize, bodies: &[Bytes]) {
+		let (header, sender) = match self.pending.lock().remove(&req_id) {
+			Some(Pending::Body(header, sender)) => (header, sender),
+			_ => return,
+		};
+
+		let body = match bodies.first() {
+			Some(body) => body,
+			None => return,
+		};
+
+		match verification::check_body(&header, body) {
+			Ok(()) => {
+				self.cache.lock().insert_block_body(header.hash(), body.clone());
+				let _ = sender.send(body.clone());
+			}
+			Err(e) => debug!(target: "on_demand", "Peer {} gave bad block body: {}", peer, e),
+		}
+	}
+
+	fn on_receipts(&self, peer: PeerId, req_id: usize, receipts: &[Vec<Receipt>]) {
+		let (header, sender) = match self.pending.lock().remove(&req_id) {
+			Some(Pending::Receipts(header, sender)) => (header, sender),
+			_ => return,
+		};
+
+		let receipts = match receipts.first() {
+			Some(receipts) => receipts,
+			None => return,
+		};
+
+		match verification::check_receipts(&header, receipts) {
+			Ok(()) => {
+				self.cache.lock().insert_block_receipts(header.hash(), receipts.clone());
+				let _ = sender.send(receipts.clone());
+			}
+			Err(e) => debug!(target: "on_demand", "Peer {} gave bad receipts: {}", peer, e),
+		}
+	}
+
+	fn on_state_proofs(&self, peer: PeerId, req_id: usize, proofs: &[Vec<Bytes>]) {
+		let (header, req, sender) = match self.pending.lock().remove(&req_id) {
+			Some(Pending::State(header, req, sender)) => (header, req, sender),
+			_ => return,
+		};
+
+		let proof = match proofs.first() {
+			Some(proof) => proof,
+			None => return,
+		};
+
+		match verification::check_state_proof(header.state_root(), &req, proof) {
+			Ok(item) => { let _ = sender.send(item); }
+			Err(e) => debug!(target: "on_demand", "Peer {} gave bad state proof: {}", peer, e),
+		}
+	}
+
+	fn on_code(&self, peer: PeerId, req_id: usize, codes: &[Bytes]) {
+		let (code_hash, sender) = match self.pending.lock().remove(&req_id) {
+			Some(Pending::Code(code_hash, sender)) => (code_hash, sender),
+			_ => return,
+		};
+
+		let code = match codes.first() {
+			Some(code) => code,
+			None => return,
+		};
+
+		match code.sha3() == code_hash {
+			true => {
+				self.cache.lock().insert_code(code_hash, code.clone());
+				let _ = sender.send(code.clone());
+			}
+			false => debug!(target: "on_demand", "Peer {} gave code not matching hash {}", peer, code_hash),
+		}
+	}
+
+	fn on_disconnect(&self, _peer: PeerId, unfulfilled: &[usize]) {
+		let mut pending = self.pending.lock();
+		for req_id in unfulfilled {
+			pending.remove(req_id);
+		}
+	}
+
+	fn on_timeout(&self, _peer: PeerId, expired: &[usize]) {
+		let mut pending = self.pending.lock();
+		for req_id in expired {
+			pending.remove(req_id);
+		}
+	}
+}
+
+/// Logs of a block matching the filter. `body` and `receipts` must have been
+/// verified against `header`.
+pub fn block_logs(filter: &Filter, header: &Header, body: &[u8], receipts: &[Receipt]) -> Vec<LocalizedLogEntry> {
+	let block_hash = header.hash();
+	let tx_hashes = BodyView::new(body).transaction_hashes();
+
+	let mut log_index = 0;
+	let mut logs = Vec::new();
+	for (tx_index, (receipt, tx_hash)) in receipts.iter().zip(tx_hashes).enumerate() {
+		for entry in &receipt.logs {
+			if filter.matches(entry) {
+				logs.push(LocalizedLogEntry {
+					entry: entry.clone(),
+					block_hash: block_hash,
+					block_number: header.number(),
+					transaction_hash: tx_hash,
+					transaction_index: tx_index,
+					log_index: log_index,
+				});
+			}
+			log_index += 1;
+		}
+	}
+
+	logs
+}
+
+/// Receipt of the transaction with the given hash, if it's in the block.
+/// `body` and `receipts` must have been verified against `header`.
+pub fn transaction_receipt(header: &Header, body: &[u8], receipts: &[Receipt], tx_hash: &H256) -> Option<LocalizedReceipt> {
+	let view = BodyView::new(body);
+	let block_hash = header.hash();
+	let index = match view.transaction_hashes().iter().position(|hash| hash == tx_hash) {
+		Some(index) => index,
+		None => return None,
+	};
+
+	let (tx, receipt) = match (view.localized_transaction_at(&block_hash, header.number(), index), receipts.get(index)) {
+		(Some(tx), Some(receipt)) => (tx, receipt),
+		_ => return None,
+	};
+
+	let prior_gas_used = match index {
+		0 => U256::zero(),
+		i => receipts[i - 1].gas_used,
+	};
+	let first_log_index = receipts[..index].iter().fold(0, |sum, r| sum + r.logs.len());
+
+	Some(LocalizedReceipt {
+		transaction_hash: *tx_hash,
+		transaction_index: index,
+		block_hash: block_hash,
+		block_number: header.number(),
+		cumulative_gas_used: receipt.gas_used,
+		gas_used: receipt.gas_used - prior_gas_used,
+		contract_address: match tx.action {
+			Action::Call(_) => None,
+			Action::Create => tx.sender().ok().map(|sender| contract_address(&sender, &tx.nonce)),
+		},
+		logs: receipt.logs.iter().enumerate().map(|(i, entry)| LocalizedLogEntry {
+			entry: entry.clone(),
+			block_hash: block_hash,
+			block_number: header.number(),
+			transaction_hash: *tx_hash,
+			transaction_index: index,
+			log_index: first_log_index + i,
+		}).collect(),
+		log_bloom: receipt.log_bloom,
+		state_root: receipt.state_root,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{block_logs, transaction_receipt, wait, Error, OnDemand, Pending};
+	use std::sync::{mpsc, Arc};
+	use cache::Cache;
+	use net::Handler;
+	use time::Duration;
+	use ethcore::filter::Filter;
+	use ethcore::header::Header;
+	use ethcore::ids::BlockID;
+	use ethcore::log_entry::LogEntry;
+	use ethcore::receipt::Receipt;
+	use ethcore::transaction::{Action, Transaction};
+	use util::{Address, H256, U256, Hashable, Mutex};
+	use rlp::{RlpStream, Stream};
+
+	fn log(address: u64) -> LogEntry {
+		LogEntry { address: Address::from(address), topics: Vec::new(), data: Vec::new() }
+	}
+
+	fn block() -> (Header, Vec<u8>, Vec<H256>, Vec<Receipt>) {
+		let txs: Vec<_> = (0..2).map(|nonce| Transaction {
+			nonce: U256::from(nonce),
+			action: Action::Call(Address::from(1)),
+			gas: U256::from(21000),
+			..Default::default()
+		}.fake_sign(Address::from(2))).collect();
+
+		let mut stream = RlpStream::new_list(2);
+		stream.begin_list(txs.len());
+		for tx in &txs {
+			stream.append(tx);
+		}
+		stream.begin_list(0);
+
+		let receipts = vec![
+			Receipt::new(H256::new(), U256::from(21000), vec![log(5), log(6)]),
+			Receipt::new(H256::new(), U256::from(50000), vec![log(5)]),
+		];
+
+		let mut header = Header::new();
+		header.set_number(10);
+		let hashes = txs.iter().map(|tx| tx.hash()).collect();
+		(header, stream.out(), hashes, receipts)
+	}
+
+	#[test]
+	fn filters_block_logs() {
+		let (header, body, hashes, receipts) = block();
+		let filter = Filter {
+			from_block: BlockID::Earliest,
+			to_block: BlockID::Latest,
+			address: Some(vec![Address::from(5)]),
+			topics: vec![None, None, None, None],
+			limit: None,
+		};
+
+		let logs = block_logs(&filter, &header, &body, &receipts);
+		assert_eq!(logs.len(), 2);
+		assert_eq!((logs[0].transaction_hash, logs[0].log_index), (hashes[0], 0));
+		assert_eq!((logs[1].transaction_hash, logs[1].log_index), (hashes[1], 2));
+		assert_eq!(logs[1].block_number, 10);
+	}
+
+	#[test]
+	fn localizes_transaction_receipt() {
+		let (header, body, hashes, receipts) = block();
+
+		let receipt = transaction_receipt(&header, &body, &receipts, &hashes[1]).unwrap();
+		assert_eq!(receipt.transaction_index, 1);
+		assert_eq!(receipt.gas_used, U256::from(29000));
+		assert_eq!(receipt.cumulative_gas_used, U256::from(50000));
+		assert_eq!(receipt.logs[0].log_index, 2);
+		assert_eq!(receipt.contract_address, None);
+
+		assert!(transaction_receipt(&header, &body, &receipts, &H256::from(1)).is_none());
+	}
+
+	#[test]
+	fn gives_up_on_closed_requests() {
+		let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+		drop(sender);
+		assert_eq!(wait(Some(receiver)), Err(Error::Unavailable));
+		assert_eq!(wait::<Vec<u8>>(None), Err(Error::Unavailable));
+	}
+
+	#[test]
+	fn checks_code_against_hash() {
+		let on_demand = OnDemand::new(Arc::new(Mutex::new(Cache::new(Default::default(), Duration::hours(1)))));
+		let code = vec![0x60, 0x00];
+
+		let (sender, receiver) = mpsc::channel();
+		on_demand.pending.lock().insert(1, Pending::Code(code.sha3(), sender));
+		on_demand.on_code(0, 1, &[vec![0x60, 0x01]]);
+		assert!(receiver.try_recv().is_err());
+
+		let (sender, receiver) = mpsc::channel();
+		on_demand.pending.lock().insert(2, Pending::Code(code.sha3(), sender));
+		on_demand.on_code(0, 2, &[code.clone()]);
+		assert_eq!(receiver.try_recv().unwrap(), code);
+		assert_eq!(on_demand.cache.lock().code(&code.sha3()), Some(code));
+	}
+}
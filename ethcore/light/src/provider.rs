@@ -16,12 +16,22 @@
 
 //! A provider for the LES protocol. This is typically a full node, who can
 //! give as much data as necessary to its peers.
+//!
+//! The full `Client` is served through a `ClientProvider`. Different kinds of
+//! requests can be served by different backends using a `Composite` provider.
 
+use std::sync::Arc;
+
+use ethcore::client::{BlockChainClient, Client};
 use ethcore::transaction::SignedTransaction;
 use ethcore::blockchain_info::BlockChainInfo;
+use ethcore::ids::BlockID;
+use rlp::{RlpStream, Stream, EMPTY_LIST_RLP};
 use util::{Bytes, H256};
 
-use request;
+use cht;
+use net::Capabilities;
+use request::{self, HashOrNumber, Kind};
 
 /// Defines the operations that a provider for `LES` must fulfill.
 ///
@@ -39,6 +49,10 @@ pub trait Provider: Send + Sync {
 	/// Earliest state.
 	fn earliest_state(&self) -> Option<u64>;
 
+	/// Get a locally known block header by ID, used to verify responses
+	/// against the roots it commits to.
+	fn block_header(&self, id: BlockID) -> Option<Bytes>;
+
 	/// Provide a list of headers starting at the requested block,
 	/// possibly in reverse and skipping `skip` at a time.
 	///
@@ -61,6 +75,7 @@ pub trait Provider: Send + Sync {
 	fn proofs(&self, req: request::StateProofs) -> Vec<Bytes>;
 
 	/// Provide contract code for the specified (block_hash, account_hash) pairs.
+	/// Code which can't be provided is returned empty.
 	fn code(&self, req: request::ContractCodes) -> Vec<Bytes>;
 
 	/// Provide header proofs from the Canonical Hash Tries.
@@ -68,4 +83,318 @@ pub trait Provider: Send + Sync {
 
 	/// Provide pending transactions.
 	fn pending_transactions(&self) -> Vec<SignedTransaction>;
+
+	/// Queue RLP-encoded transactions relayed by a peer for import.
+	fn queue_transactions(&self, transactions: Vec<Bytes>);
+
+	/// Capabilities this provider can serve, advertised in the handshake
+	/// in place of the configured ones when given.
+	fn capabilities(&self) -> Option<Capabilities> { None }
+}
+
+/// The full client as a provider, serving whatever its pruning settings keep
+/// available. Data older than the pruning horizon is answered with an empty list.
+/// The advertised capabilities are those at the time the protocol is created.
+pub struct ClientProvider {
+	client: Arc<Client>,
+	chts: cht::Cache,
+}
+
+impl ClientProvider {
+	/// Serve requests from the given client.
+	pub fn new(client: Arc<Client>) -> Self {
+		ClientProvider {
+			client: client,
+			chts: cht::Cache::new(),
+		}
+	}
+}
+
+impl Provider for ClientProvider {
+	fn chain_info(&self) -> BlockChainInfo {
+		BlockChainClient::chain_info(&*self.client)
+	}
+
+	fn reorg_depth(&self, a: &H256, b: &H256) -> Option<u64> {
+		self.client.tree_route(a, b).map(|route| route.index as u64)
+	}
+
+	fn earliest_state(&self) -> Option<u64> {
+		Some(self.client.earliest_state())
+	}
+
+	fn block_header(&self, id: BlockID) -> Option<Bytes> {
+		BlockChainClient::block_header(&*self.client, id)
+	}
+
+	fn block_headers(&self, req: request::Headers) -> Vec<Bytes> {
+		let best_num = BlockChainClient::chain_info(&*self.client).best_block_number;
+		let start_num = match req.block {
+			HashOrNumber::Number(num) => num,
+			HashOrNumber::Hash(hash) => match self.client.block_number(BlockID::Hash(hash)) {
+				Some(num) => num,
+				None => return Vec::new(),
+			},
+		};
+
+		let step = req.skip as u64 + 1;
+		(0..req.max as u64)
+			.map(|i| i.saturating_mul(step))
+			.take_while(|&offset| if req.reverse { offset <= start_num } else { offset <= best_num.saturating_sub(start_num) })
+			.map(|offset| if req.reverse { start_num - offset } else { start_num + offset })
+			.map(|num| BlockChainClient::block_header(&*self.client, BlockID::Number(num)))
+			.take_while(|header| header.is_some())
+			.flat_map(|header| header)
+			.collect()
+	}
+
+	fn block_bodies(&self, req: request::Bodies) -> Vec<Bytes> {
+		req.block_hashes.into_iter()
+			.map(|hash| self.client.block_body(BlockID::Hash(hash)).unwrap_or_else(|| EMPTY_LIST_RLP.to_vec()))
+			.collect()
+	}
+
+	fn receipts(&self, req: request::Receipts) -> Vec<Bytes> {
+		req.block_hashes.iter()
+			.map(|hash| self.client.block_receipts(hash).unwrap_or_else(|| EMPTY_LIST_RLP.to_vec()))
+			.collect()
+	}
+
+	fn proofs(&self, req: request::StateProofs) -> Vec<Bytes> {
+		req.requests.into_iter().map(|req| {
+			match self.client.prove_state(BlockID::Hash(req.block), &req.key1, req.key2.as_ref(), req.from_level) {
+				Some(nodes) => {
+					let mut stream = RlpStream::new_list(nodes.len());
+					for node in &nodes {
+						stream.append(node);
+					}
+					stream.out()
+				}
+				None => EMPTY_LIST_RLP.to_vec(),
+			}
+		}).collect()
+	}
+
+	fn code(&self, req: request::ContractCodes) -> Vec<Bytes> {
+		req.code_requests.into_iter()
+			.map(|(block, account_key)| self.client.code_by_address_hash(BlockID::Hash(block), &account_key).unwrap_or_else(Vec::new))
+			.collect()
+	}
+
+	fn header_proofs(&self, req: request::HeaderProofs) -> Vec<Bytes> {
+		let fetcher = |num| {
+			let id = BlockID::Number(num);
+			match (self.client.block_hash(id), self.client.block_total_difficulty(id)) {
+				(Some(hash), Some(td)) => Some((hash, td)),
+				_ => None,
+			}
+		};
+
+		req.requests.into_iter().map(|req| {
+			let header = BlockChainClient::block_header(&*self.client, BlockID::Number(req.block_number));
+			match (header, self.chts.prove(req.cht_number, req.block_number, req.from_level, &fetcher)) {
+				(Some(header), Some(nodes)) => {
+					let mut stream = RlpStream::new_list(2);
+					stream.append_raw(&header, 1).begin_list(nodes.len());
+					for node in &nodes {
+						stream.append(node);
+					}
+					stream.out()
+				}
+				_ => EMPTY_LIST_RLP.to_vec(),
+			}
+		}).collect()
+	}
+
+	fn pending_transactions(&self) -> Vec<SignedTransaction> {
+		BlockChainClient::pending_transactions(&*self.client)
+	}
+
+	fn queue_transactions(&self, transactions: Vec<Bytes>) {
+		BlockChainClient::queue_transactions(&*self.client, transactions)
+	}
+
+	fn capabilities(&self) -> Option<Capabilities> {
+		let chain_info = BlockChainClient::chain_info(&*self.client);
+		Some(Capabilities {
+			serve_headers: true,
+			serve_chain_since: Some(chain_info.first_block_number.unwrap_or(0)),
+			serve_state_since: Some(self.client.earliest_state()),
+			tx_relay: true,
+		})
+	}
+}
+
+/// A provider routing each kind of request to its own backend.
+///
+/// Chain info and locally known headers come from the headers backend, the
+/// earliest state from the state proofs backend and pending and relayed
+/// transactions from the default backend.
+pub struct Composite {
+	default: Arc<Provider>,
+	routes: Vec<(Kind, Arc<Provider>)>,
+}
+
+impl Composite {
+	/// Create a composite provider serving everything from the given backend.
+	pub fn new(default: Arc<Provider>) -> Self {
+		Composite {
+			default: default,
+			routes: Vec::new(),
+		}
+	}
+
+	/// Serve requests of the given kind from another backend.
+	pub fn route(mut self, kind: Kind, backend: Arc<Provider>) -> Self {
+		self.routes.retain(|&(k, _)| k != kind);
+		self.routes.push((kind, backend));
+		self
+	}
+
+	fn backend(&self, kind: Kind) -> &Provider {
+		self.routes.iter()
+			.find(|&&(k, _)| k == kind)
+			.map_or(&*self.default, |&(_, ref backend)| &**backend)
+	}
+
+	fn backend_capabilities(&self, kind: Kind) -> Capabilities {
+		self.backend(kind).capabilities().unwrap_or_else(Capabilities::default)
+	}
+}
+
+// the later of two starting blocks, if both are served at all.
+fn later(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+	match (a, b) {
+		(Some(a), Some(b)) => Some(::std::cmp::max(a, b)),
+		_ => None,
+	}
+}
+
+impl Provider for Composite {
+	fn chain_info(&self) -> BlockChainInfo {
+		self.backend(Kind::Headers).chain_info()
+	}
+
+	fn reorg_depth(&self, a: &H256, b: &H256) -> Option<u64> {
+		self.backend(Kind::Headers).reorg_depth(a, b)
+	}
+
+	fn earliest_state(&self) -> Option<u64> {
+		self.backend(Kind::StateProofs).earliest_state()
+	}
+
+	fn block_header(&self, id: BlockID) -> Option<Bytes> {
+		self.backend(Kind::Headers).block_header(id)
+	}
+
+	fn block_headers(&self, req: request::Headers) -> Vec<Bytes> {
+		self.backend(Kind::Headers).block_headers(req)
+	}
+
+	fn block_bodies(&self, req: request::Bodies) -> Vec<Bytes> {
+		self.backend(Kind::Bodies).block_bodies(req)
+	}
+
+	fn receipts(&self, req: request::Receipts) -> Vec<Bytes> {
+		self.backend(Kind::Receipts).receipts(req)
+	}
+
+	fn proofs(&self, req: request::StateProofs) -> Vec<Bytes> {
+		self.backend(Kind::StateProofs).proofs(req)
+	}
+
+	fn code(&self, req: request::ContractCodes) -> Vec<Bytes> {
+		self.backend(Kind::Codes).code(req)
+	}
+
+	fn header_proofs(&self, req: request::HeaderProofs) -> Vec<Bytes> {
+		self.backend(Kind::HeaderProofs).header_proofs(req)
+	}
+
+	fn pending_transactions(&self) -> Vec<SignedTransaction> {
+		self.default.pending_transactions()
+	}
+
+	fn queue_transactions(&self, transactions: Vec<Bytes>) {
+		self.default.queue_transactions(transactions)
+	}
+
+	fn capabilities(&self) -> Option<Capabilities> {
+		let bodies = self.backend_capabilities(Kind::Bodies);
+		let receipts = self.backend_capabilities(Kind::Receipts);
+		let proofs = self.backend_capabilities(Kind::StateProofs);
+		let code = self.backend_capabilities(Kind::Codes);
+
+		Some(Capabilities {
+			serve_headers: self.backend_capabilities(Kind::Headers).serve_headers,
+			serve_chain_since: later(bodies.serve_chain_since, receipts.serve_chain_since),
+			serve_state_since: later(proofs.serve_state_since, code.serve_state_since),
+			tx_relay: self.default.capabilities().map_or(false, |caps| caps.tx_relay),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use ethcore::blockchain_info::BlockChainInfo;
+	use ethcore::ids::BlockID;
+	use ethcore::transaction::SignedTransaction;
+	use net::Capabilities;
+	use request::{self, Kind};
+	use util::{Bytes, H256, U256};
+	use super::{Composite, Provider};
+
+	// serves its tag in response to every request.
+	struct Tagged(u8, Capabilities);
+
+	impl Provider for Tagged {
+		fn chain_info(&self) -> BlockChainInfo {
+			BlockChainInfo {
+				total_difficulty: U256::default(),
+				pending_total_difficulty: U256::default(),
+				genesis_hash: H256::default(),
+				best_block_hash: H256::from(self.0 as u64),
+				best_block_number: self.0 as u64,
+				ancient_block_hash: None,
+				ancient_block_number: None,
+				first_block_hash: None,
+				first_block_number: None,
+			}
+		}
+		fn reorg_depth(&self, _a: &H256, _b: &H256) -> Option<u64> { None }
+		fn earliest_state(&self) -> Option<u64> { Some(self.0 as u64) }
+		fn block_header(&self, _id: BlockID) -> Option<Bytes> { Some(vec![self.0]) }
+		fn block_headers(&self, _req: request::Headers) -> Vec<Bytes> { vec![vec![self.0]] }
+		fn block_bodies(&self, _req: request::Bodies) -> Vec<Bytes> { vec![vec![self.0]] }
+		fn receipts(&self, _req: request::Receipts) -> Vec<Bytes> { vec![vec![self.0]] }
+		fn proofs(&self, _req: request::StateProofs) -> Vec<Bytes> { vec![vec![self.0]] }
+		fn code(&self, _req: request::ContractCodes) -> Vec<Bytes> { vec![vec![self.0]] }
+		fn header_proofs(&self, _req: request::HeaderProofs) -> Vec<Bytes> { vec![vec![self.0]] }
+		fn pending_transactions(&self) -> Vec<SignedTransaction> { Vec::new() }
+		fn queue_transactions(&self, _transactions: Vec<Bytes>) {}
+		fn capabilities(&self) -> Option<Capabilities> { Some(self.1.clone()) }
+	}
+
+	#[test]
+	fn routes_requests_and_merges_capabilities() {
+		let pruned = Capabilities { serve_headers: true, serve_chain_since: Some(100), serve_state_since: Some(1000), tx_relay: true };
+		let archive = Capabilities { serve_headers: false, serve_chain_since: None, serve_state_since: Some(0), tx_relay: false };
+
+		let provider = Composite::new(Arc::new(Tagged(1, pruned)))
+			.route(Kind::StateProofs, Arc::new(Tagged(2, archive.clone())))
+			.route(Kind::Codes, Arc::new(Tagged(2, archive)));
+
+		assert_eq!(provider.chain_info().best_block_number, 1);
+		assert_eq!(provider.block_bodies(request::Bodies { block_hashes: Vec::new() }), vec![vec![1]]);
+		assert_eq!(provider.proofs(request::StateProofs { requests: Vec::new() }), vec![vec![2]]);
+		assert_eq!(provider.earliest_state(), Some(2));
+
+		assert_eq!(provider.capabilities(), Some(Capabilities {
+			serve_headers: true,
+			serve_chain_since: Some(100),
+			serve_state_since: Some(0),
+			tx_relay: true,
+		}));
+	}
 }
\ No newline at end of file
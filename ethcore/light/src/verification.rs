@@ -0,0 +1,359 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Verification of LES responses against the requests they answer.
+//!
+//! Headers are checked to match the requested range, to form a chain,
+//! to agree with the trusted checkpoints and to carry a valid seal,
+//! bodies and receipts are checked against the roots committed to by a known
+//! header and state proofs are checked against a known state root.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use ethcore::engines::Engine;
+use ethcore::header::Header;
+use ethcore::receipt::Receipt;
+use util::{Bytes, H256, Hashable, MemoryDB, HashDB, Trie, TrieDB, TrieError, ordered_trie_root};
+use rlp::{self, UntrustedRlp, View};
+
+use request::{self, HashOrNumber};
+
+/// Ways in which a response may fail verification.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+	/// More headers than requested.
+	TooManyHeaders(usize, usize),
+	/// First header doesn't match the requested block.
+	WrongStartBlock,
+	/// A header's number doesn't follow from the `skip` and `reverse` parameters.
+	WrongSequence { expected: u64, found: u64 },
+	/// Consecutive headers don't link by parent hash.
+	Unlinked(H256),
+	/// Receipts don't hash to the header's receipts root.
+	WrongReceiptsRoot { expected: H256, found: H256 },
+	/// Transactions don't hash to the header's transactions root.
+	WrongTransactionsRoot { expected: H256, found: H256 },
+	/// Uncles don't hash to the header's uncles hash.
+	WrongUnclesHash { expected: H256, found: H256 },
+	/// A block body is malformed.
+	BadBody,
+	/// A proof is missing nodes or has a corrupt node.
+	BadProof(TrieError),
+	/// A proven account is malformed.
+	BadAccount,
+	/// A header conflicts with a trusted checkpoint.
+	CheckpointMismatch { number: u64, expected: H256, found: H256 },
+	/// A header's seal is rejected by the consensus engine.
+	BadSeal(H256),
+}
+
+impl From<Box<TrieError>> for Error {
+	fn from(err: Box<TrieError>) -> Self {
+		Error::BadProof(*err)
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Error::TooManyHeaders(max, got) => write!(f, "Too many headers: requested {}, got {}", max, got),
+			Error::WrongStartBlock => write!(f, "First header doesn't match request"),
+			Error::WrongSequence { expected, found } => write!(f, "Wrong header sequence: expected #{}, found #{}", expected, found),
+			Error::Unlinked(ref hash) => write!(f, "Header {} doesn't link to its neighbour", hash),
+			Error::WrongReceiptsRoot { ref expected, ref found } => write!(f, "Wrong receipts root: expected {}, found {}", expected, found),
+			Error::WrongTransactionsRoot { ref expected, ref found } => write!(f, "Wrong transactions root: expected {}, found {}", expected, found),
+			Error::WrongUnclesHash { ref expected, ref found } => write!(f, "Wrong uncles hash: expected {}, found {}", expected, found),
+			Error::BadBody => write!(f, "Malformed block body"),
+			Error::BadProof(ref err) => write!(f, "Bad proof: {}", err),
+			Error::BadAccount => write!(f, "Malformed account in proof"),
+			Error::CheckpointMismatch { number, ref expected, ref found } => write!(f, "Header #{} conflicts with checkpoint: expected {}, found {}", number, expected, found),
+			Error::BadSeal(ref hash) => write!(f, "Header {} has an invalid seal", hash),
+		}
+	}
+}
+
+/// Check that none of the headers conflicts with a trusted checkpoint.
+pub fn check_checkpoints(checkpoints: &BTreeMap<u64, H256>, headers: &[Header]) -> Result<(), Error> {
+	for header in headers {
+		if let Some(expected) = checkpoints.get(&header.number()) {
+			let found = header.hash();
+			if found != *expected {
+				return Err(Error::CheckpointMismatch { number: header.number(), expected: expected.clone(), found: found });
+			}
+		}
+	}
+	Ok(())
+}
+
+/// Check the seal of every header with the consensus engine. This needs nothing but
+/// the header itself, so it works for authority signatures as well as proof-of-work.
+pub fn check_seals(engine: &Engine, headers: &[Header]) -> Result<(), Error> {
+	for header in headers {
+		if let Err(e) = engine.verify_block_seal(header) {
+			trace!(target: "les", "Header #{} failed seal verification: {}", header.number(), e);
+			return Err(Error::BadSeal(header.hash()));
+		}
+	}
+	Ok(())
+}
+
+/// Check that the headers answer the request: they start at the requested block,
+/// step according to `skip` and `reverse`, and link together when contiguous.
+pub fn check_headers(req: &request::Headers, headers: &[Header]) -> Result<(), Error> {
+	if headers.len() > req.max {
+		return Err(Error::TooManyHeaders(req.max, headers.len()));
+	}
+
+	let first = match headers.first() {
+		Some(first) => first,
+		None => return Ok(()),
+	};
+
+	let starts_right = match req.block {
+		HashOrNumber::Hash(ref hash) => first.hash() == *hash,
+		HashOrNumber::Number(number) => first.number() == number,
+	};
+	if !starts_right {
+		return Err(Error::WrongStartBlock);
+	}
+
+	let step = req.skip as u64 + 1;
+	for pair in headers.windows(2) {
+		let (prev, cur) = (&pair[0], &pair[1]);
+		let expected = if req.reverse { prev.number().checked_sub(step) } else { prev.number().checked_add(step) };
+		if expected != Some(cur.number()) {
+			return Err(Error::WrongSequence { expected: expected.unwrap_or(0), found: cur.number() });
+		}
+
+		if req.skip == 0 {
+			let linked = if req.reverse {
+				*prev.parent_hash() == cur.hash()
+			} else {
+				*cur.parent_hash() == prev.hash()
+			};
+			if !linked {
+				return Err(Error::Unlinked(cur.hash()));
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// Check that a block body (`[transactions, uncles]`) matches the transactions
+/// root and uncles hash of its header.
+pub fn check_body(header: &Header, body: &[u8]) -> Result<(), Error> {
+	let body = UntrustedRlp::new(body);
+	let transactions = try!(body.at(0).map_err(|_| Error::BadBody));
+	let uncles = try!(body.at(1).map_err(|_| Error::BadBody));
+
+	let found = ordered_trie_root(transactions.iter().map(|tx| tx.as_raw().to_vec()));
+	if *header.transactions_root() != found {
+		return Err(Error::WrongTransactionsRoot { expected: header.transactions_root().clone(), found: found });
+	}
+
+	let found = uncles.as_raw().sha3();
+	if *header.uncles_hash() != found {
+		return Err(Error::WrongUnclesHash { expected: header.uncles_hash().clone(), found: found });
+	}
+
+	Ok(())
+}
+
+/// Check that the receipts for a block hash to the header's receipts root.
+pub fn check_receipts(header: &Header, receipts: &[Receipt]) -> Result<(), Error> {
+	let found = ordered_trie_root(receipts.iter().map(|r| rlp::encode(r).to_vec()));
+	match *header.receipts_root() == found {
+		true => Ok(()),
+		false => Err(Error::WrongReceiptsRoot { expected: header.receipts_root().clone(), found: found }),
+	}
+}
+
+/// Check a state proof against the given state root. Returns the proven value:
+/// the account RLP, or the storage value if `req.key2` is set. `None` means the
+/// proof shows the item is absent.
+pub fn check_state_proof(state_root: &H256, req: &request::StateProof, proof: &[Bytes]) -> Result<Option<Bytes>, Error> {
+	let mut db = MemoryDB::new();
+	for node in proof {
+		db.insert(&node[..]);
+	}
+
+	let account = try!(prove_item(&db, state_root, &req.key1));
+	match (account, req.key2.as_ref()) {
+		(Some(account), Some(key2)) => {
+			let storage_root: H256 = try!(UntrustedRlp::new(&account).val_at(2).map_err(|_| Error::BadAccount));
+			prove_item(&db, &storage_root, key2)
+		}
+		(account, None) => Ok(account),
+		(None, Some(_)) => Ok(None),
+	}
+}
+
+fn prove_item(db: &HashDB, root: &H256, key: &H256) -> Result<Option<Bytes>, Error> {
+	let trie = try!(TrieDB::new(db, root));
+	let value = try!(trie.get(key));
+	Ok(value.map(|v| v.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::BTreeMap;
+	use ethcore::header::Header;
+	use ethcore::receipt::Receipt;
+	use ethcore::spec::Spec;
+	use util::{H256, U256, Hashable, MemoryDB, HashDB, TrieMut, TrieDBMut, TrieError};
+	use request::{Headers, HashOrNumber, StateProof};
+	use rlp::RlpStream;
+
+	fn chain(len: u64) -> Vec<Header> {
+		let mut headers: Vec<Header> = Vec::new();
+		for number in 0..len {
+			let mut header = Header::new();
+			header.set_number(number);
+			if let Some(parent) = headers.last() {
+				header.set_parent_hash(parent.hash());
+			}
+			headers.push(header);
+		}
+		headers
+	}
+
+	#[test]
+	fn accepts_linked_headers() {
+		let headers = chain(5);
+		let req = Headers { block: HashOrNumber::Number(1), max: 3, skip: 0, reverse: false };
+		assert_eq!(check_headers(&req, &headers[1..4]), Ok(()));
+
+		let reversed: Vec<_> = headers.iter().rev().cloned().collect();
+		let req = Headers { block: HashOrNumber::Hash(headers[4].hash()), max: 5, skip: 0, reverse: true };
+		assert_eq!(check_headers(&req, &reversed), Ok(()));
+	}
+
+	#[test]
+	fn checks_skip_and_range() {
+		let headers = chain(7);
+		let skipped = vec![headers[0].clone(), headers[3].clone(), headers[6].clone()];
+		let req = Headers { block: HashOrNumber::Number(0), max: 3, skip: 2, reverse: false };
+		assert_eq!(check_headers(&req, &skipped), Ok(()));
+
+		let req = Headers { block: HashOrNumber::Number(0), max: 2, skip: 2, reverse: false };
+		assert_eq!(check_headers(&req, &skipped), Err(Error::TooManyHeaders(2, 3)));
+
+		let req = Headers { block: HashOrNumber::Number(1), max: 3, skip: 2, reverse: false };
+		assert_eq!(check_headers(&req, &skipped), Err(Error::WrongStartBlock));
+
+		let req = Headers { block: HashOrNumber::Number(0), max: 3, skip: 1, reverse: false };
+		assert_eq!(check_headers(&req, &skipped), Err(Error::WrongSequence { expected: 2, found: 3 }));
+	}
+
+	#[test]
+	fn rejects_unlinked_headers() {
+		let mut headers = chain(3);
+		headers[2].set_parent_hash(H256::from(1));
+		let req = Headers { block: HashOrNumber::Number(0), max: 3, skip: 0, reverse: false };
+		assert_eq!(check_headers(&req, &headers), Err(Error::Unlinked(headers[2].hash())));
+	}
+
+	#[test]
+	fn checks_checkpoints() {
+		let headers = chain(3);
+		let mut checkpoints = BTreeMap::new();
+		checkpoints.insert(1, headers[1].hash());
+		assert_eq!(check_checkpoints(&checkpoints, &headers), Ok(()));
+
+		checkpoints.insert(2, H256::from(1));
+		assert_eq!(check_checkpoints(&checkpoints, &headers), Err(Error::CheckpointMismatch {
+			number: 2,
+			expected: H256::from(1),
+			found: headers[2].hash(),
+		}));
+	}
+
+	#[test]
+	fn checks_seals() {
+		let headers = chain(3);
+		assert_eq!(check_seals(&*Spec::new_null().engine, &headers), Ok(()));
+		assert_eq!(check_seals(&*Spec::new_test_round().engine, &headers), Err(Error::BadSeal(headers[0].hash())));
+	}
+
+	#[test]
+	fn checks_body_roots() {
+		let body = {
+			let mut stream = RlpStream::new_list(2);
+			stream.begin_list(1).append_raw(&::rlp::encode(&vec![1u8, 2, 3]), 1);
+			stream.begin_list(0);
+			stream.out()
+		};
+
+		let mut header = Header::new();
+		header.set_transactions_root(::util::ordered_trie_root(vec![::rlp::encode(&vec![1u8, 2, 3]).to_vec()]));
+		header.set_uncles_hash(::rlp::EMPTY_LIST_RLP.sha3());
+		assert_eq!(check_body(&header, &body), Ok(()));
+
+		header.set_uncles_hash(H256::from(3));
+		assert_eq!(check_body(&header, &body), Err(Error::WrongUnclesHash { expected: H256::from(3), found: ::rlp::EMPTY_LIST_RLP.sha3() }));
+		assert_eq!(check_body(&header, &[0xc0]), Err(Error::BadBody));
+	}
+
+	#[test]
+	fn checks_receipts_root() {
+		let receipts = vec![Receipt::new(H256::from(1), U256::from(21000), Vec::new())];
+		let mut header = Header::new();
+		header.set_receipts_root(::util::ordered_trie_root(receipts.iter().map(|r| ::rlp::encode(r).to_vec())));
+		assert_eq!(check_receipts(&header, &receipts), Ok(()));
+
+		header.set_receipts_root(H256::from(2));
+		assert!(check_receipts(&header, &receipts).is_err());
+	}
+
+	#[test]
+	fn checks_state_proofs() {
+		let mut db = MemoryDB::new();
+		let mut storage_root = H256::new();
+		{
+			let mut trie = TrieDBMut::new(&mut db, &mut storage_root);
+			trie.insert(&H256::from(7), b"storage value").unwrap();
+		}
+
+		let account = {
+			let mut stream = RlpStream::new_list(4);
+			stream.append(&0u64).append(&0u64).append(&storage_root).append(&H256::new());
+			stream.out()
+		};
+
+		let mut state_root = H256::new();
+		{
+			let mut trie = TrieDBMut::new(&mut db, &mut state_root);
+			trie.insert(&H256::from(5), &account).unwrap();
+			trie.insert(&H256::from(6), b"other account").unwrap();
+		}
+
+		let proof: Vec<_> = db.keys().keys().filter_map(|k| db.get(k)).map(|v| v.to_vec()).collect();
+
+		let req = StateProof { block: H256::new(), key1: H256::from(5), key2: None, from_level: 0 };
+		assert_eq!(check_state_proof(&state_root, &req, &proof), Ok(Some(account.clone())));
+
+		let req = StateProof { key2: Some(H256::from(7)), ..req };
+		assert_eq!(check_state_proof(&state_root, &req, &proof), Ok(Some(b"storage value".to_vec())));
+
+		match check_state_proof(&state_root, &req, &[]) {
+			Err(Error::BadProof(TrieError::InvalidStateRoot(_))) => {},
+			other => panic!("unexpected result: {:?}", other),
+		}
+	}
+}
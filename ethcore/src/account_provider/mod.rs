@@ -16,16 +16,22 @@
 
 //! Account management.
 
+mod policy;
+
 use std::{fs, fmt};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::{Instant, Duration};
-use util::{Mutex, RwLock};
-use ethstore::{SecretStore, Error as SSError, SafeAccount, EthStore};
+use util::{Mutex, RwLock, U256};
+use ethstore::{SecretStore, Error as SSError, SafeAccount, EthStore, phrase_entropy};
 use ethstore::dir::{KeyDirectory};
-use ethstore::ethkey::{Address, Message, Public, Secret, Random, Generator};
-use ethjson::misc::AccountMeta;
+use ethstore::ethkey::{Address, Message, Public, Secret, Random, Brain, Generator};
+use ethjson::hash::Address as JsonAddress;
+use ethjson::uint::Uint;
+use ethjson::misc::{AccountMeta, AccountsSettings, AccountPolicy as JsonAccountPolicy, DailySpending};
 pub use ethstore::ethkey::Signature;
+pub use self::policy::{AccountPolicy, PolicyViolation};
+use self::policy::Spending;
 
 /// Type of unlock.
 #[derive(Clone)]
@@ -53,6 +59,10 @@ pub enum Error {
 	NotUnlocked,
 	/// Returned when signing fails.
 	SStore(SSError),
+	/// Returned when a brain wallet phrase has less than required entropy (estimated and required bits).
+	WeakPhrase(u32, u32),
+	/// Returned when signing is refused by the account policy.
+	Policy(PolicyViolation),
 }
 
 impl fmt::Display for Error {
@@ -60,6 +70,8 @@ impl fmt::Display for Error {
 		match *self {
 			Error::NotUnlocked => write!(f, "Account is locked"),
 			Error::SStore(ref e) => write!(f, "{}", e),
+			Error::WeakPhrase(bits, required) => write!(f, "Phrase is too weak: about {} bits of entropy, at least {} required", bits, required),
+			Error::Policy(ref violation) => write!(f, "{}", violation),
 		}
 	}
 }
@@ -163,12 +175,140 @@ impl AddressBook {
 	}
 }
 
+/// Disk-backed settings of accounts: tags, per-dapp visibility, signing policies and value sent today. Uses JSON.
+struct AccountsSettingsStore {
+	path: PathBuf,
+	cache: AccountsSettings,
+	spending: Spending,
+	transient: bool,
+}
+
+impl AccountsSettingsStore {
+	pub fn new(path: String) -> Self {
+		let mut path: PathBuf = path.into();
+		path.push("accounts_settings.json");
+		let mut r = AccountsSettingsStore {
+			path: path,
+			cache: AccountsSettings::default(),
+			spending: Spending::default(),
+			transient: false,
+		};
+		r.revert();
+		r
+	}
+
+	pub fn transient() -> Self {
+		AccountsSettingsStore {
+			path: Default::default(),
+			cache: AccountsSettings::default(),
+			spending: Spending::default(),
+			transient: true,
+		}
+	}
+
+	pub fn tags(&self, a: &Address) -> Vec<String> {
+		let a: JsonAddress = a.clone().into();
+		self.cache.tags.get(&a).cloned().unwrap_or_else(Vec::new)
+	}
+
+	pub fn set_tags(&mut self, a: Address, tags: Vec<String>) {
+		let a: JsonAddress = a.into();
+		if tags.is_empty() {
+			self.cache.tags.remove(&a);
+		} else {
+			self.cache.tags.insert(a, tags);
+		}
+		self.save();
+	}
+
+	pub fn dapp_accounts(&self, dapp: &str) -> Option<Vec<Address>> {
+		self.cache.dapps.get(dapp).map(|accounts| accounts.iter().cloned().map(Into::into).collect())
+	}
+
+	pub fn set_dapp_accounts(&mut self, dapp: String, accounts: Vec<Address>) {
+		self.cache.dapps.insert(dapp, accounts.into_iter().map(Into::into).collect());
+		self.save();
+	}
+
+	pub fn policy(&self, a: &Address) -> Option<AccountPolicy> {
+		let a: JsonAddress = a.clone().into();
+		self.cache.policies.get(&a).map(|p| AccountPolicy {
+			max_daily_value: p.max_daily_value.map(Into::into),
+			allowed_recipients: p.allowed_recipients.as_ref().map(|r| r.iter().cloned().map(Into::into).collect()),
+			require_confirmation: p.require_confirmation,
+		})
+	}
+
+	pub fn set_policy(&mut self, a: Address, policy: Option<AccountPolicy>) {
+		let a: JsonAddress = a.into();
+		match policy {
+			Some(p) => {
+				self.cache.policies.insert(a, JsonAccountPolicy {
+					max_daily_value: p.max_daily_value.map(Uint),
+					allowed_recipients: p.allowed_recipients.map(|r| r.into_iter().map(Into::into).collect()),
+					require_confirmation: p.require_confirmation,
+				});
+			},
+			None => {
+				self.cache.policies.remove(&a);
+			},
+		}
+		self.save();
+	}
+
+	pub fn spent(&mut self, a: &Address) -> U256 {
+		self.spending.spent(a)
+	}
+
+	pub fn note_spent(&mut self, a: Address, value: U256) {
+		self.spending.add(a, value);
+		self.save();
+	}
+
+	fn revert(&mut self) {
+		if self.transient { return; }
+		let _ = fs::File::open(self.path.clone())
+			.map_err(|e| trace!(target: "accounts", "Couldn't open accounts settings: {}", e))
+			.and_then(|f| AccountsSettings::load(&f)
+				.map_err(|e| warn!(target: "accounts", "Couldn't read accounts settings: {}", e))
+				.and_then(|s| { self.cache = s; Ok(()) })
+			);
+		self.spending = Spending {
+			day: self.cache.spending.day,
+			spent: self.cache.spending.spent.iter().map(|(a, v)| (a.clone().into(), v.clone().into())).collect(),
+		};
+	}
+
+	fn save(&mut self) {
+		if self.transient { return; }
+		self.cache.spending = DailySpending {
+			day: self.spending.day,
+			spent: self.spending.spent.iter().map(|(a, v)| (a.clone().into(), Uint(*v))).collect(),
+		};
+		// write a temporary file first, so that a crash never leaves the settings truncated.
+		let mut temp = self.path.clone();
+		temp.set_extension("json.tmp");
+		let _ = fs::File::create(&temp)
+			.map_err(|e| warn!(target: "accounts", "Couldn't open accounts settings for writing: {}", e))
+			.and_then(|mut f| self.cache.save(&mut f)
+				.map_err(|e| warn!(target: "accounts", "Couldn't write accounts settings: {}", e))
+				.and_then(|_| f.sync_all().map_err(|e| warn!(target: "accounts", "Couldn't write accounts settings: {}", e)))
+			)
+			.and_then(|_| fs::rename(&temp, &self.path)
+				.map_err(|e| warn!(target: "accounts", "Couldn't replace accounts settings: {}", e))
+			);
+	}
+}
+
 /// Account management.
 /// Responsible for unlocking accounts.
 pub struct AccountProvider {
 	unlocked: Mutex<HashMap<Address, AccountData>>,
 	sstore: Box<SecretStore>,
 	address_book: Mutex<AddressBook>,
+	settings: Mutex<AccountsSettingsStore>,
+	/// Minimal estimated entropy (in bits) of brain wallet phrases.
+	min_phrase_entropy: u32,
 }
 
 impl AccountProvider {
@@ -177,7 +317,9 @@ impl AccountProvider {
 		AccountProvider {
 			unlocked: Mutex::new(HashMap::new()),
 			address_book: Mutex::new(AddressBook::new(sstore.local_path().into())),
+			settings: Mutex::new(AccountsSettingsStore::new(sstore.local_path().into())),
 			sstore: sstore,
+			min_phrase_entropy: 0,
 		}
 	}
 
@@ -186,11 +328,29 @@ impl AccountProvider {
 		AccountProvider {
 			unlocked: Mutex::new(HashMap::new()),
 			address_book: Mutex::new(AddressBook::transient()),
+			settings: Mutex::new(AccountsSettingsStore::transient()),
+			min_phrase_entropy: 0,
 			sstore: Box::new(EthStore::open(Box::new(NullDir::default()))
 				.expect("NullDir load always succeeds; qed"))
 		}
 	}
 
+	/// Refuse brain wallet phrases with estimated entropy lower than `bits`.
+	pub fn with_min_phrase_entropy(mut self, bits: u32) -> Self {
+		self.min_phrase_entropy = bits;
+		self
+	}
+
+	/// Creates new account from a brain wallet phrase.
+	pub fn new_account_from_phrase(&self, phrase: &str, password: &str) -> Result<Address, Error> {
+		let entropy = phrase_entropy(phrase);
+		if entropy < self.min_phrase_entropy as f64 {
+			return Err(Error::WeakPhrase(entropy as u32, self.min_phrase_entropy));
+		}
+		let keypair = Brain::new(phrase.to_owned()).generate().expect("Brain wallet generation always succeeds; qed");
+		self.insert_account(keypair.secret().clone(), password)
+	}
+
 	/// Creates new random account.
 	pub fn new_account(&self, password: &str) -> Result<Address, Error> {
 		self.new_account_and_public(password).map(|d| d.0)
@@ -275,6 +435,69 @@ impl AccountProvider {
 		Ok(())
 	}
 
+	/// Returns tags of given account or address.
+	pub fn account_tags(&self, account: &Address) -> Vec<String> {
+		self.settings.lock().tags(account)
+	}
+
+	/// Replaces tags of given account or address.
+	pub fn set_account_tags(&self, account: Address, tags: Vec<String>) {
+		self.settings.lock().set_tags(account, tags)
+	}
+
+	/// Returns accounts visible to given dapp.
+	/// Dapps without explicit settings can see all accounts.
+	pub fn dapps_addresses(&self, dapp: &str) -> Result<Vec<Address>, Error> {
+		let accounts = try!(self.accounts());
+		Ok(match self.dapp_restriction(dapp) {
+			Some(allowed) => allowed.into_iter().filter(|a| accounts.contains(a)).collect(),
+			None => accounts,
+		})
+	}
+
+	/// Returns addresses given dapp is restricted to, or `None` if it can see all of them.
+	pub fn dapp_restriction(&self, dapp: &str) -> Option<Vec<Address>> {
+		self.settings.lock().dapp_accounts(dapp)
+	}
+
+	/// Sets accounts visible to given dapp.
+	pub fn set_dapps_addresses(&self, dapp: String, addresses: Vec<Address>) {
+		self.settings.lock().set_dapp_accounts(dapp, addresses)
+	}
+
+	/// Returns signing policy of given account.
+	pub fn account_policy(&self, account: &Address) -> Option<AccountPolicy> {
+		self.settings.lock().policy(account)
+	}
+
+	/// Sets or removes signing policy of given account.
+	pub fn set_account_policy(&self, account: Address, policy: Option<AccountPolicy>) {
+		self.settings.lock().set_policy(account, policy)
+	}
+
+	/// Checks if a transaction can be signed without confirmation.
+	/// The check is repeated when the transaction is signed, since other transactions may be signed meanwhile.
+	pub fn check_transaction(&self, from: &Address, to: Option<&Address>, value: &U256) -> Result<(), PolicyViolation> {
+		let mut settings = self.settings.lock();
+		match settings.policy(from) {
+			Some(policy) => policy.check(to, value, &settings.spent(from)),
+			None => Ok(()),
+		}
+	}
+
+	/// Checks if an arbitrary hash can be signed without confirmation.
+	pub fn check_signature(&self, account: &Address) -> Result<(), PolicyViolation> {
+		match self.account_policy(account) {
+			Some(policy) => policy.check_hash(),
+			None => Ok(()),
+		}
+	}
+
+	/// Records value sent in a signed transaction, counted against the daily limit.
+	pub fn note_spent(&self, from: Address, value: U256) {
+		self.settings.lock().note_spent(from, value)
+	}
+
 	/// Returns `true` if the password for `account` is `password`. `false` if not.
 	pub fn test_password(&self, account: &Address, password: &str) -> Result<bool, Error> {
 		match self.sstore.sign(account, password, &Default::default()) {
@@ -355,7 +578,29 @@ impl AccountProvider {
 	}
 
 	/// Signs the message. If password is not provided the account must be unlocked.
+	/// Fails if the signing policy of the account does not allow signing arbitrary messages.
 	pub fn sign(&self, account: Address, password: Option<String>, message: Message) -> Result<Signature, Error> {
+		try!(self.check_signature(&account).map_err(Error::Policy));
+		self.sign_confirmed(account, password, message)
+	}
+
+	/// Signs hash of a transaction of `from` sending `value` to `to`. If password is not provided the account must be unlocked.
+	/// Fails if the transaction violates the signing policy of the account; otherwise the value counts against its daily limit.
+	pub fn sign_transaction(&self, from: Address, to: Option<&Address>, value: U256, password: Option<String>, hash: Message) -> Result<Signature, Error> {
+		// keep the settings locked, so that concurrent transactions can't exceed the daily limit together.
+		let mut settings = self.settings.lock();
+		if let Some(policy) = settings.policy(&from) {
+			let spent = settings.spent(&from);
+			try!(policy.check(to, &value, &spent).map_err(Error::Policy));
+		}
+		let signature = try!(self.sign_confirmed(from, password, hash));
+		settings.note_spent(from, value);
+		Ok(signature)
+	}
+
+	/// Signs the message without checking the signing policy, for requests confirmed by the user.
+	/// If password is not provided the account must be unlocked.
+	pub fn sign_confirmed(&self, account: Address, password: Option<String>, message: Message) -> Result<Signature, Error> {
 		let password = try!(password.map(Ok).unwrap_or_else(|| self.password(&account)));
 		Ok(try!(self.sstore.sign(&account, &password, &message)))
 	}
@@ -379,11 +624,11 @@ impl AccountProvider {
 
 #[cfg(test)]
 mod tests {
-	use super::{AccountProvider, AddressBook, Unlock};
+	use super::{AccountProvider, AddressBook, AccountsSettingsStore, AccountPolicy, PolicyViolation, Unlock};
 	use std::collections::HashMap;
 	use std::time::Instant;
 	use ethjson::misc::AccountMeta;
-	use ethstore::ethkey::{Generator, Random};
+	use ethstore::ethkey::{Brain, Generator, Random};
 	use devtools::RandomTempPath;
 
 	#[test]
@@ -397,6 +642,76 @@ mod tests {
 		assert_eq!(b.get(), hash_map![1.into() => AccountMeta{name: "One".to_owned(), meta: "{1:1}".to_owned(), uuid: None}]);
 	}
 
+	#[test]
+	fn should_save_and_reload_accounts_settings() {
+		let temp = RandomTempPath::create_dir();
+		let path = temp.as_str().to_owned();
+		let mut s = AccountsSettingsStore::new(path.clone());
+		s.set_tags(1.into(), vec!["savings".to_owned()]);
+		s.set_dapp_accounts("wallet".to_owned(), vec![1.into(), 2.into()]);
+		let policy = AccountPolicy {
+			max_daily_value: Some(10.into()),
+			allowed_recipients: Some(vec![3.into()].into_iter().collect()),
+			require_confirmation: true,
+		};
+		s.set_policy(1.into(), Some(policy.clone()));
+		s.note_spent(1.into(), 7.into());
+		let mut s = AccountsSettingsStore::new(path);
+		assert_eq!(s.policy(&1.into()), Some(policy));
+		assert_eq!(s.spent(&1.into()), 7.into());
+		assert_eq!(s.tags(&1.into()), vec!["savings".to_owned()]);
+		assert_eq!(s.dapp_accounts("wallet"), Some(vec![1.into(), 2.into()]));
+		assert_eq!(s.dapp_accounts("other"), None);
+	}
+
+	#[test]
+	fn should_restrict_dapps_addresses() {
+		let ap = AccountProvider::transient_provider();
+		let a = ap.new_account("test").unwrap();
+		let b = ap.new_account("test").unwrap();
+		assert_eq!(ap.dapps_addresses("wallet").unwrap().len(), 2);
+		ap.set_dapps_addresses("wallet".to_owned(), vec![b, 5.into()]);
+		assert_eq!(ap.dapps_addresses("wallet").unwrap(), vec![b]);
+		assert!(!ap.dapps_addresses("wallet").unwrap().contains(&a));
+	}
+
+	#[test]
+	fn should_check_account_policy() {
+		let ap = AccountProvider::transient_provider();
+		let a = ap.new_account("test").unwrap();
+		assert!(ap.check_transaction(&a, None, &1000.into()).is_ok());
+
+		ap.set_account_policy(a, Some(AccountPolicy {
+			max_daily_value: Some(100.into()),
+			allowed_recipients: None,
+			require_confirmation: false,
+		}));
+		assert!(ap.check_transaction(&a, Some(&1.into()), &100.into()).is_ok());
+		ap.note_spent(a, 60.into());
+		assert_eq!(ap.check_transaction(&a, Some(&1.into()), &50.into()), Err(PolicyViolation::DailyLimitExceeded {
+			limit: 100.into(),
+			spent: 60.into(),
+		}));
+		assert_eq!(ap.check_signature(&a), Err(PolicyViolation::UnverifiableRequest));
+		assert!(ap.sign_transaction(a, Some(&1.into()), 50.into(), Some("test".into()), Default::default()).is_err());
+		assert!(ap.sign(a, Some("test".into()), Default::default()).is_err());
+		assert!(ap.sign_confirmed(a, Some("test".into()), Default::default()).is_ok());
+		assert!(ap.sign_transaction(a, Some(&1.into()), 40.into(), Some("test".into()), Default::default()).is_ok());
+		assert!(ap.check_transaction(&a, Some(&1.into()), &1.into()).is_err());
+
+		ap.set_account_policy(a, None);
+		assert!(ap.check_transaction(&a, Some(&1.into()), &50.into()).is_ok());
+	}
+
+	#[test]
+	fn should_refuse_weak_phrases() {
+		let ap = AccountProvider::transient_provider().with_min_phrase_entropy(64);
+		assert!(ap.new_account_from_phrase("password", "test").is_err());
+		let phrase = "abacus abdomen abdominal abide abiding ability";
+		let address = ap.new_account_from_phrase(phrase, "test").unwrap();
+		assert_eq!(address, Brain::new(phrase.to_owned()).generate().unwrap().address());
+	}
+
 	#[test]
 	fn unlock_account_temp() {
 		let kp = Random.generate().unwrap();
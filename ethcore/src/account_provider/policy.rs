@@ -0,0 +1,201 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Signing policies of accounts.
+
+use std::fmt;
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+use ethstore::ethkey::Address;
+use util::{U256, Uint};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Rules checked before anything is signed with an account.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountPolicy {
+	/// Maximal value which can be transferred during a single (UTC) day.
+	pub max_daily_value: Option<U256>,
+	/// If set, transactions may only be sent to these addresses.
+	pub allowed_recipients: Option<HashSet<Address>>,
+	/// Every request has to be confirmed in the signer, even when the account is unlocked.
+	pub require_confirmation: bool,
+}
+
+impl AccountPolicy {
+	/// Check a transaction sending `value` to `to` (`None` for contract creation),
+	/// given the value already sent today.
+	pub fn check(&self, to: Option<&Address>, value: &U256, spent_today: &U256) -> Result<(), PolicyViolation> {
+		if self.require_confirmation {
+			return Err(PolicyViolation::ConfirmationRequired);
+		}
+
+		if let Some(ref allowed) = self.allowed_recipients {
+			if !to.map_or(false, |to| allowed.contains(to)) {
+				return Err(PolicyViolation::RecipientNotAllowed(to.cloned()));
+			}
+		}
+
+		if let Some(ref limit) = self.max_daily_value {
+			let (total, overflow) = spent_today.overflowing_add(*value);
+			if overflow || total > *limit {
+				return Err(PolicyViolation::DailyLimitExceeded {
+					limit: *limit,
+					spent: *spent_today,
+				});
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Check signing of an arbitrary hash.
+	/// The hash may be a transaction, so any value or recipient rule requires confirmation.
+	pub fn check_hash(&self) -> Result<(), PolicyViolation> {
+		if self.require_confirmation {
+			return Err(PolicyViolation::ConfirmationRequired);
+		}
+		if self.max_daily_value.is_some() || self.allowed_recipients.is_some() {
+			return Err(PolicyViolation::UnverifiableRequest);
+		}
+		Ok(())
+	}
+}
+
+/// Reason why a request was refused by the policy.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolicyViolation {
+	/// Transaction would exceed the daily limit.
+	DailyLimitExceeded {
+		/// Daily limit.
+		limit: U256,
+		/// Value already sent today.
+		spent: U256,
+	},
+	/// Recipient is not on the list of allowed recipients.
+	RecipientNotAllowed(Option<Address>),
+	/// Account requires every request to be confirmed.
+	ConfirmationRequired,
+	/// Content of the request can't be checked against the policy.
+	UnverifiableRequest,
+}
+
+impl fmt::Display for PolicyViolation {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			PolicyViolation::DailyLimitExceeded { ref limit, ref spent } =>
+				write!(f, "Daily limit of {} exceeded ({} already sent today)", limit, spent),
+			PolicyViolation::RecipientNotAllowed(Some(ref to)) => write!(f, "Recipient {} is not allowed", to),
+			PolicyViolation::RecipientNotAllowed(None) => write!(f, "Contract creation is not allowed"),
+			PolicyViolation::ConfirmationRequired => write!(f, "Account requires confirmation of every request"),
+			PolicyViolation::UnverifiableRequest => write!(f, "Signing arbitrary data is not allowed by the account policy"),
+		}
+	}
+}
+
+fn today() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) / SECONDS_PER_DAY
+}
+
+/// Value sent by each account during the current day.
+#[derive(Debug, Default, PartialEq)]
+pub struct Spending {
+	/// Day the values were sent on, in days since the UNIX epoch.
+	pub day: u64,
+	/// Value sent by each account.
+	pub spent: HashMap<Address, U256>,
+}
+
+impl Spending {
+	/// Value sent by `account` today.
+	pub fn spent(&mut self, account: &Address) -> U256 {
+		self.spent_on(today(), account)
+	}
+
+	/// Record `value` sent by `account` today.
+	pub fn add(&mut self, account: Address, value: U256) {
+		self.add_on(today(), account, value)
+	}
+
+	fn roll(&mut self, day: u64) {
+		if day != self.day {
+			self.day = day;
+			self.spent.clear();
+		}
+	}
+
+	fn spent_on(&mut self, day: u64, account: &Address) -> U256 {
+		self.roll(day);
+		self.spent.get(account).cloned().unwrap_or_else(U256::zero)
+	}
+
+	fn add_on(&mut self, day: u64, account: Address, value: U256) {
+		self.roll(day);
+		let spent = self.spent.entry(account).or_insert_with(U256::zero);
+		*spent = match spent.overflowing_add(value) {
+			(_, true) => U256::max_value(),
+			(total, false) => total,
+		};
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{AccountPolicy, PolicyViolation, Spending};
+	use util::U256;
+
+	#[test]
+	fn should_check_recipients() {
+		let policy = AccountPolicy {
+			allowed_recipients: Some(vec![1.into()].into_iter().collect()),
+			..Default::default()
+		};
+		assert_eq!(policy.check(Some(&1.into()), &0.into(), &0.into()), Ok(()));
+		assert_eq!(policy.check(Some(&2.into()), &0.into(), &0.into()), Err(PolicyViolation::RecipientNotAllowed(Some(2.into()))));
+		assert_eq!(policy.check(None, &0.into(), &0.into()), Err(PolicyViolation::RecipientNotAllowed(None)));
+	}
+
+	#[test]
+	fn should_check_daily_limit() {
+		let policy = AccountPolicy {
+			max_daily_value: Some(100.into()),
+			..Default::default()
+		};
+		assert_eq!(policy.check(None, &60.into(), &40.into()), Ok(()));
+		assert!(policy.check(None, &61.into(), &40.into()).is_err());
+		assert!(policy.check(None, &U256::max_value(), &1.into()).is_err());
+	}
+
+	#[test]
+	fn should_require_confirmation() {
+		let policy = AccountPolicy {
+			require_confirmation: true,
+			..Default::default()
+		};
+		assert_eq!(policy.check(None, &0.into(), &0.into()), Err(PolicyViolation::ConfirmationRequired));
+		assert_eq!(policy.check_hash(), Err(PolicyViolation::ConfirmationRequired));
+		assert_eq!(AccountPolicy::default().check_hash(), Ok(()));
+	}
+
+	#[test]
+	fn should_reset_spending_every_day() {
+		let mut spending = Spending::default();
+		spending.add_on(1, 5.into(), 10.into());
+		spending.add_on(1, 5.into(), 15.into());
+		assert_eq!(spending.spent_on(1, &5.into()), 25.into());
+		assert_eq!(spending.spent_on(2, &5.into()), 0.into());
+	}
+}
@@ -30,13 +30,27 @@ use error::{Error, BlockError, TransactionError};
 use factory::Factories;
 use header::Header;
 use receipt::Receipt;
-use state::State;
+use state::{State, ApplyOutcome};
 use state_db::StateDB;
 use trace::FlatTrace;
 use transaction::SignedTransaction;
 use verification::PreverifiedBlock;
 use views::BlockView;
 
+/// Minimal number of transactions in a block for parallel or pipelined execution to be attempted.
+const BATCH_EXECUTION_MIN_TRANSACTIONS: usize = 4;
+
+/// Strategy for executing transactions of an enacted block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExecutionMode {
+	/// Execute and commit transactions one by one.
+	Sequential,
+	/// Execute transactions speculatively in parallel first.
+	Parallel,
+	/// Execute transactions one by one, computing intermediate state roots on a background thread.
+	Pipelined,
+}
+
 /// A block, encoded as it is on the block chain.
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct Block {
@@ -344,18 +358,65 @@ impl<'x> OpenBlock<'x> {
 		let env_info = self.env_info();
 //		info!("env_info says gas_used={}", env_info.gas_used);
 		match self.block.state.apply(&env_info, self.engine, &t, self.block.traces.is_some()) {
-			Ok(outcome) => {
-				self.block.transactions_set.insert(h.unwrap_or_else(||t.hash()));
-				self.block.base.transactions.push(t);
-				let t = outcome.trace;
-				self.block.traces.as_mut().map(|traces| traces.push(t));
-				self.block.receipts.push(outcome.receipt);
-				Ok(self.block.receipts.last().expect("receipt just pushed; qed"))
-			}
+			Ok(outcome) => Ok(self.push_outcome(t, h, outcome)),
 			Err(x) => Err(From::from(x))
 		}
 	}
 
+	/// Push transactions into the block, executing them speculatively in parallel first.
+	///
+	/// Falls back to pushing them one by one if there are too few of them to benefit
+	/// or some are duplicates.
+	fn push_transactions_parallel(&mut self, transactions: &[SignedTransaction]) -> Result<(), Error> {
+		let threads = ::num_cpus::get();
+		if threads < 2 || !self.is_batch_worthwhile(transactions) {
+			return push_transactions(self, transactions);
+		}
+
+		let env_info = self.env_info();
+		let tracing = self.block.traces.is_some();
+		let outcomes = try!(self.block.state.apply_parallel(&env_info, self.engine, transactions, tracing, threads));
+		for (t, outcome) in transactions.iter().zip(outcomes) {
+			self.push_outcome(t.clone(), None, outcome);
+		}
+		Ok(())
+	}
+
+	/// Push transactions into the block, computing intermediate state roots on a background
+	/// thread while the following transactions are executed.
+	///
+	/// Falls back to pushing them one by one under the same conditions as parallel execution.
+	fn push_transactions_pipelined(&mut self, transactions: &[SignedTransaction]) -> Result<(), Error> {
+		if ::num_cpus::get() < 2 || !self.is_batch_worthwhile(transactions) {
+			return push_transactions(self, transactions);
+		}
+
+		let env_info = self.env_info();
+		let tracing = self.block.traces.is_some();
+		let outcomes = try!(self.block.state.apply_pipelined(&env_info, self.engine, transactions, tracing));
+		for (t, outcome) in transactions.iter().zip(outcomes) {
+			self.push_outcome(t.clone(), None, outcome);
+		}
+		Ok(())
+	}
+
+	/// Whether there are enough transactions to benefit from executing them as a batch
+	/// and none of them is a duplicate.
+	fn is_batch_worthwhile(&self, transactions: &[SignedTransaction]) -> bool {
+		let mut hashes = HashSet::new();
+		transactions.len() >= BATCH_EXECUTION_MIN_TRANSACTIONS && transactions.iter()
+			.all(|t| !self.block.transactions_set.contains(&t.hash()) && hashes.insert(t.hash()))
+	}
+
+	fn push_outcome(&mut self, t: SignedTransaction, h: Option<H256>, outcome: ApplyOutcome) -> &Receipt {
+		self.block.transactions_set.insert(h.unwrap_or_else(||t.hash()));
+		self.block.base.transactions.push(t);
+		let t = outcome.trace;
+		self.block.traces.as_mut().map(|traces| traces.push(t));
+		self.block.receipts.push(outcome.receipt);
+		self.block.receipts.last().expect("receipt just pushed; qed")
+	}
+
 	/// Turn this into a `ClosedBlock`.
 	pub fn close(self) -> ClosedBlock {
 		let mut s = self;
@@ -517,6 +578,7 @@ pub fn enact(
 	parent: &Header,
 	last_hashes: Arc<LastHashes>,
 	factories: Factories,
+	execution: ExecutionMode,
 ) -> Result<LockedBlock, Error> {
 	{
 		if ::log::max_log_level() >= ::log::LogLevel::Trace {
@@ -535,7 +597,11 @@ pub fn enact(
 	b.set_transactions_root(header.transactions_root().clone());
 	b.set_receipts_root(header.receipts_root().clone());
 
-	try!(push_transactions(&mut b, transactions));
+	match execution {
+		ExecutionMode::Sequential => try!(push_transactions(&mut b, transactions)),
+		ExecutionMode::Parallel => try!(b.push_transactions_parallel(transactions)),
+		ExecutionMode::Pipelined => try!(b.push_transactions_pipelined(transactions)),
+	}
 	for u in uncles {
 		try!(b.push_uncle(u.clone()));
 	}
@@ -578,9 +644,10 @@ pub fn enact_verified(
 	parent: &Header,
 	last_hashes: Arc<LastHashes>,
 	factories: Factories,
+	execution: ExecutionMode,
 ) -> Result<LockedBlock, Error> {
 	let view = BlockView::new(&block.bytes);
-	enact(&block.header, &block.transactions, &view.uncles(), engine, tracing, db, parent, last_hashes, factories)
+	enact(&block.header, &block.transactions, &view.uncles(), engine, tracing, db, parent, last_hashes, factories, execution)
 }
 
 #[cfg(test)]
@@ -612,7 +679,7 @@ mod tests {
 	) -> Result<LockedBlock, Error> {
 		let block = BlockView::new(block_bytes);
 		let header = block.header();
-		enact(&header, &block.transactions(), &block.uncles(), engine, tracing, db, parent, last_hashes, factories)
+		enact(&header, &block.transactions(), &block.uncles(), engine, tracing, db, parent, last_hashes, factories, ExecutionMode::Sequential)
 	}
 
 	/// Enact the block given by `block_bytes` using `engine` on the database `db` with given `parent` block header. Seal the block aferwards
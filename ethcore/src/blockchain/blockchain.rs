@@ -33,7 +33,9 @@ use types::tree_route::TreeRoute;
 use blockchain::update::ExtrasUpdate;
 use blockchain::{CacheSize, ImportRoute, Config};
 use db::{self, Writable, Readable, CacheUpdatePolicy};
-use cache_manager::CacheManager;
+use cache_manager::{CacheManager, CacheItem};
+use types::cache_stats::BlockChainCacheStats;
+use integrity::Corruption;
 
 const LOG_BLOOMS_LEVELS: usize = 3;
 const LOG_BLOOMS_ELEMENTS_PER_INDEX: usize = 16;
@@ -155,11 +157,32 @@ enum CacheID {
 	BlockReceipts(H256),
 }
 
+const CACHE_HEADERS: usize = 0;
+const CACHE_BODIES: usize = 1;
+const CACHE_RECEIPTS: usize = 2;
+const CACHE_DETAILS: usize = 3;
+const CACHES: usize = 4;
+
+/// Lookups noted before they are handed to the cache manager.
+const CACHE_LOOKUPS_BATCH: usize = 256;
+
+impl CacheItem for CacheID {
+	fn cache(&self) -> usize {
+		match *self {
+			CacheID::BlockHeader(_) => CACHE_HEADERS,
+			CacheID::BlockBody(_) => CACHE_BODIES,
+			CacheID::BlockReceipts(_) => CACHE_RECEIPTS,
+			CacheID::BlockDetails(_) | CacheID::BlockHashes(_) | CacheID::TransactionAddresses(_) | CacheID::BlocksBlooms(_) => CACHE_DETAILS,
+		}
+	}
+}
+
 impl bc::group::BloomGroupDatabase for BlockChain {
 	fn blooms_at(&self, position: &bc::group::GroupPosition) -> Option<bc::group::BloomGroup> {
 		let position = LogGroupPosition::from(position.clone());
+		let hit = self.blocks_blooms.read().contains_key(&position);
 		let result = self.db.read_with_cache(db::COL_EXTRA, &self.blocks_blooms, &position).map(Into::into);
-		self.cache_man.lock().note_used(CacheID::BlocksBlooms(position));
+		self.note_lookup(CacheID::BlocksBlooms(position), hit);
 		result
 	}
 }
@@ -190,14 +213,18 @@ pub struct BlockChain {
 	blocks_blooms: RwLock<HashMap<LogGroupPosition, BloomGroup>>,
 	block_receipts: RwLock<HashMap<H256, BlockReceipts>>,
 
-	db: Arc<Database>,
+	db: Arc<KeyValueDB>,
 
+	// lookups not yet noted by the cache manager, handed over in batches.
+	cache_lookups: Mutex<Vec<(CacheID, bool)>>,
 	cache_man: Mutex<CacheManager<CacheID>>,
 
 	pending_best_block: RwLock<Option<BestBlock>>,
 	pending_block_hashes: RwLock<HashMap<BlockNumber, H256>>,
 	pending_block_details: RwLock<HashMap<H256, BlockDetails>>,
 	pending_transaction_addresses: RwLock<HashMap<H256, Option<TransactionAddress>>>,
+
+	sender_index: bool,
 }
 
 impl BlockProvider for BlockChain {
@@ -222,14 +249,7 @@ impl BlockProvider for BlockChain {
 	/// Get raw block data
 	fn block(&self, hash: &H256) -> Option<Bytes> {
 		match (self.block_header_data(hash), self.block_body(hash)) {
-			(Some(header), Some(body)) => {
-				let mut block = RlpStream::new_list(3);
-				let body_rlp = Rlp::new(&body);
-				block.append_raw(&header, 1);
-				block.append_raw(body_rlp.at(0).as_raw(), 1);
-				block.append_raw(body_rlp.at(1).as_raw(), 1);
-				Some(block.out())
-			},
+			(Some(header), Some(body)) => Some(Self::assemble_block(&header, &body)),
 			_ => None,
 		}
 	}
@@ -240,6 +260,7 @@ impl BlockProvider for BlockChain {
 		{
 			let read = self.block_headers.read();
 			if let Some(v) = read.get(hash) {
+				self.note_lookup(CacheID::BlockHeader(hash.clone()), true);
 				return Some(v.clone());
 			}
 		}
@@ -266,7 +287,7 @@ impl BlockProvider for BlockChain {
 			None => None
 		};
 
-		self.cache_man.lock().note_used(CacheID::BlockHeader(hash.clone()));
+		self.note_lookup(CacheID::BlockHeader(hash.clone()), false);
 		result
 	}
 
@@ -276,6 +297,7 @@ impl BlockProvider for BlockChain {
 		{
 			let read = self.block_bodies.read();
 			if let Some(v) = read.get(hash) {
+				self.note_lookup(CacheID::BlockBody(hash.clone()), true);
 				return Some(v.clone());
 			}
 		}
@@ -302,36 +324,40 @@ impl BlockProvider for BlockChain {
 			None => None
 		};
 
-		self.cache_man.lock().note_used(CacheID::BlockBody(hash.clone()));
+		self.note_lookup(CacheID::BlockBody(hash.clone()), false);
 
 		result
 	}
 
 	/// Get the familial details concerning a block.
 	fn block_details(&self, hash: &H256) -> Option<BlockDetails> {
+		let hit = self.block_details.read().contains_key(hash);
 		let result = self.db.read_with_cache(db::COL_EXTRA, &self.block_details, hash);
-		self.cache_man.lock().note_used(CacheID::BlockDetails(hash.clone()));
+		self.note_lookup(CacheID::BlockDetails(hash.clone()), hit);
 		result
 	}
 
 	/// Get the hash of given block's number.
 	fn block_hash(&self, index: BlockNumber) -> Option<H256> {
+		let hit = self.block_hashes.read().contains_key(&index);
 		let result = self.db.read_with_cache(db::COL_EXTRA, &self.block_hashes, &index);
-		self.cache_man.lock().note_used(CacheID::BlockHashes(index));
+		self.note_lookup(CacheID::BlockHashes(index), hit);
 		result
 	}
 
 	/// Get the address of transaction with given hash.
 	fn transaction_address(&self, hash: &H256) -> Option<TransactionAddress> {
+		let hit = self.transaction_addresses.read().contains_key(hash);
 		let result = self.db.read_with_cache(db::COL_EXTRA, &self.transaction_addresses, hash);
-		self.cache_man.lock().note_used(CacheID::TransactionAddresses(hash.clone()));
+		self.note_lookup(CacheID::TransactionAddresses(hash.clone()), hit);
 		result
 	}
 
 	/// Get receipts of block with given hash.
 	fn block_receipts(&self, hash: &H256) -> Option<BlockReceipts> {
+		let hit = self.block_receipts.read().contains_key(hash);
 		let result = self.db.read_with_cache(db::COL_EXTRA, &self.block_receipts, hash);
-		self.cache_man.lock().note_used(CacheID::BlockReceipts(hash.clone()));
+		self.note_lookup(CacheID::BlockReceipts(hash.clone()), hit);
 		result
 	}
 
@@ -351,8 +377,10 @@ impl BlockProvider for BlockChain {
 		blocks.sort_by(|a, b| b.cmp(a));
 
 		let mut log_index = 0;
+		let hashes = self.block_hashes(&blocks);
 		let mut logs = blocks.into_iter()
-			.filter_map(|number| self.block_hash(number).map(|hash| (number, hash)))
+			.zip(hashes)
+			.filter_map(|(number, hash)| hash.map(|hash| (number, hash)))
 			.filter_map(|(number, hash)| self.block_receipts(&hash).map(|r| (number, hash, r.receipts)))
 			.filter_map(|(number, hash, receipts)| self.block_body(&hash).map(|ref b| (number, hash, receipts, BodyView::new(b).transaction_hashes())))
 			.flat_map(|(number, hash, mut receipts, mut hashes)| {
@@ -414,12 +442,45 @@ impl<'a> Iterator for AncestryIter<'a> {
 	}
 }
 
+/// Number of block hashes `CanonicalIter` reads at once.
+const CANONICAL_ITER_BATCH: BlockNumber = 256;
+
+/// An iterator over numbers and hashes of canonical blocks, walking away from the genesis.
+pub struct CanonicalIter<'a> {
+	next: BlockNumber,
+	end: BlockNumber,
+	buffer: VecDeque<H256>,
+	chain: &'a BlockChain,
+}
+
+impl<'a> Iterator for CanonicalIter<'a> {
+	type Item = (BlockNumber, H256);
+	fn next(&mut self) -> Option<(BlockNumber, H256)> {
+		if self.buffer.is_empty() && self.next < self.end {
+			let end = cmp::min(self.next + CANONICAL_ITER_BATCH, self.end);
+			let numbers: Vec<BlockNumber> = (self.next..end).collect();
+			self.buffer = self.chain.block_hashes(&numbers).into_iter()
+				.take_while(Option::is_some)
+				.filter_map(|hash| hash)
+				.collect();
+			if self.buffer.len() < numbers.len() {
+				self.end = self.next + self.buffer.len() as BlockNumber;
+			}
+		}
+
+		self.buffer.pop_front().map(|hash| {
+			self.next += 1;
+			(self.next - 1, hash)
+		})
+	}
+}
+
 impl BlockChain {
 	#[cfg_attr(feature="dev", allow(useless_let_if_seq))]
 	/// Create new instance of blockchain from given Genesis
-	pub fn new(config: Config, genesis: &[u8], db: Arc<Database>) -> BlockChain {
+	pub fn new(config: Config, genesis: &[u8], db: Arc<KeyValueDB>) -> BlockChain {
 		// 400 is the avarage size of the key
-		let cache_man = CacheManager::new(config.pref_cache_size, config.max_cache_size, 400);
+		let cache_man = CacheManager::with_caches(config.pref_cache_size, config.max_cache_size, 400, CACHES);
 
 		let mut bc = BlockChain {
 			blooms_config: bc::Config {
@@ -437,11 +498,13 @@ impl BlockChain {
 			blocks_blooms: RwLock::new(HashMap::new()),
 			block_receipts: RwLock::new(HashMap::new()),
 			db: db.clone(),
+			cache_lookups: Mutex::new(Vec::with_capacity(CACHE_LOOKUPS_BATCH)),
 			cache_man: Mutex::new(cache_man),
 			pending_best_block: RwLock::new(None),
 			pending_block_hashes: RwLock::new(HashMap::new()),
 			pending_block_details: RwLock::new(HashMap::new()),
 			pending_transaction_addresses: RwLock::new(HashMap::new()),
+			sender_index: config.sender_index,
 		};
 
 		// load best block
@@ -463,7 +526,7 @@ impl BlockChain {
 					children: vec![]
 				};
 
-				let mut batch = DBTransaction::new(&db);
+				let mut batch = DBTransaction::new();
 				batch.put(db::COL_HEADERS, &hash, block.header_rlp().as_raw());
 				batch.put(db::COL_BODIES, &hash, &Self::block_to_body(genesis));
 
@@ -727,6 +790,7 @@ impl BlockChain {
 				location: BlockLocation::CanonChain,
 			};
 
+			self.prepare_sender_index_update(batch, bytes, &info, None);
 			self.prepare_update(batch, ExtrasUpdate {
 				block_hashes: self.prepare_block_hashes_update(bytes, &info),
 				block_details: self.prepare_block_details_update(bytes, &info),
@@ -775,6 +839,7 @@ impl BlockChain {
 			let mut update = HashMap::new();
 			update.insert(hash, block_details);
 
+			self.prepare_sender_index_update(batch, bytes, &info, None);
 			self.prepare_update(batch, ExtrasUpdate {
 				block_hashes: self.prepare_block_hashes_update(bytes, &info),
 				block_details: update,
@@ -813,6 +878,12 @@ impl BlockChain {
 	/// Expects the block to be valid and already verified.
 	/// If the block is already known, does nothing.
 	pub fn insert_block(&self, batch: &mut DBTransaction, bytes: &[u8], receipts: Vec<Receipt>) -> ImportRoute {
+		self.insert_block_with_senders(batch, bytes, receipts, None)
+	}
+
+	/// Inserts the block like `insert_block`, given senders of its transactions recovered during verification.
+	/// The sender index recovers them from signatures when not supplied.
+	pub fn insert_block_with_senders(&self, batch: &mut DBTransaction, bytes: &[u8], receipts: Vec<Receipt>, senders: Option<Vec<Address>>) -> ImportRoute {
 		// create views onto rlp
 		let block = BlockView::new(bytes);
 		let header = block.header_view();
@@ -829,6 +900,7 @@ impl BlockChain {
 		batch.put_compressed(db::COL_BODIES, &hash, Self::block_to_body(bytes));
 
 		let info = self.block_info(&header);
+		self.prepare_sender_index_update(batch, bytes, &info, senders);
 
 		if let BlockLocation::BranchBecomingCanonChain(ref d) = info.location {
 			info!(target: "reorg", "Reorg to {} ({} {} {})",
@@ -852,6 +924,81 @@ impl BlockChain {
 		ImportRoute::from(info)
 	}
 
+	/// Updates the sender index, if it is enabled, with transactions of the block and blocks it enacts and retracts.
+	/// Only canonical transactions are indexed. Senders of the block are kept to follow later reorgs.
+	fn prepare_sender_index_update(&self, batch: &mut DBTransaction, block_bytes: &[u8], info: &BlockInfo, senders: Option<Vec<Address>>) {
+		if !self.sender_index {
+			return;
+		}
+
+		let block = BlockView::new(block_bytes);
+		let senders = match senders {
+			Some(senders) => senders,
+			None => match block.transactions().iter().map(SignedTransaction::sender).collect::<Result<Vec<_>, _>>() {
+				Ok(senders) => senders,
+				Err(e) => {
+					warn!("Unable to index transactions of block {}: {:?}", info.hash, e);
+					return;
+				},
+			},
+		};
+
+		match info.location {
+			BlockLocation::Branch => (),
+			BlockLocation::CanonChain => self.prepare_sender_entries(batch, &info.hash, info.number, &senders, true),
+			BlockLocation::BranchBecomingCanonChain(ref data) => {
+				for hash in &data.retracted {
+					self.prepare_stored_sender_entries(batch, hash, false);
+				}
+				for hash in &data.enacted {
+					self.prepare_stored_sender_entries(batch, hash, true);
+				}
+				self.prepare_sender_entries(batch, &info.hash, info.number, &senders, true);
+			},
+		}
+
+		batch.write(db::COL_EXTRA, &info.hash, &BlockSenders { senders: senders });
+	}
+
+	/// Adds or removes sender index entries of a block already in the database.
+	/// Blocks imported while the index was disabled are skipped.
+	fn prepare_stored_sender_entries(&self, batch: &mut DBTransaction, hash: &H256, canonical: bool) {
+		let senders: Option<BlockSenders> = self.db.read(db::COL_EXTRA, hash);
+		if let (Some(senders), Some(number)) = (senders, self.block_number(hash)) {
+			self.prepare_sender_entries(batch, hash, number, &senders.senders, canonical);
+		}
+	}
+
+	fn prepare_sender_entries(&self, batch: &mut DBTransaction, hash: &H256, number: BlockNumber, senders: &[Address], canonical: bool) {
+		for (index, sender) in senders.iter().enumerate() {
+			let key = SenderTransactionKey::new(sender, number, hash, index);
+			if canonical {
+				batch.put(db::COL_EXTRA, &key, &[]);
+			} else {
+				batch.delete(db::COL_EXTRA, &key);
+			}
+		}
+	}
+
+	/// Get locations of at most `limit` canonical transactions sent by given address, in blocks from `from` to `to` inclusive.
+	/// Returns `None` if the sender index is disabled.
+	pub fn transactions_by_sender(&self, sender: &Address, from: BlockNumber, to: BlockNumber, limit: usize) -> Option<Vec<TransactionAddress>> {
+		if !self.sender_index {
+			return None;
+		}
+
+		let prefix = SenderTransactionKey::prefix(sender);
+		let addresses = self.db.iter_from_prefix(db::COL_EXTRA, &prefix)
+			.filter_map(|(key, _)| SenderTransactionKey::decode(&key))
+			.skip_while(|&(number, _)| number < from)
+			.take_while(|&(number, _)| number <= to)
+			.filter(|&(number, ref address)| self.block_hash(number).as_ref() == Some(&address.block_hash))
+			.map(|(_, address)| address)
+			.take(limit)
+			.collect();
+		Some(addresses)
+	}
+
 	/// Get inserted block info which is critical to prepare extras updates.
 	fn block_info(&self, header: &HeaderView) -> BlockInfo {
 		let hash = header.sha3();
@@ -977,6 +1124,214 @@ impl BlockChain {
 		}
 	}
 
+	/// Checks whether logs of the block with given hash may satisfy `may_match`, given only the
+	/// addresses and topics used by them. Stored receipts are inspected without being decoded;
+	/// those which may match are then decoded into the cache, so they aren't read again.
+	/// Returns true if the receipts are cached, missing or stored in the legacy encoding.
+	pub fn block_logs_may_match<F>(&self, hash: &H256, may_match: F) -> bool where F: Fn(&[Address], &[H256]) -> bool {
+		if self.block_receipts.read().contains_key(hash) {
+			return true;
+		}
+
+		type ReceiptsKey = db::Key<BlockReceipts, Target=H264>;
+		let bytes = match self.db.get(db::COL_EXTRA, &ReceiptsKey::key(hash)) {
+			Ok(Some(bytes)) => bytes,
+			_ => return true,
+		};
+
+		let matches = {
+			let view = BlockReceiptsView::new(&bytes);
+			match (view.addresses(), view.topics()) {
+				(Ok(Some(addresses)), Ok(Some(topics))) => may_match(&addresses, &topics),
+				_ => true,
+			}
+		};
+
+		if matches {
+			let receipts: BlockReceipts = decode(&bytes);
+			self.block_receipts.write().insert(hash.clone(), receipts);
+			self.note_lookup(CacheID::BlockReceipts(hash.clone()), false);
+		}
+		matches
+	}
+
+	/// Get hashes of canonical blocks with given numbers. Hashes which are not cached are read in a single batch.
+	pub fn block_hashes(&self, numbers: &[BlockNumber]) -> Vec<Option<H256>> {
+		let mut hashes: Vec<Option<H256>> = {
+			let cache = self.block_hashes.read();
+			let mut cache_man = self.cache_man.lock();
+			numbers.iter().map(|number| {
+				let hash = cache.get(number).cloned();
+				if hash.is_some() {
+					cache_man.note_lookup(CacheID::BlockHashes(*number), true);
+				}
+				hash
+			}).collect()
+		};
+
+		let missing: Vec<usize> = (0..numbers.len()).filter(|i| hashes[*i].is_none()).collect();
+		if missing.is_empty() {
+			return hashes;
+		}
+
+		type HashKey = db::Key<H256, Target=BlockNumberKey>;
+		let keys: Vec<BlockNumberKey> = missing.iter().map(|i| HashKey::key(&numbers[*i])).collect();
+		let keys: Vec<&[u8]> = keys.iter().map(|key| &**key).collect();
+		let values = self.db.get_many(db::COL_EXTRA, &keys)
+			.expect("Low level database error. Some issue with disk?");
+
+		let mut cache = self.block_hashes.write();
+		let mut cache_man = self.cache_man.lock();
+		for (i, value) in missing.into_iter().zip(values) {
+			if let Some(value) = value {
+				let hash: H256 = decode(&value);
+				cache.insert(numbers[i], hash.clone());
+				cache_man.note_lookup(CacheID::BlockHashes(numbers[i]), false);
+				hashes[i] = Some(hash);
+			}
+		}
+		hashes
+	}
+
+	/// Get canonical blocks with numbers from `from` to `to` inclusive, reading headers and bodies in batches.
+	/// Blocks read this way are not cached. Stops at the first missing block.
+	pub fn blocks_in_range(&self, from: BlockNumber, to: BlockNumber) -> Vec<Bytes> {
+		let hashes: Vec<H256> = self.canonical_iter(from, to).map(|(_, hash)| hash).collect();
+		let keys: Vec<&[u8]> = hashes.iter().map(|hash| &**hash).collect();
+		let headers = self.db.get_many(db::COL_HEADERS, &keys)
+			.expect("Low level database error. Some issue with disk?");
+		let bodies = self.db.get_many(db::COL_BODIES, &keys)
+			.expect("Low level database error. Some issue with disk?");
+
+		let mut blocks = Vec::with_capacity(hashes.len());
+		for ((hash, header), body) in hashes.iter().zip(headers).zip(bodies) {
+			let block = match (header, body) {
+				(Some(header), Some(body)) => Some(Self::assemble_block(
+					&UntrustedRlp::new(&header).decompress(RlpType::Blocks),
+					&UntrustedRlp::new(&body).decompress(RlpType::Blocks),
+				)),
+				// the best block may not be written yet.
+				_ => self.block(hash),
+			};
+
+			match block {
+				Some(block) => blocks.push(block),
+				None => break,
+			}
+		}
+		blocks
+	}
+
+	/// Get receipts of canonical blocks with numbers from `from` to `to` inclusive, reading them in a single batch.
+	/// Receipts read this way are not cached. Stops at the first block with missing receipts.
+	pub fn receipts_in_range(&self, from: BlockNumber, to: BlockNumber) -> Vec<(H256, BlockReceipts)> {
+		type ReceiptsKey = db::Key<BlockReceipts, Target=H264>;
+		let hashes: Vec<H256> = self.canonical_iter(from, to).map(|(_, hash)| hash).collect();
+		let keys: Vec<H264> = hashes.iter().map(|hash| ReceiptsKey::key(hash)).collect();
+		let keys: Vec<&[u8]> = keys.iter().map(|key| &**key).collect();
+		let values = self.db.get_many(db::COL_EXTRA, &keys)
+			.expect("Low level database error. Some issue with disk?");
+
+		hashes.into_iter().zip(values)
+			.take_while(|&(_, ref value)| value.is_some())
+			.filter_map(|(hash, value)| value.map(|value| (hash, decode(&value))))
+			.collect()
+	}
+
+	/// Check the stored body and receipts of a block against the roots in its header.
+	/// Data is read straight from the database, bypassing the caches, and decoded without
+	/// panicking so that damaged entries are reported rather than crashing the caller.
+	pub fn check_block_data(&self, hash: &H256) -> Result<(), Corruption> {
+		type ReceiptsKey = db::Key<BlockReceipts, Target=H264>;
+		let read = |col: Option<u32>, key: &[u8]| self.db.get(col, key)
+			.expect("Low level database error. Some issue with disk?");
+
+		let header = try!(read(db::COL_HEADERS, hash).ok_or(Corruption::MissingHeader));
+		let header = UntrustedRlp::new(&header).decompress(RlpType::Blocks).to_vec();
+		if &header.sha3() != hash {
+			return Err(Corruption::InvalidHeader);
+		}
+		let header: Header = try!(UntrustedRlp::new(&header).as_val().map_err(|_| Corruption::InvalidHeader));
+
+		let body = try!(read(db::COL_BODIES, hash).ok_or(Corruption::MissingBody));
+		let body = UntrustedRlp::new(&body).decompress(RlpType::Blocks);
+		let body = UntrustedRlp::new(&body);
+		let (transactions, uncles) = match (body.at(0), body.at(1)) {
+			(Ok(ref t), Ok(ref u)) if t.is_list() && u.is_list() => (t.as_raw().to_vec(), u.as_raw().to_vec()),
+			_ => return Err(Corruption::InvalidBody),
+		};
+
+		let transactions_root = ordered_trie_root(UntrustedRlp::new(&transactions).iter().map(|t| t.as_raw().to_vec()));
+		if &transactions_root != header.transactions_root() {
+			return Err(Corruption::TransactionsRoot(Mismatch { expected: header.transactions_root().clone(), found: transactions_root }));
+		}
+		let uncles_hash = uncles.sha3();
+		if &uncles_hash != header.uncles_hash() {
+			return Err(Corruption::UnclesHash(Mismatch { expected: header.uncles_hash().clone(), found: uncles_hash }));
+		}
+
+		// genesis receipts are never written.
+		if header.number() == 0 {
+			return Ok(());
+		}
+
+		let receipts = try!(read(db::COL_EXTRA, &ReceiptsKey::key(hash)).ok_or(Corruption::MissingReceipts));
+		let receipts = try!(BlockReceiptsView::new(&receipts).receipts().map_err(|_| Corruption::InvalidReceipts));
+		let receipts_root = ordered_trie_root(receipts.iter().map(|r| r.rlp_bytes().to_vec()));
+		if &receipts_root != header.receipts_root() {
+			return Err(Corruption::ReceiptsRoot(Mismatch { expected: header.receipts_root().clone(), found: receipts_root }));
+		}
+
+		Ok(())
+	}
+
+	/// Remove canonical blocks `from` to `to` inclusive so that they form the gap between the
+	/// ancient blocks and the first block, which sync downloads again in the background.
+	///
+	/// Headers, bodies, details, receipts and the number index of those blocks are removed.
+	/// Transaction addresses and blooms are left in place; re-imported blocks overwrite them with
+	/// the same values. Caches are not updated, so the chain must be reopened after `batch` is written.
+	/// `from` must be above the genesis and `to` below the best block, and there must be no other gap.
+	pub fn prepare_redownload(&self, batch: &mut DBTransaction, from: BlockNumber, to: BlockNumber) {
+		type DetailsKey = db::Key<BlockDetails, Target=H264>;
+		type ReceiptsKey = db::Key<BlockReceipts, Target=H264>;
+		type HashKey = db::Key<H256, Target=BlockNumberKey>;
+
+		assert!(from > 0 && from <= to && to < self.best_block_number(), "Invalid range of blocks to re-download");
+		assert!(self.best_ancient_block.read().is_none(), "Only one gap in the chain is supported");
+
+		let hashes: Vec<H256> = self.canonical_iter(from, to).map(|(_, hash)| hash).collect();
+		assert_eq!(hashes.len() as BlockNumber, to - from + 1);
+		let parent = self.block_hash(from - 1).expect("Block below the range is canonical; qed");
+		let first = self.block_hash(to + 1).expect("Block above the range is canonical; qed");
+
+		for (number, hash) in (from..).zip(hashes.iter()) {
+			batch.delete(db::COL_HEADERS, hash);
+			batch.delete(db::COL_BODIES, hash);
+			batch.delete(db::COL_EXTRA, &DetailsKey::key(hash));
+			batch.delete(db::COL_EXTRA, &ReceiptsKey::key(hash));
+			batch.delete(db::COL_EXTRA, &*HashKey::key(&number));
+		}
+
+		// the parent gets the re-downloaded block added as a child again.
+		let mut parent_details = self.block_details(&parent).expect("Canonical block has details; qed");
+		parent_details.children.retain(|child| child != &hashes[0]);
+		batch.write(db::COL_EXTRA, &parent, &parent_details);
+
+		batch.put(db::COL_EXTRA, b"ancient", &parent);
+		batch.put(db::COL_EXTRA, b"first", &first);
+	}
+
+	/// Iterator over numbers and hashes of canonical blocks from `from` to `to` inclusive.
+	pub fn canonical_iter(&self, from: BlockNumber, to: BlockNumber) -> CanonicalIter {
+		CanonicalIter {
+			next: from,
+			end: to.saturating_add(1),
+			buffer: VecDeque::new(),
+			chain: self,
+		}
+	}
+
 	/// Iterator that lists `first` and then all of `first`'s ancestors, by hash.
 	pub fn ancestry_iter(&self, first: H256) -> Option<AncestryIter> {
 		if self.is_known(&first) {
@@ -1225,9 +1580,64 @@ impl BlockChain {
 		}
 	}
 
+	/// Current size of every cache managed by the cache manager.
+	fn cache_sizes(&self) -> [usize; CACHES] {
+		let mut sizes = [0; CACHES];
+		sizes[CACHE_HEADERS] = self.block_headers.read().heap_size_of_children();
+		sizes[CACHE_BODIES] = self.block_bodies.read().heap_size_of_children();
+		sizes[CACHE_RECEIPTS] = self.block_receipts.read().heap_size_of_children();
+		sizes[CACHE_DETAILS] = self.block_details.read().heap_size_of_children() +
+			self.block_hashes.read().heap_size_of_children() +
+			self.transaction_addresses.read().heap_size_of_children() +
+			self.blocks_blooms.read().heap_size_of_children();
+		sizes
+	}
+
+	/// Note a cache lookup. Lookups reach the cache manager in batches so that
+	/// cache hits don't contend on its lock.
+	fn note_lookup(&self, id: CacheID, hit: bool) {
+		let lookups = {
+			let mut lookups = self.cache_lookups.lock();
+			lookups.push((id, hit));
+			if lookups.len() < CACHE_LOOKUPS_BATCH {
+				return;
+			}
+			mem::replace(&mut *lookups, Vec::with_capacity(CACHE_LOOKUPS_BATCH))
+		};
+		self.apply_lookups(lookups);
+	}
+
+	/// Hand all the pending lookups over to the cache manager.
+	fn flush_lookups(&self) {
+		let lookups = mem::replace(&mut *self.cache_lookups.lock(), Vec::new());
+		self.apply_lookups(lookups);
+	}
+
+	fn apply_lookups(&self, lookups: Vec<(CacheID, bool)>) {
+		let mut cache_man = self.cache_man.lock();
+		for (id, hit) in lookups {
+			cache_man.note_lookup(id, hit);
+		}
+	}
+
+	/// Get hit and miss counts, sizes and budgets of the caches.
+	pub fn cache_stats(&self) -> BlockChainCacheStats {
+		self.flush_lookups();
+		let sizes = self.cache_sizes();
+		let cache_man = self.cache_man.lock();
+		BlockChainCacheStats {
+			headers: cache_man.stats(CACHE_HEADERS, sizes[CACHE_HEADERS]),
+			bodies: cache_man.stats(CACHE_BODIES, sizes[CACHE_BODIES]),
+			receipts: cache_man.stats(CACHE_RECEIPTS, sizes[CACHE_RECEIPTS]),
+			details: cache_man.stats(CACHE_DETAILS, sizes[CACHE_DETAILS]),
+		}
+	}
+
 	/// Ticks our cache system and throws out any old data.
 	pub fn collect_garbage(&self) {
-		let current_size = self.cache_size().total();
+		// items are only collected once their usage is known.
+		self.flush_lookups();
+		let current_sizes = self.cache_sizes();
 
 		let mut block_headers = self.block_headers.write();
 		let mut block_bodies = self.block_bodies.write();
@@ -1238,7 +1648,7 @@ impl BlockChain {
 		let mut block_receipts = self.block_receipts.write();
 
 		let mut cache_man = self.cache_man.lock();
-		cache_man.collect_garbage(current_size, | ids | {
+		cache_man.collect_garbage(&current_sizes, | cache, ids | {
 			for id in &ids {
 				match *id {
 					CacheID::BlockHeader(ref h) => { block_headers.remove(h); },
@@ -1259,16 +1669,28 @@ impl BlockChain {
 			blocks_blooms.shrink_to_fit();
 			block_receipts.shrink_to_fit();
 
-			block_headers.heap_size_of_children() +
-			block_bodies.heap_size_of_children() +
-			block_details.heap_size_of_children() +
-			block_hashes.heap_size_of_children() +
-			transaction_addresses.heap_size_of_children() +
-			blocks_blooms.heap_size_of_children() +
-			block_receipts.heap_size_of_children()
+			match cache {
+				CACHE_HEADERS => block_headers.heap_size_of_children(),
+				CACHE_BODIES => block_bodies.heap_size_of_children(),
+				CACHE_RECEIPTS => block_receipts.heap_size_of_children(),
+				_ => block_details.heap_size_of_children() +
+					block_hashes.heap_size_of_children() +
+					transaction_addresses.heap_size_of_children() +
+					blocks_blooms.heap_size_of_children(),
+			}
 		});
 	}
 
+	/// Assemble a block from its header and body.
+	fn assemble_block(header: &[u8], body: &[u8]) -> Bytes {
+		let mut block = RlpStream::new_list(3);
+		let body_rlp = Rlp::new(body);
+		block.append_raw(header, 1);
+		block.append_raw(body_rlp.at(0).as_raw(), 1);
+		block.append_raw(body_rlp.at(1).as_raw(), 1);
+		block.out()
+	}
+
 	/// Create a block body from a block.
 	pub fn block_to_body(block: &[u8]) -> Bytes {
 		let mut body = RlpStream::new_list(2);
@@ -1297,7 +1719,7 @@ impl BlockChain {
 	}
 
 	#[cfg(test)]
-	pub fn db(&self) -> &Arc<Database> {
+	pub fn db(&self) -> &Arc<KeyValueDB> {
 		&self.db
 	}
 }
@@ -1307,20 +1729,21 @@ mod tests {
 	#![cfg_attr(feature="dev", allow(similar_names))]
 	use std::sync::Arc;
 	use rustc_serialize::hex::FromHex;
-	use util::{Database, DatabaseConfig};
+	use util::{Address, Database, DatabaseConfig};
 	use util::hash::*;
 	use util::sha3::Hashable;
 	use receipt::Receipt;
 	use blockchain::{BlockProvider, BlockChain, Config, ImportRoute};
+	use db;
 	use tests::helpers::*;
 	use devtools::*;
 	use blockchain::generator::{ChainGenerator, ChainIterator, BlockFinalizer};
-	use blockchain::extras::TransactionAddress;
+	use blockchain::extras::{TransactionAddress, SenderTransactionKey};
 	use views::BlockView;
 	use transaction::{Transaction, Action};
 	use log_entry::{LogEntry, LocalizedLogEntry};
 
-	fn new_db(path: &str) -> Arc<Database> {
+	fn new_db(path: &str) -> Arc<KeyValueDB> {
 		Arc::new(Database::open(&DatabaseConfig::with_columns(::db::NUM_COLUMNS), path).unwrap())
 	}
 
@@ -1617,6 +2040,58 @@ mod tests {
 		}));
 	}
 
+	#[test]
+	fn should_index_transactions_by_sender() {
+		let mut canon_chain = ChainGenerator::default();
+		let mut finalizer = BlockFinalizer::default();
+		let genesis = canon_chain.generate(&mut finalizer).unwrap();
+		let mut fork_chain = canon_chain.fork(1);
+		let mut fork_finalizer = finalizer.fork();
+
+		let t1 = Transaction {
+			nonce: 0.into(),
+			gas_price: 0.into(),
+			gas: 100_000.into(),
+			action: Action::Create,
+			value: 100.into(),
+			data: vec![],
+		}.sign(&"".sha3(), None);
+		let sender = t1.sender().unwrap();
+
+		let b1a = canon_chain.with_transaction(t1.clone()).generate(&mut finalizer).unwrap();
+		let b1b = fork_chain.generate(&mut fork_finalizer).unwrap();
+		let b2 = fork_chain.with_transaction(t1.clone()).generate(&mut fork_finalizer).unwrap();
+
+		let b1a_hash = BlockView::new(&b1a).header_view().sha3();
+		let b2_hash = BlockView::new(&b2).header_view().sha3();
+
+		let temp = RandomTempPath::new();
+		let db = new_db(temp.as_str());
+		let bc = BlockChain::new(Config { sender_index: true, ..Config::default() }, &genesis, db.clone());
+		insert_block(&db, &bc, &b1a, vec![]);
+		insert_block(&db, &bc, &b1b, vec![]);
+
+		assert_eq!(bc.transactions_by_sender(&sender, 0, 10, 10), Some(vec![TransactionAddress {
+			block_hash: b1a_hash,
+			index: 0,
+		}]));
+		assert_eq!(bc.transactions_by_sender(&sender, 2, 10, 10), Some(vec![]));
+		assert_eq!(bc.transactions_by_sender(&Address::default(), 0, 10, 10), Some(vec![]));
+
+		assert_eq!(bc.transactions_by_sender(&sender, 0, 10, 0), Some(vec![]));
+
+		// retracted block is no longer reported nor kept in the index
+		insert_block(&db, &bc, &b2, vec![]);
+		assert_eq!(bc.transactions_by_sender(&sender, 0, 10, 10), Some(vec![TransactionAddress {
+			block_hash: b2_hash,
+			index: 0,
+		}]));
+		assert_eq!(db.iter_from_prefix(db::COL_EXTRA, &SenderTransactionKey::prefix(&sender)).count(), 1);
+
+		let bc = BlockChain::new(Config::default(), &genesis, db.clone());
+		assert_eq!(bc.transactions_by_sender(&sender, 0, 10, 10), None);
+	}
+
 	#[test]
 	#[cfg_attr(feature="dev", allow(cyclomatic_complexity))]
 	fn test_small_fork() {
@@ -1779,6 +2254,20 @@ mod tests {
 		assert_eq!(bc.best_block_number(), 49);
 	}
 
+	#[test]
+	fn counts_batched_cache_lookups() {
+		let bc_result = generate_dummy_blockchain(10);
+		let bc = bc_result.reference();
+		let before = bc.cache_stats().details;
+
+		for _ in 0..5 {
+			bc.block_hash(3);
+		}
+		// lookups pending in the batch are counted too.
+		let after = bc.cache_stats().details;
+		assert_eq!(after.hits + after.misses, before.hits + before.misses + 5);
+	}
+
 	#[test]
 	fn can_collect_garbage() {
 		let bc_result = generate_dummy_blockchain(3000);
@@ -1799,6 +2288,26 @@ mod tests {
 		assert!(bc.cache_size().blocks < 1024 * 1024);
 	}
 
+	#[test]
+	fn should_read_canonical_ranges() {
+		let bc_result = generate_dummy_blockchain(10);
+		let bc = bc_result.reference();
+
+		let hashes: Vec<_> = bc.canonical_iter(2, 20).collect();
+		assert_eq!(hashes.len(), 8);
+		assert_eq!(hashes[0], (2, bc.block_hash(2).unwrap()));
+		assert_eq!(hashes[7], (9, bc.best_block_hash()));
+		assert_eq!(bc.block_hashes(&[9, 3, 10]), vec![bc.block_hash(9), bc.block_hash(3), None]);
+
+		let blocks = bc.blocks_in_range(0, 20);
+		assert_eq!(blocks.len(), 10);
+		assert_eq!(blocks[4], bc.block(&bc.block_hash(4).unwrap()).unwrap());
+
+		let receipts = bc.receipts_in_range(5, 9);
+		assert_eq!(receipts.len(), 5);
+		assert_eq!(receipts[0].0, bc.block_hash(5).unwrap());
+	}
+
 	#[test]
 	fn can_contain_arbitrary_block_sequence_with_extra() {
 		let bc_result = generate_dummy_blockchain_with_extra(25);
@@ -1834,7 +2343,7 @@ mod tests {
 		}
 	}
 
-	fn insert_block(db: &Arc<Database>, bc: &BlockChain, bytes: &[u8], receipts: Vec<Receipt>) -> ImportRoute {
+	fn insert_block(db: &Arc<KeyValueDB>, bc: &BlockChain, bytes: &[u8], receipts: Vec<Receipt>) -> ImportRoute {
 		let mut batch = db.transaction();
 		let res = bc.insert_block(&mut batch, bytes, receipts);
 		db.write(batch).unwrap();
@@ -1963,6 +2472,42 @@ mod tests {
 		]);
 	}
 
+	#[test]
+	fn reads_receipts_of_matching_blocks_once() {
+		let mut canon_chain = ChainGenerator::default();
+		let mut finalizer = BlockFinalizer::default();
+		let genesis = canon_chain.generate(&mut finalizer).unwrap();
+		let t1 = Transaction {
+			nonce: 0.into(),
+			gas_price: 0.into(),
+			gas: 100_000.into(),
+			action: Action::Create,
+			value: 101.into(),
+			data: "601080600c6000396000f3006000355415600957005b60203560003555".from_hex().unwrap(),
+		}.sign(&"".sha3(), None);
+		let b1 = canon_chain.with_transaction(t1).generate(&mut finalizer).unwrap();
+		let hash = BlockView::new(&b1).hash();
+		let address = Address::from(1);
+
+		let temp = RandomTempPath::new();
+		let db = new_db(temp.as_str());
+		{
+			let bc = BlockChain::new(Config::default(), &genesis, db.clone());
+			insert_block(&db, &bc, &b1, vec![Receipt {
+				state_root: H256::default(),
+				gas_used: 10_000.into(),
+				log_bloom: Default::default(),
+				logs: vec![LogEntry { address: address.clone(), topics: vec![], data: vec![1], }],
+			}]);
+		}
+
+		let bc = BlockChain::new(Config::default(), &genesis, db.clone());
+		assert!(!bc.block_logs_may_match(&hash, |addresses, _| !addresses.contains(&address)));
+		assert!(!bc.block_receipts.read().contains_key(&hash));
+		assert!(bc.block_logs_may_match(&hash, |addresses, _| addresses.contains(&address)));
+		assert!(bc.block_receipts.read().contains_key(&hash));
+	}
+
 	#[test]
 	fn test_bloom_filter_simple() {
 		// TODO: From here
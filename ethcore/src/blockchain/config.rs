@@ -25,6 +25,8 @@ pub struct Config {
 	pub max_cache_size: usize,
 	/// Backing db cache_size
 	pub db_cache_size: Option<usize>,
+	/// Maintain an index of transactions by sender.
+	pub sender_index: bool,
 }
 
 impl Default for Config {
@@ -33,6 +35,7 @@ impl Default for Config {
 			pref_cache_size: 1 << 14,
 			max_cache_size: 1 << 20,
 			db_cache_size: None,
+			sender_index: false,
 		}
 	}
 }
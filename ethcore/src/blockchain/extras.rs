@@ -21,6 +21,7 @@ use util::*;
 use rlp::*;
 use header::BlockNumber;
 use receipt::Receipt;
+use log_entry::LogEntry;
 use db::Key;
 use blooms::{GroupPosition, BloomGroup};
 
@@ -37,6 +38,10 @@ pub enum ExtrasIndex {
 	BlocksBlooms = 3,
 	/// Block receipts index
 	BlockReceipts = 4,
+	/// Transactions by sender index
+	SenderTransaction = 5,
+	/// Block transaction senders index
+	BlockSenders = 6,
 }
 
 fn with_index(hash: &H256, i: ExtrasIndex) -> H264 {
@@ -70,6 +75,65 @@ impl Key<H256> for BlockNumber {
 	}
 }
 
+/// Key of a sender index entry: index, sender, block number, block hash and transaction index.
+///
+/// The block number is stored big-endian, so entries of a sender are ordered by block number.
+pub struct SenderTransactionKey([u8; 65]);
+
+impl SenderTransactionKey {
+	/// Creates the key of given transaction location.
+	pub fn new(sender: &Address, number: BlockNumber, block_hash: &H256, index: usize) -> Self {
+		let mut result = [0u8; 65];
+		result[..21].copy_from_slice(&Self::prefix(sender));
+		for i in 0..8 {
+			result[21 + i] = (number >> (8 * (7 - i))) as u8;
+		}
+		result[29..61].copy_from_slice(block_hash);
+		for i in 0..4 {
+			result[61 + i] = (index >> (8 * (3 - i))) as u8;
+		}
+		SenderTransactionKey(result)
+	}
+
+	/// Prefix shared by keys of all the transactions of given sender.
+	pub fn prefix(sender: &Address) -> [u8; 21] {
+		let mut result = [0u8; 21];
+		result[0] = ExtrasIndex::SenderTransaction as u8;
+		result[1..].copy_from_slice(sender);
+		result
+	}
+
+	/// Decodes block number and transaction address from a key.
+	pub fn decode(key: &[u8]) -> Option<(BlockNumber, TransactionAddress)> {
+		if key.len() != 65 || key[0] != ExtrasIndex::SenderTransaction as u8 {
+			return None;
+		}
+
+		let number = key[21..29].iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+		let index = key[61..65].iter().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+		Some((number, TransactionAddress {
+			block_hash: H256::from_slice(&key[29..61]),
+			index: index,
+		}))
+	}
+}
+
+impl Deref for SenderTransactionKey {
+	type Target = [u8];
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl Key<BlockSenders> for H256 {
+	type Target = H264;
+
+	fn key(&self) -> H264 {
+		with_index(self, ExtrasIndex::BlockSenders)
+	}
+}
+
 impl Key<BlockDetails> for H256 {
 	type Target = H264;
 
@@ -176,6 +240,28 @@ impl Encodable for BlockDetails {
 	}
 }
 
+/// Senders of the transactions of a block covered by the sender index.
+/// Kept so that the index can follow reorgs without recovering signatures again.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BlockSenders {
+	/// Transaction senders, in transaction order
+	pub senders: Vec<Address>,
+}
+
+impl Decodable for BlockSenders {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		Ok(BlockSenders {
+			senders: try!(decoder.as_rlp().as_val()),
+		})
+	}
+}
+
+impl Encodable for BlockSenders {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.append(&self.senders);
+	}
+}
+
 /// Represents address of certain transaction within block
 #[derive(Debug, PartialEq, Clone)]
 pub struct TransactionAddress {
@@ -223,17 +309,142 @@ impl BlockReceipts {
 	}
 }
 
+/// Version marker of the dictionary-compressed receipts encoding.
+///
+/// The legacy encoding is a plain list of receipts, so its first item is always a list.
+const COMPRESSED_RECEIPTS_VERSION: u8 = 1;
+
+/// Lazy view of block receipts as stored in the database.
+///
+/// Receipts are stored with every distinct log address and topic of the block written once,
+/// and logs referring to them by index. The dictionaries can be inspected without decoding
+/// any of the receipts.
+pub struct BlockReceiptsView<'a> {
+	rlp: UntrustedRlp<'a>,
+}
+
+impl<'a> BlockReceiptsView<'a> {
+	/// Creates new view over encoded block receipts.
+	pub fn new(bytes: &'a [u8]) -> Self {
+		BlockReceiptsView {
+			rlp: UntrustedRlp::new(bytes),
+		}
+	}
+
+	/// Returns true if receipts are stored using the dictionary encoding.
+	pub fn is_compressed(&self) -> bool {
+		self.rlp.item_count() == 4 && self.rlp.at(0).map(|r| r.is_data()).unwrap_or(false)
+	}
+
+	/// Addresses of all the logs in the block, or `None` for the legacy encoding.
+	pub fn addresses(&self) -> Result<Option<Vec<Address>>, DecoderError> {
+		match self.is_compressed() {
+			true => self.rlp.val_at(1).map(Some),
+			false => Ok(None),
+		}
+	}
+
+	/// Topics of all the logs in the block, or `None` for the legacy encoding.
+	pub fn topics(&self) -> Result<Option<Vec<H256>>, DecoderError> {
+		match self.is_compressed() {
+			true => self.rlp.val_at(2).map(Some),
+			false => Ok(None),
+		}
+	}
+
+	/// Decodes all the receipts.
+	pub fn receipts(&self) -> Result<Vec<Receipt>, DecoderError> {
+		if !self.is_compressed() {
+			return self.rlp.as_val();
+		}
+
+		let version: u8 = try!(self.rlp.val_at(0));
+		if version != COMPRESSED_RECEIPTS_VERSION {
+			return Err(DecoderError::Custom("Unknown receipts encoding version"));
+		}
+
+		let addresses: Vec<Address> = try!(self.rlp.val_at(1));
+		let topics: Vec<H256> = try!(self.rlp.val_at(2));
+		let out_of_range = "Receipts dictionary index out of range";
+
+		let mut receipts = Vec::new();
+		for r in try!(self.rlp.at(3)).iter() {
+			let mut logs = Vec::new();
+			for l in try!(r.at(3)).iter() {
+				let index: usize = try!(l.val_at(0));
+				let address = try!(addresses.get(index).cloned().ok_or(DecoderError::Custom(out_of_range)));
+				let mut log_topics = Vec::new();
+				for t in try!(l.at(1)).iter() {
+					let index: usize = try!(t.as_val());
+					log_topics.push(try!(topics.get(index).cloned().ok_or(DecoderError::Custom(out_of_range))));
+				}
+				logs.push(LogEntry {
+					address: address,
+					topics: log_topics,
+					data: try!(l.val_at(2)),
+				});
+			}
+
+			receipts.push(Receipt {
+				state_root: try!(r.val_at(0)),
+				gas_used: try!(r.val_at(1)),
+				log_bloom: try!(r.val_at(2)),
+				logs: logs,
+			});
+		}
+		Ok(receipts)
+	}
+}
+
 impl Decodable for BlockReceipts {
 	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let view = BlockReceiptsView::new(decoder.as_raw());
 		Ok(BlockReceipts {
-			receipts: try!(Decodable::decode(decoder))
+			receipts: try!(view.receipts()),
 		})
 	}
 }
 
 impl Encodable for BlockReceipts {
 	fn rlp_append(&self, s: &mut RlpStream) {
-		s.append(&self.receipts);
+		let mut addresses = Vec::new();
+		let mut address_indices = HashMap::new();
+		let mut topics = Vec::new();
+		let mut topic_indices = HashMap::new();
+		for log in self.receipts.iter().flat_map(|r| r.logs.iter()) {
+			address_indices.entry(log.address.clone()).or_insert_with(|| {
+				addresses.push(log.address.clone());
+				addresses.len() - 1
+			});
+			for topic in &log.topics {
+				topic_indices.entry(topic.clone()).or_insert_with(|| {
+					topics.push(topic.clone());
+					topics.len() - 1
+				});
+			}
+		}
+
+		s.begin_list(4);
+		s.append(&COMPRESSED_RECEIPTS_VERSION);
+		s.append(&addresses);
+		s.append(&topics);
+		s.begin_list(self.receipts.len());
+		for receipt in &self.receipts {
+			s.begin_list(4);
+			s.append(&receipt.state_root);
+			s.append(&receipt.gas_used);
+			s.append(&receipt.log_bloom);
+			s.begin_list(receipt.logs.len());
+			for log in &receipt.logs {
+				s.begin_list(3);
+				s.append(&address_indices[&log.address]);
+				s.begin_list(log.topics.len());
+				for topic in &log.topics {
+					s.append(&topic_indices[topic]);
+				}
+				s.append(&log.data);
+			}
+		}
 	}
 }
 
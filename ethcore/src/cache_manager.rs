@@ -16,62 +16,196 @@
 
 use std::collections::{VecDeque, HashSet};
 use std::hash::Hash;
+use types::cache_stats::CacheStats;
 
 const COLLECTION_QUEUE_SIZE: usize = 8;
+/// Smallest share of the total budget, in percent, given to any cache.
+const MIN_BUDGET_SHARE: usize = 5;
 
-pub struct CacheManager<T> where T: Eq + Hash {
+/// Item kept in one of the caches governed by a `CacheManager`.
+pub trait CacheItem: Eq + Hash {
+	/// Index of the cache holding the item.
+	fn cache(&self) -> usize { 0 }
+}
+
+struct ManagedCache<T> {
+	usage: VecDeque<HashSet<T>>,
+	/// Share of the total budget, in percent.
+	share: usize,
+	hits: u64,
+	misses: u64,
+	/// Misses since the budget was last rebalanced.
+	recent_misses: u64,
+}
+
+/// Distributes the total cache budget across several caches.
+///
+/// Every cache starts with an even share of the budget. On each collection the shares move
+/// towards the caches which missed most often since the previous collection.
+pub struct CacheManager<T> where T: CacheItem {
 	pref_cache_size: usize,
 	max_cache_size: usize,
 	bytes_per_cache_entry: usize,
-	cache_usage: VecDeque<HashSet<T>>
+	caches: Vec<ManagedCache<T>>,
 }
 
-impl<T> CacheManager<T> where T: Eq + Hash {
+impl<T> CacheManager<T> where T: CacheItem {
+	/// Creates new manager of a single cache.
 	pub fn new(pref_cache_size: usize, max_cache_size: usize, bytes_per_cache_entry: usize) -> Self {
+		Self::with_caches(pref_cache_size, max_cache_size, bytes_per_cache_entry, 1)
+	}
+
+	/// Creates new manager sharing the budget between given number of caches.
+	pub fn with_caches(pref_cache_size: usize, max_cache_size: usize, bytes_per_cache_entry: usize, caches: usize) -> Self {
+		assert!(caches > 0 && caches * MIN_BUDGET_SHARE <= 100, "Unsupported number of caches: {}", caches);
 		CacheManager {
 			pref_cache_size: pref_cache_size,
 			max_cache_size: max_cache_size,
 			bytes_per_cache_entry: bytes_per_cache_entry,
-			cache_usage: (0..COLLECTION_QUEUE_SIZE).into_iter().map(|_| Default::default()).collect(),
+			caches: (0..caches).map(|_| ManagedCache {
+				usage: (0..COLLECTION_QUEUE_SIZE).into_iter().map(|_| Default::default()).collect(),
+				share: 100 / caches,
+				hits: 0,
+				misses: 0,
+				recent_misses: 0,
+			}).collect(),
 		}
 	}
 
 	pub fn note_used(&mut self, id: T) {
-		if !self.cache_usage[0].contains(&id) {
-			if let Some(c) = self.cache_usage.iter_mut().skip(1).find(|e| e.contains(&id)) {
+		let usage = &mut self.caches[id.cache()].usage;
+		if !usage[0].contains(&id) {
+			if let Some(c) = usage.iter_mut().skip(1).find(|e| e.contains(&id)) {
 				c.remove(&id);
 			}
-			self.cache_usage[0].insert(id);
+			usage[0].insert(id);
 		}
 	}
 
-	/// Collects unused objects from cache.
-	/// First params is the current size of the cache.
-	/// Second one is an with objects to remove. It should also return new size of the cache.
-	pub fn collect_garbage<F>(&mut self, current_size: usize, mut notify_unused: F) where F: FnMut(HashSet<T>) -> usize {
-		if current_size < self.pref_cache_size {
-			self.rotate_cache_if_needed();
-			return;
+	/// Notes a lookup of given item, which was found in the cache if `hit` is true.
+	pub fn note_lookup(&mut self, id: T, hit: bool) {
+		{
+			let cache = &mut self.caches[id.cache()];
+			if hit {
+				cache.hits += 1;
+			} else {
+				cache.misses += 1;
+				cache.recent_misses += 1;
+			}
+		}
+		self.note_used(id);
+	}
+
+	/// Part of the maximal cache size currently assigned to given cache, in bytes.
+	pub fn budget(&self, cache: usize) -> usize {
+		self.max_cache_size / 100 * self.caches[cache].share
+	}
+
+	/// Usage statistics of given cache, currently holding `size` bytes.
+	pub fn stats(&self, cache: usize, size: usize) -> CacheStats {
+		CacheStats {
+			hits: self.caches[cache].hits,
+			misses: self.caches[cache].misses,
+			size: size,
+			budget: self.budget(cache),
 		}
+	}
 
-		for _ in 0..COLLECTION_QUEUE_SIZE {
-			if let Some(back) = self.cache_usage.pop_back() {
-				let current_size = notify_unused(back);
-				self.cache_usage.push_front(Default::default());
-				if current_size < self.max_cache_size {
-					break
+	/// Collects unused objects from caches.
+	/// First param holds the current size of every cache.
+	/// Second one is called with the index of a cache and objects to remove from it. It should return new size of that cache.
+	pub fn collect_garbage<F>(&mut self, current_sizes: &[usize], mut notify_unused: F) where F: FnMut(usize, HashSet<T>) -> usize {
+		self.rebalance();
+
+		for (index, current_size) in current_sizes.iter().cloned().enumerate() {
+			let share = self.caches[index].share;
+			if current_size < self.pref_cache_size / 100 * share {
+				self.rotate_cache_if_needed(index);
+				continue;
+			}
+
+			let max_cache_size = self.budget(index);
+			let usage = &mut self.caches[index].usage;
+			for _ in 0..COLLECTION_QUEUE_SIZE {
+				if let Some(back) = usage.pop_back() {
+					let current_size = notify_unused(index, back);
+					usage.push_front(Default::default());
+					if current_size < max_cache_size {
+						break
+					}
 				}
 			}
 		}
 	}
 
-	fn rotate_cache_if_needed(&mut self) {
-		if self.cache_usage.is_empty() { return }
+	/// Moves the budget towards caches with the most misses since the last call.
+	fn rebalance(&mut self) {
+		let total_misses = self.caches.iter().fold(0, |acc, c| acc + c.recent_misses);
+		if self.caches.len() < 2 || total_misses == 0 {
+			return;
+		}
+
+		let spare = (100 - MIN_BUDGET_SHARE * self.caches.len()) as u64;
+		for cache in &mut self.caches {
+			let target = MIN_BUDGET_SHARE + (spare * cache.recent_misses / total_misses) as usize;
+			// move only halfway, so a single burst of misses does not starve other caches.
+			cache.share = (cache.share + target) / 2;
+			cache.recent_misses = 0;
+		}
+	}
+
+	fn rotate_cache_if_needed(&mut self, index: usize) {
+		let pref_cache_size = self.pref_cache_size / 100 * self.caches[index].share;
+		let usage = &mut self.caches[index].usage;
+		if usage.is_empty() { return }
 
-		if self.cache_usage[0].len() * self.bytes_per_cache_entry > self.pref_cache_size / COLLECTION_QUEUE_SIZE {
-			if let Some(cache) = self.cache_usage.pop_back() {
-				self.cache_usage.push_front(cache);
+		if usage[0].len() * self.bytes_per_cache_entry > pref_cache_size / COLLECTION_QUEUE_SIZE {
+			if let Some(cache) = usage.pop_back() {
+				usage.push_front(cache);
 			}
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{CacheManager, CacheItem};
+
+	#[derive(Debug, PartialEq, Eq, Hash)]
+	struct Item(usize, u64);
+
+	impl CacheItem for Item {
+		fn cache(&self) -> usize { self.0 }
+	}
+
+	#[test]
+	fn should_move_budget_to_missing_cache() {
+		let mut manager = CacheManager::with_caches(1000, 10_000, 10, 2);
+		assert_eq!(manager.budget(0), 5_000);
+		assert_eq!(manager.budget(1), 5_000);
+
+		for i in 0..10 {
+			manager.note_lookup(Item(1, i), false);
+		}
+		manager.note_lookup(Item(0, 0), true);
+		manager.collect_garbage(&[0, 0], |_, _| 0);
+
+		assert!(manager.budget(1) > manager.budget(0));
+		assert_eq!(manager.stats(1, 0).misses, 10);
+		assert_eq!(manager.stats(0, 0).hits, 1);
+	}
+
+	#[test]
+	fn should_only_collect_caches_over_budget() {
+		let mut manager = CacheManager::with_caches(100, 1000, 10, 2);
+		manager.note_used(Item(0, 1));
+		manager.note_used(Item(1, 1));
+
+		let mut collected = vec![];
+		manager.collect_garbage(&[10, 1000], |cache, ids| {
+			collected.push((cache, ids.len()));
+			0
+		});
+		assert_eq!(collected, vec![(1, 0)]);
+	}
+}
@@ -15,7 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use ipc::IpcConfig;
-use util::H256;
+use util::{Address, H256};
 
 /// Represents what has to be handled by actor listening to chain events
 #[ipc]
@@ -31,6 +31,11 @@ pub trait ChainNotify : Send + Sync {
 		// does nothing by default
 	}
 
+	/// fires when a validator is reported for missing its slot before block `number`
+	fn benign_misbehavior(&self, _validator: Address, _number: u64) {
+		// does nothing by default
+	}
+
 	/// fires when chain achieves active mode
 	fn start(&self) {
 		// does nothing by default
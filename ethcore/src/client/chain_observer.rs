@@ -0,0 +1,246 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! In-process observers of block import, for indexing services built as extensions.
+//!
+//! Unlike `ChainNotify`, which only carries hashes, observers get full block data
+//! as soon as it is committed, so they never have to query the client back.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Weak};
+use std::sync::mpsc::{self, SyncSender, Receiver};
+use std::thread::{self, JoinHandle};
+use util::{Bytes, H256, Mutex, RwLock};
+use blockchain::{BlockChain, BlockProvider, ImportRoute};
+use header::BlockNumber;
+use receipt::Receipt;
+use trace::FlatBlockTraces;
+use types::state_diff::StateDiff;
+use views::BlockView;
+
+/// Notifications queued for the observer thread before import blocks.
+const MAX_PENDING_NOTIFICATIONS: usize = 1024;
+/// State diffs of this many recently imported blocks are kept to be given with their retraction.
+const STATE_DIFFS_KEPT: usize = 128;
+
+/// Data of a block entering or leaving the chain.
+#[derive(Debug, Clone)]
+pub struct ObservedBlock {
+	/// Block hash.
+	pub hash: H256,
+	/// Block number.
+	pub number: BlockNumber,
+	/// Encoded block.
+	pub block: Bytes,
+	/// Receipts of the block transactions.
+	pub receipts: Vec<Receipt>,
+	/// Traces of the block transactions, if tracing is enabled.
+	pub traces: Option<FlatBlockTraces>,
+	/// Accounts changed by the block, when an observer asks for them. Given for imported
+	/// blocks and for retracted ones imported recently enough for the diff to be kept.
+	pub state_diff: Option<StateDiff>,
+}
+
+impl ObservedBlock {
+	/// Data of a block stored in the chain, without traces and state diff.
+	pub fn from_chain(chain: &BlockChain, hash: &H256) -> Option<ObservedBlock> {
+		chain.block(hash).map(|block| ObservedBlock {
+			hash: hash.clone(),
+			number: BlockView::new(&block).header_view().number(),
+			receipts: chain.block_receipts(hash).map_or_else(Vec::new, |r| r.receipts),
+			traces: None,
+			state_diff: None,
+			block: block,
+		})
+	}
+}
+
+/// Receives data of every imported and retracted block.
+///
+/// Calls are made from a dedicated thread, in import order. Import only waits for
+/// observers when they fall more than `MAX_PENDING_NOTIFICATIONS` blocks behind.
+pub trait ChainObserver: Send + Sync {
+	/// Whether state diffs of imported blocks are needed. Computing them slows import down.
+	fn wants_state_diffs(&self) -> bool {
+		false
+	}
+
+	/// A block has been imported. `route` tells which blocks it made canonical, if any.
+	/// Blocks it retracted are reported to `block_retracted` first.
+	fn block_imported(&self, _block: &ObservedBlock, _route: &ImportRoute) {
+		// does nothing by default
+	}
+
+	/// A block has left the canonical chain.
+	fn block_retracted(&self, _block: &ObservedBlock) {
+		// does nothing by default
+	}
+
+	/// A canonical block has been imported out of order, either restored from a snapshot
+	/// or downloaded as an ancient block. Such blocks come without traces and state diff.
+	fn ancient_block_imported(&self, _block: &ObservedBlock) {
+		// does nothing by default
+	}
+}
+
+enum Notification {
+	Imported {
+		block: ObservedBlock,
+		route: ImportRoute,
+		retracted: Vec<ObservedBlock>,
+	},
+	Ancient(ObservedBlock),
+	Restored {
+		chain: Arc<BlockChain>,
+		from: BlockNumber,
+		to: BlockNumber,
+	},
+	Flush(mpsc::Sender<()>),
+}
+
+/// Hands block data over to the observers on a thread of its own.
+pub struct ObserverDispatcher {
+	observers: Arc<RwLock<Vec<Weak<ChainObserver>>>>,
+	sender: Mutex<Option<SyncSender<Notification>>>,
+	thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ObserverDispatcher {
+	/// Create a dispatcher. The thread is only started with the first observer.
+	pub fn new() -> Self {
+		ObserverDispatcher {
+			observers: Arc::new(RwLock::new(Vec::new())),
+			sender: Mutex::new(None),
+			thread: Mutex::new(None),
+		}
+	}
+
+	/// Add an observer.
+	pub fn add(&self, observer: Arc<ChainObserver>) {
+		self.observers.write().push(Arc::downgrade(&observer));
+
+		let mut sender = self.sender.lock();
+		if sender.is_some() {
+			return;
+		}
+		let (tx, rx) = mpsc::sync_channel(MAX_PENDING_NOTIFICATIONS);
+		let observers = self.observers.clone();
+		match thread::Builder::new().name("Chain Observers".into()).spawn(move || dispatch(observers, rx)) {
+			Ok(handle) => {
+				*sender = Some(tx);
+				*self.thread.lock() = Some(handle);
+			},
+			Err(e) => warn!(target: "client", "Unable to start chain observers thread: {}", e),
+		}
+	}
+
+	/// Whether any observer is still alive.
+	pub fn is_observed(&self) -> bool {
+		self.sender.lock().is_some() && self.observers.read().iter().any(|o| o.upgrade().is_some())
+	}
+
+	/// Whether any observer wants state diffs of imported blocks.
+	pub fn wants_state_diffs(&self) -> bool {
+		self.observers.read().iter().filter_map(|o| o.upgrade()).any(|o| o.wants_state_diffs())
+	}
+
+	/// Report an imported block along with the blocks it retracted.
+	pub fn imported(&self, block: ObservedBlock, route: ImportRoute, retracted: Vec<ObservedBlock>) {
+		self.send(Notification::Imported { block: block, route: route, retracted: retracted });
+	}
+
+	/// Report a block imported out of order.
+	pub fn ancient(&self, block: ObservedBlock) {
+		self.send(Notification::Ancient(block));
+	}
+
+	/// Report the canonical blocks `from..to` (inclusive) of a chain restored from a snapshot.
+	pub fn restored(&self, chain: Arc<BlockChain>, from: BlockNumber, to: BlockNumber) {
+		self.send(Notification::Restored { chain: chain, from: from, to: to });
+	}
+
+	/// Wait until all reported blocks have been given to the observers.
+	pub fn flush(&self) {
+		let (tx, rx) = mpsc::channel();
+		if self.send(Notification::Flush(tx)) {
+			let _ = rx.recv();
+		}
+	}
+
+	fn send(&self, notification: Notification) -> bool {
+		// clone the sender so that a full queue doesn't block other reporters on the lock.
+		let sender = self.sender.lock().clone();
+		match sender {
+			Some(sender) => sender.send(notification).is_ok(),
+			None => false,
+		}
+	}
+}
+
+impl Drop for ObserverDispatcher {
+	fn drop(&mut self) {
+		// closing the channel ends the thread once the pending notifications are delivered.
+		self.sender.lock().take();
+		if let Some(handle) = self.thread.lock().take() {
+			let _ = handle.join();
+		}
+	}
+}
+
+fn dispatch(observers: Arc<RwLock<Vec<Weak<ChainObserver>>>>, notifications: Receiver<Notification>) {
+	let mut state_diffs: VecDeque<(H256, StateDiff)> = VecDeque::new();
+	for notification in notifications.iter() {
+		let observers: Vec<Arc<ChainObserver>> = observers.read().iter().filter_map(|o| o.upgrade()).collect();
+		match notification {
+			Notification::Imported { block, route, mut retracted } => {
+				for old in retracted.iter_mut().filter(|old| old.state_diff.is_none()) {
+					old.state_diff = state_diffs.iter().find(|&&(ref h, _)| h == &old.hash).map(|&(_, ref diff)| diff.clone());
+				}
+				for observer in &observers {
+					for old in &retracted {
+						observer.block_retracted(old);
+					}
+					observer.block_imported(&block, &route);
+				}
+				if let Some(ref diff) = block.state_diff {
+					state_diffs.push_back((block.hash.clone(), diff.clone()));
+					if state_diffs.len() > STATE_DIFFS_KEPT {
+						state_diffs.pop_front();
+					}
+				}
+			},
+			Notification::Ancient(block) => {
+				for observer in &observers {
+					observer.ancient_block_imported(&block);
+				}
+			},
+			Notification::Restored { chain, from, to } => {
+				for number in from..(to + 1) {
+					let block = match chain.block_hash(number).and_then(|h| ObservedBlock::from_chain(&chain, &h)) {
+						Some(block) => block,
+						None => continue,
+					};
+					for observer in &observers {
+						observer.ancient_block_imported(&block);
+					}
+				}
+			},
+			Notification::Flush(done) => {
+				let _ = done.send(());
+			},
+		}
+	}
+}
@@ -25,14 +25,15 @@ use time::precise_time_ns;
 use util::{Bytes, PerfTimer, Itertools, Mutex, RwLock, Hashable};
 use util::{journaldb, TrieFactory, Trie};
 use util::trie::TrieSpec;
-use util::{U256, H256, Address, H2048, Uint, FixedHash};
+use util::{U256, H256, Address, H2048, Uint, FixedHash, OutOfBounds};
 use util::kvdb::*;
+use util::metrics::{metrics, DEFAULT_BUCKETS};
 
 // other
 use io::*;
 use views::{HeaderView, BodyView, BlockView};
 use error::{ImportError, ExecutionError, CallError, BlockError, ImportResult, Error as EthcoreError};
-use header::BlockNumber;
+use header::{BlockNumber, Header};
 use state::{State, CleanupMode};
 use spec::Spec;
 use basic_types::Seal;
@@ -46,24 +47,34 @@ use transaction::{LocalizedTransaction, SignedTransaction, Action};
 use blockchain::extras::TransactionAddress;
 use types::filter::Filter;
 use types::mode::Mode as IpcMode;
+use types::database_stats::ColumnStats as DatabaseColumnStats;
+use types::memory_usage::MemoryUsage;
+use types::cache_stats::BlockChainCacheStats;
+use types::block_rewards::BlockRewards;
+use types::chain_spec_info::ChainSpecInfo;
+use types::pow_cache_status::PowCacheStatus;
+use types::validator_uptime::ValidatorUptime;
+use types::log_proof::LogProof;
 use log_entry::LocalizedLogEntry;
 use verification::queue::BlockQueue;
 use blockchain::{BlockChain, BlockProvider, TreeRoute, ImportRoute};
 use client::{
 	BlockID, TransactionID, UncleID, TraceId, ClientConfig, BlockChainClient,
 	MiningBlockChainClient, TraceFilter, CallAnalytics, BlockImportError, Mode,
-	ChainNotify,
+	ChainNotify, ChainObserver, ObservedBlock, StateOverride,
 };
+use client::chain_observer::ObserverDispatcher;
 use client::Error as ClientError;
 use env_info::EnvInfo;
 use executive::{Executive, Executed, TransactOptions, contract_address};
-use receipt::LocalizedReceipt;
+use receipt::{Receipt, LocalizedReceipt};
 use trace::{TraceDB, ImportRequest as TraceImportRequest, LocalizedTrace, Database as TraceDatabase};
 use trace;
 use trace::FlatTransactionTraces;
 use evm::{Factory as EvmFactory, Schedule};
 use miner::{Miner, MinerService};
 use snapshot::{self, io as snapshot_io};
+use client::finality::RollingFinality;
 use factory::Factories;
 use rlp::{decode, View, UntrustedRlp};
 use state_db::StateDB;
@@ -78,6 +89,8 @@ pub use verification::queue::QueueInfo as BlockQueueInfo;
 const MAX_TX_QUEUE_SIZE: usize = 4096;
 const MAX_QUEUE_SIZE_TO_SLEEP_ON: usize = 2;
 const MIN_HISTORY_SIZE: u64 = 8;
+/// Maximum number of blocks walked back from the best block to restore finality.
+const FINALITY_ANCESTRY: usize = 4096;
 
 impl fmt::Display for BlockChainInfo {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -121,6 +134,15 @@ impl SleepState {
 	}
 }
 
+/// Imported blocks which are buffered but not flushed to disk yet.
+#[derive(Default)]
+struct PendingCommit {
+	/// Number of blocks imported since the last flush.
+	blocks: usize,
+	/// Eras to be marked canonical right before the next flush.
+	eras: Vec<(u64, H256)>,
+}
+
 /// Blockchain database client backed by a persistent database. Owns and manages a blockchain and a block queue.
 /// Call `import_block()` to import a block asynchronously; `flush_queue()` flushes the queue.
 pub struct Client {
@@ -130,11 +152,12 @@ pub struct Client {
 	engine: Arc<Engine>,
 	config: ClientConfig,
 	pruning: journaldb::Algorithm,
-	db: RwLock<Arc<Database>>,
+	db: RwLock<Arc<KeyValueDB>>,
 	state_db: Mutex<StateDB>,
 	block_queue: BlockQueue,
 	report: RwLock<ClientReport>,
 	import_lock: Mutex<()>,
+	pending_commit: Mutex<PendingCommit>,
 	panic_handler: Arc<PanicHandler>,
 	verifier: Box<Verifier>,
 	miner: Arc<Miner>,
@@ -142,12 +165,15 @@ pub struct Client {
 	liveness: AtomicBool,
 	io_channel: Mutex<IoChannel<ClientIoMessage>>,
 	notify: RwLock<Vec<Weak<ChainNotify>>>,
+	observers: ObserverDispatcher,
 	queue_transactions: AtomicUsize,
 	last_hashes: RwLock<VecDeque<H256>>,
 	factories: Factories,
 	history: u64,
 	rng: Mutex<OsRng>,
 	on_mode_change: Mutex<Option<Box<FnMut(&Mode) + 'static + Send>>>,
+	checkpoints: BTreeMap<BlockNumber, H256>,
+	finality: Mutex<Option<RollingFinality>>,
 }
 
 impl Client {
@@ -161,9 +187,19 @@ impl Client {
 		db_config: &DatabaseConfig,
 	) -> Result<Arc<Client>, ClientError> {
 		let path = path.to_path_buf();
-		let gb = spec.genesis_block();
-
 		let db = Arc::new(try!(Database::open(&db_config, &path.to_str().expect("DB path could not be converted to string.")).map_err(ClientError::Database)));
+		Client::with_db(config, spec, db, miner, message_channel)
+	}
+
+	/// Create a new client with given spec on top of an already opened database.
+	pub fn with_db(
+		config: ClientConfig,
+		spec: &Spec,
+		db: Arc<KeyValueDB>,
+		miner: Arc<Miner>,
+		message_channel: IoChannel<ClientIoMessage>,
+	) -> Result<Arc<Client>, ClientError> {
+		let gb = spec.genesis_block();
 		let chain = Arc::new(BlockChain::new(config.blockchain.clone(), &gb, db.clone()));
 		let tracedb = RwLock::new(TraceDB::new(config.tracing.clone(), db.clone(), chain.clone()));
 
@@ -176,7 +212,7 @@ impl Client {
 		let journal_db = journaldb::new(db.clone(), config.pruning, ::db::COL_STATE);
 		let mut state_db = StateDB::new(journal_db, config.state_cache_size);
 		if state_db.journal_db().is_empty() && try!(spec.ensure_db_good(&mut state_db, &trie_factory)) {
-			let mut batch = DBTransaction::new(&db);
+			let mut batch = DBTransaction::new();
 			try!(state_db.journal_under(&mut batch, 0, &spec.genesis_header().hash()));
 			try!(db.write(batch).map_err(ClientError::Database));
 		}
@@ -196,7 +232,7 @@ impl Client {
 			if latest > earliest && latest - earliest > history {
 				for era in earliest..(latest - history + 1) {
 					trace!("Removing era {}", era);
-					let mut batch = DBTransaction::new(&db);
+					let mut batch = DBTransaction::new();
 					try!(state_db.mark_canonical(&mut batch, era, &chain.block_hash(era).expect("Old block not found in the database")));
 					try!(db.write(batch).map_err(ClientError::Database));
 				}
@@ -209,7 +245,14 @@ impl Client {
 
 		let engine = spec.engine.clone();
 
+		let finality = engine.validators().map(|validators| {
+			let mut finality = RollingFinality::new(validators);
+			Client::build_finality(&mut finality, &chain);
+			finality
+		});
+
 		let block_queue = BlockQueue::new(config.queue.clone(), engine.clone(), message_channel.clone(), config.verifier_type.verifying_seal());
+		block_queue.set_best_block(chain.best_block_hash());
 		let panic_handler = PanicHandler::new_in_arc();
 		panic_handler.forward_from(&block_queue);
 
@@ -236,16 +279,20 @@ impl Client {
 			block_queue: block_queue,
 			report: RwLock::new(Default::default()),
 			import_lock: Mutex::new(()),
+			pending_commit: Mutex::new(Default::default()),
 			panic_handler: panic_handler,
 			miner: miner,
 			io_channel: Mutex::new(message_channel),
 			notify: RwLock::new(Vec::new()),
+			observers: ObserverDispatcher::new(),
 			queue_transactions: AtomicUsize::new(0),
 			last_hashes: RwLock::new(VecDeque::new()),
 			factories: factories,
 			history: history,
 			rng: Mutex::new(try!(OsRng::new().map_err(::util::UtilError::StdIo))),
 			on_mode_change: Mutex::new(None),
+			checkpoints: spec.checkpoints.clone(),
+			finality: Mutex::new(finality),
 		};
 		Ok(Arc::new(client))
 	}
@@ -263,6 +310,16 @@ impl Client {
 		}
 	}
 
+	/// Adds an observer to be given data of every imported and retracted block.
+	pub fn add_observer(&self, target: Arc<ChainObserver>) {
+		self.observers.add(target);
+	}
+
+	/// Waits until the observers have been given all blocks imported so far.
+	pub fn flush_observers(&self) {
+		self.observers.flush();
+	}
+
 	/// Register an action to be done if a mode change happens.
 	pub fn on_mode_change<F>(&self, f: F) where F: 'static + FnMut(&Mode) + Send {
 		*self.on_mode_change.lock() = Some(Box::new(f));
@@ -276,6 +333,43 @@ impl Client {
 		}
 	}
 
+	/// Import blocks remaining in the queue and commit buffered database writes.
+	pub fn flush_to_disk(&self) -> Result<(), String> {
+		self.flush_queue();
+		self.flush_pending_commit()
+	}
+
+	/// Whether marking eras canonical may be delayed until the next flush. Only the journal
+	/// of `OverlayRecent` knows its earliest era, so that eras left behind by an unclean
+	/// exit are marked canonical on the next start.
+	fn delays_canonicalization(&self) -> bool {
+		self.config.commit_batch > 0 && self.pruning == journaldb::Algorithm::OverlayRecent
+	}
+
+	/// Mark delayed eras canonical and flush all buffered changes to disk in a single batch.
+	fn flush_pending_commit(&self) -> Result<(), String> {
+		{
+			let _import_lock = self.import_lock.lock();
+			let eras = {
+				let mut pending = self.pending_commit.lock();
+				pending.blocks = 0;
+				::std::mem::replace(&mut pending.eras, Vec::new())
+			};
+
+			if !eras.is_empty() {
+				let mut batch = DBTransaction::new();
+				{
+					let mut state_db = self.state_db.lock();
+					for (era, hash) in eras {
+						state_db.mark_canonical(&mut batch, era, &hash).expect("DB commit failed");
+					}
+				}
+				self.db.read().write_buffered(batch);
+			}
+		}
+		self.db.read().flush()
+	}
+
 	/// The env info as of the best block.
 	fn latest_env_info(&self) -> EnvInfo {
 		let header_data = self.best_block_header();
@@ -330,6 +424,11 @@ impl Client {
 			return Err(());
 		}
 
+		if let Err(e) = self.check_reorg_depth(&chain, header) {
+			warn!(target: "client", "Block import failed for #{} ({})\nError: {:?}", header.number(), header.hash(), e);
+			return Err(());
+		}
+
 		// Verify Block Family
 		let verify_family_result = self.verifier.verify_block_family(header, &block.bytes, engine, &**chain);
 		if let Err(e) = verify_family_result {
@@ -344,7 +443,12 @@ impl Client {
 			let last_hashes = self.build_last_hashes(header.parent_hash().clone());
 			let db = self.state_db.lock().boxed_clone_canon(header.parent_hash());
 
-			let enact_result = enact_verified(block, engine, self.tracedb.read().tracing_enabled(), db, &parent, last_hashes, self.factories.clone());
+			let execution = match (self.config.parallel_execution, self.config.pipeline_state_root) {
+				(true, _) => ExecutionMode::Parallel,
+				(false, true) => ExecutionMode::Pipelined,
+				(false, false) => ExecutionMode::Sequential,
+			};
+			let enact_result = enact_verified(block, engine, self.tracedb.read().tracing_enabled(), db, &parent, last_hashes, self.factories.clone(), execution);
 			let locked_block = try!(enact_result.map_err(|e| {
 				warn!(target: "client", "Block import failed for #{} ({})\nError: {:?}", header.number(), header.hash(), e);
 			}));
@@ -362,6 +466,35 @@ impl Client {
 		}
 	}
 
+	/// Check that `header` does not fork off the canonical chain deeper than the configured maximum.
+	fn check_reorg_depth(&self, chain: &BlockChain, header: &Header) -> Result<(), BlockError> {
+		let max_depth = match self.config.max_reorg_depth {
+			Some(max_depth) => max_depth,
+			None => return Ok(()),
+		};
+		let parent = header.parent_hash();
+		let parent_number = match chain.block_number(parent) {
+			Some(number) => number,
+			// unknown parents are reported by the caller.
+			None => return Ok(()),
+		};
+		let fork_number = if chain.block_hash(parent_number).as_ref() == Some(parent) {
+			parent_number
+		} else {
+			let route = chain.tree_route(chain.best_block_hash(), parent.clone());
+			chain.block_number(&route.ancestor).unwrap_or(0)
+		};
+
+		let lowest = ::std::cmp::max(
+			chain.best_block_number().saturating_sub(max_depth),
+			self.finalized_block().map_or(0, |(number, _)| number),
+		);
+		if fork_number < lowest {
+			return Err(BlockError::ReorgTooDeep(OutOfBounds { min: Some(lowest), max: None, found: fork_number }));
+		}
+		Ok(())
+	}
+
 	fn calculate_enacted_retracted(&self, import_results: &[ImportRoute]) -> (Vec<H256>, Vec<H256>) {
 		fn map_to_vec(map: Vec<(H256, bool)>) -> Vec<H256> {
 			map.into_iter().map(|(k, _v)| k).collect()
@@ -404,6 +537,7 @@ impl Client {
 			let _timer = PerfTimer::new("import_verified_blocks");
 			let start = precise_time_ns();
 
+			let import_time = metrics().histogram("parity_block_import_duration_seconds", "Time taken to execute and commit a single block.", &[], DEFAULT_BUCKETS);
 			for block in blocks {
 				let header = &block.header;
 				let is_invalid = invalid_blocks.contains(header.parent_hash());
@@ -411,6 +545,7 @@ impl Client {
 					invalid_blocks.insert(header.hash());
 					continue;
 				}
+				let block_start = precise_time_ns();
 				if let Ok(closed_block) = self.check_and_close_block(&block) {
 					imported_blocks.push(header.hash());
 
@@ -418,6 +553,7 @@ impl Client {
 					import_results.push(route);
 
 					self.report.write().accrue_block(&block);
+					import_time.observe((precise_time_ns() - block_start) as f64 / 1_000_000_000f64);
 				} else {
 					invalid_blocks.insert(header.hash());
 				}
@@ -429,6 +565,9 @@ impl Client {
 			if !invalid_blocks.is_empty() {
 				self.block_queue.mark_as_bad(&invalid_blocks);
 			}
+			if !imported_blocks.is_empty() {
+				self.block_queue.set_best_block(self.chain.read().best_block_hash());
+			}
 			let is_empty = self.block_queue.mark_as_good(&imported_blocks);
 			let duration_ns = precise_time_ns() - start;
 			(imported_blocks, import_results, invalid_blocks, imported, duration_ns, is_empty)
@@ -455,7 +594,15 @@ impl Client {
 			}
 		}
 
-		self.db.read().flush().expect("DB flush failed.");
+		// during a major sync, keep blocks buffered until there are enough of them.
+		let flush = {
+			let mut pending = self.pending_commit.lock();
+			pending.blocks += imported;
+			is_empty || pending.blocks >= self.config.commit_batch
+		};
+		if flush {
+			self.flush_pending_commit().expect("DB flush failed.");
+		}
 		imported
 	}
 
@@ -483,12 +630,26 @@ impl Client {
 			));
 
 			// Commit results
-			let receipts = ::rlp::decode(&receipts_bytes);
-			let mut batch = DBTransaction::new(&self.db.read());
+			let receipts: Vec<Receipt> = ::rlp::decode(&receipts_bytes);
+			let observed = match self.observers.is_observed() {
+				true => Some(ObservedBlock {
+					hash: hash.clone(),
+					number: header.number(),
+					block: block_bytes.clone(),
+					receipts: receipts.clone(),
+					traces: None,
+					state_diff: None,
+				}),
+				false => None,
+			};
+			let mut batch = DBTransaction::new();
 			chain.insert_unordered_block(&mut batch, &block_bytes, receipts, None, false, true);
 			// Final commit to the DB
 			self.db.read().write_buffered(batch);
 			chain.commit();
+			if let Some(observed) = observed {
+				self.observers.ancient(observed);
+			}
 		}
 		self.db.read().flush().expect("DB flush failed.");
 		Ok(hash)
@@ -508,7 +669,15 @@ impl Client {
 
 		//let traces = From::from(block.traces().clone().unwrap_or_else(Vec::new));
 
-		let mut batch = DBTransaction::new(&self.db.read());
+		let observed = match self.observers.is_observed() {
+			true => Some(self.observed_import(&chain, &block, hash, block_data, &traces)),
+			false => None,
+		};
+
+		let senders = block.transactions().iter().map(SignedTransaction::sender).collect::<Result<Vec<_>, _>>().ok();
+		let header = block.header().clone();
+
+		let mut batch = DBTransaction::new();
 		// CHECK! I *think* this is fine, even if the state_root is equal to another
 		// already-imported block of the same number.
 		// TODO: Prove it with a test.
@@ -519,13 +688,17 @@ impl Client {
 		if number >= self.history {
 			let n = number - self.history;
 			if let Some(ancient_hash) = chain.block_hash(n) {
-				state.mark_canonical(&mut batch, n, &ancient_hash).expect("DB commit failed");
+				if self.delays_canonicalization() {
+					self.pending_commit.lock().eras.push((n, ancient_hash));
+				} else {
+					state.mark_canonical(&mut batch, n, &ancient_hash).expect("DB commit failed");
+				}
 			} else {
 				debug!(target: "client", "Missing expected hash for block {}", n);
 			}
 		}
 
-		let route = chain.insert_block(&mut batch, block_data, receipts);
+		let route = chain.insert_block_with_senders(&mut batch, block_data, receipts, senders);
 		self.tracedb.read().import(&mut batch, TraceImportRequest {
 			traces: traces.into(),
 			block_hash: hash.clone(),
@@ -540,9 +713,82 @@ impl Client {
 		self.db.read().write_buffered(batch);
 		chain.commit();
 		self.update_last_hashes(&parent, hash);
+		self.update_finality(&chain, &route);
+		if is_canon {
+			if let Some(parent_header) = chain.block_header(&parent) {
+				self.engine.on_block_committed(&header, &parent_header);
+			}
+		}
+
+		if let Some(imported) = observed {
+			let retracted = route.retracted.iter().filter_map(|h| self.observed_block(&chain, h)).collect::<Vec<_>>();
+			drop(chain);
+			self.observers.imported(imported, route.clone(), retracted);
+		}
 		route
 	}
 
+	// data of a block about to be committed, for the observers.
+	fn observed_import<B>(&self, chain: &BlockChain, block: &B, hash: &H256, block_data: &[u8], traces: &[FlatTransactionTraces]) -> ObservedBlock where B: IsBlock {
+		let state_diff = match self.observers.wants_state_diffs() {
+			true => chain.block_header(block.header().parent_hash())
+				.and_then(|parent| State::from_existing(self.state_db.lock().boxed_clone(), parent.state_root().clone(), self.engine.account_start_nonce(), self.factories.clone()).ok())
+				.map(|pre| block.state().diff_from(pre)),
+			false => None,
+		};
+
+		ObservedBlock {
+			hash: hash.clone(),
+			number: block.header().number(),
+			block: block_data.to_vec(),
+			receipts: block.receipts().to_owned(),
+			traces: block.traces().as_ref().map(|_| traces.to_vec().into()),
+			state_diff: state_diff,
+		}
+	}
+
+	// data of a block already in the database, for the observers.
+	fn observed_block(&self, chain: &BlockChain, hash: &H256) -> Option<ObservedBlock> {
+		ObservedBlock::from_chain(chain, hash).map(|block| ObservedBlock {
+			traces: self.tracedb.read().traces(hash),
+			..block
+		})
+	}
+
+	fn update_finality(&self, chain: &BlockChain, route: &ImportRoute) {
+		let mut finality = self.finality.lock();
+		let finality = match *finality {
+			Some(ref mut finality) => finality,
+			None => return,
+		};
+		if !route.retracted.is_empty() {
+			Client::build_finality(finality, chain);
+			return;
+		}
+		for hash in &route.enacted {
+			if let Some(header) = chain.block_header(hash) {
+				for finalized in finality.push(hash.clone(), header.number(), header.author().clone()) {
+					trace!(target: "client", "Block {} is final", finalized);
+				}
+			}
+		}
+	}
+
+	/// Restore finality from the ancestry of the best block.
+	fn build_finality(finality: &mut RollingFinality, chain: &BlockChain) {
+		let ancestry = chain.ancestry_iter(chain.best_block_hash()).into_iter()
+			.flat_map(|iter| iter)
+			.take(FINALITY_ANCESTRY)
+			.filter_map(|hash| chain.block_header(&hash).map(|header| (hash, header.number(), header.author().clone())))
+			.take_while(|&(_, number, _)| number > 0);
+		finality.build_ancestry(ancestry);
+	}
+
+	/// Number and hash of the latest block finalized by the consensus engine, if it has a notion of finality.
+	pub fn finalized_block(&self) -> Option<(BlockNumber, H256)> {
+		self.finality.lock().as_ref().and_then(|finality| finality.last_finalized())
+	}
+
 	fn update_last_hashes(&self, parent: &H256, hash: &H256) {
 		let mut hashes = self.last_hashes.write();
 		if hashes.front().map_or(false, |h| h == parent) {
@@ -568,6 +814,21 @@ impl Client {
 		self.miner.update_sealing(self)
 	}
 
+	/// Flush and compact the database. Blocks until compaction is finished.
+	pub fn compact_database(&self) {
+		let db = self.db.read().clone();
+		info!(target: "client", "Compacting database");
+		match db.compact() {
+			Ok(()) => info!(target: "client", "Database compaction finished"),
+			Err(e) => warn!(target: "client", "Database compaction failed: {}", e),
+		}
+	}
+
+	/// Pass on the engine's report of a validator which missed its slot before block `number`.
+	pub fn report_benign(&self, validator: &Address, number: BlockNumber) {
+		self.notify(|notify| notify.benign_misbehavior(validator.clone(), number));
+	}
+
 	/// Attempt to get a copy of a specific block's final state.
 	///
 	/// This will not fail if given BlockID::Latest.
@@ -599,6 +860,28 @@ impl Client {
 		})
 	}
 
+	/// Number of the earliest block whose state is available.
+	pub fn earliest_state(&self) -> BlockNumber {
+		let best_block_number = self.chain.read().best_block_number();
+		let earliest = match self.state_db.lock().is_pruned() {
+			true if best_block_number >= self.history => best_block_number - self.history + 1,
+			_ => 0,
+		};
+		::std::cmp::max(earliest, self.chain.read().first_block_number().unwrap_or(0))
+	}
+
+	/// Prove an account, and optionally one of its storage items, in the state after
+	/// the given block. See `State::prove`. Returns `None` if the state is unavailable.
+	pub fn prove_state(&self, id: BlockID, account_key: &H256, storage_key: Option<&H256>, from_level: u32) -> Option<Vec<Bytes>> {
+		self.state_at(id).and_then(|state| state.prove(account_key, storage_key, from_level).ok())
+	}
+
+	/// Get the code of an account by the hash of its address, in the state after the given block.
+	/// Returns `None` if the state is unavailable or the account doesn't exist.
+	pub fn code_by_address_hash(&self, id: BlockID, account_key: &H256) -> Option<Bytes> {
+		self.state_at(id).and_then(|state| state.code_by_address_hash(account_key).ok().and_then(|code| code))
+	}
+
 	/// Attempt to get a copy of a specific block's beginning state.
 	///
 	/// This will not fail if given BlockID::Latest.
@@ -631,6 +914,12 @@ impl Client {
 		self.chain.read().cache_size()
 	}
 
+	/// Get canonical blocks with numbers from `from` to `to` inclusive, reading them in batches.
+	/// Stops at the first missing block.
+	pub fn blocks_in_range(&self, from: BlockNumber, to: BlockNumber) -> Vec<Bytes> {
+		self.chain.read().blocks_in_range(from, to)
+	}
+
 	/// Get the report.
 	pub fn report(&self) -> ClientReport {
 		let mut report = self.report.read().clone();
@@ -638,6 +927,65 @@ impl Client {
 		report
 	}
 
+	/// Pass measured drift of the local clock (in ms) to the engine.
+	pub fn set_clock_drift(&self, drift_ms: i64) {
+		self.engine.set_clock_drift(drift_ms);
+	}
+
+	/// Get the consensus engine.
+	pub fn engine(&self) -> &Engine {
+		&*self.engine
+	}
+
+	/// Get the miner.
+	pub fn miner(&self) -> Arc<Miner> {
+		self.miner.clone()
+	}
+
+	/// Seal a block if the miner's seal interval has passed.
+	pub fn seal_interval_tick(&self) {
+		self.miner.seal_interval_tick(self);
+	}
+
+	/// Environment for non-persistent calls on top of given block.
+	fn call_env_info(&self, block: BlockID) -> Result<EnvInfo, CallError> {
+		let header = try!(self.block_header(block).ok_or(CallError::StatePruned));
+		let view = HeaderView::new(&header);
+		let last_hashes = self.build_last_hashes(view.parent_hash());
+		Ok(EnvInfo {
+			number: view.number(),
+			author: view.author(),
+			timestamp: view.timestamp(),
+			difficulty: view.difficulty(),
+			last_hashes: last_hashes,
+			gas_used: U256::zero(),
+			gas_limit: U256::max_value(),
+		})
+	}
+
+	/// Execute a transaction without persisting the result, giving the sender enough balance to pay for it.
+	fn call_in_state(&self, state: &mut State, env_info: &EnvInfo, t: &SignedTransaction, analytics: CallAnalytics) -> Result<Executed, CallError> {
+		let original_state = if analytics.state_diffing { Some(state.clone()) } else { None };
+
+		let sender = try!(t.sender().map_err(|e| {
+			let message = format!("Transaction malformed: {:?}", e);
+			ExecutionError::TransactionMalformed(message)
+		}));
+		let balance = state.balance(&sender);
+		let needed_balance = t.value + t.gas * t.gas_price;
+		if balance < needed_balance {
+			// give the sender a sufficient balance
+			state.add_balance(&sender, &(needed_balance - balance), CleanupMode::NoEmpty);
+		}
+		let options = TransactOptions { tracing: analytics.transaction_tracing, vm_tracing: analytics.vm_tracing, check_nonce: false, defer_fees: false };
+		let mut ret = try!(Executive::new(state, env_info, &*self.engine, &self.factories.vm).transact(t, options));
+
+		// TODO gav move this into Executive.
+		ret.state_diff = original_state.map(|original| state.diff_from(original));
+
+		Ok(ret)
+	}
+
 	/// Tick the client.
 	// TODO: manage by real events.
 	pub fn tick(&self) {
@@ -685,6 +1033,7 @@ impl Client {
 			BlockID::Hash(ref hash) => self.chain.read().block_number(hash),
 			BlockID::Earliest => Some(0),
 			BlockID::Latest | BlockID::Pending => Some(self.chain.read().best_block_number()),
+			BlockID::Finalized => self.finalized_block().map(|(number, _)| number),
 		}
 	}
 
@@ -724,24 +1073,34 @@ impl Client {
 		Ok(())
 	}
 
+	/// Check that the state under `root` is complete and that every node hashes to its key.
+	/// Aborts early if `flag` becomes false.
+	pub fn verify_state(&self, root: &H256, p: &snapshot::Progress, flag: &AtomicBool) -> Result<(), EthcoreError> {
+		let db = self.state_db.lock().journal_db().boxed_clone();
+		try!(snapshot::verify_state(db.as_hashdb(), root, p, flag));
+
+		Ok(())
+	}
+
 	/// Ask the client what the history parameter is.
 	pub fn pruning_history(&self) -> u64 {
 		self.history
 	}
 
-	fn block_hash(chain: &BlockChain, id: BlockID) -> Option<H256> {
+	fn resolve_block_hash(&self, chain: &BlockChain, id: BlockID) -> Option<H256> {
 		match id {
 			BlockID::Hash(hash) => Some(hash),
 			BlockID::Number(number) => chain.block_hash(number),
 			BlockID::Earliest => chain.block_hash(0),
 			BlockID::Latest | BlockID::Pending => Some(chain.best_block_hash()),
+			BlockID::Finalized => self.finalized_block().map(|(_, hash)| hash),
 		}
 	}
 
 	fn transaction_address(&self, id: TransactionID) -> Option<TransactionAddress> {
 		match id {
 			TransactionID::Hash(ref hash) => self.chain.read().transaction_address(hash),
-			TransactionID::Location(id, index) => Self::block_hash(&self.chain.read(), id).map(|hash| TransactionAddress {
+			TransactionID::Location(id, index) => self.resolve_block_hash(&self.chain.read(), id).map(|hash| TransactionAddress {
 				block_hash: hash,
 				index: index,
 			})
@@ -782,6 +1141,7 @@ impl snapshot::DatabaseRestore for Client {
 		let mut chain = self.chain.write();
 		let mut tracedb = self.tracedb.write();
 		self.miner.clear();
+		*self.pending_commit.lock() = Default::default();
 		let db = self.db.write();
 		try!(db.restore(new_db));
 
@@ -789,6 +1149,12 @@ impl snapshot::DatabaseRestore for Client {
 		*state_db = StateDB::new(journaldb::new(db.clone(), self.pruning, ::db::COL_STATE), cache_size);
 		*chain = Arc::new(BlockChain::new(self.config.blockchain.clone(), &[], db.clone()));
 		*tracedb = TraceDB::new(self.config.tracing.clone(), db.clone(), chain.clone());
+		if let Some(ref mut finality) = *self.finality.lock() {
+			Client::build_finality(finality, &chain);
+		}
+		if self.observers.is_observed() {
+			self.observers.restored(chain.clone(), chain.first_block_number().unwrap_or(1), chain.best_block_number());
+		}
 		Ok(())
 	}
 }
@@ -796,39 +1162,29 @@ impl snapshot::DatabaseRestore for Client {
 
 impl BlockChainClient for Client {
 	fn call(&self, t: &SignedTransaction, block: BlockID, analytics: CallAnalytics) -> Result<Executed, CallError> {
-		let header = try!(self.block_header(block).ok_or(CallError::StatePruned));
-		let view = HeaderView::new(&header);
-		let last_hashes = self.build_last_hashes(view.parent_hash());
-		let env_info = EnvInfo {
-			number: view.number(),
-			author: view.author(),
-			timestamp: view.timestamp(),
-			difficulty: view.difficulty(),
-			last_hashes: last_hashes,
-			gas_used: U256::zero(),
-			gas_limit: U256::max_value(),
-		};
+		self.call_with_overrides(t, block, analytics, StateOverride::new())
+	}
+
+	fn call_with_overrides(&self, t: &SignedTransaction, block: BlockID, analytics: CallAnalytics, overrides: StateOverride) -> Result<Executed, CallError> {
+		let env_info = try!(self.call_env_info(block));
 		// that's just a copy of the state.
 		let mut state = try!(self.state_at(block).ok_or(CallError::StatePruned));
-		let original_state = if analytics.state_diffing { Some(state.clone()) } else { None };
-
-		let sender = try!(t.sender().map_err(|e| {
-			let message = format!("Transaction malformed: {:?}", e);
-			ExecutionError::TransactionMalformed(message)
-		}));
-		let balance = state.balance(&sender);
-		let needed_balance = t.value + t.gas * t.gas_price;
-		if balance < needed_balance {
-			// give the sender a sufficient balance
-			state.add_balance(&sender, &(needed_balance - balance), CleanupMode::NoEmpty);
+		for (address, account) in &overrides {
+			state.apply_override(address, account);
 		}
-		let options = TransactOptions { tracing: analytics.transaction_tracing, vm_tracing: analytics.vm_tracing, check_nonce: false };
-		let mut ret = try!(Executive::new(&mut state, &env_info, &*self.engine, &self.factories.vm).transact(t, options));
-
-		// TODO gav move this into Executive.
-		ret.state_diff = original_state.map(|original| state.diff_from(original));
+		self.call_in_state(&mut state, &env_info, t, analytics)
+	}
 
-		Ok(ret)
+	fn call_many(&self, transactions: Vec<SignedTransaction>, block: BlockID, analytics: CallAnalytics) -> Result<Vec<Executed>, CallError> {
+		let mut env_info = try!(self.call_env_info(block));
+		let mut state = try!(self.state_at(block).ok_or(CallError::StatePruned));
+		let mut results = Vec::with_capacity(transactions.len());
+		for t in &transactions {
+			let ret = try!(self.call_in_state(&mut state, &env_info, t, analytics));
+			env_info.gas_used = env_info.gas_used + ret.gas_used;
+			results.push(ret);
+		}
+		Ok(results)
 	}
 
 	fn replay(&self, id: TransactionID, analytics: CallAnalytics) -> Result<Executed, CallError> {
@@ -842,7 +1198,7 @@ impl BlockChainClient for Client {
 			return Err(CallError::TransactionNotFound);
 		}
 
-		let options = TransactOptions { tracing: analytics.transaction_tracing, vm_tracing: analytics.vm_tracing, check_nonce: false };
+		let options = TransactOptions { tracing: analytics.transaction_tracing, vm_tracing: analytics.vm_tracing, check_nonce: false, defer_fees: false };
 		let view = HeaderView::new(&header_data);
 		let last_hashes = self.build_last_hashes(view.hash());
 		let mut env_info = EnvInfo {
@@ -910,12 +1266,12 @@ impl BlockChainClient for Client {
 
 	fn block_header(&self, id: BlockID) -> Option<Bytes> {
 		let chain = self.chain.read();
-		Self::block_hash(&chain, id).and_then(|hash| chain.block_header_data(&hash))
+		self.resolve_block_hash(&chain, id).and_then(|hash| chain.block_header_data(&hash))
 	}
 
 	fn block_body(&self, id: BlockID) -> Option<Bytes> {
 		let chain = self.chain.read();
-		Self::block_hash(&chain, id).and_then(|hash| chain.block_body(&hash))
+		self.resolve_block_hash(&chain, id).and_then(|hash| chain.block_body(&hash))
 	}
 
 	fn block(&self, id: BlockID) -> Option<Bytes> {
@@ -925,14 +1281,14 @@ impl BlockChainClient for Client {
 			}
 		}
 		let chain = self.chain.read();
-		Self::block_hash(&chain, id).and_then(|hash| {
+		self.resolve_block_hash(&chain, id).and_then(|hash| {
 			chain.block(&hash)
 		})
 	}
 
 	fn block_status(&self, id: BlockID) -> BlockStatus {
 		let chain = self.chain.read();
-		match Self::block_hash(&chain, id) {
+		match self.resolve_block_hash(&chain, id) {
 			Some(ref hash) if chain.is_known(hash) => BlockStatus::InChain,
 			Some(hash) => self.block_queue.status(&hash).into(),
 			None => BlockStatus::Unknown
@@ -946,7 +1302,7 @@ impl BlockChainClient for Client {
 			}
 		}
 		let chain = self.chain.read();
-		Self::block_hash(&chain, id).and_then(|hash| chain.block_details(&hash)).map(|d| d.total_difficulty)
+		self.resolve_block_hash(&chain, id).and_then(|hash| chain.block_details(&hash)).map(|d| d.total_difficulty)
 	}
 
 	fn nonce(&self, address: &Address, id: BlockID) -> Option<U256> {
@@ -959,7 +1315,7 @@ impl BlockChainClient for Client {
 
 	fn block_hash(&self, id: BlockID) -> Option<H256> {
 		let chain = self.chain.read();
-		Self::block_hash(&chain, id)
+		self.resolve_block_hash(&chain, id)
 	}
 
 	fn code(&self, address: &Address, id: BlockID) -> Option<Option<Bytes>> {
@@ -1005,16 +1361,17 @@ impl BlockChainClient for Client {
 			}
 		}
 
+		// seeking positions the iterator at `after` itself, which belongs to the previous page.
 		let accounts = iter.filter_map(|item| {
 			item.ok().map(|(addr, _)| Address::from_slice(&addr))
-		}).take(count as usize).collect();
+		}).skip_while(|addr| after.map_or(false, |after| addr == after)).take(count as usize).collect();
 
 		Some(accounts)
 	}
 
 	fn list_storage(&self, id: BlockID, account: &Address, after: Option<&H256>, count: u64) -> Option<Vec<H256>> {
 		if !self.factories.trie.is_fat() {
-			trace!(target: "fatdb", "list_stroage: Not a fat DB");
+			trace!(target: "fatdb", "list_storage: Not a fat DB");
 			return None;
 		}
 
@@ -1045,13 +1402,13 @@ impl BlockChainClient for Client {
 
 		if let Some(after) = after {
 			if let Err(e) = iter.seek(after) {
-				trace!(target: "fatdb", "list_accounts: Couldn't seek the DB: {:?}", e);
+				trace!(target: "fatdb", "list_storage: Couldn't seek the DB: {:?}", e);
 			}
 		}
 
 		let keys = iter.filter_map(|item| {
 			item.ok().map(|(key, _)| H256::from_slice(&key))
-		}).take(count as usize).collect();
+		}).skip_while(|key| after.map_or(false, |after| key == after)).take(count as usize).collect();
 
 		Some(keys)
 	}
@@ -1118,6 +1475,16 @@ impl BlockChainClient for Client {
 		}))
 	}
 
+	fn log_proof(&self, id: TransactionID, log_index: usize) -> Option<LogProof> {
+		self.transaction_address(id).and_then(|address| {
+			let chain = self.chain.read();
+			match (chain.block_header_data(&address.block_hash), chain.block_receipts(&address.block_hash)) {
+				(Some(header), Some(receipts)) => LogProof::new(header, &receipts.receipts, address.index, log_index),
+				_ => None,
+			}
+		})
+	}
+
 	fn tree_route(&self, from: &H256, to: &H256) -> Option<TreeRoute> {
 		let chain = self.chain.read();
 		match chain.is_known(from) && chain.is_known(to) {
@@ -1135,7 +1502,7 @@ impl BlockChainClient for Client {
 	}
 
 	fn block_receipts(&self, hash: &H256) -> Option<Bytes> {
-		self.chain.read().block_receipts(hash).map(|receipts| ::rlp::encode(&receipts).to_vec())
+		self.chain.read().block_receipts(hash).map(|receipts| ::rlp::encode(&receipts.receipts).to_vec())
 	}
 
 	fn import_block(&self, bytes: Bytes) -> Result<H256, BlockImportError> {
@@ -1186,6 +1553,38 @@ impl BlockChainClient for Client {
 		self.engine.additional_params().into_iter().collect()
 	}
 
+	fn compact_db(&self) -> Result<(), String> {
+		self.io_channel.lock().send(ClientIoMessage::CompactDatabase)
+			.map_err(|e| format!("Error queueing database compaction: {}", e))
+	}
+
+	fn db_stats(&self) -> Vec<DatabaseColumnStats> {
+		self.db.read().column_stats().into_iter().map(|stats| DatabaseColumnStats {
+			name: ::db::column_name(stats.column).into(),
+			estimated_keys: stats.estimated_keys,
+			estimated_size: stats.estimated_size,
+		}).collect()
+	}
+
+	fn memory_usage(&self) -> MemoryUsage {
+		MemoryUsage {
+			block_queue: self.block_queue.queue_info().mem_used,
+			blockchain_cache: self.chain.read().cache_size().total(),
+			state_cache: self.state_db.lock().mem_used(),
+			trace_cache: self.tracedb.read().cache_size(),
+		}
+	}
+
+	fn blockchain_cache_stats(&self) -> BlockChainCacheStats {
+		self.chain.read().cache_stats()
+	}
+
+	fn transactions_by_sender(&self, sender: &Address, from: BlockNumber, to: BlockNumber, limit: usize) -> Option<Vec<LocalizedTransaction>> {
+		let chain = self.chain.read();
+		chain.transactions_by_sender(sender, from, to, limit)
+			.map(|addresses| addresses.iter().filter_map(|address| chain.transaction(address)).collect())
+	}
+
 	fn blocks_with_bloom(&self, bloom: &H2048, from_block: BlockID, to_block: BlockID) -> Option<Vec<BlockNumber>> {
 		match (self.block_number(from_block), self.block_number(to_block)) {
 			(Some(from), Some(to)) => Some(self.chain.read().blocks_with_bloom(bloom, from, to)),
@@ -1202,7 +1601,14 @@ impl BlockChainClient for Client {
 			.into_iter()
 			.collect::<Vec<u64>>();
 
-		self.chain.read().logs(blocks, |entry| filter.matches(entry), filter.limit)
+		let chain = self.chain.read();
+		let blocks = blocks.into_iter()
+			.filter(|number| chain.block_hash(*number).map_or(false, |hash| {
+				chain.block_logs_may_match(&hash, |addresses, topics| filter.matches_dictionary(addresses, topics))
+			}))
+			.collect();
+
+		chain.logs(blocks, |entry| filter.matches(entry), filter.limit)
 	}
 
 	fn filter_traces(&self, filter: TraceFilter) -> Option<Vec<LocalizedTrace>> {
@@ -1272,6 +1678,14 @@ impl BlockChainClient for Client {
 		self.miner.pending_transactions(self.chain.read().best_block_number())
 	}
 
+	fn local_transactions(&self) -> Vec<H256> {
+		self.miner.local_transactions()
+			.into_iter()
+			.filter(|&(_, ref status)| status.is_current())
+			.map(|(hash, _)| hash)
+			.collect()
+	}
+
 	fn signing_network_id(&self) -> Option<u8> {
 		self.engine.signing_network_id(&self.latest_env_info())
 	}
@@ -1286,6 +1700,32 @@ impl BlockChainClient for Client {
 		self.uncle(id)
 			.map(|header| self.engine.extra_info(&decode(&header)))
 	}
+
+	fn block_rewards(&self, id: BlockID) -> Option<BlockRewards> {
+		self.block(id).map(|block| {
+			let view = BlockView::new(&block);
+			self.engine.block_rewards(&view.header(), &view.uncles())
+		})
+	}
+
+	fn chain_spec_info(&self) -> ChainSpecInfo {
+		ChainSpecInfo {
+			engine: self.engine.name().to_owned(),
+			network_id: self.engine.params().network_id,
+			transitions: self.engine.transitions(),
+			validators: self.engine.validators(),
+			params: self.engine.additional_params().into_iter().collect(),
+			checkpoints: self.checkpoints.clone(),
+		}
+	}
+
+	fn pow_cache_status(&self) -> Option<PowCacheStatus> {
+		self.engine.pow_cache_status()
+	}
+
+	fn validator_uptime(&self) -> Option<Vec<ValidatorUptime>> {
+		self.engine.validator_uptime()
+	}
 }
 
 impl MiningBlockChainClient for Client {
@@ -1345,6 +1785,7 @@ impl MiningBlockChainClient for Client {
 			let route = self.commit_block(block, &h, &block_data);
 			trace!(target: "client", "Imported sealed block #{} ({})", number, h);
 			self.state_db.lock().sync_cache(&route.enacted, &route.retracted, false);
+			self.block_queue.set_best_block(self.chain.read().best_block_hash());
 			route
 		};
 		let (enacted, retracted) = self.calculate_enacted_retracted(&[route]);
@@ -1359,7 +1800,7 @@ impl MiningBlockChainClient for Client {
 				precise_time_ns() - start,
 			);
 		});
-		self.db.read().flush().expect("DB flush failed.");
+		self.flush_pending_commit().expect("DB flush failed.");
 		Ok(h)
 	}
 }
@@ -1388,7 +1829,7 @@ fn should_not_cache_details_before_commit() {
 		let go_thread = go.clone();
 		let another_client = client.reference().clone();
 		thread::spawn(move || {
-			let mut batch = DBTransaction::new(&*another_client.chain.read().db().clone());
+			let mut batch = DBTransaction::new();
 			another_client.chain.read().insert_block(&mut batch, &new_block, Vec::new());
 			go_thread.store(true, Ordering::SeqCst);
 		});
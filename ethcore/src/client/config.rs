@@ -23,7 +23,7 @@ pub use trace::Config as TraceConfig;
 pub use evm::VMType;
 
 use verification::{VerifierType, QueueConfig};
-use util::{journaldb, CompactionProfile};
+use util::{journaldb, CompactionProfile, CompactionStyle};
 
 /// Client state db compaction profile
 #[derive(Debug, PartialEq)]
@@ -119,8 +119,17 @@ pub struct ClientConfig {
 	pub db_cache_size: Option<usize>,
 	/// State db compaction profile
 	pub db_compaction: DatabaseCompactionProfile,
+	/// RocksDB compaction style
+	pub db_compaction_style: CompactionStyle,
+	/// RocksDB write-ahead log size limit (in MB) if not default
+	pub db_wal_size: Option<usize>,
+	/// RocksDB block cache size (in MB) of columns other than the state one if not default
+	pub db_block_cache_size: Option<usize>,
 	/// Should db have WAL enabled?
 	pub db_wal: bool,
+	/// Maximal number of blocks imported while the queue is not drained before
+	/// flushing them to the database. 0 flushes after every import round.
+	pub commit_batch: usize,
 	/// Operating mode
 	pub mode: Mode,
 	/// Type of block verifier used by client.
@@ -133,6 +142,15 @@ pub struct ClientConfig {
 	pub history: u64,
 	/// Check seal valididity on block import
 	pub check_seal: bool,
+	/// Execute independent transactions of imported blocks in parallel.
+	pub parallel_execution: bool,
+	/// Compute intermediate state roots of imported blocks on a background thread.
+	pub pipeline_state_root: bool,
+	/// Re-walk the state in the background after a snapshot restoration.
+	pub verify_restored_state: bool,
+	/// Reject blocks forking off the canonical chain more than this many blocks below the best block
+	/// or below the latest finalized block.
+	pub max_reorg_depth: Option<u64>,
 }
 
 #[cfg(test)]
@@ -0,0 +1,145 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Finality of blocks sealed by a fixed set of validators.
+//!
+//! A block is final once more than half of the validators have sealed it or one of its
+//! descendants: reverting it would then require a majority of validators to seal a competing chain.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use util::{Address, H256};
+use header::BlockNumber;
+
+/// Tracks the canonical blocks which are not final yet and the validators which sealed them.
+pub struct RollingFinality {
+	validators: HashSet<Address>,
+	headers: VecDeque<(H256, BlockNumber, Address)>,
+	sign_count: HashMap<Address, usize>,
+	last_finalized: Option<(BlockNumber, H256)>,
+}
+
+impl RollingFinality {
+	/// Create a tracker for blocks sealed by `validators`.
+	pub fn new(validators: Vec<Address>) -> Self {
+		RollingFinality {
+			validators: validators.into_iter().collect(),
+			headers: VecDeque::new(),
+			sign_count: HashMap::new(),
+			last_finalized: None,
+		}
+	}
+
+	/// Forget all blocks which are not final yet, e.g. when they are retracted by a reorganisation.
+	/// The last finalized block is kept.
+	pub fn clear(&mut self) {
+		self.headers.clear();
+		self.sign_count.clear();
+	}
+
+	/// Start over from the ancestry of the best block, given newest first.
+	/// Only as many ancestors are consumed as needed for a majority of validators.
+	/// The last finalized block is recomputed from the ancestry, since it may have been retracted.
+	pub fn build_ancestry<I>(&mut self, ancestry: I) where I: IntoIterator<Item=(H256, BlockNumber, Address)> {
+		self.clear();
+		self.last_finalized = None;
+		let mut signers = HashSet::new();
+		let mut headers = Vec::new();
+		for (hash, number, signer) in ancestry {
+			if self.validators.contains(&signer) {
+				signers.insert(signer.clone());
+			}
+			headers.push((hash, number, signer));
+			if signers.len() * 2 > self.validators.len() {
+				break;
+			}
+		}
+		for (hash, number, signer) in headers.into_iter().rev() {
+			self.push(hash, number, signer);
+		}
+	}
+
+	/// Push the next canonical block, sealed by `signer`.
+	/// Returns the blocks which became final, oldest first.
+	pub fn push(&mut self, hash: H256, number: BlockNumber, signer: Address) -> Vec<H256> {
+		if self.validators.contains(&signer) {
+			*self.sign_count.entry(signer.clone()).or_insert(0) += 1;
+		}
+		self.headers.push_back((hash, number, signer));
+
+		let mut finalized = Vec::new();
+		while self.sign_count.len() * 2 > self.validators.len() {
+			let (hash, number, signer) = self.headers.pop_front().expect("some validator signed a pushed header; qed");
+			let remove = match self.sign_count.get_mut(&signer) {
+				Some(count) => {
+					*count -= 1;
+					*count == 0
+				}
+				None => false,
+			};
+			if remove {
+				self.sign_count.remove(&signer);
+			}
+			self.last_finalized = Some((number, hash.clone()));
+			finalized.push(hash);
+		}
+		finalized
+	}
+
+	/// Number and hash of the latest final block.
+	pub fn last_finalized(&self) -> Option<(BlockNumber, H256)> {
+		self.last_finalized.clone()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::{Address, H256};
+	use super::RollingFinality;
+
+	#[test]
+	fn finalizes_once_majority_signed_on_top() {
+		let validators: Vec<Address> = (1..4).map(Address::from).collect();
+		let mut finality = RollingFinality::new(validators.clone());
+
+		assert!(finality.push(H256::from(1), 1, validators[0]).is_empty());
+		// a repeated signer adds no weight.
+		assert!(finality.push(H256::from(2), 2, validators[0]).is_empty());
+		assert_eq!(finality.push(H256::from(3), 3, validators[1]), vec![H256::from(1), H256::from(2)]);
+		assert_eq!(finality.last_finalized(), Some((2, H256::from(2))));
+
+		assert!(finality.push(H256::from(4), 4, Address::from(9)).is_empty());
+		finality.clear();
+		assert!(finality.push(H256::from(5), 5, validators[2]).is_empty());
+		assert_eq!(finality.push(H256::from(6), 6, validators[0]), vec![H256::from(5)]);
+		assert_eq!(finality.last_finalized(), Some((5, H256::from(5))));
+	}
+
+	#[test]
+	fn builds_from_ancestry() {
+		let validators: Vec<Address> = (1..4).map(Address::from).collect();
+		let mut finality = RollingFinality::new(validators.clone());
+
+		let ancestry = (1..6).rev().map(|n| (H256::from(n), n, validators[n as usize % 3]));
+		finality.build_ancestry(ancestry);
+		assert_eq!(finality.last_finalized(), Some((4, H256::from(4))));
+		// block 5 is pending; a different validator on top finalizes it.
+		assert_eq!(finality.push(H256::from(6), 6, validators[0]), vec![H256::from(5)]);
+
+		// a fork without a majority of signers finalizes nothing.
+		finality.build_ancestry(vec![(H256::from(7), 1, validators[0])]);
+		assert_eq!(finality.last_finalized(), None);
+	}
+}
@@ -16,7 +16,9 @@
 
 //! Blockchain database client.
 
+mod chain_observer;
 mod config;
+mod finality;
 mod error;
 mod test_client;
 mod trace;
@@ -31,13 +33,15 @@ pub use types::trace_filter::Filter as TraceFilter;
 pub use executive::{Executed, Executive, TransactOptions};
 pub use env_info::{LastHashes, EnvInfo};
 pub use self::chain_notify::ChainNotify;
+pub use self::chain_observer::{ChainObserver, ObservedBlock};
 
 pub use types::call_analytics::CallAnalytics;
+pub use types::state_override::{AccountOverride, StateOverride};
 pub use block_import_error::BlockImportError;
 pub use transaction_import::TransactionImportResult;
 pub use transaction_import::TransactionImportError;
 pub use self::traits::{BlockChainClient, MiningBlockChainClient};
-pub use verification::VerifierType;
+pub use verification::{VerifierType, VerifierSettings};
 
 /// IPC interfaces
 #[cfg(feature="ipc")]
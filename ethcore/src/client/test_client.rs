@@ -26,6 +26,7 @@ use blockchain::TreeRoute;
 use client::{
 	BlockChainClient, MiningBlockChainClient, BlockChainInfo, BlockStatus, BlockID,
 	TransactionID, UncleID, TraceId, TraceFilter, LastHashes, CallAnalytics, BlockImportError,
+	StateOverride,
 };
 use db::{NUM_COLUMNS, COL_STATE};
 use header::{Header as BlockHeader, BlockNumber};
@@ -38,6 +39,14 @@ use evm::{Factory as EvmFactory, VMType, Schedule};
 use miner::{Miner, MinerService, TransactionImportResult};
 use spec::Spec;
 use types::mode::Mode;
+use types::database_stats::ColumnStats;
+use types::memory_usage::MemoryUsage;
+use types::cache_stats::BlockChainCacheStats;
+use types::block_rewards::BlockRewards;
+use types::chain_spec_info::ChainSpecInfo;
+use types::pow_cache_status::PowCacheStatus;
+use types::validator_uptime::ValidatorUptime;
+use types::log_proof::LogProof;
 use views::BlockView;
 
 use verification::queue::QueueInfo;
@@ -289,12 +298,29 @@ impl TestBlockChainClient {
 			BlockID::Hash(hash) => Some(hash),
 			BlockID::Number(n) => self.numbers.read().get(&(n as usize)).cloned(),
 			BlockID::Earliest => self.numbers.read().get(&0).cloned(),
-			BlockID::Latest | BlockID::Pending => self.numbers.read().get(&(self.numbers.read().len() - 1)).cloned()
+			BlockID::Latest | BlockID::Pending => self.numbers.read().get(&(self.numbers.read().len() - 1)).cloned(),
+			BlockID::Finalized => None,
 		}
 	}
 
 	/// Inserts a transaction to miners transactions queue.
 	pub fn insert_transaction_to_queue(&self) {
+		let signed_tx = self.funded_transaction();
+		let res = self.miner.import_external_transactions(self, vec![signed_tx]);
+		let res = res.into_iter().next().unwrap().expect("Successful import");
+		assert_eq!(res, TransactionImportResult::Current);
+	}
+
+	/// Inserts a locally submitted transaction to miner transactions queue and returns its hash.
+	pub fn insert_local_transaction_to_queue(&self) -> H256 {
+		let signed_tx = self.funded_transaction();
+		let hash = signed_tx.hash();
+		let res = self.miner.import_own_transaction(self, signed_tx).expect("Successful import");
+		assert_eq!(res, TransactionImportResult::Current);
+		hash
+	}
+
+	fn funded_transaction(&self) -> SignedTransaction {
 		let keypair = Random.generate().unwrap();
 		let tx = Transaction {
 			action: Action::Create,
@@ -306,9 +332,7 @@ impl TestBlockChainClient {
 		};
 		let signed_tx = tx.sign(keypair.secret(), None);
 		self.set_balance(signed_tx.sender().unwrap(), 10_000_000.into());
-		let res = self.miner.import_external_transactions(self, vec![signed_tx]);
-		let res = res.into_iter().next().unwrap().expect("Successful import");
-		assert_eq!(res, TransactionImportResult::Current);
+		signed_tx
 	}
 }
 
@@ -366,6 +390,14 @@ impl BlockChainClient for TestBlockChainClient {
 		self.execution_result.read().clone().unwrap()
 	}
 
+	fn call_with_overrides(&self, _t: &SignedTransaction, _block: BlockID, _analytics: CallAnalytics, _overrides: StateOverride) -> Result<Executed, CallError> {
+		self.execution_result.read().clone().unwrap()
+	}
+
+	fn call_many(&self, transactions: Vec<SignedTransaction>, _block: BlockID, _analytics: CallAnalytics) -> Result<Vec<Executed>, CallError> {
+		transactions.iter().map(|_| self.execution_result.read().clone().unwrap()).collect()
+	}
+
 	fn replay(&self, _id: TransactionID, _analytics: CallAnalytics) -> Result<Executed, CallError> {
 		self.execution_result.read().clone().unwrap()
 	}
@@ -443,6 +475,10 @@ impl BlockChainClient for TestBlockChainClient {
 		self.receipts.read().get(&id).cloned()
 	}
 
+	fn log_proof(&self, _id: TransactionID, _log_index: usize) -> Option<LogProof> {
+		None
+	}
+
 	fn blocks_with_bloom(&self, _bloom: &H2048, _from_block: BlockID, _to_block: BlockID) -> Option<Vec<BlockNumber>> {
 		unimplemented!();
 	}
@@ -487,6 +523,32 @@ impl BlockChainClient for TestBlockChainClient {
 			.map(|header| self.spec.engine.extra_info(&header))
 	}
 
+	fn block_rewards(&self, id: BlockID) -> Option<BlockRewards> {
+		self.block(id).map(|block| {
+			let view = BlockView::new(&block);
+			self.spec.engine.block_rewards(&view.header(), &view.uncles())
+		})
+	}
+
+	fn chain_spec_info(&self) -> ChainSpecInfo {
+		ChainSpecInfo {
+			engine: self.spec.engine.name().to_owned(),
+			network_id: self.spec.engine.params().network_id,
+			transitions: self.spec.engine.transitions(),
+			validators: self.spec.engine.validators(),
+			params: self.spec.engine.additional_params().into_iter().collect(),
+			checkpoints: self.spec.checkpoints.clone(),
+		}
+	}
+
+	fn pow_cache_status(&self) -> Option<PowCacheStatus> {
+		self.spec.engine.pow_cache_status()
+	}
+
+	fn validator_uptime(&self) -> Option<Vec<ValidatorUptime>> {
+		self.spec.engine.validator_uptime()
+	}
+
 
 	fn block_status(&self, id: BlockID) -> BlockStatus {
 		match id {
@@ -549,7 +611,7 @@ impl BlockChainClient for TestBlockChainClient {
 				U256::zero(),
 				vec![])]);
 			let mut rlp = RlpStream::new();
-			rlp.append(&receipt);
+			rlp.append(&receipt.receipts);
 			return Some(rlp.out());
 		}
 		None
@@ -622,6 +684,26 @@ impl BlockChainClient for TestBlockChainClient {
 		Default::default()
 	}
 
+	fn compact_db(&self) -> Result<(), String> {
+		Ok(())
+	}
+
+	fn db_stats(&self) -> Vec<ColumnStats> {
+		Vec::new()
+	}
+
+	fn memory_usage(&self) -> MemoryUsage {
+		MemoryUsage::default()
+	}
+
+	fn blockchain_cache_stats(&self) -> BlockChainCacheStats {
+		BlockChainCacheStats::default()
+	}
+
+	fn transactions_by_sender(&self, _sender: &Address, _from: BlockNumber, _to: BlockNumber, _limit: usize) -> Option<Vec<LocalizedTransaction>> {
+		None
+	}
+
 	fn chain_info(&self) -> BlockChainInfo {
 		BlockChainInfo {
 			total_difficulty: *self.difficulty.read(),
@@ -662,6 +744,14 @@ impl BlockChainClient for TestBlockChainClient {
 		self.miner.pending_transactions(self.chain_info().best_block_number)
 	}
 
+	fn local_transactions(&self) -> Vec<H256> {
+		self.miner.local_transactions()
+			.into_iter()
+			.filter(|&(_, ref status)| status.is_current())
+			.map(|(hash, _)| hash)
+			.collect()
+	}
+
 	fn signing_network_id(&self) -> Option<u8> { None }
 
 	fn mode(&self) -> Mode { Mode::Active }
@@ -13,6 +13,10 @@ impl TraceDatabaseExtras for BlockChain {
 		(self as &BlockProvider).block_hash(block_number)
 	}
 
+	fn block_hashes(&self, block_numbers: &[BlockNumber]) -> Vec<Option<H256>> {
+		BlockChain::block_hashes(self, block_numbers)
+	}
+
 	fn transaction_hash(&self, block_number: BlockNumber, tx_position: usize) -> Option<H256> {
 		(self as &BlockProvider).block_hash(block_number)
 			.and_then(|block_hash| {
@@ -36,9 +36,18 @@ use ipc::IpcConfig;
 use types::ids::*;
 use types::trace_filter::Filter as TraceFilter;
 use types::call_analytics::CallAnalytics;
+use types::state_override::StateOverride;
 use types::blockchain_info::BlockChainInfo;
 use types::block_status::BlockStatus;
 use types::mode::Mode;
+use types::database_stats::ColumnStats;
+use types::memory_usage::MemoryUsage;
+use types::cache_stats::BlockChainCacheStats;
+use types::block_rewards::BlockRewards;
+use types::chain_spec_info::ChainSpecInfo;
+use types::pow_cache_status::PowCacheStatus;
+use types::validator_uptime::ValidatorUptime;
+use types::log_proof::LogProof;
 
 #[ipc(client_ident="RemoteClient")]
 /// Blockchain database client. Owns and manages a blockchain and a block queue.
@@ -134,6 +143,10 @@ pub trait BlockChainClient : Sync + Send {
 	/// Get transaction receipt with given hash.
 	fn transaction_receipt(&self, id: TransactionID) -> Option<LocalizedReceipt>;
 
+	/// Get a proof of the log at `log_index` within the receipt of given transaction,
+	/// which can be checked against the header of the block containing it.
+	fn log_proof(&self, id: TransactionID, log_index: usize) -> Option<LogProof>;
+
 	/// Get a tree route between `from` and `to`.
 	/// See `BlockChain::tree_route`.
 	fn tree_route(&self, from: &H256, to: &H256) -> Option<TreeRoute>;
@@ -165,6 +178,22 @@ pub trait BlockChainClient : Sync + Send {
 	/// Get the registrar address, if it exists.
 	fn additional_params(&self) -> BTreeMap<String, String>;
 
+	/// Schedule flushing and compacting the database in the background.
+	fn compact_db(&self) -> Result<(), String>;
+
+	/// Get estimated statistics of every database column.
+	fn db_stats(&self) -> Vec<ColumnStats>;
+
+	/// Get estimated heap usage of caches and queues.
+	fn memory_usage(&self) -> MemoryUsage;
+
+	/// Get hit and miss counts, sizes and budgets of the blockchain caches.
+	fn blockchain_cache_stats(&self) -> BlockChainCacheStats;
+
+	/// Get at most `limit` canonical transactions sent by given address in blocks `from` to `to` inclusive.
+	/// Returns `None` if the sender index is disabled.
+	fn transactions_by_sender(&self, sender: &Address, from: BlockNumber, to: BlockNumber, limit: usize) -> Option<Vec<LocalizedTransaction>>;
+
 	/// Get the best block header.
 	fn best_block_header(&self) -> Bytes;
 
@@ -177,6 +206,12 @@ pub trait BlockChainClient : Sync + Send {
 	/// Makes a non-persistent transaction call.
 	fn call(&self, t: &SignedTransaction, block: BlockID, analytics: CallAnalytics) -> Result<Executed, CallError>;
 
+	/// Makes a non-persistent transaction call with account state replaced as given by `overrides`.
+	fn call_with_overrides(&self, t: &SignedTransaction, block: BlockID, analytics: CallAnalytics, overrides: StateOverride) -> Result<Executed, CallError>;
+
+	/// Makes non-persistent transaction calls one after another, each seeing the state left by the previous ones.
+	fn call_many(&self, transactions: Vec<SignedTransaction>, block: BlockID, analytics: CallAnalytics) -> Result<Vec<Executed>, CallError>;
+
 	/// Replays a given transaction for inspection.
 	fn replay(&self, t: TransactionID, analytics: CallAnalytics) -> Result<Executed, CallError>;
 
@@ -201,6 +236,9 @@ pub trait BlockChainClient : Sync + Send {
 	/// list all transactions
 	fn pending_transactions(&self) -> Vec<SignedTransaction>;
 
+	/// Hashes of locally submitted transactions which are still in the queue.
+	fn local_transactions(&self) -> Vec<H256>;
+
 	/// Sorted list of transaction gas prices from at least last sample_size blocks.
 	fn gas_price_corpus(&self, sample_size: usize) -> Vec<U256> {
 		let mut h = self.chain_info().best_block_hash;
@@ -250,6 +288,18 @@ pub trait BlockChainClient : Sync + Send {
 
 	/// Returns engine-related extra info for `UncleID`.
 	fn uncle_extra_info(&self, id: UncleID) -> Option<BTreeMap<String, String>>;
+
+	/// Returns rewards bestowed by the engine on closing given block.
+	fn block_rewards(&self, id: BlockID) -> Option<BlockRewards>;
+
+	/// Returns consensus-related parameters of the active chain specification.
+	fn chain_spec_info(&self) -> ChainSpecInfo;
+
+	/// Returns status of the proof-of-work verification cache, if the engine uses one.
+	fn pow_cache_status(&self) -> Option<PowCacheStatus>;
+
+	/// Returns assigned and missed slots of recent validators, if the engine tracks their liveness.
+	fn validator_uptime(&self) -> Option<Vec<ValidatorUptime>>;
 }
 
 /// Extended client interface used for mining
@@ -39,6 +39,19 @@ pub const COL_ACCOUNT_BLOOM: Option<u32> = Some(5);
 /// Number of columns in DB
 pub const NUM_COLUMNS: Option<u32> = Some(6);
 
+/// Human-readable name of a column.
+pub fn column_name(col: Option<u32>) -> &'static str {
+	match col {
+		COL_STATE => "state",
+		COL_HEADERS => "headers",
+		COL_BODIES => "bodies",
+		COL_EXTRA => "extra",
+		COL_TRACE => "trace",
+		COL_ACCOUNT_BLOOM => "account_bloom",
+		_ => "default",
+	}
+}
+
 /// Modes for updating caches.
 #[derive(Clone, Copy)]
 pub enum CacheUpdatePolicy {
@@ -16,17 +16,18 @@
 
 //! A blockchain engine that supports a non-instant BFT proof-of-authority.
 
-use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering as AtomicOrdering};
+use std::sync::atomic::{AtomicUsize, AtomicIsize, AtomicBool, Ordering as AtomicOrdering};
 use std::sync::Weak;
 use std::time::{UNIX_EPOCH, Duration};
 use util::*;
 use ethkey::{verify_address, Signature};
-use rlp::{UntrustedRlp, View, encode};
+use rlp::encode;
 use account_provider::AccountProvider;
 use block::*;
-use spec::CommonParams;
-use engines::Engine;
-use header::Header;
+use spec::{CommonParams, SealSchema, SealField, SealFieldType};
+use engines::{Engine, LivenessTracker};
+use header::{Header, BlockNumber};
+use state::CleanupMode;
 use error::{Error, BlockError};
 use evm::Schedule;
 use ethjson;
@@ -35,6 +36,8 @@ use service::ClientIoMessage;
 use transaction::SignedTransaction;
 use env_info::EnvInfo;
 use builtin::Builtin;
+use types::validator_uptime::ValidatorUptime;
+use types::block_rewards::{BlockRewards, Reward};
 
 /// `AuthorityRound` params.
 #[derive(Debug, PartialEq)]
@@ -47,14 +50,27 @@ pub struct AuthorityRoundParams {
 	pub authorities: Vec<Address>,
 	/// Number of authorities.
 	pub authority_n: usize,
+	/// Proposer weights of the authorities, one per authority; all 1 if empty.
+	pub authority_weights: Vec<u64>,
+	/// How far a block timestamp may lie outside the time window of its step; one step by default.
+	pub timestamp_tolerance: Duration,
+	/// Block from which timestamps are checked against their step; never by default.
+	pub validate_step_timestamp_transition: u64,
+	/// Reward paid to the author of every block.
+	pub block_reward: U256,
 }
 
 impl From<ethjson::spec::AuthorityRoundParams> for AuthorityRoundParams {
 	fn from(p: ethjson::spec::AuthorityRoundParams) -> Self {
+		let step_duration = Duration::from_secs(p.step_duration.into());
 		AuthorityRoundParams {
 			gas_limit_bound_divisor: p.gas_limit_bound_divisor.into(),
-			step_duration: Duration::from_secs(p.step_duration.into()),
+			step_duration: step_duration,
+			timestamp_tolerance: p.timestamp_tolerance.map_or(step_duration, |t| Duration::from_secs(t.into())),
+			validate_step_timestamp_transition: p.validate_step_timestamp_transition.map_or(u64::max_value(), Into::into),
+			block_reward: p.block_reward.map_or_else(U256::zero, Into::into),
 			authority_n: p.authorities.len(),
+			authority_weights: p.authority_weights.unwrap_or_else(Vec::new).into_iter().map(Into::into).collect(),
 			authorities: p.authorities.into_iter().map(Into::into).collect::<Vec<_>>(),
 		}
 	}
@@ -70,14 +86,48 @@ pub struct AuthorityRound {
 	message_channel: Mutex<Option<IoChannel<ClientIoMessage>>>,
 	step: AtomicUsize,
 	proposed: AtomicBool,
+	clock_drift: AtomicIsize,
+	proposer_schedule: ProposerSchedule,
+	seal_schema: SealSchema,
+	liveness: LivenessTracker,
 }
 
-fn header_step(header: &Header) -> Result<usize, ::rlp::DecoderError> {
-	UntrustedRlp::new(&header.seal()[0]).as_val()
+/// Number of most recent steps over which validator liveness is tracked.
+const LIVENESS_WINDOW: usize = 1000;
+
+/// Deterministic weighted assignment of steps to authorities.
+///
+/// Steps are split into rounds as long as the sum of weights; each authority proposes
+/// as many consecutive steps of a round as its weight. Equal weights give plain
+/// round-robin in the order of authorities, as do all-zero weights.
+struct ProposerSchedule {
+	/// Running sums of the weights.
+	cumulative: Vec<u64>,
 }
 
-fn header_signature(header: &Header) -> Result<Signature, ::rlp::DecoderError> {
-	UntrustedRlp::new(&header.seal()[1]).as_val::<H520>().map(Into::into)
+impl ProposerSchedule {
+	fn new(weights: &[u64]) -> Self {
+		let cumulative: Vec<u64> = weights.iter()
+			.scan(0u64, |acc, w| {
+				*acc = acc.saturating_add(*w);
+				Some(*acc)
+			})
+			.collect();
+		match cumulative.last() {
+			Some(&0) => ProposerSchedule { cumulative: (1..weights.len() as u64 + 1).collect() },
+			_ => ProposerSchedule { cumulative: cumulative },
+		}
+	}
+
+	/// Index of the authority proposing at `step`.
+	fn proposer(&self, step: usize) -> usize {
+		let total = *self.cumulative.last().expect("There is at least one authority; qed");
+		let slot = step as u64 % total;
+		// first authority whose running sum exceeds the slot.
+		match self.cumulative.binary_search_by(|sum| if *sum > slot { ::std::cmp::Ordering::Greater } else { ::std::cmp::Ordering::Less }) {
+			Ok(index) | Err(index) => index,
+		}
+	}
 }
 
 trait AsMillis {
@@ -94,6 +144,14 @@ impl AuthorityRound {
 	/// Create a new instance of AuthorityRound engine.
 	pub fn new(params: CommonParams, our_params: AuthorityRoundParams, builtins: BTreeMap<Address, Builtin>) -> Result<Arc<Self>, Error> {
 		let initial_step = (unix_now().as_secs() / our_params.step_duration.as_secs()) as usize;
+		let weights: Vec<_> = (0..our_params.authority_n)
+			.map(|i| our_params.authority_weights.get(i).cloned().unwrap_or(1))
+			.collect();
+		let proposer_schedule = ProposerSchedule::new(&weights);
+		let seal_schema = params.seal_schema.with_builtin(vec![
+			SealField::new("step", SealFieldType::Uint),
+			SealField::new("signature", SealFieldType::Signature),
+		]);
 		let engine = Arc::new(
 			AuthorityRound {
 				params: params,
@@ -102,20 +160,38 @@ impl AuthorityRound {
 				transition_service: try!(IoService::<BlockArrived>::start()),
 				message_channel: Mutex::new(None),
 				step: AtomicUsize::new(initial_step),
-				proposed: AtomicBool::new(false)
+				proposed: AtomicBool::new(false),
+				clock_drift: AtomicIsize::new(0),
+				proposer_schedule: proposer_schedule,
+				seal_schema: seal_schema,
+				liveness: LivenessTracker::new(LIVENESS_WINDOW),
 			});
 		let handler = TransitionHandler { engine: Arc::downgrade(&engine) };
 		try!(engine.transition_service.register_handler(Arc::new(handler)));
 		Ok(engine)
 	}
 
-	fn step(&self) -> usize {
+	fn current_step(&self) -> usize {
 		self.step.load(AtomicOrdering::SeqCst)
 	}
 
-	fn remaining_step_duration(&self) -> Duration {
+	/// Current UNIX time corrected by the measured clock drift.
+	fn now(&self) -> Duration {
 		let now = unix_now();
-		let step_end = self.our_params.step_duration * (self.step() as u32 + 1);
+		let drift = self.clock_drift.load(AtomicOrdering::Relaxed);
+		let correction = Duration::from_millis(drift.abs() as u64);
+		if drift >= 0 {
+			now + correction
+		} else if now > correction {
+			now - correction
+		} else {
+			Duration::from_secs(0)
+		}
+	}
+
+	fn remaining_step_duration(&self) -> Duration {
+		let now = self.now();
+		let step_end = self.our_params.step_duration * (self.current_step() as u32 + 1);
 		if step_end > now {
 			step_end - now
 		} else {
@@ -124,13 +200,51 @@ impl AuthorityRound {
 	}
 
 	fn step_proposer(&self, step: usize) -> &Address {
-		let p = &self.our_params;
-		p.authorities.get(step % p.authority_n).expect("There are authority_n authorities; taking number modulo authority_n gives number in authority_n range; qed")
+		let index = self.proposer_schedule.proposer(step);
+		self.our_params.authorities.get(index).expect("Schedule contains only indices of authorities; qed")
 	}
 
 	fn is_step_proposer(&self, step: usize, address: &Address) -> bool {
 		self.step_proposer(step) == address
 	}
+
+	/// Check that the timestamp of `header` lies within the time window of `step`, give or take the tolerance.
+	fn verify_step_timestamp(&self, header: &Header, step: usize) -> Result<(), BlockError> {
+		let duration = self.our_params.step_duration.as_secs();
+		let tolerance = self.our_params.timestamp_tolerance.as_secs();
+		let step_start = (step as u64).saturating_mul(duration);
+		let min = step_start.saturating_sub(tolerance);
+		let max = step_start.saturating_add(duration).saturating_add(tolerance).saturating_sub(1);
+		if header.timestamp() < min || header.timestamp() > max {
+			trace!(target: "poa", "verify_block_family: timestamp {} outside of step {}", header.timestamp(), step);
+			return Err(BlockError::InvalidTimestamp(OutOfBounds { min: Some(min), max: Some(max), found: header.timestamp() }));
+		}
+		Ok(())
+	}
+
+	/// Record the proposer of `step` and report proposers of the steps skipped since `parent_step`.
+	/// Steps which are already older than the tracking window are ignored.
+	fn note_liveness(&self, number: BlockNumber, parent_step: usize, step: usize) {
+		let oldest = self.current_step().saturating_sub(self.liveness.window());
+		if step < oldest {
+			return;
+		}
+		self.liveness.note_produced(step, self.step_proposer(step).clone());
+		for missed in max(parent_step + 1, oldest)..step {
+			let validator = self.step_proposer(missed).clone();
+			if self.liveness.note_missed(missed, validator) {
+				self.report_benign(&validator, number);
+			}
+		}
+	}
+
+	fn header_step(&self, header: &Header) -> Result<usize, ::rlp::DecoderError> {
+		self.seal_schema.field(header.seal(), "step")
+	}
+
+	fn header_signature(&self, header: &Header) -> Result<Signature, ::rlp::DecoderError> {
+		self.seal_schema.field::<H520>(header.seal(), "signature").map(Into::into)
+	}
 }
 
 fn unix_now() -> Duration {
@@ -149,7 +263,7 @@ const ENGINE_TIMEOUT_TOKEN: TimerToken = 23;
 impl IoHandler<BlockArrived> for TransitionHandler {
 	fn initialize(&self, io: &IoContext<BlockArrived>) {
 		if let Some(engine) = self.engine.upgrade() {
-			io.register_timer_once(ENGINE_TIMEOUT_TOKEN, engine.remaining_step_duration().as_millis())
+			io.register_priority_timer_once(ENGINE_TIMEOUT_TOKEN, engine.remaining_step_duration().as_millis())
 				.unwrap_or_else(|e| warn!(target: "poa", "Failed to start consensus step timer: {}.", e))
 		}
 	}
@@ -157,15 +271,8 @@ impl IoHandler<BlockArrived> for TransitionHandler {
 	fn timeout(&self, io: &IoContext<BlockArrived>, timer: TimerToken) {
 		if timer == ENGINE_TIMEOUT_TOKEN {
 			if let Some(engine) = self.engine.upgrade() {
-				engine.step.fetch_add(1, AtomicOrdering::SeqCst);
-				engine.proposed.store(false, AtomicOrdering::SeqCst);
-				if let Some(ref channel) = *engine.message_channel.lock() {
-					match channel.send(ClientIoMessage::UpdateSealing) {
-						Ok(_) => trace!(target: "poa", "timeout: UpdateSealing message sent for step {}.", engine.step.load(AtomicOrdering::Relaxed)),
-						Err(err) => trace!(target: "poa", "timeout: Could not send a sealing message {} for step {}.", err, engine.step.load(AtomicOrdering::Relaxed)),
-					}
-				}
-				io.register_timer_once(ENGINE_TIMEOUT_TOKEN, engine.remaining_step_duration().as_millis())
+				engine.step();
+				io.register_priority_timer_once(ENGINE_TIMEOUT_TOKEN, engine.remaining_step_duration().as_millis())
 					.unwrap_or_else(|e| warn!(target: "poa", "Failed to restart consensus step timer: {}.", e))
 			}
 		}
@@ -176,16 +283,27 @@ impl Engine for AuthorityRound {
 	fn name(&self) -> &str { "AuthorityRound" }
 	fn version(&self) -> SemanticVersion { SemanticVersion::new(1, 0, 0) }
 	/// Two fields - consensus step and the corresponding proposer signature.
-	fn seal_fields(&self) -> usize { 2 }
+	fn seal_schema(&self) -> &SealSchema { &self.seal_schema }
 
 	fn params(&self) -> &CommonParams { &self.params }
+
+	fn transitions(&self) -> BTreeMap<String, BlockNumber> {
+		let mut transitions = BTreeMap::new();
+		// unset transitions never happen
+		if self.our_params.validate_step_timestamp_transition != u64::max_value() {
+			transitions.insert("validateStepTimestampTransition".to_owned(), self.our_params.validate_step_timestamp_transition);
+		}
+		transitions
+	}
+
+	fn validators(&self) -> Option<Vec<Address>> { Some(self.our_params.authorities.clone()) }
 	fn builtins(&self) -> &BTreeMap<Address, Builtin> { &self.builtins }
 
 	/// Additional engine-specific information for the user/developer concerning `header`.
 	fn extra_info(&self, header: &Header) -> BTreeMap<String, String> {
 		map![
-			"step".into() => header_step(header).as_ref().map(ToString::to_string).unwrap_or("".into()),
-			"signature".into() => header_signature(header).as_ref().map(ToString::to_string).unwrap_or("".into())
+			"step".into() => self.header_step(header).as_ref().map(ToString::to_string).unwrap_or("".into()),
+			"signature".into() => self.header_signature(header).as_ref().map(ToString::to_string).unwrap_or("".into())
 		]
 	}
 
@@ -207,14 +325,34 @@ impl Engine for AuthorityRound {
 	}
 
 	/// Apply the block reward on finalisation of the block.
-	/// This assumes that all uncles are valid uncles (i.e. of at least one generation before the current).
-	fn on_close_block(&self, _block: &mut ExecutedBlock) {}
+	fn on_close_block(&self, block: &mut ExecutedBlock) {
+		let fields = block.fields_mut();
+		let rewards = self.block_rewards(fields.header, fields.uncles);
+		if rewards.author.amount.is_zero() {
+			return;
+		}
+		fields.state.add_balance(&rewards.author.address, &rewards.author.amount, CleanupMode::NoEmpty);
+		if let Err(e) = fields.state.commit() {
+			warn!("Encountered error on state commit: {}", e);
+		}
+	}
+
+	fn block_rewards(&self, header: &Header, _uncles: &[Header]) -> BlockRewards {
+		BlockRewards {
+			author: Reward { address: header.author().clone(), amount: self.our_params.block_reward },
+			uncles: Vec::new(),
+		}
+	}
 
 	fn is_sealer(&self, author: &Address) -> Option<bool> {
 		let p = &self.our_params;
 		Some(p.authorities.contains(author))
 	}
 
+	fn is_proposer(&self, _height: BlockNumber, round: usize, address: &Address) -> Option<bool> {
+		Some(self.is_step_proposer(round, address))
+	}
+
 	/// Attempt to seal the block internally.
 	///
 	/// This operation is synchronous and may (quite reasonably) not be available, in which `false` will
@@ -222,7 +360,7 @@ impl Engine for AuthorityRound {
 	fn generate_seal(&self, block: &ExecutedBlock, accounts: Option<&AccountProvider>) -> Option<Vec<Bytes>> {
 		if self.proposed.load(AtomicOrdering::SeqCst) { return None; }
 		let header = block.header();
-		let step = self.step();
+		let step = self.current_step();
 		if self.is_step_proposer(step, header.author()) {
 			if let Some(ap) = accounts {
 				// Account should be permanently unlocked, otherwise sealing will fail.
@@ -240,24 +378,20 @@ impl Engine for AuthorityRound {
 		None
 	}
 
-	/// Check the number of seal fields.
+	/// Check the seal fields.
 	fn verify_block_basic(&self, header: &Header, _block: Option<&[u8]>) -> Result<(), Error> {
-		if header.seal().len() != self.seal_fields() {
-			trace!(target: "poa", "verify_block_basic: wrong number of seal fields");
-			Err(From::from(BlockError::InvalidSealArity(
-				Mismatch { expected: self.seal_fields(), found: header.seal().len() }
-			)))
-		} else {
-			Ok(())
-		}
+		self.seal_schema.verify(header.seal()).map_err(|e| {
+			trace!(target: "poa", "verify_block_basic: invalid seal fields");
+			From::from(e)
+		})
 	}
 
 	/// Check if the signature belongs to the correct proposer.
 	fn verify_block_unordered(&self, header: &Header, _block: Option<&[u8]>) -> Result<(), Error> {
-		let header_step = try!(header_step(header));
+		let header_step = try!(self.header_step(header));
 		// Give one step slack if step is lagging, double vote is still not possible.
-		if header_step <= self.step() + 1 {
-			let proposer_signature = try!(header_signature(header));
+		if header_step <= self.current_step() + 1 {
+			let proposer_signature = try!(self.header_signature(header));
 			let ok_sig = try!(verify_address(self.step_proposer(header_step), &proposer_signature, &header.bare_hash()));
 			if ok_sig {
 				Ok(())
@@ -277,12 +411,16 @@ impl Engine for AuthorityRound {
 			return Err(From::from(BlockError::RidiculousNumber(OutOfBounds { min: Some(1), max: None, found: header.number() })));
 		}
 
-		let step = try!(header_step(header));
+		let step = try!(self.header_step(header));
+		let parent_step = try!(self.header_step(parent));
 		// Check if parent is from a previous step.
-		if step == try!(header_step(parent)) {
+		if step == parent_step {
 			trace!(target: "poa", "Multiple blocks proposed for step {}.", step);
 			try!(Err(BlockError::DoubleVote(header.author().clone())));
 		}
+		if header.number() >= self.our_params.validate_step_timestamp_transition {
+			try!(self.verify_step_timestamp(header, step));
+		}
 
 		// Check difficulty is correct given the two timestamps.
 		if header.difficulty() != parent.difficulty() {
@@ -297,6 +435,14 @@ impl Engine for AuthorityRound {
 		Ok(())
 	}
 
+	fn on_block_committed(&self, header: &Header, parent: &Header) {
+		if let (Ok(step), Ok(parent_step)) = (self.header_step(header), self.header_step(parent)) {
+			if step > parent_step {
+				self.note_liveness(header.number(), parent_step, step);
+			}
+		}
+	}
+
 	fn verify_transaction_basic(&self, t: &SignedTransaction, _header: &Header) -> Result<(), Error> {
 		try!(t.check_low_s());
 		Ok(())
@@ -306,10 +452,41 @@ impl Engine for AuthorityRound {
 		t.sender().map(|_|()) // Perform EC recovery and cache sender
 	}
 
+	fn step(&self) {
+		self.step.fetch_add(1, AtomicOrdering::SeqCst);
+		self.proposed.store(false, AtomicOrdering::SeqCst);
+		if let Some(ref channel) = *self.message_channel.lock() {
+			match channel.send_priority(ClientIoMessage::UpdateSealing) {
+				Ok(_) => trace!(target: "poa", "timeout: UpdateSealing message sent for step {}.", self.current_step()),
+				Err(err) => trace!(target: "poa", "timeout: Could not send a sealing message {} for step {}.", err, self.current_step()),
+			}
+		}
+	}
+
 	fn register_message_channel(&self, message_channel: IoChannel<ClientIoMessage>) {
 		let mut guard = self.message_channel.lock();
 		*guard = Some(message_channel);
 	}
+
+	fn set_clock_drift(&self, drift_ms: i64) {
+		if drift_ms.abs() as u64 >= self.our_params.step_duration.as_millis() / 2 {
+			warn!(target: "poa", "Local clock drift of {}ms is significant compared to the step duration. Consensus steps may be missed.", drift_ms);
+		}
+		self.clock_drift.store(drift_ms as isize, AtomicOrdering::Relaxed);
+	}
+
+	fn report_benign(&self, validator: &Address, number: BlockNumber) {
+		info!(target: "poa", "Validator {} missed its step before block #{}.", validator, number);
+		if let Some(ref channel) = *self.message_channel.lock() {
+			if let Err(err) = channel.send(ClientIoMessage::ReportBenign(validator.clone(), number)) {
+				trace!(target: "poa", "Could not report validator {}: {}", validator, err);
+			}
+		}
+	}
+
+	fn validator_uptime(&self) -> Option<Vec<ValidatorUptime>> {
+		Some(self.liveness.uptime())
+	}
 }
 
 #[cfg(test)]
@@ -325,6 +502,7 @@ mod tests {
 	use account_provider::AccountProvider;
 	use spec::Spec;
 	use std::time::UNIX_EPOCH;
+	use super::ProposerSchedule;
 
 	#[test]
 	fn has_valid_metadata() {
@@ -373,6 +551,24 @@ mod tests {
 		assert!(verify_result.is_err());
 	}
 
+	#[test]
+	fn block_rewards_match_closed_block_balances() {
+		let spec = ::ethereum::new_kovan();
+		let engine = &*spec.engine;
+		let genesis_header = spec.genesis_header();
+		let mut db = get_temp_state_db().take();
+		spec.ensure_db_good(&mut db, &TrieFactory::new(TrieSpec::Secure)).unwrap();
+		let last_hashes = Arc::new(vec![genesis_header.hash()]);
+		let author: Address = 5.into();
+		let b = OpenBlock::new(engine, Default::default(), false, db, &genesis_header, last_hashes, author, (3141562.into(), 31415620.into()), vec![]).unwrap();
+		let b = b.close();
+
+		let rewards = engine.block_rewards(b.header(), &[]);
+		assert_eq!(rewards.author.address, author);
+		assert_eq!(rewards.author.amount, "4563918244f40000".into());
+		assert_eq!(b.state().balance(&author), rewards.total());
+	}
+
 	#[test]
 	fn generates_seal_and_does_not_double_propose() {
 		let tap = AccountProvider::transient_provider();
@@ -427,4 +623,92 @@ mod tests {
 		header.set_seal(vec![encode(&step).to_vec(), encode(&(&*signature as &[u8])).to_vec()]);
 		assert!(engine.verify_block_seal(&header).is_ok());
 	}
+
+	#[test]
+	fn weighted_proposer_schedule() {
+		let schedule = |weights: &[u64], steps: usize| {
+			let schedule = ProposerSchedule::new(weights);
+			(0..steps).map(|step| schedule.proposer(step)).collect::<Vec<_>>()
+		};
+		assert_eq!(schedule(&[1, 1, 1], 6), vec![0, 1, 2, 0, 1, 2]);
+		assert_eq!(schedule(&[2, 1], 3), vec![0, 0, 1]);
+		assert_eq!(schedule(&[5, 1, 1], 7), vec![0, 0, 0, 0, 0, 1, 2]);
+		assert_eq!(schedule(&[0, 1], 2), vec![1, 1]);
+		assert_eq!(schedule(&[1, 0, 1], 2), vec![0, 2]);
+		assert_eq!(schedule(&[0, 0], 2), vec![0, 1]);
+
+		let huge = ProposerSchedule::new(&[u64::max_value() / 2, 1]);
+		assert_eq!(huge.proposer(0), 0);
+		assert_eq!(huge.proposer((u64::max_value() / 2) as usize), 1);
+	}
+
+	#[test]
+	fn is_proposer_follows_steps() {
+		let engine = Spec::new_test_round().engine;
+		let validators = engine.validators().unwrap();
+		assert_eq!(engine.is_proposer(1, 0, &validators[0]), Some(true));
+		assert_eq!(engine.is_proposer(1, 0, &validators[1]), Some(false));
+		assert_eq!(engine.is_proposer(1, 1, &validators[1]), Some(true));
+	}
+
+	#[test]
+	fn tracks_missed_steps() {
+		let engine = Spec::new_test_round().engine;
+		assert_eq!(engine.validator_uptime(), Some(vec![]));
+
+		// One second steps.
+		let current_step = UNIX_EPOCH.elapsed().unwrap().as_secs() as usize;
+
+		let mut parent = Header::default();
+		parent.set_gas_limit(10000.into());
+		parent.set_seal(vec![encode(&(current_step - 4)).to_vec(), encode(&H520::default()).to_vec()]);
+		let mut header = Header::default();
+		header.set_number(1);
+		header.set_gas_limit(10000.into());
+		header.set_timestamp((current_step - 1) as u64);
+		header.set_seal(vec![encode(&(current_step - 1)).to_vec(), encode(&H520::default()).to_vec()]);
+		// Verification alone doesn't count: the block may never be committed.
+		assert!(engine.verify_block_family(&header, &parent, None).is_ok());
+		assert_eq!(engine.validator_uptime(), Some(vec![]));
+
+		engine.on_block_committed(&header, &parent);
+		// Committing again does not count the same steps twice.
+		engine.on_block_committed(&header, &parent);
+
+		let uptime = engine.validator_uptime().unwrap();
+		assert_eq!(uptime.iter().map(|u| u.slots).sum::<u64>(), 3);
+		assert_eq!(uptime.iter().map(|u| u.missed).sum::<u64>(), 2);
+	}
+
+	#[test]
+	fn rejects_timestamp_outside_of_step() {
+		let engine = Spec::new_test_round().engine;
+		let mut parent = Header::default();
+		parent.set_gas_limit(10000.into());
+		parent.set_seal(vec![encode(&1usize).to_vec(), encode(&H520::default()).to_vec()]);
+		let mut header = Header::default();
+		header.set_number(1);
+		header.set_gas_limit(10000.into());
+		header.set_seal(vec![encode(&10usize).to_vec(), encode(&H520::default()).to_vec()]);
+
+		// Not checked before the transition block.
+		for &timestamp in &[0, 8, 12] {
+			header.set_timestamp(timestamp);
+			assert!(engine.verify_block_family(&header, &parent, None).is_ok());
+		}
+		header.set_number(2);
+
+		// One second steps with the default tolerance of one step.
+		for timestamp in 9..12 {
+			header.set_timestamp(timestamp);
+			assert!(engine.verify_block_family(&header, &parent, None).is_ok());
+		}
+		for &timestamp in &[8, 12] {
+			header.set_timestamp(timestamp);
+			match engine.verify_block_family(&header, &parent, None) {
+				Err(Error::Block(BlockError::InvalidTimestamp(_))) => {},
+				e => panic!("Unexpected result: {:?}", e),
+			}
+		}
+	}
 }
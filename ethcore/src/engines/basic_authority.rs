@@ -20,7 +20,7 @@ use ethkey::{recover, public_to_address};
 use account_provider::AccountProvider;
 use block::*;
 use builtin::Builtin;
-use spec::CommonParams;
+use spec::{CommonParams, SealSchema, SealField, SealFieldType};
 use engines::Engine;
 use env_info::EnvInfo;
 use error::{BlockError, Error};
@@ -58,15 +58,18 @@ pub struct BasicAuthority {
 	params: CommonParams,
 	our_params: BasicAuthorityParams,
 	builtins: BTreeMap<Address, Builtin>,
+	seal_schema: SealSchema,
 }
 
 impl BasicAuthority {
 	/// Create a new instance of BasicAuthority engine
 	pub fn new(params: CommonParams, our_params: BasicAuthorityParams, builtins: BTreeMap<Address, Builtin>) -> Self {
+		let seal_schema = params.seal_schema.with_builtin(vec![SealField::new("signature", SealFieldType::Signature)]);
 		BasicAuthority {
 			params: params,
 			our_params: our_params,
 			builtins: builtins,
+			seal_schema: seal_schema,
 		}
 	}
 }
@@ -75,9 +78,15 @@ impl Engine for BasicAuthority {
 	fn name(&self) -> &str { "BasicAuthority" }
 	fn version(&self) -> SemanticVersion { SemanticVersion::new(1, 0, 0) }
 	// One field - the signature
-	fn seal_fields(&self) -> usize { 1 }
+	fn seal_schema(&self) -> &SealSchema { &self.seal_schema }
 
 	fn params(&self) -> &CommonParams { &self.params }
+
+	fn validators(&self) -> Option<Vec<Address>> {
+		let mut validators: Vec<_> = self.our_params.authorities.iter().cloned().collect();
+		validators.sort();
+		Some(validators)
+	}
 	fn builtins(&self) -> &BTreeMap<Address, Builtin> { &self.builtins }
 
 	/// Additional engine-specific information for the user/developer concerning `header`.
@@ -131,20 +140,13 @@ impl Engine for BasicAuthority {
 
 	fn verify_block_basic(&self, header: &Header, _block: Option<&[u8]>) -> result::Result<(), Error> {
 		// check the seal fields.
-		// TODO: pull this out into common code.
-		if header.seal().len() != self.seal_fields() {
-			return Err(From::from(BlockError::InvalidSealArity(
-				Mismatch { expected: self.seal_fields(), found: header.seal().len() }
-			)));
-		}
+		try!(self.seal_schema.verify(header.seal()));
 		Ok(())
 	}
 
 	fn verify_block_unordered(&self, header: &Header, _block: Option<&[u8]>) -> result::Result<(), Error> {
-		use rlp::{UntrustedRlp, View};
-
 		// check the signature is legit.
-		let sig = try!(UntrustedRlp::new(&header.seal()[0]).as_val::<H520>());
+		let sig = try!(self.seal_schema.field::<H520>(header.seal(), "signature"));
 		let signer = public_to_address(&try!(recover(&sig.into(), &header.bare_hash())));
 		if !self.our_params.authorities.contains(&signer) {
 			return try!(Err(BlockError::InvalidSeal));
@@ -0,0 +1,107 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tracking of validators which miss their slots.
+
+use std::collections::BTreeMap;
+use util::{Address, RwLock};
+use types::validator_uptime::ValidatorUptime;
+
+/// Records, for each of the most recent steps, the validator assigned to it and whether it produced a block.
+pub struct LivenessTracker {
+	window: usize,
+	slots: RwLock<BTreeMap<usize, (Address, bool)>>,
+}
+
+impl LivenessTracker {
+	/// Create a tracker remembering `window` steps back from the latest recorded one.
+	pub fn new(window: usize) -> Self {
+		LivenessTracker {
+			window: window,
+			slots: RwLock::new(BTreeMap::new()),
+		}
+	}
+
+	/// Number of steps remembered.
+	pub fn window(&self) -> usize { self.window }
+
+	/// Record that `validator` produced the block of `step`.
+	pub fn note_produced(&self, step: usize, validator: Address) {
+		self.note(step, validator, true);
+	}
+
+	/// Record that `validator` produced no block in `step`.
+	/// Returns `false` if the step was recorded before and the miss should not be reported again.
+	pub fn note_missed(&self, step: usize, validator: Address) -> bool {
+		self.note(step, validator, false)
+	}
+
+	fn note(&self, step: usize, validator: Address, produced: bool) -> bool {
+		let mut slots = self.slots.write();
+		let newly = slots.insert(step, (validator, produced)).is_none();
+		let latest = *slots.keys().next_back().expect("a slot was just inserted; qed");
+		if latest >= self.window {
+			*slots = slots.split_off(&(latest - self.window + 1));
+		}
+		newly
+	}
+
+	/// Assigned and missed slots of each validator within the window, ordered by address.
+	pub fn uptime(&self) -> Vec<ValidatorUptime> {
+		let mut uptime: BTreeMap<Address, ValidatorUptime> = BTreeMap::new();
+		for &(ref address, produced) in self.slots.read().values() {
+			let entry = uptime.entry(address.clone()).or_insert_with(|| ValidatorUptime {
+				address: address.clone(),
+				slots: 0,
+				missed: 0,
+			});
+			entry.slots += 1;
+			if !produced {
+				entry.missed += 1;
+			}
+		}
+		uptime.into_iter().map(|(_, u)| u).collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::Address;
+	use super::LivenessTracker;
+
+	#[test]
+	fn counts_missed_slots_within_window() {
+		let tracker = LivenessTracker::new(4);
+		let a = Address::from(1);
+		let b = Address::from(2);
+
+		tracker.note_produced(1, a);
+		assert!(tracker.note_missed(2, b));
+		assert!(!tracker.note_missed(2, b));
+		tracker.note_produced(3, a);
+		tracker.note_produced(4, b);
+
+		let uptime = tracker.uptime();
+		assert_eq!((uptime[0].address, uptime[0].slots, uptime[0].missed), (a, 2, 0));
+		assert_eq!((uptime[1].address, uptime[1].slots, uptime[1].missed), (b, 2, 1));
+
+		// step 6 pushes steps 1 and 2 out of the window.
+		tracker.note_produced(6, b);
+		let uptime = tracker.uptime();
+		assert_eq!((uptime[0].slots, uptime[0].missed), (1, 0));
+		assert_eq!((uptime[1].slots, uptime[1].missed), (2, 0));
+	}
+}
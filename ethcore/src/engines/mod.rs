@@ -20,11 +20,13 @@ mod null_engine;
 mod instant_seal;
 mod basic_authority;
 mod authority_round;
+mod liveness;
 
 pub use self::null_engine::NullEngine;
 pub use self::instant_seal::InstantSeal;
 pub use self::basic_authority::BasicAuthority;
 pub use self::authority_round::AuthorityRound;
+pub use self::liveness::LivenessTracker;
 
 use util::*;
 use account_provider::AccountProvider;
@@ -32,12 +34,16 @@ use block::ExecutedBlock;
 use builtin::Builtin;
 use env_info::EnvInfo;
 use error::Error;
-use spec::CommonParams;
+use spec::{CommonParams, SealSchema};
 use evm::Schedule;
 use io::IoChannel;
 use service::ClientIoMessage;
-use header::Header;
+use header::{Header, BlockNumber};
 use transaction::SignedTransaction;
+use types::block_rewards::{BlockRewards, Reward};
+use types::pow_cache_status::PowCacheStatus;
+use types::validator_uptime::ValidatorUptime;
+use ethash::CacheOptions as PowCacheOptions;
 
 /// A consensus mechanism for the chain. Generally either proof-of-work or proof-of-stake-based.
 /// Provides hooks into each of the major parts of block import.
@@ -47,8 +53,11 @@ pub trait Engine : Sync + Send {
 	/// The version of this engine. Should be of the form
 	fn version(&self) -> SemanticVersion { SemanticVersion::new(0, 0, 0) }
 
+	/// Named header seal fields and their RLP types, as declared by the spec.
+	fn seal_schema(&self) -> &SealSchema { &self.params().seal_schema }
+
 	/// The number of additional header fields required for this engine.
-	fn seal_fields(&self) -> usize { 0 }
+	fn seal_fields(&self) -> usize { self.seal_schema().len() }
 
 	/// Additional engine-specific information for the user/developer concerning `header`.
 	fn extra_info(&self, _header: &Header) -> BTreeMap<String, String> { BTreeMap::new() }
@@ -59,6 +68,12 @@ pub trait Engine : Sync + Send {
 	/// Get the general parameters of the chain.
 	fn params(&self) -> &CommonParams;
 
+	/// Block numbers from which rule changes take effect, keyed by spec parameter name.
+	fn transitions(&self) -> BTreeMap<String, BlockNumber> { BTreeMap::new() }
+
+	/// Accounts allowed to seal blocks if the engine uses a fixed validator set.
+	fn validators(&self) -> Option<Vec<Address>> { None }
+
 	/// Get the EVM schedule for the given `env_info`.
 	fn schedule(&self, env_info: &EnvInfo) -> Schedule;
 
@@ -79,10 +94,24 @@ pub trait Engine : Sync + Send {
 	fn on_new_block(&self, _block: &mut ExecutedBlock) {}
 	/// Block transformation functions, after the transactions.
 	fn on_close_block(&self, _block: &mut ExecutedBlock) {}
+	/// Called once a block has been committed as the new best block, along with its parent.
+	fn on_block_committed(&self, _header: &Header, _parent: &Header) {}
+
+	/// Rewards bestowed on closing a block with given header and uncles.
+	fn block_rewards(&self, header: &Header, _uncles: &[Header]) -> BlockRewards {
+		BlockRewards {
+			author: Reward { address: header.author().clone(), amount: U256::zero() },
+			uncles: Vec::new(),
+		}
+	}
 
 	/// If Some(true) this author is able to generate seals, generate_seal has to be implemented.
 	/// None indicates that this Engine never seals internally regardless of author (e.g. PoW).
 	fn is_sealer(&self, _author: &Address) -> Option<bool> { None }
+
+	/// If Some(true) `address` is the designated proposer for given height and round (step).
+	/// None indicates that this Engine has no notion of a designated proposer.
+	fn is_proposer(&self, _height: BlockNumber, _round: usize, _address: &Address) -> Option<bool> { None }
 	/// Checks if default address is able to seal.
 	fn is_default_sealer(&self) -> Option<bool> { self.is_sealer(&Default::default()) }
 	/// Attempt to seal the block internally.
@@ -95,7 +124,13 @@ pub trait Engine : Sync + Send {
 
 	/// Phase 1 quick block verification. Only does checks that are cheap. `block` (the header's full block)
 	/// may be provided for additional checks. Returns either a null `Ok` or a general error detailing the problem with import.
-	fn verify_block_basic(&self, _header: &Header,  _block: Option<&[u8]>) -> Result<(), Error> { Ok(()) }
+	/// By default only checks the seal against `seal_schema`, if the spec declares seal fields.
+	fn verify_block_basic(&self, header: &Header, _block: Option<&[u8]>) -> Result<(), Error> {
+		match self.seal_schema().is_empty() {
+			true => Ok(()),
+			false => self.seal_schema().verify(header.seal()).map_err(From::from),
+		}
+	}
 
 	/// Phase 2 verification. Perform costly checks such as transaction signatures. `block` (the header's full block)
 	/// may be provided for additional checks. Returns either a null `Ok` or a general error detailing the problem with import.
@@ -146,5 +181,27 @@ pub trait Engine : Sync + Send {
 
 	/// Add a channel for communication with Client which can be used for sealing.
 	fn register_message_channel(&self, _message_channel: IoChannel<ClientIoMessage>) {}
+
+	/// Notify the engine about measured drift of the local clock (in ms, positive when the local clock is behind).
+	/// Engines which rely on wall clock time may use it to correct their timing.
+	fn set_clock_drift(&self, _drift_ms: i64) {}
+
+	/// Advance the engine to its next step. Engines with timed steps call it from their own timer;
+	/// tests may call it directly to advance time deterministically.
+	fn step(&self) {}
+
+	/// Configure where and how the proof-of-work verification cache is stored, for engines which use one.
+	fn set_pow_cache_options(&self, _options: PowCacheOptions) {}
+
+	/// Status of the proof-of-work verification cache, for engines which use one.
+	fn pow_cache_status(&self) -> Option<PowCacheStatus> { None }
+
+	/// Report a validator which produced no block in its slot before block `number`.
+	/// Such misbehaviour is benign: it is not punished by the consensus rules.
+	/// Reports are passed on to the client, which notifies its listeners.
+	fn report_benign(&self, _validator: &Address, _number: BlockNumber) {}
+
+	/// Assigned and missed slots of each validator over a recent window, for engines which track liveness.
+	fn validator_uptime(&self) -> Option<Vec<ValidatorUptime>> { None }
 	// TODO: sealing stuff - though might want to leave this for later.
 }
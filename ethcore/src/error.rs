@@ -78,6 +78,8 @@ pub enum TransactionError {
 	CodeBanned,
 	/// Invalid network ID given.
 	InvalidNetworkId,
+	/// Transaction is rejected by the transaction filter.
+	NotAllowed,
 }
 
 impl fmt::Display for TransactionError {
@@ -102,6 +104,7 @@ impl fmt::Display for TransactionError {
 			RecipientBanned => "Recipient is temporarily banned.".into(),
 			CodeBanned => "Contract code is temporarily banned.".into(),
 			InvalidNetworkId => "Transaction of this network ID is not allowed on this chain.".into(),
+			NotAllowed => "Sender is not permitted to execute this transaction.".into(),
 		};
 
 		f.write_fmt(format_args!("Transaction error ({})", msg))
@@ -117,6 +120,8 @@ pub enum BlockError {
 	ExtraDataOutOfBounds(OutOfBounds<usize>),
 	/// Seal is incorrect format.
 	InvalidSealArity(Mismatch<usize>),
+	/// Seal field at the given position does not decode as its declared type.
+	InvalidSealField(usize),
 	/// Block has too much gas used.
 	TooMuchGasUsed(OutOfBounds<U256>),
 	/// Uncles hash in header is invalid.
@@ -169,6 +174,8 @@ pub enum BlockError {
 	UnknownUncleParent(H256),
 	/// The same author issued different votes at the same step.
 	DoubleVote(H160),
+	/// Block forks off the canonical chain below the lowest block which may be reorganised.
+	ReorgTooDeep(OutOfBounds<BlockNumber>),
 }
 
 impl fmt::Display for BlockError {
@@ -179,6 +186,7 @@ impl fmt::Display for BlockError {
 			TooManyUncles(ref oob) => format!("Block has too many uncles. {}", oob),
 			ExtraDataOutOfBounds(ref oob) => format!("Extra block data too long. {}", oob),
 			InvalidSealArity(ref mis) => format!("Block seal in incorrect format: {}", mis),
+			InvalidSealField(index) => format!("Block seal field #{} is malformed", index),
 			TooMuchGasUsed(ref oob) => format!("Block has too much gas used. {}", oob),
 			InvalidUnclesHash(ref mis) => format!("Block has invalid uncles hash: {}", mis),
 			UncleTooOld(ref oob) => format!("Uncle block is too old. {}", oob),
@@ -203,6 +211,7 @@ impl fmt::Display for BlockError {
 			UnknownParent(ref hash) => format!("Unknown parent: {}", hash),
 			UnknownUncleParent(ref hash) => format!("Unknown uncle parent: {}", hash),
 			DoubleVote(ref address) => format!("Author {} issued too many blocks.", address),
+			ReorgTooDeep(ref oob) => format!("Block forks off the canonical chain too deep: {}", oob),
 		};
 
 		f.write_fmt(format_args!("Block error ({})", msg))
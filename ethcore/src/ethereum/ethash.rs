@@ -14,20 +14,22 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethash::{quick_get_difficulty, slow_get_seedhash, EthashManager};
+use ethash::{quick_get_difficulty, slow_get_seedhash, EthashManager, CacheOptions};
 use util::*;
 use block::*;
 use builtin::Builtin;
 use env_info::EnvInfo;
 use error::{BlockError, TransactionError, Error};
-use header::Header;
+use header::{Header, BlockNumber};
 use state::CleanupMode;
-use spec::CommonParams;
+use spec::{CommonParams, SealSchema, SealField, SealFieldType};
 use transaction::SignedTransaction;
 use engines::Engine;
+use types::block_rewards::{BlockRewards, Reward};
+use types::pow_cache_status::PowCacheStatus;
 use evm::Schedule;
 use ethjson;
-use rlp::{self, UntrustedRlp, View};
+use rlp;
 
 /// Ethash params.
 #[derive(Debug, PartialEq)]
@@ -60,6 +62,11 @@ pub struct EthashParams {
 	pub difficulty_hardfork_bound_divisor: U256,
 	/// Block on which there is no additional difficulty from the exponential bomb.
 	pub bomb_defuse_transition: u64,
+	/// Delays of the exponential bomb: starting from the given block the bomb is pushed back by the given number of blocks.
+	/// Delays accumulate.
+	pub difficulty_bomb_delays: BTreeMap<BlockNumber, BlockNumber>,
+	/// Number of blocks after which the exponential bomb doubles.
+	pub exp_diff_period: u64,
 	/// Number of first block where EIP-150 rules begin.
 	pub eip150_transition: u64,
 	/// Number of first block where EIP-155 rules begin.
@@ -95,6 +102,8 @@ impl From<ethjson::spec::EthashParams> for EthashParams {
 			difficulty_hardfork_transition: p.difficulty_hardfork_transition.map_or(u64::max_value(), Into::into),
 			difficulty_hardfork_bound_divisor: p.difficulty_hardfork_bound_divisor.map_or(p.difficulty_bound_divisor.into(), Into::into),
 			bomb_defuse_transition: p.bomb_defuse_transition.map_or(u64::max_value(), Into::into),
+			difficulty_bomb_delays: p.difficulty_bomb_delays.unwrap_or_else(BTreeMap::new).into_iter().map(|(block, delay)| (block.into(), delay.into())).collect(),
+			exp_diff_period: p.exp_diff_period.map_or(100000, Into::into),
 			eip150_transition: p.eip150_transition.map_or(0, Into::into),
 			eip155_transition: p.eip155_transition.map_or(0, Into::into),
 			eip160_transition: p.eip160_transition.map_or(0, Into::into),
@@ -114,16 +123,22 @@ pub struct Ethash {
 	ethash_params: EthashParams,
 	builtins: BTreeMap<Address, Builtin>,
 	pow: EthashManager,
+	seal_schema: SealSchema,
 }
 
 impl Ethash {
 	/// Create a new instance of Ethash engine
 	pub fn new(params: CommonParams, ethash_params: EthashParams, builtins: BTreeMap<Address, Builtin>) -> Self {
+		let seal_schema = params.seal_schema.with_builtin(vec![
+			SealField::new("mixHash", SealFieldType::Hash),
+			SealField::new("nonce", SealFieldType::Nonce),
+		]);
 		Ethash {
 			params: params,
 			ethash_params: ethash_params,
 			builtins: builtins,
 			pow: EthashManager::new(),
+			seal_schema: seal_schema,
 		}
 	}
 }
@@ -131,16 +146,51 @@ impl Ethash {
 impl Engine for Ethash {
 	fn name(&self) -> &str { "Ethash" }
 	fn version(&self) -> SemanticVersion { SemanticVersion::new(1, 0, 0) }
-	// Two fields - mix
-	fn seal_fields(&self) -> usize { 2 }
+	// Two fields - mix hash and nonce
+	fn seal_schema(&self) -> &SealSchema { &self.seal_schema }
 
 	fn params(&self) -> &CommonParams { &self.params }
 	fn additional_params(&self) -> HashMap<String, String> { hash_map!["registrar".to_owned() => self.ethash_params.registrar.hex()] }
 
+	fn transitions(&self) -> BTreeMap<String, BlockNumber> {
+		let p = &self.ethash_params;
+		vec![
+			("homesteadTransition", p.homestead_transition),
+			("daoHardforkTransition", p.dao_hardfork_transition),
+			("difficultyHardforkTransition", p.difficulty_hardfork_transition),
+			("bombDefuseTransition", p.bomb_defuse_transition),
+			("eip150Transition", p.eip150_transition),
+			("eip155Transition", p.eip155_transition),
+			("eip160Transition", p.eip160_transition),
+			("eip161abcTransition", p.eip161abc_transition),
+			("eip161dTransition", p.eip161d_transition),
+			("ecip1010PauseTransition", p.ecip1010_pause_transition),
+			("ecip1010ContinueTransition", p.ecip1010_continue_transition),
+		].into_iter()
+			// unset transitions never happen
+			.filter(|&(_, block)| block != u64::max_value())
+			.map(|(name, block)| (name.to_owned(), block))
+			.collect()
+	}
+
 	fn builtins(&self) -> &BTreeMap<Address, Builtin> {
 		&self.builtins
 	}
 
+	fn set_pow_cache_options(&self, options: CacheOptions) {
+		self.pow.set_options(options);
+	}
+
+	fn pow_cache_status(&self) -> Option<PowCacheStatus> {
+		let status = self.pow.status();
+		Some(PowCacheStatus {
+			current_epoch: status.current_epoch,
+			generating_epoch: status.generating.map(|(epoch, _)| epoch),
+			progress: status.generating.map_or(0, |(_, progress)| progress),
+			cached_epochs: status.cached_epochs,
+		})
+	}
+
 	/// Additional engine-specific information for the user/developer concerning `header`.
 	fn extra_info(&self, header: &Header) -> BTreeMap<String, String> {
 		map!["nonce".to_owned() => format!("0x{}", header.nonce().hex()), "mixHash".to_owned() => format!("0x{}", header.mix_hash().hex())]
@@ -213,16 +263,15 @@ impl Engine for Ethash {
 	/// Apply the block reward on finalisation of the block.
 	/// This assumes that all uncles are valid uncles (i.e. of at least one generation before the current).
 	fn on_close_block(&self, block: &mut ExecutedBlock) {
-		let reward = self.ethash_params.block_reward;
 		let fields = block.fields_mut();
+		let rewards = self.block_rewards(fields.header, fields.uncles);
 
 		// Bestow block reward
-		fields.state.add_balance(fields.header.author(), &(reward + reward / U256::from(32) * U256::from(fields.uncles.len())), CleanupMode::NoEmpty);
+		fields.state.add_balance(&rewards.author.address, &rewards.author.amount, CleanupMode::NoEmpty);
 
 		// Bestow uncle rewards
-		let current_number = fields.header.number();
-		for u in fields.uncles.iter() {
-			fields.state.add_balance(u.author(), &(reward * U256::from(8 + u.number() - current_number) / U256::from(8)), CleanupMode::NoEmpty);
+		for u in &rewards.uncles {
+			fields.state.add_balance(&u.address, &u.amount, CleanupMode::NoEmpty);
 		}
 
 		// Commit state so that we can actually figure out the state root.
@@ -231,15 +280,24 @@ impl Engine for Ethash {
 		}
 	}
 
+	fn block_rewards(&self, header: &Header, uncles: &[Header]) -> BlockRewards {
+		let reward = self.ethash_params.block_reward;
+		let current_number = header.number();
+		BlockRewards {
+			author: Reward {
+				address: header.author().clone(),
+				amount: reward + reward / U256::from(32) * U256::from(uncles.len()),
+			},
+			uncles: uncles.iter().map(|u| Reward {
+				address: u.author().clone(),
+				amount: reward * U256::from(8 + u.number() - current_number) / U256::from(8),
+			}).collect(),
+		}
+	}
+
 	fn verify_block_basic(&self, header: &Header, _block: Option<&[u8]>) -> result::Result<(), Error> {
 		// check the seal fields.
-		if header.seal().len() != self.seal_fields() {
-			return Err(From::from(BlockError::InvalidSealArity(
-				Mismatch { expected: self.seal_fields(), found: header.seal().len() }
-			)));
-		}
-		try!(UntrustedRlp::new(&header.seal()[0]).as_val::<H256>());
-		try!(UntrustedRlp::new(&header.seal()[1]).as_val::<H64>());
+		try!(self.seal_schema.verify(header.seal()));
 
 		// TODO: consider removing these lines.
 		let min_difficulty = self.ethash_params.minimum_difficulty;
@@ -329,8 +387,31 @@ impl Engine for Ethash {
 
 #[cfg_attr(feature="dev", allow(wrong_self_convention))]
 impl Ethash {
+	/// Exponent of the difficulty bomb added to the difficulty of block `number`, if any.
+	fn bomb_exponent(&self, number: BlockNumber) -> Option<usize> {
+		let p = &self.ethash_params;
+		if number >= p.bomb_defuse_transition {
+			return None;
+		}
+
+		// the bomb behaves as if the chain was this many blocks shorter.
+		let delay = p.difficulty_bomb_delays.iter()
+			.filter(|&(block, _)| *block <= number)
+			.fold(0, |acc, (_, delay)| acc + *delay);
+		// a zero period is rejected when the spec is loaded.
+		let period = (number.saturating_sub(delay) / p.exp_diff_period) as usize;
+
+		if number < p.ecip1010_pause_transition {
+			period.checked_sub(2)
+		} else if number < p.ecip1010_continue_transition {
+			((p.ecip1010_pause_transition / p.exp_diff_period) as usize).checked_sub(2)
+		} else {
+			let pause = (p.ecip1010_continue_transition.saturating_sub(p.ecip1010_pause_transition) / p.exp_diff_period) as usize;
+			period.checked_sub(pause).and_then(|period| period.checked_sub(2))
+		}
+	}
+
 	fn calculate_difficulty(&self, header: &Header, parent: &Header) -> U256 {
-		const EXP_DIFF_PERIOD: u64 = 100000;
 		if header.number() == 0 {
 			panic!("Can't calculate genesis block difficulty");
 		}
@@ -362,22 +443,8 @@ impl Ethash {
 			}
 		};
 		target = max(min_difficulty, target);
-		if header.number() < self.ethash_params.bomb_defuse_transition {
-			if header.number() < self.ethash_params.ecip1010_pause_transition {
-				let period = ((parent.number() + 1) / EXP_DIFF_PERIOD) as usize;
-				if period > 1 {
-					target = max(min_difficulty, target + (U256::from(1) << (period - 2)));
-				}
-			}
-			else if header.number() < self.ethash_params.ecip1010_continue_transition {
-				let fixed_difficulty = ((self.ethash_params.ecip1010_pause_transition / EXP_DIFF_PERIOD) - 2) as usize;
-				target = max(min_difficulty, target + (U256::from(1) << fixed_difficulty));
-			}
-			else {
-				let period = ((parent.number() + 1) / EXP_DIFF_PERIOD) as usize;
-				let delay = ((self.ethash_params.ecip1010_continue_transition - self.ethash_params.ecip1010_pause_transition) / EXP_DIFF_PERIOD) as usize;
-				target = max(min_difficulty, target + (U256::from(1) << (period - delay - 2)));
-			}
+		if let Some(exponent) = self.bomb_exponent(parent.number() + 1) {
+			target = max(min_difficulty, target + (U256::from(1) << exponent));
 		}
 		target
 	}
@@ -466,6 +533,30 @@ mod tests {
 		assert_eq!(b.state().balance(&uncle_author), "3cb71f51fc558000".into());
 	}
 
+	#[test]
+	fn block_rewards_match_closed_block_balances() {
+		let engine = new_morden().engine;
+		let uncle_author: Address = "ef2d6d194084c2de36e0dabfce45d046b37d1106".into();
+		let mut header = Header::new();
+		header.set_number(1);
+		let mut uncle = Header::new();
+		uncle.set_author(uncle_author);
+
+		let rewards = engine.block_rewards(&header, &[uncle]);
+		assert_eq!(rewards.author.address, Address::zero());
+		assert_eq!(rewards.author.amount, "478eae0e571ba000".into());
+		assert_eq!(rewards.uncles[0].address, uncle_author);
+		assert_eq!(rewards.uncles[0].amount, "3cb71f51fc558000".into());
+		assert_eq!(rewards.total(), U256::from_str("478eae0e571ba000").unwrap() + U256::from_str("3cb71f51fc558000").unwrap());
+	}
+
+	#[test]
+	fn reports_set_transitions_only() {
+		let transitions = new_morden().engine.transitions();
+		assert_eq!(transitions.get("homesteadTransition"), Some(&494000));
+		assert!(transitions.get("ecip1010PauseTransition").is_none());
+	}
+
 	#[test]
 	fn has_valid_metadata() {
 		let engine = new_morden().engine;
@@ -720,6 +811,53 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn bomb_exponent_does_not_underflow() {
+		let spec = new_homestead_test();
+		let ethparams = EthashParams {
+			ecip1010_pause_transition: 100000,
+			ecip1010_continue_transition: 1000000,
+			..get_default_ethash_params()
+		};
+		let ethash = Ethash::new(spec.params, ethparams, BTreeMap::new());
+
+		assert_eq!(ethash.bomb_exponent(150000), None);
+		assert_eq!(ethash.bomb_exponent(1050000), None);
+		assert_eq!(ethash.bomb_exponent(1300000), Some(2));
+	}
+
+	#[test]
+	fn difficulty_bomb_delays() {
+		let spec = new_homestead_test();
+		let mut delays = BTreeMap::new();
+		delays.insert(4370000, 3000000);
+		delays.insert(7280000, 2000000);
+		let ethparams = EthashParams {
+			difficulty_bomb_delays: delays,
+			..get_default_ethash_params()
+		};
+		let ethash = Ethash::new(spec.params, ethparams, BTreeMap::new());
+
+		assert_eq!(ethash.bomb_exponent(4369999), Some(41));
+		assert_eq!(ethash.bomb_exponent(4370000), Some(11));
+		assert_eq!(ethash.bomb_exponent(7279999), Some(40));
+		assert_eq!(ethash.bomb_exponent(7280000), Some(20));
+
+		let mut parent_header = Header::default();
+		parent_header.set_number(4370000);
+		parent_header.set_difficulty(U256::from_str("6F62EAF8D3C").unwrap());
+		parent_header.set_timestamp(1452838500);
+		let mut header = Header::default();
+		header.set_number(parent_header.number() + 1);
+		header.set_timestamp(parent_header.timestamp() + 20);
+
+		// 0x6F62EAF8D3C - 0x6F62EAF8D3C / 2048 + 2^11
+		assert_eq!(
+			U256::from_str("6F54FE9BF4B").unwrap(),
+			ethash.calculate_difficulty(&header, &parent_header)
+		);
+	}
+
 	#[test]
 	fn test_difficulty_bomb_continue() {
 		let spec = new_homestead_test();
@@ -23,8 +23,11 @@
 pub mod ethash;
 /// Export the denominations module.
 pub mod denominations;
+/// Export the built-in chain registry.
+pub mod registry;
 
 pub use self::ethash::{Ethash};
+pub use ethash::CacheOptions as PowCacheOptions;
 pub use self::denominations::*;
 
 use super::spec::*;
@@ -66,6 +69,9 @@ pub fn new_mainnet_like() -> Spec { load(include_bytes!("../../res/ethereum/fron
 /// Create a new Ropsten chain spec.
 pub fn new_ropsten() -> Spec { load(include_bytes!("../../res/ethereum/ropsten.json")) }
 
+/// Create a new Kovan chain spec.
+pub fn new_kovan() -> Spec { load(include_bytes!("../../res/ethereum/kovan.json")) }
+
 /// Create a new Morden chain spec.
 pub fn new_morden() -> Spec { load(include_bytes!("../../res/ethereum/morden.json")) }
 
@@ -106,6 +112,15 @@ mod tests {
 		let _ = morden.engine;
 	}
 
+	#[test]
+	fn kovan() {
+		let kovan = new_kovan();
+
+		let genesis = kovan.genesis_block();
+		assert_eq!(BlockView::new(&genesis).header_view().sha3(), "a3c565fc15c7478862d50ccd6561e3c06b24cc509bf388941c25ea985ce32cb9".into());
+		assert_eq!(kovan.engine.params().network_id, 0x2A);
+	}
+
 	#[test]
 	fn frontier() {
 		let frontier = new_frontier();
@@ -113,6 +128,7 @@ mod tests {
 		assert_eq!(frontier.state_root(), "d7f8974fb5ac78d9ac099b9ad5018bedc2ce0a72dad1827a1709da30580f0544".into());
 		let genesis = frontier.genesis_block();
 		assert_eq!(BlockView::new(&genesis).header_view().sha3(), "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3".into());
+		assert_eq!(frontier.latest_checkpoint(), Some((1920000, "4985f5ca3d2afbec36529aa96f74de3cc10a2a4a6c44f2157a57d2c6059a11bb".into())));
 
 		let _ = frontier.engine;
 	}
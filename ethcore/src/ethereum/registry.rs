@@ -0,0 +1,88 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Registry of the chain specifications built into the client.
+
+use spec::Spec;
+
+/// Chain specification shipped with the client.
+pub struct BuiltinChain {
+	/// Canonical name of the chain.
+	pub name: &'static str,
+	/// Other names the chain can be selected by.
+	pub aliases: &'static [&'static str],
+	/// Whether the chain is a test network.
+	pub testnet: bool,
+	/// Loads the chain specification.
+	pub spec: fn() -> Spec,
+}
+
+impl BuiltinChain {
+	/// Returns true if the chain is known by the given name.
+	pub fn is_called(&self, name: &str) -> bool {
+		self.name == name || self.aliases.contains(&name)
+	}
+}
+
+/// All built-in chains.
+pub static BUILTIN_CHAINS: &'static [BuiltinChain] = &[
+	BuiltinChain { name: "mainnet", aliases: &["frontier", "homestead"], testnet: false, spec: super::new_frontier },
+	BuiltinChain { name: "classic", aliases: &["frontier-dogmatic", "homestead-dogmatic"], testnet: false, spec: super::new_classic },
+	BuiltinChain { name: "expanse", aliases: &[], testnet: false, spec: super::new_expanse },
+	BuiltinChain { name: "morden", aliases: &["testnet"], testnet: true, spec: super::new_morden },
+	BuiltinChain { name: "ropsten", aliases: &[], testnet: true, spec: super::new_ropsten },
+	BuiltinChain { name: "olympic", aliases: &[], testnet: true, spec: super::new_olympic },
+	BuiltinChain { name: "kovan", aliases: &[], testnet: true, spec: super::new_kovan },
+];
+
+/// Finds a built-in chain by its name or one of its aliases.
+pub fn find(name: &str) -> Option<&'static BuiltinChain> {
+	BUILTIN_CHAINS.iter().find(|chain| chain.is_called(name))
+}
+
+/// Names of the built-in test networks.
+pub fn testnets() -> Vec<&'static str> {
+	BUILTIN_CHAINS.iter().filter(|chain| chain.testnet).map(|chain| chain.name).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{find, testnets, BUILTIN_CHAINS};
+
+	#[test]
+	fn should_find_chains_by_alias() {
+		assert_eq!(find("homestead").unwrap().name, "mainnet");
+		assert_eq!(find("testnet").unwrap().name, "morden");
+		assert_eq!(find("ropsten").unwrap().name, "ropsten");
+		assert_eq!(find("kovan").unwrap().name, "kovan");
+		assert!(find("kovan.json").is_none());
+	}
+
+	#[test]
+	fn should_list_testnets() {
+		assert_eq!(testnets(), vec!["morden", "ropsten", "olympic", "kovan"]);
+	}
+
+	#[test]
+	fn names_should_be_unique() {
+		for (i, chain) in BUILTIN_CHAINS.iter().enumerate() {
+			for other in &BUILTIN_CHAINS[i + 1..] {
+				assert!(!other.is_called(chain.name));
+				assert!(chain.aliases.iter().all(|alias| !other.is_called(alias)));
+			}
+		}
+	}
+}
@@ -53,6 +53,8 @@ pub struct TransactOptions {
 	pub vm_tracing: bool,
 	/// Check transaction nonce before execution.
 	pub check_nonce: bool,
+	/// Do not credit the fee to the block author, leaving it to the caller.
+	pub defer_fees: bool,
 }
 
 /// Transaction executor.
@@ -62,6 +64,7 @@ pub struct Executive<'a> {
 	engine: &'a Engine,
 	vm_factory: &'a Factory,
 	depth: usize,
+	defer_fees: bool,
 }
 
 impl<'a> Executive<'a> {
@@ -73,6 +76,7 @@ impl<'a> Executive<'a> {
 			engine: engine,
 			vm_factory: vm_factory,
 			depth: 0,
+			defer_fees: false,
 		}
 	}
 
@@ -84,6 +88,7 @@ impl<'a> Executive<'a> {
 			engine: engine,
 			vm_factory: vm_factory,
 			depth: parent_depth + 1,
+			defer_fees: false,
 		}
 	}
 
@@ -102,6 +107,7 @@ impl<'a> Executive<'a> {
 	/// This function should be used to execute transaction.
 	pub fn transact(&'a mut self, t: &SignedTransaction, options: TransactOptions) -> Result<Executed, ExecutionError> {
 		let check = options.check_nonce;
+		self.defer_fees = options.defer_fees;
 		match options.tracing {
 			true => match options.vm_tracing {
 				true => self.transact_with_tracer(t, check, ExecutiveTracer::default(), ExecutiveVMTracer::toplevel()),
@@ -446,8 +452,10 @@ impl<'a> Executive<'a> {
 		trace!("exec::finalize: Refunding refund_value={}, sender={}\n", refund_value, sender);
 		// Below: NoEmpty is safe since the sender must already be non-null to have sent this transaction
 		self.state.add_balance(&sender, &refund_value, CleanupMode::NoEmpty);
-		trace!("exec::finalize: Compensating author: fees_value={}, author={}\n", fees_value, &self.info.author);
-		self.state.add_balance(&self.info.author, &fees_value, substate.to_cleanup_mode(&schedule));
+		if !self.defer_fees {
+			trace!("exec::finalize: Compensating author: fees_value={}, author={}\n", fees_value, &self.info.author);
+			self.state.add_balance(&self.info.author, &fees_value, substate.to_cleanup_mode(&schedule));
+		}
 
 		// perform suicides
 		for address in &substate.suicides {
@@ -463,7 +471,7 @@ impl<'a> Executive<'a> {
 
 		match result {
 			Err(evm::Error::Internal) => Err(ExecutionError::Internal),
-			Err(_) => {
+			Err(e) => {
 				Ok(Executed {
 					gas: t.gas,
 					gas_used: t.gas,
@@ -472,6 +480,7 @@ impl<'a> Executive<'a> {
 					logs: vec![],
 					contracts_created: vec![],
 					output: output,
+					exception: Some(e.into()),
 					trace: trace,
 					vm_trace: vm_trace,
 					state_diff: None,
@@ -486,6 +495,7 @@ impl<'a> Executive<'a> {
 					logs: substate.logs,
 					contracts_created: substate.contracts_created,
 					output: output,
+					exception: None,
 					trace: trace,
 					vm_trace: vm_trace,
 					state_diff: None,
@@ -1067,7 +1077,7 @@ mod tests {
 
 		let executed = {
 			let mut ex = Executive::new(&mut state, &info, &engine, &factory);
-			let opts = TransactOptions { check_nonce: true, tracing: false, vm_tracing: false };
+			let opts = TransactOptions { check_nonce: true, tracing: false, vm_tracing: false, defer_fees: false };
 			ex.transact(&t, opts).unwrap()
 		};
 
@@ -1101,7 +1111,7 @@ mod tests {
 
 		let res = {
 			let mut ex = Executive::new(&mut state, &info, &engine, &factory);
-			let opts = TransactOptions { check_nonce: true, tracing: false, vm_tracing: false };
+			let opts = TransactOptions { check_nonce: true, tracing: false, vm_tracing: false, defer_fees: false };
 			ex.transact(&t, opts)
 		};
 
@@ -1133,7 +1143,7 @@ mod tests {
 
 		let res = {
 			let mut ex = Executive::new(&mut state, &info, &engine, &factory);
-			let opts = TransactOptions { check_nonce: true, tracing: false, vm_tracing: false };
+			let opts = TransactOptions { check_nonce: true, tracing: false, vm_tracing: false, defer_fees: false };
 			ex.transact(&t, opts)
 		};
 
@@ -1167,7 +1177,7 @@ mod tests {
 
 		let res = {
 			let mut ex = Executive::new(&mut state, &info, &engine, &factory);
-			let opts = TransactOptions { check_nonce: true, tracing: false, vm_tracing: false };
+			let opts = TransactOptions { check_nonce: true, tracing: false, vm_tracing: false, defer_fees: false };
 			ex.transact(&t, opts)
 		};
 
@@ -1201,7 +1211,7 @@ mod tests {
 
 		let res = {
 			let mut ex = Executive::new(&mut state, &info, &engine, &factory);
-			let opts = TransactOptions { check_nonce: true, tracing: false, vm_tracing: false };
+			let opts = TransactOptions { check_nonce: true, tracing: false, vm_tracing: false, defer_fees: false };
 			ex.transact(&t, opts)
 		};
 
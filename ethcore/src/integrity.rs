@@ -0,0 +1,235 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Offline integrity checking of stored block data.
+//!
+//! Bodies and receipts of canonical blocks are hashed and compared with the roots in their
+//! headers. Damaged blocks can be scheduled for re-download: they are removed from the
+//! database and reopened as the gap which ancient block sync fills in the background.
+
+use std::fmt;
+use std::sync::Arc;
+
+use blockchain::BlockChain;
+use header::BlockNumber;
+use util::{H256, Mismatch};
+use util::kvdb::KeyValueDB;
+
+/// Number of blocks between progress reports.
+const PROGRESS_INTERVAL: BlockNumber = 10000;
+
+/// Damage found in the stored data of a block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Corruption {
+	/// Header is missing.
+	MissingHeader,
+	/// Header does not hash to the block hash or cannot be decoded.
+	InvalidHeader,
+	/// Body is missing.
+	MissingBody,
+	/// Body cannot be decoded.
+	InvalidBody,
+	/// Transactions do not hash to the transactions root in the header.
+	TransactionsRoot(Mismatch<H256>),
+	/// Uncles do not hash to the uncles hash in the header.
+	UnclesHash(Mismatch<H256>),
+	/// Receipts are missing.
+	MissingReceipts,
+	/// Receipts cannot be decoded.
+	InvalidReceipts,
+	/// Receipts do not hash to the receipts root in the header.
+	ReceiptsRoot(Mismatch<H256>),
+}
+
+impl fmt::Display for Corruption {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Corruption::MissingHeader => write!(f, "header is missing"),
+			Corruption::InvalidHeader => write!(f, "header is damaged"),
+			Corruption::MissingBody => write!(f, "body is missing"),
+			Corruption::InvalidBody => write!(f, "body cannot be decoded"),
+			Corruption::TransactionsRoot(ref mis) => write!(f, "transactions root mismatch: {}", mis),
+			Corruption::UnclesHash(ref mis) => write!(f, "uncles hash mismatch: {}", mis),
+			Corruption::MissingReceipts => write!(f, "receipts are missing"),
+			Corruption::InvalidReceipts => write!(f, "receipts cannot be decoded"),
+			Corruption::ReceiptsRoot(ref mis) => write!(f, "receipts root mismatch: {}", mis),
+		}
+	}
+}
+
+/// A canonical block whose stored data is damaged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorruptedBlock {
+	/// Block number.
+	pub number: BlockNumber,
+	/// Block hash.
+	pub hash: H256,
+	/// What is wrong with the block.
+	pub corruption: Corruption,
+}
+
+/// Reasons damaged blocks cannot be scheduled for re-download.
+#[derive(Debug, PartialEq)]
+pub enum RepairError {
+	/// Blocks between the given numbers have not been downloaded yet.
+	GapPending(BlockNumber, BlockNumber),
+	/// The best block is damaged; only blocks below it can be re-downloaded.
+	BestBlock(BlockNumber),
+	/// Low-level database error.
+	Database(String),
+}
+
+impl fmt::Display for RepairError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			RepairError::GapPending(from, to) => write!(f, "Blocks #{} to #{} are still being downloaded; run again once sync has caught up", from, to),
+			RepairError::BestBlock(number) => write!(f, "The best block #{} is damaged and cannot be re-downloaded", number),
+			RepairError::Database(ref e) => write!(f, "Database error: {}", e),
+		}
+	}
+}
+
+/// Check bodies and receipts of all canonical blocks above the genesis stored in `db`.
+/// Blocks in a gap which has not been downloaded yet are skipped.
+pub fn check_chain(db: Arc<KeyValueDB>) -> Vec<CorruptedBlock> {
+	let chain = BlockChain::new(Default::default(), &[], db);
+	let info = chain.chain_info();
+
+	// with a gap, ancient blocks end at the best ancient block and recent ones start at the first block.
+	let mut ranges = vec![(1, info.ancient_block_number.unwrap_or(info.best_block_number))];
+	if let (Some(_), Some(first)) = (info.ancient_block_number, info.first_block_number) {
+		ranges.push((first, info.best_block_number));
+	}
+
+	let mut corrupted = Vec::new();
+	for (from, to) in ranges {
+		for (number, hash) in chain.canonical_iter(from, to) {
+			if let Err(corruption) = chain.check_block_data(&hash) {
+				warn!("Block #{} ({}): {}", number, hash, corruption);
+				corrupted.push(CorruptedBlock {
+					number: number,
+					hash: hash,
+					corruption: corruption,
+				});
+			}
+			if number % PROGRESS_INTERVAL == 0 {
+				info!("Checked block #{}", number);
+			}
+		}
+	}
+	corrupted
+}
+
+/// Remove the canonical blocks from the lowest to the highest `corrupted` one, so that
+/// ancient block sync downloads them again the next time the client runs.
+/// Returns the range of blocks removed, or `None` if there was nothing to remove.
+pub fn schedule_redownload(db: Arc<KeyValueDB>, corrupted: &[CorruptedBlock]) -> Result<Option<(BlockNumber, BlockNumber)>, RepairError> {
+	let from = match corrupted.iter().map(|b| b.number).min() {
+		Some(from) => from,
+		None => return Ok(None),
+	};
+	let to = corrupted.iter().map(|b| b.number).max().expect("corrupted is not empty; qed");
+
+	let chain = BlockChain::new(Default::default(), &[], db.clone());
+	let info = chain.chain_info();
+	if let (Some(ancient), Some(first)) = (info.ancient_block_number, info.first_block_number) {
+		return Err(RepairError::GapPending(ancient + 1, first - 1));
+	}
+	if to >= info.best_block_number {
+		return Err(RepairError::BestBlock(info.best_block_number));
+	}
+
+	let mut batch = db.transaction();
+	chain.prepare_redownload(&mut batch, from, to);
+	try!(db.write(batch).map_err(RepairError::Database));
+	try!(db.flush().map_err(RepairError::Database));
+
+	Ok(Some((from, to)))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use util::{H256, Database, DatabaseConfig, KeyValueDB};
+	use util::sha3::Hashable;
+	use devtools::RandomTempPath;
+	use blockchain::{BlockChain, BlockProvider, Config};
+	use blockchain::extras::BlockReceipts;
+	use blockchain::generator::{ChainGenerator, ChainIterator, BlockFinalizer};
+	use views::BlockView;
+	use super::{check_chain, schedule_redownload, Corruption};
+
+	fn chain_with_blocks(path: &str, count: usize) -> (Arc<KeyValueDB>, Vec<H256>) {
+		let db: Arc<KeyValueDB> = Arc::new(Database::open(&DatabaseConfig::with_columns(::db::NUM_COLUMNS), path).unwrap());
+		let mut canon_chain = ChainGenerator::default();
+		let mut finalizer = BlockFinalizer::default();
+		let genesis = canon_chain.generate(&mut finalizer).unwrap();
+		let bc = BlockChain::new(Config::default(), &genesis, db.clone());
+
+		let mut hashes = vec![BlockView::new(&genesis).header_view().sha3()];
+		for _ in 0..count {
+			let block = canon_chain.generate(&mut finalizer).unwrap();
+			hashes.push(BlockView::new(&block).header_view().sha3());
+			let mut batch = db.transaction();
+			bc.insert_block(&mut batch, &block, vec![]);
+			db.write(batch).unwrap();
+			bc.commit();
+		}
+		(db, hashes)
+	}
+
+	#[test]
+	fn detects_damaged_body_and_receipts() {
+		let temp = RandomTempPath::new();
+		let (db, hashes) = chain_with_blocks(temp.as_str(), 5);
+		assert!(check_chain(db.clone()).is_empty());
+
+		let mut batch = db.transaction();
+		batch.put(::db::COL_BODIES, &hashes[2], &[0xc2, 0xc0, 0x01]);
+		batch.delete(::db::COL_EXTRA, &<H256 as ::db::Key<BlockReceipts>>::key(&hashes[4]));
+		db.write(batch).unwrap();
+
+		let corrupted = check_chain(db);
+		assert_eq!(corrupted.len(), 2);
+		assert_eq!(corrupted[0].number, 2);
+		assert_eq!(corrupted[0].corruption, Corruption::InvalidBody);
+		assert_eq!(corrupted[1].number, 4);
+		assert_eq!(corrupted[1].corruption, Corruption::MissingReceipts);
+	}
+
+	#[test]
+	fn reopens_damaged_range_as_gap() {
+		let temp = RandomTempPath::new();
+		let (db, hashes) = chain_with_blocks(temp.as_str(), 8);
+
+		let mut batch = db.transaction();
+		batch.delete(::db::COL_BODIES, &hashes[3]);
+		batch.delete(::db::COL_BODIES, &hashes[5]);
+		db.write(batch).unwrap();
+
+		let corrupted = check_chain(db.clone());
+		assert_eq!(schedule_redownload(db.clone(), &corrupted), Ok(Some((3, 5))));
+
+		let bc = BlockChain::new(Config::default(), &[], db.clone());
+		let info = bc.chain_info();
+		assert_eq!(info.ancient_block_hash, Some(hashes[2].clone()));
+		assert_eq!(info.first_block_hash, Some(hashes[6].clone()));
+		assert!(!bc.is_known(&hashes[4]));
+		assert_eq!(bc.block_hash(4), None);
+		assert!(bc.block_details(&hashes[2]).unwrap().children.is_empty());
+		assert!(check_chain(db).is_empty());
+	}
+}
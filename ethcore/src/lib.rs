@@ -93,6 +93,7 @@ extern crate rayon;
 extern crate hyper;
 extern crate ethash;
 extern crate ethkey;
+extern crate ethcrypto;
 extern crate semver;
 extern crate ethcore_ipc_nano as nanoipc;
 extern crate ethcore_devtools as devtools;
@@ -138,7 +139,10 @@ pub mod miner;
 pub mod snapshot;
 pub mod action_params;
 pub mod db;
+pub mod integrity;
 pub mod verification;
+pub mod proved_execution;
+pub mod private_transactions;
 #[macro_use] pub mod evm;
 
 mod cache_manager;
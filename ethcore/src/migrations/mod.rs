@@ -26,3 +26,6 @@ pub use self::v9::Extract;
 
 mod v10;
 pub use self::v10::ToV10;
+
+mod v11;
+pub use self::v11::ToV11;
@@ -0,0 +1,106 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Receipts dictionary compression.
+
+use std::sync::Arc;
+use db::COL_EXTRA;
+use blockchain::extras::{BlockReceipts, ExtrasIndex};
+use rlp::{self, UntrustedRlp, View};
+use util::migration::{Error, Migration, Progress, Batch, Config};
+use util::Database;
+
+/// Re-encodes block receipts in the current storage format.
+fn compress_receipts(value: &[u8]) -> Result<Vec<u8>, Error> {
+	let receipts: BlockReceipts = try!(UntrustedRlp::new(value).as_val()
+		.map_err(|e| Error::Custom(format!("Invalid block receipts: {:?}", e))));
+	Ok(rlp::encode(&receipts).to_vec())
+}
+
+/// Stores block receipts with per-block address and topic dictionaries.
+#[derive(Default)]
+pub struct ToV11 {
+	progress: Progress,
+}
+
+impl ToV11 {
+	/// New v11 migration
+	pub fn new() -> ToV11 { ToV11 { progress: Progress::default() } }
+}
+
+impl Migration for ToV11 {
+	fn version(&self) -> u32 {
+		11
+	}
+
+	fn columns(&self) -> Option<u32> { Some(6) }
+
+	fn migrate(&mut self, source: Arc<Database>, config: &Config, dest: &mut Database, col: Option<u32>) -> Result<(), Error> {
+		let mut batch = Batch::new(config, col);
+		for (key, value) in source.iter(col) {
+			self.progress.tick();
+			let is_receipts = col == COL_EXTRA && key.len() == 33 && key[0] == ExtrasIndex::BlockReceipts as u8;
+			let value = match is_receipts {
+				true => try!(compress_receipts(&value)),
+				false => value.to_vec(),
+			};
+			try!(batch.insert(key.to_vec(), value, dest));
+		}
+		batch.commit(dest)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rlp::{self, RlpStream, Stream};
+	use util::{Address, H256, U256};
+	use blockchain::extras::{BlockReceipts, BlockReceiptsView};
+	use log_entry::LogEntry;
+	use receipt::Receipt;
+	use super::compress_receipts;
+
+	#[test]
+	fn should_compress_legacy_receipts() {
+		let log = LogEntry {
+			address: Address::from(1u64),
+			topics: vec![H256::from(2u64), H256::from(2u64)],
+			data: vec![1, 2, 3],
+		};
+		let receipts = vec![
+			Receipt::new(H256::from(3u64), U256::from(21_000), vec![log.clone()]),
+			Receipt::new(H256::from(4u64), U256::from(42_000), vec![log.clone(), log]),
+		];
+
+		let mut legacy = RlpStream::new();
+		legacy.append(&receipts);
+		let legacy = legacy.out();
+		assert!(!BlockReceiptsView::new(&legacy).is_compressed());
+
+		let compressed = compress_receipts(&legacy).unwrap();
+		assert!(compressed.len() < legacy.len());
+
+		let view = BlockReceiptsView::new(&compressed);
+		assert!(view.is_compressed());
+		assert_eq!(view.addresses().unwrap(), Some(vec![Address::from(1u64)]));
+		assert_eq!(view.topics().unwrap(), Some(vec![H256::from(2u64)]));
+
+		let decoded: BlockReceipts = rlp::decode(&compressed);
+		assert_eq!(rlp::encode(&decoded.receipts).to_vec(), legacy);
+
+		// already compressed receipts are left as they are.
+		assert_eq!(compress_receipts(&compressed).unwrap(), compressed);
+	}
+}
@@ -43,7 +43,8 @@ pub enum Status {
 }
 
 impl Status {
-	fn is_current(&self) -> bool {
+	/// Returns true if the transaction is still in the queue.
+	pub fn is_current(&self) -> bool {
 		*self == Status::Pending || *self == Status::Future
 	}
 }
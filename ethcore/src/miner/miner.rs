@@ -32,11 +32,12 @@ use transaction::{Action, SignedTransaction};
 use receipt::{Receipt, RichReceipt};
 use spec::Spec;
 use engines::Engine;
-use miner::{MinerService, MinerStatus, TransactionQueue, PrioritizationStrategy, AccountDetails, TransactionOrigin};
+use miner::{MinerService, MinerStatus, TransactionQueue, PrioritizationStrategy, AccountDetails, TransactionOrigin, NonceGap};
 use miner::banning_queue::{BanningTransactionQueue, Threshold};
 use miner::work_notify::WorkPoster;
 use miner::price_info::PriceInfo;
 use miner::local_transactions::{Status as LocalTransactionStatus};
+use miner::transaction_filter::TransactionFilter;
 use header::BlockNumber;
 
 /// Different possible definitions for pending transaction set.
@@ -85,12 +86,15 @@ pub struct MinerOptions {
 	pub new_work_notify: Vec<String>,
 	/// Force the miner to reseal, even when nobody has asked for work.
 	pub force_sealing: bool,
-	/// Reseal on receipt of new external transactions.
+	/// Reseal on receipt of new external transactions. When disabled, gossiped transactions
+	/// are only picked up with the next block or when the pending block expires.
 	pub reseal_on_external_tx: bool,
 	/// Reseal on receipt of new local transactions.
 	pub reseal_on_own_tx: bool,
 	/// Minimum period between transaction-inspired reseals.
 	pub reseal_min_period: Duration,
+	/// Maximum period a pending block is served before it is re-prepared, even if nothing changed.
+	pub reseal_max_period: Duration,
 	/// Maximum amount of gas to bother considering for block insertion.
 	pub tx_gas_limit: U256,
 	/// Maximum size of the transaction queue.
@@ -107,6 +111,8 @@ pub struct MinerOptions {
 	pub tx_queue_gas_limit: GasLimit,
 	/// Banning settings
 	pub tx_queue_banning: Banning,
+	/// Maximal time a non-local transaction may wait in the future queue before being dropped.
+	pub tx_queue_max_future_age: Option<Duration>,
 }
 
 impl Default for MinerOptions {
@@ -122,9 +128,11 @@ impl Default for MinerOptions {
 			tx_queue_strategy: PrioritizationStrategy::GasPriceOnly,
 			pending_set: PendingSet::AlwaysQueue,
 			reseal_min_period: Duration::from_secs(2),
+			reseal_max_period: Duration::from_secs(120),
 			work_queue_size: 20,
 			enable_resubmission: true,
 			tx_queue_banning: Banning::Disabled,
+			tx_queue_max_future_age: None,
 		}
 	}
 }
@@ -204,6 +212,19 @@ impl GasPricer {
 struct SealingWork {
 	queue: UsingQueue<ClosedBlock>,
 	enabled: bool,
+	/// Transaction set changed since the pending block was last prepared.
+	transactions_changed: bool,
+	/// When the pending block was last prepared.
+	last_prepared: Instant,
+}
+
+impl SealingWork {
+	/// Returns true if the pending block built on top of `best_hash` can be served as is.
+	fn is_fresh(&self, best_hash: &H256, min_period: Duration, max_period: Duration) -> bool {
+		let built_on_best = self.queue.peek_last_ref().map_or(false, |b| b.block().fields().header.parent_hash() == best_hash);
+		let age = self.last_prepared.elapsed();
+		built_on_best && age < max_period && !(self.transactions_changed && age >= min_period)
+	}
 }
 
 /// Keeps track of transactions using priority queue and holds currently mined block.
@@ -227,6 +248,10 @@ pub struct Miner {
 	accounts: Option<Arc<AccountProvider>>,
 	work_poster: Option<WorkPoster>,
 	gas_pricer: Mutex<GasPricer>,
+	transaction_filters: RwLock<Vec<Arc<TransactionFilter>>>,
+	gas_limit_contract: Option<Address>,
+	seal_interval: RwLock<Option<Duration>>,
+	last_interval_seal: Mutex<Instant>,
 }
 
 impl Miner {
@@ -258,7 +283,9 @@ impl Miner {
 				queue: UsingQueue::new(options.work_queue_size),
 				enabled: options.force_sealing
 					|| !options.new_work_notify.is_empty()
-					|| spec.engine.is_default_sealer().unwrap_or(false)
+					|| spec.engine.is_default_sealer().unwrap_or(false),
+				transactions_changed: false,
+				last_prepared: Instant::now(),
 			}),
 			seals_internally: spec.engine.is_default_sealer().is_some(),
 			gas_range_target: RwLock::new((U256::zero(), U256::zero())),
@@ -269,6 +296,10 @@ impl Miner {
 			engine: spec.engine.clone(),
 			work_poster: work_poster,
 			gas_pricer: Mutex::new(gas_pricer),
+			transaction_filters: RwLock::new(Vec::new()),
+			gas_limit_contract: spec.params.gas_limit_contract,
+			seal_interval: RwLock::new(None),
+			last_interval_seal: Mutex::new(Instant::now()),
 		}
 	}
 
@@ -287,6 +318,49 @@ impl Miner {
 		Arc::new(Miner::new_raw(options, gas_pricer, spec, accounts))
 	}
 
+	/// Seal a block if the seal interval has passed since the last one. Should be called periodically.
+	pub fn seal_interval_tick(&self, chain: &MiningBlockChainClient) {
+		let interval = match *self.seal_interval.read() {
+			Some(interval) => interval,
+			None => return,
+		};
+		if Instant::now() < *self.last_interval_seal.lock() + interval {
+			return;
+		}
+		trace!(target: "miner", "seal_interval_tick: sealing a block");
+		self.force_seal(chain);
+	}
+
+	/// Add a filter consulted before transactions are queued or included in a block.
+	/// Transactions must be allowed by every filter.
+	pub fn add_transaction_filter(&self, filter: Arc<TransactionFilter>) {
+		self.transaction_filters.write().push(filter);
+	}
+
+	fn transaction_allowed(&self, chain: &MiningBlockChainClient, tx: &SignedTransaction) -> bool {
+		self.transaction_filters.read().iter().all(|filter| filter.transaction_allowed(chain, tx))
+	}
+
+	/// Gas range target for the next block. Set by the gas limit contract, if there is one,
+	/// otherwise by the configured floor and ceiling.
+	fn gas_range_target(&self, chain: &MiningBlockChainClient) -> (U256, U256) {
+		let governed = self.gas_limit_contract
+			.and_then(|address| chain.storage_at(&address, &H256::zero(), BlockID::Latest))
+			.map(U256::from)
+			.and_then(|limit| match limit.is_zero() {
+				true => None,
+				false => Some(limit),
+			});
+
+		match governed {
+			Some(limit) => {
+				trace!(target: "miner", "Gas limit target set by contract: {}", limit);
+				(limit, limit)
+			},
+			None => (self.gas_floor_target(), self.gas_ceil_target()),
+		}
+	}
+
 	fn forced_sealing(&self) -> bool {
 		self.options.force_sealing || !self.options.new_work_notify.is_empty()
 	}
@@ -296,6 +370,13 @@ impl Miner {
 		self.sealing_work.lock().queue.reset();
 	}
 
+	/// Stop sealing and drop pending sealing work. Sealing is re-enabled on the next work request.
+	pub fn stop_sealing(&self) {
+		let mut sealing_work = self.sealing_work.lock();
+		sealing_work.enabled = false;
+		sealing_work.queue.reset();
+	}
+
 	/// Get `Some` `clone()` of the current pending block's state or `None` if we're not sealing.
 	pub fn pending_state(&self) -> Option<State> {
 		self.sealing_work.lock().queue.peek_last_ref().map(|b| b.block().fields().state.clone())
@@ -323,6 +404,8 @@ impl Miner {
 		let (transactions, mut open_block, original_work_hash) = {
 			let transactions = {self.transaction_queue.lock().top_transactions()};
 			let mut sealing_work = self.sealing_work.lock();
+			sealing_work.transactions_changed = false;
+			sealing_work.last_prepared = Instant::now();
 			let last_work_hash = sealing_work.queue.peek_last_ref().map(|pb| pb.block().fields().header.hash());
 			let best_hash = chain.best_block_header().sha3();
 /*
@@ -344,7 +427,7 @@ impl Miner {
 					trace!(target: "miner", "prepare_block: No existing work - making new block");
 					chain.prepare_open_block(
 						self.author(),
-						(self.gas_floor_target(), self.gas_ceil_target()),
+						self.gas_range_target(chain),
 						self.extra_data()
 					)
 				}
@@ -359,6 +442,12 @@ impl Miner {
 		// TODO Push new uncles too.
 		for tx in transactions {
 			let hash = tx.hash();
+			// permissions may change, so the transaction stays queued.
+			if !self.transaction_allowed(chain, &tx) {
+				debug!(target: "miner", "Skipping transaction {:?} not allowed by the transaction filter", hash);
+				continue;
+			}
+
 			let start = Instant::now();
 			let result = open_block.push_transaction(tx, None);
 			let took = start.elapsed();
@@ -534,11 +623,15 @@ impl Miner {
 	}
 
 	/// Returns true if we had to prepare new pending block.
+	///
+	/// The pending block is cached and only re-prepared when the chain head or the
+	/// transaction set has changed (subject to `reseal_min_period`) or it is older than `reseal_max_period`.
 	fn prepare_work_sealing(&self, chain: &MiningBlockChainClient) -> bool {
 		trace!(target: "miner", "prepare_work_sealing: entering");
+		let best_hash = chain.chain_info().best_block_hash;
 		let prepare_new = {
 			let mut sealing_work = self.sealing_work.lock();
-			let have_work = sealing_work.queue.peek_last_ref().is_some();
+			let have_work = sealing_work.is_fresh(&best_hash, self.options.reseal_min_period, self.options.reseal_max_period);
 			trace!(target: "miner", "prepare_work_sealing: have_work={}", have_work);
 			if !have_work {
 				sealing_work.enabled = true;
@@ -588,6 +681,10 @@ impl Miner {
 						debug!(target: "miner", "Rejected tx {:?} with invalid signature: {:?}", tx.hash(), e);
 						Err(e)
 					},
+					Ok(()) if !self.transaction_allowed(chain, &tx) => {
+						debug!(target: "miner", "Rejected tx {:?} not allowed by the transaction filter", tx.hash());
+						Err(TransactionError::NotAllowed.into())
+					},
 					Ok(()) => {
 						let origin = accounts.as_ref().and_then(|accounts| {
 							tx.sender().ok().and_then(|sender| match accounts.contains(&sender) {
@@ -651,6 +748,7 @@ impl MinerService for Miner {
 			transactions_in_pending_queue: status.pending,
 			transactions_in_future_queue: status.future,
 			transactions_in_pending_block: sealing_work.queue.peek_last_ref().map_or(0, |b| b.transactions().len()),
+			transactions_mem_used: status.mem_used,
 		}
 	}
 
@@ -686,7 +784,7 @@ impl MinerService for Miner {
 					// give the sender a sufficient balance
 					state.add_balance(&sender, &(needed_balance - balance), CleanupMode::NoEmpty);
 				}
-				let options = TransactOptions { tracing: analytics.transaction_tracing, vm_tracing: analytics.vm_tracing, check_nonce: false };
+				let options = TransactOptions { tracing: analytics.transaction_tracing, vm_tracing: analytics.vm_tracing, check_nonce: false, defer_fees: false };
 				let mut ret = try!(Executive::new(&mut state, &env_info, &*self.engine, chain.vm_factory()).transact(t, options));
 
 				// TODO gav move this into Executive.
@@ -815,6 +913,10 @@ impl MinerService for Miner {
 			)
 		};
 
+		if self.options.reseal_on_external_tx && results.iter().any(|r| r.is_ok()) {
+			self.sealing_work.lock().transactions_changed = true;
+		}
+
 		if !results.is_empty() && self.options.reseal_on_external_tx &&	self.tx_reseal_allowed() {
 			// --------------------------------------------------------------------------
 			// | NOTE Code below requires transaction_queue and sealing_work locks.     |
@@ -856,6 +958,10 @@ impl MinerService for Miner {
 			import
 		};
 
+		if imported.is_ok() {
+			self.sealing_work.lock().transactions_changed = true;
+		}
+
 		// --------------------------------------------------------------------------
 		// | NOTE Code below requires transaction_queue and sealing_work locks.     |
 		// | Make sure to release the locks before calling that method.             |
@@ -879,6 +985,16 @@ impl MinerService for Miner {
 		queue.top_transactions()
 	}
 
+	fn future_transactions(&self) -> Vec<SignedTransaction> {
+		let queue = self.transaction_queue.lock();
+		queue.future_transactions()
+	}
+
+	fn nonce_gaps(&self) -> Vec<NonceGap> {
+		let queue = self.transaction_queue.lock();
+		queue.nonce_gaps(Instant::now())
+	}
+
 	fn local_transactions(&self) -> BTreeMap<H256, LocalTransactionStatus> {
 		let queue = self.transaction_queue.lock();
 		queue.local_transactions()
@@ -1032,6 +1148,24 @@ impl MinerService for Miner {
 		self.sealing_work.lock().queue.is_in_use()
 	}
 
+	fn force_seal(&self, chain: &MiningBlockChainClient) -> Option<H256> {
+		if !self.seals_internally {
+			return None;
+		}
+		*self.last_interval_seal.lock() = Instant::now();
+
+		let (block, _) = self.prepare_block(chain);
+		match self.seal_block_internally(block) {
+			Ok(sealed) => chain.import_sealed_block(sealed).ok(),
+			Err(_) => None,
+		}
+	}
+
+	fn set_seal_interval(&self, interval: Option<Duration>) {
+		*self.last_interval_seal.lock() = Instant::now();
+		*self.seal_interval.write() = interval;
+	}
+
 	fn map_sealing_work<F, T>(&self, chain: &MiningBlockChainClient, f: F) -> Option<T> where F: FnOnce(&ClosedBlock) -> T {
 		trace!(target: "miner", "map_sealing_work: entering");
 		self.prepare_work_sealing(chain);
@@ -1128,6 +1262,14 @@ impl MinerService for Miner {
 			});
 		}
 
+		// Drop transactions which have been waiting for a nonce gap to be filled for too long
+		if let Some(max_age) = self.options.tx_queue_max_future_age {
+			let removed = self.transaction_queue.lock().remove_old_future(max_age, Instant::now());
+			if removed > 0 {
+				debug!(target: "miner", "Dropped {} old transactions from the future queue.", removed);
+			}
+		}
+
 		if enacted.len() > 0 {
 			// --------------------------------------------------------------------------
 			// | NOTE Code below requires transaction_queue and sealing_work locks.     |
@@ -1186,6 +1328,52 @@ mod tests {
 		assert!(miner.submit_seal(&client, res.unwrap(), vec![]).is_ok());
 	}
 
+	#[test]
+	fn should_reuse_pending_block_until_transactions_change() {
+		// given
+		let client = TestBlockChainClient::default();
+		let miner = Miner::new_raw(MinerOptions {
+			reseal_on_external_tx: true,
+			reseal_min_period: Duration::from_secs(0),
+			..Default::default()
+		}, GasPricer::new_fixed(0u64.into()), &Spec::new_test(), None);
+
+		let first = miner.map_sealing_work(&client, |b| b.block().fields().header.hash()).unwrap();
+		assert_eq!(miner.map_sealing_work(&client, |b| b.block().fields().header.hash()).unwrap(), first);
+
+		// when
+		let res = miner.import_external_transactions(&client, vec![transaction()]).pop().unwrap();
+		assert_eq!(res.unwrap(), TransactionImportResult::Current);
+
+		// then
+		let second = miner.map_sealing_work(&client, |b| b.transactions().len()).unwrap();
+		assert_eq!(second, 1);
+
+		// new head invalidates the cached block
+		client.add_blocks(1, EachBlockWith::Nothing);
+		let parent = miner.map_sealing_work(&client, |b| *b.block().fields().header.parent_hash()).unwrap();
+		assert_eq!(parent, client.chain_info().best_block_hash);
+	}
+
+	#[test]
+	fn should_not_reprepare_on_external_transactions_when_disabled() {
+		// given
+		let client = TestBlockChainClient::default();
+		let miner = Miner::new_raw(MinerOptions {
+			reseal_on_external_tx: false,
+			reseal_min_period: Duration::from_secs(0),
+			..Default::default()
+		}, GasPricer::new_fixed(0u64.into()), &Spec::new_test(), None);
+		assert_eq!(miner.map_sealing_work(&client, |b| b.transactions().len()), Some(0));
+
+		// when
+		miner.import_external_transactions(&client, vec![transaction()]).pop().unwrap().unwrap();
+
+		// then
+		assert_eq!(miner.map_sealing_work(&client, |b| b.transactions().len()), Some(0));
+		assert_eq!(miner.status().transactions_in_pending_queue, 1);
+	}
+
 	fn miner() -> Miner {
 		Arc::try_unwrap(Miner::new(
 			MinerOptions {
@@ -1194,6 +1382,7 @@ mod tests {
 				reseal_on_external_tx: false,
 				reseal_on_own_tx: true,
 				reseal_min_period: Duration::from_secs(5),
+				reseal_max_period: Duration::from_secs(120),
 				tx_gas_limit: !U256::zero(),
 				tx_queue_size: 1024,
 				tx_queue_gas_limit: GasLimit::None,
@@ -1202,6 +1391,7 @@ mod tests {
 				work_queue_size: 5,
 				enable_resubmission: true,
 				tx_queue_banning: Banning::Disabled,
+				tx_queue_max_future_age: None,
 			},
 			GasPricer::new_fixed(0u64.into()),
 			&Spec::new_test(),
@@ -1279,6 +1469,67 @@ mod tests {
 		assert!(miner.prepare_work_sealing(&client));
 	}
 
+	#[test]
+	fn should_take_gas_limit_target_from_contract() {
+		// given
+		let client = TestBlockChainClient::default();
+		let contract = Address::from(7);
+		let mut spec = Spec::new_test();
+		spec.params.gas_limit_contract = Some(contract);
+		let miner = Miner::with_spec(&spec);
+		miner.set_gas_floor_target(1_000_000.into());
+		miner.set_gas_ceil_target(2_000_000.into());
+
+		// then
+		assert_eq!(miner.gas_range_target(&client), (1_000_000.into(), 2_000_000.into()));
+		client.set_storage(contract, H256::zero(), H256::from(U256::from(8_000_000)));
+		assert_eq!(miner.gas_range_target(&client), (8_000_000.into(), 8_000_000.into()));
+	}
+
+	struct DenyAll;
+
+	impl TransactionFilter for DenyAll {
+		fn transaction_allowed(&self, _chain: &MiningBlockChainClient, _tx: &SignedTransaction) -> bool {
+			false
+		}
+	}
+
+	#[test]
+	fn should_reject_transactions_not_allowed_by_filter() {
+		// given
+		let client = TestBlockChainClient::default();
+		let miner = miner();
+		miner.add_transaction_filter(Arc::new(DenyAll));
+
+		// when
+		let res = miner.import_external_transactions(&client, vec![transaction()]).pop().unwrap();
+		let own = miner.import_own_transaction(&client, transaction());
+
+		// then
+		match res {
+			Err(Error::Transaction(TransactionError::NotAllowed)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert!(own.is_err());
+		assert_eq!(miner.all_transactions().len(), 0);
+	}
+
+	#[test]
+	fn should_not_seal_transactions_not_allowed_by_filter() {
+		// given
+		let client = TestBlockChainClient::default();
+		let miner = miner();
+		miner.import_external_transactions(&client, vec![transaction()]).pop().unwrap().unwrap();
+
+		// when
+		miner.add_transaction_filter(Arc::new(DenyAll));
+
+		// then
+		assert!(miner.prepare_work_sealing(&client));
+		assert_eq!(miner.pending_transactions(0).len(), 0);
+		assert_eq!(miner.all_transactions().len(), 1);
+	}
+
 	#[test]
 	fn should_not_seal_unless_enabled() {
 		let miner = miner();
@@ -1292,6 +1543,19 @@ mod tests {
 		assert!(miner.requires_reseal(1u8.into()));
 	}
 
+	#[test]
+	fn should_not_reseal_after_stopping() {
+		let miner = miner();
+		let client = TestBlockChainClient::default();
+		miner.import_external_transactions(&client, vec![transaction()]).pop().unwrap().unwrap();
+		assert!(miner.prepare_work_sealing(&client));
+
+		miner.stop_sealing();
+
+		assert!(!miner.requires_reseal(1u8.into()));
+		assert!(!miner.is_sealing());
+	}
+
 	#[test]
 	fn internal_seals_without_work() {
 		let miner = Miner::with_spec(&Spec::new_instant());
@@ -1313,4 +1577,17 @@ mod tests {
 		assert!(miner.pending_block().is_none());
 		assert_eq!(client.chain_info().best_block_number, 4 as BlockNumber);
 	}
+
+	#[test]
+	fn force_seals_empty_blocks() {
+		let c = generate_dummy_client(2);
+		let client = c.reference().as_ref();
+
+		assert!(Miner::with_spec(&Spec::new_test()).force_seal(client).is_none());
+
+		let miner = Miner::with_spec(&Spec::new_instant());
+		let hash = miner.force_seal(client).unwrap();
+		assert_eq!(client.chain_info().best_block_hash, hash);
+		assert_eq!(client.chain_info().best_block_number, 3 as BlockNumber);
+	}
 }
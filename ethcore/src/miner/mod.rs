@@ -47,15 +47,18 @@ mod local_transactions;
 mod miner;
 mod price_info;
 mod transaction_queue;
+mod transaction_filter;
 mod work_notify;
 
 pub use self::external::{ExternalMiner, ExternalMinerService};
 pub use self::miner::{Miner, MinerOptions, Banning, PendingSet, GasPricer, GasPriceCalibratorOptions, GasLimit};
-pub use self::transaction_queue::{TransactionQueue, PrioritizationStrategy, AccountDetails, TransactionOrigin};
+pub use self::transaction_queue::{TransactionQueue, PrioritizationStrategy, AccountDetails, TransactionOrigin, NonceGap};
 pub use self::local_transactions::{Status as LocalTransactionStatus};
+pub use self::transaction_filter::TransactionFilter;
 pub use client::TransactionImportResult;
 
 use std::collections::BTreeMap;
+use std::time::Duration;
 use util::{H256, U256, Address, Bytes};
 use client::{MiningBlockChainClient, Executed, CallAnalytics};
 use block::ClosedBlock;
@@ -144,6 +147,12 @@ pub trait MinerService : Send + Sync {
 	/// Get a list of all transactions.
 	fn all_transactions(&self) -> Vec<SignedTransaction>;
 
+	/// Get a list of transactions waiting for a nonce gap to be filled.
+	fn future_transactions(&self) -> Vec<SignedTransaction>;
+
+	/// Get a per-sender report of nonce gaps blocking transactions in the queue.
+	fn nonce_gaps(&self) -> Vec<NonceGap>;
+
 	/// Get a list of all pending transactions.
 	fn pending_transactions(&self, best_block: BlockNumber) -> Vec<SignedTransaction>;
 
@@ -162,6 +171,14 @@ pub trait MinerService : Send + Sync {
 	/// Is it currently sealing?
 	fn is_sealing(&self) -> bool;
 
+	/// Seal and import a block right away, even if it contains no transactions.
+	/// Only possible if the engine seals internally. Returns the hash of the imported block.
+	fn force_seal(&self, chain: &MiningBlockChainClient) -> Option<H256>;
+
+	/// Set the interval after which a block is sealed even if there were no new transactions.
+	/// Only used if the engine seals internally. `None` disables interval sealing.
+	fn set_seal_interval(&self, interval: Option<Duration>);
+
 	/// Suggested gas price.
 	fn sensible_gas_price(&self) -> U256;
 
@@ -193,4 +210,6 @@ pub struct MinerStatus {
 	pub transactions_in_future_queue: usize,
 	/// Number of transactions included in currently mined block
 	pub transactions_in_pending_block: usize,
+	/// Estimated heap memory used by the transaction queue
+	pub transactions_mem_used: usize,
 }
@@ -84,11 +84,13 @@
 
 use std::ops::Deref;
 use std::cmp::Ordering;
-use std::cmp;
+use std::{cmp, mem};
 use std::collections::{HashSet, HashMap, BTreeSet, BTreeMap};
+use std::time::{Duration, Instant};
 use linked_hash_map::LinkedHashMap;
 use util::{Address, H256, Uint, U256};
 use util::table::Table;
+use heapsize::HeapSizeOf;
 use transaction::*;
 use error::{Error, TransactionError};
 use client::TransactionImportResult;
@@ -255,6 +257,8 @@ struct VerifiedTransaction {
 	transaction: SignedTransaction,
 	/// transaction origin
 	origin: TransactionOrigin,
+	/// Time the transaction was inserted into the queue
+	insertion_time: Instant,
 }
 
 impl VerifiedTransaction {
@@ -263,6 +267,7 @@ impl VerifiedTransaction {
 		Ok(VerifiedTransaction {
 			transaction: transaction,
 			origin: origin,
+			insertion_time: Instant::now(),
 		})
 	}
 
@@ -446,6 +451,23 @@ pub struct TransactionQueueStatus {
 	pub pending: usize,
 	/// Number of future transactions (waiting for transactions with lower nonces first)
 	pub future: usize,
+	/// Estimated heap memory used by the queue, in bytes
+	pub mem_used: usize,
+}
+
+/// Transactions of a single sender stuck in `future` because of a missing nonce.
+#[derive(Debug, PartialEq, Clone)]
+pub struct NonceGap {
+	/// Sender of the stuck transactions.
+	pub sender: Address,
+	/// Nonce of the first missing transaction (the one which has to be sent to unblock the sender).
+	pub next_nonce: U256,
+	/// Number of nonces missing between `next_nonce` and the highest queued nonce.
+	pub missing: U256,
+	/// Nonces of the transactions waiting in `future` (ascending).
+	pub queued: Vec<U256>,
+	/// How long the oldest of the queued transactions has been waiting.
+	pub waiting: Duration,
 }
 
 /// Details of account
@@ -604,9 +626,14 @@ impl TransactionQueue {
 
 	/// Returns current status for this queue
 	pub fn status(&self) -> TransactionQueueStatus {
+		let orders = self.current.by_priority.len() + self.future.by_priority.len();
 		TransactionQueueStatus {
 			pending: self.current.by_priority.len(),
 			future: self.future.by_priority.len(),
+			// every order is kept in three indexes.
+			mem_used: self.by_hash.values().map(|tx| tx.transaction.heap_size_of_children()).sum::<usize>()
+				+ self.by_hash.len() * (mem::size_of::<H256>() + mem::size_of::<VerifiedTransaction>())
+				+ orders * 3 * mem::size_of::<TransactionOrder>(),
 		}
 	}
 
@@ -944,8 +971,8 @@ impl TransactionQueue {
 		self.local_transactions.all_transactions()
 	}
 
-	#[cfg(test)]
-	fn future_transactions(&self) -> Vec<SignedTransaction> {
+	/// Returns transactions from the queue which can't be mined yet because of a nonce gap, ordered by priority.
+	pub fn future_transactions(&self) -> Vec<SignedTransaction> {
 		self.future.by_priority
 			.iter()
 			.map(|t| self.by_hash.get(&t.hash).expect("All transactions in `current` and `future` are always included in `by_hash`"))
@@ -953,6 +980,56 @@ impl TransactionQueue {
 			.collect()
 	}
 
+	/// Removes non-local transactions which have been waiting in `future` for longer than `max_age`.
+	/// Returns number of removed transactions.
+	pub fn remove_old_future(&mut self, max_age: Duration, now: Instant) -> usize {
+		let old = self.future.by_priority
+			.iter()
+			.map(|t| self.by_hash.get(&t.hash).expect("All transactions in `current` and `future` are always included in `by_hash`"))
+			.filter(|t| !t.origin.is_local() && now > t.insertion_time && now.duration_since(t.insertion_time) > max_age)
+			.map(|t| (t.sender(), t.nonce()))
+			.collect::<Vec<_>>();
+
+		for &(ref sender, ref nonce) in &old {
+			if let Some(order) = self.future.drop(sender, nonce) {
+				trace!(target: "txqueue", "Dropping old future transaction: {:?} (sender: {:?}, nonce: {})", order.hash, sender, nonce);
+				self.by_hash.remove(&order.hash);
+			}
+		}
+		assert_eq!(self.future.by_priority.len() + self.current.by_priority.len(), self.by_hash.len());
+		old.len()
+	}
+
+	/// Returns senders which have transactions stuck in `future` because of a nonce gap.
+	pub fn nonce_gaps(&self, now: Instant) -> Vec<NonceGap> {
+		let mut by_sender: BTreeMap<Address, (U256, Vec<U256>, Instant)> = BTreeMap::new();
+		for order in &self.future.by_priority {
+			let tx = self.by_hash.get(&order.hash).expect("All transactions in `current` and `future` are always included in `by_hash`");
+			let nonce = tx.nonce();
+			let entry = by_sender.entry(tx.sender()).or_insert_with(|| (nonce - order.nonce_height, Vec::new(), tx.insertion_time));
+			entry.1.push(nonce);
+			if tx.insertion_time < entry.2 {
+				entry.2 = tx.insertion_time;
+			}
+		}
+
+		by_sender.into_iter().map(|(sender, (state_nonce, mut queued, oldest))| {
+			queued.sort();
+			let next_nonce = self.last_nonces.get(&sender).map_or(state_nonce, |n| *n + U256::one());
+			let highest = *queued.last().expect("Every sender has at least one queued transaction; qed");
+			let span = if highest >= next_nonce { highest - next_nonce + U256::one() } else { U256::zero() };
+			let queued_count = U256::from(queued.len());
+			let missing = if span > queued_count { span - queued_count } else { U256::zero() };
+			NonceGap {
+				sender: sender,
+				next_nonce: next_nonce,
+				missing: missing,
+				queued: queued,
+				waiting: if now > oldest { now.duration_since(oldest) } else { Duration::from_secs(0) },
+			}
+		}).collect()
+	}
+
 	/// Returns hashes of all transactions from current, ordered by priority.
 	pub fn pending_hashes(&self) -> Vec<H256> {
 		self.current.by_priority
@@ -1209,6 +1286,7 @@ fn check_if_removed(sender: &Address, nonce: &U256, dropped: Option<HashMap<Addr
 #[cfg(test)]
 mod test {
 	extern crate rustc_serialize;
+	use std::time::{Duration, Instant};
 	use util::table::*;
 	use util::*;
 	use ethkey::{Random, Generator};
@@ -1966,6 +2044,52 @@ mod test {
 		assert_eq!(top[0], tx);
 	}
 
+	#[test]
+	fn should_report_nonce_gaps() {
+		// given
+		let mut txq = TransactionQueue::default();
+		let (tx, tx2) = new_tx_pair_default(3.into(), 0.into());
+		let sender = tx.sender().unwrap();
+		txq.add(tx, TransactionOrigin::External, &default_account_details, &gas_estimator).unwrap();
+		txq.add(tx2, TransactionOrigin::External, &default_account_details, &gas_estimator).unwrap();
+
+		// when
+		let gaps = txq.nonce_gaps(Instant::now());
+
+		// then
+		assert_eq!(gaps.len(), 1);
+		assert_eq!(gaps[0].sender, sender);
+		assert_eq!(gaps[0].next_nonce, default_nonce() + 1.into());
+		assert_eq!(gaps[0].missing, 2.into());
+		assert_eq!(gaps[0].queued, vec![default_nonce() + 3.into()]);
+	}
+
+	#[test]
+	fn should_remove_old_future_transactions() {
+		// given
+		let mut txq = TransactionQueue::default();
+		let (tx, tx2) = new_tx_pair_default(2.into(), 0.into());
+		let (local, local2) = new_tx_pair_default(2.into(), 0.into());
+		txq.add(tx, TransactionOrigin::External, &default_account_details, &gas_estimator).unwrap();
+		txq.add(tx2, TransactionOrigin::External, &default_account_details, &gas_estimator).unwrap();
+		txq.add(local, TransactionOrigin::Local, &default_account_details, &gas_estimator).unwrap();
+		txq.add(local2, TransactionOrigin::Local, &default_account_details, &gas_estimator).unwrap();
+		assert_eq!(txq.status().future, 2);
+
+		// when
+		let max_age = Duration::from_secs(60);
+		let now = Instant::now();
+		let removed_early = txq.remove_old_future(max_age, now);
+		let removed = txq.remove_old_future(max_age, now + Duration::from_secs(61));
+
+		// then
+		assert_eq!(removed_early, 0);
+		assert_eq!(removed, 1);
+		let stats = txq.status();
+		assert_eq!(stats.pending, 2);
+		assert_eq!(stats.future, 1);
+	}
+
 	#[test]
 	fn should_correctly_update_futures_when_removing() {
 		// given
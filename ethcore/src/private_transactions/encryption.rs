@@ -0,0 +1,136 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Key exchange and authenticated encryption of private payloads.
+//!
+//! A payload is encrypted once with a random AES key and authenticated with
+//! HMAC-SHA256 of the initialisation vector and cipher text under a second
+//! random key. Both keys are then encrypted to the public key of every
+//! recipient, so any one of them can recover them with their account key.
+
+use crypto::hmac::Hmac;
+use crypto::mac::{Mac, MacResult};
+use crypto::sha2::Sha256;
+use ethcrypto::{aes, ecies, KEY_LENGTH_AES};
+use ethkey::{Public, public_to_address};
+use rlp::*;
+use util::{Address, Bytes, H128, H256, FixedHash};
+use account_provider::AccountProvider;
+use super::Error;
+
+/// Length of the payload authentication key.
+const MAC_KEY_LENGTH: usize = 32;
+
+/// Payload encrypted to a group of recipients.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncryptedPayload {
+	/// Payload keys encrypted to each recipient, paired with the recipient address.
+	pub keys: Vec<(Address, Bytes)>,
+	/// Initialisation vector of the payload cipher.
+	pub iv: H128,
+	/// Encrypted payload.
+	pub cipher: Bytes,
+	/// Authentication code of the initialisation vector and encrypted payload.
+	pub mac: H256,
+}
+
+fn mac(key: &[u8], iv: &H128, cipher: &[u8]) -> H256 {
+	let mut hmac = Hmac::new(Sha256::new(), key);
+	hmac.input(iv);
+	hmac.input(cipher);
+	H256::from_slice(hmac.result().code())
+}
+
+impl EncryptedPayload {
+	/// Encrypt `plain` so that any of `recipients` can decrypt it.
+	pub fn encrypt(plain: &[u8], recipients: &[Public]) -> Result<Self, Error> {
+		let key = H128::random();
+		let mac_key = H256::random();
+		let iv = H128::random();
+		let mut cipher = vec![0u8; plain.len()];
+		aes::encrypt(&key, &iv, plain, &mut cipher);
+
+		let mut key_material = key.to_vec();
+		key_material.extend_from_slice(&mac_key);
+		let mut keys = Vec::with_capacity(recipients.len());
+		for public in recipients {
+			let encrypted = try!(ecies::encrypt(public, &[], &key_material).map_err(|e| Error::Encryption(format!("{}", e))));
+			keys.push((public_to_address(public), encrypted));
+		}
+
+		Ok(EncryptedPayload {
+			keys: keys,
+			mac: mac(&mac_key, &iv, &cipher),
+			iv: iv,
+			cipher: cipher,
+		})
+	}
+
+	/// Addresses of the recipients able to decrypt the payload.
+	pub fn recipients(&self) -> Vec<Address> {
+		self.keys.iter().map(|&(ref address, _)| address.clone()).collect()
+	}
+
+	/// Authenticate and decrypt the payload with the key of `account`, which must be unlocked.
+	pub fn decrypt(&self, accounts: &AccountProvider, account: &Address) -> Result<Bytes, Error> {
+		let encrypted = match self.keys.iter().find(|&&(ref address, _)| address == account) {
+			Some(&(_, ref encrypted)) => encrypted,
+			None => return Err(Error::NotPermitted(account.clone())),
+		};
+		let key_material = try!(accounts.decrypt(account.clone(), None, &[], encrypted).map_err(|e| Error::Encryption(format!("{}", e))));
+		if key_material.len() != KEY_LENGTH_AES + MAC_KEY_LENGTH {
+			return Err(Error::Encryption("Invalid payload key length".into()));
+		}
+		let (key, mac_key) = key_material.split_at(KEY_LENGTH_AES);
+		if MacResult::new(&mac(mac_key, &self.iv, &self.cipher)) != MacResult::new(&self.mac) {
+			return Err(Error::Encryption("Payload authentication failed".into()));
+		}
+
+		let mut plain = vec![0u8; self.cipher.len()];
+		aes::decrypt(key, &self.iv, &self.cipher, &mut plain);
+		Ok(plain)
+	}
+}
+
+impl Encodable for EncryptedPayload {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(4);
+		s.begin_list(self.keys.len());
+		for &(ref address, ref key) in &self.keys {
+			s.begin_list(2).append(address).append(key);
+		}
+		s.append(&self.iv);
+		s.append(&self.cipher);
+		s.append(&self.mac);
+	}
+}
+
+impl Decodable for EncryptedPayload {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		let mut keys = Vec::new();
+		for key_rlp in try!(d.at(0)).iter() {
+			keys.push((try!(key_rlp.val_at(0)), try!(key_rlp.val_at(1))));
+		}
+
+		Ok(EncryptedPayload {
+			keys: keys,
+			iv: try!(d.val_at(1)),
+			cipher: try!(d.val_at(2)),
+			mac: try!(d.val_at(3)),
+		})
+	}
+}
@@ -0,0 +1,460 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Private transactions.
+//!
+//! A private contract is an ordinary contract whose storage is kept off-chain by
+//! a group of validators. Its public storage only holds, in slot 0, a commitment
+//! to the private storage, updated with `setState(bytes32)`.
+//!
+//! A private transaction is a signed call to such a contract. The sender executes
+//! it against the private state the contract currently commits to, encrypts it to
+//! the validators, sends it to them over the network and posts the commitment to
+//! the resulting state in a public transaction. Validators execute it themselves
+//! from the same state, keep the resulting state if they arrive at the same
+//! commitment and refuse to include commitments they have not verified, or which
+//! do not follow the current one. States are only kept encrypted to the node account.
+//!
+//! Validator public keys are configured on every node, they are not exchanged.
+
+mod encryption;
+mod store;
+
+pub use self::encryption::EncryptedPayload;
+pub use self::store::{PrivateState, StoredTransaction};
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use ethkey::{Public, recover};
+use rlp::*;
+use util::{Address, Bytes, H256, U256, Mutex, Mismatch, Hashable};
+use account_provider::AccountProvider;
+use client::{BlockID, BlockChainClient, CallAnalytics, MiningBlockChainClient, AccountOverride};
+use miner::{MinerService, TransactionFilter};
+use types::executed::CallError;
+use types::transaction::{Transaction, SignedTransaction, Action};
+use self::store::{Store, StoredState};
+
+/// Signature of the private contract method recording a state commitment.
+const SET_STATE_METHOD: &'static str = "setState(bytes32)";
+/// Gas of public commitment transactions.
+const PUBLIC_TRANSACTION_GAS: u64 = 100_000;
+
+/// Private transaction errors.
+#[derive(Debug)]
+pub enum Error {
+	/// The payload could not be encrypted or decrypted.
+	Encryption(String),
+	/// The account is not one of the validators the transaction was encrypted to.
+	NotPermitted(Address),
+	/// The sender of the transaction has no account on this node.
+	NotAuthorized(Address),
+	/// The transaction does not call the private contract.
+	BadTransactionType,
+	/// The transaction cannot be decoded.
+	Decoder(DecoderError),
+	/// Off-chain execution failed.
+	Call(CallError),
+	/// The state the transaction was executed on is not known to this node.
+	UnknownState(H256),
+	/// Execution resulted in a different state than the sender committed to.
+	StateMismatch(Mismatch<H256>),
+	/// The public commitment transaction could not be signed.
+	Signing(String),
+	/// The public commitment transaction was rejected by the transaction queue.
+	Import(String),
+	/// Private states or transactions could not be read or written.
+	Io(io::Error),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Error::Encryption(ref e) => write!(f, "Encryption error: {}", e),
+			Error::NotPermitted(ref account) => write!(f, "Account {} is not permitted to read the transaction", account),
+			Error::NotAuthorized(ref account) => write!(f, "Sender {} has no account on this node", account),
+			Error::BadTransactionType => write!(f, "Private transactions must call a private contract"),
+			Error::Decoder(ref e) => write!(f, "Invalid private transaction: {}", e),
+			Error::Call(ref e) => write!(f, "{}", e),
+			Error::UnknownState(ref commitment) => write!(f, "Unknown private state {}", commitment),
+			Error::StateMismatch(ref mis) => write!(f, "Private contract state commitment mismatch: {}", mis),
+			Error::Signing(ref e) => write!(f, "Cannot sign public transaction: {}", e),
+			Error::Import(ref e) => write!(f, "Public transaction rejected: {}", e),
+			Error::Io(ref e) => write!(f, "Private transactions store error: {}", e),
+		}
+	}
+}
+
+impl From<DecoderError> for Error {
+	fn from(err: DecoderError) -> Self {
+		Error::Decoder(err)
+	}
+}
+
+impl From<CallError> for Error {
+	fn from(err: CallError) -> Self {
+		Error::Call(err)
+	}
+}
+
+impl From<io::Error> for Error {
+	fn from(err: io::Error) -> Self {
+		Error::Io(err)
+	}
+}
+
+/// Private transaction as distributed to the validators.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrivateTransaction {
+	/// Private contract called by the transaction.
+	pub contract: Address,
+	/// Commitment to the contract storage the call was executed on.
+	pub prior: H256,
+	/// Commitment to the contract storage after the call.
+	pub commitment: H256,
+	/// Signed transaction encrypted to the validators.
+	pub payload: EncryptedPayload,
+}
+
+impl PrivateTransaction {
+	/// Hash of the encoded private transaction.
+	pub fn hash(&self) -> H256 {
+		::rlp::encode(self).sha3()
+	}
+}
+
+impl Encodable for PrivateTransaction {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(4);
+		s.append(&self.contract);
+		s.append(&self.prior);
+		s.append(&self.commitment);
+		s.append(&self.payload);
+	}
+}
+
+impl Decodable for PrivateTransaction {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		Ok(PrivateTransaction {
+			contract: try!(d.val_at(0)),
+			prior: try!(d.val_at(1)),
+			commitment: try!(d.val_at(2)),
+			payload: try!(d.val_at(3)),
+		})
+	}
+}
+
+/// Result of sending a private transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Receipt {
+	/// Hash of the private transaction.
+	pub hash: H256,
+	/// Commitment to the contract storage after the call.
+	pub commitment: H256,
+	/// Hash of the public commitment transaction.
+	pub public_transaction: H256,
+}
+
+/// Private transactions configuration.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ProviderConfig {
+	/// Account signing public transactions and decrypting private ones. Must be unlocked.
+	pub account: Address,
+	/// Public keys of the validators private transactions are encrypted to.
+	pub validators: Vec<Public>,
+}
+
+/// Sends, verifies and distributes private transactions, and keeps the private states.
+pub struct Provider {
+	config: ProviderConfig,
+	accounts: Arc<AccountProvider>,
+	store: Store,
+	// public key of the node account, states are encrypted to.
+	public: Mutex<Option<Public>>,
+	// encoded private transactions waiting to be sent to the peers.
+	outgoing: Mutex<Vec<Bytes>>,
+}
+
+impl Provider {
+	/// Create a new provider keeping its data under `path`.
+	pub fn new(config: ProviderConfig, accounts: Arc<AccountProvider>, path: &Path) -> Result<Self, Error> {
+		Ok(Provider {
+			config: config,
+			accounts: accounts,
+			store: try!(Store::open(path)),
+			public: Mutex::new(None),
+			outgoing: Mutex::new(Vec::new()),
+		})
+	}
+
+	/// Whether the node account is one of the validators.
+	pub fn is_validator(&self) -> bool {
+		let account = &self.config.account;
+		self.config.validators.iter().any(|public| &::ethkey::public_to_address(public) == account)
+	}
+
+	/// Execute `transaction` off-chain, encrypt it to the validators, queue it for distribution
+	/// and post the commitment to the resulting contract storage in a public transaction.
+	/// The sender must have an account on this node, the public transaction is paid by the node account.
+	pub fn send_transaction(&self, client: &MiningBlockChainClient, miner: &MinerService, transaction: SignedTransaction) -> Result<Receipt, Error> {
+		let sender = try!(transaction.sender().map_err(|e| Error::Signing(format!("{}", e))));
+		if !try!(self.accounts.accounts().map_err(|e| Error::Signing(format!("{}", e)))).contains(&sender) {
+			return Err(Error::NotAuthorized(sender));
+		}
+
+		let contract = try!(contract_of(&transaction));
+		let prior = current_commitment(client, &contract);
+		let mut state = try!(self.state(&contract, &prior));
+		try!(execute(client, &transaction, &contract, &mut state));
+
+		let private = PrivateTransaction {
+			contract: contract,
+			prior: prior,
+			commitment: state.commitment(),
+			payload: try!(EncryptedPayload::encrypt(&::rlp::encode(&transaction), &self.config.validators)),
+		};
+		let hash = private.hash();
+		try!(self.insert_state(&contract, &prior, &state));
+
+		let public = try!(self.public_transaction(client, miner, &private));
+		let public_hash = public.hash();
+		try!(miner.import_own_transaction(client, public).map_err(|e| Error::Import(format!("{}", e))));
+		trace!(target: "privatetx", "Sent private transaction {} with public transaction {}", hash, public_hash);
+
+		let receipt = Receipt {
+			hash: hash,
+			commitment: private.commitment,
+			public_transaction: public_hash,
+		};
+		self.outgoing.lock().push(::rlp::encode(&private).to_vec());
+		try!(self.store.insert_transaction(&StoredTransaction {
+			private: private,
+			public_transaction: Some(public_hash),
+		}));
+		Ok(receipt)
+	}
+
+	/// Verify a private transaction received from another node: decrypt it, execute it on the
+	/// state it was based on and check the result against the sender's commitment. The resulting
+	/// state is kept, allowing the commitment to be included in blocks.
+	pub fn import_transaction(&self, client: &BlockChainClient, rlp: &[u8]) -> Result<H256, Error> {
+		let private: PrivateTransaction = try!(UntrustedRlp::new(rlp).as_val());
+		let hash = private.hash();
+		if self.store.contains_transaction(&hash) {
+			return Ok(hash);
+		}
+
+		let plain = try!(private.payload.decrypt(&self.accounts, &self.config.account));
+		let transaction: SignedTransaction = try!(UntrustedRlp::new(&plain).as_val());
+		if try!(contract_of(&transaction)) != private.contract {
+			return Err(Error::BadTransactionType);
+		}
+
+		let mut state = try!(self.state(&private.contract, &private.prior));
+		try!(execute(client, &transaction, &private.contract, &mut state));
+		if state.commitment() != private.commitment {
+			return Err(Error::StateMismatch(Mismatch { expected: private.commitment, found: state.commitment() }));
+		}
+
+		try!(self.insert_state(&private.contract, &private.prior, &state));
+		try!(self.store.insert_transaction(&StoredTransaction {
+			private: private,
+			public_transaction: None,
+		}));
+		trace!(target: "privatetx", "Imported private transaction {}", hash);
+		Ok(hash)
+	}
+
+	/// Private transaction with given hash, in its encrypted form, if it was sent or imported by this node.
+	pub fn transaction(&self, hash: &H256) -> Result<Option<StoredTransaction>, Error> {
+		self.store.transaction(hash).map_err(Into::into)
+	}
+
+	/// Take the encoded private transactions to be sent to the peers.
+	pub fn drain_outgoing(&self) -> Vec<Bytes> {
+		::std::mem::replace(&mut *self.outgoing.lock(), Vec::new())
+	}
+
+	// decrypted state of `contract` with given commitment.
+	fn state(&self, contract: &Address, commitment: &H256) -> Result<PrivateState, Error> {
+		if commitment.is_zero() {
+			return Ok(PrivateState::default());
+		}
+		let stored = match try!(self.store.state(contract, commitment)) {
+			Some(stored) => stored,
+			None => return Err(Error::UnknownState(commitment.clone())),
+		};
+		let plain = try!(stored.payload.decrypt(&self.accounts, &self.config.account));
+		Ok(try!(UntrustedRlp::new(&plain).as_val()))
+	}
+
+	// encrypt `state` to the node account and keep it.
+	fn insert_state(&self, contract: &Address, prior: &H256, state: &PrivateState) -> Result<(), Error> {
+		let public = try!(self.public());
+		let stored = StoredState {
+			prior: prior.clone(),
+			payload: try!(EncryptedPayload::encrypt(&::rlp::encode(state), &[public])),
+		};
+		try!(self.store.insert_state(contract, &state.commitment(), &stored));
+		Ok(())
+	}
+
+	// public key of the node account, recovered from a signature.
+	fn public(&self) -> Result<Public, Error> {
+		let mut public = self.public.lock();
+		if let Some(ref public) = *public {
+			return Ok(public.clone());
+		}
+		let message = SET_STATE_METHOD.sha3();
+		let signature = try!(self.accounts.sign(self.config.account.clone(), None, message.clone()).map_err(|e| Error::Signing(format!("{}", e))));
+		let recovered = try!(recover(&signature, &message).map_err(|e| Error::Encryption(format!("{}", e))));
+		*public = Some(recovered.clone());
+		Ok(recovered)
+	}
+
+	fn public_transaction(&self, client: &MiningBlockChainClient, miner: &MinerService, private: &PrivateTransaction) -> Result<SignedTransaction, Error> {
+		let account = self.config.account.clone();
+		let nonce = miner.last_nonce(&account).map(|nonce| nonce + U256::one()).unwrap_or_else(|| client.latest_nonce(&account));
+
+		let public = Transaction {
+			nonce: nonce,
+			gas_price: miner.sensible_gas_price(),
+			gas: PUBLIC_TRANSACTION_GAS.into(),
+			action: Action::Call(private.contract.clone()),
+			value: U256::zero(),
+			data: set_state_call(&private.commitment),
+		};
+		let network_id = client.signing_network_id();
+		let signature = try!(self.accounts.sign(account, None, public.hash(network_id)).map_err(|e| Error::Signing(format!("{}", e))));
+		Ok(public.with_signature(signature, network_id))
+	}
+}
+
+/// Validators only let commitments they verified, and which follow the current one, into blocks.
+impl TransactionFilter for Provider {
+	fn transaction_allowed(&self, chain: &MiningBlockChainClient, transaction: &SignedTransaction) -> bool {
+		let (contract, commitment) = match (&transaction.action, set_state_commitment(&transaction.data)) {
+			(&Action::Call(ref contract), Some(commitment)) => (contract, commitment),
+			_ => return true,
+		};
+		match self.store.prior(contract, &commitment) {
+			Some(prior) => prior == current_commitment(chain, contract),
+			None => {
+				trace!(target: "privatetx", "Rejecting unverified commitment {} to {}", commitment, contract);
+				false
+			},
+		}
+	}
+}
+
+fn contract_of(transaction: &SignedTransaction) -> Result<Address, Error> {
+	match transaction.action {
+		Action::Call(ref contract) => Ok(contract.clone()),
+		Action::Create => Err(Error::BadTransactionType),
+	}
+}
+
+fn set_state_call(commitment: &H256) -> Bytes {
+	let mut data = SET_STATE_METHOD.sha3()[0..4].to_vec();
+	data.extend_from_slice(commitment);
+	data
+}
+
+// commitment recorded by a `setState` call.
+fn set_state_commitment(data: &[u8]) -> Option<H256> {
+	match data.len() == 4 + 32 && data[0..4] == SET_STATE_METHOD.sha3()[0..4] {
+		true => Some(H256::from_slice(&data[4..])),
+		false => None,
+	}
+}
+
+/// Commitment currently recorded by the public contract.
+fn current_commitment<C: BlockChainClient + ?Sized>(client: &C, contract: &Address) -> H256 {
+	client.storage_at(contract, &H256::zero(), BlockID::Latest).unwrap_or_else(H256::zero)
+}
+
+// execute `transaction` on the private `state` of `contract` and apply the changes.
+fn execute<C: BlockChainClient + ?Sized>(client: &C, transaction: &SignedTransaction, contract: &Address, state: &mut PrivateState) -> Result<(), Error> {
+	let analytics = CallAnalytics {
+		transaction_tracing: false,
+		vm_tracing: false,
+		state_diffing: true,
+	};
+	let mut overrides = BTreeMap::new();
+	overrides.insert(contract.clone(), AccountOverride {
+		balance: None,
+		code: None,
+		storage: state.storage.clone(),
+	});
+	let executed = try!(client.call_with_overrides(transaction, BlockID::Latest, analytics, overrides));
+	state.apply(contract, executed.state_diff.as_ref());
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use account_provider::AccountProvider;
+	use util::H256;
+	use super::{EncryptedPayload, Error, set_state_call, set_state_commitment};
+
+	#[test]
+	fn only_validators_decrypt_payload() {
+		let accounts = Arc::new(AccountProvider::transient_provider());
+		let (validator, public) = accounts.new_account_and_public("").unwrap();
+		let (outsider, _) = accounts.new_account_and_public("").unwrap();
+		accounts.unlock_account_permanently(validator, "".into()).unwrap();
+		accounts.unlock_account_permanently(outsider, "".into()).unwrap();
+
+		let payload = EncryptedPayload::encrypt(b"private call", &[public]).unwrap();
+		assert_eq!(payload.recipients(), vec![validator]);
+		assert!(payload.cipher != b"private call".to_vec());
+
+		let decoded: EncryptedPayload = ::rlp::decode(&::rlp::encode(&payload));
+		assert_eq!(decoded.decrypt(&accounts, &validator).unwrap(), b"private call".to_vec());
+		match decoded.decrypt(&accounts, &outsider) {
+			Err(Error::NotPermitted(account)) => assert_eq!(account, outsider),
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn rejects_tampered_payload() {
+		let accounts = Arc::new(AccountProvider::transient_provider());
+		let (validator, public) = accounts.new_account_and_public("").unwrap();
+		accounts.unlock_account_permanently(validator, "".into()).unwrap();
+
+		let mut payload = EncryptedPayload::encrypt(b"private call", &[public]).unwrap();
+		payload.cipher[0] ^= 1;
+		match payload.decrypt(&accounts, &validator) {
+			Err(Error::Encryption(_)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn recognizes_set_state_calls() {
+		let commitment = H256::from(7);
+		let call = set_state_call(&commitment);
+		assert_eq!(set_state_commitment(&call), Some(commitment));
+		assert_eq!(set_state_commitment(&call[..35]), None);
+		assert_eq!(set_state_commitment(&[0u8; 36]), None);
+	}
+}
@@ -0,0 +1,343 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Private contract states and private transactions kept on disk.
+//!
+//! States are only written encrypted, transactions only in the encrypted form
+//! they are distributed in, so nothing private is stored in the clear.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use rlp::*;
+use util::{Address, Bytes, H256, Hashable, Mutex};
+use types::state_diff::StateDiff;
+use super::{EncryptedPayload, PrivateTransaction};
+
+/// Maximum number of private transactions kept.
+const MAX_TRANSACTIONS: usize = 4096;
+/// Maximum number of states kept for each contract.
+const MAX_STATES_PER_CONTRACT: usize = 256;
+
+/// Storage of a private contract.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PrivateState {
+	/// Non-zero storage entries.
+	pub storage: BTreeMap<H256, H256>,
+}
+
+impl PrivateState {
+	/// Commitment to the storage, as recorded by the public contract. Empty storage,
+	/// the state of a contract which never had its commitment set, commits to zero.
+	pub fn commitment(&self) -> H256 {
+		if self.storage.is_empty() {
+			return H256::zero();
+		}
+		let mut stream = RlpStream::new_list(self.storage.len());
+		for (key, value) in &self.storage {
+			stream.begin_list(2).append(key).append(value);
+		}
+		stream.out().sha3()
+	}
+
+	/// Apply the storage changes of `contract` in a call's state diff.
+	pub fn apply(&mut self, contract: &Address, diff: Option<&StateDiff>) {
+		let changes = diff.and_then(|diff| diff.get().get(contract)).map(|account| &account.storage);
+		for (key, value) in changes.into_iter().flat_map(|changes| changes.iter()) {
+			match value.post() {
+				Some(value) if !value.is_zero() => { self.storage.insert(key.clone(), value.clone()); },
+				_ => { self.storage.remove(key); },
+			}
+		}
+	}
+}
+
+impl Encodable for PrivateState {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(self.storage.len());
+		for (key, value) in &self.storage {
+			s.begin_list(2).append(key).append(value);
+		}
+	}
+}
+
+impl Decodable for PrivateState {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		let mut storage = BTreeMap::new();
+		for entry in d.iter() {
+			storage.insert(try!(entry.val_at(0)), try!(entry.val_at(1)));
+		}
+		Ok(PrivateState { storage: storage })
+	}
+}
+
+/// Verified state of a private contract as kept on disk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredState {
+	/// Commitment of the state the transaction producing this one was executed on.
+	pub prior: H256,
+	/// Encrypted `PrivateState`.
+	pub payload: EncryptedPayload,
+}
+
+impl Encodable for StoredState {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(2).append(&self.prior).append(&self.payload);
+	}
+}
+
+impl Decodable for StoredState {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		Ok(StoredState {
+			prior: try!(d.val_at(0)),
+			payload: try!(d.val_at(1)),
+		})
+	}
+}
+
+/// Private transaction as kept on disk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredTransaction {
+	/// Private transaction as distributed to the validators.
+	pub private: PrivateTransaction,
+	/// Hash of the public commitment transaction if it was sent by this node.
+	pub public_transaction: Option<H256>,
+}
+
+impl Encodable for StoredTransaction {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(2).append(&self.private);
+		match self.public_transaction {
+			Some(ref hash) => s.append(hash),
+			None => s.append_empty_data(),
+		};
+	}
+}
+
+impl Decodable for StoredTransaction {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		let public = try!(d.at(1));
+		Ok(StoredTransaction {
+			private: try!(d.val_at(0)),
+			public_transaction: match public.is_empty() {
+				true => None,
+				false => Some(try!(public.as_val())),
+			},
+		})
+	}
+}
+
+fn read(path: &Path) -> io::Result<Option<Bytes>> {
+	let mut file = match fs::File::open(path) {
+		Ok(file) => file,
+		Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+		Err(e) => return Err(e),
+	};
+	let mut bytes = Vec::new();
+	try!(file.read_to_end(&mut bytes));
+	Ok(Some(bytes))
+}
+
+// write through a temporary file so that a crash never leaves a partial entry.
+fn write(path: &Path, bytes: &[u8]) -> io::Result<()> {
+	let temp = path.with_extension("tmp");
+	{
+		let mut file = try!(fs::File::create(&temp));
+		try!(file.write_all(bytes));
+		try!(file.sync_all());
+	}
+	fs::rename(&temp, path)
+}
+
+// entries of a directory, oldest first.
+fn entries_by_age(dir: &Path) -> io::Result<Vec<PathBuf>> {
+	let mut entries = Vec::new();
+	for entry in try!(fs::read_dir(dir)) {
+		let entry = try!(entry);
+		let modified = try!(entry.metadata().and_then(|m| m.modified()));
+		entries.push((modified, entry.path()));
+	}
+	entries.sort();
+	Ok(entries.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Private states by contract and commitment, and recent private transactions by hash.
+pub struct Store {
+	path: PathBuf,
+	// prior commitment of every known state.
+	priors: Mutex<HashMap<(Address, H256), H256>>,
+	// hashes of the kept transactions, oldest first.
+	transactions: Mutex<VecDeque<H256>>,
+}
+
+impl Store {
+	/// Open the store kept under `path`.
+	pub fn open(path: &Path) -> io::Result<Self> {
+		try!(fs::create_dir_all(path.join("states")));
+		try!(fs::create_dir_all(path.join("transactions")));
+
+		let transactions = try!(entries_by_age(&path.join("transactions"))).into_iter()
+			.filter_map(|entry| entry.file_name().and_then(|name| name.to_str()).and_then(|name| name.parse().ok()))
+			.collect();
+
+		Ok(Store {
+			path: path.to_owned(),
+			priors: Mutex::new(HashMap::new()),
+			transactions: Mutex::new(transactions),
+		})
+	}
+
+	fn state_path(&self, contract: &Address, commitment: &H256) -> PathBuf {
+		self.path.join("states").join(format!("{:?}", contract)).join(format!("{:?}", commitment))
+	}
+
+	fn transaction_path(&self, hash: &H256) -> PathBuf {
+		self.path.join("transactions").join(format!("{:?}", hash))
+	}
+
+	/// State of `contract` with given commitment, if it was verified by this node.
+	pub fn state(&self, contract: &Address, commitment: &H256) -> io::Result<Option<StoredState>> {
+		match try!(read(&self.state_path(contract, commitment))) {
+			Some(bytes) => UntrustedRlp::new(&bytes).as_val().map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}", e))),
+			None => Ok(None),
+		}
+	}
+
+	/// Prior commitment of the state of `contract` with given commitment, if it was verified by this node.
+	pub fn prior(&self, contract: &Address, commitment: &H256) -> Option<H256> {
+		let key = (contract.clone(), commitment.clone());
+		if let Some(prior) = self.priors.lock().get(&key) {
+			return Some(prior.clone());
+		}
+		let prior = self.state(contract, commitment).ok().and_then(|state| state.map(|state| state.prior));
+		if let Some(ref prior) = prior {
+			self.priors.lock().insert(key, prior.clone());
+		}
+		prior
+	}
+
+	/// Keep a verified state of `contract`, dropping the oldest ones over the limit.
+	pub fn insert_state(&self, contract: &Address, commitment: &H256, state: &StoredState) -> io::Result<()> {
+		let path = self.state_path(contract, commitment);
+		let dir = path.parent().expect("state path is within a contract directory; qed").to_owned();
+		try!(fs::create_dir_all(&dir));
+		try!(write(&path, &::rlp::encode(state)));
+		self.priors.lock().insert((contract.clone(), commitment.clone()), state.prior.clone());
+
+		let entries = try!(entries_by_age(&dir));
+		if entries.len() > MAX_STATES_PER_CONTRACT {
+			let mut priors = self.priors.lock();
+			for entry in &entries[..entries.len() - MAX_STATES_PER_CONTRACT] {
+				try!(fs::remove_file(entry));
+				if let Some(commitment) = entry.file_name().and_then(|name| name.to_str()).and_then(|name| name.parse().ok()) {
+					priors.remove(&(contract.clone(), commitment));
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Private transaction with given hash, if kept.
+	pub fn transaction(&self, hash: &H256) -> io::Result<Option<StoredTransaction>> {
+		match try!(read(&self.transaction_path(hash))) {
+			Some(bytes) => UntrustedRlp::new(&bytes).as_val().map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}", e))),
+			None => Ok(None),
+		}
+	}
+
+	/// Whether a private transaction with given hash is kept.
+	pub fn contains_transaction(&self, hash: &H256) -> bool {
+		self.transactions.lock().contains(hash)
+	}
+
+	/// Keep a private transaction, dropping the oldest ones over the limit.
+	pub fn insert_transaction(&self, transaction: &StoredTransaction) -> io::Result<()> {
+		let hash = transaction.private.hash();
+		try!(write(&self.transaction_path(&hash), &::rlp::encode(transaction)));
+
+		let mut transactions = self.transactions.lock();
+		if !transactions.contains(&hash) {
+			transactions.push_back(hash);
+		}
+		while transactions.len() > MAX_TRANSACTIONS {
+			let oldest = transactions.pop_front().expect("more than MAX_TRANSACTIONS entries; qed");
+			try!(fs::remove_file(self.transaction_path(&oldest)));
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+	use devtools::RandomTempPath;
+	use types::account_diff::{AccountDiff, Diff};
+	use types::state_diff::StateDiff;
+	use util::{Address, H128, H256};
+	use super::{PrivateState, Store, StoredState, StoredTransaction};
+	use super::super::{EncryptedPayload, PrivateTransaction};
+
+	fn payload() -> EncryptedPayload {
+		EncryptedPayload { keys: vec![], iv: H128::zero(), cipher: vec![1, 2, 3], mac: H256::zero() }
+	}
+
+	#[test]
+	fn state_follows_contract_storage_changes() {
+		let contract = Address::from(5);
+		let mut storage = BTreeMap::new();
+		storage.insert(H256::from(1), Diff::Born(H256::from(2)));
+		storage.insert(H256::from(3), Diff::Died(H256::from(4)));
+		let mut diff = StateDiff { raw: BTreeMap::new() };
+		diff.raw.insert(contract, AccountDiff { balance: Diff::Same, nonce: Diff::Same, code: Diff::Same, storage: storage.clone() });
+		diff.raw.insert(Address::from(6), AccountDiff { balance: Diff::Same, nonce: Diff::Same, code: Diff::Same, storage: storage });
+
+		let mut state = PrivateState::default();
+		assert_eq!(state.commitment(), H256::zero());
+		state.storage.insert(H256::from(3), H256::from(4));
+		state.apply(&contract, Some(&diff));
+
+		assert_eq!(state.storage.len(), 1);
+		assert_eq!(state.storage.get(&H256::from(1)), Some(&H256::from(2)));
+		assert!(state.commitment() != H256::zero());
+		assert_eq!(::rlp::decode::<PrivateState>(&::rlp::encode(&state)), state);
+	}
+
+	#[test]
+	fn keeps_states_and_transactions_across_restarts() {
+		let path = RandomTempPath::new();
+		let contract = Address::from(5);
+		let transaction = StoredTransaction {
+			private: PrivateTransaction { contract: contract, prior: H256::zero(), commitment: H256::from(2), payload: payload() },
+			public_transaction: None,
+		};
+		let hash = transaction.private.hash();
+		{
+			let store = Store::open(path.as_path()).unwrap();
+			store.insert_state(&contract, &H256::from(2), &StoredState { prior: H256::zero(), payload: payload() }).unwrap();
+			store.insert_transaction(&transaction).unwrap();
+		}
+
+		let store = Store::open(path.as_path()).unwrap();
+		assert_eq!(store.prior(&contract, &H256::from(2)), Some(H256::zero()));
+		assert_eq!(store.prior(&contract, &H256::from(3)), None);
+		assert!(store.contains_transaction(&hash));
+		assert_eq!(store.transaction(&hash).unwrap(), Some(transaction));
+	}
+}
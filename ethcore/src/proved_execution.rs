@@ -0,0 +1,243 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction execution against a partial, proved state.
+//!
+//! Light clients don't hold the state, so to execute a call they fetch
+//! accounts, storage and code as proofs from the network. The set of items
+//! a call needs is only known while it runs, so execution is iterative:
+//! execute against what is known, fetch and verify the `Missing` items,
+//! and retry until the call completes.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use util::{Address, H256, U256, Bytes};
+use util::kvdb::in_memory;
+use util::journaldb::{self, Algorithm};
+use db::{COL_STATE, NUM_COLUMNS};
+use engines::Engine;
+use env_info::EnvInfo;
+use executive::{Executive, Executed, TransactOptions};
+use factory::Factories;
+use pod_account::PodAccount;
+use pod_state::PodState;
+use state::{State, CleanupMode};
+use state_db::StateDB;
+use types::executed::ExecutionError;
+use types::transaction::SignedTransaction;
+
+/// An account whose contents have been proved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProvedAccount {
+	/// Account nonce.
+	pub nonce: U256,
+	/// Account balance.
+	pub balance: U256,
+	/// Account code.
+	pub code: Bytes,
+}
+
+/// A piece of state needed by an execution.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StateItem {
+	/// Account nonce, balance and code.
+	Account(Address),
+	/// Storage item of an account.
+	Storage(Address, H256),
+}
+
+/// Proved execution errors.
+#[derive(Debug)]
+pub enum Error {
+	/// The execution read state which is not known yet.
+	Missing(Vec<StateItem>),
+	/// The execution failed.
+	Execution(ExecutionError),
+}
+
+impl From<ExecutionError> for Error {
+	fn from(err: ExecutionError) -> Self {
+		Error::Execution(err)
+	}
+}
+
+/// Proved parts of the state at a given block.
+///
+/// Accounts proved not to exist are stored as `None`.
+#[derive(Debug, Default, Clone)]
+pub struct ProvedState {
+	accounts: HashMap<Address, Option<ProvedAccount>>,
+	storage: HashMap<(Address, H256), H256>,
+}
+
+impl ProvedState {
+	/// Create an empty proved state.
+	pub fn new() -> Self {
+		ProvedState::default()
+	}
+
+	/// Insert a proved account, or `None` if it was proved not to exist.
+	pub fn insert_account(&mut self, address: Address, account: Option<ProvedAccount>) {
+		self.accounts.insert(address, account);
+	}
+
+	/// Insert a proved storage item.
+	pub fn insert_storage(&mut self, address: Address, key: H256, value: H256) {
+		self.storage.insert((address, key), value);
+	}
+
+	/// Whether given item is known. Storage of non-existent accounts is always known.
+	pub fn is_known(&self, item: &StateItem) -> bool {
+		match *item {
+			StateItem::Account(ref address) => self.accounts.contains_key(address),
+			StateItem::Storage(ref address, ref key) => match self.accounts.get(address) {
+				Some(&None) => true,
+				_ => self.storage.contains_key(&(address.clone(), key.clone())),
+			},
+		}
+	}
+
+	/// Execute a transaction against the known state, without checking the nonce
+	/// and topping up the sender's balance as needed, like `eth_call` does.
+	///
+	/// Returns `Error::Missing` with every unknown item the execution read; the result
+	/// of the execution is only meaningful once all of them are known.
+	pub fn execute(&self, t: &SignedTransaction, env_info: &EnvInfo, engine: &Engine) -> Result<Executed, Error> {
+		let factories = Factories::default();
+		let mut state = self.build_state(engine, factories.clone());
+		state.record_reads();
+
+		let sender = try!(t.sender().map_err(|e| ExecutionError::TransactionMalformed(format!("Transaction malformed: {:?}", e))));
+		let balance = state.balance(&sender);
+		let needed_balance = t.value + t.gas * t.gas_price;
+		if balance < needed_balance {
+			state.add_balance(&sender, &(needed_balance - balance), CleanupMode::NoEmpty);
+		}
+
+		let options = TransactOptions { tracing: false, vm_tracing: false, check_nonce: false, defer_fees: false };
+		let result = Executive::new(&mut state, env_info, engine, &factories.vm).transact(t, options);
+
+		let mut missing = Vec::new();
+		for (address, keys) in state.reads() {
+			let account = StateItem::Account(address.clone());
+			if !self.is_known(&account) {
+				missing.push(account);
+			}
+			for key in keys {
+				let item = StateItem::Storage(address.clone(), key);
+				if !self.is_known(&item) {
+					missing.push(item);
+				}
+			}
+		}
+
+		if !missing.is_empty() {
+			return Err(Error::Missing(missing));
+		}
+
+		result.map_err(Into::into)
+	}
+
+	// build an in-memory state holding all known items.
+	fn build_state(&self, engine: &Engine, factories: Factories) -> State {
+		let mut accounts = BTreeMap::new();
+		for (address, account) in &self.accounts {
+			if let Some(ref account) = *account {
+				accounts.insert(address.clone(), PodAccount {
+					balance: account.balance,
+					nonce: account.nonce,
+					code: Some(account.code.clone()),
+					storage: BTreeMap::new(),
+				});
+			}
+		}
+		for (&(ref address, ref key), value) in &self.storage {
+			if let Some(account) = accounts.get_mut(address) {
+				account.storage.insert(key.clone(), value.clone());
+			}
+		}
+
+		let backing = Arc::new(in_memory(NUM_COLUMNS.unwrap_or(0)));
+		let db = StateDB::new(journaldb::new(backing, Algorithm::Archive, COL_STATE), 0);
+		let mut state = State::new(db, engine.account_start_nonce(), factories.clone());
+		state.populate_from(PodState::from(accounts));
+		state.commit().expect("in-memory database writes cannot fail; qed");
+
+		let (root, db) = state.drop();
+		State::from_existing(db, root, engine.account_start_nonce(), factories)
+			.expect("state root has just been committed to the database; qed")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use util::{Address, H256, U256};
+	use env_info::EnvInfo;
+	use spec::Spec;
+	use types::transaction::{Transaction, Action};
+	use rustc_serialize::hex::FromHex;
+
+	fn call(to: Address) -> SignedTransaction {
+		Transaction {
+			nonce: 0.into(),
+			gas_price: 0.into(),
+			gas: 100_000.into(),
+			action: Action::Call(to),
+			value: 0.into(),
+			data: Vec::new(),
+		}.fake_sign(Address::from(0x10))
+	}
+
+	fn account(code: Bytes) -> Option<ProvedAccount> {
+		Some(ProvedAccount { nonce: 0.into(), balance: 0.into(), code: code })
+	}
+
+	#[test]
+	fn reports_missing_accounts() {
+		let spec = Spec::new_test();
+		let state = ProvedState::new();
+
+		match state.execute(&call(Address::from(0x20)), &EnvInfo::default(), &*spec.engine) {
+			Err(Error::Missing(missing)) => {
+				assert!(missing.contains(&StateItem::Account(Address::from(0x10))));
+				assert!(missing.contains(&StateItem::Account(Address::from(0x20))));
+			}
+			other => panic!("unexpected result: {:?}", other.map(|e| e.output)),
+		}
+	}
+
+	#[test]
+	fn executes_once_storage_is_known() {
+		let spec = Spec::new_test();
+		let contract = Address::from(0x20);
+		let mut state = ProvedState::new();
+		state.insert_account(Address::from(0x10), None);
+		state.insert_account(EnvInfo::default().author, None);
+		// return storage item 0.
+		state.insert_account(contract, account(FromHex::from_hex("60005460005260206000f3").unwrap()));
+
+		match state.execute(&call(contract), &EnvInfo::default(), &*spec.engine) {
+			Err(Error::Missing(missing)) => assert_eq!(missing, vec![StateItem::Storage(contract, H256::zero())]),
+			other => panic!("unexpected result: {:?}", other.map(|e| e.output)),
+		}
+
+		state.insert_storage(contract, H256::zero(), H256::from(U256::from(42)));
+		let executed = state.execute(&call(contract), &EnvInfo::default(), &*spec.engine).unwrap();
+		assert_eq!(executed.output, H256::from(U256::from(42)).to_vec());
+	}
+}
@@ -19,6 +19,7 @@
 use util::*;
 use io::*;
 use spec::Spec;
+use header::BlockNumber;
 use error::*;
 use client::{Client, ClientConfig, ChainNotify};
 use miner::Miner;
@@ -48,8 +49,14 @@ pub enum ClientIoMessage {
 	FeedBlockChunk(H256, Bytes),
 	/// Take a snapshot for the block with given number.
 	TakeSnapshot(u64),
+	/// Verify the state restored at the block with given number and state root.
+	VerifyRestoredState(u64, H256),
 	/// Trigger sealing update (useful for internal sealing).
 	UpdateSealing,
+	/// A validator missed its slot before the block with given number.
+	ReportBenign(Address, BlockNumber),
+	/// Flush and compact the database.
+	CompactDatabase,
 }
 
 /// Client service setup. Creates and registers client and network services with the IO subsystem.
@@ -91,8 +98,16 @@ impl ClientService {
 
 		db_config.compaction = config.db_compaction.compaction_profile(client_path);
 		db_config.wal = config.db_wal;
+		db_config.compaction_style = config.db_compaction_style;
+		if let Some(size) = config.db_wal_size {
+			db_config.max_wal_size = size as u64 * 1024 * 1024;
+		}
+		if let Some(size) = config.db_block_cache_size {
+			db_config.default_cache_size = size;
+		}
 
 		let pruning = config.pruning;
+		let verify_restored_state = config.verify_restored_state;
 		let client = try!(Client::new(config, &spec, client_path, miner, io_service.channel(), &db_config));
 
 		let snapshot_params = SnapServiceParams {
@@ -103,6 +118,7 @@ impl ClientService {
 			channel: io_service.channel(),
 			snapshot_root: snapshot_path.into(),
 			db_restore: client.clone(),
+			verify_restored_state: verify_restored_state,
 		};
 		let snapshot = Arc::new(try!(SnapshotService::new(snapshot_params)));
 
@@ -172,20 +188,24 @@ struct ClientIoHandler {
 
 const CLIENT_TICK_TIMER: TimerToken = 0;
 const SNAPSHOT_TICK_TIMER: TimerToken = 1;
+const SEAL_TICK_TIMER: TimerToken = 2;
 
 const CLIENT_TICK_MS: u64 = 5000;
 const SNAPSHOT_TICK_MS: u64 = 10000;
+const SEAL_TICK_MS: u64 = 1000;
 
 impl IoHandler<ClientIoMessage> for ClientIoHandler {
 	fn initialize(&self, io: &IoContext<ClientIoMessage>) {
 		io.register_timer(CLIENT_TICK_TIMER, CLIENT_TICK_MS).expect("Error registering client timer");
 		io.register_timer(SNAPSHOT_TICK_TIMER, SNAPSHOT_TICK_MS).expect("Error registering snapshot timer");
+		io.register_timer(SEAL_TICK_TIMER, SEAL_TICK_MS).expect("Error registering seal timer");
 	}
 
 	fn timeout(&self, _io: &IoContext<ClientIoMessage>, timer: TimerToken) {
 		match timer {
 			CLIENT_TICK_TIMER => self.client.tick(),
 			SNAPSHOT_TICK_TIMER => self.snapshot.tick(),
+			SEAL_TICK_TIMER => self.client.seal_interval_tick(),
 			_ => warn!("IO service triggered unregistered timer '{}'", timer),
 		}
 	}
@@ -218,10 +238,37 @@ impl IoHandler<ClientIoMessage> for ClientIoHandler {
 					debug!(target: "snapshot", "Failed to initialize periodic snapshot thread: {:?}", e);
 				}
 			},
+			ClientIoMessage::VerifyRestoredState(num, ref root) => {
+				let client = self.client.clone();
+				let snapshot = self.snapshot.clone();
+				let root = root.clone();
+
+				let res = thread::Builder::new().name("State Verification".into()).spawn(move || {
+					if let Err(e) = snapshot.verify_restored_state(&*client, num, &root) {
+						warn!("Restored state at block #{} failed verification: {}", num, e);
+					}
+				});
+
+				if let Err(e) = res {
+					debug!(target: "snapshot", "Failed to initialize state verification thread: {:?}", e);
+				}
+			},
 			ClientIoMessage::UpdateSealing => {
 				trace!(target: "authorityround", "message: UpdateSealing");
 				self.client.update_sealing()
 			},
+			ClientIoMessage::ReportBenign(ref validator, number) => self.client.report_benign(validator, number),
+			ClientIoMessage::CompactDatabase => {
+				let client = self.client.clone();
+
+				let res = thread::Builder::new().name("Database Compaction".into()).spawn(move || {
+					client.compact_database();
+				});
+
+				if let Err(e) = res {
+					debug!(target: "client", "Failed to initialize database compaction thread: {:?}", e);
+				}
+			},
 			_ => {} // ignore other messages
 		}
 	}
@@ -209,6 +209,11 @@ impl Account {
 		&self.code_hash
 	}
 
+	/// Get the account's storage root.
+	pub fn storage_root(&self) -> &H256 {
+		&self.storage_root
+	}
+
 	#[cfg(test)]
 	pub fn storage_root_mut(&mut self) -> &mut H256 {
 		&mut self.storage_root
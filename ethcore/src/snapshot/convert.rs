@@ -0,0 +1,112 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Conversion of a database between pruning algorithms.
+//!
+//! Blocks, receipts and traces do not depend on the pruning algorithm and are copied
+//! verbatim. The state at the best block is chunked and fed back through the
+//! `StateRebuilder`, exactly as it would be when restoring from a snapshot.
+
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+use blockchain::{BlockChain, BlockProvider};
+use client::Error as ClientError;
+use error::Error;
+
+use util::{snappy, Mutex};
+use util::hash::H256;
+use util::journaldb::{self, Algorithm};
+use util::kvdb::KeyValueDB;
+
+use super::io::SnapshotWriter;
+use super::{chunk_state, Progress, StateRebuilder, ManifestData, SNAPSHOT_VERSION};
+use super::error::Error as SnapshotError;
+
+/// Number of bytes copied in a single database transaction.
+const COPY_BATCH_SIZE: usize = 4 * 1024 * 1024;
+
+// snapshot writer which feeds state chunks straight into a rebuilder.
+struct RebuildingWriter {
+	rebuilder: StateRebuilder,
+	snappy_buffer: Vec<u8>,
+	flag: AtomicBool,
+}
+
+impl SnapshotWriter for RebuildingWriter {
+	fn write_state_chunk(&mut self, _hash: H256, chunk: &[u8]) -> io::Result<()> {
+		let len = try!(snappy::decompress_into(chunk, &mut self.snappy_buffer)
+			.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e))));
+		self.rebuilder.feed(&self.snappy_buffer[..len], &self.flag)
+			.map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{}", e)))
+	}
+
+	fn write_block_chunk(&mut self, _hash: H256, _chunk: &[u8]) -> io::Result<()> {
+		Ok(())
+	}
+
+	fn finish(self, _manifest: ManifestData) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+/// Rewrite the database `source`, pruned with `from`, into the empty database `target`
+/// using the `to` algorithm. Only the state at the best block is carried over, so converting
+/// into an archive database keeps full history only from that block onwards.
+pub fn convert_pruning(source: Arc<KeyValueDB>, from: Algorithm, target: Arc<KeyValueDB>, to: Algorithm, progress: &Progress) -> Result<(), Error> {
+	let chain = BlockChain::new(Default::default(), &[], source.clone());
+	let best_hash = chain.best_block_hash();
+	let best_header = try!(chain.block_header(&best_hash).ok_or(SnapshotError::BlockNotFound(best_hash)));
+
+	info!(target: "snapshot", "Converting database from {} to {} at block #{}", from, to, best_header.number());
+
+	for col in 0..::db::NUM_COLUMNS.unwrap_or(0) {
+		let col = Some(col);
+		if col == ::db::COL_STATE || col == ::db::COL_ACCOUNT_BLOOM { continue }
+
+		let mut batch = target.transaction();
+		let mut size = 0;
+		for (key, value) in source.iter(col) {
+			size += key.len() + value.len();
+			batch.put(col, &key, &value);
+			if size >= COPY_BATCH_SIZE {
+				try!(target.write(batch).map_err(ClientError::Database));
+				batch = target.transaction();
+				size = 0;
+			}
+		}
+		try!(target.write(batch).map_err(ClientError::Database));
+	}
+
+	let state_db = journaldb::new(source, from, ::db::COL_STATE);
+	let writer = Mutex::new(RebuildingWriter {
+		rebuilder: StateRebuilder::new(target.clone(), to, SNAPSHOT_VERSION),
+		snappy_buffer: Vec::new(),
+		flag: AtomicBool::new(true),
+	});
+
+	try!(chunk_state(state_db.as_hashdb(), best_header.state_root(), &writer, progress));
+
+	let rebuilder = writer.into_inner().rebuilder;
+	if rebuilder.state_root() != *best_header.state_root() {
+		return Err(SnapshotError::WrongStateRoot(*best_header.state_root(), rebuilder.state_root()).into());
+	}
+	try!(rebuilder.check_missing());
+	try!(target.flush().map_err(ClientError::Database));
+
+	Ok(())
+}
@@ -45,8 +45,14 @@ pub enum Error {
 	MissingCode(Vec<H256>),
 	/// Unrecognized code encoding.
 	UnrecognizedCodeState(u8),
+	/// Snapshot format version which can't be restored.
+	UnsupportedVersion(u64),
 	/// Restoration aborted.
 	RestorationAborted,
+	/// State value does not hash to its key.
+	CorruptedState(H256),
+	/// State verification aborted.
+	VerificationAborted,
 	/// Trie error.
 	Trie(TrieError),
 	/// Decoder error.
@@ -69,7 +75,11 @@ impl fmt::Display for Error {
 				a pruned database. Please re-run with the --pruning archive flag."),
 			Error::MissingCode(ref missing) => write!(f, "Incomplete snapshot: {} contract codes not found.", missing.len()),
 			Error::UnrecognizedCodeState(state) => write!(f, "Unrecognized code encoding ({})", state),
+			Error::UnsupportedVersion(version) => write!(f, "Unsupported snapshot format version {}. Supported versions: {} to {}.",
+				version, super::MIN_SUPPORTED_VERSION, super::SNAPSHOT_VERSION),
 			Error::RestorationAborted => write!(f, "Snapshot restoration aborted."),
+			Error::CorruptedState(ref hash) => write!(f, "State value stored under {} has a different hash.", hash),
+			Error::VerificationAborted => write!(f, "State verification aborted."),
 			Error::Io(ref err) => err.fmt(f),
 			Error::Decoder(ref err) => err.fmt(f),
 			Error::Trie(ref err) => err.fmt(f),
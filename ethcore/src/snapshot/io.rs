@@ -29,7 +29,7 @@ use util::Bytes;
 use util::hash::H256;
 use rlp::{self, Encodable, RlpStream, UntrustedRlp, Stream, View};
 
-use super::ManifestData;
+use super::{ManifestData, LEGACY_SNAPSHOT_VERSION};
 
 /// Something which can write snapshots.
 /// Writing the same chunk multiple times will lead to implementation-defined
@@ -120,13 +120,14 @@ impl SnapshotWriter for PackedWriter {
 	fn finish(mut self, manifest: ManifestData) -> io::Result<()> {
 		// we ignore the hashes fields of the manifest under the assumption that
 		// they are consistent with ours.
-		let mut stream = RlpStream::new_list(5);
+		let mut stream = RlpStream::new_list(6);
 		stream
 			.append(&self.state_hashes)
 			.append(&self.block_hashes)
 			.append(&manifest.state_root)
 			.append(&manifest.block_number)
-			.append(&manifest.block_hash);
+			.append(&manifest.block_hash)
+			.append(&manifest.version);
 
 		let manifest_rlp = stream.out();
 
@@ -260,7 +261,14 @@ impl PackedReader {
 		let state: Vec<ChunkInfo> = try!(rlp.val_at(0));
 		let blocks: Vec<ChunkInfo> = try!(rlp.val_at(1));
 
+		// files written before versioning have no version field.
+		let version = match rlp.item_count() {
+			5 => LEGACY_SNAPSHOT_VERSION,
+			_ => try!(rlp.val_at(5)),
+		};
+
 		let manifest = ManifestData {
+			version: version,
 			state_hashes: state.iter().map(|c| c.0).collect(),
 			block_hashes: blocks.iter().map(|c| c.0).collect(),
 			state_root: try!(rlp.val_at(2)),
@@ -347,7 +355,7 @@ mod tests {
 	use devtools::RandomTempPath;
 	use util::sha3::Hashable;
 
-	use snapshot::ManifestData;
+	use snapshot::{ManifestData, SNAPSHOT_VERSION};
 	use super::{SnapshotWriter, SnapshotReader, PackedWriter, PackedReader, LooseWriter, LooseReader};
 
 	const STATE_CHUNKS: &'static [&'static [u8]] = &[b"dog", b"cat", b"hello world", b"hi", b"notarealchunk"];
@@ -374,6 +382,7 @@ mod tests {
 		}
 
 		let manifest = ManifestData {
+			version: SNAPSHOT_VERSION,
 			state_hashes: state_hashes,
 			block_hashes: block_hashes,
 			state_root: b"notarealroot".sha3(),
@@ -412,6 +421,7 @@ mod tests {
 		}
 
 		let manifest = ManifestData {
+			version: SNAPSHOT_VERSION,
 			state_hashes: state_hashes,
 			block_hashes: block_hashes,
 			state_root: b"notarealroot".sha3(),
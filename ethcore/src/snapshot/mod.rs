@@ -34,7 +34,7 @@ use util::{Bytes, Hashable, HashDB, DBValue, snappy, U256, Uint};
 use util::Mutex;
 use util::hash::{FixedHash, H256};
 use util::journaldb::{self, Algorithm, JournalDB};
-use util::kvdb::Database;
+use util::kvdb::KeyValueDB;
 use util::trie::{TrieDB, TrieDBMut, Trie, TrieMut};
 use util::sha3::SHA3_NULL_RLP;
 use rlp::{RlpStream, Stream, UntrustedRlp, View};
@@ -55,7 +55,9 @@ pub use self::error::Error;
 pub use self::service::{Service, DatabaseRestore};
 pub use self::traits::SnapshotService;
 pub use self::watcher::Watcher;
-pub use types::snapshot_manifest::ManifestData;
+pub use self::convert::convert_pruning;
+pub use self::verify::verify_state;
+pub use types::snapshot_manifest::{ManifestData, LEGACY_SNAPSHOT_VERSION};
 pub use types::restoration_status::RestorationStatus;
 
 pub mod io;
@@ -63,7 +65,9 @@ pub mod service;
 
 mod account;
 mod block;
+mod convert;
 mod error;
+mod verify;
 mod watcher;
 
 #[cfg(test)]
@@ -80,6 +84,23 @@ mod traits {
 	include!(concat!(env!("OUT_DIR"), "/snapshot_service_trait.rs"));
 }
 
+/// Snapshot format version written by this client.
+///
+/// The version is recorded in the manifest and determines the encoding of all chunks.
+/// Version 2 introduced the version field; its chunks are encoded as in version 1.
+pub const SNAPSHOT_VERSION: u64 = 2;
+
+/// Oldest snapshot format version which can still be restored.
+pub const MIN_SUPPORTED_VERSION: u64 = LEGACY_SNAPSHOT_VERSION;
+
+/// Check whether snapshots of the given format version can be restored.
+pub fn check_version(version: u64) -> Result<(), Error> {
+	match version >= MIN_SUPPORTED_VERSION && version <= SNAPSHOT_VERSION {
+		true => Ok(()),
+		false => Err(Error::UnsupportedVersion(version)),
+	}
+}
+
 // Try to have chunks be around 4MB (before compression)
 const PREFERRED_CHUNK_SIZE: usize = 4 * 1024 * 1024;
 
@@ -148,6 +169,7 @@ pub fn take_snapshot<W: SnapshotWriter + Send>(
 	info!("produced {} state chunks and {} block chunks.", state_hashes.len(), block_hashes.len());
 
 	let manifest_data = ManifestData {
+		version: SNAPSHOT_VERSION,
 		state_hashes: state_hashes,
 		block_hashes: block_hashes,
 		state_root: *state_root,
@@ -195,7 +217,7 @@ impl<'a> BlockChunker<'a> {
 
 			let pair = {
 				let mut pair_stream = RlpStream::new_list(2);
-				pair_stream.append_raw(&abridged_rlp, 1).append(&receipts);
+				pair_stream.append_raw(&abridged_rlp, 1).append(&receipts.receipts);
 				pair_stream.out()
 			};
 
@@ -392,22 +414,33 @@ pub struct StateRebuilder {
 	known_code: HashMap<H256, H256>, // code hashes mapped to first account with this code.
 	missing_code: HashMap<H256, Vec<H256>>, // maps code hashes to lists of accounts missing that code.
 	bloom: Bloom,
+	version: u64,
 }
 
 impl StateRebuilder {
-	/// Create a new state rebuilder to write into the given backing DB.
-	pub fn new(db: Arc<Database>, pruning: Algorithm) -> Self {
+	/// Create a new state rebuilder to write into the given backing DB,
+	/// reading chunks of the given snapshot format version.
+	pub fn new(db: Arc<KeyValueDB>, pruning: Algorithm, version: u64) -> Self {
 		StateRebuilder {
 			db: journaldb::new(db.clone(), pruning, ::db::COL_STATE),
 			state_root: SHA3_NULL_RLP,
 			known_code: HashMap::new(),
 			missing_code: HashMap::new(),
 			bloom: StateDB::load_bloom(&*db),
+			version: version,
 		}
 	}
 
 	/// Feed an uncompressed state chunk into the rebuilder.
 	pub fn feed(&mut self, chunk: &[u8], flag: &AtomicBool) -> Result<(), ::error::Error> {
+		// versions 1 and 2 share the chunk encoding.
+		match self.version {
+			LEGACY_SNAPSHOT_VERSION | SNAPSHOT_VERSION => self.feed_accounts(chunk, flag),
+			version => Err(Error::UnsupportedVersion(version).into()),
+		}
+	}
+
+	fn feed_accounts(&mut self, chunk: &[u8], flag: &AtomicBool) -> Result<(), ::error::Error> {
 		let rlp = UntrustedRlp::new(chunk);
 		let empty_rlp = StateAccount::new_basic(U256::zero(), U256::zero()).rlp();
 		let mut pairs = Vec::with_capacity(rlp.item_count());
@@ -571,18 +604,19 @@ pub fn verify_old_block(rng: &mut OsRng, header: &Header, engine: &Engine, chain
 /// After all chunks have been submitted, we "glue" the chunks together.
 pub struct BlockRebuilder {
 	chain: BlockChain,
-	db: Arc<Database>,
+	db: Arc<KeyValueDB>,
 	rng: OsRng,
 	disconnected: Vec<(u64, H256)>,
 	best_number: u64,
 	best_hash: H256,
 	best_root: H256,
 	fed_blocks: u64,
+	version: u64,
 }
 
 impl BlockRebuilder {
 	/// Create a new BlockRebuilder.
-	pub fn new(chain: BlockChain, db: Arc<Database>, manifest: &ManifestData) -> Result<Self, ::error::Error> {
+	pub fn new(chain: BlockChain, db: Arc<KeyValueDB>, manifest: &ManifestData) -> Result<Self, ::error::Error> {
 		Ok(BlockRebuilder {
 			chain: chain,
 			db: db,
@@ -592,12 +626,21 @@ impl BlockRebuilder {
 			best_hash: manifest.block_hash,
 			best_root: manifest.state_root,
 			fed_blocks: 0,
+			version: manifest.version,
 		})
 	}
 
 	/// Feed the rebuilder an uncompressed block chunk.
 	/// Returns the number of blocks fed or any errors.
 	pub fn feed(&mut self, chunk: &[u8], engine: &Engine, abort_flag: &AtomicBool) -> Result<u64, ::error::Error> {
+		// versions 1 and 2 share the chunk encoding.
+		match self.version {
+			LEGACY_SNAPSHOT_VERSION | SNAPSHOT_VERSION => self.feed_blocks(chunk, engine, abort_flag),
+			version => Err(Error::UnsupportedVersion(version).into()),
+		}
+	}
+
+	fn feed_blocks(&mut self, chunk: &[u8], engine: &Engine, abort_flag: &AtomicBool) -> Result<u64, ::error::Error> {
 		use basic_types::Seal::With;
 		use util::U256;
 		use util::triehash::ordered_trie_root;
@@ -24,6 +24,7 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use super::{ManifestData, StateRebuilder, BlockRebuilder, RestorationStatus, SnapshotService};
+use super::Error as SnapshotError;
 use super::io::{SnapshotReader, LooseReader, SnapshotWriter, LooseWriter};
 
 use blockchain::BlockChain;
@@ -92,6 +93,7 @@ impl Restoration {
 	// make a new restoration using the given parameters.
 	fn new(params: RestorationParams) -> Result<Self, Error> {
 		let manifest = params.manifest;
+		try!(super::check_version(manifest.version));
 
 		let state_chunks = manifest.state_hashes.iter().cloned().collect();
 		let block_chunks = manifest.block_hashes.iter().cloned().collect();
@@ -103,11 +105,12 @@ impl Restoration {
 		let blocks = try!(BlockRebuilder::new(chain, raw_db.clone(), &manifest));
 
 		let root = manifest.state_root.clone();
+		let version = manifest.version;
 		Ok(Restoration {
 			manifest: manifest,
 			state_chunks_left: state_chunks,
 			block_chunks_left: block_chunks,
-			state: StateRebuilder::new(raw_db.clone(), params.pruning),
+			state: StateRebuilder::new(raw_db.clone(), params.pruning, version),
 			blocks: blocks,
 			writer: params.writer,
 			snappy_buffer: Vec::new(),
@@ -205,6 +208,8 @@ pub struct ServiceParams {
 	pub snapshot_root: PathBuf,
 	/// A handle for database restoration.
 	pub db_restore: Arc<DatabaseRestore>,
+	/// Whether to verify the state in the background after a restoration.
+	pub verify_restored_state: bool,
 }
 
 /// `SnapshotService` implementation.
@@ -225,6 +230,9 @@ pub struct Service {
 	progress: super::Progress,
 	taking_snapshot: AtomicBool,
 	restoring_snapshot: AtomicBool,
+	verify_restored_state: bool,
+	verifying_state: AtomicBool,
+	verification_progress: super::Progress,
 }
 
 impl Service {
@@ -246,6 +254,9 @@ impl Service {
 			progress: Default::default(),
 			taking_snapshot: AtomicBool::new(false),
 			restoring_snapshot: AtomicBool::new(false),
+			verify_restored_state: params.verify_restored_state,
+			verifying_state: AtomicBool::new(false),
+			verification_progress: Default::default(),
 		};
 
 		// create the root snapshot dir if it doesn't exist.
@@ -326,6 +337,11 @@ impl Service {
 	/// Tick the snapshot service. This will log any active snapshot
 	/// being taken.
 	pub fn tick(&self) {
+		if self.verifying_state.load(Ordering::SeqCst) {
+			let p = &self.verification_progress;
+			info!("Verifying restored state: {} accounts {} bytes", p.accounts(), p.size());
+		}
+
 		if self.progress.done() || !self.taking_snapshot.load(Ordering::SeqCst) { return }
 
 		let p = &self.progress;
@@ -388,6 +404,42 @@ impl Service {
 		Ok(())
 	}
 
+	/// Re-walk the state restored at the block with the given number, checking that every
+	/// node is present and hashes to its key. The client keeps importing blocks meanwhile, so
+	/// this only succeeds if the restored state is not pruned before the walk completes.
+	pub fn verify_restored_state(&self, client: &Client, num: u64, root: &H256) -> Result<(), Error> {
+		if self.verifying_state.compare_and_swap(false, true, Ordering::SeqCst) {
+			info!("Skipping verification of restored state at #{} as another one is in progress.", num);
+			return Ok(());
+		}
+
+		info!("Verifying restored state at #{}", num);
+		self.verification_progress.reset();
+
+		let res = client.verify_state(root, &self.verification_progress, &self.verifying_state);
+		self.verifying_state.store(false, Ordering::SeqCst);
+		if let Err(Error::Snapshot(SnapshotError::VerificationAborted)) = res {
+			info!("Verification of restored state at #{} aborted.", num);
+			return Ok(());
+		}
+		if let Err(e) = res {
+			if client.chain_info().best_block_number >= num + client.pruning_history() {
+				info!("Verification of restored state failed: block state pruned. \
+					Run with a longer `--pruning-history` to verify the restored state.");
+				return Ok(());
+			}
+			return Err(e);
+		}
+
+		info!("Verified restored state at #{}: {} accounts {} bytes", num, self.verification_progress.accounts(), self.verification_progress.size());
+		Ok(())
+	}
+
+	/// Abort an in-progress verification of restored state, if there is one.
+	pub fn abort_verification(&self) {
+		self.verifying_state.store(false, Ordering::SeqCst);
+	}
+
 	/// Initialize the restoration synchronously.
 	/// The recover flag indicates whether to recover the restored snapshot.
 	pub fn init_restore(&self, manifest: ManifestData, recover: bool) -> Result<(), Error> {
@@ -450,6 +502,7 @@ impl Service {
 		trace!(target: "snapshot", "finalizing restoration");
 
 		let recover = rest.as_ref().map_or(false, |rest| rest.writer.is_some());
+		let restored = rest.as_ref().map(|rest| (rest.manifest.block_number, rest.manifest.state_root.clone()));
 
 		// destroy the restoration before replacing databases and snapshot.
 		try!(rest.take().map(Restoration::finalize).unwrap_or(Ok(())));
@@ -475,6 +528,12 @@ impl Service {
 		let _ = fs::remove_dir_all(self.restoration_dir());
 		*self.status.lock() = RestorationStatus::Inactive;
 
+		if let (true, Some((num, root))) = (self.verify_restored_state, restored) {
+			if let Err(e) = self.io_channel.lock().send(ClientIoMessage::VerifyRestoredState(num, root)) {
+				trace!("Error sending snapshot service message: {:?}", e);
+			}
+		}
+
 		Ok(())
 	}
 
@@ -606,6 +665,7 @@ impl SnapshotService for Service {
 impl Drop for Service {
 	fn drop(&mut self) {
 		self.abort_restore();
+		self.abort_verification();
 	}
 }
 
@@ -647,6 +707,7 @@ mod tests {
 			channel: service.channel(),
 			snapshot_root: dir,
 			db_restore: Arc::new(NoopDBRestore),
+			verify_restored_state: false,
 		};
 
 		let service = Service::new(snapshot_params).unwrap();
@@ -656,6 +717,7 @@ mod tests {
 		assert_eq!(service.status(), RestorationStatus::Inactive);
 
 		let manifest = ManifestData {
+			version: super::SNAPSHOT_VERSION,
 			state_hashes: vec![],
 			block_hashes: vec![],
 			state_root: Default::default(),
@@ -22,11 +22,14 @@ mod service;
 
 pub mod helpers;
 
-use super::ManifestData;
+use super::{ManifestData, SNAPSHOT_VERSION, check_version};
+use rlp::{RlpStream, Stream};
+use util::H256;
 
 #[test]
 fn manifest_rlp() {
 	let manifest = ManifestData {
+		version: SNAPSHOT_VERSION,
 		block_hashes: Vec::new(),
 		state_hashes: Vec::new(),
 		block_number: 1234567,
@@ -35,4 +38,28 @@ fn manifest_rlp() {
 	};
 	let raw = manifest.clone().into_rlp();
 	assert_eq!(ManifestData::from_rlp(&raw).unwrap(), manifest);
-}
\ No newline at end of file
+}
+
+#[test]
+fn legacy_manifest_rlp() {
+	// manifests written before versioning.
+	let mut stream = RlpStream::new_list(5);
+	stream.append(&Vec::<H256>::new())
+		.append(&vec![H256::from(1)])
+		.append(&H256::from(2))
+		.append(&1234567u64)
+		.append(&H256::from(3));
+
+	let manifest = ManifestData::from_rlp(&stream.out()).unwrap();
+	assert_eq!(manifest.version, 1);
+	assert_eq!(manifest.block_hashes, vec![H256::from(1)]);
+	assert_eq!(manifest.block_hash, H256::from(3));
+	assert!(check_version(manifest.version).is_ok());
+}
+
+#[test]
+fn rejects_future_versions() {
+	assert!(check_version(SNAPSHOT_VERSION).is_ok());
+	assert!(check_version(SNAPSHOT_VERSION + 1).is_err());
+	assert!(check_version(0).is_err());
+}
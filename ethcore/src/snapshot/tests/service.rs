@@ -73,6 +73,7 @@ fn restored_is_equivalent() {
 		channel: IoChannel::disconnected(),
 		snapshot_root: path,
 		db_restore: client2.clone(),
+		verify_restored_state: false,
 	};
 
 	let service = Service::new(service_params).unwrap();
@@ -116,12 +117,14 @@ fn guards_delete_folders() {
 		channel: IoChannel::disconnected(),
 		snapshot_root: path.clone(),
 		db_restore: Arc::new(NoopDBRestore),
+		verify_restored_state: false,
 	};
 
 	let service = Service::new(service_params).unwrap();
 	path.push("restoration");
 
 	let manifest = ManifestData {
+		version: snapshot::SNAPSHOT_VERSION,
 		state_hashes: vec![],
 		block_hashes: vec![],
 		block_number: 0,
@@ -16,7 +16,7 @@
 
 //! State snapshotting tests.
 
-use snapshot::{chunk_state, Error as SnapshotError, Progress, StateRebuilder};
+use snapshot::{chunk_state, verify_state, Error as SnapshotError, Progress, StateRebuilder, SNAPSHOT_VERSION};
 use snapshot::account::Account;
 use snapshot::io::{PackedReader, PackedWriter, SnapshotReader, SnapshotWriter};
 use super::helpers::{compare_dbs, StateProducer};
@@ -57,6 +57,7 @@ fn snap_and_restore() {
 	let state_hashes = chunk_state(&old_db, &state_root, &writer, &Progress::default()).unwrap();
 
 	writer.into_inner().finish(::snapshot::ManifestData {
+		version: SNAPSHOT_VERSION,
 		state_hashes: state_hashes,
 		block_hashes: Vec::new(),
 		state_root: state_root,
@@ -68,7 +69,7 @@ fn snap_and_restore() {
 	db_path.push("db");
 	let db = {
 		let new_db = Arc::new(Database::open(&db_cfg, &db_path.to_string_lossy()).unwrap());
-		let mut rebuilder = StateRebuilder::new(new_db.clone(), Algorithm::Archive);
+		let mut rebuilder = StateRebuilder::new(new_db.clone(), Algorithm::Archive, SNAPSHOT_VERSION);
 		let reader = PackedReader::new(&snap_file).unwrap().unwrap();
 
 		let flag = AtomicBool::new(true);
@@ -134,7 +135,7 @@ fn get_code_from_prev_chunk() {
 	let db_cfg = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
 	let new_db = Arc::new(Database::open(&db_cfg, &db_path.to_string_lossy()).unwrap());
 
-	let mut rebuilder = StateRebuilder::new(new_db, Algorithm::Archive);
+	let mut rebuilder = StateRebuilder::new(new_db, Algorithm::Archive, SNAPSHOT_VERSION);
 	let flag = AtomicBool::new(true);
 
 	rebuilder.feed(&chunk1, &flag).unwrap();
@@ -164,6 +165,7 @@ fn checks_flag() {
 	let state_hashes = chunk_state(&old_db, &state_root, &writer, &Progress::default()).unwrap();
 
 	writer.into_inner().finish(::snapshot::ManifestData {
+		version: SNAPSHOT_VERSION,
 		state_hashes: state_hashes,
 		block_hashes: Vec::new(),
 		state_root: state_root,
@@ -175,7 +177,7 @@ fn checks_flag() {
 	db_path.push("db");
 	{
 		let new_db = Arc::new(Database::open(&db_cfg, &db_path.to_string_lossy()).unwrap());
-		let mut rebuilder = StateRebuilder::new(new_db.clone(), Algorithm::Archive);
+		let mut rebuilder = StateRebuilder::new(new_db.clone(), Algorithm::Archive, SNAPSHOT_VERSION);
 		let reader = PackedReader::new(&snap_file).unwrap().unwrap();
 
 		let flag = AtomicBool::new(false);
@@ -190,4 +192,49 @@ fn checks_flag() {
 			}
 		}
 	}
-}
\ No newline at end of file
+}
+
+#[test]
+fn rejects_chunks_of_unsupported_version() {
+	let db_path = RandomTempPath::create_dir();
+	let db_cfg = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let new_db = Arc::new(Database::open(&db_cfg, &db_path.to_string_lossy()).unwrap());
+
+	let mut rebuilder = StateRebuilder::new(new_db, Algorithm::Archive, SNAPSHOT_VERSION + 1);
+	match rebuilder.feed(&::rlp::EMPTY_LIST_RLP, &AtomicBool::new(true)) {
+		Err(Error::Snapshot(SnapshotError::UnsupportedVersion(v))) => assert_eq!(v, SNAPSHOT_VERSION + 1),
+		_ => panic!("unexpected result when feeding a chunk of unsupported version"),
+	}
+}
+
+#[test]
+fn verifies_state() {
+	use util::{HashDB, DBValue};
+
+	let mut producer = StateProducer::new();
+	let mut rng = XorShiftRng::from_seed([9, 10, 11, 12]);
+	let mut db = MemoryDB::new();
+
+	for _ in 0..50 {
+		producer.tick(&mut rng, &mut db);
+	}
+
+	let state_root = producer.state_root();
+	let flag = AtomicBool::new(true);
+	let progress = Progress::default();
+	verify_state(&db, &state_root, &progress, &flag).unwrap();
+	assert!(progress.accounts() > 0);
+
+	// replace the root node with garbage stored under the same key.
+	let mut root_node = db.get(&state_root).unwrap().to_vec();
+	*root_node.last_mut().unwrap() ^= 0xff;
+	while db.contains(&state_root) {
+		db.remove_and_purge(&state_root);
+	}
+	db.emplace(state_root.clone(), DBValue::from_vec(root_node));
+
+	match verify_state(&db, &state_root, &Progress::default(), &flag) {
+		Err(SnapshotError::CorruptedState(hash)) => assert_eq!(hash, state_root),
+		_ => panic!("corrupted root node was not detected"),
+	}
+}
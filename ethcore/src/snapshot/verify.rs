@@ -0,0 +1,125 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Verification of a restored state.
+//!
+//! The account trie, every storage trie and all contract code are read back from the
+//! database and each value is hashed and compared with the key it is stored under.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use account_db::AccountDB;
+use util::{HashDB, DBValue, Hashable, Mutex};
+use util::hash::{FixedHash, H256};
+use util::sha3::SHA3_EMPTY;
+use util::trie::{TrieDB, Trie};
+
+use super::account::Account;
+use super::{Error, Progress};
+
+// read-only database which hides values not hashing to their key and remembers the first such key.
+// the tries only read through it; writes are dropped so the checked database is never altered.
+struct CheckedDB<'a> {
+	db: &'a HashDB,
+	progress: &'a Progress,
+	corrupted: &'a Mutex<Option<H256>>,
+}
+
+impl<'a> HashDB for CheckedDB<'a> {
+	fn keys(&self) -> HashMap<H256, i32> {
+		self.db.keys()
+	}
+
+	fn get(&self, key: &H256) -> Option<DBValue> {
+		let value = match self.db.get(key) {
+			Some(value) => value,
+			None => return None,
+		};
+
+		if &(&*value).sha3() != key {
+			let mut corrupted = self.corrupted.lock();
+			if corrupted.is_none() {
+				*corrupted = Some(key.clone());
+			}
+			return None;
+		}
+
+		self.progress.size.fetch_add(value.len(), Ordering::SeqCst);
+		Some(value)
+	}
+
+	fn contains(&self, key: &H256) -> bool {
+		self.get(key).is_some()
+	}
+
+	fn insert(&mut self, value: &[u8]) -> H256 {
+		warn!(target: "snapshot", "Ignoring write to the state being verified");
+		value.sha3()
+	}
+
+	fn emplace(&mut self, _key: H256, _value: DBValue) {
+		warn!(target: "snapshot", "Ignoring write to the state being verified");
+	}
+
+	fn remove(&mut self, _key: &H256) {
+		warn!(target: "snapshot", "Ignoring removal from the state being verified");
+	}
+}
+
+/// Walk the state under `root`, checking that every trie node and piece of code is present
+/// and hashes to the key it is stored under. Aborts early if `flag` becomes false.
+pub fn verify_state(db: &HashDB, root: &H256, progress: &Progress, flag: &AtomicBool) -> Result<(), Error> {
+	let corrupted = Mutex::new(None);
+	let res = walk_state(db, root, progress, &corrupted, flag);
+
+	// a value failing the hash check looks missing to the trie, so report it first.
+	match corrupted.into_inner() {
+		Some(hash) => Err(Error::CorruptedState(hash)),
+		None => res,
+	}
+}
+
+fn walk_state(db: &HashDB, root: &H256, progress: &Progress, corrupted: &Mutex<Option<H256>>, flag: &AtomicBool) -> Result<(), Error> {
+	let checked = CheckedDB { db: db, progress: progress, corrupted: corrupted };
+	let account_trie = try!(TrieDB::new(&checked, root));
+
+	// account_key here is the address' hash.
+	for item in try!(account_trie.iter()) {
+		if !flag.load(Ordering::SeqCst) {
+			return Err(Error::VerificationAborted);
+		}
+
+		let (account_key, account_data) = try!(item);
+		let account = Account::from_thin_rlp(&*account_data);
+
+		let account_db = AccountDB::from_hash(db, H256::from_slice(&account_key));
+		let checked_account = CheckedDB { db: &account_db, progress: progress, corrupted: corrupted };
+
+		let storage_trie = try!(TrieDB::new(&checked_account, account.storage_root()));
+		for item in try!(storage_trie.iter()) {
+			try!(item);
+		}
+
+		if account.code_hash() != &SHA3_EMPTY && checked_account.get(account.code_hash()).is_none() {
+			return Err(Error::MissingCode(vec![account.code_hash().clone()]));
+		}
+
+		progress.accounts.fetch_add(1, Ordering::SeqCst);
+	}
+
+	Ok(())
+}
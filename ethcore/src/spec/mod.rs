@@ -22,3 +22,4 @@ pub mod spec;
 
 pub use self::spec::*;
 pub use self::genesis::Genesis;
+pub use self::seal::{SealSchema, SealField, SealFieldType};
@@ -17,7 +17,8 @@
 //! Spec seal.
 
 use rlp::*;
-use util::hash::{H64, H256};
+use util::{H64, H256, H520, U256, Bytes, Mismatch};
+use error::BlockError;
 use ethjson;
 
 /// Classic ethereum seal.
@@ -79,3 +80,186 @@ impl Into<Generic> for Seal {
 		}
 	}
 }
+
+/// RLP type of a named seal field.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SealFieldType {
+	/// Unsigned integer.
+	Uint,
+	/// 32-byte hash.
+	Hash,
+	/// 8-byte nonce.
+	Nonce,
+	/// 65-byte signature.
+	Signature,
+	/// Arbitrary bytes.
+	Bytes,
+}
+
+impl SealFieldType {
+	/// Check that `rlp` decodes as a value of this type.
+	pub fn check(&self, rlp: &UntrustedRlp) -> Result<(), DecoderError> {
+		match *self {
+			SealFieldType::Uint => rlp.as_val::<U256>().map(|_| ()),
+			SealFieldType::Hash => rlp.as_val::<H256>().map(|_| ()),
+			SealFieldType::Nonce => rlp.as_val::<H64>().map(|_| ()),
+			SealFieldType::Signature => rlp.as_val::<H520>().map(|_| ()),
+			SealFieldType::Bytes => rlp.as_val::<Bytes>().map(|_| ()),
+		}
+	}
+}
+
+impl From<ethjson::spec::SealFieldType> for SealFieldType {
+	fn from(t: ethjson::spec::SealFieldType) -> Self {
+		match t {
+			ethjson::spec::SealFieldType::Uint => SealFieldType::Uint,
+			ethjson::spec::SealFieldType::Hash => SealFieldType::Hash,
+			ethjson::spec::SealFieldType::Nonce => SealFieldType::Nonce,
+			ethjson::spec::SealFieldType::Signature => SealFieldType::Signature,
+			ethjson::spec::SealFieldType::Bytes => SealFieldType::Bytes,
+		}
+	}
+}
+
+/// Named seal field of a block header.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SealField {
+	/// Field name.
+	pub name: String,
+	/// RLP type of the field.
+	pub field_type: SealFieldType,
+}
+
+impl SealField {
+	/// Create a new seal field.
+	pub fn new(name: &str, field_type: SealFieldType) -> Self {
+		SealField {
+			name: name.to_owned(),
+			field_type: field_type,
+		}
+	}
+}
+
+impl From<ethjson::spec::SealField> for SealField {
+	fn from(f: ethjson::spec::SealField) -> Self {
+		SealField {
+			name: f.name,
+			field_type: f.field_type.into(),
+		}
+	}
+}
+
+/// Ordered named fields making up the seal of a block header.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SealSchema {
+	fields: Vec<SealField>,
+}
+
+impl SealSchema {
+	/// Create a schema from fields in seal order.
+	pub fn new(fields: Vec<SealField>) -> Self {
+		SealSchema {
+			fields: fields,
+		}
+	}
+
+	/// Fields in seal order.
+	pub fn fields(&self) -> &[SealField] { &self.fields }
+
+	/// Number of seal fields.
+	pub fn len(&self) -> usize { self.fields.len() }
+
+	/// Whether the seal has no fields.
+	pub fn is_empty(&self) -> bool { self.fields.is_empty() }
+
+	/// Position of the field called `name` in the seal.
+	pub fn index(&self, name: &str) -> Option<usize> {
+		self.fields.iter().position(|f| f.name == name)
+	}
+
+	/// Check that `seal` has exactly the fields of this schema, each of the declared type.
+	pub fn verify(&self, seal: &[Bytes]) -> Result<(), BlockError> {
+		if seal.len() != self.fields.len() {
+			return Err(BlockError::InvalidSealArity(Mismatch { expected: self.fields.len(), found: seal.len() }));
+		}
+		for (index, (field, value)) in self.fields.iter().zip(seal).enumerate() {
+			if field.field_type.check(&UntrustedRlp::new(value)).is_err() {
+				return Err(BlockError::InvalidSealField(index));
+			}
+		}
+		Ok(())
+	}
+
+	/// Schema of an engine with the built-in `fields`, overridden by this declared one.
+	/// The declared schema must start with the built-in fields, in order, since engines
+	/// look them up; it may change their types and add fields after them.
+	pub fn with_builtin(&self, fields: Vec<SealField>) -> SealSchema {
+		if self.is_empty() {
+			return SealSchema::new(fields);
+		}
+		let compatible = self.fields.len() >= fields.len()
+			&& self.fields.iter().zip(&fields).all(|(declared, builtin)| declared.name == builtin.name);
+		match compatible {
+			true => self.clone(),
+			false => {
+				warn!(target: "spec", "Ignoring declared seal fields, they don't start with the engine fields {:?}", fields.iter().map(|f| &f.name).collect::<Vec<_>>());
+				SealSchema::new(fields)
+			},
+		}
+	}
+
+	/// Decode the field called `name` from `seal`.
+	pub fn field<T>(&self, seal: &[Bytes], name: &str) -> Result<T, DecoderError> where T: RlpDecodable {
+		let index = try!(self.index(name).ok_or(DecoderError::Custom("Unknown seal field")));
+		let value = try!(seal.get(index).ok_or(DecoderError::RlpIsTooShort));
+		UntrustedRlp::new(value).as_val()
+	}
+}
+
+impl From<Vec<ethjson::spec::SealField>> for SealSchema {
+	fn from(fields: Vec<ethjson::spec::SealField>) -> Self {
+		SealSchema::new(fields.into_iter().map(Into::into).collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rlp::encode;
+	use util::{H256, H520};
+	use error::BlockError;
+	use super::{SealSchema, SealField, SealFieldType};
+
+	fn schema() -> SealSchema {
+		SealSchema::new(vec![
+			SealField::new("step", SealFieldType::Uint),
+			SealField::new("signature", SealFieldType::Signature),
+		])
+	}
+
+	#[test]
+	fn verifies_seal_against_schema() {
+		let schema = schema();
+		let good = vec![encode(&3usize).to_vec(), encode(&H520::default()).to_vec()];
+		assert_eq!(schema.verify(&good), Ok(()));
+		assert_eq!(schema.field::<usize>(&good, "step"), Ok(3));
+
+		match schema.verify(&good[..1]) {
+			Err(BlockError::InvalidSealArity(_)) => {},
+			e => panic!("Unexpected result: {:?}", e),
+		}
+
+		let bad = vec![encode(&3usize).to_vec(), encode(&H256::default()).to_vec()];
+		assert_eq!(schema.verify(&bad), Err(BlockError::InvalidSealField(1)));
+		assert!(schema.field::<usize>(&good, "nonce").is_err());
+	}
+
+	#[test]
+	fn declared_schema_overrides_builtin_fields() {
+		let builtin = vec![SealField::new("step", SealFieldType::Uint)];
+		assert_eq!(SealSchema::default().with_builtin(builtin.clone()), SealSchema::new(builtin.clone()));
+		assert_eq!(schema().with_builtin(builtin.clone()), schema());
+
+		let unrelated = SealSchema::new(vec![SealField::new("nonce", SealFieldType::Nonce)]);
+		assert_eq!(unrelated.with_builtin(builtin.clone()), SealSchema::new(builtin));
+	}
+}
@@ -25,6 +25,7 @@ use header::{BlockNumber, Header};
 use state_db::StateDB;
 use super::genesis::Genesis;
 use super::seal::Generic as GenericSeal;
+use super::seal::SealSchema;
 use ethereum;
 use ethjson;
 use rlp::{Rlp, RlpStream, View, Stream};
@@ -45,6 +46,14 @@ pub struct CommonParams {
 	pub min_gas_limit: U256,
 	/// Fork block to check.
 	pub fork_block: Option<(BlockNumber, H256)>,
+	/// Contract deciding which nodes may connect, for permissioned networks.
+	pub node_permission_contract: Option<Address>,
+	/// Contract deciding which transactions are allowed, for permissioned networks.
+	pub transaction_permission_contract: Option<Address>,
+	/// Contract storing the target block gas limit in its first storage slot.
+	pub gas_limit_contract: Option<Address>,
+	/// Named seal fields declared by the spec, for engines without a built-in seal.
+	pub seal_schema: SealSchema,
 }
 
 impl From<ethjson::spec::Params> for CommonParams {
@@ -56,6 +65,10 @@ impl From<ethjson::spec::Params> for CommonParams {
 			subprotocol_name: p.subprotocol_name.unwrap_or_else(|| "eth".to_owned()),
 			min_gas_limit: p.min_gas_limit.into(),
 			fork_block: if let (Some(n), Some(h)) = (p.fork_block, p.fork_hash) { Some((n.into(), h.into())) } else { None },
+			node_permission_contract: p.node_permission_contract.map(Into::into),
+			transaction_permission_contract: p.transaction_permission_contract.map(Into::into),
+			gas_limit_contract: p.gas_limit_contract.map(Into::into),
+			seal_schema: p.seal_fields.map(Into::into).unwrap_or_default(),
 		}
 	}
 }
@@ -73,6 +86,10 @@ pub struct Spec {
 	/// Known nodes on the network in enode format.
 	pub nodes: Vec<String>,
 
+	/// Trusted block hashes by number, which light clients may sync from
+	/// instead of verifying every header since genesis.
+	pub checkpoints: BTreeMap<u64, H256>,
+
 	/// Parameters common to all engines.
 	pub params: CommonParams,
 
@@ -118,6 +135,9 @@ impl From<ethjson::spec::Spec> for Spec {
 			engine: Spec::engine(s.engine, params, builtins),
 			fork_name: s.fork_name.map(Into::into),
 			nodes: s.nodes.unwrap_or_else(Vec::new),
+			checkpoints: s.checkpoints.unwrap_or_else(BTreeMap::new).into_iter()
+				.map(|(number, hash)| (number.into(), hash.into()))
+				.collect(),
 			parent_hash: g.parent_hash,
 			transactions_root: g.transactions_root,
 			receipts_root: g.receipts_root,
@@ -166,6 +186,11 @@ impl Spec {
 	/// Get the known knodes of the network in enode format.
 	pub fn nodes(&self) -> &[String] { &self.nodes }
 
+	/// Get the highest trusted checkpoint, if any.
+	pub fn latest_checkpoint(&self) -> Option<(u64, H256)> {
+		self.checkpoints.iter().next_back().map(|(n, h)| (*n, h.clone()))
+	}
+
 	/// Get the configured Network ID.
 	pub fn network_id(&self) -> usize { self.params.network_id }
 
@@ -175,6 +200,12 @@ impl Spec {
 	/// Get the configured network fork block.
 	pub fn fork_block(&self) -> Option<(BlockNumber, H256)> { self.params.fork_block }
 
+	/// Get the contract deciding which nodes may connect, if any.
+	pub fn node_permission_contract(&self) -> Option<Address> { self.params.node_permission_contract }
+
+	/// Get the contract deciding which transactions are allowed, if any.
+	pub fn transaction_permission_contract(&self) -> Option<Address> { self.params.transaction_permission_contract }
+
 	/// Get the header of the genesis block.
 	pub fn genesis_header(&self) -> Header {
 		let mut header: Header = Default::default();
@@ -268,11 +299,34 @@ impl Spec {
 	/// Loads spec from json file.
 	pub fn load<R>(reader: R) -> Result<Self, String> where R: Read {
 		match ethjson::spec::Spec::load(reader) {
-			Ok(spec) => Ok(spec.into()),
+			Ok(spec) => {
+				try!(Spec::validate_engine(&spec.engine));
+				Ok(spec.into())
+			},
 			_ => Err("Spec json is invalid".into()),
 		}
 	}
 
+	/// Check engine parameters which can't be expressed in the json schema.
+	fn validate_engine(engine_spec: &ethjson::spec::Engine) -> Result<(), String> {
+		match *engine_spec {
+			ethjson::spec::Engine::Ethash(ref ethash) => match ethash.params.exp_diff_period.map(Into::<u64>::into) {
+				Some(0) => Err("Spec json is invalid: expDiffPeriod must be greater than zero".into()),
+				_ => Ok(()),
+			},
+			ethjson::spec::Engine::AuthorityRound(ref authority_round) => {
+				let params = &authority_round.params;
+				match params.authority_weights {
+					Some(ref weights) if weights.len() != params.authorities.len() => Err(format!(
+						"Spec json is invalid: {} authority weights given for {} authorities", weights.len(), params.authorities.len()
+					)),
+					_ => Ok(()),
+				}
+			},
+			_ => Ok(()),
+		}
+	}
+
 	/// Create a new Spec which conforms to the Frontier-era Morden chain except that it's a NullEngine consensus.
 	pub fn new_test() -> Spec { load_bundled!("null_morden") }
 
@@ -285,6 +339,9 @@ impl Spec {
 	/// Create a new Spec with AuthorityRound consensus which does internal sealing (not requiring work).
 	/// Accounts with secrets "1".sha3() and "2".sha3() are the authorities.
 	pub fn new_test_round() -> Self { load_bundled!("authority_round") }
+
+	/// Create a new Spec with BasicAuthority consensus. The account with secret "".sha3() is the only authority.
+	pub fn new_test_basic_authority() -> Self { load_bundled!("basic_authority") }
 }
 
 #[cfg(test)]
@@ -301,6 +358,16 @@ mod tests {
 		assert!(Spec::load(&[] as &[u8]).is_err());
 	}
 
+	#[test]
+	fn rejects_authority_weights_not_matching_authorities() {
+		let spec = String::from_utf8(include_bytes!("../../res/authority_round.json").to_vec()).unwrap();
+		let matching = spec.replace(r#""stepDuration": "1","#, r#""stepDuration": "1", "authorityWeights": ["1", "2"],"#);
+		let missing = spec.replace(r#""stepDuration": "1","#, r#""stepDuration": "1", "authorityWeights": ["1"],"#);
+
+		assert!(Spec::load(matching.as_bytes()).is_ok());
+		assert!(Spec::load(missing.as_bytes()).is_err());
+	}
+
 	#[test]
 	fn test_chain() {
 		let test_spec = Spec::new_test();
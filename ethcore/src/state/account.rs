@@ -317,6 +317,9 @@ impl Account {
 	/// Return the storage root associated with this account or None if it has been altered via the overlay.
 	pub fn storage_root(&self) -> Option<&H256> { if self.storage_is_clean() {Some(&self.storage_root)} else {None} }
 
+	/// Return the storage root the uncommitted storage changes apply to.
+	pub fn base_storage_root(&self) -> &H256 { &self.storage_root }
+
 	/// Return the storage overlay.
 	pub fn storage_changes(&self) -> &HashMap<H256, H256> { &self.storage_changes }
 
@@ -434,6 +437,15 @@ impl Account {
 		}
 		self.storage_changes = other.storage_changes;
 	}
+
+	/// Replace self with the data from other account, keeping the storage root.
+	/// `other` must be a later version of this account, with storage changes
+	/// including all of those already committed here.
+	pub fn overwrite_keeping_storage_root(&mut self, other: Account) {
+		let storage_root = self.storage_root.clone();
+		self.overwrite_with(other);
+		self.storage_root = storage_root;
+	}
 }
 
 impl fmt::Debug for Account {
@@ -17,6 +17,8 @@
 use std::cell::{RefCell, RefMut};
 use std::collections::hash_map::Entry;
 use util::*;
+use util::trie::recorder::{Recorder, BasicRecorder};
+use rlp::{Rlp, View};
 use receipt::Receipt;
 use engines::Engine;
 use env_info::EnvInfo;
@@ -27,10 +29,13 @@ use trace::FlatTrace;
 use pod_account::*;
 use pod_state::{self, PodState};
 use types::state_diff::StateDiff;
+use types::state_override::AccountOverride;
 use transaction::SignedTransaction;
 use state_db::StateDB;
 
 mod account;
+mod pipeline;
+mod speculative;
 mod substate;
 
 pub use self::account::Account;
@@ -190,6 +195,8 @@ pub struct State {
 	checkpoints: RefCell<Vec<HashMap<Address, Option<AccountEntry>>>>,
 	account_start_nonce: U256,
 	factories: Factories,
+	// accounts and storage keys read, when recording.
+	reads: RefCell<Option<HashMap<Address, HashSet<H256>>>>,
 }
 
 #[derive(Copy, Clone)]
@@ -211,7 +218,6 @@ const SEC_TRIE_DB_UNWRAP_STR: &'static str = "A state can only be created with v
 
 impl State {
 	/// Creates new state with empty state root
-	#[cfg(test)]
 	pub fn new(mut db: StateDB, account_start_nonce: U256, factories: Factories) -> State {
 		let mut root = H256::new();
 		{
@@ -226,6 +232,7 @@ impl State {
 			checkpoints: RefCell::new(Vec::new()),
 			account_start_nonce: account_start_nonce,
 			factories: factories,
+			reads: RefCell::new(None),
 		}
 	}
 
@@ -241,12 +248,75 @@ impl State {
 			cache: RefCell::new(HashMap::new()),
 			checkpoints: RefCell::new(Vec::new()),
 			account_start_nonce: account_start_nonce,
-			factories: factories
+			factories: factories,
+			reads: RefCell::new(None),
 		};
 
 		Ok(state)
 	}
 
+	/// Start recording every account and storage key read from this state,
+	/// including reads later reverted.
+	pub fn record_reads(&mut self) {
+		*self.reads.get_mut() = Some(HashMap::new());
+	}
+
+	/// Accounts read since `record_reads`, with the storage keys read from each.
+	pub fn reads(&self) -> HashMap<Address, HashSet<H256>> {
+		self.reads.borrow().clone().unwrap_or_else(HashMap::new)
+	}
+
+	/// Stop recording reads and return the ones recorded.
+	pub fn take_reads(&mut self) -> HashMap<Address, HashSet<H256>> {
+		self.reads.get_mut().take().unwrap_or_else(HashMap::new)
+	}
+
+	fn note_read(&self, address: &Address, key: Option<&H256>) {
+		if let Some(ref mut reads) = *self.reads.borrow_mut() {
+			let keys = reads.entry(address.clone()).or_insert_with(HashSet::new);
+			if let Some(key) = key {
+				keys.insert(key.clone());
+			}
+		}
+	}
+
+	/// Prove an account, and optionally one of its storage items, by the trie nodes
+	/// visited looking them up. Keys are the hashes of the address and storage key.
+	/// Nodes above `from_level` are omitted.
+	pub fn prove(&self, account_key: &H256, storage_key: Option<&H256>, from_level: u32) -> Result<Vec<Bytes>, Box<TrieError>> {
+		let mut recorder = BasicRecorder::with_depth(from_level);
+		let account = {
+			let trie = try!(TrieDB::new(self.db.as_hashdb(), &self.root));
+			try!(trie.get_recorded(&account_key[..], &mut recorder))
+		};
+
+		if let (Some(account), Some(storage_key)) = (account, storage_key) {
+			let storage_root: H256 = Rlp::new(&account).val_at(2);
+			let account_db = self.factories.accountdb.readonly(self.db.as_hashdb(), account_key.clone());
+			let trie = try!(TrieDB::new(&*account_db, &storage_root));
+			try!(trie.get_recorded(&storage_key[..], &mut recorder));
+		}
+
+		Ok(recorder.drain().into_iter().map(|record| record.data).collect())
+	}
+
+	/// Get the code of an account by the hash of its address.
+	pub fn code_by_address_hash(&self, account_key: &H256) -> Result<Option<Bytes>, Box<TrieError>> {
+		let trie = try!(TrieDB::new(self.db.as_hashdb(), &self.root));
+		let account = match try!(trie.get(&account_key[..])) {
+			Some(account) => account,
+			None => return Ok(None),
+		};
+
+		let code_hash: H256 = Rlp::new(&account).val_at(3);
+		if code_hash == SHA3_EMPTY {
+			return Ok(Some(Vec::new()));
+		}
+
+		let account_db = self.factories.accountdb.readonly(self.db.as_hashdb(), account_key.clone());
+		Ok(account_db.get(&code_hash).map(|code| code.to_vec()))
+	}
+
 	/// Create a recoverable checkpoint of this state.
 	pub fn checkpoint(&mut self) {
 		self.checkpoints.get_mut().push(HashMap::new());
@@ -377,6 +447,8 @@ impl State {
 
 	/// Mutate storage of account `address` so that it is `value` for `key`.
 	pub fn storage_at(&self, address: &Address, key: &H256) -> H256 {
+		self.note_read(address, Some(key));
+
 		// Storage key search and update works like this:
 		// 1. If there's an entry for the account in the local cache check for the key and return it if found.
 		// 2. If there's an entry for the account in the global cache check for the key or load it into that account.
@@ -504,12 +576,27 @@ impl State {
 		self.require_or_from(a, true, || Account::new_contract(0.into(), self.account_start_nonce), |_|{}).reset_code(code);
 	}
 
+	/// Replace the balance, code and storage of account `a` as given by `account`.
+	pub fn apply_override(&mut self, a: &Address, account: &AccountOverride) {
+		if let Some(ref balance) = account.balance {
+			let current = self.balance(a);
+			self.sub_balance(a, &current);
+			self.add_balance(a, balance, CleanupMode::ForceCreate);
+		}
+		if let Some(ref code) = account.code {
+			self.reset_code(a, code.clone());
+		}
+		for (key, value) in &account.storage {
+			self.set_storage(a, *key, *value);
+		}
+	}
+
 	/// Execute a given transaction.
 	/// This will change the state accordingly.
 	pub fn apply(&mut self, env_info: &EnvInfo, engine: &Engine, t: &SignedTransaction, tracing: bool) -> ApplyResult {
 //		let old = self.to_pod();
 
-		let options = TransactOptions { tracing: tracing, vm_tracing: false, check_nonce: true };
+		let options = TransactOptions { tracing: tracing, vm_tracing: false, check_nonce: true, defer_fees: false };
 		let vm_factory = self.factories.vm.clone();
 		let e = try!(Executive::new(self, env_info, engine, &vm_factory).transact(t, options));
 
@@ -584,8 +671,6 @@ impl State {
 		self.cache.borrow_mut().clear();
 	}
 
-	#[cfg(test)]
-	#[cfg(feature = "json-tests")]
 	/// Populate the state from `accounts`.
 	pub fn populate_from(&mut self, accounts: PodState) {
 		assert!(self.checkpoints.borrow().is_empty());
@@ -661,6 +746,8 @@ impl State {
 	/// Populates local cache if nothing found.
 	fn ensure_cached<F, U>(&self, a: &Address, require: RequireCache, check_bloom: bool, f: F) -> U
 		where F: Fn(Option<&Account>) -> U {
+		self.note_read(a, None);
+
 		// check local cache first
 		if let Some(ref mut maybe_acc) = self.cache.borrow_mut().get_mut(a) {
 			if let Some(ref mut account) = maybe_acc.account {
@@ -711,6 +798,8 @@ impl State {
 	fn require_or_from<'a, F: FnOnce() -> Account, G: FnOnce(&mut Account)>(&'a self, a: &Address, require_code: bool, default: F, not_default: G)
 		-> RefMut<'a, Account>
 	{
+		self.note_read(a, None);
+
 		let contains_key = self.cache.borrow().contains_key(a);
 		if !contains_key {
 			match self.db.get_cached_account(a) {
@@ -783,6 +872,7 @@ impl Clone for State {
 			checkpoints: RefCell::new(Vec::new()),
 			account_start_nonce: self.account_start_nonce.clone(),
 			factories: self.factories.clone(),
+			reads: RefCell::new(None),
 		}
 	}
 }
@@ -1680,6 +1770,32 @@ fn storage_at_from_database() {
 	assert_eq!(s.storage_at(&a, &H256::from(&U256::from(1u64))), H256::from(&U256::from(69u64)));
 }
 
+#[test]
+fn prove_account_and_storage() {
+	let a = Address::from(1);
+	let key = H256::from(&U256::from(1u64));
+	let temp = RandomTempPath::new();
+	let mut state = get_temp_state_in(temp.as_path());
+	state.require_or_from(&a, false, ||Account::new_contract(42.into(), 0.into()), |_|{});
+	state.init_code(&a, vec![1, 2, 3]);
+	state.set_storage(&a, key.clone(), H256::from(&U256::from(69u64)));
+	state.commit().unwrap();
+
+	let proof = state.prove(&a.sha3(), Some(&key.sha3()), 0).unwrap();
+	let mut db = MemoryDB::new();
+	for node in &proof {
+		db.insert(node);
+	}
+
+	let account = TrieDB::new(&db, state.root()).unwrap().get(&a.sha3()).unwrap().unwrap();
+	let storage_root: H256 = Rlp::new(&account).val_at(2);
+	let value = TrieDB::new(&db, &storage_root).unwrap().get(&key.sha3()).unwrap().unwrap();
+	assert_eq!(::rlp::decode::<U256>(&value), U256::from(69u64));
+
+	assert_eq!(state.code_by_address_hash(&a.sha3()).unwrap(), Some(vec![1, 2, 3]));
+	assert_eq!(state.code_by_address_hash(&Address::from(2).sha3()).unwrap(), None);
+}
+
 #[test]
 fn get_from_database() {
 	let a = Address::zero();
@@ -1798,6 +1914,27 @@ fn alter_balance() {
 	assert_eq!(state.balance(&b), U256::from(18u64));
 }
 
+#[test]
+fn apply_account_override() {
+	let mut state_result = get_temp_state();
+	let mut state = state_result.reference_mut();
+	let a = Address::zero();
+	state.add_balance(&a, &U256::from(69u64), CleanupMode::NoEmpty);
+	state.set_storage(&a, H256::from(1u64), H256::from(2u64));
+	state.commit().unwrap();
+
+	let mut account = AccountOverride::default();
+	account.balance = Some(U256::from(5u64));
+	account.code = Some(vec![0x60, 0x00]);
+	account.storage.insert(H256::from(3u64), H256::from(4u64));
+	state.apply_override(&a, &account);
+
+	assert_eq!(state.balance(&a), U256::from(5u64));
+	assert_eq!(state.code(&a), Some(Arc::new(vec![0x60, 0x00])));
+	assert_eq!(state.storage_at(&a, &H256::from(1u64)), H256::from(2u64));
+	assert_eq!(state.storage_at(&a, &H256::from(3u64)), H256::from(4u64));
+}
+
 #[test]
 fn alter_nonce() {
 	let mut state_result = get_temp_state();
@@ -0,0 +1,268 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Pipelined computation of intermediate state roots.
+//!
+//! Transactions are executed one by one without committing the state. After each of them
+//! the modified accounts are handed over to a background thread, which commits them into its
+//! own copy of the database and records the state root for the receipt, while the next
+//! transaction is being executed. At the end of the block the background thread's database
+//! and committed accounts are taken over by the state.
+
+use std::sync::mpsc::{channel, Sender, Receiver};
+use crossbeam::scope;
+use util::*;
+use engines::Engine;
+use env_info::EnvInfo;
+use error::Error;
+use executive::{Executive, Executed, TransactOptions};
+use factory::Factories;
+use receipt::Receipt;
+use state_db::StateDB;
+use transaction::SignedTransaction;
+use super::{State, ApplyOutcome, AccountEntry, AccountState};
+
+/// Background committer of the accounts modified by each transaction.
+struct Committer {
+	db: StateDB,
+	root: H256,
+	factories: Factories,
+	accounts: HashMap<Address, AccountEntry>,
+	// storage root of each account in the executing state, which its storage changes apply to.
+	base_roots: HashMap<Address, H256>,
+	roots: Vec<H256>,
+}
+
+impl Committer {
+	fn new(db: StateDB, root: H256, factories: Factories) -> Self {
+		Committer {
+			db: db,
+			root: root,
+			factories: factories,
+			accounts: HashMap::new(),
+			base_roots: HashMap::new(),
+			roots: Vec::new(),
+		}
+	}
+
+	/// Commit every batch of modified accounts, until the sender is dropped.
+	fn run(mut self, receiver: Receiver<Vec<(Address, AccountEntry)>>) -> Result<Self, Error> {
+		for accounts in receiver {
+			for (address, entry) in accounts {
+				self.note(address, entry);
+			}
+			try!(State::commit_into(&self.factories, &mut self.db, &mut self.root, &mut self.accounts));
+			self.roots.push(self.root.clone());
+		}
+		Ok(self)
+	}
+
+	/// Note a new version of the account. Storage changes are handed over until the end of the
+	/// block, so the ones already committed are kept unless the account has been recreated.
+	fn note(&mut self, address: Address, entry: AccountEntry) {
+		let base_root = entry.account.as_ref().map(|account| account.base_storage_root().clone());
+		let same_base = base_root.is_some() && self.base_roots.get(&address) == base_root.as_ref();
+		match base_root {
+			Some(root) => { self.base_roots.insert(address.clone(), root); },
+			None => { self.base_roots.remove(&address); },
+		}
+
+		if same_base {
+			if let Some(&mut AccountEntry { account: Some(ref mut ours), ref mut state }) = self.accounts.get_mut(&address) {
+				ours.overwrite_keeping_storage_root(entry.account.expect("same_base is only set for existing accounts; qed"));
+				*state = AccountState::Dirty;
+				return;
+			}
+		}
+		self.accounts.insert(address, entry);
+	}
+}
+
+impl State {
+	/// Execute given transactions in order. The outcome is the same as calling `apply`
+	/// for each of them with `env_info.gas_used` advanced after every transaction, but
+	/// the intermediate state roots are computed on a background thread.
+	///
+	/// If an error is returned, the state should be discarded.
+	pub fn apply_pipelined(&mut self, env_info: &EnvInfo, engine: &Engine, transactions: &[SignedTransaction], tracing: bool) -> Result<Vec<ApplyOutcome>, Error> {
+		try!(self.commit());
+		let committer = Committer::new(self.db.boxed_clone(), self.root.clone(), self.factories.clone());
+		let (sender, receiver) = channel();
+
+		let (executed, committer) = scope(|scope| {
+			let handle = scope.spawn(move || committer.run(receiver));
+			let executed = self.execute_pipelined(env_info, engine, transactions, tracing, sender);
+			(executed, handle.join())
+		});
+		// execution errors take precedence, as they make the committer stop early.
+		let executed = try!(executed);
+		let committer = try!(committer);
+
+		let Committer { db, root, accounts, roots, .. } = committer;
+		self.db.take_journal_db(db);
+		self.root = root;
+		self.cache.get_mut().extend(accounts);
+
+		Ok(executed.into_iter().zip(roots).map(|(e, root)| ApplyOutcome {
+			receipt: Receipt::new(root, e.cumulative_gas_used, e.logs),
+			trace: e.trace,
+		}).collect())
+	}
+
+	/// Execute transactions, handing over the accounts modified by each of them to `sender`.
+	fn execute_pipelined(&mut self, env_info: &EnvInfo, engine: &Engine, transactions: &[SignedTransaction], tracing: bool, sender: Sender<Vec<(Address, AccountEntry)>>) -> Result<Vec<Executed>, Error> {
+		let mut env_info = EnvInfo {
+			last_hashes: env_info.last_hashes.clone(),
+			..*env_info
+		};
+		let vm_factory = self.factories.vm.clone();
+
+		let mut executed = Vec::with_capacity(transactions.len());
+		for t in transactions {
+			let options = TransactOptions { tracing: tracing, vm_tracing: false, check_nonce: true, defer_fees: false };
+			let e = try!(Executive::new(self, &env_info, engine, &vm_factory).transact(t, options));
+			env_info.gas_used = e.cumulative_gas_used;
+			// fails only if the committer has stopped, its error is returned when joined.
+			let _ = sender.send(self.hand_over_dirty());
+			executed.push(e);
+		}
+		Ok(executed)
+	}
+
+	/// Clone the accounts modified since the last call and mark them as committed.
+	/// Their storage changes stay in place, as the database is not updated, but their
+	/// code is committed here so that it is handed over only once.
+	fn hand_over_dirty(&mut self) -> Vec<(Address, AccountEntry)> {
+		let factories = &self.factories;
+		let db = &mut self.db;
+		self.cache.get_mut().iter_mut()
+			.filter(|&(_, ref entry)| entry.is_dirty())
+			.map(|(address, entry)| {
+				let dirty = entry.clone_dirty();
+				if let Some(ref mut account) = entry.account {
+					let addr_hash = account.address_hash(address);
+					let mut account_db = factories.accountdb.create(db.as_hashdb_mut(), addr_hash);
+					account.commit_code(account_db.as_hashdb_mut());
+				}
+				entry.state = AccountState::Committed;
+				(address.clone(), dirty)
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_serialize::hex::FromHex;
+	use util::{U256, H256, Address, FixedHash};
+	use tests::helpers::*;
+	use transaction::Action;
+
+	#[test]
+	fn should_match_sequential_execution() {
+		let engine = TestEngine::new(5);
+		let mut state_result = get_temp_state();
+		let state = state_result.reference_mut();
+
+		// increments the value at 0 and stores 1 at the caller.
+		let counter = Address::from(5);
+		state.init_code(&counter, "60005460010160005560013355".from_hex().unwrap());
+
+		let transactions = vec![
+			secret_transaction("a", 0, 1, Action::Call(Address::from(1)), ""),
+			secret_transaction("b", 0, 1, Action::Call(counter.clone()), ""),
+			secret_transaction("a", 1, 1, Action::Call(Address::from(2)), ""),
+			// stores 1 at 0.
+			secret_transaction("b", 1, 1, Action::Create, "600160005560006000f3"),
+			secret_transaction("c", 0, 1, Action::Call(counter.clone()), ""),
+		];
+		fund_senders(state, &transactions);
+
+		let info = block_env_info(Address::from(0xa));
+		let (sequential, expected) = apply_sequentially(state, &info, &engine, &transactions);
+
+		let outcomes = state.apply_pipelined(&info, &engine, &transactions, false).unwrap();
+		assert_eq!(receipt_roots(outcomes), expected);
+		assert_eq!(state.root(), sequential.root());
+		assert_eq!(state.storage_at(&counter, &H256::zero()), H256::from(&U256::from(2u64)));
+		assert_eq!(state.storage_root(&counter), sequential.storage_root(&counter));
+	}
+
+	#[test]
+	fn should_drop_storage_of_recreated_account() {
+		let engine = TestEngine::new(5);
+		let mut state_result = get_temp_state();
+		let state = state_result.reference_mut();
+
+		// sends its balance to the caller and self-destructs.
+		let contract = Address::from(5);
+		state.init_code(&contract, "33ff".from_hex().unwrap());
+		state.set_storage(&contract, H256::from(1), H256::from(1));
+
+		let transactions = vec![
+			secret_transaction("a", 0, 1, Action::Call(contract.clone()), ""),
+			// recreates the account without code or storage.
+			secret_transaction("b", 0, 1, Action::Call(contract.clone()), ""),
+			secret_transaction("c", 0, 1, Action::Call(contract.clone()), ""),
+		];
+		fund_senders(state, &transactions);
+
+		let info = block_env_info(Address::from(0xa));
+		let (sequential, expected) = apply_sequentially(state, &info, &engine, &transactions);
+
+		let outcomes = state.apply_pipelined(&info, &engine, &transactions, false).unwrap();
+		assert_eq!(receipt_roots(outcomes), expected);
+		assert_eq!(state.root(), sequential.root());
+		assert_eq!(state.storage_at(&contract, &H256::from(1)), H256::zero());
+		assert_eq!(state.storage_root(&contract), sequential.storage_root(&contract));
+	}
+
+	#[test]
+	fn should_reapply_storage_deletions() {
+		let engine = TestEngine::new(5);
+		let mut state_result = get_temp_state();
+		let state = state_result.reference_mut();
+
+		// stores the second word of the call data at the key given by the first.
+		let contract = Address::from(5);
+		state.init_code(&contract, "60203560003555".from_hex().unwrap());
+		state.set_storage(&contract, H256::from(1), H256::from(5));
+		let store = |secret: &str, nonce: u64, key: u64, value: u64| {
+			secret_transaction(secret, nonce, 1, Action::Call(contract.clone()), &format!("{:064x}{:064x}", key, value))
+		};
+
+		let transactions = vec![
+			store("a", 0, 0, 1),
+			// deletes a key committed before the block.
+			store("b", 0, 1, 0),
+			// deletes a key set within the block.
+			store("a", 1, 0, 0),
+			store("c", 0, 2, 2),
+		];
+		fund_senders(state, &transactions);
+
+		let info = block_env_info(Address::from(0xa));
+		let (sequential, expected) = apply_sequentially(state, &info, &engine, &transactions);
+
+		let outcomes = state.apply_pipelined(&info, &engine, &transactions, false).unwrap();
+		assert_eq!(receipt_roots(outcomes), expected);
+		assert_eq!(state.root(), sequential.root());
+		assert_eq!(state.storage_at(&contract, &H256::zero()), H256::zero());
+		assert_eq!(state.storage_at(&contract, &H256::from(1)), H256::zero());
+		assert_eq!(state.storage_at(&contract, &H256::from(2)), H256::from(2));
+		assert_eq!(state.storage_root(&contract), sequential.storage_root(&contract));
+	}
+}
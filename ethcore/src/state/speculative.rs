@@ -0,0 +1,273 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Speculative parallel execution of transactions.
+//!
+//! Every transaction is first executed on its own copy of the state, recording the accounts
+//! it touches. The results are then applied in order; a transaction which touched an account
+//! modified by an earlier one is executed again on top of the current state instead.
+//! Conflicts are detected per account, which covers balances, nonces, code and storage.
+
+use std::cmp;
+use crossbeam::scope;
+use util::*;
+use engines::Engine;
+use env_info::EnvInfo;
+use error::Error;
+use executive::{Executive, Executed, TransactOptions};
+use receipt::Receipt;
+use transaction::SignedTransaction;
+use super::{State, ApplyOutcome, ApplyResult, CleanupMode};
+
+/// Transaction executed on a separate copy of the state.
+struct Speculation {
+	state: State,
+	executed: Executed,
+	touched: HashSet<Address>,
+}
+
+impl Speculation {
+	/// Execute transaction on `state` without paying the fee to the block author, so that
+	/// the author is not touched unless the transaction itself accesses it.
+	/// Returns `None` if the transaction is invalid.
+	fn run(mut state: State, env_info: &EnvInfo, engine: &Engine, t: &SignedTransaction, tracing: bool) -> Option<Self> {
+		state.record_reads();
+		let options = TransactOptions { tracing: tracing, vm_tracing: false, check_nonce: true, defer_fees: true };
+		let vm_factory = state.factories.vm.clone();
+		let executed = match Executive::new(&mut state, env_info, engine, &vm_factory).transact(t, options) {
+			Ok(executed) => executed,
+			Err(_) => return None,
+		};
+
+		let mut touched: HashSet<Address> = state.take_reads().into_iter().map(|(address, _)| address).collect();
+		touched.extend(state.dirty_accounts());
+		Some(Speculation {
+			state: state,
+			executed: executed,
+			touched: touched,
+		})
+	}
+}
+
+/// Execute transactions on copies of `state`, using up to `threads` threads.
+fn speculate(state: &State, env_info: &EnvInfo, engine: &Engine, transactions: &[SignedTransaction], tracing: bool, threads: usize) -> Vec<Option<Speculation>> {
+	let threads = cmp::max(1, cmp::min(threads, transactions.len()));
+	let mut jobs: Vec<Vec<_>> = (0..threads).map(|_| Vec::new()).collect();
+	for (index, t) in transactions.iter().enumerate() {
+		jobs[index % threads].push((index, state.clone(), t.clone()));
+	}
+
+	// block gas limit is checked when the results are applied.
+	let env_info = EnvInfo {
+		gas_used: U256::zero(),
+		last_hashes: env_info.last_hashes.clone(),
+		..*env_info
+	};
+	let env_info = &env_info;
+
+	let mut results: Vec<(usize, Option<Speculation>)> = scope(|scope| {
+		let handles: Vec<_> = jobs.into_iter()
+			.map(|job| scope.spawn(move || job.into_iter()
+				.map(|(index, state, t)| (index, Speculation::run(state, env_info, engine, &t, tracing)))
+				.collect::<Vec<_>>()
+			))
+			.collect();
+		handles.into_iter().flat_map(|handle| handle.join()).collect()
+	});
+
+	results.sort_by_key(|&(index, _)| index);
+	results.into_iter().map(|(_, speculation)| speculation).collect()
+}
+
+impl State {
+	/// Execute given transactions in order. The outcome is the same as calling `apply`
+	/// for each of them with `env_info.gas_used` advanced after every transaction, but
+	/// the transactions are first executed speculatively on up to `threads` threads.
+	pub fn apply_parallel(&mut self, env_info: &EnvInfo, engine: &Engine, transactions: &[SignedTransaction], tracing: bool, threads: usize) -> Result<Vec<ApplyOutcome>, Error> {
+		// speculations must not see uncommitted changes, as they are copied along with the state.
+		try!(self.commit());
+		let speculations = speculate(self, env_info, engine, transactions, tracing, threads);
+
+		let mut env_info = EnvInfo {
+			last_hashes: env_info.last_hashes.clone(),
+			..*env_info
+		};
+		// the author is credited by every transaction.
+		let mut modified = HashSet::new();
+		modified.insert(env_info.author.clone());
+
+		let mut outcomes = Vec::with_capacity(transactions.len());
+		let mut merged = 0;
+		for (t, speculation) in transactions.iter().zip(speculations) {
+			let independent = speculation.as_ref().map_or(false, |speculation| {
+				env_info.gas_used + t.gas <= env_info.gas_limit && speculation.touched.is_disjoint(&modified)
+			});
+
+			let outcome = match (independent, speculation) {
+				(true, Some(speculation)) => {
+					merged += 1;
+					modified.extend(speculation.state.dirty_accounts());
+					try!(self.apply_speculation(&env_info, engine, t, speculation))
+				},
+				_ => {
+					self.record_reads();
+					let outcome = self.apply(&env_info, engine, t, tracing);
+					modified.extend(self.take_reads().into_iter().map(|(address, _)| address));
+					try!(outcome)
+				},
+			};
+
+			env_info.gas_used = outcome.receipt.gas_used;
+			outcomes.push(outcome);
+		}
+
+		trace!(target: "state", "Applied {} of {} transactions from parallel execution", merged, transactions.len());
+		Ok(outcomes)
+	}
+
+	/// Accounts modified and not yet committed.
+	fn dirty_accounts(&self) -> Vec<Address> {
+		self.cache.borrow().iter()
+			.filter(|&(_, entry)| entry.is_dirty())
+			.map(|(address, _)| address.clone())
+			.collect()
+	}
+
+	/// Copy over changes of an independent speculation, pay the fee to the block author
+	/// and commit, the way the executive and `apply` would have.
+	fn apply_speculation(&mut self, env_info: &EnvInfo, engine: &Engine, t: &SignedTransaction, speculation: Speculation) -> ApplyResult {
+		let Speculation { state, executed, .. } = speculation;
+		for (address, entry) in state.cache.into_inner().into_iter().filter(|&(_, ref entry)| entry.is_dirty()) {
+			self.cache.get_mut().insert(address, entry);
+		}
+
+		let schedule = engine.schedule(env_info);
+		let fees = executed.gas_used * t.gas_price;
+		let mut garbage = HashSet::new();
+		{
+			let cleanup_mode = match (schedule.no_empty, schedule.kill_empty) {
+				(false, _) => CleanupMode::ForceCreate,
+				(true, false) => CleanupMode::NoEmpty,
+				(true, true) => CleanupMode::KillEmpty(&mut garbage),
+			};
+			self.add_balance(&env_info.author, &fees, cleanup_mode);
+		}
+		for address in &garbage {
+			if self.exists(address) && !self.exists_and_not_null(address) {
+				self.kill_account(address);
+			}
+		}
+
+		try!(self.commit());
+		let receipt = Receipt::new(self.root().clone(), env_info.gas_used + executed.gas_used, executed.logs);
+		Ok(ApplyOutcome {
+			receipt: receipt,
+			trace: executed.trace,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_serialize::hex::FromHex;
+	use util::{U256, H256, Address, FixedHash};
+	use ethereum;
+	use state::CleanupMode;
+	use tests::helpers::*;
+	use transaction::Action;
+
+	#[test]
+	fn should_match_sequential_execution() {
+		let engine = TestEngine::new(5);
+		let mut state_result = get_temp_state();
+		let state = state_result.reference_mut();
+
+		let transfer = |secret: &str, nonce: u64, to: u64| secret_transaction(secret, nonce, 1, Action::Call(Address::from(to)), "");
+		let transactions = vec![
+			transfer("a", 0, 1),
+			transfer("b", 0, 2),
+			// depends on the first transaction
+			transfer("a", 1, 3),
+			// pays to the recipient of the second one
+			transfer("c", 0, 2),
+			// invalid nonce
+			transfer("d", 5, 4),
+		];
+		fund_senders(state, &transactions);
+
+		let info = block_env_info(Address::from(0xa));
+		let (sequential, expected) = apply_sequentially(state, &info, &engine, &transactions[..4]);
+
+		assert!(state.clone().apply_parallel(&info, &engine, &transactions, false, 4).is_err());
+
+		let outcomes = state.apply_parallel(&info, &engine, &transactions[..4], false, 4).unwrap();
+		assert_eq!(receipt_roots(outcomes), expected);
+		assert_eq!(state.root(), sequential.root());
+		assert_eq!(state.balance(&info.author), U256::from(4 * 21_000));
+	}
+
+	#[test]
+	fn should_see_fees_paid_to_the_author() {
+		let engine = TestEngine::new(5);
+		let mut state_result = get_temp_state();
+		let state = state_result.reference_mut();
+
+		// stores the balance of the block author at 0.
+		let reader = Address::from(5);
+		state.init_code(&reader, "4131600055".from_hex().unwrap());
+
+		let transactions = vec![
+			secret_transaction("a", 0, 1, Action::Call(Address::from(1)), ""),
+			secret_transaction("b", 0, 1, Action::Call(Address::from(2)), ""),
+			secret_transaction("c", 0, 1, Action::Call(reader.clone()), ""),
+			secret_transaction("d", 0, 1, Action::Call(Address::from(3)), ""),
+		];
+		fund_senders(state, &transactions);
+
+		let info = block_env_info(Address::from(0xa));
+		let (sequential, expected) = apply_sequentially(state, &info, &engine, &transactions);
+
+		let outcomes = state.apply_parallel(&info, &engine, &transactions, false, 4).unwrap();
+		assert_eq!(receipt_roots(outcomes), expected);
+		assert_eq!(state.root(), sequential.root());
+		assert_eq!(state.storage_at(&reader, &H256::zero()), H256::from(&U256::from(2 * 21_000)));
+	}
+
+	#[test]
+	fn should_kill_empty_author_without_fees() {
+		let engine = ethereum::new_eip161_test().engine;
+		let mut state_result = get_temp_state();
+		let state = state_result.reference_mut();
+
+		let author = Address::from(0xa);
+		state.add_balance(&author, &U256::zero(), CleanupMode::ForceCreate);
+
+		let transactions = vec![
+			secret_transaction("a", 0, 0, Action::Call(Address::from(1)), ""),
+			secret_transaction("b", 0, 0, Action::Call(Address::from(2)), ""),
+		];
+		fund_senders(state, &transactions);
+		assert!(state.exists(&author));
+
+		let info = block_env_info(author.clone());
+		let (sequential, expected) = apply_sequentially(state, &info, &*engine, &transactions);
+
+		let outcomes = state.apply_parallel(&info, &*engine, &transactions, false, 2).unwrap();
+		assert_eq!(receipt_roots(outcomes), expected);
+		assert_eq!(state.root(), sequential.root());
+		assert!(!state.exists(&author));
+	}
+}
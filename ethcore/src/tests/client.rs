@@ -26,6 +26,8 @@ use devtools::*;
 use miner::Miner;
 use rlp::{Rlp, View};
 use spec::Spec;
+use account_provider::AccountProvider;
+use header::Header;
 use views::BlockView;
 use util::stats::Histogram;
 
@@ -47,6 +49,28 @@ fn imports_from_empty() {
 	client.flush_queue();
 }
 
+#[test]
+fn imports_into_in_memory_db() {
+	let spec = get_test_spec();
+	let db = Arc::new(::util::kvdb::in_memory(::db::NUM_COLUMNS.unwrap_or(0)));
+
+	let client = Client::with_db(
+		ClientConfig::default(),
+		&spec,
+		db,
+		Arc::new(Miner::with_spec(&spec)),
+		IoChannel::disconnected(),
+	).unwrap();
+
+	for block in get_good_dummy_block_seq(3) {
+		client.import_block(block).unwrap();
+	}
+	client.flush_queue();
+	client.import_verified_blocks();
+
+	assert_eq!(client.chain_info().best_block_number, 3);
+}
+
 #[test]
 fn should_return_registrar() {
 	let dir = RandomTempPath::new();
@@ -250,6 +274,47 @@ fn can_handle_long_fork() {
 	assert_eq!(2000, client.chain_info().best_block_number);
 }
 
+#[test]
+fn rejects_reorg_deeper_than_maximum() {
+	let dir = RandomTempPath::new();
+	let spec = get_test_spec();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let mut config = ClientConfig::default();
+	config.max_reorg_depth = Some(2);
+	let client = Client::new(config, &spec, dir.as_path(), Arc::new(Miner::with_spec(&spec)), IoChannel::disconnected(), &db_config).unwrap();
+
+	let blocks = get_good_dummy_block_seq(5);
+	for block in &blocks {
+		client.import_block(block.clone()).unwrap();
+	}
+	client.flush_queue();
+	while client.import_verified_blocks() > 0 {}
+	assert_eq!(client.chain_info().best_block_number, 6);
+
+	let fork_block = |parent: &[u8]| {
+		let parent = BlockView::new(parent).header();
+		let mut header = Header::new();
+		header.set_gas_limit(parent.gas_limit().clone());
+		header.set_difficulty(parent.difficulty().clone());
+		header.set_timestamp(parent.timestamp() + 5);
+		header.set_number(parent.number() + 1);
+		header.set_parent_hash(parent.hash());
+		header.set_state_root(parent.state_root().clone());
+		create_test_block(&header)
+	};
+	// forks off block #5, one block below the best one.
+	let shallow = fork_block(&blocks[4]);
+	// forks off block #2, four blocks below the best one.
+	let deep = fork_block(&blocks[1]);
+	client.import_block(shallow.clone()).unwrap();
+	client.import_block(deep.clone()).unwrap();
+	client.flush_queue();
+	client.import_verified_blocks();
+
+	assert!(client.block(BlockID::Hash(BlockView::new(&shallow).header_view().sha3())).is_some());
+	assert!(client.block(BlockID::Hash(BlockView::new(&deep).header_view().sha3())).is_none());
+}
+
 #[test]
 fn can_mine() {
 	let dummy_blocks = get_good_dummy_block_seq(2);
@@ -284,3 +349,268 @@ fn change_history_size() {
 	let client = Client::new(config, &test_spec, dir.as_path(), Arc::new(Miner::with_spec(&test_spec)), IoChannel::disconnected(), &db_config).unwrap();
 	assert_eq!(client.state().balance(&address), 100.into());
 }
+
+#[test]
+fn should_list_accounts_in_fat_db_pages() {
+	let dir = RandomTempPath::new();
+	let spec = get_test_spec();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let mut config = ClientConfig::default();
+	config.fat_db = true;
+
+	let client = Client::new(
+		config,
+		&spec,
+		dir.as_path(),
+		Arc::new(Miner::with_spec(&spec)),
+		IoChannel::disconnected(),
+		&db_config
+	).unwrap();
+
+	let all = client.list_accounts(BlockID::Latest, None, 10).unwrap();
+	assert_eq!(all.len(), 5);
+
+	let first = client.list_accounts(BlockID::Latest, None, 2).unwrap();
+	let rest = client.list_accounts(BlockID::Latest, Some(&first[1]), 10).unwrap();
+	assert_eq!(first.into_iter().chain(rest.into_iter()).collect::<Vec<_>>(), all);
+}
+
+#[test]
+fn should_not_list_accounts_without_fat_db() {
+	let client_result = generate_dummy_client(0);
+	let client = client_result.reference();
+
+	assert!(client.list_accounts(BlockID::Latest, None, 10).is_none());
+}
+
+#[test]
+fn should_call_with_state_overrides() {
+	use client::{AccountOverride, StateOverride};
+	use transaction::{Transaction, Action};
+
+	let client_result = generate_dummy_client(0);
+	let client = client_result.reference();
+	let contract = Address::from(0x1234u64);
+
+	// SLOAD(0), MSTORE(0), RETURN(0, 32)
+	let mut account = AccountOverride::default();
+	account.code = Some("60005460005260206000f3".from_hex().unwrap());
+	account.storage.insert(H256::zero(), H256::from(42u64));
+	let mut overrides = StateOverride::new();
+	overrides.insert(contract, account);
+
+	let t = Transaction {
+		nonce: 0.into(),
+		gas_price: 0.into(),
+		gas: 100_000.into(),
+		action: Action::Call(contract),
+		value: 0.into(),
+		data: vec![],
+	}.fake_sign(Address::default());
+
+	let executed = client.call_with_overrides(&t, BlockID::Latest, Default::default(), overrides).unwrap();
+	assert_eq!(executed.output, H256::from(42u64).to_vec());
+
+	let executed = client.call(&t, BlockID::Latest, Default::default()).unwrap();
+	assert!(executed.output.is_empty());
+}
+
+#[test]
+fn should_call_many_on_accumulated_state() {
+	use executive::contract_address;
+	use transaction::{Transaction, Action};
+
+	let client_result = generate_dummy_client(0);
+	let client = client_result.reference();
+	let sender = Address::from(0x1111u64);
+
+	let create = Transaction {
+		nonce: 0.into(),
+		gas_price: 0.into(),
+		gas: 100_000.into(),
+		action: Action::Create,
+		value: 0.into(),
+		data: vec![0x60, 0x00],
+	}.fake_sign(sender);
+
+	let results = client.call_many(vec![create.clone(), create], BlockID::Latest, Default::default()).unwrap();
+	assert_eq!(results.len(), 2);
+	// the second creation sees the nonce bumped by the first one.
+	assert_eq!(results[0].contracts_created, vec![contract_address(&sender, &0.into())]);
+	assert_eq!(results[1].contracts_created, vec![contract_address(&sender, &1.into())]);
+}
+
+struct RecordingObserver {
+	state_diffs: bool,
+	imported: Mutex<Vec<::client::ObservedBlock>>,
+	retracted: Mutex<Vec<::client::ObservedBlock>>,
+	ancient: Mutex<Vec<H256>>,
+}
+
+impl RecordingObserver {
+	fn new(state_diffs: bool) -> Self {
+		RecordingObserver {
+			state_diffs: state_diffs,
+			imported: Mutex::new(Vec::new()),
+			retracted: Mutex::new(Vec::new()),
+			ancient: Mutex::new(Vec::new()),
+		}
+	}
+}
+
+impl ::client::ChainObserver for RecordingObserver {
+	fn wants_state_diffs(&self) -> bool {
+		self.state_diffs
+	}
+
+	fn block_imported(&self, block: &::client::ObservedBlock, _route: &::blockchain::ImportRoute) {
+		self.imported.lock().push(block.clone());
+	}
+
+	fn block_retracted(&self, block: &::client::ObservedBlock) {
+		self.retracted.lock().push(block.clone());
+	}
+
+	fn ancient_block_imported(&self, block: &::client::ObservedBlock) {
+		self.ancient.lock().push(block.hash.clone());
+	}
+}
+
+// imports two blocks and a heavier sibling of the second one, returning the hashes of the first two.
+fn observe_reorg(observer: Arc<RecordingObserver>) -> Vec<H256> {
+	let dir = RandomTempPath::new();
+	let spec = get_test_spec();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let client = Client::new(ClientConfig::default(), &spec, dir.as_path(), Arc::new(Miner::with_spec(&spec)), IoChannel::disconnected(), &db_config).unwrap();
+	client.add_observer(observer.clone());
+
+	let blocks = get_good_dummy_block_seq(2);
+	for block in &blocks {
+		client.import_block(block.clone()).unwrap();
+	}
+	client.flush_queue();
+	while client.import_verified_blocks() > 0 {}
+	client.flush_observers();
+
+	let hashes = blocks.iter().map(|b| BlockView::new(b).header_view().sha3()).collect::<Vec<_>>();
+	assert_eq!(observer.imported.lock().iter().map(|b| b.hash.clone()).collect::<Vec<_>>(), hashes);
+	assert_eq!(observer.imported.lock()[0].block, blocks[0]);
+	assert!(observer.retracted.lock().is_empty());
+
+	// heavier sibling of the second block retracts the last two.
+	let parent = BlockView::new(&blocks[0]).header();
+	let mut header = Header::new();
+	header.set_gas_limit(parent.gas_limit().clone());
+	header.set_difficulty(U256::from(10) * *parent.difficulty());
+	header.set_timestamp(parent.timestamp() + 5);
+	header.set_number(parent.number() + 1);
+	header.set_parent_hash(parent.hash());
+	header.set_state_root(parent.state_root().clone());
+	client.import_block(create_test_block(&header)).unwrap();
+	client.flush_queue();
+	client.import_verified_blocks();
+	client.flush_observers();
+
+	assert_eq!(client.chain_info().best_block_hash, header.hash());
+	assert_eq!(observer.imported.lock().last().map(|b| b.hash.clone()), Some(header.hash()));
+	hashes
+}
+
+#[test]
+fn should_notify_observers_of_imported_and_retracted_blocks() {
+	let observer = Arc::new(RecordingObserver::new(false));
+	let hashes = observe_reorg(observer.clone());
+
+	assert!(observer.imported.lock()[0].state_diff.is_none());
+	let mut retracted = observer.retracted.lock().iter().map(|b| b.hash.clone()).collect::<Vec<_>>();
+	retracted.sort();
+	let mut expected = hashes[1..].to_vec();
+	expected.sort();
+	assert_eq!(retracted, expected);
+}
+
+#[test]
+fn should_give_observers_state_diffs_of_retracted_blocks() {
+	let observer = Arc::new(RecordingObserver::new(true));
+	observe_reorg(observer.clone());
+
+	let retracted = observer.retracted.lock();
+	assert!(!retracted.is_empty());
+	assert!(retracted.iter().all(|b| b.state_diff.is_some()));
+}
+
+#[test]
+fn should_notify_observers_of_ancient_blocks() {
+	let dir = RandomTempPath::new();
+	let spec = get_test_spec();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let client = Client::new(ClientConfig::default(), &spec, dir.as_path(), Arc::new(Miner::with_spec(&spec)), IoChannel::disconnected(), &db_config).unwrap();
+	let observer = Arc::new(RecordingObserver::new(false));
+	client.add_observer(observer.clone());
+
+	let block = get_good_dummy_block();
+	let hash = client.import_block_with_receipts(block, ::rlp::EMPTY_LIST_RLP.to_vec()).unwrap();
+	client.flush_observers();
+
+	assert_eq!(*observer.ancient.lock(), vec![hash]);
+	assert!(observer.imported.lock().is_empty());
+}
+
+#[test]
+fn should_give_observers_state_diffs() {
+	let dir = RandomTempPath::new();
+	let spec = Spec::new_null();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let client = Client::new(ClientConfig::default(), &spec, dir.as_path(), Arc::new(Miner::with_spec(&spec)), IoChannel::disconnected(), &db_config).unwrap();
+	let observer = Arc::new(RecordingObserver::new(true));
+	client.add_observer(observer.clone());
+	let address = Address::random();
+
+	let mut b = client.prepare_open_block(Address::default(), (3141562.into(), 31415620.into()), vec![]);
+	b.block_mut().fields_mut().state.add_balance(&address, &5.into(), CleanupMode::NoEmpty);
+	b.block_mut().fields_mut().state.commit().unwrap();
+	let b = b.close_and_lock().seal(&*spec.engine, vec![]).unwrap();
+	let hash = client.import_sealed_block(b).unwrap();
+	client.flush_observers();
+
+	let imported = observer.imported.lock();
+	assert_eq!(imported.len(), 1);
+	assert_eq!(imported[0].hash, hash);
+	assert!(imported[0].state_diff.as_ref().expect("state diffs requested; qed").get().contains_key(&address));
+}
+
+#[test]
+fn should_recompute_finalized_block_on_reorg() {
+	let spec = Spec::new_test_basic_authority();
+	let tap = AccountProvider::transient_provider();
+	let authority = tap.insert_account("".sha3(), "").unwrap();
+	tap.unlock_account_permanently(authority, "".into()).unwrap();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let seal_on = |client: &Client, author: Address| {
+		let b = client.prepare_open_block(author, (3141562.into(), 31415620.into()), vec![]).close_and_lock();
+		let seal = spec.engine.generate_seal(b.block(), Some(&tap)).unwrap();
+		let b = b.seal(&*spec.engine, seal).unwrap();
+		let bytes = b.rlp_bytes();
+		client.import_sealed_block(b).unwrap();
+		bytes
+	};
+
+	// the only validator authored a block, which is final at once.
+	let dir = RandomTempPath::new();
+	let client = Client::new(ClientConfig::default(), &spec, dir.as_path(), Arc::new(Miner::with_spec(&spec)), IoChannel::disconnected(), &db_config).unwrap();
+	seal_on(&client, authority);
+	assert_eq!(client.finalized_block(), Some((1, client.chain_info().best_block_hash)));
+
+	// a longer fork authored by somebody else retracts it.
+	let fork_dir = RandomTempPath::new();
+	let fork_client = Client::new(ClientConfig::default(), &spec, fork_dir.as_path(), Arc::new(Miner::with_spec(&spec)), IoChannel::disconnected(), &db_config).unwrap();
+	let fork = vec![seal_on(&fork_client, Address::from(9)), seal_on(&fork_client, Address::from(9))];
+	for block in fork {
+		client.import_block(block).unwrap();
+	}
+	client.flush_queue();
+	while client.import_verified_blocks() > 0 {}
+
+	assert_eq!(client.chain_info().best_block_hash, fork_client.chain_info().best_block_hash);
+	assert_eq!(client.finalized_block(), None);
+}
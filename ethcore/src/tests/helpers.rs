@@ -36,6 +36,7 @@ use header::Header;
 use transaction::{Action, SignedTransaction, Transaction};
 use rlp::{self, RlpStream, Stream};
 use views::BlockView;
+use rustc_serialize::hex::FromHex;
 
 #[cfg(feature = "json-tests")]
 pub enum ChainEra {
@@ -362,6 +363,53 @@ pub fn get_temp_state_in(path: &Path) -> State {
 	State::new(journal_db, U256::from(0), Default::default())
 }
 
+/// Transaction sending 100 wei with hex `data`, signed with the key derived from `secret`.
+pub fn secret_transaction(secret: &str, nonce: u64, gas_price: u64, action: Action, data: &str) -> SignedTransaction {
+	Transaction {
+		nonce: nonce.into(),
+		gas_price: gas_price.into(),
+		gas: 100_000.into(),
+		action: action,
+		value: 100.into(),
+		data: data.from_hex().unwrap(),
+	}.sign(&secret.sha3(), None)
+}
+
+/// Give the senders of `transactions` enough to pay for them and commit.
+pub fn fund_senders(state: &mut State, transactions: &[SignedTransaction]) {
+	for t in transactions {
+		state.add_balance(&t.sender().unwrap(), &U256::from(1_000_000), CleanupMode::NoEmpty);
+	}
+	state.commit().unwrap();
+}
+
+/// Environment of a block with room for ten `secret_transaction`s.
+pub fn block_env_info(author: Address) -> EnvInfo {
+	let mut info = EnvInfo::default();
+	info.gas_limit = 1_000_000.into();
+	info.author = author;
+	info
+}
+
+/// Apply `transactions` one by one to a copy of `state`, as a reference for other ways
+/// of executing them. Returns the copy and the state root and gas used of each receipt.
+pub fn apply_sequentially(state: &State, info: &EnvInfo, engine: &Engine, transactions: &[SignedTransaction]) -> (State, Vec<(H256, U256)>) {
+	let mut sequential = state.clone();
+	let mut info = EnvInfo { last_hashes: info.last_hashes.clone(), ..*info };
+	let mut receipts = Vec::new();
+	for t in transactions {
+		let receipt = sequential.apply(&info, engine, t, false).unwrap().receipt;
+		info.gas_used = receipt.gas_used;
+		receipts.push((receipt.state_root, receipt.gas_used));
+	}
+	(sequential, receipts)
+}
+
+/// State root and gas used of each receipt, to compare with `apply_sequentially`.
+pub fn receipt_roots(outcomes: Vec<ApplyOutcome>) -> Vec<(H256, U256)> {
+	outcomes.into_iter().map(|outcome| (outcome.receipt.state_root, outcome.receipt.gas_used)).collect()
+}
+
 pub fn get_good_dummy_block_seq(count: usize) -> Vec<Bytes> {
 	let test_spec = get_test_spec();
   	get_good_dummy_block_fork_seq(1, count, &test_spec.genesis_header().hash())
@@ -440,6 +488,8 @@ pub fn get_default_ethash_params() -> EthashParams{
 		difficulty_hardfork_transition: u64::max_value(),
 		difficulty_hardfork_bound_divisor: U256::from(0),
 		bomb_defuse_transition: u64::max_value(),
+		difficulty_bomb_delays: BTreeMap::new(),
+		exp_diff_period: 100000,
 		eip150_transition: u64::max_value(),
 		eip155_transition: u64::max_value(),
 		eip160_transition: u64::max_value(),
@@ -16,6 +16,7 @@
 
 //! Traces config.
 use bloomchain::Config as BloomConfig;
+use util::Address;
 
 /// Traces config.
 #[derive(Debug, PartialEq, Clone)]
@@ -29,6 +30,10 @@ pub struct Config {
 	pub pref_cache_size: usize,
 	/// Max cache-size.
 	pub max_cache_size: usize,
+	/// If not empty, only traces of transactions involving any of these addresses are stored.
+	pub addresses: Vec<Address>,
+	/// Number of most recent canonical blocks to keep traces for. `None` keeps all of them.
+	pub history: Option<u64>,
 }
 
 impl Default for Config {
@@ -41,6 +46,8 @@ impl Default for Config {
 			},
 			pref_cache_size: 15 * 1024 * 1024,
 			max_cache_size: 20 * 1024 * 1024,
+			addresses: Vec::new(),
+			history: None,
 		}
 	}
 }
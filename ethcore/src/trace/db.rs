@@ -20,13 +20,13 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use bloomchain::{Number, Config as BloomConfig};
 use bloomchain::group::{BloomGroupDatabase, BloomGroupChain, GroupPosition, BloomGroup};
-use util::{H256, H264, Database, DBTransaction, RwLock, HeapSizeOf};
+use util::{H256, H264, Address, KeyValueDB, DBTransaction, RwLock, HeapSizeOf};
 use header::BlockNumber;
 use trace::{LocalizedTrace, Config, Filter, Database as TraceDatabase, ImportRequest, DatabaseExtras};
 use db::{self, Key, Writable, Readable, CacheUpdatePolicy};
 use blooms;
 use super::flat::{FlatTrace, FlatBlockTraces, FlatTransactionTraces};
-use cache_manager::CacheManager;
+use cache_manager::{CacheManager, CacheItem};
 
 const TRACE_DB_VER: &'static [u8] = b"1.0";
 
@@ -99,6 +99,8 @@ enum CacheID {
 	Bloom(TraceGroupPosition),
 }
 
+impl CacheItem for CacheID {}
+
 /// Trace database.
 pub struct TraceDB<T> where T: DatabaseExtras {
 	// cache
@@ -106,11 +108,15 @@ pub struct TraceDB<T> where T: DatabaseExtras {
 	blooms: RwLock<HashMap<TraceGroupPosition, blooms::BloomGroup>>,
 	cache_manager: RwLock<CacheManager<CacheID>>,
 	// db
-	tracesdb: Arc<Database>,
+	tracesdb: Arc<KeyValueDB>,
 	// config,
 	bloom_config: BloomConfig,
 	// tracing enabled
 	enabled: bool,
+	// addresses to keep traces for, all if empty
+	addresses: Vec<Address>,
+	// number of recent blocks to keep traces for
+	history: Option<BlockNumber>,
 	// extras
 	extras: Arc<T>,
 }
@@ -126,8 +132,8 @@ impl<T> BloomGroupDatabase for TraceDB<T> where T: DatabaseExtras {
 
 impl<T> TraceDB<T> where T: DatabaseExtras {
 	/// Creates new instance of `TraceDB`.
-	pub fn new(config: Config, tracesdb: Arc<Database>, extras: Arc<T>) -> Self {
-		let mut batch = DBTransaction::new(&tracesdb);
+	pub fn new(config: Config, tracesdb: Arc<KeyValueDB>, extras: Arc<T>) -> Self {
+		let mut batch = DBTransaction::new();
 		let genesis = extras.block_hash(0)
 			.expect("Genesis block is always inserted upon extras db creation qed");
 		batch.write(db::COL_TRACE, &genesis, &FlatBlockTraces::default());
@@ -141,11 +147,14 @@ impl<T> TraceDB<T> where T: DatabaseExtras {
 			tracesdb: tracesdb,
 			bloom_config: config.blooms,
 			enabled: config.enabled,
+			addresses: config.addresses,
+			history: config.history,
 			extras: extras,
 		}
 	}
 
-	fn cache_size(&self) -> usize {
+	/// Returns the memory used by the trace cache.
+	pub fn cache_size(&self) -> usize {
 		let traces = self.traces.read().heap_size_of_children();
 		let blooms = self.blooms.read().heap_size_of_children();
 		traces + blooms
@@ -165,7 +174,7 @@ impl<T> TraceDB<T> where T: DatabaseExtras {
 		let mut blooms = self.blooms.write();
 		let mut cache_manager = self.cache_manager.write();
 
-		cache_manager.collect_garbage(current_size, | ids | {
+		cache_manager.collect_garbage(&[current_size], | _, ids | {
 			for id in &ids {
 				match *id {
 					CacheID::Trace(ref h) => { traces.remove(h); },
@@ -180,12 +189,43 @@ impl<T> TraceDB<T> where T: DatabaseExtras {
 	}
 
 	/// Returns traces for block with hash.
-	fn traces(&self, block_hash: &H256) -> Option<FlatBlockTraces> {
+	pub fn traces(&self, block_hash: &H256) -> Option<FlatBlockTraces> {
 		let result = self.tracesdb.read_with_cache(db::COL_TRACE, &self.traces, block_hash);
 		self.note_used(CacheID::Trace(block_hash.clone()));
 		result
 	}
 
+	/// Drops traces of transactions which do not involve any of the traced addresses,
+	/// keeping transaction positions intact.
+	fn retain_traced_addresses(&self, traces: FlatBlockTraces) -> FlatBlockTraces {
+		let tx_traces: Vec<FlatTransactionTraces> = traces.into();
+		tx_traces.into_iter()
+			.map(|tx_traces| match tx_traces.involves(&self.addresses) {
+				true => tx_traces,
+				false => FlatTransactionTraces::from(Vec::new()),
+			})
+			.collect::<Vec<_>>()
+			.into()
+	}
+
+	/// Removes traces of canonical blocks which fell out of the history window
+	/// after importing `new_blocks` blocks ending with `block_number`.
+	fn prune(&self, batch: &mut DBTransaction, block_number: BlockNumber, new_blocks: usize) {
+		let history = match self.history {
+			Some(history) if block_number > history => history,
+			_ => return,
+		};
+
+		let last = block_number - history;
+		let first = last.saturating_sub(new_blocks as BlockNumber - 1);
+		let numbers: Vec<BlockNumber> = (first..last + 1).collect();
+		let mut traces = self.traces.write();
+		for hash in self.extras.block_hashes(&numbers).into_iter().filter_map(|hash| hash) {
+			batch.delete(db::COL_TRACE, &<H256 as Key<FlatBlockTraces>>::key(&hash));
+			traces.remove(&hash);
+		}
+	}
+
 	/// Returns vector of transaction traces for given block.
 	fn transactions_traces(&self, block_hash: &H256) -> Option<Vec<FlatTransactionTraces>> {
 		self.traces(block_hash).map(Into::into)
@@ -246,7 +286,7 @@ impl<T> TraceDatabase for TraceDB<T> where T: DatabaseExtras {
 
 	/// Traces of import request's enacted blocks are expected to be already in database
 	/// or to be the currently inserted trace.
-	fn import(&self, batch: &mut DBTransaction, request: ImportRequest) {
+	fn import(&self, batch: &mut DBTransaction, mut request: ImportRequest) {
 		// valid (canon):  retracted 0, enacted 1 => false, true,
 		// valid (branch): retracted 0, enacted 0 => false, false,
 		// valid (bbcc):   retracted 1, enacted 1 => true, true,
@@ -259,6 +299,10 @@ impl<T> TraceDatabase for TraceDB<T> where T: DatabaseExtras {
 			return;
 		}
 
+		if !self.addresses.is_empty() {
+			request.traces = self.retain_traced_addresses(request.traces);
+		}
+
 		// now let's rebuild the blooms
 		if !request.enacted.is_empty() {
 			let range_start = request.block_number as Number + 1 - request.enacted.len();
@@ -272,7 +316,15 @@ impl<T> TraceDatabase for TraceDB<T> where T: DatabaseExtras {
 				.map(|block_hash| if block_hash == &request.block_hash {
 					request.traces.bloom()
 				} else {
-					self.traces(block_hash).expect("Traces database is incomplete.").bloom()
+					match self.traces(block_hash) {
+						Some(traces) => traces.bloom(),
+						// traces of blocks beyond the history window may have been pruned.
+						None if self.history.is_some() => {
+							warn!(target: "trace", "Traces of re-enacted block {} were pruned, it won't match trace filters", block_hash);
+							FlatBlockTraces::default().bloom()
+						},
+						None => panic!("Traces database is incomplete."),
+					}
 				})
 				.map(blooms::Bloom::from)
 				.map(Into::into)
@@ -302,6 +354,11 @@ impl<T> TraceDatabase for TraceDB<T> where T: DatabaseExtras {
 			// note_used must be called after locking traces to avoid cache/traces deadlock on garbage collection
 			self.note_used(CacheID::Trace(request.block_hash.clone()));
 		}
+
+		// traces of side branches are never pruned
+		if request.enacted.len() > request.retracted {
+			self.prune(batch, request.block_number, request.enacted.len() - request.retracted);
+		}
 	}
 
 	fn trace(&self, block_number: BlockNumber, tx_position: usize, trace_position: Vec<usize>) -> Option<LocalizedTrace> {
@@ -386,15 +443,18 @@ impl<T> TraceDatabase for TraceDB<T> where T: DatabaseExtras {
 
 	fn filter(&self, filter: &Filter) -> Vec<LocalizedTrace> {
 		let chain = BloomGroupChain::new(self.bloom_config, self);
-		let numbers = chain.filter(filter);
+		let numbers: Vec<BlockNumber> = chain.filter(filter).into_iter().map(|n| n as BlockNumber).collect();
+		let hashes = self.extras.block_hashes(&numbers);
 		numbers.into_iter()
-			.flat_map(|n| {
-				let number = n as BlockNumber;
-				let hash = self.extras.block_hash(number)
-					.expect("Expected to find block hash. Extras db is probably corrupted");
-				let traces = self.traces(&hash)
-					.expect("Expected to find a trace. Db is probably corrupted.");
-				self.matching_block_traces(filter, traces, hash, number)
+			.zip(hashes)
+			.flat_map(|(number, hash)| {
+				let hash = hash.expect("Expected to find block hash. Extras db is probably corrupted");
+				match self.traces(&hash) {
+					Some(traces) => self.matching_block_traces(filter, traces, hash, number),
+					// blooms are kept for pruned blocks
+					None if self.history.is_some() => Vec::new(),
+					None => panic!("Expected to find a trace. Db is probably corrupted."),
+				}
 			})
 			.collect()
 	}
@@ -455,7 +515,7 @@ mod tests {
 		}
 	}
 
-	fn new_db(path: &str) -> Arc<Database> {
+	fn new_db(path: &str) -> Arc<KeyValueDB> {
 		Arc::new(Database::open(&DatabaseConfig::with_columns(::db::NUM_COLUMNS), path).unwrap())
 	}
 
@@ -574,7 +634,7 @@ mod tests {
 
 		// import block 0
 		let request = create_noncanon_import_request(0, block_0.clone());
-		let mut batch = DBTransaction::new(&db);
+		let mut batch = DBTransaction::new();
 		tracedb.import(&mut batch, request);
 		db.write(batch).unwrap();
 
@@ -605,7 +665,7 @@ mod tests {
 
 		// import block 1
 		let request = create_simple_import_request(1, block_1.clone());
-		let mut batch = DBTransaction::new(&db);
+		let mut batch = DBTransaction::new();
 		tracedb.import(&mut batch, request);
 		db.write(batch).unwrap();
 
@@ -621,7 +681,7 @@ mod tests {
 
 		// import block 2
 		let request = create_simple_import_request(2, block_2.clone());
-		let mut batch = DBTransaction::new(&db);
+		let mut batch = DBTransaction::new();
 		tracedb.import(&mut batch, request);
 		db.write(batch).unwrap();
 
@@ -662,6 +722,124 @@ mod tests {
 		assert_eq!(tracedb.trace(2, 0, vec![]).unwrap(), create_simple_localized_trace(2, block_2.clone(), tx_2.clone()));
 	}
 
+	#[test]
+	fn test_prune_old_traces() {
+		let temp = RandomTempPath::new();
+		let db = new_db(temp.as_str());
+		let mut config = Config::default();
+		config.enabled = true;
+		config.history = Some(1);
+		let block_1 = H256::from(0xa1);
+		let block_2 = H256::from(0xa2);
+		let tx_2 = H256::from(0xaf);
+
+		let mut extras = Extras::default();
+		extras.block_hashes.insert(0, H256::default());
+		extras.block_hashes.insert(1, block_1.clone());
+		extras.block_hashes.insert(2, block_2.clone());
+		extras.transaction_hashes.insert(2, vec![tx_2.clone()]);
+
+		let tracedb = TraceDB::new(config, db.clone(), Arc::new(extras));
+
+		for (number, hash) in vec![(1, block_1.clone()), (2, block_2.clone())] {
+			let mut batch = DBTransaction::new();
+			tracedb.import(&mut batch, create_simple_import_request(number, hash));
+			db.write(batch).unwrap();
+		}
+
+		assert_eq!(tracedb.block_traces(0), None);
+		assert_eq!(tracedb.block_traces(1), None);
+
+		let filter = Filter {
+			range: (1..2),
+			from_address: AddressesFilter::from(vec![Address::from(1)]),
+			to_address: AddressesFilter::from(vec![]),
+		};
+		assert_eq!(tracedb.filter(&filter), vec![create_simple_localized_trace(2, block_2.clone(), tx_2.clone())]);
+	}
+
+	#[test]
+	fn test_reorg_past_pruned_traces() {
+		let temp = RandomTempPath::new();
+		let db = new_db(temp.as_str());
+		let mut config = Config::default();
+		config.enabled = true;
+		config.history = Some(1);
+		let block_1 = H256::from(0xa1);
+		let block_2 = H256::from(0xa2);
+		let block_2b = H256::from(0xb2);
+
+		let mut extras = Extras::default();
+		extras.block_hashes.insert(0, H256::default());
+		extras.block_hashes.insert(1, block_1.clone());
+		extras.block_hashes.insert(2, block_2.clone());
+
+		let tracedb = TraceDB::new(config, db.clone(), Arc::new(extras));
+
+		for (number, hash) in vec![(1, block_1.clone()), (2, block_2.clone())] {
+			let mut batch = DBTransaction::new();
+			tracedb.import(&mut batch, create_simple_import_request(number, hash));
+			db.write(batch).unwrap();
+		}
+		assert!(tracedb.traces(&block_1).is_none());
+
+		// a reorg re-enacting the pruned block.
+		let mut request = create_simple_import_request(2, block_2b.clone());
+		request.enacted = vec![block_1, block_2b.clone()];
+		request.retracted = 2;
+		let mut batch = DBTransaction::new();
+		tracedb.import(&mut batch, request);
+		db.write(batch).unwrap();
+
+		assert!(tracedb.traces(&block_2b).is_some());
+	}
+
+	#[test]
+	fn test_trace_only_selected_addresses() {
+		let temp = RandomTempPath::new();
+		let db = new_db(temp.as_str());
+		let mut config = Config::default();
+		config.enabled = true;
+		config.addresses = vec![Address::from(2)];
+		let block_1 = H256::from(0xa1);
+		let block_2 = H256::from(0xa2);
+
+		let mut extras = Extras::default();
+		extras.block_hashes.insert(0, H256::default());
+		extras.block_hashes.insert(1, block_1.clone());
+		extras.block_hashes.insert(2, block_2.clone());
+
+		let tracedb = TraceDB::new(config, db.clone(), Arc::new(extras));
+
+		// involves address 2
+		let mut batch = DBTransaction::new();
+		let request = create_simple_import_request(1, block_1.clone());
+		let expected = request.traces.clone();
+		tracedb.import(&mut batch, request);
+		db.write(batch).unwrap();
+		assert_eq!(tracedb.traces(&block_1), Some(expected));
+
+		// does not involve address 2
+		let mut batch = DBTransaction::new();
+		let mut request = create_simple_import_request(2, block_2.clone());
+		request.traces = FlatBlockTraces::from(vec![FlatTransactionTraces::from(vec![FlatTrace {
+			trace_address: Default::default(),
+			subtraces: 0,
+			action: Action::Call(Call {
+				from: 3.into(),
+				to: 4.into(),
+				value: 3.into(),
+				gas: 4.into(),
+				input: vec![],
+				call_type: CallType::Call,
+			}),
+			result: Res::FailedCall(TraceError::OutOfGas),
+		}])]);
+		tracedb.import(&mut batch, request);
+		db.write(batch).unwrap();
+		assert_eq!(tracedb.traces(&block_2), Some(FlatBlockTraces::from(vec![FlatTransactionTraces::from(vec![])])));
+	}
+
 	#[test]
 	fn query_trace_after_reopen() {
 		let temp = RandomTempPath::new();
@@ -684,7 +862,7 @@ mod tests {
 
 			// import block 1
 			let request = create_simple_import_request(1, block_0.clone());
-			let mut batch = DBTransaction::new(&db);
+			let mut batch = DBTransaction::new();
 			tracedb.import(&mut batch, request);
 			db.write(batch).unwrap();
 		}
@@ -112,6 +112,11 @@ pub trait DatabaseExtras {
 	/// Returns hash of given block number.
 	fn block_hash(&self, block_number: BlockNumber) -> Option<H256>;
 
+	/// Returns hashes of given block numbers.
+	fn block_hashes(&self, block_numbers: &[BlockNumber]) -> Vec<Option<H256>> {
+		block_numbers.iter().map(|n| self.block_hash(*n)).collect()
+	}
+
 	/// Returns hash of transaction at given position.
 	fn transaction_hash(&self, block_number: BlockNumber, tx_position: usize) -> Option<H256>;
 }
@@ -0,0 +1,44 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Block rewards description.
+
+use util::{Address, U256};
+
+/// Reward bestowed on a single account.
+#[derive(Debug, Default, PartialEq, Clone, Binary)]
+pub struct Reward {
+	/// Rewarded account.
+	pub address: Address,
+	/// Reward amount.
+	pub amount: U256,
+}
+
+/// Rewards bestowed by the consensus engine on closing a block.
+#[derive(Debug, Default, PartialEq, Clone, Binary)]
+pub struct BlockRewards {
+	/// Reward of the block author, including rewards for included uncles.
+	pub author: Reward,
+	/// Rewards of included uncles' authors.
+	pub uncles: Vec<Reward>,
+}
+
+impl BlockRewards {
+	/// Total amount issued with the block.
+	pub fn total(&self) -> U256 {
+		self.uncles.iter().fold(self.author.amount, |acc, reward| acc + reward.amount)
+	}
+}
@@ -0,0 +1,38 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Active chain specification summary.
+
+use std::collections::BTreeMap;
+use util::{Address, H256};
+use header::BlockNumber;
+
+/// Consensus-related parameters of the active chain specification.
+#[derive(Debug, Default, PartialEq, Clone, Binary)]
+pub struct ChainSpecInfo {
+	/// Name of the consensus engine.
+	pub engine: String,
+	/// Network ID.
+	pub network_id: usize,
+	/// Block numbers from which rule changes take effect, keyed by spec parameter name.
+	pub transitions: BTreeMap<String, BlockNumber>,
+	/// Accounts allowed to seal blocks if the engine uses a fixed validator set.
+	pub validators: Option<Vec<Address>>,
+	/// Additional engine-specific parameters.
+	pub params: BTreeMap<String, String>,
+	/// Trusted block hashes, by block number.
+	pub checkpoints: BTreeMap<BlockNumber, H256>,
+}
@@ -18,7 +18,7 @@
 
 use util::{Bytes, U256, Address, U512};
 use rlp::*;
-use trace::{VMTrace, FlatTrace};
+use trace::{VMTrace, FlatTrace, TraceError};
 use types::log_entry::LogEntry;
 use types::state_diff::StateDiff;
 use std::fmt;
@@ -92,6 +92,8 @@ pub struct Executed {
 	pub contracts_created: Vec<Address>,
 	/// Transaction output.
 	pub output: Bytes,
+	/// The exception which terminated execution, if any.
+	pub exception: Option<TraceError>,
 	/// The trace of this transaction.
 	pub trace: Vec<FlatTrace>,
 	/// The VM trace of this transaction.
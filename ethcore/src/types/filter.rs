@@ -108,11 +108,25 @@ impl Filter {
 			_ => true
 		})
 	}
+
+	/// Returns true if a block whose logs use only given addresses and topics
+	/// may contain a log entry matching the filter.
+	pub fn matches_dictionary(&self, addresses: &[Address], topics: &[H256]) -> bool {
+		let matches = !addresses.is_empty() && match self.address {
+			Some(ref filter) if !filter.is_empty() => filter.iter().any(|address| addresses.contains(address)),
+			_ => true
+		};
+
+		matches && self.topics.iter().all(|topic| match *topic {
+			Some(ref filter) if !filter.is_empty() => filter.iter().any(|topic| topics.contains(topic)),
+			_ => true
+		})
+	}
 }
 
 #[cfg(test)]
 mod tests {
-	use util::FixedHash;
+	use util::{Address, H256, FixedHash};
 	use filter::Filter;
 	use client::BlockID;
 	use log_entry::LogEntry;
@@ -248,4 +262,28 @@ mod tests {
 		assert_eq!(filter.matches(&entry1), false);
 		assert_eq!(filter.matches(&entry2), false);
 	}
+
+	#[test]
+	fn test_filter_matches_dictionary() {
+		let filter = Filter {
+			from_block: BlockID::Earliest,
+			to_block: BlockID::Latest,
+			address: Some(vec!["b372018f3be9e171df0581136b59d2faf73a7d5d".into()]),
+			topics: vec![
+				Some(vec!["ff74e91598aed6ae5d2fdcf8b24cd2c7be49a0808112a305069355b7160f23f9".into()]),
+				None,
+			],
+			limit: None,
+		};
+
+		let address: Address = "b372018f3be9e171df0581136b59d2faf73a7d5d".into();
+		let other_address: Address = "b372018f3be9e171df0581136b59d2faf73a7d5e".into();
+		let topic: H256 = "ff74e91598aed6ae5d2fdcf8b24cd2c7be49a0808112a305069355b7160f23f9".into();
+		let other_topic: H256 = "ff74e91598aed6ae5d2fdcf8b24cd2c7be49a0808112a305069355b7160f23fa".into();
+
+		assert!(filter.matches_dictionary(&[other_address, address], &[other_topic, topic]));
+		assert!(!filter.matches_dictionary(&[other_address], &[topic]));
+		assert!(!filter.matches_dictionary(&[address], &[other_topic]));
+		assert!(!filter.matches_dictionary(&[], &[]));
+	}
 }
@@ -33,6 +33,8 @@ pub enum BlockID {
 	Latest,
 	/// Pending block.
 	Pending,
+	/// Latest block finalized by the consensus engine.
+	Finalized,
 }
 
 /// Uniquely identifies transaction.
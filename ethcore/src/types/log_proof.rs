@@ -0,0 +1,230 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Self-contained proofs of log entries, for cross-chain relays.
+//!
+//! A proof holds the header of the block containing the log, the receipts trie
+//! nodes on the path to the receipt of the transaction which emitted it, and
+//! the position of the log. Relays only need to know that the header hash is
+//! part of the source chain to trust the proved log.
+//!
+//! The canonical encoding is the RLP list
+//! `[header, [node, ...], transaction_index, log_index]`.
+
+use std::fmt;
+use rlp::*;
+use util::{Bytes, H256, Mismatch};
+use util::memorydb::MemoryDB;
+use util::trie::{TrieDB, TrieDBMut, Trie, TrieMut, TrieError};
+use util::trie::recorder::BasicRecorder;
+use util::hashdb::HashDB;
+use header::Header;
+use log_entry::LogEntry;
+use receipt::Receipt;
+
+/// Log proof verification errors.
+#[derive(Debug)]
+pub enum Error {
+	/// The header or a receipt cannot be decoded.
+	Decoder(DecoderError),
+	/// The proof nodes don't lead to a receipt in the receipts trie.
+	MissingReceipt,
+	/// The proof nodes are incomplete or inconsistent with the receipts root.
+	Trie(TrieError),
+	/// The receipt doesn't have a log at the given index.
+	MissingLog(Mismatch<usize>),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Error::Decoder(ref e) => write!(f, "Invalid proof encoding: {}", e),
+			Error::MissingReceipt => write!(f, "Receipt is not part of the proof"),
+			Error::Trie(ref e) => write!(f, "Invalid receipt proof: {}", e),
+			Error::MissingLog(ref mis) => write!(f, "Log index out of bounds: {}", mis),
+		}
+	}
+}
+
+impl From<DecoderError> for Error {
+	fn from(err: DecoderError) -> Self {
+		Error::Decoder(err)
+	}
+}
+
+impl From<Box<TrieError>> for Error {
+	fn from(err: Box<TrieError>) -> Self {
+		Error::Trie(*err)
+	}
+}
+
+/// Proof of a log entry emitted in a block.
+#[derive(Debug, Default, Clone, PartialEq, Binary)]
+pub struct LogProof {
+	/// Encoded header of the block.
+	pub header: Bytes,
+	/// Receipts trie nodes on the path from the root to the receipt, root first.
+	pub receipt_proof: Vec<Bytes>,
+	/// Index of the transaction in the block.
+	pub transaction_index: usize,
+	/// Index of the log in the transaction receipt.
+	pub log_index: usize,
+}
+
+impl LogProof {
+	/// Prove the log at `log_index` of the receipt at `transaction_index`, given
+	/// all receipts of the block with the encoded `header`. Returns `None` if there
+	/// is no such log.
+	pub fn new(header: Bytes, receipts: &[Receipt], transaction_index: usize, log_index: usize) -> Option<Self> {
+		if receipts.get(transaction_index).map_or(true, |receipt| log_index >= receipt.logs.len()) {
+			return None;
+		}
+
+		let mut db = MemoryDB::new();
+		let mut root = H256::new();
+		{
+			let mut trie = TrieDBMut::new(&mut db, &mut root);
+			for (i, receipt) in receipts.iter().enumerate() {
+				trie.insert(&::rlp::encode(&i), &::rlp::encode(receipt))
+					.expect("trie is built in memory from scratch; qed");
+			}
+		}
+
+		let mut recorder = BasicRecorder::new();
+		{
+			let trie = TrieDB::new(&db, &root).expect("trie was just built in memory; qed");
+			trie.get_recorded(&::rlp::encode(&transaction_index), &mut recorder).expect("trie was just built in memory; qed");
+		}
+
+		Some(LogProof {
+			header: header,
+			receipt_proof: recorder.drain().into_iter().map(|record| record.data).collect(),
+			transaction_index: transaction_index,
+			log_index: log_index,
+		})
+	}
+
+	/// Check the receipt proof against the receipts root of the header.
+	/// Returns the hash of the block containing the log and the log itself.
+	pub fn verify(&self) -> Result<(H256, LogEntry), Error> {
+		let header: Header = try!(UntrustedRlp::new(&self.header).as_val());
+
+		let mut db = MemoryDB::new();
+		for node in &self.receipt_proof {
+			db.insert(node);
+		}
+
+		let receipt = {
+			let trie = try!(TrieDB::new(&db, header.receipts_root()));
+			match try!(trie.get(&::rlp::encode(&self.transaction_index))) {
+				Some(receipt) => try!(UntrustedRlp::new(&receipt).as_val::<Receipt>()),
+				None => return Err(Error::MissingReceipt),
+			}
+		};
+
+		let logs = receipt.logs.len();
+		match receipt.logs.into_iter().nth(self.log_index) {
+			Some(log) => Ok((header.hash(), log)),
+			None => Err(Error::MissingLog(Mismatch { expected: logs, found: self.log_index })),
+		}
+	}
+}
+
+impl Encodable for LogProof {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(4);
+		s.append_raw(&self.header, 1);
+		s.begin_list(self.receipt_proof.len());
+		for node in &self.receipt_proof {
+			s.append(node);
+		}
+		s.append(&self.transaction_index);
+		s.append(&self.log_index);
+	}
+}
+
+impl Decodable for LogProof {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		if d.item_count() != 4 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		Ok(LogProof {
+			header: try!(d.at(0)).as_raw().to_vec(),
+			receipt_proof: try!(d.val_at(1)),
+			transaction_index: try!(d.val_at(2)),
+			log_index: try!(d.val_at(3)),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::{H256, Address};
+	use util::triehash::ordered_trie_root;
+	use header::Header;
+	use log_entry::LogEntry;
+	use receipt::Receipt;
+	use super::{LogProof, Error};
+
+	fn receipts() -> Vec<Receipt> {
+		(0..20u64).map(|i| {
+			let logs = (0..(i % 3)).map(|l| LogEntry {
+				address: Address::from(i),
+				topics: vec![H256::from(l)],
+				data: vec![i as u8; 40],
+			}).collect();
+			Receipt::new(H256::from(i), (21000 * (i + 1)).into(), logs)
+		}).collect()
+	}
+
+	fn header(receipts: &[Receipt]) -> Header {
+		let mut header = Header::new();
+		header.set_number(10);
+		header.set_receipts_root(ordered_trie_root(receipts.iter().map(|r| ::rlp::encode(r).to_vec())));
+		header
+	}
+
+	#[test]
+	fn proves_log() {
+		let receipts = receipts();
+		let header = header(&receipts);
+
+		let proof = LogProof::new(::rlp::encode(&header).to_vec(), &receipts, 14, 1).unwrap();
+		let decoded: LogProof = ::rlp::decode(&::rlp::encode(&proof));
+		assert_eq!(decoded, proof);
+
+		let (hash, log) = decoded.verify().unwrap();
+		assert_eq!(hash, header.hash());
+		assert_eq!(log, receipts[14].logs[1]);
+
+		assert!(LogProof::new(::rlp::encode(&header).to_vec(), &receipts, 14, 2).is_none());
+		assert!(LogProof::new(::rlp::encode(&header).to_vec(), &receipts, 20, 0).is_none());
+	}
+
+	#[test]
+	fn rejects_proof_for_other_block() {
+		let receipts = receipts();
+		let mut proof = LogProof::new(::rlp::encode(&header(&receipts)).to_vec(), &receipts, 5, 0).unwrap();
+		proof.header = ::rlp::encode(&header(&receipts[..10])).to_vec();
+
+		match proof.verify() {
+			Err(Error::Trie(_)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+}
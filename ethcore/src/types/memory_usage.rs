@@ -0,0 +1,37 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Client memory usage.
+
+/// Estimated heap usage of the client's caches and queues, in bytes.
+#[derive(Debug, Default, PartialEq, Clone, Binary)]
+pub struct MemoryUsage {
+	/// Block verification queue.
+	pub block_queue: usize,
+	/// Blockchain caches (blocks, details, receipts, blooms).
+	pub blockchain_cache: usize,
+	/// State database and its account and code caches.
+	pub state_cache: usize,
+	/// Trace database cache.
+	pub trace_cache: usize,
+}
+
+impl MemoryUsage {
+	/// Total amount used by the client.
+	pub fn total(&self) -> usize {
+		self.block_queue + self.blockchain_cache + self.state_cache + self.trace_cache
+	}
+}
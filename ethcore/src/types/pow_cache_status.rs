@@ -0,0 +1,30 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Proof-of-work cache status.
+
+/// Status of the cache used to verify proof-of-work seals (the ethash light cache).
+#[derive(Debug, Default, PartialEq, Clone, Binary)]
+pub struct PowCacheStatus {
+	/// Epoch of the most recently used cache.
+	pub current_epoch: Option<u64>,
+	/// Epoch of the cache being generated, if any.
+	pub generating_epoch: Option<u64>,
+	/// Generation progress of that cache, in percent.
+	pub progress: u8,
+	/// Epochs which have a cache stored on disk.
+	pub cached_epochs: Vec<u64>,
+}
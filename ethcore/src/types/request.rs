@@ -0,0 +1,448 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Typed requests shared by the `eth` and `les` protocols, along with their RLP codecs.
+//!
+//! Both protocols encode requests identically; LES additionally prefixes each packet
+//! with a request ID, so a LES packet is `[req_id, request]`.
+//!
+//! Responses to header, body and receipt requests carry one encoded item per answered
+//! entry. Eth sends them as `[items...]` and LES as `[req_id, buffer, items...]`.
+
+// TODO: make IPC compatible.
+
+use util::{H256, U256, Bytes};
+use rlp::*;
+use header::Header;
+
+/// Identifies the first block of a header request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashOrNumber {
+	/// Block hash.
+	Hash(H256),
+	/// Block number.
+	Number(u64),
+}
+
+impl From<H256> for HashOrNumber {
+	fn from(hash: H256) -> Self {
+		HashOrNumber::Hash(hash)
+	}
+}
+
+impl From<u64> for HashOrNumber {
+	fn from(number: u64) -> Self {
+		HashOrNumber::Number(number)
+	}
+}
+
+impl Encodable for HashOrNumber {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		match *self {
+			HashOrNumber::Hash(ref hash) => s.append(hash),
+			HashOrNumber::Number(ref number) => s.append(number),
+		};
+	}
+}
+
+impl Decodable for HashOrNumber {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let rlp = decoder.as_rlp();
+		// a number never takes up 32 bytes.
+		if rlp.size() == 32 {
+			Ok(HashOrNumber::Hash(try!(rlp.as_val())))
+		} else {
+			Ok(HashOrNumber::Number(try!(rlp.as_val())))
+		}
+	}
+}
+
+/// A request for block headers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Headers {
+	/// Block information for the request being made.
+	pub block: HashOrNumber,
+	/// The maximum amount of headers which can be returned.
+	pub max: usize,
+	/// The amount of headers to skip between each response entry.
+	pub skip: usize,
+	/// Whether the headers should proceed in falling number from the initial block.
+	pub reverse: bool,
+}
+
+impl Encodable for Headers {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(4);
+		s.append(&self.block);
+		s.append(&self.max);
+		s.append(&self.skip);
+		s.append(&if self.reverse { 1u32 } else { 0u32 });
+	}
+}
+
+impl Decodable for Headers {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		Ok(Headers {
+			block: try!(d.val_at(0)),
+			max: try!(d.val_at(1)),
+			skip: try!(d.val_at(2)),
+			reverse: try!(d.val_at(3)),
+		})
+	}
+}
+
+/// A request for specific block bodies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bodies {
+	/// Hashes which bodies are being requested for.
+	pub block_hashes: Vec<H256>
+}
+
+impl Encodable for Bodies {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.append(&self.block_hashes);
+	}
+}
+
+impl Decodable for Bodies {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		Ok(Bodies {
+			block_hashes: try!(decoder.as_rlp().as_val()),
+		})
+	}
+}
+
+/// A request for transaction receipts.
+///
+/// This request is answered with a list of transaction receipts for each block
+/// requested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipts {
+	/// Block hashes to return receipts for.
+	pub block_hashes: Vec<H256>,
+}
+
+impl Encodable for Receipts {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.append(&self.block_hashes);
+	}
+}
+
+impl Decodable for Receipts {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		Ok(Receipts {
+			block_hashes: try!(decoder.as_rlp().as_val()),
+		})
+	}
+}
+
+/// A request for a state proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateProof {
+	/// Block hash to query state from.
+	pub block: H256,
+	/// Key of the state trie -- corresponds to account hash.
+	pub key1: H256,
+	/// Key in that account's storage trie; if empty, then the account RLP should be
+	/// returned.
+	pub key2: Option<H256>,
+	/// if greater than zero, trie nodes beyond this level may be omitted.
+	pub from_level: u32, // could even safely be u8; trie w/ 32-byte key can be at most 64-levels deep.
+}
+
+impl Encodable for StateProof {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(4);
+		s.append(&self.block);
+		s.append(&self.key1);
+		match self.key2 {
+			Some(ref key2) => s.append(key2),
+			None => s.append_empty_data(),
+		};
+		s.append(&self.from_level);
+	}
+}
+
+impl Decodable for StateProof {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		Ok(StateProof {
+			block: try!(d.val_at(0)),
+			key1: try!(d.val_at(1)),
+			key2: {
+				let key2 = try!(d.at(2));
+				if key2.is_empty() { None } else { Some(try!(key2.as_val())) }
+			},
+			from_level: try!(d.val_at(3)),
+		})
+	}
+}
+
+/// A request for state proofs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateProofs {
+	/// All the proof requests.
+	pub requests: Vec<StateProof>,
+}
+
+/// A request for contract code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractCodes {
+	/// Block hash and account key (== sha3(address)) pairs to fetch code for.
+	pub code_requests: Vec<(H256, H256)>,
+}
+
+/// A request for a header proof from the Canonical Hash Trie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderProof {
+	/// Number of the CHT.
+	pub cht_number: u64,
+	/// Block number requested.
+	pub block_number: u64,
+	/// If greater than zero, trie nodes beyond this level may be omitted.
+	pub from_level: u32,
+}
+
+impl Encodable for HeaderProof {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(3);
+		s.append(&self.cht_number);
+		s.append(&self.block_number);
+		s.append(&self.from_level);
+	}
+}
+
+impl Decodable for HeaderProof {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		Ok(HeaderProof {
+			cht_number: try!(d.val_at(0)),
+			block_number: try!(d.val_at(1)),
+			from_level: try!(d.val_at(2)),
+		})
+	}
+}
+
+/// A request for header proofs from the CHT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderProofs {
+	/// All the proof requests.
+	pub requests: Vec<HeaderProof>,
+}
+
+/// A response made of one encoded item per answered request entry, in request order.
+pub trait Response: Sized {
+	/// The encoded items.
+	fn items(&self) -> &[Bytes];
+
+	/// Build a response out of encoded items.
+	fn from_items(items: Vec<Bytes>) -> Self;
+
+	/// Encode as an eth packet, `[items...]`.
+	fn eth_encode(&self) -> RlpStream {
+		let items = self.items();
+		let mut stream = RlpStream::new_list(items.len());
+		for item in items {
+			stream.append_raw(item, 1);
+		}
+		stream
+	}
+
+	/// Decode an eth packet.
+	fn eth_decode(rlp: &UntrustedRlp) -> Self {
+		Self::from_items(rlp.iter().map(|item| item.as_raw().to_vec()).collect())
+	}
+
+	/// Encode as a LES packet, `[req_id, buffer, items...]`.
+	fn les_encode(&self, req_id: u64, buffer: &U256) -> Bytes {
+		let items = self.items();
+		let mut stream = RlpStream::new_list(items.len() + 2);
+		stream.append(&req_id).append(buffer);
+		for item in items {
+			stream.append_raw(item, 1);
+		}
+		stream.out()
+	}
+
+	/// Decode the items of a LES packet, past the request ID and buffer.
+	fn les_decode(rlp: &UntrustedRlp) -> Self {
+		Self::from_items(rlp.iter().skip(2).map(|item| item.as_raw().to_vec()).collect())
+	}
+}
+
+/// Response to a headers request.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BlockHeaders {
+	/// Encoded headers.
+	pub headers: Vec<Bytes>,
+}
+
+impl BlockHeaders {
+	/// Decode the headers.
+	pub fn decode(&self) -> Result<Vec<Header>, DecoderError> {
+		self.headers.iter().map(|header| UntrustedRlp::new(header).as_val()).collect()
+	}
+}
+
+impl Response for BlockHeaders {
+	fn items(&self) -> &[Bytes] { &self.headers }
+
+	fn from_items(items: Vec<Bytes>) -> Self { BlockHeaders { headers: items } }
+}
+
+/// Response to a bodies request.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BlockBodies {
+	/// Encoded block bodies.
+	pub bodies: Vec<Bytes>,
+}
+
+impl Response for BlockBodies {
+	fn items(&self) -> &[Bytes] { &self.bodies }
+
+	fn from_items(items: Vec<Bytes>) -> Self { BlockBodies { bodies: items } }
+}
+
+/// Response to a receipts request.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BlockReceipts {
+	/// Encoded receipt lists, one per block.
+	pub receipts: Vec<Bytes>,
+}
+
+impl Response for BlockReceipts {
+	fn items(&self) -> &[Bytes] { &self.receipts }
+
+	fn from_items(items: Vec<Bytes>) -> Self { BlockReceipts { receipts: items } }
+}
+
+/// Kinds of requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+	/// Requesting headers.
+	Headers,
+	/// Requesting block bodies.
+	Bodies,
+	/// Requesting transaction receipts.
+	Receipts,
+	/// Requesting proofs of state trie nodes.
+	StateProofs,
+	/// Requesting contract code by hash.
+	Codes,
+	/// Requesting header proofs (from the CHT).
+	HeaderProofs,
+}
+
+/// Encompasses all possible types of requests in a single structure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+	/// Requesting headers.
+	Headers(Headers),
+	/// Requesting block bodies.
+	Bodies(Bodies),
+	/// Requesting transaction receipts.
+	Receipts(Receipts),
+	/// Requesting state proofs.
+	StateProofs(StateProofs),
+	/// Requesting contract codes.
+	Codes(ContractCodes),
+	/// Requesting header proofs.
+	HeaderProofs(HeaderProofs),
+}
+
+impl Request {
+	/// Get the kind of request this is.
+	pub fn kind(&self) -> Kind {
+		match *self {
+			Request::Headers(_) => Kind::Headers,
+			Request::Bodies(_) => Kind::Bodies,
+			Request::Receipts(_) => Kind::Receipts,
+			Request::StateProofs(_) => Kind::StateProofs,
+			Request::Codes(_) => Kind::Codes,
+			Request::HeaderProofs(_) => Kind::HeaderProofs,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::{H256, U256};
+	use rlp::{self, UntrustedRlp, Stream};
+	use super::*;
+
+	#[test]
+	fn headers_by_number_golden() {
+		let req = Headers { block: HashOrNumber::Number(1), max: 2, skip: 0, reverse: false };
+		let encoded = rlp::encode(&req).to_vec();
+		assert_eq!(encoded, vec![0xc4, 0x01, 0x02, 0x80, 0x80]);
+		assert_eq!(rlp::decode::<Headers>(&encoded), req);
+	}
+
+	#[test]
+	fn headers_by_hash_golden() {
+		let req = Headers { block: HashOrNumber::Hash(H256::new()), max: 1, skip: 0, reverse: true };
+		let mut expected = vec![0xe4, 0xa0];
+		expected.extend_from_slice(&[0u8; 32]);
+		expected.extend_from_slice(&[0x01, 0x80, 0x01]);
+
+		let encoded = rlp::encode(&req).to_vec();
+		assert_eq!(encoded, expected);
+		assert_eq!(rlp::decode::<Headers>(&encoded), req);
+	}
+
+	#[test]
+	fn bodies_golden() {
+		let req = Bodies { block_hashes: vec![H256::new()] };
+		let mut expected = vec![0xe1, 0xa0];
+		expected.extend_from_slice(&[0u8; 32]);
+
+		let encoded = rlp::encode(&req).to_vec();
+		assert_eq!(encoded, expected);
+		assert_eq!(rlp::decode::<Bodies>(&encoded), req);
+		assert_eq!(rlp::decode::<Receipts>(&encoded).block_hashes, req.block_hashes);
+	}
+
+	#[test]
+	fn state_proof_roundtrip() {
+		let account = StateProof { block: H256::new(), key1: 5.into(), key2: None, from_level: 0 };
+		assert_eq!(rlp::decode::<StateProof>(&rlp::encode(&account)), account);
+
+		let storage = StateProof { key2: Some(7.into()), from_level: 2, ..account };
+		assert_eq!(rlp::decode::<StateProof>(&rlp::encode(&storage)), storage);
+	}
+
+	#[test]
+	fn headers_response_golden() {
+		let response = BlockHeaders { headers: vec![vec![0xc0], vec![0xc1, 0x01]] };
+
+		let eth = response.eth_encode().out();
+		assert_eq!(eth, vec![0xc3, 0xc0, 0xc1, 0x01]);
+		assert_eq!(BlockHeaders::eth_decode(&UntrustedRlp::new(&eth)), response);
+
+		let les = response.les_encode(5, &U256::from(16));
+		assert_eq!(les, vec![0xc5, 0x05, 0x10, 0xc0, 0xc1, 0x01]);
+		assert_eq!(BlockHeaders::les_decode(&UntrustedRlp::new(&les)), response);
+	}
+
+	#[test]
+	fn header_proof_golden() {
+		let req = HeaderProof { cht_number: 1, block_number: 4096, from_level: 0 };
+		let encoded = rlp::encode(&req).to_vec();
+		assert_eq!(encoded, vec![0xc5, 0x01, 0x82, 0x10, 0x00, 0x80]);
+		assert_eq!(rlp::decode::<HeaderProof>(&encoded), req);
+	}
+}
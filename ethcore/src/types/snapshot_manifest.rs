@@ -20,9 +20,14 @@ use util::hash::H256;
 use rlp::*;
 use util::Bytes;
 
+/// Format version of snapshots whose manifest has no version field.
+pub const LEGACY_SNAPSHOT_VERSION: u64 = 1;
+
 /// Manifest data.
 #[derive(Debug, Clone, PartialEq, Eq, Binary)]
 pub struct ManifestData {
+	/// Snapshot format version, determining the encoding of the chunks.
+	pub version: u64,
 	/// List of state chunk hashes.
 	pub state_hashes: Vec<H256>,
 	/// List of block chunk hashes.
@@ -37,18 +42,22 @@ pub struct ManifestData {
 
 impl ManifestData {
 	/// Encode the manifest data to rlp.
+	///
+	/// The version comes last, so that readers of the legacy format ignore it.
 	pub fn into_rlp(self) -> Bytes {
-		let mut stream = RlpStream::new_list(5);
+		let mut stream = RlpStream::new_list(6);
 		stream.append(&self.state_hashes);
 		stream.append(&self.block_hashes);
 		stream.append(&self.state_root);
 		stream.append(&self.block_number);
 		stream.append(&self.block_hash);
+		stream.append(&self.version);
 
 		stream.out()
 	}
 
 	/// Try to restore manifest data from raw bytes, interpreted as RLP.
+	/// Manifests without a version field are of the legacy format.
 	pub fn from_rlp(raw: &[u8]) -> Result<Self, DecoderError> {
 		let decoder = UntrustedRlp::new(raw);
 
@@ -57,8 +66,13 @@ impl ManifestData {
 		let state_root: H256 = try!(decoder.val_at(2));
 		let block_number: u64 = try!(decoder.val_at(3));
 		let block_hash: H256 = try!(decoder.val_at(4));
+		let version: u64 = match decoder.item_count() {
+			5 => LEGACY_SNAPSHOT_VERSION,
+			_ => try!(decoder.val_at(5)),
+		};
 
 		Ok(ManifestData {
+			version: version,
 			state_hashes: state_hashes,
 			block_hashes: block_hashes,
 			state_root: state_root,
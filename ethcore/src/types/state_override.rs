@@ -0,0 +1,34 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Account state replacements for non-persistent calls.
+
+use std::collections::BTreeMap;
+use util::{U256, H256, Address, Bytes};
+
+/// Values replacing an account's state for the duration of a call.
+#[derive(Debug, PartialEq, Clone, Default, Binary)]
+pub struct AccountOverride {
+	/// Replacement balance.
+	pub balance: Option<U256>,
+	/// Replacement code.
+	pub code: Option<Bytes>,
+	/// Storage slots to set; other slots keep their values.
+	pub storage: BTreeMap<H256, H256>,
+}
+
+/// Account overrides keyed by address.
+pub type StateOverride = BTreeMap<Address, AccountOverride>;
@@ -18,7 +18,7 @@
 
 use std::collections::VecDeque;
 use rlp::*;
-use util::HeapSizeOf;
+use util::{HeapSizeOf, Address};
 use basic_types::LogBloom;
 use super::trace::{Action, Res};
 
@@ -44,6 +44,19 @@ impl FlatTrace {
 	pub fn bloom(&self) -> LogBloom {
 		self.action.bloom() | self.result.bloom()
 	}
+
+	/// Returns true if any of given addresses is a party of the trace.
+	pub fn involves(&self, addresses: &[Address]) -> bool {
+		let (from, to) = match self.action {
+			Action::Call(ref call) => (call.from, Some(call.to)),
+			Action::Create(ref create) => (create.from, match self.result {
+				Res::Create(ref result) => Some(result.address),
+				_ => None,
+			}),
+			Action::Suicide(ref suicide) => (suicide.address, Some(suicide.refund_address)),
+		};
+		addresses.contains(&from) || to.map_or(false, |to| addresses.contains(&to))
+	}
 }
 
 impl HeapSizeOf for FlatTrace {
@@ -98,6 +111,11 @@ impl FlatTransactionTraces {
 	pub fn bloom(&self) -> LogBloom {
 		self.0.iter().fold(Default::default(), | bloom, trace | bloom | trace.bloom())
 	}
+
+	/// Returns true if any of given addresses is a party of any of the traces.
+	pub fn involves(&self, addresses: &[Address]) -> bool {
+		self.0.iter().any(|trace| trace.involves(addresses))
+	}
 }
 
 impl Encodable for FlatTransactionTraces {
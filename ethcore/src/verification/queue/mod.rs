@@ -53,6 +53,8 @@ pub struct Config {
 	/// Maximum heap memory to use.
 	/// When the limit is reached, is_full returns true.
 	pub max_mem_use: usize,
+	/// Settings for the number of verifiers and adaptation strategy.
+	pub verifier_settings: VerifierSettings,
 }
 
 impl Default for Config {
@@ -60,6 +62,25 @@ impl Default for Config {
 		Config {
 			max_queue_size: 30000,
 			max_mem_use: 50 * 1024 * 1024,
+			verifier_settings: VerifierSettings::default(),
+		}
+	}
+}
+
+/// Verifier pool settings.
+#[derive(Debug, PartialEq, Clone)]
+pub struct VerifierSettings {
+	/// Whether to scale the number of active verifiers according to the load.
+	pub scale_verifiers: bool,
+	/// Number of verifier threads in the pool.
+	pub num_verifiers: usize,
+}
+
+impl Default for VerifierSettings {
+	fn default() -> Self {
+		VerifierSettings {
+			scale_verifiers: true,
+			num_verifiers: min(::num_cpus::get(), MAX_VERIFIERS),
 		}
 	}
 }
@@ -99,6 +120,7 @@ impl VerifierHandle {
 pub struct Verifying<K: Kind> {
 	hash: H256,
 	output: Option<K::Verified>,
+	priority: bool,
 }
 
 impl<K: Kind> HeapSizeOf for Verifying<K> {
@@ -117,6 +139,99 @@ pub enum Status {
 	Unknown,
 }
 
+// items in insertion order, except for the prioritized ones which are kept
+// ahead of all others.
+struct PriorityQueue<T> {
+	items: VecDeque<T>,
+	// number of prioritized items at the front.
+	prioritized: usize,
+}
+
+impl<T> PriorityQueue<T> {
+	fn new() -> Self {
+		PriorityQueue {
+			items: VecDeque::new(),
+			prioritized: 0,
+		}
+	}
+
+	// add an item after all the others, or after all the prioritized ones.
+	fn push(&mut self, item: T, priority: bool) {
+		if priority {
+			self.items.insert(self.prioritized, item);
+			self.prioritized += 1;
+		} else {
+			self.items.push_back(item);
+		}
+	}
+
+	// take the first item along with whether it was prioritized.
+	fn pop_front(&mut self) -> Option<(T, bool)> {
+		self.items.pop_front().map(|item| {
+			let priority = self.prioritized > 0;
+			if priority {
+				self.prioritized -= 1;
+			}
+			(item, priority)
+		})
+	}
+
+	// move the items matching the predicate behind the prioritized ones, keeping their order.
+	fn promote<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+		let rest = self.items.split_off(self.prioritized);
+		let mut others = VecDeque::new();
+		for item in rest {
+			if f(&item) {
+				self.items.push_back(item);
+				self.prioritized += 1;
+			} else {
+				others.push_back(item);
+			}
+		}
+		self.items.append(&mut others);
+	}
+
+	// take up to `max` items from the front.
+	fn drain_front(&mut self, max: usize) -> Vec<T> {
+		let count = min(max, self.items.len());
+		self.prioritized -= min(self.prioritized, count);
+		self.items.drain(..count).collect()
+	}
+
+	// keep only the items matching the predicate, in order.
+	fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+		let mut prioritized = 0;
+		let mut kept = VecDeque::new();
+		for (i, item) in self.items.drain(..).enumerate() {
+			if f(&item) {
+				if i < self.prioritized {
+					prioritized += 1;
+				}
+				kept.push_back(item);
+			}
+		}
+		self.items = kept;
+		self.prioritized = prioritized;
+	}
+
+	fn len(&self) -> usize {
+		self.items.len()
+	}
+
+	fn is_empty(&self) -> bool {
+		self.items.is_empty()
+	}
+
+	fn clear(&mut self) {
+		self.items.clear();
+		self.prioritized = 0;
+	}
+
+	fn shrink_to_fit(&mut self) {
+		self.items.shrink_to_fit();
+	}
+}
+
 // the internal queue sizes.
 struct Sizes {
 	unverified: AtomicUsize,
@@ -125,7 +240,8 @@ struct Sizes {
 }
 
 /// A queue of items to be verified. Sits between network or other I/O and the `BlockChain`.
-/// Keeps them in the same order as inserted, minus invalid items.
+/// Keeps them in the same order as inserted, minus invalid items, except that items
+/// extending the best block are verified and returned ahead of all others.
 pub struct VerificationQueue<K: Kind> {
 	panic_handler: Arc<PanicHandler>,
 	engine: Arc<Engine>,
@@ -136,9 +252,13 @@ pub struct VerificationQueue<K: Kind> {
 	ready_signal: Arc<QueueSignal>,
 	empty: Arc<SCondvar>,
 	processing: RwLock<HashSet<H256>>,
+	best_block: RwLock<H256>,
+	// queued items descending from the best block.
+	prioritized: RwLock<HashSet<H256>>,
 	ticks_since_adjustment: AtomicUsize,
 	max_queue_size: usize,
 	max_mem_use: usize,
+	scale_verifiers: bool,
 }
 
 struct QueueSignal {
@@ -185,9 +305,9 @@ impl QueueSignal {
 
 struct Verification<K: Kind> {
 	// All locks must be captured in the order declared here.
-	unverified: Mutex<VecDeque<K::Unverified>>,
+	unverified: Mutex<PriorityQueue<K::Unverified>>,
 	verifying: Mutex<VecDeque<Verifying<K>>>,
-	verified: Mutex<VecDeque<K::Verified>>,
+	verified: Mutex<PriorityQueue<K::Verified>>,
 	bad: Mutex<HashSet<H256>>,
 	more_to_verify: SMutex<()>,
 	empty: SMutex<()>,
@@ -199,9 +319,9 @@ impl<K: Kind> VerificationQueue<K> {
 	/// Creates a new queue instance.
 	pub fn new(config: Config, engine: Arc<Engine>, message_channel: IoChannel<ClientIoMessage>, check_seal: bool) -> Self {
 		let verification = Arc::new(Verification {
-			unverified: Mutex::new(VecDeque::new()),
+			unverified: Mutex::new(PriorityQueue::new()),
 			verifying: Mutex::new(VecDeque::new()),
-			verified: Mutex::new(VecDeque::new()),
+			verified: Mutex::new(PriorityQueue::new()),
 			bad: Mutex::new(HashSet::new()),
 			more_to_verify: SMutex::new(()),
 			empty: SMutex::new(()),
@@ -222,8 +342,13 @@ impl<K: Kind> VerificationQueue<K> {
 		let empty = Arc::new(SCondvar::new());
 		let panic_handler = PanicHandler::new_in_arc();
 
-		let max_verifiers = min(::num_cpus::get(), MAX_VERIFIERS);
-		let default_amount = max(::num_cpus::get(), 3) - 2;
+		let max_verifiers = min(MAX_VERIFIERS, max(1, config.verifier_settings.num_verifiers));
+		let scale_verifiers = config.verifier_settings.scale_verifiers;
+		let default_amount = if scale_verifiers {
+			min(max_verifiers, max(::num_cpus::get(), 3) - 2)
+		} else {
+			max_verifiers
+		};
 		let mut verifiers = Vec::with_capacity(max_verifiers);
 
 		debug!(target: "verification", "Allocating {} verifiers, {} initially active", max_verifiers, default_amount);
@@ -269,10 +394,13 @@ impl<K: Kind> VerificationQueue<K> {
 			verifiers: Mutex::new((verifiers, default_amount)),
 			deleting: deleting,
 			processing: RwLock::new(HashSet::new()),
+			best_block: RwLock::new(H256::zero()),
+			prioritized: RwLock::new(HashSet::new()),
 			empty: empty,
 			ticks_since_adjustment: AtomicUsize::new(0),
 			max_queue_size: max(config.max_queue_size, MIN_QUEUE_LIMIT),
 			max_mem_use: max(config.max_mem_use, MIN_MEM_LIMIT),
+			scale_verifiers: scale_verifiers,
 		}
 	}
 
@@ -319,13 +447,13 @@ impl<K: Kind> VerificationQueue<K> {
 				let mut unverified = verification.unverified.lock();
 				let mut verifying = verification.verifying.lock();
 
-				let item = match unverified.pop_front() {
+				let (item, priority) = match unverified.pop_front() {
 					Some(item) => item,
 					None => continue,
 				};
 
 				verification.sizes.unverified.fetch_sub(item.heap_size_of_children(), AtomicOrdering::SeqCst);
-				verifying.push_back(Verifying { hash: item.hash(), output: None });
+				verifying.push_back(Verifying { hash: item.hash(), output: None, priority: priority });
 				item
 			};
 
@@ -333,26 +461,18 @@ impl<K: Kind> VerificationQueue<K> {
 			let is_ready = match K::verify(item, &*engine, verification.check_seal) {
 				Ok(verified) => {
 					let mut verifying = verification.verifying.lock();
-					let mut idx = None;
-					for (i, e) in verifying.iter_mut().enumerate() {
+					for e in verifying.iter_mut() {
 						if e.hash == hash {
-							idx = Some(i);
-
 							verification.sizes.verifying.fetch_add(verified.heap_size_of_children(), AtomicOrdering::SeqCst);
 							e.output = Some(verified);
 							break;
 						}
 					}
 
-					if idx == Some(0) {
-						// we're next!
-						let mut verified = verification.verified.lock();
-						let mut bad = verification.bad.lock();
-						VerificationQueue::drain_verifying(&mut verifying, &mut verified, &mut bad, &verification.sizes);
-						true
-					} else {
-						false
-					}
+					// we may be next, or overtake the others.
+					let mut verified = verification.verified.lock();
+					let mut bad = verification.bad.lock();
+					VerificationQueue::drain_verifying(&mut verifying, &mut verified, &mut bad, &verification.sizes)
 				},
 				Err(_) => {
 					let mut verifying = verification.verifying.lock();
@@ -362,12 +482,7 @@ impl<K: Kind> VerificationQueue<K> {
 					bad.insert(hash.clone());
 					verifying.retain(|e| e.hash != hash);
 
-					if verifying.front().map_or(false, |x| x.output.is_some()) {
-						VerificationQueue::drain_verifying(&mut verifying, &mut verified, &mut bad, &verification.sizes);
-						true
-					} else {
-						false
-					}
+					VerificationQueue::drain_verifying(&mut verifying, &mut verified, &mut bad, &verification.sizes)
 				}
 			};
 			if is_ready {
@@ -377,17 +492,38 @@ impl<K: Kind> VerificationQueue<K> {
 		}
 	}
 
+	// move verified items to the verified queue. Returns whether any were moved.
 	fn drain_verifying(
 		verifying: &mut VecDeque<Verifying<K>>,
-		verified: &mut VecDeque<K::Verified>,
+		verified: &mut PriorityQueue<K::Verified>,
 		bad: &mut HashSet<H256>,
 		sizes: &Sizes,
-	) {
+	) -> bool {
 		let mut removed_size = 0;
 		let mut inserted_size = 0;
+		let mut drained = Vec::new();
+
+		// prioritized items overtake the others once all prioritized items ahead of them are verified.
+		let mut i = 0;
+		while i < verifying.len() {
+			if !verifying[i].priority {
+				i += 1;
+				continue;
+			}
+			if verifying[i].output.is_none() {
+				break;
+			}
+			let output = verifying.remove(i).and_then(|x| x.output).expect("output checked to be present above; qed");
+			drained.push((output, true));
+		}
 
 		while let Some(output) = verifying.front_mut().and_then(|x| x.output.take()) {
-			assert!(verifying.pop_front().is_some());
+			let priority = verifying.pop_front().expect("front item has just been taken the output of; qed").priority;
+			drained.push((output, priority));
+		}
+
+		let any_drained = !drained.is_empty();
+		for (output, priority) in drained {
 			let size = output.heap_size_of_children();
 			removed_size += size;
 
@@ -395,12 +531,13 @@ impl<K: Kind> VerificationQueue<K> {
 				bad.insert(output.hash());
 			} else {
 				inserted_size += size;
-				verified.push_back(output);
+				verified.push(output, priority);
 			}
 		}
 
 		sizes.verifying.fetch_sub(removed_size, AtomicOrdering::SeqCst);
 		sizes.verified.fetch_add(inserted_size, AtomicOrdering::SeqCst);
+		any_drained
 	}
 
 	/// Clear the queue and stop verification activity.
@@ -418,6 +555,7 @@ impl<K: Kind> VerificationQueue<K> {
 		sizes.verified.store(0, AtomicOrdering::Release);
 
 		self.processing.write().clear();
+		self.prioritized.write().clear();
 	}
 
 	/// Wait for unverified queue to be empty
@@ -439,9 +577,32 @@ impl<K: Kind> VerificationQueue<K> {
 		Status::Unknown
 	}
 
+	/// Set the best block. Items on top of it, directly or through other such queued items,
+	/// are verified and returned ahead of all others, including the ones queued before.
+	pub fn set_best_block(&self, hash: H256) {
+		*self.best_block.write() = hash.clone();
+
+		let mut unverified = self.verification.unverified.lock();
+		let mut verified = self.verification.verified.lock();
+		let mut prioritized = self.prioritized.write();
+		{
+			let mut extends_best = |parent: H256, item: H256| {
+				if parent == hash || prioritized.contains(&parent) {
+					prioritized.insert(item);
+					true
+				} else {
+					false
+				}
+			};
+			verified.promote(|item| extends_best(item.parent_hash(), item.hash()));
+			unverified.promote(|item| extends_best(item.parent_hash(), item.hash()));
+		}
+	}
+
 	/// Add a block to the queue.
 	pub fn import(&self, input: K::Input) -> ImportResult {
 		let h = input.hash();
+		let parent_hash = input.parent_hash();
 		{
 			if self.processing.read().contains(&h) {
 				return Err(ImportError::AlreadyQueued.into());
@@ -462,8 +623,14 @@ impl<K: Kind> VerificationQueue<K> {
 			Ok(item) => {
 				self.verification.sizes.unverified.fetch_add(item.heap_size_of_children(), AtomicOrdering::SeqCst);
 
+				let priority = *self.best_block.read() == parent_hash || self.prioritized.read().contains(&parent_hash);
+				if priority {
+					trace!(target: "verification", "Prioritizing {} extending the best chain", h);
+					self.prioritized.write().insert(h.clone());
+				}
+
 				self.processing.write().insert(h.clone());
-				self.verification.unverified.lock().push_back(item);
+				self.verification.unverified.lock().push(item, priority);
 				self.more_to_verify.notify_all();
 				Ok(h)
 			},
@@ -480,30 +647,31 @@ impl<K: Kind> VerificationQueue<K> {
 		if hashes.is_empty() {
 			return;
 		}
-		let mut verified_lock = self.verification.verified.lock();
-		let mut verified = &mut *verified_lock;
+		let mut verified = self.verification.verified.lock();
 		let mut bad = self.verification.bad.lock();
 		let mut processing = self.processing.write();
+		let mut prioritized = self.prioritized.write();
 		bad.reserve(hashes.len());
 		for hash in hashes {
 			bad.insert(hash.clone());
 			processing.remove(hash);
+			prioritized.remove(hash);
 		}
 
-		let mut new_verified = VecDeque::new();
 		let mut removed_size = 0;
-		for output in verified.drain(..) {
+		verified.retain(|output| {
 			if bad.contains(&output.parent_hash()) {
 				removed_size += output.heap_size_of_children();
 				bad.insert(output.hash());
 				processing.remove(&output.hash());
+				prioritized.remove(&output.hash());
+				false
 			} else {
-				new_verified.push_back(output);
+				true
 			}
-		}
+		});
 
 		self.verification.sizes.verified.fetch_sub(removed_size, AtomicOrdering::SeqCst);
-		*verified = new_verified;
 	}
 
 	/// Mark given item as processed.
@@ -513,8 +681,10 @@ impl<K: Kind> VerificationQueue<K> {
 			return self.processing.read().is_empty();
 		}
 		let mut processing = self.processing.write();
+		let mut prioritized = self.prioritized.write();
 		for hash in hashes {
 			processing.remove(hash);
+			prioritized.remove(hash);
 		}
 		processing.is_empty()
 	}
@@ -522,8 +692,7 @@ impl<K: Kind> VerificationQueue<K> {
 	/// Removes up to `max` verified items from the queue
 	pub fn drain(&self, max: usize) -> Vec<K::Verified> {
 		let mut verified = self.verification.verified.lock();
-		let count = min(max, verified.len());
-		let result = verified.drain(..count).collect::<Vec<_>>();
+		let result = verified.drain_front(max);
 
 		let drained_size = result.iter().map(HeapSizeOf::heap_size_of_children).fold(0, |a, c| a + c);
 		self.verification.sizes.verified.fetch_sub(drained_size, AtomicOrdering::SeqCst);
@@ -597,6 +766,11 @@ impl<K: Kind> VerificationQueue<K> {
 		};
 
 		self.processing.write().shrink_to_fit();
+		self.prioritized.write().shrink_to_fit();
+
+		if !self.scale_verifiers {
+			return;
+		}
 
 		if self.ticks_since_adjustment.fetch_add(1, AtomicOrdering::SeqCst) + 1 >= READJUSTMENT_PERIOD {
 			self.ticks_since_adjustment.store(0, AtomicOrdering::SeqCst);
@@ -689,6 +863,7 @@ mod tests {
 	use spec::*;
 	use super::{BlockQueue, Config};
 	use super::kind::blocks::Unverified;
+	use header::Header;
 	use tests::helpers::*;
 	use error::*;
 	use views::*;
@@ -791,6 +966,37 @@ mod tests {
 		assert!(queue.verifiers.lock().1 == 1);
 	}
 
+	#[test]
+	fn fixed_verifier_pool() {
+		let spec = get_test_spec();
+		let engine = spec.engine;
+		let mut config = Config::default();
+		config.verifier_settings.scale_verifiers = false;
+		config.verifier_settings.num_verifiers = 2;
+		let queue = BlockQueue::new(config, engine, IoChannel::disconnected(), true);
+		assert_eq!(queue.verifiers.lock().0.len(), 2);
+		assert_eq!(queue.verifiers.lock().1, 2);
+
+		// nothing to verify, but the pool is not scaled down.
+		queue.collect_garbage();
+		assert_eq!(queue.verifiers.lock().1, 2);
+	}
+
+	#[test]
+	fn verifier_pool_is_capped() {
+		use super::MAX_VERIFIERS;
+
+		let spec = get_test_spec();
+		let engine = spec.engine;
+		let mut config = Config::default();
+		config.verifier_settings.num_verifiers = MAX_VERIFIERS * 4;
+		let queue = BlockQueue::new(config, engine, IoChannel::disconnected(), true);
+		assert_eq!(queue.verifiers.lock().0.len(), MAX_VERIFIERS);
+
+		queue.scale_verifiers(MAX_VERIFIERS * 4);
+		assert_eq!(queue.verifiers.lock().1, MAX_VERIFIERS);
+	}
+
 	#[test]
 	fn readjust_verifiers() {
 		let queue = get_test_queue();
@@ -828,4 +1034,51 @@ mod tests {
 		queue.collect_garbage();
 		assert_eq!(queue.verifiers.lock().1, 1);
 	}
+
+	#[test]
+	fn prioritizes_best_chain_extensions() {
+		let queue = get_test_queue();
+		{
+			let verifiers = queue.verifiers.lock();
+			for i in 0..verifiers.1 {
+				verifiers.0[i].sleep();
+			}
+		}
+
+		let spec = get_test_spec();
+		let genesis = spec.genesis_header();
+		let hash = |b: &Bytes| BlockView::new(b).header().hash();
+		let canonical = get_good_dummy_block_seq(3);
+		// a fork of the canonical chain at genesis, with different timestamps.
+		let side = (1..5).fold(Vec::new(), |mut blocks: Vec<Bytes>, number| {
+			let mut header = Header::new();
+			header.set_gas_limit(spec.engine.params().min_gas_limit);
+			header.set_difficulty(U256::from(number) * U256([0, 1, 0, 0]));
+			header.set_timestamp(number * 10 + 5);
+			header.set_number(number);
+			header.set_parent_hash(blocks.last().map_or_else(|| genesis.hash(), &hash));
+			header.set_state_root(genesis.state_root().clone());
+			blocks.push(create_test_block(&header));
+			blocks
+		});
+
+		// the fork is queued first, then the canonical chain before it becomes the best one.
+		for block in side.iter().chain(canonical[1..3].iter()) {
+			queue.import(Unverified::new(block.clone())).expect("Block good by definition; qed");
+		}
+		queue.set_best_block(hash(&canonical[0]));
+		queue.import(Unverified::new(canonical[3].clone())).expect("Block good by definition; qed");
+
+		{
+			let verifiers = queue.verifiers.lock();
+			for i in 0..verifiers.1 {
+				verifiers.0[i].wake_up();
+			}
+		}
+		queue.flush();
+
+		let drained = queue.drain(side.len() + 3).iter().map(|b| b.header.hash()).collect::<Vec<_>>();
+		let expected = canonical[1..].iter().chain(side.iter()).map(&hash).collect::<Vec<_>>();
+		assert_eq!(drained, expected);
+	}
 }
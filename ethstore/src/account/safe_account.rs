@@ -76,6 +76,11 @@ impl Into<json::KeyFile> for SafeAccount {
 
 impl Crypto {
 	pub fn create(secret: &Secret, password: &str, iterations: u32) -> Self {
+		Crypto::encrypt(&**secret, password, iterations)
+	}
+
+	/// Encrypt arbitrary data with given password.
+	pub fn encrypt(plain: &[u8], password: &str, iterations: u32) -> Self {
 		let salt: [u8; 32] = Random::random();
 		let iv: [u8; 16] = Random::random();
 
@@ -83,10 +88,10 @@ impl Crypto {
 		// DK = [ DK[0..15] DK[16..31] ] = [derived_left_bits, derived_right_bits]
 		let (derived_left_bits, derived_right_bits) = crypto::derive_key_iterations(password, &salt, iterations);
 
-		let mut ciphertext = [0u8; 32];
+		let mut ciphertext = vec![0u8; plain.len()];
 
 		// aes-128-ctr with initial vector of iv
-		crypto::aes::encrypt(&derived_left_bits, &iv, &**secret, &mut ciphertext);
+		crypto::aes::encrypt(&derived_left_bits, &iv, plain, &mut ciphertext);
 
 		// KECCAK(DK[16..31] ++ <ciphertext>), where DK[16..31] - derived_right_bits
 		let mac = crypto::derive_mac(&derived_right_bits, &ciphertext).keccak256();
@@ -95,7 +100,7 @@ impl Crypto {
 			cipher: Cipher::Aes128Ctr(Aes128Ctr {
 				iv: iv,
 			}),
-			ciphertext: ciphertext.to_vec(),
+			ciphertext: ciphertext,
 			kdf: Kdf::Pbkdf2(Pbkdf2 {
 				dklen: crypto::KEY_LENGTH as u32,
 				salt: salt,
@@ -111,6 +116,15 @@ impl Crypto {
 			return Err(Error::InvalidSecret);
 		}
 
+		let plain = try!(self.decrypt(password));
+		let mut secret = Secret::default();
+		let from = 32 - plain.len();
+		(&mut *secret)[from..].copy_from_slice(&plain);
+		Ok(secret)
+	}
+
+	/// Decrypt data with given password.
+	pub fn decrypt(&self, password: &str) -> Result<Vec<u8>, Error> {
 		let (derived_left_bits, derived_right_bits) = match self.kdf {
 			Kdf::Pbkdf2(ref params) => crypto::derive_key_iterations(password, &params.salt, params.c),
 			Kdf::Scrypt(ref params) => try!(crypto::derive_key_scrypt(password, &params.salt, params.n, params.p, params.r)),
@@ -122,16 +136,15 @@ impl Crypto {
 			return Err(Error::InvalidPassword);
 		}
 
-		let mut secret = Secret::default();
+		let mut plain = vec![0u8; self.ciphertext.len()];
 
 		match self.cipher {
 			Cipher::Aes128Ctr(ref params) => {
-				let from = 32 - self.ciphertext.len();
-				crypto::aes::decrypt(&derived_left_bits, &params.iv, &self.ciphertext, &mut (&mut *secret)[from..])
+				crypto::aes::decrypt(&derived_left_bits, &params.iv, &self.ciphertext, &mut plain)
 			},
 		}
 
-		Ok(secret)
+		Ok(plain)
 	}
 }
 
@@ -217,6 +230,14 @@ mod tests {
 		assert_eq!(keypair.secret(), &secret);
 	}
 
+	#[test]
+	fn crypto_encrypt_data() {
+		let plain = b"some data longer than a single secret, to be encrypted".to_vec();
+		let crypto = Crypto::encrypt(&plain, "this is sparta", 10240);
+		assert_eq!(crypto.decrypt("this is sparta").unwrap(), plain);
+		assert!(crypto.decrypt("this is sparta!").is_err());
+	}
+
 	#[test]
 	#[should_panic]
 	fn crypto_invalid_password() {
@@ -0,0 +1,127 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Password-protected backups of whole key directories.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Read, Write};
+use ethkey::Address;
+use dir::KeyDirectory;
+use account::{Crypto, SafeAccount};
+use {json, Error};
+
+const BUNDLE_VERSION: u32 = 1;
+
+/// Write all accounts of `src` together with given additional `files` to `writer`,
+/// encrypted with `password`. Returns number of exported accounts.
+pub fn export_bundle<W>(src: &KeyDirectory, files: BTreeMap<String, String>, password: &str, iterations: u32, writer: &mut W) -> Result<usize, Error> where W: Write {
+	let accounts = try!(src.load());
+	let count = accounts.len();
+	let contents = json::BundleContents {
+		keys: accounts.into_iter().map(Into::into).collect(),
+		files: files,
+	};
+
+	let mut plain = Vec::new();
+	try!(contents.write(&mut plain).map_err(|e| Error::Custom(format!("{:?}", e))));
+	let bundle = json::Bundle {
+		version: BUNDLE_VERSION,
+		crypto: Crypto::encrypt(&plain, password, iterations).into(),
+	};
+	try!(bundle.write(writer).map_err(|e| Error::Custom(format!("{:?}", e))));
+	Ok(count)
+}
+
+/// Import accounts from a bundle read from `reader` into `dst`, skipping accounts already present.
+/// Returns imported accounts and the additional files stored in the bundle.
+pub fn import_bundle<R>(reader: R, password: &str, dst: &KeyDirectory) -> Result<(Vec<Address>, BTreeMap<String, String>), Error> where R: Read {
+	let bundle = try!(json::Bundle::load(reader).map_err(|e| Error::Custom(format!("Invalid bundle: {:?}", e))));
+	if bundle.version != BUNDLE_VERSION {
+		return Err(Error::Custom(format!("Unsupported bundle version: {}", bundle.version)));
+	}
+
+	let plain = try!(Crypto::from(bundle.crypto).decrypt(password));
+	let contents = try!(json::BundleContents::load(&plain[..]).map_err(|e| Error::Custom(format!("Invalid bundle contents: {:?}", e))));
+
+	let existing_accounts = try!(dst.load()).into_iter().map(|a| a.address).collect::<HashSet<_>>();
+	let mut imported = Vec::new();
+	for key in contents.keys {
+		let account = SafeAccount::from_file(key, None);
+		if existing_accounts.contains(&account.address) {
+			continue;
+		}
+		imported.push(try!(dst.insert(account)).address);
+	}
+
+	Ok((imported, contents.files))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+	use parking_lot::RwLock;
+	use ethkey::{Address, Generator, Random};
+	use dir::KeyDirectory;
+	use account::SafeAccount;
+	use Error;
+	use super::{export_bundle, import_bundle};
+
+	#[derive(Default)]
+	struct MemoryDirectory {
+		accounts: RwLock<Vec<SafeAccount>>,
+	}
+
+	impl KeyDirectory for MemoryDirectory {
+		fn load(&self) -> Result<Vec<SafeAccount>, Error> {
+			Ok(self.accounts.read().clone())
+		}
+
+		fn insert(&self, account: SafeAccount) -> Result<SafeAccount, Error> {
+			self.accounts.write().push(account.clone());
+			Ok(account)
+		}
+
+		fn remove(&self, address: &Address) -> Result<(), Error> {
+			self.accounts.write().retain(|a| &a.address != address);
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn should_export_and_import_bundle() {
+		let src = MemoryDirectory::default();
+		let keypair = Random.generate().unwrap();
+		src.insert(SafeAccount::create(&keypair, [0u8; 16], "key password", 1024, "Test".to_owned(), "{}".to_owned())).unwrap();
+		let mut files = BTreeMap::new();
+		files.insert("address_book.json".to_owned(), "{}".to_owned());
+
+		let mut bundle = Vec::new();
+		assert_eq!(export_bundle(&src, files.clone(), "bundle password", 1024, &mut bundle).unwrap(), 1);
+
+		let dst = MemoryDirectory::default();
+		assert!(import_bundle(&bundle[..], "wrong password", &dst).is_err());
+		let (imported, imported_files) = import_bundle(&bundle[..], "bundle password", &dst).unwrap();
+		assert_eq!(imported, vec![keypair.address()]);
+		assert_eq!(imported_files, files);
+
+		let account = dst.load().unwrap().pop().unwrap();
+		assert_eq!(account.name, "Test");
+		assert!(account.check_password("key password"));
+
+		// importing again does not duplicate accounts
+		assert!(import_bundle(&bundle[..], "bundle password", &dst).unwrap().0.is_empty());
+	}
+}
@@ -0,0 +1,56 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::{Read, Write};
+use std::collections::BTreeMap;
+use serde_json;
+use super::{Crypto, KeyFile};
+
+/// Password-protected backup of key files.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bundle {
+	pub version: u32,
+	pub crypto: Crypto,
+}
+
+/// Decrypted contents of a `Bundle`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct BundleContents {
+	/// Key files.
+	pub keys: Vec<KeyFile>,
+	/// Other files of the keys directory (e.g. the address book), by file name.
+	pub files: BTreeMap<String, String>,
+}
+
+impl Bundle {
+	pub fn load<R>(reader: R) -> Result<Self, serde_json::Error> where R: Read {
+		serde_json::from_reader(reader)
+	}
+
+	pub fn write<W>(&self, writer: &mut W) -> Result<(), serde_json::Error> where W: Write {
+		serde_json::to_writer(writer, self)
+	}
+}
+
+impl BundleContents {
+	pub fn load<R>(reader: R) -> Result<Self, serde_json::Error> where R: Read {
+		serde_json::from_reader(reader)
+	}
+
+	pub fn write<W>(&self, writer: &mut W) -> Result<(), serde_json::Error> where W: Write {
+		serde_json::to_writer(writer, self)
+	}
+}
@@ -39,6 +39,7 @@ pub mod dir;
 pub mod ethkey;
 
 mod account;
+mod backup;
 mod json;
 
 mod error;
@@ -49,9 +50,10 @@ mod random;
 mod secret_store;
 
 pub use self::account::SafeAccount;
+pub use self::backup::{export_bundle, import_bundle};
 pub use self::error::Error;
 pub use self::ethstore::EthStore;
 pub use self::import::{import_accounts, read_geth_accounts};
 pub use self::presale::PresaleWallet;
 pub use self::secret_store::SecretStore;
-pub use self::random::random_phrase;
+pub use self::random::{random_phrase, phrase_entropy};
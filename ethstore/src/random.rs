@@ -14,9 +14,17 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashSet;
 use rand::{Rng, OsRng};
 use itertools::Itertools;
 
+lazy_static! {
+	static ref WORDS: Vec<String> = String::from_utf8_lossy(include_bytes!("../res/wordlist.txt"))
+		.lines()
+		.map(|s| s.to_owned())
+		.collect();
+}
+
 pub trait Random {
 	fn random() -> Self where Self: Sized;
 }
@@ -45,19 +53,35 @@ impl Random for [u8; 32] {
 /// 12 gives 155 bits of entropy (almost saturating address space); 20 gives 258 bits
 /// which is enough to saturate 32-byte key space
 pub fn random_phrase(words: usize) -> String {
-	lazy_static! {
-		static ref WORDS: Vec<String> = String::from_utf8_lossy(include_bytes!("../res/wordlist.txt"))
-			.lines()
-			.map(|s| s.to_owned())
-			.collect();
-	}
 	let mut rng = OsRng::new().unwrap();
 	(0..words).map(|_| rng.choose(&WORDS).unwrap()).join(" ")
 }
 
+/// Estimate the entropy of a brain wallet phrase, in bits.
+///
+/// Words from the `random_phrase` dictionary count as a random choice from it,
+/// other words as random strings over the character classes they use.
+/// Repeated words are counted once.
+pub fn phrase_entropy(phrase: &str) -> f64 {
+	let mut seen = HashSet::new();
+	phrase.split_whitespace()
+		.filter(|word| seen.insert(*word))
+		.map(|word| if WORDS.iter().any(|w| w == word) {
+			(WORDS.len() as f64).log2()
+		} else {
+			let mut pool = 0;
+			if word.chars().any(|c| c.is_lowercase()) { pool += 26; }
+			if word.chars().any(|c| c.is_uppercase()) { pool += 26; }
+			if word.chars().any(|c| c.is_numeric()) { pool += 10; }
+			if word.chars().any(|c| !c.is_alphanumeric()) { pool += 33; }
+			word.chars().count() as f64 * (pool as f64).log2()
+		})
+		.sum()
+}
+
 #[cfg(test)]
 mod tests {
-	use super::random_phrase;
+	use super::{random_phrase, phrase_entropy};
 
 	#[test]
 	fn should_produce_right_number_of_words() {
@@ -70,4 +94,13 @@ mod tests {
 		let p = random_phrase(10);
 		assert!(!p.contains('\r'), "Carriage return should be trimmed.");
 	}
+
+	#[test]
+	fn should_estimate_phrase_entropy() {
+		assert!(phrase_entropy(&random_phrase(12)) > 120.0);
+		assert_eq!(phrase_entropy("abacus abacus abacus"), phrase_entropy("abacus"));
+		assert!(phrase_entropy("password") < 40.0);
+		assert!(phrase_entropy("Password1!") > phrase_entropy("password"));
+		assert_eq!(phrase_entropy("   "), 0.0);
+	}
 }
@@ -0,0 +1,70 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Locally stored accounts settings.
+
+use std::io::{Read, Write};
+use std::collections::HashMap;
+use serde_json;
+use hash;
+use uint::Uint;
+
+/// Settings of accounts which are not stored in the key files.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccountsSettings {
+	/// Tags of each account.
+	pub tags: HashMap<hash::Address, Vec<String>>,
+	/// Accounts visible to each dapp, by dapp id.
+	pub dapps: HashMap<String, Vec<hash::Address>>,
+	/// Signing policy of each account.
+	#[serde(default)]
+	pub policies: HashMap<hash::Address, AccountPolicy>,
+	/// Value sent by each account on the day of the latest transaction.
+	#[serde(default)]
+	pub spending: DailySpending,
+}
+
+/// Value sent by accounts during a single (UTC) day, counted against their daily limits.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DailySpending {
+	/// Days since the UNIX epoch.
+	pub day: u64,
+	/// Value sent by each account.
+	pub spent: HashMap<hash::Address, Uint>,
+}
+
+/// Rules checked before a transaction from an account is signed.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccountPolicy {
+	/// Maximal value transferred per day.
+	pub max_daily_value: Option<Uint>,
+	/// Only these recipients are allowed, if set.
+	pub allowed_recipients: Option<Vec<hash::Address>>,
+	/// Every request has to be confirmed in the signer.
+	pub require_confirmation: bool,
+}
+
+impl AccountsSettings {
+	/// Read settings from JSON.
+	pub fn load<R>(reader: R) -> Result<Self, serde_json::Error> where R: Read {
+		serde_json::from_reader(reader)
+	}
+
+	/// Write settings as JSON.
+	pub fn save<W>(&self, writer: &mut W) -> Result<(), serde_json::Error> where W: Write {
+		serde_json::to_writer(writer, self)
+	}
+}
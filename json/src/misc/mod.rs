@@ -17,5 +17,7 @@
 //! Misc deserialization.
 
 mod account_meta;
+mod accounts_settings;
 
 pub use self::account_meta::AccountMeta;
+pub use self::accounts_settings::{AccountsSettings, AccountPolicy, DailySpending};
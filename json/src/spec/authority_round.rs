@@ -30,6 +30,18 @@ pub struct AuthorityRoundParams {
 	pub step_duration: Uint,
 	/// Valid authorities
 	pub authorities: Vec<Address>,
+	/// Proposer weights of the authorities (in the same order).
+	#[serde(rename="authorityWeights")]
+	pub authority_weights: Option<Vec<Uint>>,
+	/// Seconds a block timestamp may lie outside the time window of its step.
+	#[serde(rename="timestampTolerance")]
+	pub timestamp_tolerance: Option<Uint>,
+	/// Block from which timestamps must lie within the time window of their step.
+	#[serde(rename="validateStepTimestampTransition")]
+	pub validate_step_timestamp_transition: Option<Uint>,
+	/// Reward paid to the author of every block.
+	#[serde(rename="blockReward")]
+	pub block_reward: Option<Uint>,
 }
 
 /// Authority engine deserialization.
@@ -50,7 +62,11 @@ mod tests {
 			"params": {
 				"gasLimitBoundDivisor": "0x0400",
 				"stepDuration": "0x02",
-				"authorities" : ["0xc6d9d2cd449a754c494264e1809c50e34d64562b"]
+				"authorities" : ["0xc6d9d2cd449a754c494264e1809c50e34d64562b"],
+				"authorityWeights" : ["0x02"],
+				"timestampTolerance" : "0x01",
+				"validateStepTimestampTransition" : "0x10",
+				"blockReward": "0x4563918244F40000"
 			}
 		}"#;
 
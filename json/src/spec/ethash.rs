@@ -16,6 +16,7 @@
 
 //! Ethash params deserialization.
 
+use std::collections::BTreeMap;
 use uint::Uint;
 use hash::Address;
 
@@ -66,6 +67,12 @@ pub struct EthashParams {
 	/// See main EthashParams docs.
 	#[serde(rename="bombDefuseTransition")]
 	pub bomb_defuse_transition: Option<Uint>,
+	/// See main EthashParams docs.
+	#[serde(rename="difficultyBombDelays")]
+	pub difficulty_bomb_delays: Option<BTreeMap<Uint, Uint>>,
+	/// See main EthashParams docs.
+	#[serde(rename="expDiffPeriod")]
+	pub exp_diff_period: Option<Uint>,
 
 	/// See main EthashParams docs.
 	#[serde(rename="eip150Transition")]
@@ -149,6 +156,10 @@ mod tests {
 				"difficultyHardforkTransition": "0x59d9",
 				"difficultyHardforkBoundDivisor": "0x0200",
 				"bombDefuseTransition": "0x42",
+				"difficultyBombDelays": {
+					"0x2dc6c0": "0x2dc6c0"
+				},
+				"expDiffPeriod": "0x0186a0",
 				"eip150Transition": "0x42",
 				"eip155Transition": "0x42",
 				"eip160Transition": "0x42",
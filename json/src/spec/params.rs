@@ -17,7 +17,8 @@
 //! Spec params deserialization.
 
 use uint::Uint;
-use hash::H256;
+use hash::{H256, Address};
+use spec::SealField;
 
 /// Spec params.
 #[derive(Debug, PartialEq, Deserialize)]
@@ -45,6 +46,19 @@ pub struct Params {
 	/// Expected fork block hash.
 	#[serde(rename="forkCanonHash")]
 	pub fork_hash: Option<H256>,
+	/// Contract deciding which nodes may connect.
+	#[serde(rename="nodePermissionContract")]
+	pub node_permission_contract: Option<Address>,
+	/// Contract deciding which transactions are allowed.
+	#[serde(rename="transactionPermissionContract")]
+	pub transaction_permission_contract: Option<Address>,
+	/// Contract storing the target block gas limit in its first storage slot.
+	#[serde(rename="gasLimitContract")]
+	pub gas_limit_contract: Option<Address>,
+	/// Named seal fields of block headers, overriding those built into the engine.
+	/// They must start with the engine's own fields, in order.
+	#[serde(rename="sealFields")]
+	pub seal_fields: Option<Vec<SealField>>,
 }
 
 #[cfg(test)]
@@ -60,7 +74,11 @@ mod tests {
 			"networkID" : "0x1",
 			"subprotocolName" : "exp",
 			"minGasLimit": "0x1388",
-			"accountStartNonce": "0x00"
+			"accountStartNonce": "0x00",
+			"nodePermissionContract": "0x0000000000000000000000000000000000000005",
+			"transactionPermissionContract": "0x0000000000000000000000000000000000000006",
+			"gasLimitContract": "0x0000000000000000000000000000000000000007",
+			"sealFields": [{ "name": "signature", "type": "signature" }]
 		}"#;
 
 		let _deserialized: Params = serde_json::from_str(s).unwrap();
@@ -49,10 +49,40 @@ pub enum Seal {
 	Generic(Generic),
 }
 
+/// RLP type of a seal field.
+#[derive(Debug, PartialEq, Deserialize)]
+pub enum SealFieldType {
+	/// Unsigned integer.
+	#[serde(rename="uint")]
+	Uint,
+	/// 32-byte hash.
+	#[serde(rename="hash")]
+	Hash,
+	/// 8-byte nonce.
+	#[serde(rename="nonce")]
+	Nonce,
+	/// 65-byte signature.
+	#[serde(rename="signature")]
+	Signature,
+	/// Arbitrary bytes.
+	#[serde(rename="bytes")]
+	Bytes,
+}
+
+/// Named seal field.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct SealField {
+	/// Field name.
+	pub name: String,
+	/// Field type.
+	#[serde(rename="type")]
+	pub field_type: SealFieldType,
+}
+
 #[cfg(test)]
 mod tests {
 	use serde_json;
-	use spec::Seal;
+	use spec::{Seal, SealField, SealFieldType};
 
 	#[test]
 	fn builtin_deserialization() {
@@ -70,4 +100,20 @@ mod tests {
 		let _deserialized: Vec<Seal> = serde_json::from_str(s).unwrap();
 		// TODO: validate all fields
 	}
+
+	#[test]
+	fn seal_field_deserialization() {
+		let s = r#"[{
+			"name": "step",
+			"type": "uint"
+		},{
+			"name": "signature",
+			"type": "signature"
+		}]"#;
+		let deserialized: Vec<SealField> = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized, vec![
+			SealField { name: "step".into(), field_type: SealFieldType::Uint },
+			SealField { name: "signature".into(), field_type: SealFieldType::Signature },
+		]);
+	}
 }
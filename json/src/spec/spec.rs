@@ -16,9 +16,12 @@
 
 //! Spec deserialization.
 
+use std::collections::BTreeMap;
 use std::io::Read;
 use serde_json;
 use serde_json::Error;
+use hash::H256;
+use uint::Uint;
 use spec::{Params, Genesis, Engine, State};
 
 /// Spec deserialization.
@@ -39,6 +42,8 @@ pub struct Spec {
 	pub accounts: State,
 	/// Boot nodes.
 	pub nodes: Option<Vec<String>>,
+	/// Trusted block hashes, by block number.
+	pub checkpoints: Option<BTreeMap<Uint, H256>>,
 }
 
 impl Spec {
@@ -99,6 +104,9 @@ mod tests {
 	"nodes": [
 		"enode://b1217cbaa440e35ed471157123fe468e19e8b5ad5bedb4b1fdbcbdab6fb2f5ed3e95dd9c24a22a79fdb2352204cea207df27d92bfd21bfd41545e8b16f637499@104.44.138.37:30303"
 	],
+	"checkpoints": {
+		"0x1d4c00": "0x4985f5ca3d2afbec36529aa96f74de3cc10a2a4a6c44f2157a57d2c6059a11bb"
+	},
 	"accounts": {
 		"0000000000000000000000000000000000000001": { "balance": "1", "nonce": "1048576", "builtin": { "name": "ecrecover", "pricing": { "linear": { "base": 3000, "word": 0 } } } },
 		"0000000000000000000000000000000000000002": { "balance": "1", "nonce": "1048576", "builtin": { "name": "sha256", "pricing": { "linear": { "base": 60, "word": 12 } } } },
@@ -107,7 +115,8 @@ mod tests {
 		"102e61f5d8f9bc71d0ad4a084df4e65e05ce0e1c": { "balance": "1606938044258990275541962092341162602522202993782792835301376", "nonce": "1048576" }
 	}
 		}"#;
-		let _deserialized: Spec = serde_json::from_str(s).unwrap();
+		let deserialized: Spec = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized.checkpoints.unwrap().len(), 1);
 		// TODO: validate all fields
 	}
 }
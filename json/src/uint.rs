@@ -17,7 +17,7 @@
 //! Lenient uint json deserialization for test json files.
 
 use std::str::FromStr;
-use serde::{Deserialize, Deserializer, Error};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, Error};
 use serde::de::Visitor;
 use util::{U256, Uint as U};
 
@@ -49,6 +49,12 @@ impl Into<u8> for Uint {
 	}
 }
 
+impl Serialize for Uint {
+	fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error> where S: Serializer {
+		serializer.serialize_str(&self.0.to_string())
+	}
+}
+
 impl Deserialize for Uint {
 	fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error>
 		where D: Deserializer {
@@ -104,6 +110,14 @@ mod test {
 		]);
 	}
 
+	#[test]
+	fn uint_serialization_roundtrip() {
+		let value = Uint(U256::from(1_000_000_000_000_000_000u64));
+		let serialized = serde_json::to_string(&value).unwrap();
+		assert_eq!(serialized, r#""1000000000000000000""#);
+		assert_eq!(serde_json::from_str::<Uint>(&serialized).unwrap(), value);
+	}
+
 	#[test]
 	fn uint_into() {
 		assert_eq!(U256::from(10), Uint(U256::from(10)).into());
@@ -26,20 +26,28 @@ extern crate time;
 #[macro_use]
 extern crate lazy_static;
 
-use std::{env, thread, fs};
+mod rotating;
+
+use std::{env, thread};
 use std::sync::Arc;
-use std::io::Write;
+use std::time::Duration;
 use isatty::{stderr_isatty, stdout_isatty};
 use env_logger::LogBuilder;
 use regex::Regex;
 use util::RotatingLogger;
 use util::log::Colour;
 
+pub use rotating::RotatingFile;
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Config {
 	pub mode: Option<String>,
 	pub color: bool,
 	pub file: Option<String>,
+	/// Size (in bytes) after which the log file is rotated.
+	pub file_max_size: Option<u64>,
+	/// Age after which the log file is rotated.
+	pub file_max_age: Option<Duration>,
 }
 
 impl Default for Config {
@@ -48,6 +56,8 @@ impl Default for Config {
 			mode: None,
 			color: !cfg!(windows),
 			file: None,
+			file_max_size: None,
+			file_max_age: None,
 		}
 	}
 }
@@ -79,11 +89,9 @@ pub fn setup_log(config: &Config) -> Result<Arc<RotatingLogger>, String> {
 	let enable_color = config.color && isatty;
 	let logs = Arc::new(RotatingLogger::new(levels));
 	let logger = logs.clone();
-	let mut open_options = fs::OpenOptions::new();
 
 	let maybe_file = match config.file.as_ref() {
-		Some(f) => Some(try!(open_options
-			.append(true).create(true).open(f)
+		Some(f) => Some(try!(RotatingFile::open(f.clone(), config.file_max_size, config.file_max_age)
 			.map_err(|_| format!("Cannot write to log file given: {}", f))
 		)),
 		None => None,
@@ -106,10 +114,9 @@ pub fn setup_log(config: &Config) -> Result<Arc<RotatingLogger>, String> {
 			false => removed_color.clone(),
 		};
 
-		if let Some(mut file) = maybe_file.as_ref() {
+		if let Some(ref file) = maybe_file {
 			// ignore errors - there's nothing we can do
-			let _ = file.write_all(removed_color.as_bytes());
-			let _ = file.write_all(b"\n");
+			let _ = file.write_line(&removed_color);
 		}
 		logger.append(removed_color);
 		if !isatty && record.level() <= LogLevel::Info && stdout_isatty() {
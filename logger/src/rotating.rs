@@ -0,0 +1,129 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Log file target rotated by size and age.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use util::Mutex;
+
+/// Number of rotated files kept next to the current one.
+const KEEP_FILES: usize = 5;
+
+struct State {
+	file: File,
+	size: u64,
+	opened: Instant,
+}
+
+/// Appends lines to a file, rotating it to `<path>.1`, `<path>.2`, ... when it grows
+/// over `max_size` bytes or gets older than `max_age`.
+pub struct RotatingFile {
+	path: PathBuf,
+	max_size: Option<u64>,
+	max_age: Option<Duration>,
+	state: Mutex<State>,
+}
+
+fn open(path: &Path) -> io::Result<State> {
+	let file = try!(OpenOptions::new().append(true).create(true).open(path));
+	let size = try!(file.metadata()).len();
+	Ok(State {
+		file: file,
+		size: size,
+		opened: Instant::now(),
+	})
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+	let mut name = path.as_os_str().to_owned();
+	name.push(format!(".{}", index));
+	PathBuf::from(name)
+}
+
+impl RotatingFile {
+	/// Opens (or creates) the log file at `path`.
+	pub fn open<P: Into<PathBuf>>(path: P, max_size: Option<u64>, max_age: Option<Duration>) -> io::Result<Self> {
+		let path = path.into();
+		let state = try!(open(&path));
+		Ok(RotatingFile {
+			path: path,
+			max_size: max_size,
+			max_age: max_age,
+			state: Mutex::new(state),
+		})
+	}
+
+	/// Appends a line to the file, rotating it first if needed.
+	pub fn write_line(&self, line: &str) -> io::Result<()> {
+		let mut state = self.state.lock();
+		let too_big = self.max_size.map_or(false, |max| state.size > 0 && state.size + line.len() as u64 + 1 > max);
+		let too_old = self.max_age.map_or(false, |max| state.opened.elapsed() >= max);
+		if too_big || too_old {
+			*state = try!(self.rotate());
+		}
+
+		try!(state.file.write_all(line.as_bytes()));
+		try!(state.file.write_all(b"\n"));
+		state.size += line.len() as u64 + 1;
+		Ok(())
+	}
+
+	fn rotate(&self) -> io::Result<State> {
+		let _ = fs::remove_file(rotated_path(&self.path, KEEP_FILES));
+		for index in (1..KEEP_FILES).rev() {
+			let from = rotated_path(&self.path, index);
+			if from.exists() {
+				try!(fs::rename(&from, rotated_path(&self.path, index + 1)));
+			}
+		}
+		try!(fs::rename(&self.path, rotated_path(&self.path, 1)));
+		open(&self.path)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{env, fs};
+	use std::io::Read;
+	use super::{RotatingFile, rotated_path};
+
+	fn read(path: &::std::path::Path) -> String {
+		let mut s = String::new();
+		fs::File::open(path).unwrap().read_to_string(&mut s).unwrap();
+		s
+	}
+
+	#[test]
+	fn should_rotate_when_size_exceeded() {
+		let dir = env::temp_dir().join(format!("parity-log-rotate-{}", ::time::precise_time_ns()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("parity.log");
+
+		let file = RotatingFile::open(path.clone(), Some(10), None).unwrap();
+		file.write_line("first").unwrap();
+		file.write_line("second").unwrap();
+		file.write_line("third").unwrap();
+
+		assert_eq!(read(&path), "third\n");
+		assert_eq!(read(&rotated_path(&path, 1)), "second\n");
+		assert_eq!(read(&rotated_path(&path, 2)), "first\n");
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
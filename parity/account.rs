@@ -14,9 +14,17 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethcore::ethstore::{EthStore, SecretStore, import_accounts, read_geth_accounts};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use ethcore::ethstore::{EthStore, SecretStore, import_accounts, read_geth_accounts, export_bundle, import_bundle};
 use ethcore::ethstore::dir::DiskDirectory;
 use ethcore::account_provider::AccountProvider;
+use ethcore::transaction::{Action, Transaction};
+use ethcore_rpc::v1::types::UnsignedTransaction;
+use util::{Address, H256};
+use serde_json;
 use helpers::{password_prompt, password_from_file};
 
 #[derive(Debug, PartialEq)]
@@ -24,9 +32,15 @@ pub enum AccountCmd {
 	New(NewAccount),
 	List(String),
 	Import(ImportAccounts),
-	ImportFromGeth(ImportFromGethAccounts)
+	ImportFromGeth(ImportFromGethAccounts),
+	Export(ExportAccounts),
+	ImportBundle(ImportAccountsBundle),
+	SignTransaction(SignTransaction),
 }
 
+/// Metadata files of the keys directory which are included in exported bundles.
+const BUNDLE_FILES: &'static [&'static str] = &["address_book.json", "accounts_settings.json"];
+
 #[derive(Debug, PartialEq)]
 pub struct NewAccount {
 	pub iterations: u32,
@@ -49,12 +63,46 @@ pub struct ImportFromGethAccounts {
 	pub to: String,
 }
 
+/// Parameters for exporting all accounts to an encrypted bundle
+#[derive(Debug, PartialEq)]
+pub struct ExportAccounts {
+	pub iterations: u32,
+	/// keys directory
+	pub path: String,
+	/// bundle file
+	pub file: String,
+	pub password_file: Option<String>,
+}
+
+/// Parameters for importing accounts from an encrypted bundle
+#[derive(Debug, PartialEq)]
+pub struct ImportAccountsBundle {
+	/// bundle file
+	pub file: String,
+	/// directory to import accounts to
+	pub to: String,
+	pub password_file: Option<String>,
+}
+
+/// Parameters for signing a transaction composed by `parity_composeTransaction`
+#[derive(Debug, PartialEq)]
+pub struct SignTransaction {
+	/// keys directory
+	pub path: String,
+	/// file with the unsigned transaction
+	pub file: String,
+	pub password_file: Option<String>,
+}
+
 pub fn execute(cmd: AccountCmd) -> Result<String, String> {
 	match cmd {
 		AccountCmd::New(new_cmd) => new(new_cmd),
 		AccountCmd::List(path) => list(path),
 		AccountCmd::Import(import_cmd) => import(import_cmd),
-		AccountCmd::ImportFromGeth(import_geth_cmd) => import_geth(import_geth_cmd)
+		AccountCmd::ImportFromGeth(import_geth_cmd) => import_geth(import_geth_cmd),
+		AccountCmd::Export(export_cmd) => export(export_cmd),
+		AccountCmd::ImportBundle(import_cmd) => import_from_bundle(import_cmd),
+		AccountCmd::SignTransaction(sign_cmd) => sign_transaction(sign_cmd),
 	}
 }
 
@@ -69,11 +117,15 @@ fn secret_store(dir: Box<DiskDirectory>, iterations: Option<u32>) -> Result<EthS
 	}.map_err(|e| format!("Could not open keys store: {}", e))
 }
 
+fn password(password_file: Option<String>) -> Result<String, String> {
+	match password_file {
+		Some(file) => password_from_file(file),
+		None => password_prompt(),
+	}
+}
+
 fn new(n: NewAccount) -> Result<String, String> {
-	let password: String = match n.password_file {
-		Some(file) => try!(password_from_file(file)),
-		None => try!(password_prompt()),
-	};
+	let password = try!(password(n.password_file));
 
 	let dir = Box::new(try!(keys_dir(n.path)));
 	let secret_store = Box::new(try!(secret_store(dir, Some(n.iterations))));
@@ -118,3 +170,82 @@ fn import_geth(i: ImportFromGethAccounts) -> Result<String, String> {
 		Err(err) => Err(format!("Import geth accounts failed. {}", err))
 	}
 }
+
+fn export(e: ExportAccounts) -> Result<String, String> {
+	let password = try!(password(e.password_file));
+	let dir = try!(keys_dir(e.path.clone()));
+
+	let mut files = BTreeMap::new();
+	for name in BUNDLE_FILES {
+		let path = Path::new(&e.path).join(name);
+		if let Ok(mut file) = fs::File::open(&path) {
+			let mut content = String::new();
+			try!(file.read_to_string(&mut content).map_err(|err| format!("Could not read {}: {}", path.display(), err)));
+			files.insert(name.to_string(), content);
+		}
+	}
+
+	let mut file = try!(fs::File::create(&e.file).map_err(|err| format!("Could not create {}: {}", e.file, err)));
+	let count = try!(export_bundle(&dir, files, &password, e.iterations, &mut file).map_err(|err| format!("Exporting accounts failed: {}", err)));
+	Ok(format!("{} account(s) exported to {}", count, e.file))
+}
+
+fn import_from_bundle(i: ImportAccountsBundle) -> Result<String, String> {
+	let password = try!(password(i.password_file));
+	let to = try!(keys_dir(i.to.clone()));
+	let file = try!(fs::File::open(&i.file).map_err(|err| format!("Could not open {}: {}", i.file, err)));
+	let (imported, files) = try!(import_bundle(file, &password, &to).map_err(|err| format!("Importing accounts failed: {}", err)));
+
+	let mut skipped = Vec::new();
+	// only known metadata files are restored and never over existing ones.
+	for (name, content) in files.into_iter().filter(|&(ref name, _)| BUNDLE_FILES.contains(&name.as_str())) {
+		let path = Path::new(&i.to).join(&name);
+		if path.exists() {
+			skipped.push(name);
+			continue;
+		}
+		let mut file = try!(fs::File::create(&path).map_err(|err| format!("Could not create {}: {}", path.display(), err)));
+		try!(file.write_all(content.as_bytes()).map_err(|err| format!("Could not write {}: {}", path.display(), err)));
+	}
+
+	let mut result = format!("{} account(s) imported", imported.len());
+	if !skipped.is_empty() {
+		result.push_str(&format!(", kept existing {}", skipped.join(", ")));
+	}
+	Ok(result)
+}
+
+fn sign_transaction(s: SignTransaction) -> Result<String, String> {
+	let mut content = String::new();
+	try!(fs::File::open(&s.file)
+		.and_then(|mut file| file.read_to_string(&mut content))
+		.map_err(|err| format!("Could not read {}: {}", s.file, err)));
+	let unsigned: UnsignedTransaction = try!(serde_json::from_str(&content).map_err(|err| format!("Invalid transaction file: {}", err)));
+
+	let from: Address = unsigned.from.clone().into();
+	let expected_hash: H256 = unsigned.hash.clone().into();
+	let network_id = unsigned.network_id;
+	let transaction: Transaction = unsigned.into();
+	// never trust the hash, it has to match what is being shown.
+	let hash = transaction.hash(network_id);
+	if hash != expected_hash {
+		return Err("Transaction hash does not match its contents.".into());
+	}
+
+	println!("Signing transaction from {:?}:", from);
+	match transaction.action {
+		Action::Create => println!("  to:        contract creation"),
+		Action::Call(ref to) => println!("  to:        {:?}", to),
+	}
+	println!("  value:     {} wei", transaction.value);
+	println!("  nonce:     {}", transaction.nonce);
+	println!("  gas:       {} at {} wei", transaction.gas, transaction.gas_price);
+	println!("  data:      {} bytes", transaction.data.len());
+
+	let password = try!(password(s.password_file));
+	let dir = Box::new(try!(keys_dir(s.path)));
+	let secret_store = Box::new(try!(secret_store(dir, None)));
+	let acc_provider = AccountProvider::new(secret_store);
+	let signature = try!(acc_provider.sign(from, Some(password), hash).map_err(|err| format!("Could not sign transaction: {}", err)));
+	Ok(format!("0x{}", signature))
+}
@@ -15,19 +15,23 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::str::{FromStr, from_utf8};
-use std::{io, fs};
+use std::{io, fs, cmp};
 use std::io::{BufReader, BufRead};
 use std::time::{Instant, Duration};
 use std::thread::sleep;
 use std::sync::Arc;
+use std::path::Path;
 use rustc_serialize::hex::FromHex;
 use io::{PanicHandler, ForwardPanic};
 use util::{ToPretty, Uint, U256, H256, Address, Hashable};
+use util::kvdb::{Database, DatabaseConfig};
 use rlp::PayloadInfo;
 use ethcore::service::ClientService;
 use ethcore::client::{Mode, DatabaseCompactionProfile, VMType, BlockImportError, BlockChainClient, BlockID};
 use ethcore::error::ImportError;
 use ethcore::miner::Miner;
+use ethcore::snapshot::{self, Progress};
+use ethcore::integrity;
 use cache::CacheConfig;
 use informant::{Informant, MillisecondDuration};
 use params::{SpecType, Pruning, Switch, tracing_switch_to_bool, fatdb_switch_to_bool};
@@ -37,6 +41,9 @@ use dir::Directories;
 use user_defaults::UserDefaults;
 use fdlimit;
 
+/// Number of blocks read from the database at once when exporting.
+const EXPORT_BATCH_SIZE: u64 = 1000;
+
 #[derive(Debug, PartialEq)]
 pub enum DataFormat {
 	Hex,
@@ -66,6 +73,8 @@ pub enum BlockchainCmd {
 	Import(ImportBlockchain),
 	Export(ExportBlockchain),
 	ExportState(ExportState),
+	ConvertPruning(ConvertPruning),
+	Check(CheckDatabase),
 }
 
 #[derive(Debug, PartialEq)]
@@ -124,11 +133,32 @@ pub struct ExportState {
 	pub max_balance: Option<U256>,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct ConvertPruning {
+	pub spec: SpecType,
+	pub dirs: Directories,
+	pub pruning: Pruning,
+	pub compaction: DatabaseCompactionProfile,
+	pub wal: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CheckDatabase {
+	pub spec: SpecType,
+	pub dirs: Directories,
+	pub pruning: Pruning,
+	pub compaction: DatabaseCompactionProfile,
+	pub wal: bool,
+	pub repair: bool,
+}
+
 pub fn execute(cmd: BlockchainCmd) -> Result<String, String> {
 	match cmd {
 		BlockchainCmd::Import(import_cmd) => execute_import(import_cmd),
 		BlockchainCmd::Export(export_cmd) => execute_export(export_cmd),
 		BlockchainCmd::ExportState(export_cmd) => execute_export_state(export_cmd),
+		BlockchainCmd::ConvertPruning(convert_cmd) => execute_convert_pruning(convert_cmd),
+		BlockchainCmd::Check(check_cmd) => execute_check(check_cmd),
 	}
 }
 
@@ -364,20 +394,31 @@ fn execute_export(cmd: ExportBlockchain) -> Result<String, String> {
 	let from = try!(client.block_number(cmd.from_block).ok_or("From block could not be found"));
 	let to = try!(client.block_number(cmd.to_block).ok_or("To block could not be found"));
 
-	for i in from..(to + 1) {
-		if i % 10000 == 0 {
-			info!("#{}", i);
+	let mut i = from;
+	while i <= to {
+		let last = cmp::min(i + EXPORT_BATCH_SIZE - 1, to);
+		let blocks = client.blocks_in_range(i, last);
+		if blocks.len() as u64 != last - i + 1 {
+			return Err("Error exporting incomplete chain".into());
 		}
-		let b = try!(client.block(BlockID::Number(i)).ok_or("Error exporting incomplete chain"));
-		match format {
-			DataFormat::Binary => { out.write(&b).expect("Couldn't write to stream."); }
-			DataFormat::Hex => { out.write_fmt(format_args!("{}", b.pretty())).expect("Couldn't write to stream."); }
+
+		for b in blocks {
+			if i % 10000 == 0 {
+				info!("#{}", i);
+			}
+			match format {
+				DataFormat::Binary => { out.write(&b).expect("Couldn't write to stream."); }
+				DataFormat::Hex => { out.write_fmt(format_args!("{}", b.pretty())).expect("Couldn't write to stream."); }
+			}
+			i += 1;
 		}
 	}
 
 	Ok("Export completed.".into())
 }
 
+const NO_FAT_DB_STATE: &'static str = "Specified block not found or the database was not created with --fat-db on";
+
 fn execute_export_state(cmd: ExportState) -> Result<String, String> {
 	// Setup panic handler
 	let service = try!(start_client(cmd.dirs, cmd.spec, cmd.pruning, cmd.pruning_history, cmd.tracing, cmd.fat_db, cmd.compaction, cmd.wal, cmd.cache_config));
@@ -395,9 +436,10 @@ fn execute_export_state(cmd: ExportState) -> Result<String, String> {
 	let at = cmd.at;
 	let mut i = 0usize;
 
-	out.write_fmt(format_args!("{{ \"state\": [", )).expect("Couldn't write to stream.");
+	// output is a chain spec `accounts` section, so the state can be used as a new genesis.
+	out.write_fmt(format_args!("{{ \"accounts\": {{", )).expect("Couldn't write to stream.");
 	loop {
-		let accounts = try!(client.list_accounts(at, last.as_ref(), 1000).ok_or("Specified block not found"));
+		let accounts = try!(client.list_accounts(at, last.as_ref(), 1000).ok_or(NO_FAT_DB_STATE));
 		if accounts.is_empty() {
 			break;
 		}
@@ -412,27 +454,27 @@ fn execute_export_state(cmd: ExportState) -> Result<String, String> {
 			if i != 0 {
 				out.write(b",").expect("Write error");
 			}
-			out.write_fmt(format_args!("\n\"0x{}\": {{\"balance\": \"{:x}\", \"nonce\": \"{:x}\"", account.hex(), balance, client.nonce(&account, at).unwrap_or_else(U256::zero))).expect("Write error");
+			out.write_fmt(format_args!("\n\"0x{}\": {{\"balance\": \"0x{:x}\", \"nonce\": \"0x{:x}\"", account.hex(), balance, client.nonce(&account, at).unwrap_or_else(U256::zero))).expect("Write error");
 			let code = client.code(&account, at).unwrap_or(None).unwrap_or_else(Vec::new);
 			if !code.is_empty() {
-				out.write_fmt(format_args!(", \"code_hash\": \"0x{}\"", code.sha3().hex())).expect("Write error");
 				if cmd.code {
-					out.write_fmt(format_args!(", \"code\": \"{}\"", code.to_hex())).expect("Write error");
+					out.write_fmt(format_args!(", \"code\": \"0x{}\"", code.to_hex())).expect("Write error");
+				} else {
+					out.write_fmt(format_args!(", \"code_hash\": \"0x{}\"", code.sha3().hex())).expect("Write error");
 				}
 			}
 			let storage_root = client.storage_root(&account, at).unwrap_or(::util::SHA3_NULL_RLP);
 			if storage_root != ::util::SHA3_NULL_RLP {
-				out.write_fmt(format_args!(", \"storage_root\": \"0x{}\"", storage_root.hex())).expect("Write error");
 				if cmd.storage {
 					out.write_fmt(format_args!(", \"storage\": {{")).expect("Write error");
 					let mut last_storage: Option<H256> = None;
+					let mut si = 0;
 					loop {
-						let keys = try!(client.list_storage(at, &account, last_storage.as_ref(), 1000).ok_or("Specified block not found"));
+						let keys = try!(client.list_storage(at, &account, last_storage.as_ref(), 1000).ok_or(NO_FAT_DB_STATE));
 						if keys.is_empty() {
 							break;
 						}
 
-						let mut si = 0;
 						for key in keys.into_iter() {
 							if si != 0 {
 								out.write(b",").expect("Write error");
@@ -443,6 +485,8 @@ fn execute_export_state(cmd: ExportState) -> Result<String, String> {
 						}
 					}
 					out.write(b"\n}").expect("Write error");
+				} else {
+					out.write_fmt(format_args!(", \"storage_root\": \"0x{}\"", storage_root.hex())).expect("Write error");
 				}
 			}
 			out.write(b"}").expect("Write error");
@@ -453,10 +497,129 @@ fn execute_export_state(cmd: ExportState) -> Result<String, String> {
 			last = Some(account);
 		}
 	}
-	out.write_fmt(format_args!("\n]}}")).expect("Write error");
+	out.write_fmt(format_args!("\n}}}}")).expect("Write error");
 	Ok("Export completed.".into())
 }
 
+fn execute_convert_pruning(cmd: ConvertPruning) -> Result<String, String> {
+	let to = match cmd.pruning {
+		Pruning::Specific(algorithm) => algorithm,
+		Pruning::Auto => return Err("Specify the target pruning method with --pruning.".into()),
+	};
+
+	// load spec file
+	let spec = try!(cmd.spec.spec());
+
+	// load genesis hash
+	let genesis_hash = spec.genesis_header().hash();
+
+	// database paths
+	let db_dirs = cmd.dirs.database(genesis_hash, spec.fork_name.clone());
+
+	// user defaults path
+	let user_defaults_path = db_dirs.user_defaults_path();
+
+	// load user defaults
+	let mut user_defaults = try!(UserDefaults::load(&user_defaults_path));
+	if user_defaults.is_first_launch {
+		return Err("There is no database to convert.".into());
+	}
+
+	let from = user_defaults.pruning;
+	if from == to {
+		return Err(format!("Database is already using {} pruning.", to));
+	}
+
+	fdlimit::raise_fd_limit();
+
+	let compaction = cmd.compaction.compaction_profile(db_dirs.fork_path().as_path());
+
+	// execute upgrades
+	try!(execute_upgrades(&db_dirs, from, compaction));
+
+	let source_path = db_dirs.client_path(from);
+	let target_path = db_dirs.client_path(to);
+	if target_path.exists() {
+		return Err(format!("Target database {} already exists. Remove it first.", target_path.display()));
+	}
+
+	let mut db_config = DatabaseConfig::with_columns(::ethcore::db::NUM_COLUMNS);
+	db_config.compaction = compaction;
+	db_config.wal = cmd.wal;
+
+	let open = |path: &Path| Database::open(&db_config, &path.to_string_lossy())
+		.map(Arc::new)
+		.map_err(|e| format!("Error opening database {}: {}", path.display(), e));
+	let source = try!(open(&source_path));
+	let target = try!(open(&target_path));
+
+	info!("Converting database from {} to {} pruning. This may take a while.", from, to);
+	let progress = Progress::default();
+	try!(snapshot::convert_pruning(source, from, target, to, &progress).map_err(|e| {
+		let _ = fs::remove_dir_all(&target_path);
+		format!("Conversion failed: {}", e)
+	}));
+
+	user_defaults.pruning = to;
+	try!(user_defaults.save(&user_defaults_path));
+
+	Ok(format!("Converted {} accounts. The old database at {} can now be removed.", progress.accounts(), source_path.display()))
+}
+
+fn execute_check(cmd: CheckDatabase) -> Result<String, String> {
+	// load spec file
+	let spec = try!(cmd.spec.spec());
+
+	// load genesis hash
+	let genesis_hash = spec.genesis_header().hash();
+
+	// database paths
+	let db_dirs = cmd.dirs.database(genesis_hash, spec.fork_name.clone());
+
+	// user defaults path
+	let user_defaults_path = db_dirs.user_defaults_path();
+
+	// load user defaults
+	let user_defaults = try!(UserDefaults::load(&user_defaults_path));
+	if user_defaults.is_first_launch {
+		return Err("There is no database to check.".into());
+	}
+
+	fdlimit::raise_fd_limit();
+
+	// select pruning algorithm
+	let algorithm = cmd.pruning.to_algorithm(&user_defaults);
+
+	let compaction = cmd.compaction.compaction_profile(db_dirs.fork_path().as_path());
+
+	// execute upgrades
+	try!(execute_upgrades(&db_dirs, algorithm, compaction));
+
+	let mut db_config = DatabaseConfig::with_columns(::ethcore::db::NUM_COLUMNS);
+	db_config.compaction = compaction;
+	db_config.wal = cmd.wal;
+
+	let client_path = db_dirs.client_path(algorithm);
+	let db = try!(Database::open(&db_config, &client_path.to_string_lossy())
+		.map(Arc::new)
+		.map_err(|e| format!("Error opening database {}: {}", client_path.display(), e)));
+
+	info!("Checking block bodies and receipts. This may take a while.");
+	let corrupted = integrity::check_chain(db.clone());
+	if corrupted.is_empty() {
+		return Ok("No damaged blocks found.".into());
+	}
+
+	if !cmd.repair {
+		return Err(format!("Found {} damaged blocks. Run again with --repair to download them again.", corrupted.len()));
+	}
+
+	match try!(integrity::schedule_redownload(db, &corrupted).map_err(|e| format!("{}", e))) {
+		Some((from, to)) => Ok(format!("Found {} damaged blocks. Blocks #{} to #{} were removed and will be downloaded again once the client is started.", corrupted.len(), from, to)),
+		None => unreachable!("corrupted is not empty; qed"),
+	}
+}
+
 #[cfg(test)]
 mod test {
 	use super::DataFormat;
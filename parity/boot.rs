@@ -110,6 +110,8 @@ Usage:
 				color: self.flag_no_color || cfg!(windows),
 				mode: self.flag_logging.clone(),
 				file: self.flag_log_file.clone(),
+				file_max_size: None,
+				file_max_age: None,
 			}
 		}
 	}
@@ -16,6 +16,7 @@
 
 #[macro_use]
 mod usage;
+mod presets;
 
 usage! {
 	{
@@ -26,6 +27,10 @@ usage! {
 		cmd_new: bool,
 		cmd_list: bool,
 		cmd_export: bool,
+		cmd_sign_transaction: bool,
+		cmd_node_key: bool,
+		cmd_show: bool,
+		cmd_rotate: bool,
 		cmd_blocks: bool,
 		cmd_state: bool,
 		cmd_import: bool,
@@ -36,6 +41,9 @@ usage! {
 		cmd_ui: bool,
 		cmd_tools: bool,
 		cmd_hash: bool,
+		cmd_db: bool,
+		cmd_convert_pruning: bool,
+		cmd_check: bool,
 
 		// Arguments
 		arg_pid_file: String,
@@ -43,6 +51,11 @@ usage! {
 		arg_path: Vec<String>,
 
 		// Flags
+		// -- Account backup
+		flag_all: bool,
+		flag_bundle: bool,
+		// -- Database check
+		flag_repair: bool,
 		// -- Legacy Options
 		flag_geth: bool,
 		flag_testnet: bool,
@@ -73,6 +86,7 @@ usage! {
 		// -- Miscellaneous Options
 		flag_version: bool,
 		flag_no_config: bool,
+		flag_print_config: bool,
 	}
 	{
 		// -- Operating Options
@@ -83,6 +97,10 @@ usage! {
 		flag_db_path: String = "$HOME/.parity", or |c: &Config| otry!(c.parity).db_path.clone(),
 		flag_keys_path: String = "$HOME/.parity/keys", or |c: &Config| otry!(c.parity).keys_path.clone(),
 		flag_identity: String = "", or |c: &Config| otry!(c.parity).identity.clone(),
+		flag_auto_update: String = "critical", or |c: &Config| otry!(c.parity).auto_update.clone(),
+		flag_shutdown_timeout: u64 = 60u64, or |c: &Config| otry!(c.parity).shutdown_timeout.clone(),
+		flag_max_reorg_depth: Option<u64> = None,
+			or |c: &Config| otry!(c.parity).max_reorg_depth.clone().map(Some),
 
 		// -- Account Options
 		flag_unlock: Option<String> = None,
@@ -91,6 +109,12 @@ usage! {
 			or |c: &Config| otry!(c.account).password.clone(),
 		flag_keys_iterations: u32 = 10240u32,
 			or |c: &Config| otry!(c.account).keys_iterations.clone(),
+		flag_min_phrase_entropy: u32 = 64u32,
+			or |c: &Config| otry!(c.account).min_phrase_entropy.clone(),
+		flag_private_tx_account: Option<String> = None,
+			or |c: &Config| otry!(c.account).private_tx_account.clone().map(Some),
+		flag_private_tx_validators: Option<String> = None,
+			or |c: &Config| otry!(c.account).private_tx_validators.clone().map(|vec| Some(vec.join(","))),
 
 		flag_force_ui: bool = false,
 			or |c: &Config| otry!(c.ui).force.clone(),
@@ -134,7 +158,30 @@ usage! {
 			or |c: &Config| otry!(c.network).reserved_peers.clone().map(Some),
 		flag_reserved_only: bool = false,
 			or |c: &Config| otry!(c.network).reserved_only.clone(),
-		flag_no_ancient_blocks: bool = false, or |_| None,
+		flag_no_ancient_blocks: bool = false,
+			or |c: &Config| otry!(c.network).download_old_blocks.map(|d| !d).clone(),
+		flag_tx_diffusion: bool = false,
+			or |c: &Config| otry!(c.network).tx_diffusion.clone(),
+		flag_serve_light: bool = false,
+			or |c: &Config| otry!(c.network).serve_light.clone(),
+		flag_light_buffer_limit: u64 = 50_000_000u64,
+			or |c: &Config| otry!(c.network).light_buffer_limit.clone(),
+		flag_light_buffer_recharge: u64 = 1_000_000u64,
+			or |c: &Config| otry!(c.network).light_buffer_recharge.clone(),
+		flag_session_rekey_interval: Option<u64> = None,
+			or |c: &Config| otry!(c.network).session_rekey_interval.clone().map(Some),
+		flag_listen_addresses: Option<String> = None,
+			or |c: &Config| otry!(c.network).listen_addresses.clone().map(|vec| Some(vec.join(","))),
+		flag_prefer_ipv6: bool = false,
+			or |c: &Config| otry!(c.network).prefer_ipv6.clone(),
+		flag_grace_period: u64 = 86400u64,
+			or |c: &Config| otry!(c.network).grace_period.clone(),
+		flag_proxy: Option<String> = None,
+			or |c: &Config| otry!(c.network).proxy.clone().map(Some),
+		flag_max_peers_per_ip: Option<u32> = None,
+			or |c: &Config| otry!(c.network).max_peers_per_ip.clone().map(Some),
+		flag_max_peers_per_subnet: Option<u32> = None,
+			or |c: &Config| otry!(c.network).max_peers_per_subnet.clone().map(Some),
 
 		// -- API and Console Options
 		// RPC
@@ -150,6 +197,14 @@ usage! {
 			or |c: &Config| otry!(c.rpc).apis.clone().map(|vec| vec.join(",")),
 		flag_jsonrpc_hosts: String = "none",
 			or |c: &Config| otry!(c.rpc).hosts.clone().map(|vec| vec.join(",")),
+		flag_public_node: bool = false,
+			or |c: &Config| otry!(c.rpc).public_node.clone(),
+		flag_jsonrpc_log: bool = false,
+			or |c: &Config| otry!(c.rpc).log.clone(),
+		flag_jsonrpc_slow_query_ms: Option<u64> = None,
+			or |c: &Config| otry!(c.rpc).slow_query_ms.clone().map(Some),
+		flag_rpc_gas_cap: Option<String> = None,
+			or |c: &Config| otry!(c.rpc).gas_cap.clone().map(Some),
 
 		// IPC
 		flag_no_ipc: bool = false,
@@ -159,6 +214,20 @@ usage! {
 		flag_ipc_apis: String = "web3,eth,net,parity,parity_accounts,traces,rpc",
 			or |c: &Config| otry!(c.ipc).apis.clone().map(|vec| vec.join(",")),
 
+		// WEBSOCKETS
+		flag_no_ws: bool = false,
+			or |c: &Config| otry!(c.websockets).disable.clone(),
+		flag_ws_port: u16 = 8546u16,
+			or |c: &Config| otry!(c.websockets).port.clone(),
+		flag_ws_interface: String = "local",
+			or |c: &Config| otry!(c.websockets).interface.clone(),
+		flag_ws_apis: String = "web3,eth,net,parity,traces,rpc",
+			or |c: &Config| otry!(c.websockets).apis.clone().map(|vec| vec.join(",")),
+		flag_ws_origins: String = "none",
+			or |c: &Config| otry!(c.websockets).origins.clone().map(|vec| vec.join(",")),
+		flag_ws_max_pending_requests: usize = 64usize,
+			or |c: &Config| otry!(c.websockets).max_pending_requests.clone(),
+
 		// DAPPS
 		flag_no_dapps: bool = false,
 			or |c: &Config| otry!(c.dapps).disable.clone(),
@@ -175,6 +244,14 @@ usage! {
 		flag_dapps_pass: Option<String> = None,
 			or |c: &Config| otry!(c.dapps).pass.clone().map(Some),
 
+		// METRICS
+		flag_metrics: bool = false,
+			or |c: &Config| otry!(c.metrics).enable.clone(),
+		flag_metrics_port: u16 = 9615u16,
+			or |c: &Config| otry!(c.metrics).port.clone(),
+		flag_metrics_interface: String = "local",
+			or |c: &Config| otry!(c.metrics).interface.clone(),
+
 		// -- Sealing/Mining Options
 		flag_author: Option<String> = None,
 			or |c: &Config| otry!(c.mining).author.clone().map(Some),
@@ -184,6 +261,8 @@ usage! {
 			or |c: &Config| otry!(c.mining).reseal_on_txs.clone(),
 		flag_reseal_min_period: u64 = 2000u64,
 			or |c: &Config| otry!(c.mining).reseal_min_period.clone(),
+		flag_reseal_max_period: u64 = 120000u64,
+			or |c: &Config| otry!(c.mining).reseal_max_period.clone(),
 		flag_work_queue_size: usize = 20usize,
 			or |c: &Config| otry!(c.mining).work_queue_size.clone(),
 		flag_tx_gas_limit: Option<String> = None,
@@ -214,14 +293,28 @@ usage! {
 			or |c: &Config| otry!(c.mining).tx_queue_ban_count.clone(),
 		flag_tx_queue_ban_time: u16 = 180u16,
 			or |c: &Config| otry!(c.mining).tx_queue_ban_time.clone(),
+		flag_tx_queue_max_future_age: Option<u64> = None,
+			or |c: &Config| otry!(c.mining).tx_queue_max_future_age.clone().map(Some),
 		flag_remove_solved: bool = false,
 			or |c: &Config| otry!(c.mining).remove_solved.clone(),
 		flag_notify_work: Option<String> = None,
 			or |c: &Config| otry!(c.mining).notify_work.clone().map(|vec| Some(vec.join(","))),
+		flag_ethash_cache_dir: String = "$HOME/.ethash/light",
+			or |c: &Config| otry!(c.mining).ethash_cache_dir.clone(),
+		flag_ethash_cache_epochs: u64 = 2u64,
+			or |c: &Config| otry!(c.mining).ethash_cache_epochs.clone(),
+		flag_ethash_pregenerate: bool = false,
+			or |c: &Config| otry!(c.mining).ethash_pregenerate.clone(),
 
 		// -- Footprint Options
+		flag_footprint_preset: Option<String> = None,
+			or |c: &Config| otry!(c.footprint).preset.clone().map(Some),
 		flag_tracing: String = "auto",
 			or |c: &Config| otry!(c.footprint).tracing.clone(),
+		flag_tracing_addresses: Option<String> = None,
+			or |c: &Config| otry!(c.footprint).tracing_addresses.clone().map(|vec| Some(vec.join(","))),
+		flag_tracing_history: Option<u64> = None,
+			or |c: &Config| otry!(c.footprint).tracing_history.clone().map(Some),
 		flag_pruning: String = "auto",
 			or |c: &Config| otry!(c.footprint).pruning.clone(),
 		flag_pruning_history: u64 = 64u64,
@@ -240,8 +333,22 @@ usage! {
 			or |c: &Config| otry!(c.footprint).fast_and_loose.clone(),
 		flag_db_compaction: String = "auto",
 			or |c: &Config| otry!(c.footprint).db_compaction.clone(),
+		flag_db_compaction_style: String = "universal",
+			or |c: &Config| otry!(c.footprint).db_compaction_style.clone(),
+		flag_db_wal_size: u32 = 64u32,
+			or |c: &Config| otry!(c.footprint).db_wal_size.clone(),
+		flag_db_block_cache_size: u32 = 2u32,
+			or |c: &Config| otry!(c.footprint).db_block_cache_size.clone(),
+		flag_db_commit_batch: usize = 0usize,
+			or |c: &Config| otry!(c.footprint).db_commit_batch.clone(),
 		flag_fat_db: String = "auto",
 			or |c: &Config| otry!(c.footprint).fat_db.clone(),
+		flag_sender_index: bool = false,
+			or |c: &Config| otry!(c.footprint).sender_index.clone(),
+		flag_scale_verifiers: bool = false,
+			or |c: &Config| otry!(c.footprint).scale_verifiers.clone(),
+		flag_num_verifiers: Option<usize> = None,
+			or |c: &Config| otry!(c.footprint).num_verifiers.clone().map(Some),
 
 		// -- Import/Export Options
 		flag_from: String = "1", or |_| None,
@@ -257,10 +364,16 @@ usage! {
 		flag_at: String = "latest", or |_| None,
 		flag_no_periodic_snapshot: bool = false,
 			or |c: &Config| otry!(c.snapshots).disable_periodic.clone(),
+		flag_verify_restored_state: bool = false,
+			or |c: &Config| otry!(c.snapshots).verify_restored_state.clone(),
 
 		// -- Virtual Machine Options
 		flag_jitvm: bool = false,
 			or |c: &Config| otry!(c.vm).jit.clone(),
+		flag_parallel_execution: bool = false,
+			or |c: &Config| otry!(c.vm).parallel_execution.clone(),
+		flag_pipeline_state_root: bool = false,
+			or |c: &Config| otry!(c.vm).pipeline_state_root.clone(),
 
 		// -- Miscellaneous Options
 		flag_config: String = "$HOME/.parity/config.toml", or |_| None,
@@ -268,8 +381,14 @@ usage! {
 			or |c: &Config| otry!(c.misc).logging.clone().map(Some),
 		flag_log_file: Option<String> = None,
 			or |c: &Config| otry!(c.misc).log_file.clone().map(Some),
+		flag_log_file_max_size: u64 = 100u64,
+			or |c: &Config| otry!(c.misc).log_file_max_size.clone(),
+		flag_log_file_max_age: u64 = 0u64,
+			or |c: &Config| otry!(c.misc).log_file_max_age.clone(),
 		flag_no_color: bool = false,
 			or |c: &Config| otry!(c.misc).color.map(|c| !c).clone(),
+		flag_ntp_server: String = "pool.ntp.org:123",
+			or |c: &Config| otry!(c.misc).ntp_server.clone(),
 	}
 }
 
@@ -282,7 +401,9 @@ struct Config {
 	network: Option<Network>,
 	rpc: Option<Rpc>,
 	ipc: Option<Ipc>,
+	websockets: Option<Ws>,
 	dapps: Option<Dapps>,
+	metrics: Option<Metrics>,
 	mining: Option<Mining>,
 	footprint: Option<Footprint>,
 	snapshots: Option<Snapshots>,
@@ -299,6 +420,9 @@ struct Operating {
 	db_path: Option<String>,
 	keys_path: Option<String>,
 	identity: Option<String>,
+	auto_update: Option<String>,
+	shutdown_timeout: Option<u64>,
+	max_reorg_depth: Option<u64>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -306,6 +430,9 @@ struct Account {
 	unlock: Option<Vec<String>>,
 	password: Option<Vec<String>>,
 	keys_iterations: Option<u32>,
+	min_phrase_entropy: Option<u32>,
+	private_tx_account: Option<String>,
+	private_tx_validators: Option<Vec<String>>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -334,6 +461,18 @@ struct Network {
 	node_key: Option<String>,
 	reserved_peers: Option<String>,
 	reserved_only: Option<bool>,
+	download_old_blocks: Option<bool>,
+	tx_diffusion: Option<bool>,
+	serve_light: Option<bool>,
+	light_buffer_limit: Option<u64>,
+	light_buffer_recharge: Option<u64>,
+	session_rekey_interval: Option<u64>,
+	listen_addresses: Option<Vec<String>>,
+	prefer_ipv6: Option<bool>,
+	grace_period: Option<u64>,
+	proxy: Option<String>,
+	max_peers_per_ip: Option<u32>,
+	max_peers_per_subnet: Option<u32>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -344,6 +483,10 @@ struct Rpc {
 	cors: Option<String>,
 	apis: Option<Vec<String>>,
 	hosts: Option<Vec<String>>,
+	public_node: Option<bool>,
+	log: Option<bool>,
+	slow_query_ms: Option<u64>,
+	gas_cap: Option<String>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -353,6 +496,16 @@ struct Ipc {
 	apis: Option<Vec<String>>,
 }
 
+#[derive(Default, Debug, PartialEq, RustcDecodable)]
+struct Ws {
+	disable: Option<bool>,
+	port: Option<u16>,
+	interface: Option<String>,
+	apis: Option<Vec<String>>,
+	origins: Option<Vec<String>>,
+	max_pending_requests: Option<usize>,
+}
+
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
 struct Dapps {
 	disable: Option<bool>,
@@ -364,12 +517,20 @@ struct Dapps {
 	pass: Option<String>,
 }
 
+#[derive(Default, Debug, PartialEq, RustcDecodable)]
+struct Metrics {
+	enable: Option<bool>,
+	port: Option<u16>,
+	interface: Option<String>,
+}
+
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
 struct Mining {
 	author: Option<String>,
 	force_sealing: Option<bool>,
 	reseal_on_txs: Option<String>,
 	reseal_min_period: Option<u64>,
+	reseal_max_period: Option<u64>,
 	work_queue_size: Option<usize>,
 	tx_gas_limit: Option<String>,
 	tx_time_limit: Option<u64>,
@@ -385,13 +546,20 @@ struct Mining {
 	tx_queue_strategy: Option<String>,
 	tx_queue_ban_count: Option<u16>,
 	tx_queue_ban_time: Option<u16>,
+	tx_queue_max_future_age: Option<u64>,
 	remove_solved: Option<bool>,
 	notify_work: Option<Vec<String>>,
+	ethash_cache_dir: Option<String>,
+	ethash_cache_epochs: Option<u64>,
+	ethash_pregenerate: Option<bool>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
 struct Footprint {
+	preset: Option<String>,
 	tracing: Option<String>,
+	tracing_addresses: Option<Vec<String>>,
+	tracing_history: Option<u64>,
 	pruning: Option<String>,
 	pruning_history: Option<u64>,
 	fast_and_loose: Option<bool>,
@@ -401,24 +569,37 @@ struct Footprint {
 	cache_size_queue: Option<u32>,
 	cache_size_state: Option<u32>,
 	db_compaction: Option<String>,
+	db_compaction_style: Option<String>,
+	db_wal_size: Option<u32>,
+	db_block_cache_size: Option<u32>,
+	db_commit_batch: Option<usize>,
 	fat_db: Option<String>,
+	sender_index: Option<bool>,
+	scale_verifiers: Option<bool>,
+	num_verifiers: Option<usize>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
 struct Snapshots {
 	disable_periodic: Option<bool>,
+	verify_restored_state: Option<bool>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
 struct VM {
 	jit: Option<bool>,
+	parallel_execution: Option<bool>,
+	pipeline_state_root: Option<bool>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
 struct Misc {
 	logging: Option<String>,
 	log_file: Option<String>,
+	log_file_max_size: Option<u64>,
+	log_file_max_age: Option<u64>,
 	color: Option<bool>,
+	ntp_server: Option<String>,
 }
 
 #[cfg(test)]
@@ -473,6 +654,28 @@ mod tests {
 		assert_eq!(args.flag_pruning_history, 128);
 	}
 
+	#[test]
+	fn should_apply_footprint_preset_beneath_explicit_options() {
+		let mut config = Config::default();
+		let mut footprint = Footprint::default();
+		footprint.cache_size_db = Some(256);
+		config.footprint = Some(footprint);
+
+		// when
+		let args = Args::parse_with_config(&["parity", "--footprint-preset", "archive", "--tracing", "off"], config).unwrap();
+
+		// then
+		assert_eq!(args.flag_pruning, "archive".to_owned());
+		assert_eq!(args.flag_fat_db, "on".to_owned());
+		assert_eq!(args.flag_tracing, "off".to_owned());
+		assert_eq!(args.flag_cache_size_db, 256);
+	}
+
+	#[test]
+	fn should_reject_unknown_footprint_preset() {
+		assert!(Args::parse_without_config(&["parity", "--footprint-preset", "huge"]).is_err());
+	}
+
 	#[test]
 	fn should_parse_full_config() {
 		// given
@@ -490,6 +693,10 @@ mod tests {
 			cmd_new: false,
 			cmd_list: false,
 			cmd_export: false,
+			cmd_sign_transaction: false,
+			cmd_node_key: false,
+			cmd_show: false,
+			cmd_rotate: false,
 			cmd_state: false,
 			cmd_blocks: false,
 			cmd_import: false,
@@ -500,12 +707,20 @@ mod tests {
 			cmd_ui: false,
 			cmd_tools: false,
 			cmd_hash: false,
+			cmd_db: false,
+			cmd_convert_pruning: false,
+			cmd_check: false,
 
 			// Arguments
 			arg_pid_file: "".into(),
 			arg_file: None,
 			arg_path: vec![],
 
+			// -- Account backup
+			flag_all: false,
+			flag_bundle: false,
+			flag_repair: false,
+
 			// -- Operating Options
 			flag_mode: "last".into(),
 			flag_mode_timeout: 300u64,
@@ -514,11 +729,17 @@ mod tests {
 			flag_db_path: "$HOME/.parity".into(),
 			flag_keys_path: "$HOME/.parity/keys".into(),
 			flag_identity: "".into(),
+			flag_auto_update: "critical".into(),
+			flag_shutdown_timeout: 60u64,
+			flag_max_reorg_depth: None,
 
 			// -- Account Options
 			flag_unlock: Some("0xdeadbeefcafe0000000000000000000000000000".into()),
 			flag_password: vec!["~/.safe/password.file".into()],
 			flag_keys_iterations: 10240u32,
+			flag_min_phrase_entropy: 80u32,
+			flag_private_tx_account: None,
+			flag_private_tx_validators: None,
 
 			flag_force_ui: false,
 			flag_no_ui: false,
@@ -543,6 +764,17 @@ mod tests {
 			flag_reserved_peers: Some("./path_to_file".into()),
 			flag_reserved_only: false,
 			flag_no_ancient_blocks: false,
+			flag_tx_diffusion: false,
+			flag_serve_light: false,
+			flag_light_buffer_limit: 50_000_000u64,
+			flag_light_buffer_recharge: 1_000_000u64,
+			flag_session_rekey_interval: Some(3600u64),
+			flag_listen_addresses: Some("[::1]:30304".into()),
+			flag_prefer_ipv6: false,
+			flag_grace_period: 3600u64,
+			flag_proxy: Some("socks5://127.0.0.1:9050".into()),
+			flag_max_peers_per_ip: Some(2u32),
+			flag_max_peers_per_subnet: Some(8u32),
 
 			// -- API and Console Options
 			// RPC
@@ -552,12 +784,24 @@ mod tests {
 			flag_jsonrpc_cors: Some("null".into()),
 			flag_jsonrpc_apis: "web3,eth,net,parity,traces,rpc".into(),
 			flag_jsonrpc_hosts: "none".into(),
+			flag_public_node: false,
+			flag_jsonrpc_log: false,
+			flag_jsonrpc_slow_query_ms: Some(1000),
+			flag_rpc_gas_cap: Some("50000000".into()),
 
 			// IPC
 			flag_no_ipc: false,
 			flag_ipc_path: "$HOME/.parity/jsonrpc.ipc".into(),
 			flag_ipc_apis: "web3,eth,net,parity,parity_accounts,personal,traces,rpc".into(),
 
+			// WEBSOCKETS
+			flag_no_ws: false,
+			flag_ws_port: 8546u16,
+			flag_ws_interface: "local".into(),
+			flag_ws_apis: "web3,eth,net,parity,traces,rpc".into(),
+			flag_ws_origins: "none".into(),
+			flag_ws_max_pending_requests: 64usize,
+
 			// DAPPS
 			flag_no_dapps: false,
 			flag_dapps_port: 8080u16,
@@ -567,11 +811,17 @@ mod tests {
 			flag_dapps_user: Some("test_user".into()),
 			flag_dapps_pass: Some("test_pass".into()),
 
+			// METRICS
+			flag_metrics: false,
+			flag_metrics_port: 9615u16,
+			flag_metrics_interface: "local".into(),
+
 			// -- Sealing/Mining Options
 			flag_author: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
 			flag_force_sealing: true,
 			flag_reseal_on_txs: "all".into(),
 			flag_reseal_min_period: 4000u64,
+			flag_reseal_max_period: 60000u64,
 			flag_work_queue_size: 20usize,
 			flag_tx_gas_limit: Some("6283184".into()),
 			flag_tx_time_limit: Some(100u64),
@@ -587,11 +837,18 @@ mod tests {
 			flag_tx_queue_strategy: "gas_factor".into(),
 			flag_tx_queue_ban_count: 1u16,
 			flag_tx_queue_ban_time: 180u16,
+			flag_tx_queue_max_future_age: Some(3600u64),
 			flag_remove_solved: false,
 			flag_notify_work: Some("http://localhost:3001".into()),
+			flag_ethash_cache_dir: "$HOME/.ethash/light".into(),
+			flag_ethash_cache_epochs: 3u64,
+			flag_ethash_pregenerate: true,
 
 			// -- Footprint Options
+			flag_footprint_preset: Some("medium".into()),
 			flag_tracing: "auto".into(),
+			flag_tracing_addresses: Some("0xdeadbeefcafe0000000000000000000000000000".into()),
+			flag_tracing_history: Some(100000u64),
 			flag_pruning: "auto".into(),
 			flag_pruning_history: 64u64,
 			flag_cache_size_db: 64u32,
@@ -601,7 +858,14 @@ mod tests {
 			flag_cache_size: Some(128),
 			flag_fast_and_loose: false,
 			flag_db_compaction: "ssd".into(),
+			flag_db_compaction_style: "universal".into(),
+			flag_db_wal_size: 64u32,
+			flag_db_block_cache_size: 4u32,
+			flag_db_commit_batch: 32usize,
 			flag_fat_db: "auto".into(),
+			flag_sender_index: false,
+			flag_scale_verifiers: true,
+			flag_num_verifiers: Some(6),
 
 			// -- Import/Export Options
 			flag_from: "1".into(),
@@ -616,9 +880,12 @@ mod tests {
 			// -- Snapshot Optons
 			flag_at: "latest".into(),
 			flag_no_periodic_snapshot: false,
+			flag_verify_restored_state: false,
 
 			// -- Virtual Machine Options
 			flag_jitvm: false,
+			flag_parallel_execution: false,
+			flag_pipeline_state_root: false,
 
 			// -- Legacy Options
 			flag_geth: false,
@@ -652,8 +919,12 @@ mod tests {
 			flag_config: "$HOME/.parity/config.toml".into(),
 			flag_logging: Some("own_tx=trace".into()),
 			flag_log_file: Some("/var/log/parity.log".into()),
+			flag_log_file_max_size: 100u64,
+			flag_log_file_max_age: 0u64,
 			flag_no_color: false,
+			flag_ntp_server: "pool.ntp.org:123".into(),
 			flag_no_config: false,
+			flag_print_config: false,
 		});
 	}
 
@@ -684,11 +955,17 @@ mod tests {
 				db_path: None,
 				keys_path: None,
 				identity: None,
+				auto_update: None,
+				shutdown_timeout: None,
+				max_reorg_depth: None,
 			}),
 			account: Some(Account {
 				unlock: Some(vec!["0x1".into(), "0x2".into(), "0x3".into()]),
 				password: Some(vec!["passwdfile path".into()]),
 				keys_iterations: None,
+				min_phrase_entropy: None,
+				private_tx_account: None,
+				private_tx_validators: None,
 			}),
 			ui: Some(Ui {
 				force: None,
@@ -713,6 +990,18 @@ mod tests {
 				node_key: None,
 				reserved_peers: Some("./path/to/reserved_peers".into()),
 				reserved_only: Some(true),
+				download_old_blocks: None,
+				tx_diffusion: None,
+				serve_light: None,
+				light_buffer_limit: None,
+				light_buffer_recharge: None,
+				session_rekey_interval: None,
+				listen_addresses: None,
+				prefer_ipv6: None,
+				grace_period: None,
+				proxy: None,
+				max_peers_per_ip: None,
+				max_peers_per_subnet: None,
 			}),
 			rpc: Some(Rpc {
 				disable: Some(true),
@@ -721,12 +1010,17 @@ mod tests {
 				cors: None,
 				apis: None,
 				hosts: None,
+				public_node: None,
+				log: None,
+				slow_query_ms: None,
+				gas_cap: None,
 			}),
 			ipc: Some(Ipc {
 				disable: None,
 				path: None,
 				apis: Some(vec!["rpc".into(), "eth".into()]),
 			}),
+			websockets: None,
 			dapps: Some(Dapps {
 				disable: None,
 				port: Some(8080),
@@ -736,11 +1030,13 @@ mod tests {
 				user: Some("username".into()),
 				pass: Some("password".into())
 			}),
+			metrics: None,
 			mining: Some(Mining {
 				author: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
 				force_sealing: Some(true),
 				reseal_on_txs: Some("all".into()),
 				reseal_min_period: Some(4000),
+				reseal_max_period: None,
 				work_queue_size: None,
 				relay_set: None,
 				usd_per_tx: None,
@@ -753,14 +1049,21 @@ mod tests {
 				tx_queue_strategy: None,
 				tx_queue_ban_count: None,
 				tx_queue_ban_time: None,
+				tx_queue_max_future_age: None,
 				tx_gas_limit: None,
 				tx_time_limit: None,
 				extra_data: None,
 				remove_solved: None,
 				notify_work: None,
+				ethash_cache_dir: None,
+				ethash_cache_epochs: None,
+				ethash_pregenerate: None,
 			}),
 			footprint: Some(Footprint {
+				preset: None,
 				tracing: Some("on".into()),
+				tracing_addresses: None,
+				tracing_history: None,
 				pruning: Some("fast".into()),
 				pruning_history: Some(64),
 				fast_and_loose: None,
@@ -770,18 +1073,31 @@ mod tests {
 				cache_size_queue: Some(100),
 				cache_size_state: Some(25),
 				db_compaction: Some("ssd".into()),
+				db_compaction_style: None,
+				db_wal_size: None,
+				db_block_cache_size: None,
+				db_commit_batch: None,
 				fat_db: Some("off".into()),
+				sender_index: None,
+				scale_verifiers: None,
+				num_verifiers: None,
 			}),
 			snapshots: Some(Snapshots {
 				disable_periodic: Some(true),
+				verify_restored_state: None,
 			}),
 			vm: Some(VM {
 				jit: Some(false),
+				parallel_execution: None,
+				pipeline_state_root: None,
 			}),
 			misc: Some(Misc {
 				logging: Some("own_tx=trace".into()),
 				log_file: Some("/var/log/parity.log".into()),
+				log_file_max_size: None,
+				log_file_max_age: None,
 				color: Some(true),
+				ntp_server: None,
 			})
 		});
 	}
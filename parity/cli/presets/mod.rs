@@ -0,0 +1,41 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Footprint presets.
+//!
+//! Each preset is a partial config file which is applied beneath the user's
+//! config file and command line, so any option set explicitly still wins.
+
+/// Returns the config file contents for given preset name.
+pub fn preset_config_string(name: &str) -> Result<&'static str, String> {
+	match name {
+		"light" => Ok(include_str!("./config.light.toml")),
+		"medium" => Ok(include_str!("./config.medium.toml")),
+		"archive" => Ok(include_str!("./config.archive.toml")),
+		_ => Err(format!("Invalid footprint preset: {}. Expected one of: light, medium, archive.", name)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::preset_config_string;
+
+	#[test]
+	fn should_reject_unknown_preset() {
+		assert!(preset_config_string("light").is_ok());
+		assert!(preset_config_string("huge").is_err());
+	}
+}
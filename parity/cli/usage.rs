@@ -59,6 +59,7 @@ macro_rules! usage {
 			Decode(toml::DecodeError),
 			Config(String, io::Error),
 			UnknownFields(String),
+			Preset(String),
 		}
 
 		impl ArgsError {
@@ -86,6 +87,10 @@ macro_rules! usage {
 						println_stderr!("You have some extra fields in your config file:");
 						println_stderr!("{}", fields);
 						process::exit(2)
+					},
+					ArgsError::Preset(e) => {
+						println_stderr!("{}", e);
+						process::exit(2)
 					}
 				}
 			}
@@ -141,10 +146,11 @@ macro_rules! usage {
 
 				// Skip loading config file if no_config flag is specified
 				if raw_args.flag_no_config {
-					return Ok(raw_args.into_args(Config::default()));
+					let preset = try!(Self::preset_config(&raw_args, &Config::default()));
+					return Ok(raw_args.into_args(Config::default(), preset));
 				}
 
-				let config_file = raw_args.flag_config.clone().unwrap_or_else(|| raw_args.clone().into_args(Config::default()).flag_config);
+				let config_file = raw_args.flag_config.clone().unwrap_or_else(|| raw_args.clone().into_args(Config::default(), Config::default()).flag_config);
 				let config_file = replace_home(&config_file);
 				let config = match (fs::File::open(&config_file), raw_args.flag_config.is_some()) {
 					// Load config file
@@ -162,7 +168,22 @@ macro_rules! usage {
 					},
 				};
 
-				Ok(raw_args.into_args(config))
+				let preset = try!(Self::preset_config(&raw_args, &config));
+				Ok(raw_args.into_args(config, preset))
+			}
+
+			/// Loads footprint preset selected on the command line or in the config file.
+			fn preset_config(raw_args: &RawArgs, config: &Config) -> Result<Config, ArgsError> {
+				let preset = raw_args.flag_footprint_preset.clone()
+					.or_else(|| config.footprint.as_ref().map(|f| f.preset.clone()))
+					.and_then(|p| p);
+				match preset {
+					Some(name) => {
+						let preset = try!(presets::preset_config_string(&name).map_err(ArgsError::Preset));
+						Self::parse_config(preset)
+					},
+					None => Ok(Config::default()),
+				}
 			}
 
 			#[cfg(test)]
@@ -172,7 +193,9 @@ macro_rules! usage {
 
 			#[cfg(test)]
 			fn parse_with_config<S: AsRef<str>>(command: &[S], config: Config) -> Result<Self, ArgsError> {
-				Ok(try!(RawArgs::parse(command)).into_args(config))
+				let raw_args = try!(RawArgs::parse(command));
+				let preset = try!(Self::preset_config(&raw_args, &config));
+				Ok(raw_args.into_args(config, preset))
 			}
 
 			fn parse_config(config: &str) -> Result<Config, ArgsError> {
@@ -195,16 +218,25 @@ macro_rules! usage {
 			pub fn print_version() -> String {
 				format!(include_str!("./version.txt"), version())
 			}
+
+			/// Effective settings after merging command line, config file and defaults.
+			pub fn print_config(&self) -> String {
+				let mut lines = Vec::new();
+				$(
+					lines.push(format!("--{} = {:?}", stringify!($field)["flag_".len()..].replace('_', "-"), self.$field));
+				)*
+				lines.join("\n")
+			}
 		}
 
 		impl RawArgs {
-			fn into_args(self, config: Config) -> Args {
+			fn into_args(self, config: Config, preset: Config) -> Args {
 				let mut args = Args::default();
 				$(
 					args.$field_a = self.$field_a;
 				)*
 				$(
-					args.$field = self.$field.or_else(|| $from_config(&config)).unwrap_or_else(|| $default.into());
+					args.$field = self.$field.or_else(|| $from_config(&config)).or_else(|| $from_config(&preset)).unwrap_or_else(|| $default.into());
 				)*
 				args
 			}
@@ -17,36 +17,44 @@
 use std::time::Duration;
 use std::io::Read;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::cmp::max;
 use cli::{Args, ArgsError};
 use util::{Hashable, U256, Uint, Bytes, version_data, Secret, Address};
 use util::log::Colour;
-use ethsync::{NetworkConfiguration, is_valid_node_url, AllowIP};
-use ethcore::client::VMType;
+use ethsync::{NetworkConfiguration, is_valid_node_url, AllowIP, Proxy};
+use ethcore::client::{VMType, VerifierSettings};
 use ethcore::miner::{MinerOptions, Banning};
+use ethcore::ethereum::PowCacheOptions;
+use ethcore::private_transactions::ProviderConfig as PrivateTxConfig;
 
-use rpc::{IpcConfiguration, HttpConfiguration};
+use rpc::{IpcConfiguration, HttpConfiguration, WsConfiguration};
+use rpc_apis::ApiSet;
 use ethcore_rpc::NetworkSettings;
 use cache::CacheConfig;
 use helpers::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_price, replace_home,
-geth_ipc_path, parity_ipc_path, to_bootnodes, to_addresses, to_address, to_gas_limit, to_queue_strategy};
+geth_ipc_path, parity_ipc_path, to_bootnodes, to_addresses, to_address, to_public_keys, to_gas_limit, to_queue_strategy};
 use params::{ResealPolicy, AccountsConfig, GasPricerConfig, MinerExtras};
 use ethcore_logger::Config as LogConfig;
 use dir::Directories;
 use dapps::Configuration as DappsConfiguration;
+use metrics::Configuration as MetricsConfiguration;
+use updater::{UpdatePolicy, UpdateFilter};
 use signer::{Configuration as SignerConfiguration};
 use run::RunCmd;
-use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, ExportState, DataFormat};
+use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, ExportState, ConvertPruning, CheckDatabase, DataFormat};
 use presale::ImportWallet;
-use account::{AccountCmd, NewAccount, ImportAccounts, ImportFromGethAccounts};
+use account::{AccountCmd, NewAccount, ImportAccounts, ImportFromGethAccounts, ExportAccounts, ImportAccountsBundle, SignTransaction};
 use snapshot::{self, SnapshotCommand};
+use node_key::{NodeKeyCmd, ShowNodeKey, ExportNodeKey, ImportNodeKey, RotateNodeKey};
 
 #[derive(Debug, PartialEq)]
 pub enum Cmd {
 	Run(RunCmd),
 	Version,
+	PrintConfig(String),
 	Account(AccountCmd),
+	NodeKey(NodeKeyCmd),
 	ImportPresaleWallet(ImportWallet),
 	Blockchain(BlockchainCmd),
 	SignerToken(SignerConfiguration),
@@ -85,6 +93,7 @@ impl Configuration {
 		let logger_config = self.logger_config();
 		let http_conf = try!(self.http_config());
 		let ipc_conf = try!(self.ipc_config());
+		let ws_conf = try!(self.ws_config());
 		let net_conf = try!(self.net_config());
 		let network_id = self.network_id();
 		let cache_config = self.cache_config();
@@ -98,10 +107,14 @@ impl Configuration {
 		let ui_address = self.ui_port().map(|port| (self.ui_interface(), port));
 		let dapps_conf = self.dapps_config();
 		let signer_conf = self.signer_config();
+		let metrics_conf = self.metrics_config();
+		let update_policy = try!(self.update_policy());
 		let format = try!(self.format());
 
 		let cmd = if self.args.flag_version {
 			Cmd::Version
+		} else if self.args.flag_print_config {
+			Cmd::PrintConfig(self.args.print_config())
 		} else if self.args.cmd_signer && self.args.cmd_new_token {
 			Cmd::SignerToken(signer_conf)
 		} else if self.args.cmd_tools && self.args.cmd_hash {
@@ -116,6 +129,25 @@ impl Configuration {
 				AccountCmd::New(new_acc)
 			} else if self.args.cmd_list {
 				AccountCmd::List(dirs.keys)
+			} else if self.args.cmd_export && self.args.flag_all {
+				AccountCmd::Export(ExportAccounts {
+					iterations: self.args.flag_keys_iterations,
+					path: dirs.keys,
+					file: self.args.arg_file.clone().expect("<file> is required by usage; qed"),
+					password_file: self.args.flag_password.first().cloned(),
+				})
+			} else if self.args.cmd_sign_transaction {
+				AccountCmd::SignTransaction(SignTransaction {
+					path: dirs.keys,
+					file: self.args.arg_file.clone().expect("<file> is required by usage; qed"),
+					password_file: self.args.flag_password.first().cloned(),
+				})
+			} else if self.args.cmd_import && self.args.flag_bundle {
+				AccountCmd::ImportBundle(ImportAccountsBundle {
+					file: self.args.arg_file.clone().expect("<file> is required by usage; qed"),
+					to: dirs.keys,
+					password_file: self.args.flag_password.first().cloned(),
+				})
 			} else if self.args.cmd_import {
 				let import_acc = ImportAccounts {
 					from: self.args.arg_path.clone(),
@@ -126,6 +158,34 @@ impl Configuration {
 				unreachable!();
 			};
 			Cmd::Account(account_cmd)
+		} else if self.args.cmd_node_key {
+			let path = try!(self.net_config()).config_path.expect("config_path is always set by net_config; qed");
+			let node_key_cmd = if self.args.cmd_show {
+				let (listen, public) = try!(self.net_addresses());
+				NodeKeyCmd::Show(ShowNodeKey {
+					path: path,
+					use_secret: self.args.flag_node_key.as_ref().map(|s| s.parse::<Secret>().unwrap_or_else(|_| s.sha3())),
+					address: public.or(listen).expect("listen address is always set; qed"),
+				})
+			} else if self.args.cmd_export {
+				NodeKeyCmd::Export(ExportNodeKey {
+					path: path,
+					file: self.args.arg_file.clone().expect("<file> is required by usage; qed"),
+				})
+			} else if self.args.cmd_import {
+				NodeKeyCmd::Import(ImportNodeKey {
+					path: path,
+					file: self.args.arg_file.clone().expect("<file> is required by usage; qed"),
+				})
+			} else if self.args.cmd_rotate {
+				NodeKeyCmd::Rotate(RotateNodeKey {
+					path: path,
+					grace_period: self.args.flag_grace_period,
+				})
+			} else {
+				unreachable!();
+			};
+			Cmd::NodeKey(node_key_cmd)
 		} else if self.args.flag_import_geth_keys {
         	let account_cmd = AccountCmd::ImportFromGeth(
 				ImportFromGethAccounts {
@@ -202,6 +262,25 @@ impl Configuration {
 			} else {
 				unreachable!();
 			}
+		} else if self.args.cmd_db && self.args.cmd_convert_pruning {
+			let convert_cmd = ConvertPruning {
+				spec: spec,
+				dirs: dirs,
+				pruning: pruning,
+				compaction: compaction,
+				wal: wal,
+			};
+			Cmd::Blockchain(BlockchainCmd::ConvertPruning(convert_cmd))
+		} else if self.args.cmd_db && self.args.cmd_check {
+			let check_cmd = CheckDatabase {
+				spec: spec,
+				dirs: dirs,
+				pruning: pruning,
+				compaction: compaction,
+				wal: wal,
+				repair: self.args.flag_repair,
+			};
+			Cmd::Blockchain(BlockchainCmd::Check(check_cmd))
 		} else if self.args.cmd_snapshot {
 			let snapshot_cmd = SnapshotCommand {
 				cache_config: cache_config,
@@ -252,6 +331,13 @@ impl Configuration {
 				miner_options: miner_options,
 				http_conf: http_conf,
 				ipc_conf: ipc_conf,
+				ws_conf: ws_conf,
+				rpc_log: self.args.flag_jsonrpc_log,
+				rpc_slow_query: self.args.flag_jsonrpc_slow_query_ms.map(Duration::from_millis),
+				rpc_gas_cap: match self.args.flag_rpc_gas_cap {
+					Some(ref cap) => Some(try!(to_u256(cap))),
+					None => None,
+				},
 				net_conf: net_conf,
 				network_id: network_id,
 				acc_conf: try!(self.accounts_config()),
@@ -261,20 +347,42 @@ impl Configuration {
 				tracing: tracing,
 				fat_db: fat_db,
 				compaction: compaction,
+				compaction_style: try!(self.args.flag_db_compaction_style.parse()),
 				wal: wal,
+				wal_size: self.args.flag_db_wal_size as usize,
+				block_cache_size: self.args.flag_db_block_cache_size as usize,
+				commit_batch: self.args.flag_db_commit_batch,
+				sender_index: self.args.flag_sender_index,
+				tracing_addresses: try!(to_addresses(&self.args.flag_tracing_addresses)),
+				tracing_history: self.args.flag_tracing_history,
+				verifier_settings: self.verifier_settings(),
+				pow_cache_options: self.pow_cache_options(),
 				vm_type: vm_type,
+				parallel_execution: self.args.flag_parallel_execution,
+				pipeline_state_root: self.args.flag_pipeline_state_root,
 				warp_sync: warp_sync,
 				geth_compatibility: geth_compatibility,
 				ui_address: ui_address,
 				net_settings: self.network_settings(),
 				dapps_conf: dapps_conf,
 				signer_conf: signer_conf,
+				metrics_conf: metrics_conf,
+				update_policy: update_policy,
+				shutdown_timeout: Duration::from_secs(self.args.flag_shutdown_timeout),
+				ntp_server: self.args.flag_ntp_server.clone(),
 				ui: self.args.cmd_ui,
 				name: self.args.flag_identity,
 				custom_bootnodes: self.args.flag_bootnodes.is_some(),
 				no_periodic_snapshot: self.args.flag_no_periodic_snapshot,
+				verify_restored_state: self.args.flag_verify_restored_state,
+				max_reorg_depth: self.args.flag_max_reorg_depth,
+				private_tx: try!(self.private_tx_config()),
 				check_seal: !self.args.flag_no_seal_check,
 				download_old_blocks: !self.args.flag_no_ancient_blocks,
+				tx_diffusion: self.args.flag_tx_diffusion,
+				serve_light: self.args.flag_serve_light,
+				light_buffer_limit: self.args.flag_light_buffer_limit,
+				light_buffer_recharge: self.args.flag_light_buffer_recharge,
 			};
 			Cmd::Run(run_cmd)
 		};
@@ -329,10 +437,24 @@ impl Configuration {
 	}
 
 	fn logger_config(&self) -> LogConfig {
+		// daemon has no terminal attached, so log to a file by default
+		let file = match self.args.flag_log_file {
+			None if self.args.cmd_daemon => Some(Path::new(&self.directories().db).join("parity.log").to_string_lossy().into_owned()),
+			ref file => file.clone(),
+		};
+
 		LogConfig {
 			mode: self.args.flag_logging.clone(),
 			color: !self.args.flag_no_color && !cfg!(windows),
-			file: self.args.flag_log_file.clone(),
+			file: file,
+			file_max_size: match self.args.flag_log_file_max_size {
+				0 => None,
+				mb => Some(mb * 1024 * 1024),
+			},
+			file_max_age: match self.args.flag_log_file_max_age {
+				0 => None,
+				days => Some(Duration::from_secs(days * 24 * 60 * 60)),
+			},
 		}
 	}
 
@@ -380,11 +502,23 @@ impl Configuration {
 			testnet: self.args.flag_testnet,
 			password_files: self.args.flag_password.clone(),
 			unlocked_accounts: try!(to_addresses(&self.args.flag_unlock)),
+			min_phrase_entropy: self.args.flag_min_phrase_entropy,
 		};
 
 		Ok(cfg)
 	}
 
+	fn private_tx_config(&self) -> Result<Option<PrivateTxConfig>, String> {
+		if self.args.flag_private_tx_account.is_none() {
+			return Ok(None);
+		}
+
+		Ok(Some(PrivateTxConfig {
+			account: try!(to_address(self.args.flag_private_tx_account.clone())),
+			validators: try!(to_public_keys(&self.args.flag_private_tx_validators)),
+		}))
+	}
+
 	fn miner_options(&self) -> Result<MinerOptions, String> {
 		let reseal = try!(self.args.flag_reseal_on_txs.parse::<ResealPolicy>());
 
@@ -402,6 +536,7 @@ impl Configuration {
 			tx_queue_strategy: try!(to_queue_strategy(&self.args.flag_tx_queue_strategy)),
 			pending_set: try!(to_pending_set(&self.args.flag_relay_set)),
 			reseal_min_period: Duration::from_millis(self.args.flag_reseal_min_period),
+			reseal_max_period: Duration::from_millis(self.args.flag_reseal_max_period),
 			work_queue_size: self.args.flag_work_queue_size,
 			enable_resubmission: !self.args.flag_remove_solved,
 			tx_queue_banning: match self.args.flag_tx_time_limit {
@@ -411,7 +546,8 @@ impl Configuration {
 					ban_duration: Duration::from_secs(self.args.flag_tx_queue_ban_time as u64),
 				},
 				None => Banning::Disabled,
-			}
+			},
+			tx_queue_max_future_age: self.args.flag_tx_queue_max_future_age.map(Duration::from_secs),
 		};
 
 		Ok(options)
@@ -436,6 +572,19 @@ impl Configuration {
 			user: self.args.flag_dapps_user.clone(),
 			pass: self.args.flag_dapps_pass.clone(),
 			dapps_path: self.directories().dapps,
+			public_node: self.args.flag_public_node,
+		}
+	}
+
+	fn update_policy(&self) -> Result<UpdatePolicy, String> {
+		self.args.flag_auto_update.parse::<UpdateFilter>().map(Into::into)
+	}
+
+	fn metrics_config(&self) -> MetricsConfiguration {
+		MetricsConfiguration {
+			enabled: self.args.flag_metrics,
+			interface: self.metrics_interface(),
+			port: self.args.flag_metrics_port,
 		}
 	}
 
@@ -505,6 +654,27 @@ impl Configuration {
 		Ok((listen_address, public_address))
 	}
 
+	fn extra_listen_addresses(&self) -> Result<Vec<SocketAddr>, String> {
+		match self.args.flag_listen_addresses {
+			Some(ref addresses) => addresses.split(',')
+				.map(str::trim)
+				.filter(|s| !s.is_empty())
+				.map(|s| s.parse().map_err(|_| format!("Invalid address given with `--listen-addresses`: {}", s)))
+				.collect(),
+			None => Ok(Vec::new()),
+		}
+	}
+
+	fn proxy(&self) -> Result<Option<String>, String> {
+		match self.args.flag_proxy {
+			Some(ref proxy) => {
+				let parsed = try!(proxy.parse::<Proxy>().map_err(|_| format!("Invalid proxy given with `--proxy`: {}", proxy)));
+				Ok(Some(format!("{}", parsed)))
+			},
+			None => Ok(None),
+		}
+	}
+
 	fn net_config(&self) -> Result<NetworkConfiguration, String> {
 		let mut ret = NetworkConfiguration::new();
 		ret.nat_enabled = self.args.flag_nat == "any" || self.args.flag_nat == "upnp";
@@ -512,6 +682,8 @@ impl Configuration {
 		let (listen, public) = try!(self.net_addresses());
 		ret.listen_address = listen.map(|l| format!("{}", l));
 		ret.public_address = public.map(|p| format!("{}", p));
+		ret.extra_listen_addresses = try!(self.extra_listen_addresses()).into_iter().map(|a| format!("{}", a)).collect();
+		ret.prefer_ipv6 = self.args.flag_prefer_ipv6;
 		ret.use_secret = self.args.flag_node_key.as_ref().map(|s| s.parse::<Secret>().unwrap_or_else(|_| s.sha3()));
 		ret.discovery_enabled = !self.args.flag_no_discovery && !self.args.flag_nodiscover;
 		ret.max_peers = self.max_peers();
@@ -524,9 +696,30 @@ impl Configuration {
 		ret.config_path = Some(net_path.to_str().unwrap().to_owned());
 		ret.reserved_nodes = try!(self.init_reserved_nodes());
 		ret.allow_non_reserved = !self.args.flag_reserved_only;
+		ret.session_rekey_interval = self.args.flag_session_rekey_interval;
+		ret.proxy = try!(self.proxy());
+		ret.max_peers_per_ip = self.args.flag_max_peers_per_ip;
+		ret.max_peers_per_subnet = self.args.flag_max_peers_per_subnet;
 		Ok(ret)
 	}
 
+	fn pow_cache_options(&self) -> PowCacheOptions {
+		PowCacheOptions {
+			cache_dir: replace_home(&self.args.flag_ethash_cache_dir).into(),
+			keep_epochs: max(2, self.args.flag_ethash_cache_epochs),
+			pregenerate: self.args.flag_ethash_pregenerate,
+		}
+	}
+
+	fn verifier_settings(&self) -> VerifierSettings {
+		let mut settings = VerifierSettings::default();
+		settings.scale_verifiers = self.args.flag_scale_verifiers;
+		if let Some(num_verifiers) = self.args.flag_num_verifiers {
+			settings.num_verifiers = num_verifiers;
+		}
+		settings
+	}
+
 	fn network_id(&self) -> Option<usize> {
 		self.args.flag_network_id.or(self.args.flag_networkid)
 	}
@@ -587,11 +780,15 @@ impl Configuration {
 	}
 
 	fn http_config(&self) -> Result<HttpConfiguration, String> {
+		let apis: ApiSet = try!(self.rpc_apis().parse());
 		let conf = HttpConfiguration {
 			enabled: !self.args.flag_jsonrpc_off && !self.args.flag_no_jsonrpc,
 			interface: self.rpc_interface(),
 			port: self.args.flag_rpcport.unwrap_or(self.args.flag_jsonrpc_port),
-			apis: try!(self.rpc_apis().parse()),
+			apis: match self.args.flag_public_node {
+				true => ApiSet::PublicContext(apis.list_apis()),
+				false => apis,
+			},
 			hosts: self.rpc_hosts(),
 			cors: self.rpc_cors(),
 		};
@@ -599,6 +796,32 @@ impl Configuration {
 		Ok(conf)
 	}
 
+	fn ws_config(&self) -> Result<WsConfiguration, String> {
+		let apis: ApiSet = try!(self.args.flag_ws_apis.parse());
+		let conf = WsConfiguration {
+			enabled: !self.args.flag_no_ws,
+			interface: self.ws_interface(),
+			port: self.args.flag_ws_port,
+			apis: match self.args.flag_public_node {
+				true => ApiSet::PublicContext(apis.list_apis()),
+				false => apis,
+			},
+			origins: self.ws_origins(),
+			max_pending_requests: self.args.flag_ws_max_pending_requests,
+			signer_path: self.directories().signer,
+		};
+
+		Ok(conf)
+	}
+
+	fn ws_origins(&self) -> Vec<String> {
+		match self.args.flag_ws_origins.as_ref() {
+			"none" => Vec::new(),
+			"all" => vec!["*".into()],
+			origins => origins.split(',').map(Into::into).collect(),
+		}
+	}
+
 	fn network_settings(&self) -> NetworkSettings {
 		NetworkSettings {
 			name: self.args.flag_identity.clone(),
@@ -680,6 +903,14 @@ impl Configuration {
 		}.into()
 	}
 
+	fn ws_interface(&self) -> String {
+		match self.args.flag_ws_interface.as_str() {
+			"all" => "0.0.0.0",
+			"local" => "127.0.0.1",
+			x => x,
+		}.into()
+	}
+
 	fn dapps_interface(&self) -> String {
 		match self.args.flag_dapps_interface.as_str() {
 			"local" => "127.0.0.1",
@@ -687,6 +918,14 @@ impl Configuration {
 		}.into()
 	}
 
+	fn metrics_interface(&self) -> String {
+		match self.args.flag_metrics_interface.as_str() {
+			"all" => "0.0.0.0",
+			"local" => "127.0.0.1",
+			x => x,
+		}.into()
+	}
+
 	fn dapps_enabled(&self) -> bool {
 		!self.args.flag_dapps_off && !self.args.flag_no_dapps && cfg!(feature = "dapps")
 	}
@@ -709,17 +948,21 @@ mod tests {
 	use super::*;
 	use cli::Args;
 	use ethcore_rpc::NetworkSettings;
-	use ethcore::client::{VMType, BlockID};
+	use ethcore::client::{VMType, BlockID, VerifierSettings};
 	use ethcore::miner::{MinerOptions, PrioritizationStrategy};
 	use helpers::{replace_home, default_network_config};
 	use run::RunCmd;
+	use rpc_apis::{Api, ApiSet};
 	use signer::{Configuration as SignerConfiguration};
-	use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, DataFormat, ExportState};
+	use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, DataFormat, ExportState, ConvertPruning, CheckDatabase};
+	use params::Pruning;
+	use util::journaldb::Algorithm;
 	use presale::ImportWallet;
-	use account::{AccountCmd, NewAccount, ImportAccounts};
+	use account::{AccountCmd, NewAccount, ImportAccounts, ExportAccounts, ImportAccountsBundle};
 	use devtools::{RandomTempPath};
 	use std::io::Write;
 	use std::fs::{File, create_dir};
+	use std::time::Duration;
 
 	#[derive(Debug, PartialEq)]
 	struct TestPasswordReader(&'static str);
@@ -737,6 +980,19 @@ mod tests {
 		assert_eq!(conf.into_command().unwrap().cmd, Cmd::Version);
 	}
 
+	#[test]
+	fn test_command_print_config() {
+		let args = vec!["parity", "--print-config", "--no-ancient-blocks", "--port", "30304"];
+		let conf = parse(&args);
+		match conf.into_command().unwrap().cmd {
+			Cmd::PrintConfig(config) => {
+				assert!(config.contains("--no-ancient-blocks = true"));
+				assert!(config.contains("--port = 30304"));
+			},
+			_ => panic!("Should be PrintConfig"),
+		}
+	}
+
 	#[test]
 	fn test_command_account_new() {
 		let args = vec!["parity", "account", "new"];
@@ -767,6 +1023,58 @@ mod tests {
 		})));
 	}
 
+	#[test]
+	fn test_command_node_key() {
+		let args = vec!["parity", "node-key", "rotate", "--grace-period", "3600"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::NodeKey(NodeKeyCmd::Rotate(RotateNodeKey {
+			path: replace_home("$HOME/.parity/network"),
+			grace_period: 3600,
+		})));
+
+		let args = vec!["parity", "node-key", "show", "--nat", "extip:1.2.3.4"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::NodeKey(NodeKeyCmd::Show(ShowNodeKey {
+			path: replace_home("$HOME/.parity/network"),
+			use_secret: None,
+			address: "1.2.3.4:30303".parse().unwrap(),
+		})));
+	}
+
+	#[test]
+	fn test_command_account_export() {
+		let args = vec!["parity", "account", "export", "--all", "backup.json"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::Account(AccountCmd::Export(ExportAccounts {
+			iterations: 10240,
+			path: replace_home("$HOME/.parity/keys"),
+			file: "backup.json".into(),
+			password_file: None,
+		})));
+	}
+
+	#[test]
+	fn test_command_account_import_bundle() {
+		let args = vec!["parity", "account", "import", "--bundle", "backup.json", "--password", "pwd"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::Account(AccountCmd::ImportBundle(ImportAccountsBundle {
+			file: "backup.json".into(),
+			to: replace_home("$HOME/.parity/keys"),
+			password_file: Some("pwd".into()),
+		})));
+	}
+
+	#[test]
+	fn test_command_account_sign_transaction() {
+		let args = vec!["parity", "account", "sign-transaction", "tx.json", "--password", "pwd"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::Account(AccountCmd::SignTransaction(SignTransaction {
+			path: replace_home("$HOME/.parity/keys"),
+			file: "tx.json".into(),
+			password_file: Some("pwd".into()),
+		})));
+	}
+
 	#[test]
 	fn test_command_wallet_import() {
 		let args = vec!["parity", "wallet", "import", "my_wallet.json", "--password", "pwd"];
@@ -823,6 +1131,33 @@ mod tests {
 		})));
 	}
 
+	#[test]
+	fn test_command_convert_pruning() {
+		let args = vec!["parity", "db", "convert-pruning", "--pruning", "fast"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::Blockchain(BlockchainCmd::ConvertPruning(ConvertPruning {
+			spec: Default::default(),
+			dirs: Default::default(),
+			pruning: Pruning::Specific(Algorithm::OverlayRecent),
+			compaction: Default::default(),
+			wal: true,
+		})));
+	}
+
+	#[test]
+	fn test_command_db_check() {
+		let args = vec!["parity", "db", "check", "--repair"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap().cmd, Cmd::Blockchain(BlockchainCmd::Check(CheckDatabase {
+			spec: Default::default(),
+			dirs: Default::default(),
+			pruning: Default::default(),
+			compaction: Default::default(),
+			wal: true,
+			repair: true,
+		})));
+	}
+
 	#[test]
 	fn test_command_state_export() {
 		let args = vec!["parity", "export", "state", "state.json"];
@@ -898,6 +1233,10 @@ mod tests {
 			miner_options: Default::default(),
 			http_conf: Default::default(),
 			ipc_conf: Default::default(),
+			ws_conf: Default::default(),
+			rpc_log: false,
+			rpc_slow_query: None,
+			rpc_gas_cap: None,
 			net_conf: default_network_config(),
 			network_id: None,
 			warp_sync: false,
@@ -907,20 +1246,46 @@ mod tests {
 			mode: Default::default(),
 			tracing: Default::default(),
 			compaction: Default::default(),
+			compaction_style: Default::default(),
 			wal: true,
+			wal_size: 64,
+			block_cache_size: 2,
+			commit_batch: 0,
+			sender_index: false,
+			tracing_addresses: Vec::new(),
+			tracing_history: None,
+			verifier_settings: VerifierSettings { scale_verifiers: false, ..Default::default() },
+			pow_cache_options: PowCacheOptions {
+				cache_dir: replace_home("$HOME/.ethash/light").into(),
+				keep_epochs: 2,
+				pregenerate: false,
+			},
 			vm_type: Default::default(),
+			parallel_execution: false,
+			pipeline_state_root: false,
 			geth_compatibility: false,
 			ui_address: Some(("127.0.0.1".into(), 8180)),
 			net_settings: Default::default(),
 			dapps_conf: Default::default(),
 			signer_conf: Default::default(),
+			metrics_conf: Default::default(),
+			update_policy: UpdatePolicy::from(UpdateFilter::Critical),
+			shutdown_timeout: Duration::from_secs(60),
+			ntp_server: "pool.ntp.org:123".into(),
 			ui: false,
 			name: "".into(),
 			custom_bootnodes: false,
 			fat_db: Default::default(),
 			no_periodic_snapshot: false,
+			verify_restored_state: false,
+			max_reorg_depth: None,
+			private_tx: None,
 			check_seal: true,
 			download_old_blocks: true,
+			tx_diffusion: false,
+			serve_light: false,
+			light_buffer_limit: 50_000_000,
+			light_buffer_recharge: 1_000_000,
 		}));
 	}
 
@@ -945,6 +1310,38 @@ mod tests {
 		assert_eq!(conf3.miner_options().unwrap(), mining_options);
 	}
 
+	#[test]
+	fn should_parse_extra_listen_addresses() {
+		let conf = parse(&["parity", "--listen-addresses", "[::1]:30304, 127.0.0.1:30305", "--prefer-ipv6"]);
+		let net = conf.net_config().unwrap();
+		assert_eq!(net.extra_listen_addresses, vec!["[::1]:30304".to_owned(), "127.0.0.1:30305".to_owned()]);
+		assert!(net.prefer_ipv6);
+
+		let conf = parse(&["parity", "--listen-addresses", "localhost"]);
+		assert!(conf.net_config().is_err());
+	}
+
+	#[test]
+	fn should_parse_proxy() {
+		let conf = parse(&["parity", "--proxy", "127.0.0.1:9050"]);
+		assert_eq!(conf.net_config().unwrap().proxy, Some("socks5://127.0.0.1:9050".into()));
+
+		let conf = parse(&["parity", "--proxy", "http://10.0.0.1:3128"]);
+		assert_eq!(conf.net_config().unwrap().proxy, Some("http://10.0.0.1:3128".into()));
+
+		let conf = parse(&["parity", "--proxy", "proxy.local:1080"]);
+		assert!(conf.net_config().is_err());
+	}
+
+	#[test]
+	fn should_parse_peer_diversity_limits() {
+		let net = parse(&["parity"]).net_config().unwrap();
+		assert_eq!((net.max_peers_per_ip, net.max_peers_per_subnet), (None, None));
+
+		let net = parse(&["parity", "--max-peers-per-ip", "2", "--max-peers-per-subnet", "5"]).net_config().unwrap();
+		assert_eq!((net.max_peers_per_ip, net.max_peers_per_subnet), (Some(2), Some(5)));
+	}
+
 	#[test]
 	fn should_parse_network_settings() {
 		// given
@@ -1011,6 +1408,67 @@ mod tests {
 		assert_eq!(conf3.rpc_hosts(), Some(vec!["ethcore.io".into(), "something.io".into()]));
 	}
 
+	#[test]
+	fn should_parse_ws_origins() {
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--ws-origins", "all"]);
+		let conf2 = parse(&["parity", "--ws-origins", "http://*.ethcore.io,chrome-extension://*"]);
+
+		// then
+		assert_eq!(conf0.ws_origins(), Vec::<String>::new());
+		assert_eq!(conf1.ws_origins(), vec!["*".to_owned()]);
+		assert_eq!(conf2.ws_origins(), vec!["http://*.ethcore.io".to_owned(), "chrome-extension://*".to_owned()]);
+	}
+
+	#[test]
+	fn should_restrict_apis_on_public_node() {
+		// given
+		let expected = vec![Api::Web3, Api::Eth].into_iter().collect();
+
+		// when
+		let conf = parse(&["parity", "--public-node", "--jsonrpc-apis", "web3,eth,personal,parity_accounts"]);
+
+		// then
+		assert_eq!(conf.http_config().unwrap().apis, ApiSet::PublicContext(expected));
+		assert!(conf.dapps_config().public_node);
+		match conf.ws_config().unwrap().apis {
+			ApiSet::PublicContext(_) => {},
+			apis => panic!("Unexpected WebSockets APIs: {:?}", apis),
+		}
+	}
+
+	#[test]
+	fn should_parse_rpc_request_logging() {
+		// given
+
+		// when
+		let conf = parse(&["parity", "--jsonrpc-log", "--jsonrpc-slow-query-ms", "250"]);
+
+		// then
+		match conf.into_command().unwrap().cmd {
+			Cmd::Run(cmd) => {
+				assert_eq!(cmd.rpc_log, true);
+				assert_eq!(cmd.rpc_slow_query, Some(Duration::from_millis(250)));
+			},
+			_ => panic!("Should be Cmd::Run"),
+		}
+	}
+
+	#[test]
+	fn should_parse_rpc_gas_cap() {
+		// given
+
+		// when
+		let conf = parse(&["parity", "--rpc-gas-cap", "1000000"]);
+
+		// then
+		match conf.into_command().unwrap().cmd {
+			Cmd::Run(cmd) => assert_eq!(cmd.rpc_gas_cap, Some(1_000_000.into())),
+			_ => panic!("Should be Cmd::Run"),
+		}
+	}
+
 	#[test]
 	fn should_parse_dapps_hosts() {
 		// given
@@ -1028,6 +1486,63 @@ mod tests {
 		assert_eq!(conf3.dapps_hosts(), Some(vec!["ethcore.io".into(), "something.io".into()]));
 	}
 
+	#[test]
+	fn should_parse_metrics_configuration() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--metrics", "--metrics-port", "9000", "--metrics-interface", "all"]);
+
+		// then
+		assert_eq!(conf0.metrics_config(), MetricsConfiguration {
+			enabled: false,
+			interface: "127.0.0.1".into(),
+			port: 9615,
+		});
+		assert_eq!(conf1.metrics_config(), MetricsConfiguration {
+			enabled: true,
+			interface: "0.0.0.0".into(),
+			port: 9000,
+		});
+	}
+
+	#[test]
+	fn should_parse_update_policy() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--auto-update", "all"]);
+		let conf2 = parse(&["parity", "--auto-update", "none"]);
+		let conf3 = parse(&["parity", "--auto-update", "xyz"]);
+
+		// then
+		assert_eq!(conf0.update_policy(), Ok(UpdatePolicy { enable_downloading: true, filter: UpdateFilter::Critical }));
+		assert_eq!(conf1.update_policy(), Ok(UpdatePolicy { enable_downloading: true, filter: UpdateFilter::All }));
+		assert_eq!(conf2.update_policy(), Ok(UpdatePolicy { enable_downloading: false, filter: UpdateFilter::None }));
+		assert!(conf3.update_policy().is_err());
+	}
+
+	#[test]
+	fn should_log_to_rotated_file_in_daemon_mode() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity", "daemon", "pid", "--db-path", "/tmp/parity"]);
+		let conf1 = parse(&["parity", "daemon", "pid", "--log-file", "/var/log/parity.log", "--log-file-max-size", "0", "--log-file-max-age", "1"]);
+
+		// then
+		let log0 = conf0.logger_config();
+		assert_eq!(log0.file, Some("/tmp/parity/parity.log".into()));
+		assert_eq!(log0.file_max_size, Some(100 * 1024 * 1024));
+		assert_eq!(log0.file_max_age, None);
+		let log1 = conf1.logger_config();
+		assert_eq!(log1.file, Some("/var/log/parity.log".into()));
+		assert_eq!(log1.file_max_size, None);
+		assert_eq!(log1.file_max_age, Some(Duration::from_secs(24 * 60 * 60)));
+	}
+
 	#[test]
 	fn should_disable_signer_in_geth_compat() {
 		// given
@@ -1052,6 +1567,19 @@ mod tests {
 		assert_eq!(conf0.ui_enabled(), false);
 	}
 
+	#[test]
+	fn should_keep_at_least_two_ethash_cache_epochs() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity", "--ethash-cache-epochs", "1"]);
+		let conf1 = parse(&["parity", "--ethash-cache-epochs", "5"]);
+
+		// then
+		assert_eq!(conf0.pow_cache_options().keep_epochs, 2);
+		assert_eq!(conf1.pow_cache_options().keep_epochs, 5);
+	}
+
 	#[test]
 	fn should_parse_signer_configration() {
 		// given
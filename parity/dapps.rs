@@ -30,6 +30,8 @@ pub struct Configuration {
 	pub user: Option<String>,
 	pub pass: Option<String>,
 	pub dapps_path: String,
+	/// Serve the RPC with the restrictions of a public node.
+	pub public_node: bool,
 }
 
 impl Default for Configuration {
@@ -42,6 +44,7 @@ impl Default for Configuration {
 			user: None,
 			pass: None,
 			dapps_path: replace_home("$HOME/.parity/dapps"),
+			public_node: false,
 		}
 	}
 }
@@ -73,7 +76,12 @@ pub fn new(configuration: Configuration, deps: Dependencies) -> Result<Option<We
 		(username.to_owned(), password)
 	});
 
-	Ok(Some(try!(setup_dapps_server(deps, configuration.dapps_path, &addr, configuration.hosts, auth, signer_address))))
+	let apis = match configuration.public_node {
+		true => rpc_apis::ApiSet::PublicContext(rpc_apis::ApiSet::UnsafeContext.list_apis()),
+		false => rpc_apis::ApiSet::UnsafeContext,
+	};
+
+	Ok(Some(try!(setup_dapps_server(deps, configuration.dapps_path, &addr, configuration.hosts, auth, signer_address, apis))))
 }
 
 pub use self::server::WebappServer;
@@ -83,6 +91,7 @@ pub use self::server::setup_dapps_server;
 mod server {
 	use super::Dependencies;
 	use std::net::SocketAddr;
+	use rpc_apis::ApiSet;
 
 	pub struct WebappServer;
 	pub fn setup_dapps_server(
@@ -92,6 +101,7 @@ mod server {
 		_allowed_hosts: Option<Vec<String>>,
 		_auth: Option<(String, String)>,
 		_signer_address: Option<(String, u16)>,
+		_apis: ApiSet,
 	) -> Result<WebappServer, String> {
 		Err("Your Parity version has been compiled without WebApps support.".into())
 	}
@@ -103,14 +113,11 @@ mod server {
 	use std::sync::Arc;
 	use std::net::SocketAddr;
 	use std::io;
-	use util::{Bytes, Address, U256};
-
-	use ethcore::transaction::{Transaction, Action};
-	use ethcore::client::{Client, BlockChainClient, BlockID};
+	use ethcore::client::BlockChainClient;
 
 	use rpc_apis;
+	use registrar::Registrar;
 	use ethcore_rpc::is_major_importing;
-	use hash_fetch::urlhint::ContractClient;
 
 	pub use ethcore_dapps::Server as WebappServer;
 
@@ -121,19 +128,23 @@ mod server {
 		allowed_hosts: Option<Vec<String>>,
 		auth: Option<(String, String)>,
 		signer_address: Option<(String, u16)>,
+		apis: rpc_apis::ApiSet,
 	) -> Result<WebappServer, String> {
 		use ethcore_dapps as dapps;
 
 		let mut server = dapps::ServerBuilder::new(
 			dapps_path,
-			Arc::new(Registrar { client: deps.client.clone() })
+			Arc::new(Registrar::new(deps.client.clone()))
 		);
 		let sync = deps.sync.clone();
 		let client = deps.client.clone();
 		server.with_sync_status(Arc::new(move || is_major_importing(Some(sync.status().state), client.queue_info())));
+		let client = deps.client.clone();
+		server.with_activity(Arc::new(move || client.keep_alive()));
 		server.with_signer_address(signer_address);
+		server.with_access_policy(deps.apis.access_policy.clone());
 
-		let server = rpc_apis::setup_rpc(server, deps.apis.clone(), rpc_apis::ApiSet::UnsafeContext);
+		let server = rpc_apis::setup_rpc(server, deps.apis.clone(), apis);
 		let start_result = match auth {
 			None => {
 				server.start_unsecured_http(url, allowed_hosts)
@@ -157,36 +168,4 @@ mod server {
 			},
 		}
 	}
-
-	struct Registrar {
-		client: Arc<Client>,
-	}
-
-	impl ContractClient for Registrar {
-		fn registrar(&self) -> Result<Address, String> {
-			self.client.additional_params().get("registrar")
-				 .ok_or_else(|| "Registrar not defined.".into())
-				 .and_then(|registrar| {
-					 registrar.parse().map_err(|e| format!("Invalid registrar address: {:?}", e))
-				 })
-		}
-
-		fn call(&self, address: Address, data: Bytes) -> Result<Bytes, String> {
-			let from = Address::default();
-			let transaction = Transaction {
-				nonce: self.client.latest_nonce(&from),
-				action: Action::Call(address),
-				gas: U256::from(50_000_000),
-				gas_price: U256::default(),
-				value: U256::default(),
-				data: data,
-			}.fake_sign(from);
-
-			self.client.call(&transaction, BlockID::Latest, Default::default())
-				.map_err(|e| format!("{:?}", e))
-				.map(|executed| {
-					executed.output
-				})
-		}
-	}
 }
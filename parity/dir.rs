@@ -71,6 +71,13 @@ impl Directories {
 		dir.push("ipc");
 		dir
 	}
+
+	/// Path where downloaded updates are stored.
+	pub fn updates_path(&self) -> PathBuf {
+		let mut dir = Path::new(&self.db).to_path_buf();
+		dir.push("updates");
+		dir
+	}
 }
 
 #[derive(Debug, PartialEq)]
@@ -116,12 +123,26 @@ impl DatabaseDirectories {
 		dir
 	}
 
+	/// Get the path of the file holding the transaction queue between restarts.
+	pub fn transactions_path(&self) -> PathBuf {
+		let mut dir = self.fork_path();
+		dir.push("transactions.rlp");
+		dir
+	}
+
 	/// Get the path for the network directory.
 	pub fn network_path(&self) -> PathBuf {
 		let mut dir = self.fork_path();
 		dir.push("network");
 		dir
 	}
+
+	/// Get the path for the private transactions and states directory.
+	pub fn private_tx_path(&self) -> PathBuf {
+		let mut dir = self.fork_path();
+		dir.push("private");
+		dir
+	}
 }
 
 #[cfg(test)]
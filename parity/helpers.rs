@@ -18,7 +18,7 @@ use std::{io, env};
 use std::io::{Write, BufReader, BufRead};
 use std::time::Duration;
 use std::fs::File;
-use util::{clean_0x, U256, Uint, Address, path, CompactionProfile};
+use util::{clean_0x, U256, Uint, Address, H512, path, CompactionProfile};
 use util::journaldb::Algorithm;
 use ethcore::client::{Mode, BlockID, VMType, DatabaseCompactionProfile, ClientConfig, VerifierType};
 use ethcore::miner::{PendingSet, GasLimit, PrioritizationStrategy};
@@ -126,6 +126,15 @@ pub fn to_addresses(s: &Option<String>) -> Result<Vec<Address>, String> {
 	}
 }
 
+pub fn to_public_keys(s: &Option<String>) -> Result<Vec<H512>, String> {
+	match *s {
+		Some(ref keys) if !keys.is_empty() => keys.split(',')
+			.map(|k| clean_0x(k).parse().map_err(|_| format!("Invalid public key: {:?}", k)))
+			.collect(),
+		_ => Ok(Vec::new()),
+	}
+}
+
 /// Tries to parse string as a price.
 pub fn to_price(s: &str) -> Result<f32, String> {
 	s.parse::<f32>().map_err(|_| format!("Invalid transaciton price 's' given. Must be a decimal number."))
@@ -190,6 +199,8 @@ pub fn default_network_config() -> ::ethsync::NetworkConfiguration {
 		config_path: Some(replace_home("$HOME/.parity/network")),
 		net_config_path: None,
 		listen_address: Some("0.0.0.0:30303".into()),
+		extra_listen_addresses: Vec::new(),
+		prefer_ipv6: false,
 		public_address: None,
 		udp_port: None,
 		nat_enabled: true,
@@ -203,6 +214,10 @@ pub fn default_network_config() -> ::ethsync::NetworkConfiguration {
 		allow_ips: AllowIP::All,
 		reserved_nodes: Vec::new(),
 		allow_non_reserved: true,
+		session_rekey_interval: None,
+		proxy: None,
+		max_peers_per_ip: None,
+		max_peers_per_subnet: None,
 	}
 }
 
@@ -169,6 +169,12 @@ impl Informant {
 			)
 		);
 
+		if let Some(ref sync_info) = sync_status {
+			if let Some(hit_rate) = sync_info.receipts_cache_hit_rate() {
+				debug!(target: "sync", "Receipts cache: {}% hit rate ({} hits, {} misses)", hit_rate, sync_info.receipts_cache_hits, sync_info.receipts_cache_misses);
+			}
+		}
+
 		*self.chain_info.write() = Some(chain_info);
 		*self.cache_info.write() = Some(cache_info);
 		*write_report = Some(report);
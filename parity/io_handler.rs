@@ -21,10 +21,17 @@ use ethcore::service::ClientIoMessage;
 use ethsync::{SyncProvider, ManageNetwork};
 use ethcore::account_provider::AccountProvider;
 use io::{TimerToken, IoHandler, IoContext};
+use util::ntp::{TimeChecker, DRIFT_WARNING_THRESHOLD_MS};
 
 use informant::Informant;
+use updater::Updater;
 
 const INFO_TIMER: TimerToken = 0;
+const TIME_CHECK_TIMER: TimerToken = 1;
+const UPDATE_TIMER: TimerToken = 2;
+
+const TIME_CHECK_INTERVAL_MS: u64 = 10 * 60 * 1000;
+const UPDATE_INTERVAL_MS: u64 = 5 * 60 * 1000;
 
 pub struct ClientIoHandler {
 	pub client: Arc<Client>,
@@ -32,17 +39,47 @@ pub struct ClientIoHandler {
 	pub net: Arc<ManageNetwork>,
 	pub accounts: Arc<AccountProvider>,
 	pub info: Arc<Informant>,
+	pub time_checker: Arc<TimeChecker>,
+	pub updater: Arc<Updater>,
 	pub shutdown: Arc<AtomicBool>
 }
 
+impl ClientIoHandler {
+	fn check_time(&self) {
+		match self.time_checker.update() {
+			Ok(drift) => {
+				if drift.abs() >= DRIFT_WARNING_THRESHOLD_MS {
+					warn!("Your clock differs from {} by {}ms. Make sure your system time is synchronised.", self.time_checker.server(), drift);
+				} else {
+					debug!(target: "ntp", "Local clock drift: {}ms", drift);
+				}
+				self.client.set_clock_drift(drift);
+			},
+			Err(e) => debug!(target: "ntp", "Unable to check local clock drift: {}", e),
+		}
+	}
+}
+
 impl IoHandler<ClientIoMessage> for ClientIoHandler {
 	fn initialize(&self, io: &IoContext<ClientIoMessage>) {
 		io.register_timer(INFO_TIMER, 5000).expect("Error registering timer");
+		// first check shortly after start, then periodically.
+		io.register_timer_once(TIME_CHECK_TIMER, 1000).expect("Error registering timer");
+		io.register_timer(UPDATE_TIMER, UPDATE_INTERVAL_MS).expect("Error registering timer");
 	}
 
-	fn timeout(&self, _io: &IoContext<ClientIoMessage>, timer: TimerToken) {
-		if timer == INFO_TIMER && !self.shutdown.load(Ordering::SeqCst) {
-			self.info.tick();
+	fn timeout(&self, io: &IoContext<ClientIoMessage>, timer: TimerToken) {
+		if self.shutdown.load(Ordering::SeqCst) {
+			return;
+		}
+		match timer {
+			INFO_TIMER => self.info.tick(),
+			TIME_CHECK_TIMER => {
+				self.check_time();
+				io.register_timer_once(TIME_CHECK_TIMER, TIME_CHECK_INTERVAL_MS).expect("Error registering timer");
+			},
+			UPDATE_TIMER => self.updater.tick(),
+			_ => {},
 		}
 	}
 }
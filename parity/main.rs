@@ -55,6 +55,7 @@ extern crate ansi_term;
 extern crate regex;
 extern crate isatty;
 extern crate toml;
+extern crate ethabi;
 
 #[macro_use]
 extern crate ethcore_util as util;
@@ -87,6 +88,12 @@ mod cache;
 mod upgrade;
 mod rpc;
 mod dapps;
+mod metrics;
+mod registrar;
+mod updater;
+mod node_filter;
+mod transaction_filter;
+mod shutdown;
 mod informant;
 mod io_handler;
 mod cli;
@@ -101,6 +108,7 @@ mod deprecated;
 mod dir;
 mod modules;
 mod account;
+mod node_key;
 mod blockchain;
 mod presale;
 mod snapshot;
@@ -142,8 +150,10 @@ fn execute(command: Execute) -> Result<String, String> {
 			Ok("".into())
 		},
 		Cmd::Version => Ok(Args::print_version()),
+		Cmd::PrintConfig(config) => Ok(config),
 		Cmd::Hash(maybe_file) => print_hash_of(maybe_file),
 		Cmd::Account(account_cmd) => account::execute(account_cmd),
+		Cmd::NodeKey(node_key_cmd) => node_key::execute(node_key_cmd),
 		Cmd::ImportPresaleWallet(presale_cmd) => presale::execute(presale_cmd),
 		Cmd::Blockchain(blockchain_cmd) => blockchain::execute(blockchain_cmd),
 		Cmd::SignerToken(signer_cmd) => signer::execute(signer_cmd),
@@ -161,6 +171,11 @@ fn start() -> Result<String, String> {
 	}
 
 	let cmd = try!(conf.into_command());
+	if let Cmd::Run(ref run_cmd) = cmd.cmd {
+		if let Some(code) = updater::run_latest(&run_cmd.dirs.updates_path()) {
+			process::exit(code);
+		}
+	}
 	execute(cmd)
 }
 
@@ -0,0 +1,138 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prometheus-compatible metrics endpoint and `/health` readiness check.
+
+use std::sync::Arc;
+use std::io;
+use hyper::server::{Server, Handler, Request, Response, Listening};
+use hyper::status::StatusCode;
+use hyper::uri::RequestUri;
+use ethcore::client::{Client, BlockChainClient};
+use ethcore::miner::{Miner, MinerService};
+use ethsync::SyncProvider;
+use ethcore_rpc::NodeHealth;
+use util::metrics::metrics;
+use serde_json;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Configuration {
+	pub enabled: bool,
+	pub interface: String,
+	pub port: u16,
+}
+
+impl Default for Configuration {
+	fn default() -> Self {
+		Configuration {
+			enabled: false,
+			interface: "127.0.0.1".into(),
+			port: 9615,
+		}
+	}
+}
+
+pub struct Dependencies {
+	pub client: Arc<Client>,
+	pub miner: Arc<Miner>,
+	pub sync: Arc<SyncProvider>,
+	pub health: Arc<NodeHealth>,
+}
+
+pub struct MetricsServer {
+	listening: Listening,
+}
+
+impl Drop for MetricsServer {
+	fn drop(&mut self) {
+		let _ = self.listening.close();
+	}
+}
+
+struct MetricsHandler {
+	deps: Dependencies,
+}
+
+impl MetricsHandler {
+	// refresh gauges which are sampled rather than recorded at the point of change.
+	fn sample(&self) {
+		let m = metrics();
+
+		let queue = self.deps.client.queue_info();
+		m.gauge("parity_block_queue_size", "Number of blocks in the verification queue.", &[("stage", "unverified")]).set(queue.unverified_queue_size as isize);
+		m.gauge("parity_block_queue_size", "Number of blocks in the verification queue.", &[("stage", "verifying")]).set(queue.verifying_queue_size as isize);
+		m.gauge("parity_block_queue_size", "Number of blocks in the verification queue.", &[("stage", "verified")]).set(queue.verified_queue_size as isize);
+
+		let miner = self.deps.miner.status();
+		m.gauge("parity_transaction_queue_size", "Number of transactions in the queue.", &[("state", "pending")]).set(miner.transactions_in_pending_queue as isize);
+		m.gauge("parity_transaction_queue_size", "Number of transactions in the queue.", &[("state", "future")]).set(miner.transactions_in_future_queue as isize);
+
+		let sync = self.deps.sync.status();
+		m.gauge("parity_peers", "Number of connected peers.", &[("state", "connected")]).set(sync.num_peers as isize);
+		m.gauge("parity_peers", "Number of connected peers.", &[("state", "active")]).set(sync.num_active_peers as isize);
+
+		let chain = self.deps.client.chain_info();
+		m.gauge("parity_best_block", "Number of the best block.", &[]).set(chain.best_block_number as isize);
+	}
+}
+
+impl Handler for MetricsHandler {
+	fn handle(&self, req: Request, mut res: Response) {
+		match req.uri {
+			RequestUri::AbsolutePath(ref path) if path == "/metrics" => {
+				self.sample();
+				res.headers_mut().set_raw("Content-Type", vec![b"text/plain; version=0.0.4".to_vec()]);
+				if let Err(e) = res.send(metrics().render().as_bytes()) {
+					debug!(target: "metrics", "Error sending metrics response: {}", e);
+				}
+			},
+			RequestUri::AbsolutePath(ref path) if path == "/health" => {
+				let health = self.deps.health.health();
+				if !health.is_healthy() {
+					*res.status_mut() = StatusCode::ServiceUnavailable;
+				}
+				res.headers_mut().set_raw("Content-Type", vec![b"application/json".to_vec()]);
+				let body = serde_json::to_vec(&health).expect("Health is always serializable; qed");
+				if let Err(e) = res.send(&body) {
+					debug!(target: "metrics", "Error sending health response: {}", e);
+				}
+			},
+			_ => {
+				*res.status_mut() = StatusCode::NotFound;
+			},
+		}
+	}
+}
+
+pub fn new(conf: Configuration, deps: Dependencies) -> Result<Option<MetricsServer>, String> {
+	if !conf.enabled {
+		return Ok(None);
+	}
+
+	let url = format!("{}:{}", conf.interface, conf.port);
+	let server = try!(Server::http(&url as &str).map_err(|e| match e {
+		::hyper::Error::Io(ref err) if err.kind() == io::ErrorKind::AddrInUse =>
+			format!("Metrics address {} is already in use, make sure that another instance of Parity is not running or change the address using the --metrics-port and --metrics-interface options.", url),
+		e => format!("Metrics server error: {:?}", e),
+	}));
+
+	let listening = try!(server.handle(MetricsHandler { deps: deps }).map_err(|e| format!("Metrics server error: {:?}", e)));
+	info!("Metrics server listening on {}", url);
+
+	Ok(Some(MetricsServer {
+		listening: listening,
+	}))
+}
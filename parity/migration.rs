@@ -30,7 +30,7 @@ use ethcore::migrations::Extract;
 /// Database is assumed to be at default version, when no version file is found.
 const DEFAULT_VERSION: u32 = 5;
 /// Current version of database models.
-const CURRENT_VERSION: u32 = 10;
+const CURRENT_VERSION: u32 = 11;
 /// First version of the consolidated database.
 const CONSOLIDATION_VERSION: u32 = 9;
 /// Defines how many items are migrated to the new version of database at once.
@@ -146,6 +146,7 @@ pub fn default_migration_settings(compaction_profile: &CompactionProfile) -> Mig
 fn consolidated_database_migrations(compaction_profile: &CompactionProfile) -> Result<MigrationManager, Error> {
 	let mut manager = MigrationManager::new(default_migration_settings(compaction_profile));
 	try!(manager.add_migration(migrations::ToV10::new()).map_err(|_| Error::MigrationImpossible));
+	try!(manager.add_migration(migrations::ToV11::new()).map_err(|_| Error::MigrationImpossible));
 	Ok(manager)
 }
 
@@ -169,6 +170,7 @@ fn consolidate_database(
 		compaction: config.compaction_profile,
 		columns: None,
 		wal: true,
+		..Default::default()
 	};
 
 	let old_path_str = try!(old_db_path.to_str().ok_or(Error::MigrationImpossible));
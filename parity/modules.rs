@@ -17,8 +17,9 @@
 use std::sync::Arc;
 use ethcore::client::BlockChainClient;
 use hypervisor::Hypervisor;
-use ethsync::{SyncConfig, NetworkConfiguration, NetworkError};
+use ethsync::{SyncConfig, NetworkConfiguration, NetworkError, ConnectionFilter, LightServing};
 use ethcore::snapshot::SnapshotService;
+use ethcore::private_transactions::Provider as PrivateTxProvider;
 #[cfg(not(feature="ipc"))]
 use self::no_ipc_deps::*;
 #[cfg(feature="ipc")]
@@ -124,10 +125,22 @@ pub fn sync
 		net_cfg: NetworkConfiguration,
 		_client: Arc<BlockChainClient>,
 		_snapshot_service: Arc<SnapshotService>,
+		connection_filter: Option<Arc<ConnectionFilter>>,
+		light_serving: Option<LightServing>,
+		private_tx: Option<Arc<PrivateTxProvider>>,
 		log_settings: &LogConfig,
 	)
 	-> Result<SyncModules, NetworkError>
 {
+	if connection_filter.is_some() {
+		warn!("Node permissions are not enforced when sync runs as a separate process.");
+	}
+	if light_serving.is_some() {
+		warn!("Light clients are not served when sync runs as a separate process.");
+	}
+	if private_tx.is_some() {
+		warn!("Private transactions are not distributed when sync runs as a separate process.");
+	}
 	let mut hypervisor = hypervisor_ref.take().expect("There should be hypervisor for ipc configuration");
 	let args = sync_arguments(&hypervisor.io_path, sync_cfg, net_cfg, log_settings);
 	hypervisor = hypervisor.module(SYNC_MODULE_ID, args);
@@ -154,10 +167,13 @@ pub fn sync
 		net_cfg: NetworkConfiguration,
 		client: Arc<BlockChainClient>,
 		snapshot_service: Arc<SnapshotService>,
+		connection_filter: Option<Arc<ConnectionFilter>>,
+		light_serving: Option<LightServing>,
+		private_tx: Option<Arc<PrivateTxProvider>>,
 		_log_settings: &LogConfig,
 	)
 	-> Result<SyncModules, NetworkError>
 {
-	let eth_sync = try!(EthSync::new(sync_cfg, client, snapshot_service, net_cfg));
+	let eth_sync = try!(EthSync::new(sync_cfg, client, snapshot_service, net_cfg, connection_filter, light_serving, private_tx));
 	Ok((eth_sync.clone() as Arc<SyncProvider>, eth_sync.clone() as Arc<ManageNetwork>, eth_sync.clone() as Arc<ChainNotify>))
 }
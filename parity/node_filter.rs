@@ -0,0 +1,215 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Node permissions for private networks.
+//!
+//! Connections are only established with nodes allowed by the contract configured with
+//! `nodePermissionContract` in the chain spec.
+//!
+//! The contract is never called from the network IO threads. Answers are looked up on a
+//! separate thread and cached; unknown nodes are turned away until their answer is known and
+//! the connection is retried later. Cached answers are refreshed whenever the contract emits
+//! an event or the chain reorganizes. Until the contract is deployed, or while it cannot be
+//! queried, every node is allowed so that a fresh node can still sync.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Weak};
+use std::sync::mpsc::{channel, Sender};
+use std::thread;
+use ethabi::{Interface, Contract, Token};
+use ethcore::client::{Client, BlockChainClient, BlockID, ChainNotify};
+use ethcore::filter::Filter;
+use ethsync::{ConnectionFilter, ConnectionDirection};
+use hash_fetch::urlhint::ContractClient;
+use util::{Mutex, Address, H256, H512};
+use registrar::{Registrar, as_string};
+
+/// Splits node id into the two `bytes32` halves expected by the contract.
+fn node_id_params(id: &H512) -> (Token, Token) {
+	(Token::FixedBytes(id[..32].to_vec()), Token::FixedBytes(id[32..].to_vec()))
+}
+
+/// Connection filter backed by the node permission contract.
+pub struct NodeFilter {
+	contract: Contract,
+	client: Weak<Client>,
+	contract_address: Address,
+	/// Id of this node and answers of the contract for connecting nodes.
+	permission_cache: Mutex<(Option<H512>, HashMap<H512, bool>)>,
+	/// Nodes allowed only because the contract could not be queried.
+	unconfirmed: Mutex<HashSet<H512>>,
+	/// Nodes waiting for a lookup.
+	pending: Mutex<HashSet<H512>>,
+	lookups: Mutex<Sender<(H512, H512)>>,
+}
+
+impl NodeFilter {
+	/// Creates new `NodeFilter` querying the contract at `contract_address`.
+	pub fn new(client: &Arc<Client>, contract_address: Address) -> Arc<Self> {
+		let interface = Interface::load(include_bytes!("res/node_filter.json")).expect("node_filter.json is valid ABI");
+		let (tx, rx) = channel::<(H512, H512)>();
+		let filter = Arc::new(NodeFilter {
+			contract: Contract::new(interface),
+			client: Arc::downgrade(client),
+			contract_address: contract_address,
+			permission_cache: Mutex::new((None, HashMap::new())),
+			unconfirmed: Mutex::new(HashSet::new()),
+			pending: Mutex::new(HashSet::new()),
+			lookups: Mutex::new(tx),
+		});
+
+		let weak = Arc::downgrade(&filter);
+		let spawned = thread::Builder::new().name("node-permissions".into()).spawn(move || {
+			// ends once the filter (and so the sender) is dropped.
+			for (own_id, connecting_id) in rx.iter() {
+				match weak.upgrade() {
+					Some(filter) => filter.lookup(&own_id, &connecting_id),
+					None => break,
+				}
+			}
+		});
+		if let Err(e) = spawned {
+			warn!(target: "network", "Unable to spawn node permissions thread: {}", e);
+		}
+
+		filter
+	}
+
+	/// Queues a lookup of `connecting_id`, unless one is already queued.
+	fn request_lookup(&self, own_id: &H512, connecting_id: &H512) {
+		if self.pending.lock().insert(connecting_id.clone()) {
+			if self.lookups.lock().send((own_id.clone(), connecting_id.clone())).is_err() {
+				self.pending.lock().remove(connecting_id);
+			}
+		}
+	}
+
+	/// Queries the contract and caches the answer. Runs on the lookup thread.
+	fn lookup(&self, own_id: &H512, connecting_id: &H512) {
+		let result = self.query(own_id, connecting_id);
+		self.pending.lock().remove(connecting_id);
+		let allowed = match result {
+			Ok(allowed) => {
+				self.unconfirmed.lock().remove(connecting_id);
+				allowed
+			},
+			Err(e) => {
+				// allow the node, it's checked again on the next block.
+				debug!(target: "network", "Error checking node permissions for {}: {}", connecting_id, e);
+				self.unconfirmed.lock().insert(connecting_id.clone());
+				true
+			},
+		};
+		self.permission_cache.lock().1.insert(connecting_id.clone(), allowed);
+	}
+
+	fn query(&self, own_id: &H512, connecting_id: &H512) -> Result<bool, String> {
+		let client = try!(self.client.upgrade().ok_or_else(|| "Client is gone".to_owned()));
+		match client.code(&self.contract_address, BlockID::Latest) {
+			Some(Some(ref code)) if !code.is_empty() => {},
+			_ => return Err("Permission contract is not deployed yet".into()),
+		}
+		let function = try!(self.contract.function("connectionAllowed".into()).map_err(as_string));
+		let (sl, sh) = node_id_params(own_id);
+		let (pl, ph) = node_id_params(connecting_id);
+		let data = try!(function.encode_call(vec![sl, sh, pl, ph]).map_err(as_string));
+		let output = try!(Registrar::new(client).call(self.contract_address, data));
+		match try!(function.decode_output(output).map_err(as_string)).get(0) {
+			Some(&Token::Bool(allowed)) => Ok(allowed),
+			e => Err(format!("Invalid result: {:?}", e)),
+		}
+	}
+
+	/// Checks whether the contract emitted any event in given block.
+	fn has_events(&self, client: &Client, hash: &H256) -> bool {
+		!client.logs(Filter {
+			from_block: BlockID::Hash(hash.clone()),
+			to_block: BlockID::Hash(hash.clone()),
+			address: Some(vec![self.contract_address]),
+			topics: Vec::new(),
+			limit: Some(1),
+		}).is_empty()
+	}
+}
+
+impl ConnectionFilter for NodeFilter {
+	fn connection_allowed(&self, own_id: &H512, connecting_id: &H512, _direction: ConnectionDirection) -> bool {
+		{
+			let mut cache = self.permission_cache.lock();
+			cache.0 = Some(own_id.clone());
+			if let Some(allowed) = cache.1.get(connecting_id) {
+				return *allowed;
+			}
+		}
+
+		// turned away until the answer is known, the connection is retried later.
+		self.request_lookup(own_id, connecting_id);
+		false
+	}
+}
+
+impl ChainNotify for NodeFilter {
+	fn new_blocks(&self, _imported: Vec<H256>, _invalid: Vec<H256>, enacted: Vec<H256>, retracted: Vec<H256>, _sealed: Vec<H256>, _duration: u64) {
+		let client = match self.client.upgrade() {
+			Some(client) => client,
+			None => return,
+		};
+
+		let own_id = match self.permission_cache.lock().0 {
+			Some(id) => id,
+			None => return,
+		};
+
+		// cached answers are kept until they are refreshed.
+		let stale: Vec<H512> = if !retracted.is_empty() || enacted.iter().any(|hash| self.has_events(&client, hash)) {
+			trace!(target: "network", "Node permissions changed, refreshing cache");
+			self.permission_cache.lock().1.keys().cloned().collect()
+		} else {
+			self.unconfirmed.lock().iter().cloned().collect()
+		};
+
+		for id in stale {
+			self.request_lookup(&own_id, &id);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use ethabi::Token;
+	use util::H512;
+	use super::node_id_params;
+
+	#[test]
+	fn should_split_node_id() {
+		let mut id = H512::new();
+		id[0] = 1;
+		id[63] = 2;
+		let (low, high) = node_id_params(&id);
+
+		let mut expected_low = vec![0u8; 32];
+		expected_low[0] = 1;
+		let mut expected_high = vec![0u8; 32];
+		expected_high[31] = 2;
+		match (low, high) {
+			(Token::FixedBytes(low), Token::FixedBytes(high)) => {
+				assert_eq!(low, expected_low);
+				assert_eq!(high, expected_high);
+			},
+			_ => panic!("node id halves should be fixed bytes"),
+		}
+	}
+}
@@ -0,0 +1,132 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Node key management commands.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use ethcore::ethstore::ethkey::KeyPair;
+use ethsync::node_key;
+use util::Secret;
+use util::path::restrict_permissions_owner;
+
+#[derive(Debug, PartialEq)]
+pub enum NodeKeyCmd {
+	Show(ShowNodeKey),
+	Export(ExportNodeKey),
+	Import(ImportNodeKey),
+	Rotate(RotateNodeKey),
+}
+
+/// Parameters for displaying the enode URL
+#[derive(Debug, PartialEq)]
+pub struct ShowNodeKey {
+	/// network configuration directory
+	pub path: String,
+	/// key given with `--node-key`, overrides the stored one
+	pub use_secret: Option<Secret>,
+	/// address advertised in the enode URL
+	pub address: SocketAddr,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ExportNodeKey {
+	/// network configuration directory
+	pub path: String,
+	pub file: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ImportNodeKey {
+	/// network configuration directory
+	pub path: String,
+	pub file: String,
+}
+
+/// Parameters for rotating the node identity
+#[derive(Debug, PartialEq)]
+pub struct RotateNodeKey {
+	/// network configuration directory
+	pub path: String,
+	/// seconds during which the previous identity is still answered in discovery
+	pub grace_period: u64,
+}
+
+pub fn execute(cmd: NodeKeyCmd) -> Result<String, String> {
+	match cmd {
+		NodeKeyCmd::Show(show_cmd) => show(show_cmd),
+		NodeKeyCmd::Export(export_cmd) => export(export_cmd),
+		NodeKeyCmd::Import(import_cmd) => import(import_cmd),
+		NodeKeyCmd::Rotate(rotate_cmd) => rotate(rotate_cmd),
+	}
+}
+
+fn load(path: &str) -> Result<KeyPair, String> {
+	let secret = try!(node_key::load(Path::new(path))
+		.map_err(|e| format!("Unable to read node key from {}: {}. The key is generated when the node is first started.", path, e)));
+	KeyPair::from_secret(secret).map_err(|e| format!("Invalid node key: {}", e))
+}
+
+fn enode(key: &KeyPair, address: &SocketAddr) -> String {
+	format!("enode://{}@{}", key.public().hex(), address)
+}
+
+fn show(cmd: ShowNodeKey) -> Result<String, String> {
+	let key = match cmd.use_secret {
+		Some(secret) => try!(KeyPair::from_secret(secret).map_err(|e| format!("Invalid node key given with --node-key: {}", e))),
+		None => try!(load(&cmd.path)),
+	};
+	Ok(enode(&key, &cmd.address))
+}
+
+fn export(cmd: ExportNodeKey) -> Result<String, String> {
+	let key = try!(load(&cmd.path));
+	let mut file = try!(fs::File::create(&cmd.file).map_err(|e| format!("Unable to create {}: {}", cmd.file, e)));
+	if let Err(e) = restrict_permissions_owner(Path::new(&cmd.file)) {
+		warn!("Failed to modify permissions of the file (chmod: {})", e);
+	}
+	try!(file.write_all(key.secret().hex().as_bytes()).map_err(|e| format!("Unable to write {}: {}", cmd.file, e)));
+	Ok(format!("Node key {} exported to {}", key.public().hex(), cmd.file))
+}
+
+fn import(cmd: ImportNodeKey) -> Result<String, String> {
+	let mut content = String::new();
+	try!(fs::File::open(&cmd.file)
+		.and_then(|mut f| f.read_to_string(&mut content))
+		.map_err(|e| format!("Unable to read {}: {}", cmd.file, e)));
+	let secret = try!(node_key::parse_secret(&content).map_err(|e| format!("Invalid node key in {}: {}", cmd.file, e)));
+	let key = try!(KeyPair::from_secret(secret).map_err(|e| format!("Invalid node key: {}", e)));
+
+	let replaced = node_key::load(Path::new(&cmd.path)).ok()
+		.and_then(|s| KeyPair::from_secret(s).ok());
+	try!(node_key::save(Path::new(&cmd.path), key.secret()).map_err(|e| format!("Unable to save node key: {}", e)));
+
+	Ok(match replaced {
+		Some(old) => format!("Node key {} imported, replacing {}", key.public().hex(), old.public().hex()),
+		None => format!("Node key {} imported", key.public().hex()),
+	})
+}
+
+fn rotate(cmd: RotateNodeKey) -> Result<String, String> {
+	let key = try!(node_key::rotate_random(Path::new(&cmd.path), cmd.grace_period).map_err(|e| format!("Unable to rotate node key: {}", e)));
+	Ok(format!(
+		"New node identity: {}\nRestart the node to use it. The previous identity is answered in discovery for the next {} seconds.",
+		key.public().hex(),
+		cmd.grace_period
+	))
+}
@@ -30,6 +30,7 @@ pub enum SpecType {
 	Testnet,
 	Ropsten,
 	Olympic,
+	Kovan,
 	Classic,
 	Expanse,
 	Dev,
@@ -46,34 +47,47 @@ impl str::FromStr for SpecType {
 	type Err = String;
 
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
-		let spec = match s {
-			"frontier" | "homestead" | "mainnet" => SpecType::Mainnet,
-			"frontier-dogmatic" | "homestead-dogmatic" | "classic" => SpecType::Classic,
-			"morden" | "testnet" => SpecType::Testnet,
-			"ropsten" => SpecType::Ropsten,
-			"olympic" => SpecType::Olympic,
-			"expanse" => SpecType::Expanse,
-			"dev" => SpecType::Dev,
-			other => SpecType::Custom(other.into()),
+		let spec = match ethereum::registry::find(s).map(|chain| chain.name) {
+			Some("mainnet") => SpecType::Mainnet,
+			Some("classic") => SpecType::Classic,
+			Some("morden") => SpecType::Testnet,
+			Some("ropsten") => SpecType::Ropsten,
+			Some("olympic") => SpecType::Olympic,
+			Some("kovan") => SpecType::Kovan,
+			Some("expanse") => SpecType::Expanse,
+			_ if s == "dev" => SpecType::Dev,
+			_ => SpecType::Custom(s.into()),
 		};
 		Ok(spec)
 	}
 }
 
 impl SpecType {
+	/// Name of the built-in chain, if any.
+	pub fn builtin_name(&self) -> Option<&'static str> {
+		match *self {
+			SpecType::Mainnet => Some("mainnet"),
+			SpecType::Testnet => Some("morden"),
+			SpecType::Ropsten => Some("ropsten"),
+			SpecType::Olympic => Some("olympic"),
+			SpecType::Kovan => Some("kovan"),
+			SpecType::Classic => Some("classic"),
+			SpecType::Expanse => Some("expanse"),
+			SpecType::Dev | SpecType::Custom(_) => None,
+		}
+	}
+
 	pub fn spec(&self) -> Result<Spec, String> {
+		if let Some(chain) = self.builtin_name().and_then(ethereum::registry::find) {
+			return Ok((chain.spec)());
+		}
+
 		match *self {
-			SpecType::Mainnet => Ok(ethereum::new_frontier()),
-			SpecType::Testnet => Ok(ethereum::new_morden()),
-			SpecType::Ropsten => Ok(ethereum::new_ropsten()),
-			SpecType::Olympic => Ok(ethereum::new_olympic()),
-			SpecType::Classic => Ok(ethereum::new_classic()),
-			SpecType::Expanse => Ok(ethereum::new_expanse()),
-			SpecType::Dev => Ok(Spec::new_instant()),
 			SpecType::Custom(ref filename) => {
 				let file = try!(fs::File::open(filename).map_err(|_| "Could not load specification file."));
 				Spec::load(file)
-			}
+			},
+			_ => Ok(Spec::new_instant()),
 		}
 	}
 }
@@ -152,6 +166,7 @@ pub struct AccountsConfig {
 	pub testnet: bool,
 	pub password_files: Vec<String>,
 	pub unlocked_accounts: Vec<Address>,
+	pub min_phrase_entropy: u32,
 }
 
 impl Default for AccountsConfig {
@@ -161,6 +176,7 @@ impl Default for AccountsConfig {
 			testnet: false,
 			password_files: Vec::new(),
 			unlocked_accounts: Vec::new(),
+			min_phrase_entropy: 64,
 		}
 	}
 }
@@ -257,6 +273,18 @@ pub fn tracing_switch_to_bool(switch: Switch, user_defaults: &UserDefaults) -> R
 	}
 }
 
+/// Addresses to keep traces for. Traces of other addresses are never stored, so an existing
+/// trace database can't start tracing addresses it wasn't tracing before.
+pub fn tracing_addresses_to_use(addresses: Vec<Address>, user_defaults: &UserDefaults) -> Result<Vec<Address>, String> {
+	let stored = &user_defaults.tracing_addresses;
+	match (user_defaults.is_first_launch || !user_defaults.tracing, addresses.is_empty()) {
+		(true, _) => Ok(addresses),
+		(false, true) => Ok(stored.clone()),
+		(false, false) if stored.is_empty() || addresses.iter().all(|a| stored.contains(a)) => Ok(addresses),
+		(false, false) => Err("TraceDB resync required to trace other addresses".into()),
+	}
+}
+
 pub fn fatdb_switch_to_bool(switch: Switch, user_defaults: &UserDefaults, _algorithm: Algorithm) -> Result<bool, String> {
 	let result = match (user_defaults.is_first_launch, switch, user_defaults.fat_db) {
 		(false, Switch::On, false) => Err("FatDB resync required".into()),
@@ -273,9 +301,10 @@ pub fn mode_switch_to_bool(switch: Option<Mode>, user_defaults: &UserDefaults) -
 
 #[cfg(test)]
 mod tests {
+	use util::Address;
 	use util::journaldb::Algorithm;
 	use user_defaults::UserDefaults;
-	use super::{SpecType, Pruning, ResealPolicy, Switch, tracing_switch_to_bool};
+	use super::{SpecType, Pruning, ResealPolicy, Switch, tracing_switch_to_bool, tracing_addresses_to_use};
 
 	#[test]
 	fn test_spec_type_parsing() {
@@ -286,6 +315,19 @@ mod tests {
 		assert_eq!(SpecType::Testnet, "morden".parse().unwrap());
 		assert_eq!(SpecType::Ropsten, "ropsten".parse().unwrap());
 		assert_eq!(SpecType::Olympic, "olympic".parse().unwrap());
+		assert_eq!(SpecType::Kovan, "kovan".parse().unwrap());
+		assert_eq!(SpecType::Classic, "homestead-dogmatic".parse().unwrap());
+		assert_eq!(SpecType::Dev, "dev".parse().unwrap());
+		assert_eq!(SpecType::Custom("kovan.json".into()), "kovan.json".parse().unwrap());
+	}
+
+	#[test]
+	fn builtin_spec_types_should_be_registered() {
+		for spec in &[SpecType::Mainnet, SpecType::Testnet, SpecType::Ropsten, SpecType::Olympic, SpecType::Kovan, SpecType::Classic, SpecType::Expanse] {
+			let name = spec.builtin_name().unwrap();
+			assert_eq!(spec, &name.parse().unwrap());
+		}
+		assert!(SpecType::Dev.builtin_name().is_none());
 	}
 
 	#[test]
@@ -356,4 +398,16 @@ mod tests {
 		assert!(tracing_switch_to_bool(Switch::On, &user_defaults_with_tracing(false, true)).unwrap());
 		assert!(tracing_switch_to_bool(Switch::On, &user_defaults_with_tracing(false, false)).is_err());
 	}
+
+	#[test]
+	fn test_tracing_addresses_to_use() {
+		let (a, b, c) = (Address::from(1), Address::from(2), Address::from(3));
+		let mut ud = user_defaults_with_tracing(false, true);
+		ud.tracing_addresses = vec![a, b];
+
+		assert_eq!(tracing_addresses_to_use(vec![], &ud), Ok(vec![a, b]));
+		assert_eq!(tracing_addresses_to_use(vec![b], &ud), Ok(vec![b]));
+		assert!(tracing_addresses_to_use(vec![c], &ud).is_err());
+		assert_eq!(tracing_addresses_to_use(vec![c], &user_defaults_with_tracing(true, true)), Ok(vec![c]));
+	}
 }
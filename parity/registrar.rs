@@ -0,0 +1,69 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! On-chain contract client backed by the full client.
+
+use std::sync::Arc;
+use util::{Bytes, Address, U256};
+use ethcore::transaction::{Transaction, Action};
+use ethcore::client::{Client, BlockChainClient, BlockID};
+use hash_fetch::urlhint::ContractClient;
+
+/// Formats errors of contract calls and ABI encoding.
+pub fn as_string<T: ::std::fmt::Debug>(e: T) -> String {
+	format!("{:?}", e)
+}
+
+/// Performs calls to the registrar and other contracts using latest state.
+pub struct Registrar {
+	client: Arc<Client>,
+}
+
+impl Registrar {
+	pub fn new(client: Arc<Client>) -> Self {
+		Registrar {
+			client: client,
+		}
+	}
+}
+
+impl ContractClient for Registrar {
+	fn registrar(&self) -> Result<Address, String> {
+		self.client.additional_params().get("registrar")
+			 .ok_or_else(|| "Registrar not defined.".into())
+			 .and_then(|registrar| {
+				 registrar.parse().map_err(|e| format!("Invalid registrar address: {:?}", e))
+			 })
+	}
+
+	fn call(&self, address: Address, data: Bytes) -> Result<Bytes, String> {
+		let from = Address::default();
+		let transaction = Transaction {
+			nonce: self.client.latest_nonce(&from),
+			action: Action::Call(address),
+			gas: U256::from(50_000_000),
+			gas_price: U256::default(),
+			value: U256::default(),
+			data: data,
+		}.fake_sign(from);
+
+		self.client.call(&transaction, BlockID::Latest, Default::default())
+			.map_err(as_string)
+			.map(|executed| {
+				executed.output
+			})
+	}
+}
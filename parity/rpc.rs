@@ -18,15 +18,19 @@ use std::fmt;
 use std::sync::Arc;
 use std::net::SocketAddr;
 use std::io;
-use io::PanicHandler;
-use ethcore_rpc::{RpcServerError, RpcServer as Server};
+use io::{ForwardPanic, PanicHandler};
+use ethcore_rpc::{AccessPolicy, RpcServer as Server};
 use jsonipc;
 use rpc_apis;
 use rpc_apis::ApiSet;
-use helpers::parity_ipc_path;
+use helpers::{parity_ipc_path, replace_home};
+use signer::codes_path;
+use ethcore_rpc::ConfirmationsQueue;
+use ethcore_signer as ws;
 
 pub use jsonipc::Server as IpcServer;
 pub use ethcore_rpc::Server as HttpServer;
+pub use ethcore_signer::Server as WsServer;
 
 #[derive(Debug, PartialEq)]
 pub struct HttpConfiguration {
@@ -78,6 +82,32 @@ impl fmt::Display for IpcConfiguration {
 	}
 }
 
+#[derive(Debug, PartialEq)]
+pub struct WsConfiguration {
+	pub enabled: bool,
+	pub interface: String,
+	pub port: u16,
+	pub apis: ApiSet,
+	pub origins: Vec<String>,
+	pub max_pending_requests: usize,
+	/// Directory with authorization tokens, shared with Trusted Signer.
+	pub signer_path: String,
+}
+
+impl Default for WsConfiguration {
+	fn default() -> Self {
+		WsConfiguration {
+			enabled: true,
+			interface: "127.0.0.1".into(),
+			port: 8546,
+			apis: ApiSet::UnsafeContext,
+			origins: Vec::new(),
+			max_pending_requests: 64,
+			signer_path: replace_home("$HOME/.parity/signer"),
+		}
+	}
+}
+
 pub struct Dependencies {
 	pub panic_handler: Arc<PanicHandler>,
 	pub apis: Arc<rpc_apis::Dependencies>,
@@ -90,7 +120,19 @@ pub fn new_http(conf: HttpConfiguration, deps: &Dependencies) -> Result<Option<H
 
 	let url = format!("{}:{}", conf.interface, conf.port);
 	let addr = try!(url.parse().map_err(|_| format!("Invalid JSONRPC listen host/port given: {}", url)));
-	Ok(Some(try!(setup_http_rpc_server(deps, &addr, conf.cors, conf.hosts, conf.apis))))
+	Ok(Some(try!(setup_http_rpc_server(deps, &addr, conf.apis))))
+}
+
+/// Returns access policy of the HTTP server.
+/// Listening address is always allowed, unless all hosts are.
+pub fn access_policy(conf: &HttpConfiguration) -> AccessPolicy {
+	let hosts = conf.hosts.clone().map(|mut hosts| {
+		let address = format!("{}:{}", conf.interface, conf.port);
+		hosts.push(address.replace("127.0.0.1", "localhost"));
+		hosts.push(address);
+		hosts
+	});
+	AccessPolicy::new(conf.cors.clone(), hosts)
 }
 
 fn setup_rpc_server(apis: ApiSet, deps: &Dependencies) -> Result<Server, String> {
@@ -101,19 +143,16 @@ fn setup_rpc_server(apis: ApiSet, deps: &Dependencies) -> Result<Server, String>
 pub fn setup_http_rpc_server(
 	dependencies: &Dependencies,
 	url: &SocketAddr,
-	cors_domains: Option<Vec<String>>,
-	allowed_hosts: Option<Vec<String>>,
 	apis: ApiSet
 ) -> Result<HttpServer, String> {
 	let server = try!(setup_rpc_server(apis, dependencies));
 	let ph = dependencies.panic_handler.clone();
-	let start_result = server.start_http(url, cors_domains, allowed_hosts, ph);
+	let start_result = server.start_http(url, dependencies.apis.access_policy.clone(), ph);
 	match start_result {
-		Err(RpcServerError::IoError(err)) => match err.kind() {
+		Err(err) => match err.kind() {
 			io::ErrorKind::AddrInUse => Err(format!("RPC address {} is already in use, make sure that another instance of an Ethereum client is not running or change the address using the --jsonrpc-port and --jsonrpc-interface options.", url)),
 			_ => Err(format!("RPC io error: {}", err)),
 		},
-		Err(e) => Err(format!("RPC error: {:?}", e)),
 		Ok(server) => Ok(server),
 	}
 }
@@ -131,3 +170,30 @@ pub fn setup_ipc_rpc_server(dependencies: &Dependencies, addr: &str, apis: ApiSe
 		Ok(server) => Ok(server)
 	}
 }
+
+pub fn new_ws(conf: WsConfiguration, deps: &Dependencies) -> Result<Option<WsServer>, String> {
+	if !conf.enabled {
+		return Ok(None);
+	}
+
+	let url = format!("{}:{}", conf.interface, conf.port);
+	let addr = try!(url.parse().map_err(|_| format!("Invalid WebSockets listen host/port given: {}", url)));
+	// the server doesn't handle confirmations, so it gets its own (always empty) queue.
+	let server = ws::ServerBuilder::new(Arc::new(ConfirmationsQueue::default()), codes_path(conf.signer_path))
+		.allowed_origins(conf.origins)
+		.serve_ui(false)
+		.max_pending_requests(conf.max_pending_requests);
+	let server = rpc_apis::setup_rpc(server, deps.apis.clone(), conf.apis);
+
+	match server.start(addr) {
+		Err(ws::ServerError::IoError(err)) => match err.kind() {
+			io::ErrorKind::AddrInUse => Err(format!("WebSockets address {} is already in use, make sure that another instance of an Ethereum client is not running or change the address using the --ws-port and --ws-interface options.", url)),
+			_ => Err(format!("WebSockets io error: {}", err)),
+		},
+		Err(e) => Err(format!("WebSockets error: {:?}", e)),
+		Ok(server) => {
+			deps.panic_handler.forward_from(&server);
+			Ok(server)
+		},
+	}
+}
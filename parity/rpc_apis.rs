@@ -19,13 +19,16 @@ use std::collections::HashSet;
 use std::cmp::PartialEq;
 use std::str::FromStr;
 use std::sync::Arc;
-use util::RotatingLogger;
+use util::{RotatingLogger, U256};
 use ethcore::miner::{Miner, ExternalMiner};
 use ethcore::client::Client;
 use ethcore::account_provider::AccountProvider;
+use ethcore::private_transactions::Provider as PrivateTxProvider;
 use ethcore::snapshot::SnapshotService;
+use ethcore::client::BlockChainClient;
 use ethsync::{ManageNetwork, SyncProvider};
-use ethcore_rpc::{Extendable, NetworkSettings};
+use ethcore_rpc::{Extendable, NetworkSettings, NodeHealth, AccessPolicy, is_major_importing};
+use ethcore_rpc::v1::health::SyncStatus;
 pub use ethcore_rpc::SignerService;
 
 
@@ -49,6 +52,10 @@ pub enum Api {
 	ParitySet,
 	/// Traces (Safe)
 	Traces,
+	/// Geth-compatible transaction pool inspection (Safe)
+	TxPool,
+	/// Private transactions (UNSAFE: Side Effects (public transactions sent from node account))
+	Private,
 	/// Rpc (Safe)
 	Rpc,
 }
@@ -69,6 +76,8 @@ impl FromStr for Api {
 			"parity_accounts" => Ok(ParityAccounts),
 			"parity_set" => Ok(ParitySet),
 			"traces" => Ok(Traces),
+			"txpool" => Ok(TxPool),
+			"private" => Ok(Private),
 			"rpc" => Ok(Rpc),
 			api => Err(format!("Unknown api: {}", api))
 		}
@@ -81,6 +90,8 @@ pub enum ApiSet {
 	UnsafeContext,
 	IpcContext,
 	List(HashSet<Api>),
+	/// Given APIs without the account-bearing ones, served by a publicly exposed node.
+	PublicContext(HashSet<Api>),
 }
 
 impl Default for ApiSet {
@@ -119,8 +130,29 @@ pub struct Dependencies {
 	pub settings: Arc<NetworkSettings>,
 	pub net_service: Arc<ManageNetwork>,
 	pub geth_compatibility: bool,
+	pub rpc_gas_cap: Option<U256>,
 	pub dapps_interface: Option<String>,
 	pub dapps_port: Option<u16>,
+	pub health: Arc<NodeHealth>,
+	pub access_policy: Arc<AccessPolicy>,
+	pub private_tx: Option<Arc<PrivateTxProvider>>,
+}
+
+/// Sync status of a full node, used by the health checks.
+pub struct FullSyncStatus {
+	pub client: Arc<Client>,
+	pub sync: Arc<SyncProvider>,
+	pub net: Arc<ManageNetwork>,
+}
+
+impl SyncStatus for FullSyncStatus {
+	fn is_major_importing(&self) -> bool {
+		is_major_importing(Some(self.sync.status().state), self.client.queue_info())
+	}
+
+	fn peers(&self) -> (usize, usize) {
+		(self.sync.status().num_peers, self.net.network_config().min_peers as usize)
+	}
 }
 
 fn to_modules(apis: &[Api]) -> BTreeMap<String, String> {
@@ -136,6 +168,8 @@ fn to_modules(apis: &[Api]) -> BTreeMap<String, String> {
 			Api::ParityAccounts => ("parity_accounts", "1.0"),
 			Api::ParitySet => ("parity_set", "1.0"),
 			Api::Traces => ("traces", "1.0"),
+			Api::TxPool => ("txpool", "1.0"),
+			Api::Private => ("private", "1.0"),
 			Api::Rpc => ("rpc", "1.0"),
 		};
 		modules.insert(name.into(), version.into());
@@ -149,6 +183,7 @@ impl ApiSet {
 			.into_iter().collect();
 		match *self {
 			ApiSet::List(ref apis) => apis.clone(),
+			ApiSet::PublicContext(ref apis) => apis.iter().filter(|api| !api.is_account_bearing()).cloned().collect(),
 			ApiSet::UnsafeContext => safe_list,
 			ApiSet::IpcContext => {
 				safe_list.insert(Api::ParityAccounts);
@@ -164,6 +199,16 @@ impl ApiSet {
 	}
 }
 
+impl Api {
+	/// Whether the API gives access to local accounts or affects node operation.
+	fn is_account_bearing(&self) -> bool {
+		match *self {
+			Api::Personal | Api::Signer | Api::ParityAccounts | Api::ParitySet | Api::Private => true,
+			_ => false,
+		}
+	}
+}
+
 macro_rules! add_signing_methods {
 	($namespace:ident, $server:expr, $deps:expr) => {
 		let server = &$server;
@@ -177,10 +222,31 @@ macro_rules! add_signing_methods {
 }
 
 pub fn setup_rpc<T: Extendable>(server: T, deps: Arc<Dependencies>, apis: ApiSet) -> T {
+	use ethcore_rpc::v1::PublicNodeMiddleware;
+
+	match apis {
+		ApiSet::PublicContext(_) => {
+			let server = PublicNodeMiddleware::new(server, &deps.sync, &deps.net_service);
+			add_apis(&server, &deps, apis, true);
+			server.into_inner()
+		},
+		_ => {
+			add_apis(&server, &deps, apis, false);
+			server
+		},
+	}
+}
+
+fn add_apis<T: Extendable>(server: &T, deps: &Dependencies, apis: ApiSet, public_node: bool) {
 	use ethcore_rpc::v1::*;
 
 	// it's turned into vector, cause ont of the cases requires &[]
 	let apis = apis.list_apis().into_iter().collect::<Vec<_>>();
+	// public nodes never expose local accounts
+	let secret_store = match public_node {
+		true => Arc::new(AccountProvider::transient_provider()),
+		false => deps.secret_store.clone(),
+	};
 	for api in &apis {
 		match *api {
 			Api::Web3 => {
@@ -194,20 +260,31 @@ pub fn setup_rpc<T: Extendable>(server: T, deps: Arc<Dependencies>, apis: ApiSet
 					&deps.client,
 					&deps.snapshot,
 					&deps.sync,
-					&deps.secret_store,
+					&secret_store,
 					&deps.miner,
 					&deps.external_miner,
 					EthClientOptions {
 						allow_pending_receipt_query: !deps.geth_compatibility,
 						send_block_number_in_get_work: !deps.geth_compatibility,
+						max_logs: match public_node {
+							true => Some(PUBLIC_NODE_MAX_LOGS),
+							false => None,
+						},
+						gas_cap: deps.rpc_gas_cap,
 					}
 				);
 				server.add_delegate(client.to_delegate());
 
 				let filter_client = EthFilterClient::new(&deps.client, &deps.miner);
+				let filter_client = match public_node {
+					true => filter_client.with_max_logs(PUBLIC_NODE_MAX_LOGS),
+					false => filter_client,
+				};
 				server.add_delegate(filter_client.to_delegate());
 
-				add_signing_methods!(EthSigning, server, deps);
+				if !public_node {
+					add_signing_methods!(EthSigning, server, deps);
+				}
 			},
 			Api::Personal => {
 				server.add_delegate(PersonalClient::new(&deps.secret_store, &deps.client, &deps.miner, deps.geth_compatibility).to_delegate());
@@ -224,34 +301,43 @@ pub fn setup_rpc<T: Extendable>(server: T, deps: Arc<Dependencies>, apis: ApiSet
 					&deps.client,
 					&deps.miner,
 					&deps.sync,
+					&deps.snapshot,
 					&deps.net_service,
-					&deps.secret_store,
+					&secret_store,
 					deps.logger.clone(),
 					deps.settings.clone(),
 					signer,
 					deps.dapps_interface.clone(),
 					deps.dapps_port,
-				).to_delegate());
+					deps.health.clone(),
+				).with_gas_cap(deps.rpc_gas_cap).to_delegate());
 
-				add_signing_methods!(EthSigning, server, deps);
-				add_signing_methods!(ParitySigning, server, deps);
+				if !public_node {
+					add_signing_methods!(EthSigning, server, deps);
+					add_signing_methods!(ParitySigning, server, deps);
+				}
 			},
 			Api::ParityAccounts => {
 				server.add_delegate(ParityAccountsClient::new(&deps.secret_store, &deps.client).to_delegate());
 			},
 			Api::ParitySet => {
-				server.add_delegate(ParitySetClient::new(&deps.client, &deps.miner, &deps.net_service).to_delegate())
+				server.add_delegate(ParitySetClient::new(&deps.client, &deps.miner, &deps.net_service, &deps.access_policy).to_delegate())
 			},
 			Api::Traces => {
 				server.add_delegate(TracesClient::new(&deps.client, &deps.miner).to_delegate())
 			},
+			Api::TxPool => {
+				server.add_delegate(TxPoolClient::new(&deps.miner).to_delegate())
+			},
+			Api::Private => {
+				server.add_delegate(PrivateClient::new(deps.private_tx.clone(), &deps.client, &deps.miner).to_delegate())
+			},
 			Api::Rpc => {
 				let modules = to_modules(&apis);
 				server.add_delegate(RpcClient::new(modules).to_delegate());
 			}
 		}
 	}
-	server
 }
 
 #[cfg(test)]
@@ -269,6 +355,8 @@ mod test {
 		assert_eq!(Api::ParityAccounts, "parity_accounts".parse().unwrap());
 		assert_eq!(Api::ParitySet, "parity_set".parse().unwrap());
 		assert_eq!(Api::Traces, "traces".parse().unwrap());
+		assert_eq!(Api::TxPool, "txpool".parse().unwrap());
+		assert_eq!(Api::Private, "private".parse().unwrap());
 		assert_eq!(Api::Rpc, "rpc".parse().unwrap());
 		assert!("rp".parse::<Api>().is_err());
 	}
@@ -303,6 +391,13 @@ mod test {
 		assert_eq!(ApiSet::IpcContext.list_apis(), expected);
 	}
 
+	#[test]
+	fn test_api_set_public_context() {
+		let apis = vec![Api::Web3, Api::Eth, Api::Personal, Api::ParityAccounts, Api::Parity].into_iter().collect();
+		let expected = vec![Api::Web3, Api::Eth, Api::Parity].into_iter().collect();
+		assert_eq!(ApiSet::PublicContext(apis).list_apis(), expected);
+	}
+
 	#[test]
 	fn test_api_set_safe_context() {
 		let expected = vec![
@@ -14,30 +14,41 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::process;
 use std::sync::{Arc, Mutex, Condvar};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use std::net::{TcpListener};
 use ctrlc::CtrlC;
 use fdlimit::raise_fd_limit;
-use ethcore_rpc::{NetworkSettings, is_major_importing};
+use ethcore_rpc::{NetworkSettings, NodeHealth, is_major_importing, request_log};
 use ethsync::NetworkConfiguration;
-use util::{Colour, version, RotatingLogger};
+use util::{Colour, version, RotatingLogger, CompactionStyle, U256, Address};
+use util::ntp::TimeChecker;
 use io::{MayPanic, ForwardPanic, PanicHandler};
 use ethcore_logger::{Config as LogConfig};
-use ethcore::client::{Mode, DatabaseCompactionProfile, VMType, ChainNotify, BlockChainClient};
+use ethcore::client::{Mode, DatabaseCompactionProfile, VMType, VerifierSettings, ChainNotify, BlockChainClient};
 use ethcore::service::ClientService;
 use ethcore::account_provider::AccountProvider;
 use ethcore::miner::{Miner, MinerService, ExternalMiner, MinerOptions};
 use ethcore::snapshot;
-use ethsync::SyncConfig;
+use ethcore::ethereum::PowCacheOptions;
+use ethcore::private_transactions::{Provider as PrivateTxProvider, ProviderConfig as PrivateTxConfig};
+use ethsync::{SyncConfig, ConnectionFilter, LightProvider, LightClientProvider, LightServing};
 use informant::Informant;
 
-use rpc::{HttpServer, IpcServer, HttpConfiguration, IpcConfiguration};
+use rpc::{HttpServer, IpcServer, WsServer, HttpConfiguration, IpcConfiguration, WsConfiguration};
 use signer::SignerServer;
 use dapps::WebappServer;
+use metrics::MetricsServer;
+use updater::{Updater, UpdatePolicy};
+use node_filter::NodeFilter;
+use transaction_filter::ContractTransactionFilter;
+use shutdown;
 use io_handler::ClientIoHandler;
 use params::{
 	SpecType, Pruning, AccountsConfig, GasPricerConfig, MinerExtras, Switch,
-	tracing_switch_to_bool, fatdb_switch_to_bool, mode_switch_to_bool
+	tracing_switch_to_bool, tracing_addresses_to_use, fatdb_switch_to_bool, mode_switch_to_bool
 };
 use helpers::{to_client_config, execute_upgrades, passwords_from_files};
 use dir::Directories;
@@ -45,6 +56,7 @@ use cache::CacheConfig;
 use user_defaults::UserDefaults;
 use dapps;
 use signer;
+use metrics;
 use modules;
 use rpc_apis;
 use rpc;
@@ -69,6 +81,10 @@ pub struct RunCmd {
 	pub miner_options: MinerOptions,
 	pub http_conf: HttpConfiguration,
 	pub ipc_conf: IpcConfiguration,
+	pub ws_conf: WsConfiguration,
+	pub rpc_log: bool,
+	pub rpc_slow_query: Option<Duration>,
+	pub rpc_gas_cap: Option<U256>,
 	pub net_conf: NetworkConfiguration,
 	pub network_id: Option<usize>,
 	pub warp_sync: bool,
@@ -79,19 +95,41 @@ pub struct RunCmd {
 	pub tracing: Switch,
 	pub fat_db: Switch,
 	pub compaction: DatabaseCompactionProfile,
+	pub compaction_style: CompactionStyle,
 	pub wal: bool,
+	pub wal_size: usize,
+	pub block_cache_size: usize,
+	pub commit_batch: usize,
+	pub sender_index: bool,
+	pub tracing_addresses: Vec<Address>,
+	pub tracing_history: Option<u64>,
+	pub verifier_settings: VerifierSettings,
+	pub pow_cache_options: PowCacheOptions,
 	pub vm_type: VMType,
+	pub parallel_execution: bool,
+	pub pipeline_state_root: bool,
 	pub geth_compatibility: bool,
 	pub ui_address: Option<(String, u16)>,
 	pub net_settings: NetworkSettings,
 	pub dapps_conf: dapps::Configuration,
 	pub signer_conf: signer::Configuration,
+	pub metrics_conf: metrics::Configuration,
+	pub update_policy: UpdatePolicy,
+	pub shutdown_timeout: Duration,
+	pub ntp_server: String,
 	pub ui: bool,
 	pub name: String,
 	pub custom_bootnodes: bool,
 	pub no_periodic_snapshot: bool,
+	pub verify_restored_state: bool,
+	pub max_reorg_depth: Option<u64>,
+	pub private_tx: Option<PrivateTxConfig>,
 	pub check_seal: bool,
 	pub download_old_blocks: bool,
+	pub tx_diffusion: bool,
+	pub serve_light: bool,
+	pub light_buffer_limit: u64,
+	pub light_buffer_recharge: u64,
 }
 
 pub fn open_ui(dapps_conf: &dapps::Configuration, signer_conf: &signer::Configuration) -> Result<(), String> {
@@ -131,6 +169,7 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 
 	// load spec
 	let spec = try!(cmd.spec.spec());
+	spec.engine.set_pow_cache_options(cmd.pow_cache_options.clone());
 
 	// load genesis hash
 	let genesis_hash = spec.genesis_header().hash();
@@ -149,6 +188,7 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 
 	// check if tracing is on
 	let tracing = try!(tracing_switch_to_bool(cmd.tracing, &user_defaults));
+	let tracing_addresses = try!(tracing_addresses_to_use(cmd.tracing_addresses, &user_defaults));
 
 	// check if fatdb is on
 	let fat_db = try!(fatdb_switch_to_bool(cmd.fat_db, &user_defaults, algorithm));
@@ -204,10 +244,22 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 	sync_config.fork_block = spec.fork_block();
 	sync_config.warp_sync = cmd.warp_sync;
 	sync_config.download_old_blocks = cmd.download_old_blocks;
+	sync_config.tx_diffusion = cmd.tx_diffusion;
 
 	// prepare account provider
 	let account_provider = Arc::new(try!(prepare_account_provider(&cmd.dirs, cmd.acc_conf)));
 
+	// prepare private transactions provider
+	let private_tx = match cmd.private_tx {
+		Some(config) => {
+			info!("Private transactions enabled for account 0x{}", config.account.hex());
+			let provider = try!(PrivateTxProvider::new(config, account_provider.clone(), &db_dirs.private_tx_path())
+				.map_err(|e| format!("Private transactions error: {}", e)));
+			Some(Arc::new(provider))
+		},
+		None => None,
+	};
+
 	// create miner
 	let miner = Miner::new(cmd.miner_options, cmd.gas_pricer.into(), &spec, Some(account_provider.clone()));
 	miner.set_author(cmd.miner_extras.author);
@@ -215,9 +267,18 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 	miner.set_gas_ceil_target(cmd.miner_extras.gas_ceil_target);
 	miner.set_extra_data(cmd.miner_extras.extra_data);
 	miner.set_transactions_limit(cmd.miner_extras.transactions_limit);
+	if let Some(address) = spec.transaction_permission_contract() {
+		info!("Transaction permissions enforced by contract 0x{}", address.hex());
+		miner.add_transaction_filter(Arc::new(ContractTransactionFilter::new(address)));
+	}
+	if let Some(ref provider) = private_tx {
+		if provider.is_validator() {
+			miner.add_transaction_filter(provider.clone());
+		}
+	}
 
 	// create client config
-	let client_config = to_client_config(
+	let mut client_config = to_client_config(
 		&cmd.cache_config,
 		mode.clone(),
 		tracing,
@@ -230,6 +291,18 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 		cmd.pruning_history,
 		cmd.check_seal,
 	);
+	client_config.db_compaction_style = cmd.compaction_style;
+	client_config.db_wal_size = Some(cmd.wal_size);
+	client_config.db_block_cache_size = Some(cmd.block_cache_size);
+	client_config.commit_batch = cmd.commit_batch;
+	client_config.blockchain.sender_index = cmd.sender_index;
+	client_config.tracing.addresses = tracing_addresses.clone();
+	client_config.tracing.history = cmd.tracing_history;
+	client_config.parallel_execution = cmd.parallel_execution;
+	client_config.pipeline_state_root = cmd.pipeline_state_root;
+	client_config.verify_restored_state = cmd.verify_restored_state;
+	client_config.max_reorg_depth = cmd.max_reorg_depth;
+	client_config.queue.verifier_settings = cmd.verifier_settings;
 
 	// set up bootnodes
 	let mut net_conf = cmd.net_conf;
@@ -253,6 +326,9 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 		miner.clone(),
 	).map_err(|e| format!("Client service error: {:?}", e)));
 
+	let node_permission_contract = spec.node_permission_contract();
+	let engine = spec.engine.clone();
+
 	// drop the spec to free up genesis state.
 	drop(spec);
 
@@ -263,12 +339,39 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 	let client = service.client();
 	let snapshot_service = service.snapshot_service();
 
+	// re-import transactions saved on last shutdown
+	let transactions_path = db_dirs.transactions_path();
+	match shutdown::restore_transactions(&client, &miner, &transactions_path) {
+		Ok(0) => {},
+		Ok(count) => info!("Restored {} transactions from the previous session.", count),
+		Err(e) => warn!("Failed to restore transactions from the previous session: {}", e),
+	}
+
 	// create external miner
 	let external_miner = Arc::new(ExternalMiner::default());
 
+	// restrict connections to permitted nodes on private networks
+	let node_filter = node_permission_contract.map(|address| {
+		info!("Node permissions enforced by contract 0x{}", address.hex());
+		let filter = NodeFilter::new(&client, address);
+		service.add_notify(filter.clone());
+		filter as Arc<ConnectionFilter>
+	});
+
+	// serve light clients from the full client
+	let light_serving = match cmd.serve_light {
+		true => Some(LightServing {
+			provider: Arc::new(LightClientProvider::new(client.clone())) as Arc<LightProvider>,
+			engine: engine,
+			buffer_limit: cmd.light_buffer_limit,
+			buffer_recharge: cmd.light_buffer_recharge,
+		}),
+		false => None,
+	};
+
 	// create sync object
 	let (sync_provider, manage_network, chain_notify) = try!(modules::sync(
-		&mut hypervisor, sync_config, net_conf.into(), client.clone(), snapshot_service.clone(), &cmd.logger_config,
+		&mut hypervisor, sync_config, net_conf.into(), client.clone(), snapshot_service.clone(), node_filter, light_serving, private_tx.clone(), &cmd.logger_config,
 	).map_err(|e| format!("Sync error: {}", e)));
 
 	service.add_notify(chain_notify.clone());
@@ -278,6 +381,16 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 		chain_notify.start();
 	}
 
+	// periodically checked clock drift
+	let time_checker = Arc::new(TimeChecker::new(cmd.ntp_server.clone()));
+
+	// node health checks, shared by the rpc and metrics servers
+	let health = Arc::new(NodeHealth::new(Arc::new(rpc_apis::FullSyncStatus {
+		client: client.clone(),
+		sync: sync_provider.clone(),
+		net: manage_network.clone(),
+	}), time_checker.clone()));
+
 	// set up dependencies for rpc servers
 	let signer_path = cmd.signer_conf.signer_path.clone();
 	let deps_for_rpc_apis = Arc::new(rpc_apis::Dependencies {
@@ -295,6 +408,7 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 		settings: Arc::new(cmd.net_settings.clone()),
 		net_service: manage_network.clone(),
 		geth_compatibility: cmd.geth_compatibility,
+		rpc_gas_cap: cmd.rpc_gas_cap,
 		dapps_interface: match cmd.dapps_conf.enabled {
 			true => Some(cmd.dapps_conf.interface.clone()),
 			false => None,
@@ -303,6 +417,9 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 			true => Some(cmd.dapps_conf.port),
 			false => None,
 		},
+		health: health.clone(),
+		access_policy: Arc::new(rpc::access_policy(&cmd.http_conf)),
+		private_tx: private_tx,
 	});
 
 	let dependencies = rpc::Dependencies {
@@ -311,8 +428,10 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 	};
 
 	// start rpc servers
+	request_log::configure(cmd.rpc_log, cmd.rpc_slow_query);
 	let http_server = try!(rpc::new_http(cmd.http_conf, &dependencies));
 	let ipc_server = try!(rpc::new_ipc(cmd.ipc_conf, &dependencies));
+	let ws_server = try!(rpc::new_ws(cmd.ws_conf, &dependencies));
 
 	let dapps_deps = dapps::Dependencies {
 		panic_handler: panic_handler.clone(),
@@ -332,6 +451,16 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 	// start signer server
 	let signer_server = try!(signer::start(cmd.signer_conf.clone(), signer_deps));
 
+	let metrics_deps = metrics::Dependencies {
+		client: client.clone(),
+		miner: miner.clone(),
+		sync: sync_provider.clone(),
+		health: health.clone(),
+	};
+
+	// start metrics server
+	let metrics_server = try!(metrics::new(cmd.metrics_conf.clone(), metrics_deps));
+
 	let informant = Arc::new(Informant::new(
 		service.client(),
 		Some(sync_provider.clone()),
@@ -341,12 +470,17 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 	));
 	let info_notify: Arc<ChainNotify> = informant.clone();
 	service.add_notify(info_notify);
+
+	// check for new releases periodically
+	let updater = Updater::new(&client, &sync_provider, cmd.update_policy, cmd.dirs.updates_path());
 	let io_handler = Arc::new(ClientIoHandler {
 		client: service.client(),
 		info: informant,
 		sync: sync_provider.clone(),
 		net: manage_network.clone(),
 		accounts: account_provider.clone(),
+		time_checker: time_checker,
+		updater: updater,
 		shutdown: Default::default(),
 	});
 	service.register_io_handler(io_handler.clone()).expect("Error registering IO handler");
@@ -354,6 +488,7 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 	// save user defaults
 	user_defaults.pruning = algorithm;
 	user_defaults.tracing = tracing;
+	user_defaults.tracing_addresses = tracing_addresses;
 	user_defaults.fat_db = fat_db;
 	user_defaults.mode = mode;
 	try!(user_defaults.save(&user_defaults_path));
@@ -390,13 +525,19 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<(), String> {
 	}
 
 	// Handle exit
-	wait_for_exit(panic_handler, http_server, ipc_server, dapps_server, signer_server);
+	wait_for_exit(panic_handler, http_server, ipc_server, ws_server, dapps_server, signer_server, metrics_server);
 
 	// to make sure timer does not spawn requests while shutdown is in progress
-	io_handler.shutdown.store(true, ::std::sync::atomic::Ordering::SeqCst);
+	io_handler.shutdown.store(true, Ordering::SeqCst);
 	// just Arc is dropping here, to allow other reference release in its default time
 	drop(io_handler);
 
+	// stop walking the restored state, the verification thread holds the database
+	snapshot_service.abort_verification();
+
+	// stop sealing, save the transaction queue and flush the database
+	shutdown::execute(&service.client(), &miner, &transactions_path, cmd.shutdown_timeout);
+
 	// hypervisor should be shutdown first while everything still works and can be
 	// terminated gracefully
 	drop(hypervisor);
@@ -430,7 +571,7 @@ fn prepare_account_provider(dirs: &Directories, cfg: AccountsConfig) -> Result<A
 	let dir = Box::new(try!(DiskDirectory::create(dirs.keys.clone()).map_err(|e| format!("Could not open keys directory: {}", e))));
 	let account_service = AccountProvider::new(Box::new(
 		try!(EthStore::open_with_iterations(dir, cfg.iterations).map_err(|e| format!("Could not open keys directory: {}", e)))
-	));
+	)).with_min_phrase_entropy(cfg.min_phrase_entropy);
 
 	for a in cfg.unlocked_accounts {
 		if passwords.iter().find(|p| account_service.unlock_account_permanently(a, (*p).clone()).is_ok()).is_none() {
@@ -445,14 +586,23 @@ fn wait_for_exit(
 	panic_handler: Arc<PanicHandler>,
 	_http_server: Option<HttpServer>,
 	_ipc_server: Option<IpcServer>,
+	_ws_server: Option<WsServer>,
 	_dapps_server: Option<WebappServer>,
-	_signer_server: Option<SignerServer>
+	_signer_server: Option<SignerServer>,
+	_metrics_server: Option<MetricsServer>
 	) {
 	let exit = Arc::new(Condvar::new());
 
-	// Handle possible exits
+	// Handle possible exits; a second signal forces the exit
 	let e = exit.clone();
-	CtrlC::set_handler(move || { e.notify_all(); });
+	let interrupted = AtomicBool::new(false);
+	CtrlC::set_handler(move || {
+		if interrupted.swap(true, Ordering::SeqCst) {
+			warn!("Forcing exit.");
+			process::exit(1);
+		}
+		e.notify_all();
+	});
 
 	// Handle panics
 	let e = exit.clone();
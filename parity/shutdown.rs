@@ -0,0 +1,126 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Coordinated shutdown: stops sealing, persists the transaction queue and flushes the database.
+
+use std::{fs, process, thread};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+use rlp::{RlpStream, UntrustedRlp, View, Stream};
+use ethcore::client::Client;
+use ethcore::miner::{Miner, MinerService, LocalTransactionStatus};
+use ethcore::transaction::SignedTransaction;
+
+/// Shuts the client down, forcing exit if it takes longer than `timeout`.
+pub fn execute(client: &Client, miner: &Miner, transactions_path: &Path, timeout: Duration) {
+	let (done, wait) = mpsc::channel::<()>();
+	let watchdog = thread::spawn(move || {
+		if let Err(mpsc::RecvTimeoutError::Timeout) = wait.recv_timeout(timeout) {
+			warn!("Shutdown did not finish within {}s. Forcing exit.", timeout.as_secs());
+			process::exit(1);
+		}
+	});
+
+	miner.stop_sealing();
+
+	match save_transactions(miner, transactions_path) {
+		Ok(count) => info!("Saved {} queued transactions.", count),
+		Err(e) => warn!("Failed to save queued transactions: {}", e),
+	}
+
+	if let Err(e) = client.flush_to_disk() {
+		warn!("Failed to flush the database: {}", e);
+	}
+
+	let _ = done.send(());
+	let _ = watchdog.join();
+}
+
+fn encode_transactions(local: &[SignedTransaction], external: &[SignedTransaction]) -> Vec<u8> {
+	let mut stream = RlpStream::new_list(2);
+	stream.append(&local.to_vec());
+	stream.append(&external.to_vec());
+	stream.out()
+}
+
+fn decode_transactions(bytes: &[u8]) -> Result<(Vec<SignedTransaction>, Vec<SignedTransaction>), String> {
+	let rlp = UntrustedRlp::new(bytes);
+	let local = try!(rlp.val_at(0).map_err(|e| format!("{:?}", e)));
+	let external = try!(rlp.val_at(1).map_err(|e| format!("{:?}", e)));
+	Ok((local, external))
+}
+
+/// Writes current transactions from the queue to disk. Returns number of saved transactions.
+fn save_transactions(miner: &Miner, path: &Path) -> Result<usize, String> {
+	let locals = miner.local_transactions();
+	let (local, external): (Vec<_>, Vec<_>) = miner.all_transactions().into_iter().partition(|tx| {
+		match locals.get(&tx.hash()) {
+			Some(&LocalTransactionStatus::Pending) | Some(&LocalTransactionStatus::Future) => true,
+			_ => false,
+		}
+	});
+
+	let mut file = try!(fs::File::create(path).map_err(|e| e.to_string()));
+	try!(file.write_all(&encode_transactions(&local, &external)).map_err(|e| e.to_string()));
+	Ok(local.len() + external.len())
+}
+
+/// Re-imports transactions saved during the last shutdown.
+pub fn restore_transactions(client: &Client, miner: &Miner, path: &Path) -> Result<usize, String> {
+	let mut bytes = Vec::new();
+	match fs::File::open(path) {
+		Ok(mut file) => try!(file.read_to_end(&mut bytes).map_err(|e| e.to_string())),
+		Err(_) => return Ok(0),
+	};
+	// the file is only valid for a single restart.
+	let _ = fs::remove_file(path);
+
+	let (local, external) = try!(decode_transactions(&bytes));
+	let mut imported = 0;
+	for tx in local {
+		if miner.import_own_transaction(client, tx).is_ok() {
+			imported += 1;
+		}
+	}
+	imported += miner.import_external_transactions(client, external).into_iter().filter(|r| r.is_ok()).count();
+	Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+	use ethcore::transaction::{Transaction, Action};
+	use ethcore::ethstore::ethkey::{Generator, Random};
+	use super::{encode_transactions, decode_transactions};
+
+	#[test]
+	fn should_encode_and_decode_transactions() {
+		let keypair = Random.generate().unwrap();
+		let tx = Transaction {
+			action: Action::Create,
+			value: 0.into(),
+			data: vec![],
+			gas: 21_000.into(),
+			gas_price: 1.into(),
+			nonce: 0.into(),
+		}.sign(keypair.secret(), None);
+
+		let encoded = encode_transactions(&[tx.clone()], &[]);
+
+		assert_eq!(decode_transactions(&encoded), Ok((vec![tx], vec![])));
+	}
+}
@@ -0,0 +1,149 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction permissions for private networks.
+//!
+//! Transactions are only queued and sealed when allowed by the contract configured with
+//! `transactionPermissionContract` in the chain spec. Answers are cached for the current best block.
+//! Transactions are allowed whenever the contract cannot be queried, e.g. before it is deployed.
+
+use std::collections::HashMap;
+use ethabi::{Interface, Contract, Token};
+use ethcore::client::{MiningBlockChainClient, BlockID};
+use ethcore::miner::TransactionFilter;
+use ethcore::transaction::{Transaction, SignedTransaction, Action};
+use util::{Mutex, Address, H256, U256, Bytes};
+use registrar::as_string;
+
+/// Parameters the permission contract decides on: sender, recipient, value and whether it creates a contract.
+type Permission = (Address, Address, U256, bool);
+
+fn permission(tx: &SignedTransaction) -> Result<Permission, String> {
+	let sender = try!(tx.sender().map_err(as_string));
+	Ok(match tx.action {
+		Action::Create => (sender, Address::default(), tx.value, true),
+		Action::Call(ref to) => (sender, to.clone(), tx.value, false),
+	})
+}
+
+fn encode_params(permission: &Permission) -> Vec<Token> {
+	let (ref sender, ref to, ref value, create) = *permission;
+	let mut value_param = [0u8; 32];
+	value.to_big_endian(&mut value_param);
+	vec![Token::Address(**sender), Token::Address(**to), Token::Uint(value_param), Token::Bool(create)]
+}
+
+/// Transaction filter backed by the transaction permission contract.
+pub struct ContractTransactionFilter {
+	contract: Contract,
+	contract_address: Address,
+	cache: Mutex<(H256, HashMap<Permission, bool>)>,
+}
+
+impl ContractTransactionFilter {
+	/// Creates new filter querying the contract at `contract_address`.
+	pub fn new(contract_address: Address) -> Self {
+		let interface = Interface::load(include_bytes!("res/tx_acl.json")).expect("tx_acl.json is valid ABI");
+		ContractTransactionFilter {
+			contract: Contract::new(interface),
+			contract_address: contract_address,
+			cache: Mutex::new((H256::default(), HashMap::new())),
+		}
+	}
+
+	fn query(&self, chain: &MiningBlockChainClient, permission: &Permission) -> Result<bool, String> {
+		let function = try!(self.contract.function("transactionAllowed".into()).map_err(as_string));
+		let data = try!(function.encode_call(encode_params(permission)).map_err(as_string));
+		let output = try!(self.call(chain, data));
+		match try!(function.decode_output(output).map_err(as_string)).get(0) {
+			Some(&Token::Bool(allowed)) => Ok(allowed),
+			e => Err(format!("Invalid result: {:?}", e)),
+		}
+	}
+
+	fn call(&self, chain: &MiningBlockChainClient, data: Bytes) -> Result<Bytes, String> {
+		let from = Address::default();
+		let transaction = Transaction {
+			nonce: chain.latest_nonce(&from),
+			action: Action::Call(self.contract_address),
+			gas: U256::from(50_000_000),
+			gas_price: U256::default(),
+			value: U256::default(),
+			data: data,
+		}.fake_sign(from);
+
+		chain.call(&transaction, BlockID::Latest, Default::default())
+			.map_err(as_string)
+			.map(|executed| executed.output)
+	}
+}
+
+impl TransactionFilter for ContractTransactionFilter {
+	fn transaction_allowed(&self, chain: &MiningBlockChainClient, transaction: &SignedTransaction) -> bool {
+		let permission = match permission(transaction) {
+			Ok(permission) => permission,
+			// invalid signatures are rejected by the queue itself.
+			Err(_) => return true,
+		};
+
+		let best_block = chain.chain_info().best_block_hash;
+		{
+			let mut cache = self.cache.lock();
+			if cache.0 != best_block {
+				*cache = (best_block, HashMap::new());
+			}
+			if let Some(allowed) = cache.1.get(&permission) {
+				return *allowed;
+			}
+		}
+
+		match self.query(chain, &permission) {
+			Ok(allowed) => {
+				self.cache.lock().1.insert(permission, allowed);
+				allowed
+			},
+			Err(e) => {
+				debug!(target: "miner", "Error checking transaction permissions of {:?}: {}", transaction.hash(), e);
+				true
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use ethabi::Token;
+	use util::{Address, U256};
+	use super::encode_params;
+
+	#[test]
+	fn should_encode_permission_params() {
+		let sender = Address::from(1);
+		let params = encode_params(&(sender, Address::default(), U256::from(256), true));
+
+		let mut value = [0u8; 32];
+		value[30] = 1;
+		match (&params[0], &params[1], &params[2], &params[3]) {
+			(&Token::Address(ref s), &Token::Address(ref t), &Token::Uint(ref v), &Token::Bool(create)) => {
+				assert_eq!(s, &*sender);
+				assert_eq!(t, &[0u8; 20]);
+				assert_eq!(v, &value);
+				assert!(create);
+			},
+			_ => panic!("Unexpected params: {:?}", params),
+		}
+	}
+}
@@ -0,0 +1,486 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Auto-updater driven by the on-chain operations contract.
+//!
+//! Release metadata is read from the `operations` contract resolved through the registrar.
+//! New binaries are fetched through `hash_fetch`, verified against the registered checksum
+//! and installed into the updates directory, depending on the configured `UpdatePolicy`.
+//! The most recently installed release is run instead of this binary on the next start.
+
+use std::{env, fs, io};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::{Arc, Weak};
+use ethabi::{Interface, Contract, Token};
+use ethcore::client::{Client, BlockChainClient};
+use ethsync::SyncProvider;
+use ethcore_rpc::is_major_importing;
+use hash_fetch::{self, HashFetch};
+use hash_fetch::registrar::RegistrarContract;
+use util::{Mutex, H160, H256, Hashable};
+use util::misc::{sha, platform};
+use registrar::{Registrar, as_string};
+
+/// Which releases should be installed automatically.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum UpdateFilter {
+	/// All releases following the same track.
+	All,
+	/// Only critical releases.
+	Critical,
+	/// None; releases are only reported.
+	None,
+}
+
+impl FromStr for UpdateFilter {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"all" => Ok(UpdateFilter::All),
+			"critical" => Ok(UpdateFilter::Critical),
+			"none" => Ok(UpdateFilter::None),
+			other => Err(format!("Invalid auto-update policy: {}", other)),
+		}
+	}
+}
+
+/// Update policy.
+#[derive(Debug, PartialEq, Clone)]
+pub struct UpdatePolicy {
+	/// Download and install releases matching the filter.
+	pub enable_downloading: bool,
+	/// Which releases to install.
+	pub filter: UpdateFilter,
+}
+
+impl Default for UpdatePolicy {
+	fn default() -> Self {
+		UpdatePolicy {
+			enable_downloading: false,
+			filter: UpdateFilter::None,
+		}
+	}
+}
+
+impl From<UpdateFilter> for UpdatePolicy {
+	fn from(filter: UpdateFilter) -> Self {
+		UpdatePolicy {
+			enable_downloading: filter != UpdateFilter::None,
+			filter: filter,
+		}
+	}
+}
+
+impl UpdatePolicy {
+	fn should_install(&self, release: &ReleaseInfo) -> bool {
+		self.enable_downloading && match self.filter {
+			UpdateFilter::All => true,
+			UpdateFilter::Critical => release.is_critical,
+			UpdateFilter::None => false,
+		}
+	}
+}
+
+/// Release track.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ReleaseTrack {
+	Stable,
+	Beta,
+	Nightly,
+	Testing,
+	Unknown,
+}
+
+impl From<u8> for ReleaseTrack {
+	fn from(track: u8) -> Self {
+		match track {
+			1 => ReleaseTrack::Stable,
+			2 => ReleaseTrack::Beta,
+			3 => ReleaseTrack::Nightly,
+			4 => ReleaseTrack::Testing,
+			_ => ReleaseTrack::Unknown,
+		}
+	}
+}
+
+impl ReleaseTrack {
+	fn as_u8(&self) -> u8 {
+		match *self {
+			ReleaseTrack::Stable => 1,
+			ReleaseTrack::Beta => 2,
+			ReleaseTrack::Nightly => 3,
+			ReleaseTrack::Testing => 4,
+			ReleaseTrack::Unknown => 0,
+		}
+	}
+}
+
+/// Release metadata registered in the operations contract.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ReleaseInfo {
+	/// Release identifier (commit hash).
+	pub hash: H256,
+	/// Semantic version, `major.minor.patch`.
+	pub version: String,
+	/// Release track.
+	pub track: ReleaseTrack,
+	/// Whether the release is critical.
+	pub is_critical: bool,
+	/// Block number of the fork the release supports.
+	pub fork: u64,
+	/// Checksum of the binary for our platform.
+	pub binary: Option<H256>,
+}
+
+fn version_from_semver(semver: u32) -> String {
+	format!("{}.{}.{}", semver >> 16, (semver >> 8) & 0xff, semver & 0xff)
+}
+
+fn u32_from_token(token: &[u8; 32]) -> u32 {
+	token[28..].iter().fold(0u32, |acc, b| (acc << 8) | *b as u32)
+}
+
+/// Client identifier used in the operations contract.
+fn client_id() -> H256 {
+	let mut id = H256::new();
+	id[..6].copy_from_slice(b"parity");
+	id
+}
+
+/// `Operations` contract API.
+struct Operations {
+	contract: Contract,
+	registrar: RegistrarContract,
+}
+
+impl Operations {
+	fn new(registrar: RegistrarContract) -> Self {
+		let operations = Interface::load(include_bytes!("res/operations.json")).expect("operations.json is valid ABI");
+		Operations {
+			contract: Contract::new(operations),
+			registrar: registrar,
+		}
+	}
+
+	fn call(&self, name: &str, params: Vec<Token>) -> Result<Vec<Token>, String> {
+		let address = try!(self.registrar.get_address("operations"));
+		let function = try!(self.contract.function(name.into()).map_err(as_string));
+		let data = try!(function.encode_call(params).map_err(as_string));
+		let output = try!(self.registrar.client().call(address, data));
+		function.decode_output(output).map_err(as_string)
+	}
+
+	fn latest_in_track(&self, track: ReleaseTrack) -> Result<H256, String> {
+		let mut track_param = [0u8; 32];
+		track_param[31] = track.as_u8();
+		match try!(self.call("latestInTrack", vec![Token::FixedBytes(client_id().to_vec()), Token::Uint(track_param)])).get(0) {
+			Some(&Token::FixedBytes(ref hash)) if hash.len() == 32 => Ok(H256::from_slice(hash)),
+			e => Err(format!("Invalid result: {:?}", e)),
+		}
+	}
+
+	fn release(&self, hash: H256) -> Result<ReleaseInfo, String> {
+		let result = try!(self.call("release", vec![Token::FixedBytes(client_id().to_vec()), Token::FixedBytes(hash.to_vec())]));
+		match (result.get(0), result.get(1), result.get(2), result.get(3)) {
+			(Some(&Token::Uint(ref fork)), Some(&Token::Uint(ref track)), Some(&Token::Uint(ref semver)), Some(&Token::Bool(critical))) => Ok(ReleaseInfo {
+				hash: hash,
+				version: version_from_semver(u32_from_token(semver)),
+				track: ReleaseTrack::from(track[31]),
+				is_critical: critical,
+				fork: u32_from_token(fork) as u64,
+				binary: None,
+			}),
+			e => Err(format!("Invalid result: {:?}", e)),
+		}
+	}
+
+	fn checksum(&self, hash: H256, platform: H256) -> Result<Option<H256>, String> {
+		let result = try!(self.call("checksum", vec![Token::FixedBytes(client_id().to_vec()), Token::FixedBytes(hash.to_vec()), Token::FixedBytes(platform.to_vec())]));
+		match result.get(0) {
+			Some(&Token::FixedBytes(ref checksum)) if checksum.len() == 32 => {
+				let checksum = H256::from_slice(checksum);
+				Ok(if checksum.is_zero() { None } else { Some(checksum) })
+			},
+			e => Err(format!("Invalid result: {:?}", e)),
+		}
+	}
+}
+
+#[derive(Default)]
+struct UpdaterState {
+	latest: Option<ReleaseInfo>,
+	fetching: Option<ReleaseInfo>,
+	installed: Option<ReleaseInfo>,
+}
+
+/// Checks the operations contract for new releases and installs them according to the policy.
+pub struct Updater {
+	client: Weak<Client>,
+	sync: Weak<SyncProvider>,
+	operations: Operations,
+	fetcher: hash_fetch::Client,
+	policy: UpdatePolicy,
+	updates_path: PathBuf,
+	this: Option<H256>,
+	platform: H256,
+	state: Mutex<UpdaterState>,
+	weak_self: Mutex<Weak<Updater>>,
+}
+
+impl Updater {
+	/// Creates new `Updater` storing downloaded releases in `updates_path`.
+	pub fn new(client: &Arc<Client>, sync: &Arc<SyncProvider>, policy: UpdatePolicy, updates_path: PathBuf) -> Arc<Self> {
+		let contract_client = Arc::new(Registrar::new(client.clone()));
+		let updater = Arc::new(Updater {
+			client: Arc::downgrade(client),
+			sync: Arc::downgrade(sync),
+			operations: Operations::new(RegistrarContract::new(contract_client.clone())),
+			fetcher: hash_fetch::Client::new(contract_client),
+			policy: policy,
+			updates_path: updates_path,
+			this: H160::from_str(sha()).ok().map(H256::from),
+			platform: platform().sha3(),
+			state: Mutex::new(Default::default()),
+			weak_self: Mutex::new(Weak::new()),
+		});
+		*updater.weak_self.lock() = Arc::downgrade(&updater);
+		updater
+	}
+
+	/// Checks for a new release, unless the chain is still being synced. Called periodically
+	/// from an IO worker, so that contract calls and downloads stay off the import path.
+	pub fn tick(&self) {
+		let importing = match (self.client.upgrade(), self.sync.upgrade()) {
+			(Some(client), Some(sync)) => is_major_importing(Some(sync.status().state), client.queue_info()),
+			_ => return,
+		};
+
+		if !importing {
+			self.poll();
+		}
+	}
+
+	/// Checks for a new release in our track.
+	pub fn poll(&self) {
+		let this = match self.this {
+			Some(this) => this,
+			None => {
+				trace!(target: "updater", "Unknown build commit; skipping update check.");
+				return;
+			},
+		};
+
+		let latest = match self.latest_release(this) {
+			Ok(Some(latest)) => latest,
+			Ok(None) => return,
+			Err(e) => {
+				debug!(target: "updater", "Unable to check for updates: {}", e);
+				return;
+			},
+		};
+
+		let mut state = self.state.lock();
+		if state.latest.as_ref() != Some(&latest) {
+			info!("New {}release v{} ({:?}) available.", if latest.is_critical { "critical " } else { "" }, latest.version, latest.track);
+			state.latest = Some(latest.clone());
+		}
+
+		if !self.policy.should_install(&latest) || state.fetching.is_some() || state.installed.as_ref() == Some(&latest) {
+			return;
+		}
+
+		let binary = match latest.binary {
+			Some(binary) => binary,
+			None => {
+				warn!(target: "updater", "Release v{} has no binary for {}.", latest.version, platform());
+				return;
+			},
+		};
+
+		info!("Downloading release v{}...", latest.version);
+		state.fetching = Some(latest.clone());
+		drop(state);
+
+		let weak_self = self.weak_self.lock().clone();
+		if let Err(e) = self.fetcher.fetch(binary, Box::new(move |result| {
+			if let Some(updater) = weak_self.upgrade() {
+				updater.fetched(result);
+			}
+		})) {
+			warn!(target: "updater", "Unable to fetch release v{}: {:?}", latest.version, e);
+			self.state.lock().fetching = None;
+		}
+	}
+
+	/// Returns the latest release in our track if it differs from the running one.
+	fn latest_release(&self, this: H256) -> Result<Option<ReleaseInfo>, String> {
+		let current = try!(self.operations.release(this));
+		if current.track == ReleaseTrack::Unknown {
+			trace!(target: "updater", "Running release is not registered; skipping update check.");
+			return Ok(None);
+		}
+
+		let latest_hash = try!(self.operations.latest_in_track(current.track));
+		if latest_hash == this || latest_hash.is_zero() {
+			return Ok(None);
+		}
+
+		let mut latest = try!(self.operations.release(latest_hash));
+		latest.binary = try!(self.operations.checksum(latest_hash, self.platform));
+		Ok(Some(latest))
+	}
+
+	fn fetched(&self, result: Result<PathBuf, hash_fetch::Error>) {
+		let mut state = self.state.lock();
+		let release = match state.fetching.take() {
+			Some(release) => release,
+			None => return,
+		};
+
+		match result.map_err(as_string).and_then(|path| self.install(&release, &path).map_err(as_string)) {
+			Ok(path) => {
+				info!("Installed release v{} to {}. Restart Parity to use it.", release.version, path.display());
+				state.installed = Some(release);
+			},
+			Err(e) => warn!(target: "updater", "Unable to install release v{}: {}", release.version, e),
+		}
+	}
+
+	/// Copies verified binary into the updates directory and marks it as the latest one.
+	fn install(&self, release: &ReleaseInfo, binary: &Path) -> io::Result<PathBuf> {
+		try!(fs::create_dir_all(&self.updates_path));
+		let dest = self.updates_path.join(binary_name(release));
+		try!(fs::copy(binary, &dest));
+		try!(make_executable(&dest));
+		try!(write_latest(&self.updates_path, &dest));
+		Ok(dest)
+	}
+}
+
+/// Records the path of the most recently installed binary in `latest` file.
+fn write_latest(updates_path: &Path, binary: &Path) -> io::Result<()> {
+	let mut file = try!(fs::File::create(updates_path.join("latest")));
+	file.write_all(binary.to_string_lossy().as_bytes())
+}
+
+/// Path of the most recently installed binary, if any.
+fn read_latest(updates_path: &Path) -> io::Result<PathBuf> {
+	let mut latest = String::new();
+	try!(try!(fs::File::open(updates_path.join("latest"))).read_to_string(&mut latest));
+	Ok(PathBuf::from(latest.trim()))
+}
+
+/// Set for the installed release started by `run_latest`, so that it doesn't start another one.
+const RUNNING_UPDATE_ENV: &'static str = "PARITY_RUNNING_UPDATE";
+
+/// Runs the most recently installed release with the arguments of this process, unless it is this binary.
+/// Returns its exit code, or `None` if this binary should go on.
+pub fn run_latest(updates_path: &Path) -> Option<i32> {
+	if env::var_os(RUNNING_UPDATE_ENV).is_some() {
+		return None;
+	}
+	let latest = match read_latest(updates_path) {
+		Ok(ref latest) if latest.is_file() => latest.clone(),
+		_ => return None,
+	};
+	let current = env::current_exe().and_then(fs::canonicalize).ok();
+	if fs::canonicalize(&latest).ok() == current {
+		return None;
+	}
+
+	let args: Vec<String> = env::args().skip(1).collect();
+	match Command::new(&latest).args(&args).env(RUNNING_UPDATE_ENV, "1").status() {
+		Ok(status) => Some(status.code().unwrap_or(1)),
+		Err(e) => {
+			writeln!(&mut io::stderr(), "Unable to run installed release {}: {}", latest.display(), e).expect("StdErr available; qed");
+			None
+		},
+	}
+}
+
+fn binary_name(release: &ReleaseInfo) -> String {
+	format!("parity-{}-{:?}", release.version, H160::from(release.hash))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> io::Result<()> {
+	use std::os::unix::fs::PermissionsExt;
+	let mut permissions = try!(fs::metadata(path)).permissions();
+	permissions.set_mode(0o755);
+	fs::set_permissions(path, permissions)
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> io::Result<()> {
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+	use devtools::RandomTempPath;
+	use super::{UpdateFilter, UpdatePolicy, ReleaseInfo, ReleaseTrack, version_from_semver, binary_name, write_latest, read_latest};
+
+	fn release(critical: bool) -> ReleaseInfo {
+		ReleaseInfo {
+			hash: 5.into(),
+			version: "1.5.1".into(),
+			track: ReleaseTrack::Beta,
+			is_critical: critical,
+			fork: 0,
+			binary: Some(1.into()),
+		}
+	}
+
+	#[test]
+	fn should_parse_update_filter() {
+		assert_eq!("all".parse(), Ok(UpdateFilter::All));
+		assert_eq!("critical".parse(), Ok(UpdateFilter::Critical));
+		assert_eq!("none".parse(), Ok(UpdateFilter::None));
+		assert!("some".parse::<UpdateFilter>().is_err());
+	}
+
+	#[test]
+	fn should_install_according_to_policy() {
+		let all = UpdatePolicy::from(UpdateFilter::All);
+		let critical = UpdatePolicy::from(UpdateFilter::Critical);
+		let none = UpdatePolicy::from(UpdateFilter::None);
+
+		assert!(all.should_install(&release(false)));
+		assert!(!critical.should_install(&release(false)));
+		assert!(critical.should_install(&release(true)));
+		assert!(!none.should_install(&release(true)));
+	}
+
+	#[test]
+	fn should_format_version() {
+		assert_eq!(version_from_semver((1 << 16) + (5 << 8) + 2), "1.5.2");
+		assert_eq!(binary_name(&release(false)), "parity-1.5.1-0000000000000000000000000000000000000005");
+	}
+
+	#[test]
+	fn should_read_latest_installed_release() {
+		let temp = RandomTempPath::create_dir();
+		assert!(read_latest(temp.as_path()).is_err());
+
+		write_latest(temp.as_path(), Path::new("/updates/parity-1.5.1")).unwrap();
+		assert_eq!(read_latest(temp.as_path()).unwrap(), Path::new("/updates/parity-1.5.1"));
+	}
+}
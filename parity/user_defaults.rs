@@ -25,6 +25,7 @@ use serde::de::impls::BTreeMapVisitor;
 use serde_json::Value;
 use serde_json::de::from_reader;
 use serde_json::ser::to_string;
+use util::Address;
 use util::journaldb::Algorithm;
 use ethcore::client::Mode;
 
@@ -32,6 +33,7 @@ pub struct UserDefaults {
 	pub is_first_launch: bool,
 	pub pruning: Algorithm,
 	pub tracing: bool,
+	pub tracing_addresses: Vec<Address>,
 	pub fat_db: bool,
 	pub mode: Mode,
 }
@@ -42,6 +44,7 @@ impl Serialize for UserDefaults {
 		let mut map: BTreeMap<String, Value> = BTreeMap::new();
 		map.insert("pruning".into(), Value::String(self.pruning.as_str().into()));
 		map.insert("tracing".into(), Value::Bool(self.tracing));
+		map.insert("tracing_addresses".into(), Value::Array(self.tracing_addresses.iter().map(|a| Value::String(a.hex())).collect()));
 		map.insert("fat_db".into(), Value::Bool(self.fat_db));
 		let mode_str = match self.mode {
 			Mode::Off => "offline",
@@ -82,6 +85,12 @@ impl Visitor for UserDefaultsVisitor {
 		let pruning = try!(pruning.parse().map_err(|_| Error::custom("invalid pruning method")));
 		let tracing: Value = try!(map.remove("tracing".into()).ok_or_else(|| Error::custom("missing tracing")));
 		let tracing = try!(tracing.as_bool().ok_or_else(|| Error::custom("invalid tracing value")));
+		let tracing_addresses: Value = map.remove("tracing_addresses".into()).unwrap_or_else(|| Value::Array(Vec::new()));
+		let tracing_addresses = try!(tracing_addresses.as_array().ok_or_else(|| Error::custom("invalid tracing_addresses value")));
+		let tracing_addresses = try!(tracing_addresses.iter()
+			.map(|a| a.as_str().and_then(|a| a.parse().ok()))
+			.collect::<Option<Vec<Address>>>()
+			.ok_or_else(|| Error::custom("invalid tracing address")));
 		let fat_db: Value = map.remove("fat_db".into()).unwrap_or_else(|| Value::Bool(false));
 		let fat_db = try!(fat_db.as_bool().ok_or_else(|| Error::custom("invalid fat_db value")));
 
@@ -105,6 +114,7 @@ impl Visitor for UserDefaultsVisitor {
 			is_first_launch: false,
 			pruning: pruning,
 			tracing: tracing,
+			tracing_addresses: tracing_addresses,
 			fat_db: fat_db,
 			mode: mode,
 		};
@@ -119,6 +129,7 @@ impl Default for UserDefaults {
 			is_first_launch: true,
 			pruning: Algorithm::default(),
 			tracing: false,
+			tracing_addresses: Vec::new(),
 			fat_db: false,
 			mode: Mode::Active,
 		}
@@ -0,0 +1,219 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! HTTP middleware enforcing an `AccessPolicy` and the HTTP JSON-RPC server using it.
+
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use hyper::{self, server, header, Decoder, Encoder, Next, StatusCode};
+use hyper::net::HttpStream;
+use hyper::uri::RequestUri;
+use jsonrpc_core::IoHandler;
+use jsonrpc_http_server::{ServerHandler, PanicHandler};
+use v1::access_policy::AccessPolicy;
+use v1::request_log;
+
+/// Creates a handler responding to requests with disallowed `Host` header.
+pub type Rejection = fn() -> Box<server::Handler<HttpStream> + Send>;
+
+/// Validates `Host` and sets `Access-Control-Allow-Origin` headers of requests
+/// passed to the inner handler according to the shared policy.
+pub struct AccessControl<H> {
+	policy: Arc<AccessPolicy>,
+	handler: H,
+	rejection: Rejection,
+	rejected: Option<Box<server::Handler<HttpStream> + Send>>,
+	cors_header: Option<String>,
+	origin: Option<String>,
+}
+
+impl<H: server::Handler<HttpStream>> AccessControl<H> {
+	/// Wraps given handler.
+	pub fn new(policy: Arc<AccessPolicy>, handler: H) -> Self {
+		AccessControl {
+			policy: policy,
+			handler: handler,
+			rejection: forbidden,
+			rejected: None,
+			cors_header: None,
+			origin: None,
+		}
+	}
+
+	/// Use custom response for requests with disallowed `Host` header.
+	pub fn with_rejection(mut self, rejection: Rejection) -> Self {
+		self.rejection = rejection;
+		self
+	}
+}
+
+fn request_path(request: &server::Request<HttpStream>) -> String {
+	match *request.uri() {
+		RequestUri::AbsolutePath(ref path) => path.clone(),
+		RequestUri::AbsoluteUri(ref url) => url.path().to_owned(),
+		_ => "/".into(),
+	}
+}
+
+fn request_host(request: &server::Request<HttpStream>) -> Option<String> {
+	request.headers().get::<header::Host>().map(|host| match host.port {
+		Some(port) => format!("{}:{}", host.hostname, port),
+		None => host.hostname.clone(),
+	})
+}
+
+fn request_origin(request: &server::Request<HttpStream>) -> Option<String> {
+	request.headers().get_raw("Origin")
+		.and_then(|values| values.first())
+		.and_then(|value| String::from_utf8(value.clone()).ok())
+}
+
+impl<H: server::Handler<HttpStream>> server::Handler<HttpStream> for AccessControl<H> {
+	fn on_request(&mut self, request: server::Request<HttpStream>) -> Next {
+		let path = request_path(&request);
+		let host = request_host(&request);
+		let origin = request_origin(&request);
+
+		if !self.policy.is_host_allowed(&path, origin.as_ref().map(|o| o.as_str()), host.as_ref().map(|h| h.as_str())) {
+			debug!(target: "rpc", "Rejecting request to {} with disallowed host: {:?}", path, host);
+			let mut rejected = (self.rejection)();
+			let next = rejected.on_request(request);
+			self.rejected = Some(rejected);
+			return next;
+		}
+
+		self.cors_header = self.policy.cors_header(&path, origin.as_ref().map(|o| o.as_str()));
+		self.origin = origin;
+		self.handler.on_request(request)
+	}
+
+	fn on_request_readable(&mut self, decoder: &mut Decoder<HttpStream>) -> Next {
+		match self.rejected {
+			Some(ref mut rejected) => rejected.on_request_readable(decoder),
+			None => {
+				let handler = &mut self.handler;
+				request_log::with_origin(self.origin.clone(), || handler.on_request_readable(decoder))
+			},
+		}
+	}
+
+	fn on_response(&mut self, response: &mut server::Response) -> Next {
+		if let Some(ref mut rejected) = self.rejected {
+			return rejected.on_response(response);
+		}
+
+		let next = self.handler.on_response(response);
+		if let Some(ref cors_header) = self.cors_header {
+			response.headers_mut().set_raw("Access-Control-Allow-Origin", vec![cors_header.as_bytes().to_vec()]);
+		}
+		next
+	}
+
+	fn on_response_writable(&mut self, encoder: &mut Encoder<HttpStream>) -> Next {
+		match self.rejected {
+			Some(ref mut rejected) => rejected.on_response_writable(encoder),
+			None => self.handler.on_response_writable(encoder),
+		}
+	}
+}
+
+const FORBIDDEN: &'static str = "Provided Host header is not whitelisted.\n";
+
+struct Forbidden {
+	write_pos: usize,
+}
+
+fn forbidden() -> Box<server::Handler<HttpStream> + Send> {
+	Box::new(Forbidden { write_pos: 0 })
+}
+
+impl server::Handler<HttpStream> for Forbidden {
+	fn on_request(&mut self, _request: server::Request<HttpStream>) -> Next {
+		Next::write()
+	}
+
+	fn on_request_readable(&mut self, _decoder: &mut Decoder<HttpStream>) -> Next {
+		Next::write()
+	}
+
+	fn on_response(&mut self, response: &mut server::Response) -> Next {
+		response.set_status(StatusCode::Forbidden);
+		response.headers_mut().set_raw("Content-Type", vec![b"text/plain".to_vec()]);
+		response.headers_mut().set(header::ContentLength(FORBIDDEN.len() as u64));
+		Next::write()
+	}
+
+	fn on_response_writable(&mut self, encoder: &mut Encoder<HttpStream>) -> Next {
+		let bytes = FORBIDDEN.as_bytes();
+		if self.write_pos == bytes.len() {
+			return Next::end();
+		}
+
+		match encoder.write(&bytes[self.write_pos..]) {
+			Ok(written) => {
+				self.write_pos += written;
+				Next::write()
+			},
+			Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => Next::write(),
+			Err(_) => Next::end(),
+		}
+	}
+}
+
+/// HTTP JSON-RPC server.
+pub struct Server {
+	listening: Option<server::Listening>,
+}
+
+impl Drop for Server {
+	fn drop(&mut self) {
+		if let Some(listening) = self.listening.take() {
+			listening.close();
+		}
+	}
+}
+
+/// Start HTTP JSON-RPC server validating requests against given policy.
+pub fn start(
+	addr: &SocketAddr,
+	handler: Arc<IoHandler>,
+	policy: Arc<AccessPolicy>,
+	panic_handler: Arc<Mutex<Option<Box<Fn() -> () + Send>>>>,
+) -> Result<Server, io::Error> {
+	let to_io = |e: hyper::Error| match e {
+		hyper::Error::Io(e) => e,
+		e => io::Error::new(io::ErrorKind::Other, format!("{:?}", e)),
+	};
+
+	let (listening, server) = try!(try!(hyper::Server::http(addr).map_err(&to_io))
+		.handle(move |control| AccessControl::new(policy.clone(), ServerHandler::new(
+			handler.clone(),
+			// CORS and hosts are handled by `AccessControl`.
+			None,
+			None,
+			PanicHandler { handler: panic_handler.clone() },
+			control,
+		)))
+		.map_err(&to_io));
+
+	thread::spawn(move || server.run());
+
+	Ok(Server {
+		listening: Some(listening),
+	})
+}
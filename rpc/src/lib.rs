@@ -24,6 +24,7 @@ extern crate serde;
 extern crate serde_json;
 extern crate jsonrpc_core;
 extern crate jsonrpc_http_server;
+extern crate hyper;
 
 extern crate ethcore_io as io;
 extern crate ethcore;
@@ -48,15 +49,20 @@ extern crate ethjson;
 #[cfg(test)]
 extern crate ethcore_devtools as devtools;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::net::SocketAddr;
 use io::PanicHandler;
 use self::jsonrpc_core::{IoHandler, IoDelegate};
 
-pub use jsonrpc_http_server::{ServerBuilder, Server, RpcServerError};
+pub use jsonrpc_http_server::{ServerBuilder, RpcServerError};
 pub mod v1;
+pub mod http;
+pub use http::{AccessControl, Server};
 pub use v1::{SigningQueue, SignerService, ConfirmationsQueue, NetworkSettings};
 pub use v1::block_import::is_major_importing;
+pub use v1::health::NodeHealth;
+pub use v1::access_policy::AccessPolicy;
+pub use v1::request_log;
 
 /// An object that can be extended with `IoDelegates`
 pub trait Extendable {
@@ -69,6 +75,12 @@ pub struct RpcServer {
 	handler: Arc<jsonrpc_core::io::IoHandler>,
 }
 
+impl Extendable for IoHandler {
+	fn add_delegate<D: Send + Sync + 'static>(&self, delegate: IoDelegate<D>) {
+		IoHandler::add_delegate(self, delegate);
+	}
+}
+
 impl Extendable for RpcServer {
 	/// Add io delegate.
 	fn add_delegate<D: Send + Sync + 'static>(&self, delegate: IoDelegate<D>) {
@@ -85,31 +97,18 @@ impl RpcServer {
 	}
 
 	/// Start http server asynchronously and returns result with `Server` handle on success or an error.
+	/// Requests are validated against given access policy, which may be changed while the server is running.
 	pub fn start_http(
 		&self,
 		addr: &SocketAddr,
-		cors_domains: Option<Vec<String>>,
-		allowed_hosts: Option<Vec<String>>,
+		access_policy: Arc<AccessPolicy>,
 		panic_handler: Arc<PanicHandler>,
-		) -> Result<Server, RpcServerError> {
-
-		let cors_domains = cors_domains.map(|domains| {
-			domains.into_iter()
-				.map(|v| match v.as_str() {
-					"*" => jsonrpc_http_server::AccessControlAllowOrigin::Any,
-					"null" => jsonrpc_http_server::AccessControlAllowOrigin::Null,
-					v => jsonrpc_http_server::AccessControlAllowOrigin::Value(v.into()),
-				})
-				.collect()
-		});
+		) -> Result<Server, ::std::io::Error> {
 
-		ServerBuilder::new(self.handler.clone())
-			.cors(cors_domains.into())
-			.allowed_hosts(allowed_hosts.into())
-			.panic_handler(move || {
-				panic_handler.notify_all("Panic in RPC thread.".to_owned());
-			})
-			.start_http(addr)
+		let on_panic: Box<Fn() -> () + Send> = Box::new(move || {
+			panic_handler.notify_all("Panic in RPC thread.".to_owned());
+		});
+		http::start(addr, self.handler.clone(), access_policy, Arc::new(Mutex::new(Some(on_panic))))
 	}
 
 	/// Start ipc server asynchronously and returns result with `Server` handle on success or an error.
@@ -0,0 +1,308 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Origin and host validation policy for HTTP servers.
+//!
+//! Allowed origins and hosts are lists of patterns in which `*` matches any
+//! sequence of characters, e.g. `http://*.ethcore.io` or `localhost:*`.
+//! A host pattern without a port matches the host on any port.
+//! Rules can be overridden for requests with paths starting with a given prefix,
+//! optionally only for requests from origins matching a pattern.
+
+use std::sync::Arc;
+use util::RwLock;
+
+/// List of allowed patterns; `None` allows everything.
+pub type AllowList = Option<Vec<String>>;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Override {
+	prefix: String,
+	origin: Option<String>,
+	cors: Option<AllowList>,
+	hosts: Option<AllowList>,
+}
+
+/// Access policy shared by the HTTP servers and adjustable at runtime.
+pub struct AccessPolicy {
+	parent: Option<Arc<AccessPolicy>>,
+	cors: RwLock<AllowList>,
+	hosts: RwLock<AllowList>,
+	overrides: RwLock<Vec<Override>>,
+}
+
+impl AccessPolicy {
+	/// Creates new policy with given CORS domains and allowed hosts.
+	/// With no CORS domains given, no CORS headers are returned.
+	pub fn new(cors: AllowList, hosts: AllowList) -> Self {
+		AccessPolicy {
+			parent: None,
+			cors: RwLock::new(cors),
+			hosts: RwLock::new(hosts),
+			overrides: RwLock::new(Vec::new()),
+		}
+	}
+
+	/// Creates policy of another server, with its own allowed hosts and overrides,
+	/// sharing CORS domains of `parent`, so that changing them at runtime affects both servers.
+	pub fn inherit(parent: Arc<AccessPolicy>, hosts: AllowList) -> Self {
+		AccessPolicy {
+			parent: Some(parent),
+			cors: RwLock::new(None),
+			hosts: RwLock::new(hosts),
+			overrides: RwLock::new(Vec::new()),
+		}
+	}
+
+	fn with_override<F>(self, prefix: &str, origin: Option<&str>, f: F) -> Self where F: FnOnce(&mut Override) {
+		{
+			let mut overrides = self.overrides.write();
+			let pos = match overrides.iter().position(|o| o.prefix == prefix && o.origin.as_ref().map(|o| o.as_str()) == origin) {
+				Some(pos) => pos,
+				None => {
+					overrides.push(Override {
+						prefix: prefix.into(),
+						origin: origin.map(Into::into),
+						cors: None,
+						hosts: None,
+					});
+					overrides.len() - 1
+				},
+			};
+			f(&mut overrides[pos]);
+		}
+		self
+	}
+
+	/// Use different CORS domains for paths starting with `prefix`.
+	pub fn override_cors(self, prefix: &str, cors: AllowList) -> Self {
+		self.with_override(prefix, None, |o| o.cors = Some(cors))
+	}
+
+	/// Use different allowed hosts for paths starting with `prefix`.
+	pub fn override_hosts(self, prefix: &str, hosts: AllowList) -> Self {
+		self.with_override(prefix, None, |o| o.hosts = Some(hosts))
+	}
+
+	/// Use different CORS domains for requests from origins matching `origin` to paths starting with `prefix`.
+	pub fn override_origin_cors(self, origin: &str, prefix: &str, cors: AllowList) -> Self {
+		self.with_override(prefix, Some(origin), |o| o.cors = Some(cors))
+	}
+
+	/// Use different allowed hosts for requests from origins matching `origin` to paths starting with `prefix`.
+	pub fn override_origin_hosts(self, origin: &str, prefix: &str, hosts: AllowList) -> Self {
+		self.with_override(prefix, Some(origin), |o| o.hosts = Some(hosts))
+	}
+
+	/// Currently allowed CORS domains.
+	pub fn cors(&self) -> AllowList {
+		match self.parent {
+			Some(ref parent) => parent.cors(),
+			None => self.cors.read().clone(),
+		}
+	}
+
+	/// Replace allowed CORS domains.
+	pub fn set_cors(&self, cors: AllowList) {
+		match self.parent {
+			Some(ref parent) => parent.set_cors(cors),
+			None => *self.cors.write() = cors,
+		}
+	}
+
+	/// Currently allowed hosts.
+	pub fn hosts(&self) -> AllowList {
+		self.hosts.read().clone()
+	}
+
+	/// Replace allowed hosts.
+	pub fn set_hosts(&self, hosts: AllowList) {
+		*self.hosts.write() = hosts;
+	}
+
+	// overrides of the longest prefix matching the path, origin-specific ones first.
+	fn overrides<T, F>(&self, path: &str, origin: Option<&str>, f: F) -> Option<T> where F: Fn(&Override) -> Option<T> {
+		let overrides = self.overrides.read();
+		let mut matching: Vec<_> = overrides.iter()
+			.filter(|o| path.starts_with(o.prefix.as_str()))
+			.filter(|o| match (o.origin.as_ref(), origin) {
+				(None, _) => true,
+				(Some(pattern), Some(origin)) => matches(pattern, origin),
+				(Some(_), None) => false,
+			})
+			.collect();
+		matching.sort_by_key(|o| (o.prefix.len(), o.origin.is_some()));
+		matching.into_iter().rev().filter_map(f).next()
+	}
+
+	/// Checks the `Host` header of a request from given `Origin` to given path.
+	pub fn is_host_allowed(&self, path: &str, origin: Option<&str>, host: Option<&str>) -> bool {
+		let hosts = self.overrides(path, origin, |o| o.hosts.clone()).unwrap_or_else(|| self.hosts());
+		match (hosts, host) {
+			(None, _) => true,
+			(Some(_), None) => false,
+			(Some(hosts), Some(host)) => hosts.iter().any(|pattern| host_matches(pattern, host)),
+		}
+	}
+
+	/// Returns the value of `Access-Control-Allow-Origin` header for a request
+	/// to given path with given `Origin`, or `None` if the header should not be sent.
+	pub fn cors_header(&self, path: &str, origin: Option<&str>) -> Option<String> {
+		let cors = match self.overrides(path, origin, |o| o.cors.clone()).unwrap_or_else(|| self.cors()) {
+			Some(cors) => cors,
+			None => return None,
+		};
+
+		if cors.iter().any(|pattern| pattern == "*") {
+			return Some("*".into());
+		}
+
+		let origin = match origin {
+			Some(origin) => origin,
+			None => return None,
+		};
+
+		cors.iter()
+			.find(|pattern| matches(pattern, origin))
+			.map(|_| origin.into())
+	}
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+	if pattern.contains(':') {
+		return matches(pattern, host);
+	}
+	let hostname = match host.rfind(':') {
+		// don't split IPv6 addresses without port
+		Some(pos) if !host[pos..].contains(']') => &host[..pos],
+		_ => host,
+	};
+	matches(pattern, hostname)
+}
+
+/// Case-insensitive match of `value` against `pattern`, where `*` matches any sequence of characters.
+pub fn matches(pattern: &str, value: &str) -> bool {
+	let pattern = pattern.to_lowercase();
+	let value = value.to_lowercase();
+	let parts: Vec<&str> = pattern.split('*').collect();
+	if parts.len() == 1 {
+		return pattern == value;
+	}
+
+	let (first, last) = (parts[0], parts[parts.len() - 1]);
+	if value.len() < first.len() + last.len() || !value.starts_with(first) || !value.ends_with(last) {
+		return false;
+	}
+
+	let mut rest = &value[first.len()..value.len() - last.len()];
+	for part in &parts[1..parts.len() - 1] {
+		match rest.find(part) {
+			Some(pos) => rest = &rest[pos + part.len()..],
+			None => return false,
+		}
+	}
+	true
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use super::{AccessPolicy, matches};
+
+	#[test]
+	fn should_match_wildcards() {
+		assert!(matches("*", "anything"));
+		assert!(matches("http://*.ethcore.io", "http://wallet.ethcore.io"));
+		assert!(matches("http://*.ethcore.io", "HTTP://Wallet.Ethcore.io"));
+		assert!(matches("localhost:*", "localhost:8545"));
+		assert!(matches("a*b*c", "axxbyyc"));
+		assert!(!matches("a*b*c", "axxcyyb"));
+		assert!(!matches("http://*.ethcore.io", "http://ethcore.io.evil.com"));
+		assert!(!matches("ab*ba", "aba"));
+	}
+
+	#[test]
+	fn should_validate_hosts() {
+		let policy = AccessPolicy::new(None, Some(vec!["localhost".into(), "*.parity:8080".into()]));
+
+		assert!(policy.is_host_allowed("/", None, Some("localhost:8545")));
+		assert!(policy.is_host_allowed("/", None, Some("home.parity:8080")));
+		assert!(!policy.is_host_allowed("/", None, Some("home.parity:8081")));
+		assert!(!policy.is_host_allowed("/", None, Some("ethcore.io")));
+		assert!(!policy.is_host_allowed("/", None));
+		assert!(AccessPolicy::new(None, None).is_host_allowed("/", None, None));
+	}
+
+	#[test]
+	fn should_return_cors_header() {
+		let policy = AccessPolicy::new(Some(vec!["http://*.ethcore.io".into(), "null".into()]), None);
+
+		assert_eq!(policy.cors_header("/", Some("http://wallet.ethcore.io")), Some("http://wallet.ethcore.io".into()));
+		assert_eq!(policy.cors_header("/", Some("null")), Some("null".into()));
+		assert_eq!(policy.cors_header("/", Some("http://evil.com")), None);
+		assert_eq!(policy.cors_header("/", None), None);
+		assert_eq!(AccessPolicy::new(Some(vec!["*".into()]), None).cors_header("/", None), Some("*".into()));
+		assert_eq!(AccessPolicy::new(None, None).cors_header("/", Some("http://wallet.ethcore.io")), None);
+	}
+
+	#[test]
+	fn should_apply_longest_matching_override() {
+		let policy = AccessPolicy::new(Some(vec!["http://a.io".into()]), Some(vec!["localhost".into()]))
+			.override_cors("/api/", Some(vec!["http://b.io".into()]))
+			.override_cors("/api/content/", None)
+			.override_hosts("/parity-utils/", None);
+
+		assert_eq!(policy.cors_header("/", Some("http://a.io")), Some("http://a.io".into()));
+		assert_eq!(policy.cors_header("/api/ping", Some("http://a.io")), None);
+		assert_eq!(policy.cors_header("/api/ping", Some("http://b.io")), Some("http://b.io".into()));
+		assert_eq!(policy.cors_header("/api/content/x", Some("http://b.io")), None);
+		assert!(!policy.is_host_allowed("/", None, Some("ethcore.io")));
+		assert!(policy.is_host_allowed("/parity-utils/inject.js", None, Some("ethcore.io")));
+	}
+
+	#[test]
+	fn should_apply_origin_overrides() {
+		let policy = AccessPolicy::new(Some(vec!["http://a.io".into()]), Some(vec!["localhost".into()]))
+			.override_cors("/api/", None)
+			.override_origin_cors("http://*.b.io", "/api/", Some(vec!["*".into()]))
+			.override_origin_hosts("http://b.io", "/", None);
+
+		assert_eq!(policy.cors_header("/api/ping", Some("http://a.io")), None);
+		assert_eq!(policy.cors_header("/api/ping", Some("http://x.b.io")), Some("*".into()));
+		assert_eq!(policy.cors_header("/", Some("http://x.b.io")), None);
+		assert!(!policy.is_host_allowed("/", Some("http://a.io"), Some("ethcore.io")));
+		assert!(policy.is_host_allowed("/", Some("http://b.io"), Some("ethcore.io")));
+		assert!(!policy.is_host_allowed("/", None, Some("ethcore.io")));
+	}
+
+	#[test]
+	fn should_update_cors_at_runtime() {
+		let policy = AccessPolicy::new(None, None);
+		policy.set_cors(Some(vec!["http://a.io".into()]));
+		assert_eq!(policy.cors_header("/", Some("http://a.io")), Some("http://a.io".into()));
+	}
+
+	#[test]
+	fn should_share_cors_with_inherited_policy() {
+		let parent = Arc::new(AccessPolicy::new(None, Some(vec!["localhost".into()])));
+		let policy = AccessPolicy::inherit(parent.clone(), Some(vec!["*.parity".into()]));
+
+		parent.set_cors(Some(vec!["http://a.io".into()]));
+		assert_eq!(policy.cors_header("/", Some("http://a.io")), Some("http://a.io".into()));
+		assert!(policy.is_host_allowed("/", None, Some("home.parity")));
+		assert!(!policy.is_host_allowed("/", None, Some("localhost")));
+	}
+}
@@ -87,7 +87,12 @@ macro_rules! build_rpc_trait {
 		fn $method: ident (&self $(, $param: ty)*) -> Result<$out: ty, Error>
 	) => {
 		$del.add_method($name, move |base, params| {
-			(Self::$method as fn(&_ $(, $param)*) -> Result<$out, Error>).wrap_rpc(base, params)
+			let start = ::std::time::Instant::now();
+			let logged_params = ::v1::helpers::request_log::params($name, &params);
+			let res = (Self::$method as fn(&_ $(, $param)*) -> Result<$out, Error>).wrap_rpc(base, params);
+			::v1::helpers::auto_args::record_latency($name, start);
+			::v1::helpers::request_log::log($name, logged_params, start);
+			res
 		})
 	};
 
@@ -101,6 +106,15 @@ macro_rules! build_rpc_trait {
 	};
 }
 
+/// Record the time taken to serve a call to the given RPC method.
+pub fn record_latency(method: &str, start: ::std::time::Instant) {
+	let elapsed = start.elapsed();
+	let secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000f64;
+	::util::metrics::metrics()
+		.histogram("parity_rpc_request_duration_seconds", "Time taken to serve RPC requests.", &[("method", method)], ::util::metrics::DEFAULT_BUCKETS)
+		.observe(secs);
+}
+
 /// A wrapper type without an implementation of `Deserialize`
 /// which allows a special implementation of `Wrap` for functions
 /// that take a trailing default parameter.
@@ -307,4 +321,26 @@ wrap_with_trailing!(5, A, B, C, D, E);
 wrap_with_trailing!(4, A, B, C, D);
 wrap_with_trailing!(3, A, B, C);
 wrap_with_trailing!(2, A, B);
-wrap_with_trailing!(1, A);
\ No newline at end of file
+wrap_with_trailing!(1, A);
+
+// special impl for a single parameter followed by two default trailing parameters.
+impl<B, OUT, A, T, U> Wrap<B> for fn(&B, A, Trailing<T>, Trailing<U>) -> Result<OUT, Error>
+	where B: Send + Sync + 'static, OUT: Serialize, A: Deserialize, T: Default + Deserialize, U: Default + Deserialize
+{
+	fn wrap_rpc(&self, base: &B, params: Params) -> Result<Value, Error> {
+		let len = match params {
+			Params::Array(ref v) => v.len(),
+			Params::None => 0,
+			_ => return Err(errors::invalid_params("not an array", "")),
+		};
+
+		let (a, t, u) = match len {
+			1 => try!(from_params::<(A,)>(params).map(|(a,)| (a, T::default(), U::default()))),
+			2 => try!(from_params::<(A, T)>(params).map(|(a, t)| (a, t, U::default()))),
+			3 => try!(from_params::<(A, T, U)>(params)),
+			_ => return Err(Error::invalid_params()),
+		};
+
+		(self)(base, a, Trailing(t), Trailing(u)).map(to_value)
+	}
+}
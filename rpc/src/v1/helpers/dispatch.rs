@@ -15,44 +15,60 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use rlp;
-use util::{Address, H256, U256, Uint, Bytes};
+use util::{Address, H256, H520, U256, Uint, Bytes};
 use util::bytes::ToPretty;
 
 use ethkey::Signature;
 use ethcore::miner::MinerService;
 use ethcore::client::MiningBlockChainClient;
 use ethcore::transaction::{Action, SignedTransaction, Transaction};
-use ethcore::account_provider::AccountProvider;
+use ethcore::account_provider::{AccountProvider, PolicyViolation, Error as AccountError};
 
 use jsonrpc_core::Error;
-use v1::helpers::{errors, TransactionRequest, FilledTransactionRequest, ConfirmationPayload};
+use v1::helpers::{errors, TransactionRequest, FilledTransactionRequest, ConfirmationPayload, CallRequest};
 use v1::types::{
 	H256 as RpcH256, H520 as RpcH520, Bytes as RpcBytes,
 	RichRawTransaction as RpcRichRawTransaction,
 	ConfirmationPayload as RpcConfirmationPayload,
 	ConfirmationResponse,
+	UnsignedTransaction as RpcUnsignedTransaction,
 	SignRequest as RpcSignRequest,
 	DecryptRequest as RpcDecryptRequest,
 };
 
 pub const DEFAULT_MAC: [u8; 2] = [0, 0];
 
+/// Executes the request, if allowed by the signing policy of the account.
 pub fn execute<C, M>(client: &C, miner: &M, accounts: &AccountProvider, payload: ConfirmationPayload, pass: Option<String>) -> Result<ConfirmationResponse, Error>
 	where C: MiningBlockChainClient, M: MinerService
+{
+	execute_request(client, miner, accounts, payload, pass, false)
+}
+
+/// Executes a request confirmed by the user in the signer. The signing policy of the account is not checked.
+pub fn execute_confirmed<C, M>(client: &C, miner: &M, accounts: &AccountProvider, payload: ConfirmationPayload, pass: String) -> Result<ConfirmationResponse, Error>
+	where C: MiningBlockChainClient, M: MinerService
+{
+	execute_request(client, miner, accounts, payload, Some(pass), true)
+}
+
+fn execute_request<C, M>(client: &C, miner: &M, accounts: &AccountProvider, payload: ConfirmationPayload, pass: Option<String>, confirmed: bool) -> Result<ConfirmationResponse, Error>
+	where C: MiningBlockChainClient, M: MinerService
 {
 	match payload {
 		ConfirmationPayload::SendTransaction(request) => {
-			sign_and_dispatch(client, miner, accounts, request, pass)
+			sign_transaction(client, miner, accounts, request, pass, confirmed)
+				.and_then(|signed| dispatch_transaction(client, miner, signed))
 				.map(RpcH256::from)
 				.map(ConfirmationResponse::SendTransaction)
 		},
 		ConfirmationPayload::SignTransaction(request) => {
-			sign_no_dispatch(client, miner, accounts, request, pass)
+			sign_transaction(client, miner, accounts, request, pass, confirmed)
 				.map(RpcRichRawTransaction::from)
 				.map(ConfirmationResponse::SignTransaction)
 		},
 		ConfirmationPayload::Signature(address, hash) => {
-			signature(accounts, address, hash, pass)
+			signature(accounts, address, hash, pass, confirmed)
 				.map(RpcH520::from)
 				.map(ConfirmationResponse::Signature)
 		},
@@ -64,11 +80,32 @@ pub fn execute<C, M>(client: &C, miner: &M, accounts: &AccountProvider, payload:
 	}
 }
 
-fn signature(accounts: &AccountProvider, address: Address, hash: H256, password: Option<String>) -> Result<Signature, Error> {
-	accounts.sign(address, password.clone(), hash).map_err(|e| match password {
-		Some(_) => errors::from_password_error(e),
-		None => errors::from_signing_error(e),
-	})
+/// Checks the payload against the signing policy of the sender.
+/// Requests which violate the policy should be confirmed in the signer.
+pub fn check_policy(accounts: &AccountProvider, payload: &ConfirmationPayload) -> Result<(), PolicyViolation> {
+	match *payload {
+		ConfirmationPayload::SendTransaction(ref request) | ConfirmationPayload::SignTransaction(ref request) =>
+			accounts.check_transaction(&request.from, request.to.as_ref(), &request.value),
+		ConfirmationPayload::Signature(ref address, _) => accounts.check_signature(address),
+		ConfirmationPayload::Decrypt(_, _) => Ok(()),
+	}
+}
+
+fn signature(accounts: &AccountProvider, address: Address, hash: H256, password: Option<String>, confirmed: bool) -> Result<Signature, Error> {
+	let result = if confirmed {
+		accounts.sign_confirmed(address, password.clone(), hash)
+	} else {
+		accounts.sign(address, password.clone(), hash)
+	};
+	result.map_err(|e| signing_error(e, &password))
+}
+
+fn signing_error(error: AccountError, password: &Option<String>) -> Error {
+	match (error, password.is_some()) {
+		(AccountError::Policy(violation), _) => errors::from_policy_violation(violation),
+		(e, true) => errors::from_password_error(e),
+		(e, false) => errors::from_signing_error(e),
+	}
 }
 
 fn decrypt(accounts: &AccountProvider, address: Address, msg: Bytes, password: Option<String>) -> Result<Bytes, Error> {
@@ -88,31 +125,83 @@ pub fn dispatch_transaction<C, M>(client: &C, miner: &M, signed_transaction: Sig
 		.map(|_| hash)
 }
 
+/// Builds the transaction, picking the next nonce of the sender if not given.
+fn transaction<C, M>(client: &C, miner: &M, filled: FilledTransactionRequest) -> Transaction
+	where C: MiningBlockChainClient, M: MinerService {
+	Transaction {
+		nonce: filled.nonce
+			.or_else(|| miner
+				.last_nonce(&filled.from)
+				.map(|nonce| nonce + U256::one()))
+			.unwrap_or_else(|| client.latest_nonce(&filled.from)),
+
+		action: filled.to.map_or(Action::Create, Action::Call),
+		gas: filled.gas,
+		gas_price: filled.gas_price,
+		value: filled.value,
+		data: filled.data,
+	}
+}
+
+/// Fills the request and builds the transaction to be signed offline.
+pub fn compose_transaction<C, M>(client: &C, miner: &M, request: TransactionRequest) -> RpcUnsignedTransaction
+	where C: MiningBlockChainClient, M: MinerService {
+	let filled = fill_optional_fields(request, client, miner);
+	let from = filled.from;
+	RpcUnsignedTransaction::new(from, transaction(client, miner, filled), client.signing_network_id())
+}
+
+/// Attaches a signature produced offline to the composed transaction.
+/// Fails if the transaction was modified or signed by an account other than the expected sender.
+pub fn combine_transaction(unsigned: RpcUnsignedTransaction, signature: RpcH520) -> Result<SignedTransaction, Error> {
+	let signature: H520 = signature.into();
+	let from: Address = unsigned.from.clone().into();
+	let hash: H256 = unsigned.hash.clone().into();
+	let network_id = unsigned.network_id;
+	let t: Transaction = unsigned.into();
+	if t.hash(network_id) != hash {
+		return Err(errors::invalid_params("hash", "Hash does not match the transaction."));
+	}
+
+	let signed = t.with_signature(signature.into(), network_id);
+	match signed.sender() {
+		Ok(ref sender) if *sender == from => Ok(signed),
+		Ok(sender) => Err(errors::invalid_params("signature", format!("Transaction signed by {:?} instead of {:?}.", sender, from))),
+		Err(e) => Err(errors::invalid_params("signature", e)),
+	}
+}
+
+/// Attaches a signature produced offline to the composed transaction and dispatches it.
+pub fn submit_transaction<C, M>(client: &C, miner: &M, unsigned: RpcUnsignedTransaction, signature: RpcH520) -> Result<H256, Error>
+	where C: MiningBlockChainClient, M: MinerService {
+	let signed = try!(combine_transaction(unsigned, signature));
+	dispatch_transaction(client, miner, signed)
+}
+
 pub fn sign_no_dispatch<C, M>(client: &C, miner: &M, accounts: &AccountProvider, filled: FilledTransactionRequest, password: Option<String>) -> Result<SignedTransaction, Error>
 	where C: MiningBlockChainClient, M: MinerService {
+	sign_transaction(client, miner, accounts, filled, password, false)
+}
+
+/// Signs the transaction, checking the signing policy of the sender unless `confirmed` by the user.
+/// The value counts against the daily limit of the sender either way.
+fn sign_transaction<C, M>(client: &C, miner: &M, accounts: &AccountProvider, filled: FilledTransactionRequest, password: Option<String>, confirmed: bool) -> Result<SignedTransaction, Error>
+	where C: MiningBlockChainClient, M: MinerService {
 
 	let network_id = client.signing_network_id();
-	let address = filled.from;
-	let signed_transaction = {
-		let t = Transaction {
-			nonce: filled.nonce
-				.or_else(|| miner
-					.last_nonce(&filled.from)
-					.map(|nonce| nonce + U256::one()))
-				.unwrap_or_else(|| client.latest_nonce(&filled.from)),
-
-			action: filled.to.map_or(Action::Create, Action::Call),
-			gas: filled.gas,
-			gas_price: filled.gas_price,
-			value: filled.value,
-			data: filled.data,
-		};
-
-		let hash = t.hash(network_id);
-		let signature = try!(signature(accounts, address, hash, password));
-		t.with_signature(signature, network_id)
+	let (from, to, value) = (filled.from, filled.to, filled.value);
+	let t = transaction(client, miner, filled);
+	let hash = t.hash(network_id);
+	let signature = if confirmed {
+		accounts.sign_confirmed(from, password.clone(), hash).map(|signature| {
+			accounts.note_spent(from, value);
+			signature
+		})
+	} else {
+		accounts.sign_transaction(from, to.as_ref(), value, password.clone(), hash)
 	};
-	Ok(signed_transaction)
+	let signature = try!(signature.map_err(|e| signing_error(e, &password)));
+	Ok(t.with_signature(signature, network_id))
 }
 
 pub fn sign_and_dispatch<C, M>(client: &C, miner: &M, accounts: &AccountProvider, filled: FilledTransactionRequest, password: Option<String>) -> Result<H256, Error>
@@ -146,6 +235,32 @@ pub fn default_gas_price<C, M>(client: &C, miner: &M) -> U256
 	client.gas_price_median(100).unwrap_or_else(|| miner.sensible_gas_price())
 }
 
+/// Gas of calls which don't specify any, unless the gas cap is lower.
+const DEFAULT_CALL_GAS: u64 = 50_000_000;
+
+/// Turns a call request into a transaction with a fake signature, executable without the sender's key.
+/// Requests for more gas than `gas_cap` are refused.
+pub fn fake_sign_call<C, M>(client: &C, miner: &M, request: CallRequest, gas_cap: Option<U256>) -> Result<SignedTransaction, Error>
+	where C: MiningBlockChainClient, M: MinerService
+{
+	let from = request.from.unwrap_or_else(Address::zero);
+	let default_gas = U256::from(DEFAULT_CALL_GAS);
+	let gas = match (request.gas, gas_cap) {
+		(Some(gas), Some(cap)) if gas > cap => return Err(errors::gas_cap_exceeded(cap)),
+		(Some(gas), _) => gas,
+		(None, Some(cap)) if cap < default_gas => cap,
+		(None, _) => default_gas,
+	};
+	Ok(Transaction {
+		nonce: request.nonce.unwrap_or_else(|| client.latest_nonce(&from)),
+		action: request.to.map_or(Action::Create, Action::Call),
+		gas: gas,
+		gas_price: request.gas_price.unwrap_or_else(|| default_gas_price(client, miner)),
+		value: request.value.unwrap_or_else(U256::zero),
+		data: request.data.map_or_else(Vec::new, |d| d.to_vec())
+	}.fake_sign(from))
+}
+
 pub fn from_rpc<C, M>(payload: RpcConfirmationPayload, client: &C, miner: &M) -> ConfirmationPayload
 	where C: MiningBlockChainClient, M: MinerService {
 
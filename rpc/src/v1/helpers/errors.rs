@@ -21,9 +21,14 @@ macro_rules! rpc_unimplemented {
 }
 
 use std::fmt;
+use std::collections::BTreeMap;
 use rlp::DecoderError;
+use rustc_serialize::hex::ToHex;
+use util::U256;
 use ethcore::error::{Error as EthcoreError, CallError, TransactionError};
-use ethcore::account_provider::{Error as AccountError};
+use ethcore::trace::TraceError;
+use ethcore::account_provider::{Error as AccountError, PolicyViolation};
+use ethcore::private_transactions::Error as PrivateTransactionError;
 use fetch::FetchError;
 use jsonrpc_core::{Error, ErrorCode, Value};
 
@@ -33,22 +38,29 @@ mod codes {
 	pub const NO_WORK: i64 = -32001;
 	pub const NO_AUTHOR: i64 = -32002;
 	pub const NO_NEW_WORK: i64 = -32003;
+	pub const CANNOT_SEAL: i64 = -32004;
 	pub const NOT_ENOUGH_DATA: i64 = -32006;
 	pub const UNKNOWN_ERROR: i64 = -32009;
 	pub const TRANSACTION_ERROR: i64 = -32010;
 	pub const EXECUTION_ERROR: i64 = -32015;
+	pub const GAS_CAP_EXCEEDED: i64 = -32016;
 	pub const ACCOUNT_LOCKED: i64 = -32020;
 	pub const PASSWORD_INVALID: i64 = -32021;
 	pub const ACCOUNT_ERROR: i64 = -32023;
+	pub const POLICY_VIOLATION: i64 = -32024;
 	pub const SIGNER_DISABLED: i64 = -32030;
 	pub const DAPPS_DISABLED: i64 = -32031;
 	pub const NETWORK_DISABLED: i64 = -32035;
+	pub const PUBLIC_NODE: i64 = -32036;
+	pub const SENDER_INDEX_DISABLED: i64 = -32037;
+	pub const PRIVATE_TRANSACTIONS_DISABLED: i64 = -32038;
 	pub const REQUEST_REJECTED: i64 = -32040;
 	pub const REQUEST_REJECTED_LIMIT: i64 = -32041;
 	pub const REQUEST_NOT_FOUND: i64 = -32042;
 	pub const COMPILATION_ERROR: i64 = -32050;
 	pub const ENCRYPTION_ERROR: i64 = -32055;
 	pub const FETCH_ERROR: i64 = -32060;
+	pub const PRIVATE_TRANSACTION_ERROR: i64 = -32065;
 }
 
 pub fn unimplemented(details: Option<String>) -> Error {
@@ -123,6 +135,25 @@ pub fn execution<T: fmt::Debug>(data: T) -> Error {
 	}
 }
 
+pub fn vm_execution(exception: TraceError, output: &[u8]) -> Error {
+	let mut data = BTreeMap::new();
+	data.insert("error".to_owned(), Value::String(format!("{}", exception)));
+	data.insert("output".to_owned(), Value::String(format!("0x{}", output.to_hex())));
+	Error {
+		code: ErrorCode::ServerError(codes::EXECUTION_ERROR),
+		message: format!("VM execution error: {}.", exception),
+		data: Some(Value::Object(data)),
+	}
+}
+
+pub fn gas_cap_exceeded(cap: U256) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::GAS_CAP_EXCEEDED),
+		message: format!("Requested gas exceeds the limit for calls on this node ({}).", cap),
+		data: None
+	}
+}
+
 pub fn state_pruned() -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
@@ -147,6 +178,14 @@ pub fn no_new_work() -> Error {
 	}
 }
 
+pub fn cannot_seal() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::CANNOT_SEAL),
+		message: "Unable to seal a block. Sealing on demand is only possible with engines which seal internally (e.g. --chain dev).".into(),
+		data: None
+	}
+}
+
 pub fn no_author() -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::NO_AUTHOR),
@@ -195,6 +234,38 @@ pub fn network_disabled() -> Error {
 	}
 }
 
+pub fn public_node() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::PUBLIC_NODE),
+		message: "Method disabled on public nodes.".into(),
+		data: None
+	}
+}
+
+pub fn sender_index_disabled() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::SENDER_INDEX_DISABLED),
+		message: "Sender index is disabled. Start Parity with --sender-index to enable it.".into(),
+		data: None
+	}
+}
+
+pub fn too_many_transactions(limit: usize) -> Error {
+	Error {
+		code: ErrorCode::InvalidParams,
+		message: format!("More than {} transactions in the block range. Narrow the range down.", limit),
+		data: None
+	}
+}
+
+pub fn private_transactions_disabled() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::PRIVATE_TRANSACTIONS_DISABLED),
+		message: "Private transactions are disabled. Start Parity with --private-tx-account to enable them.".into(),
+		data: None
+	}
+}
+
 pub fn encryption_error<T: fmt::Debug>(error: T) -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::ENCRYPTION_ERROR),
@@ -219,6 +290,14 @@ pub fn from_signing_error(error: AccountError) -> Error {
 	}
 }
 
+pub fn from_policy_violation(violation: PolicyViolation) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::POLICY_VIOLATION),
+		message: format!("Request refused by the account policy: {}. Confirm it using Trusted Signer.", violation),
+		data: None,
+	}
+}
+
 pub fn from_password_error(error: AccountError) -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::PASSWORD_INVALID),
@@ -252,6 +331,7 @@ pub fn transaction_message(error: TransactionError) -> String {
 			format!("Transaction cost exceeds current gas limit. Limit: {}, got: {}. Try decreasing supplied gas.", limit, got)
 		},
 		InvalidNetworkId => "Invalid network id.".into(),
+		NotAllowed => "Transaction is not permitted by the node's transaction policy.".into(),
 		InvalidGasLimit(_) => "Supplied gas is beyond limit.".into(),
 		SenderBanned => "Sender is banned in local queue.".into(),
 		RecipientBanned => "Recipient is banned in local queue.".into(),
@@ -284,6 +364,17 @@ pub fn from_rlp_error(error: DecoderError) -> Error {
 	}
 }
 
+pub fn from_private_transaction_error(error: PrivateTransactionError) -> Error {
+	match error {
+		PrivateTransactionError::Call(e) => from_call_error(e),
+		e => Error {
+			code: ErrorCode::ServerError(codes::PRIVATE_TRANSACTION_ERROR),
+			message: format!("{}", e),
+			data: None,
+		},
+	}
+}
+
 pub fn from_call_error(error: CallError) -> Error {
 	match error {
 		CallError::StatePruned => state_pruned(),
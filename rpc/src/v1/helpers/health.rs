@@ -0,0 +1,132 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Node health checks shared by the RPC and the HTTP `/health` endpoint.
+
+use std::sync::Arc;
+use util::ntp::{TimeChecker, DRIFT_WARNING_THRESHOLD_MS};
+use v1::types::{Health, HealthInfo, HealthStatus, PeersDetails};
+
+/// Clock drift (in ms) above which the node is considered unhealthy.
+const TIME_DRIFT_BAD_MS: i64 = 10_000;
+
+/// Source of sync and connectivity information.
+pub trait SyncStatus: Send + Sync {
+	/// Returns true if there is a major sync happening.
+	fn is_major_importing(&self) -> bool;
+	/// Returns number of connected peers and the minimal number of peers to maintain.
+	fn peers(&self) -> (usize, usize);
+}
+
+/// Computes node health.
+pub struct NodeHealth {
+	sync_status: Arc<SyncStatus>,
+	time_checker: Arc<TimeChecker>,
+}
+
+impl NodeHealth {
+	/// Creates new `NodeHealth`.
+	pub fn new(sync_status: Arc<SyncStatus>, time_checker: Arc<TimeChecker>) -> Self {
+		NodeHealth {
+			sync_status: sync_status,
+			time_checker: time_checker,
+		}
+	}
+
+	/// Run all the checks.
+	pub fn health(&self) -> Health {
+		let (connected, min) = self.sync_status.peers();
+		Health {
+			peers: peers_health(connected, min),
+			sync: sync_health(self.sync_status.is_major_importing()),
+			time: time_health(self.time_checker.drift()),
+		}
+	}
+}
+
+fn peers_health(connected: usize, min: usize) -> HealthInfo<PeersDetails> {
+	let (status, message) = if connected == 0 {
+		(HealthStatus::Bad, "You are not connected to any peers. There is most likely some network issue. Fix connectivity.".into())
+	} else if connected < min {
+		(HealthStatus::NeedsAttention, format!("You are connected to {} peers, fewer than the desired {}.", connected, min))
+	} else {
+		(HealthStatus::Ok, String::new())
+	};
+
+	HealthInfo {
+		status: status,
+		message: message,
+		details: PeersDetails {
+			connected: connected,
+			min: min,
+		},
+	}
+}
+
+fn sync_health(is_syncing: bool) -> HealthInfo<bool> {
+	HealthInfo {
+		status: if is_syncing { HealthStatus::Bad } else { HealthStatus::Ok },
+		message: if is_syncing { "Your node is still syncing, the values you see might be outdated.".into() } else { String::new() },
+		details: is_syncing,
+	}
+}
+
+fn time_health(drift: Result<i64, String>) -> HealthInfo<i64> {
+	match drift {
+		Ok(drift) => {
+			let (status, message) = if drift.abs() >= TIME_DRIFT_BAD_MS {
+				(HealthStatus::Bad, format!("Your clock is not in sync. Detected difference is too big for the protocol to work: {}ms.", drift))
+			} else if drift.abs() >= DRIFT_WARNING_THRESHOLD_MS {
+				(HealthStatus::NeedsAttention, format!("Your clock is not in sync. Detected difference: {}ms.", drift))
+			} else {
+				(HealthStatus::Ok, String::new())
+			};
+			HealthInfo { status: status, message: message, details: drift }
+		},
+		Err(err) => HealthInfo {
+			status: HealthStatus::NeedsAttention,
+			message: format!("Unable to reach the time server: {}", err),
+			details: 0,
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{peers_health, sync_health, time_health};
+	use v1::types::HealthStatus;
+
+	#[test]
+	fn should_classify_peers() {
+		assert_eq!(peers_health(0, 25).status, HealthStatus::Bad);
+		assert_eq!(peers_health(10, 25).status, HealthStatus::NeedsAttention);
+		assert_eq!(peers_health(25, 25).status, HealthStatus::Ok);
+	}
+
+	#[test]
+	fn should_classify_sync() {
+		assert_eq!(sync_health(true).status, HealthStatus::Bad);
+		assert_eq!(sync_health(false).status, HealthStatus::Ok);
+	}
+
+	#[test]
+	fn should_classify_time_drift() {
+		assert_eq!(time_health(Ok(10)).status, HealthStatus::Ok);
+		assert_eq!(time_health(Ok(-600)).status, HealthStatus::NeedsAttention);
+		assert_eq!(time_health(Ok(20_000)).status, HealthStatus::Bad);
+		assert_eq!(time_health(Err("timeout".into())).status, HealthStatus::NeedsAttention);
+	}
+}
@@ -23,6 +23,11 @@ pub mod errors;
 pub mod dispatch;
 pub mod params;
 pub mod block_import;
+pub mod health;
+pub mod access_policy;
+pub mod request_log;
+pub mod public_node;
+pub mod sync_progress;
 
 mod poll_manager;
 mod poll_filter;
@@ -32,7 +37,7 @@ mod signing_queue;
 mod network_settings;
 
 pub use self::poll_manager::PollManager;
-pub use self::poll_filter::{PollFilter, limit_logs};
+pub use self::poll_filter::{PollFilter, limit_logs, max_logs};
 pub use self::requests::{TransactionRequest, FilledTransactionRequest, ConfirmationRequest, ConfirmationPayload, CallRequest};
 pub use self::signing_queue::{ConfirmationsQueue, ConfirmationPromise, ConfirmationResult, SigningQueue, QueueEvent};
 pub use self::signer::SignerService;
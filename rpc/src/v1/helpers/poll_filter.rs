@@ -25,3 +25,11 @@ pub fn limit_logs(mut logs: Vec<Log>, limit: Option<usize>) -> Vec<Log> {
 		_ => logs,
 	}
 }
+
+/// Returns the stricter of the limit requested in a filter and the node-wide maximum.
+pub fn max_logs(limit: Option<usize>, max: Option<usize>) -> Option<usize> {
+	match (limit, max) {
+		(Some(limit), Some(max)) => Some(::std::cmp::min(limit, max)),
+		(limit, max) => limit.or(max),
+	}
+}
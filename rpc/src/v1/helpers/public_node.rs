@@ -0,0 +1,123 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC middleware of publicly exposed nodes.
+
+use std::sync::{Arc, Weak};
+use ethsync::{SyncProvider, ManageNetwork};
+use jsonrpc_core::{IoDelegate, Error, to_value};
+use v1::types::Peers;
+use v1::helpers::errors;
+use v1::helpers::params::expect_no_params;
+use Extendable;
+
+/// Maximal number of logs returned by a single query on a public node.
+pub const PUBLIC_NODE_MAX_LOGS: usize = 1000;
+
+/// Methods which expose local accounts, details about the node operator or affect node operation.
+const DISABLED_METHODS: &'static [&'static str] = &[
+	"eth_sign",
+	"eth_sendTransaction",
+	"eth_signTransaction",
+	"parity_postSign",
+	"parity_postTransaction",
+	"parity_checkRequest",
+	"parity_decryptMessage",
+	"parity_accounts",
+	"parity_devLogs",
+	"parity_devLogsLevels",
+	"parity_devMine",
+	"parity_rpcSettings",
+	"parity_nodeName",
+	"parity_enode",
+	"parity_netPort",
+	"parity_mode",
+	"parity_localTransactions",
+	"parity_unsignedTransactionsCount",
+	"parity_signerPort",
+	"parity_dappsPort",
+	"parity_dappsInterface",
+	"parity_dbStats",
+	"parity_dbCompact",
+	"parity_memoryProfile",
+];
+
+/// Restricts the RPC of a publicly exposed node, whatever the transport.
+///
+/// Wraps the server APIs are registered with. Disabled methods return an error and
+/// `parity_netPeers` only reports peer counts. Restrictions are applied again after each
+/// registered delegate, so they replace methods of the same names whatever the order.
+pub struct PublicNodeMiddleware<T: Extendable> {
+	inner: T,
+	sync: Weak<SyncProvider>,
+	net: Weak<ManageNetwork>,
+}
+
+impl<T: Extendable> PublicNodeMiddleware<T> {
+	/// Wraps given server.
+	pub fn new(inner: T, sync: &Arc<SyncProvider>, net: &Arc<ManageNetwork>) -> Self {
+		PublicNodeMiddleware {
+			inner: inner,
+			sync: Arc::downgrade(sync),
+			net: Arc::downgrade(net),
+		}
+	}
+
+	/// Returns the wrapped server.
+	pub fn into_inner(self) -> T {
+		self.inner
+	}
+
+	fn restrictions(&self) -> IoDelegate<Restrictions> {
+		let mut del = IoDelegate::new(Arc::new(Restrictions {
+			sync: self.sync.clone(),
+			net: self.net.clone(),
+		}));
+		for name in DISABLED_METHODS {
+			del.add_method(*name, |_, _| Err(errors::public_node()));
+		}
+		del.add_method("parity_netPeers", |restrictions, params| {
+			expect_no_params(params).and_then(|_| restrictions.net_peers()).map(to_value)
+		});
+		del
+	}
+}
+
+impl<T: Extendable> Extendable for PublicNodeMiddleware<T> {
+	fn add_delegate<D: Send + Sync + 'static>(&self, delegate: IoDelegate<D>) {
+		self.inner.add_delegate(delegate);
+		self.inner.add_delegate(self.restrictions());
+	}
+}
+
+struct Restrictions {
+	sync: Weak<SyncProvider>,
+	net: Weak<ManageNetwork>,
+}
+
+impl Restrictions {
+	fn net_peers(&self) -> Result<Peers, Error> {
+		let sync_status = take_weak!(self.sync).status();
+		let net_config = take_weak!(self.net).network_config();
+
+		Ok(Peers {
+			active: sync_status.num_active_peers,
+			connected: sync_status.num_peers,
+			max: sync_status.current_max_peers(net_config.min_peers, net_config.max_peers),
+			peers: Vec::new(),
+		})
+	}
+}
@@ -0,0 +1,138 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Optional logging of served RPC requests.
+//!
+//! When enabled, every call is logged with its method, hash of parameters, caller origin
+//! and execution time. Calls slower than the threshold are logged with full parameters,
+//! except for methods which may carry passwords, secrets or signing requests.
+
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering, ATOMIC_BOOL_INIT, ATOMIC_USIZE_INIT};
+use std::time::{Duration, Instant};
+use util::Hashable;
+
+static LOG_ALL: AtomicBool = ATOMIC_BOOL_INIT;
+// 0 when disabled.
+static SLOW_THRESHOLD_MS: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Prefixes of namespaces whose parameters are never logged.
+const REDACTED_NAMESPACES: &'static [&'static str] = &["personal_", "signer_", "private_"];
+/// Account management and signing methods whose parameters are never logged.
+const REDACTED_METHODS: &'static [&'static str] = &[
+	"eth_sendTransaction", "eth_sign", "eth_signTransaction",
+	"parity_accountPolicy", "parity_accountsInfo", "parity_changePassword", "parity_checkRequest",
+	"parity_combineTransaction", "parity_composeTransaction", "parity_decryptMessage",
+	"parity_getDappsAddresses", "parity_importGethAccounts", "parity_killAccount",
+	"parity_listGethAccounts", "parity_newAccountFromPhrase", "parity_newAccountFromSecret",
+	"parity_newAccountFromWallet", "parity_postSign", "parity_postTransaction",
+	"parity_setAccountMeta", "parity_setAccountName", "parity_setAccountPolicy",
+	"parity_setAccountTags", "parity_setDappsAddresses",
+	"parity_submitSignedTransaction", "parity_testPassword",
+];
+
+thread_local!(static ORIGIN: RefCell<Option<String>> = RefCell::new(None));
+
+/// Log every call (`log_all`) and/or calls taking longer than `slow_threshold`.
+pub fn configure(log_all: bool, slow_threshold: Option<Duration>) {
+	let threshold = slow_threshold.map_or(0, |t| to_millis(t) as usize);
+	LOG_ALL.store(log_all, Ordering::Relaxed);
+	SLOW_THRESHOLD_MS.store(threshold, Ordering::Relaxed);
+}
+
+fn is_enabled() -> bool {
+	LOG_ALL.load(Ordering::Relaxed) || SLOW_THRESHOLD_MS.load(Ordering::Relaxed) > 0
+}
+
+fn to_millis(d: Duration) -> u64 {
+	d.as_secs() * 1000 + d.subsec_nanos() as u64 / 1_000_000
+}
+
+/// Run `f` with calls attributed to given origin.
+/// Transports call handlers synchronously, so the origin is kept for the current thread.
+pub fn with_origin<F, T>(origin: Option<String>, f: F) -> T where F: FnOnce() -> T {
+	let previous = ORIGIN.with(|o| ::std::mem::replace(&mut *o.borrow_mut(), origin));
+	let result = f();
+	ORIGIN.with(|o| *o.borrow_mut() = previous);
+	result
+}
+
+/// Origin of the call handled by the current thread, if the transport provided one.
+pub fn current_origin() -> Option<String> {
+	ORIGIN.with(|o| o.borrow().clone())
+}
+
+fn origin() -> String {
+	current_origin().unwrap_or_else(|| "unknown".into())
+}
+
+fn is_redacted(method: &str) -> bool {
+	REDACTED_NAMESPACES.iter().any(|prefix| method.starts_with(prefix)) || REDACTED_METHODS.contains(&method)
+}
+
+/// Representation of `method` call parameters, if logging is enabled.
+pub fn params<P: Debug>(method: &str, params: &P) -> Option<String> {
+	match (is_enabled(), is_redacted(method)) {
+		(false, _) => None,
+		(true, true) => Some("<redacted>".into()),
+		(true, false) => Some(format!("{:?}", params)),
+	}
+}
+
+/// Log served call. `params` should come from `params()`.
+pub fn log(method: &str, params: Option<String>, start: Instant) {
+	let params = match params {
+		Some(params) => params,
+		None => return,
+	};
+
+	let elapsed = to_millis(start.elapsed());
+	let threshold = SLOW_THRESHOLD_MS.load(Ordering::Relaxed) as u64;
+	if threshold > 0 && elapsed >= threshold {
+		warn!(target: "rpc", "Slow request {} from {} took {}ms. Params: {}", method, origin(), elapsed, params);
+	} else if LOG_ALL.load(Ordering::Relaxed) {
+		info!(target: "rpc", "Request {} from {} took {}ms. Params hash: {}", method, origin(), elapsed, params.sha3());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{with_origin, origin, is_redacted};
+
+	#[test]
+	fn should_restore_previous_origin() {
+		assert_eq!(origin(), "unknown");
+		with_origin(Some("http://a.io".into()), || {
+			assert_eq!(origin(), "http://a.io");
+			with_origin(None, || assert_eq!(origin(), "unknown"));
+			assert_eq!(origin(), "http://a.io");
+		});
+		assert_eq!(origin(), "unknown");
+	}
+
+	#[test]
+	fn should_redact_account_and_signing_methods() {
+		assert!(is_redacted("personal_unlockAccount"));
+		assert!(is_redacted("personal_signAndSendTransaction"));
+		assert!(is_redacted("parity_newAccountFromSecret"));
+		assert!(is_redacted("parity_newAccountFromPhrase"));
+		assert!(is_redacted("parity_changePassword"));
+		assert!(is_redacted("signer_confirmRequest"));
+		assert!(!is_redacted("eth_getBalance"));
+		assert!(!is_redacted("parity_netPeers"));
+	}
+}
@@ -0,0 +1,80 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Estimates completion time of sync stages from observed progress.
+
+use std::collections::HashMap;
+use std::time::Instant;
+use util::Mutex;
+
+/// Remembers the first observation of every stage to compute progress rates.
+#[derive(Default)]
+pub struct ProgressTracker {
+	started: Mutex<HashMap<&'static str, (Instant, u64)>>,
+}
+
+impl ProgressTracker {
+	/// Records current progress of the stage and returns estimated number of seconds
+	/// until `done` reaches `total`, if the progress rate is known.
+	pub fn eta(&self, stage: &'static str, done: u64, total: u64) -> Option<u64> {
+		self.eta_at(stage, done, total, Instant::now())
+	}
+
+	fn eta_at(&self, stage: &'static str, done: u64, total: u64, now: Instant) -> Option<u64> {
+		let mut started = self.started.lock();
+		let &mut (start, start_done) = started.entry(stage).or_insert((now, done));
+		if done < start_done {
+			// stage was restarted.
+			started.insert(stage, (now, done));
+			return None;
+		}
+
+		let elapsed = now.duration_since(start);
+		let elapsed_ms = elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1_000_000;
+		let progress = done - start_done;
+		if progress == 0 || elapsed_ms == 0 {
+			return None;
+		}
+
+		Some(total.saturating_sub(done) * elapsed_ms / progress / 1000)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+	use super::ProgressTracker;
+
+	#[test]
+	fn should_estimate_remaining_time() {
+		let tracker = ProgressTracker::default();
+		let start = Instant::now();
+
+		assert_eq!(tracker.eta_at("blocks", 100, 1100, start), None);
+		// 100 blocks in 10 seconds, 900 remaining.
+		assert_eq!(tracker.eta_at("blocks", 200, 1100, start + Duration::from_secs(10)), Some(90));
+	}
+
+	#[test]
+	fn should_reset_restarted_stage() {
+		let tracker = ProgressTracker::default();
+		let start = Instant::now();
+
+		tracker.eta_at("warpState", 10, 100, start);
+		assert_eq!(tracker.eta_at("warpState", 0, 100, start + Duration::from_secs(5)), None);
+		assert_eq!(tracker.eta_at("warpState", 10, 100, start + Duration::from_secs(15)), Some(90));
+	}
+}
@@ -20,6 +20,7 @@ extern crate ethash;
 
 use std::io::{Write};
 use std::process::{Command, Stdio};
+use std::cmp;
 use std::collections::BTreeSet;
 use std::thread;
 use std::time::{Instant, Duration};
@@ -31,37 +32,49 @@ use jsonrpc_core::*;
 use util::{H256, Address, FixedHash, U256, H64, Uint};
 use util::sha3::*;
 use util::{FromHex, Mutex};
+use util::stats::weighted_percentiles;
 use rlp::{self, UntrustedRlp, View};
 use ethcore::account_provider::AccountProvider;
-use ethcore::client::{MiningBlockChainClient, BlockID, TransactionID, UncleID};
+use ethcore::client::{MiningBlockChainClient, BlockID, TransactionID, UncleID, StateOverride as EthStateOverride};
 use ethcore::header::{Header as BlockHeader, BlockNumber as EthBlockNumber};
 use ethcore::block::IsBlock;
 use ethcore::views::*;
 use ethcore::ethereum::Ethash;
-use ethcore::transaction::{Transaction as EthTransaction, SignedTransaction, Action};
+use ethcore::transaction::SignedTransaction;
 use ethcore::log_entry::LogEntry;
+use ethcore::receipt::Receipt as EthReceipt;
 use ethcore::filter::Filter as EthcoreFilter;
 use ethcore::snapshot::SnapshotService;
 use self::ethash::SeedHashCompute;
 use v1::traits::Eth;
 use v1::types::{
 	RichBlock, Block, BlockTransactions, BlockNumber, Bytes, SyncStatus, SyncInfo,
-	Transaction, CallRequest, Index, Filter, Log, Receipt, Work,
+	Transaction, CallRequest, Index, Filter, Log, Receipt, Work, FeeHistory,
 	H64 as RpcH64, H256 as RpcH256, H160 as RpcH160, U256 as RpcU256,
 };
-use v1::helpers::{CallRequest as CRequest, errors, limit_logs};
-use v1::helpers::dispatch::{dispatch_transaction, default_gas_price};
+use v1::helpers::{CallRequest as CRequest, errors, limit_logs, max_logs};
+use v1::helpers::dispatch::{dispatch_transaction, default_gas_price, fake_sign_call};
 use v1::helpers::block_import::is_major_importing;
 use v1::helpers::auto_args::Trailing;
+use v1::helpers::request_log;
+
+const DAPPS_DOMAIN: &'static str = ".parity";
 
 const EXTRA_INFO_PROOF: &'static str = "Object exists in in blockchain (fetched earlier), extra_info is always available if object exists; qed";
 
+/// Maximal number of blocks `eth_feeHistory` reports on.
+const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+
 /// Eth RPC options
 pub struct EthClientOptions {
 	/// Returns receipt from pending blocks
 	pub allow_pending_receipt_query: bool,
 	/// Send additional block number when asking for work
 	pub send_block_number_in_get_work: bool,
+	/// Maximal number of logs returned by `eth_getLogs`
+	pub max_logs: Option<usize>,
+	/// Maximal gas allowed for `eth_call` and `eth_estimateGas`
+	pub gas_cap: Option<U256>,
 }
 
 impl Default for EthClientOptions {
@@ -69,6 +82,8 @@ impl Default for EthClientOptions {
 		EthClientOptions {
 			allow_pending_receipt_query: true,
 			send_block_number_in_get_work: true,
+			max_logs: None,
+			gas_cap: None,
 		}
 	}
 }
@@ -208,18 +223,41 @@ impl<C, SN: ?Sized, S: ?Sized, M, EM> EthClient<C, SN, S, M, EM> where
 
 	fn sign_call(&self, request: CRequest) -> Result<SignedTransaction, Error> {
 		let (client, miner) = (take_weak!(self.client), take_weak!(self.miner));
-		let from = request.from.unwrap_or(Address::zero());
-		Ok(EthTransaction {
-			nonce: request.nonce.unwrap_or_else(|| client.latest_nonce(&from)),
-			action: request.to.map_or(Action::Create, Action::Call),
-			gas: request.gas.unwrap_or(U256::from(50_000_000)),
-			gas_price: request.gas_price.unwrap_or_else(|| default_gas_price(&*client, &*miner)),
-			value: request.value.unwrap_or_else(U256::zero),
-			data: request.data.map_or_else(Vec::new, |d| d.to_vec())
-		}.fake_sign(from))
+		fake_sign_call(&*client, &*miner, request, self.options.gas_cap)
 	}
 }
 
+/// Gas used ratio and gas prices at given percentiles of gas used in a block.
+fn block_fees<C>(client: &C, bytes: &[u8], percentiles: &[f64]) -> Result<(f64, Vec<RpcU256>), Error> where C: MiningBlockChainClient {
+	let block = BlockView::new(bytes);
+	let header = block.header_view();
+	let ratio = match header.gas_limit().low_u64() {
+		0 => 0.0,
+		limit => header.gas_used().low_u64() as f64 / limit as f64,
+	};
+
+	let transactions = block.transactions();
+	if percentiles.is_empty() || transactions.is_empty() {
+		return Ok((ratio, vec![RpcU256::default(); percentiles.len()]));
+	}
+
+	let receipts: Vec<EthReceipt> = match client.block_receipts(&header.sha3()) {
+		Some(bytes) => try!(UntrustedRlp::new(&bytes).as_val().map_err(|e| errors::internal("Invalid block receipts.", e))),
+		None => return Err(errors::not_enough_data()),
+	};
+
+	// receipts contain cumulative gas used
+	let mut previous = U256::zero();
+	let mut corpus: Vec<_> = transactions.iter().zip(receipts.iter()).map(|(tx, receipt)| {
+		let gas_used = receipt.gas_used - previous;
+		previous = receipt.gas_used;
+		(tx.gas_price, gas_used.low_u64())
+	}).collect();
+	corpus.sort();
+
+	Ok((ratio, weighted_percentiles(&corpus, percentiles).into_iter().map(Into::into).collect()))
+}
+
 pub fn pending_logs<M>(miner: &M, best_block: EthBlockNumber, filter: &EthcoreFilter) -> Vec<Log> where M: MinerService {
 	let receipts = miner.pending_receipts(best_block);
 
@@ -239,6 +277,18 @@ pub fn pending_logs<M>(miner: &M, best_block: EthBlockNumber, filter: &EthcoreFi
 	result
 }
 
+/// Identifies the dapp making a call: the subdomain of dapps served on their own domains,
+/// or the origin itself for any other page.
+fn dapp_id(origin: &str) -> String {
+	let host = origin.split("://").last().unwrap_or(origin);
+	let host = host.split(':').next().unwrap_or(host);
+	if host.ends_with(DAPPS_DOMAIN) {
+		host[..host.len() - DAPPS_DOMAIN.len()].to_owned()
+	} else {
+		origin.to_owned()
+	}
+}
+
 const MAX_QUEUE_SIZE_TO_MINE_ON: usize = 4;	// because uncles go back 6.
 
 impl<C, SN: ?Sized, S: ?Sized, M, EM> EthClient<C, SN, S, M, EM> where
@@ -335,6 +385,48 @@ impl<C, SN: ?Sized, S: ?Sized, M, EM> Eth for EthClient<C, SN, S, M, EM> where
 		Ok(RpcU256::from(default_gas_price(&*client, &*miner)))
 	}
 
+	fn fee_history(&self, block_count: RpcU256, newest: BlockNumber, percentiles: Trailing<Vec<f64>>) -> Result<FeeHistory, Error> {
+		try!(self.active());
+
+		let percentiles = percentiles.0;
+		if percentiles.iter().any(|p| *p < 0.0 || *p > 100.0) || percentiles.windows(2).any(|w| w[0] > w[1]) {
+			return Err(errors::invalid_params("rewardPercentiles", "Percentiles must be increasing values between 0 and 100."));
+		}
+
+		let client = take_weak!(self.client);
+		let block_count: U256 = block_count.into();
+		let block_count = cmp::min(block_count, MAX_FEE_HISTORY_BLOCKS.into()).low_u64();
+		let mut id = match newest {
+			// pending block is not sealed yet
+			BlockNumber::Pending => BlockID::Latest,
+			num => num.into(),
+		};
+
+		let mut history = FeeHistory::default();
+		for _ in 0..block_count {
+			let bytes = match client.block(id.clone()) {
+				Some(bytes) => bytes,
+				None if history.gas_used_ratio.is_empty() => return Err(errors::invalid_params("newestBlock", "Unknown block.")),
+				None => break,
+			};
+
+			let (ratio, reward) = try!(block_fees(&*client, &bytes, &percentiles));
+			history.gas_used_ratio.push(ratio);
+			history.reward.push(reward);
+
+			let header = BlockView::new(&bytes).header_view();
+			history.oldest_block = header.number().into();
+			if header.number() == 0 {
+				break;
+			}
+			id = BlockID::Hash(header.parent_hash());
+		}
+
+		history.gas_used_ratio.reverse();
+		history.reward.reverse();
+		Ok(history)
+	}
+
 	fn accounts(&self) -> Result<Vec<RpcH160>, Error> {
 		try!(self.active());
 
@@ -342,7 +434,11 @@ impl<C, SN: ?Sized, S: ?Sized, M, EM> Eth for EthClient<C, SN, S, M, EM> where
 		let accounts = try!(store.accounts().map_err(|e| errors::internal("Could not fetch accounts.", e)));
 		let addresses = try!(store.addresses_info().map_err(|e| errors::internal("Could not fetch accounts.", e)));
 
-		let set: BTreeSet<Address> = accounts.into_iter().chain(addresses.keys().cloned()).collect();
+		let mut set: BTreeSet<Address> = accounts.into_iter().chain(addresses.keys().cloned()).collect();
+		// dapps only see the accounts they were given access to.
+		if let Some(allowed) = request_log::current_origin().and_then(|origin| store.dapp_restriction(&dapp_id(&origin))) {
+			set.retain(|address| allowed.contains(address));
+		}
 		Ok(set.into_iter().map(Into::into).collect())
 	}
 
@@ -532,7 +628,7 @@ impl<C, SN: ?Sized, S: ?Sized, M, EM> Eth for EthClient<C, SN, S, M, EM> where
 			logs.extend(pending);
 		}
 
-		let logs = limit_logs(logs, filter.limit);
+		let logs = limit_logs(logs, max_logs(filter.limit, self.options.max_logs));
 
 		Ok(logs)
 	}
@@ -623,21 +719,26 @@ impl<C, SN: ?Sized, S: ?Sized, M, EM> Eth for EthClient<C, SN, S, M, EM> where
 		self.send_raw_transaction(raw)
 	}
 
-	fn call(&self, request: CallRequest, num: Trailing<BlockNumber>) -> Result<Bytes, Error> {
+	fn call(&self, request: CallRequest, num: Trailing<BlockNumber>, overrides: Trailing<StateOverride>) -> Result<Bytes, Error> {
 		try!(self.active());
 
 		let request = CallRequest::into(request);
 		let signed = try!(self.sign_call(request));
+		let overrides: EthStateOverride = overrides.0.into_iter()
+			.map(|(address, account)| (address.into(), account.into()))
+			.collect();
 
 		let result = match num.0 {
+			BlockNumber::Pending if !overrides.is_empty() => return Err(errors::unimplemented(Some("State overrides are not supported for the pending block.".into()))),
 			BlockNumber::Pending => take_weak!(self.miner).call(&*take_weak!(self.client), &signed, Default::default()),
-			num => take_weak!(self.client).call(&signed, num.into(), Default::default()),
+			num => take_weak!(self.client).call_with_overrides(&signed, num.into(), Default::default(), overrides),
 		};
 
-
-		result
-			.map(|b| b.output.into())
-			.map_err(errors::from_call_error)
+		let executed = try!(result.map_err(errors::from_call_error));
+		match executed.exception {
+			Some(exception) => Err(errors::vm_execution(exception, &executed.output)),
+			None => Ok(executed.output.into()),
+		}
 	}
 
 	fn estimate_gas(&self, request: CallRequest, num: Trailing<BlockNumber>) -> Result<RpcU256, Error> {
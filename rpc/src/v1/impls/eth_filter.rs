@@ -25,7 +25,7 @@ use ethcore::client::{BlockChainClient, BlockID};
 use util::Mutex;
 use v1::traits::EthFilter;
 use v1::types::{BlockNumber, Index, Filter, FilterChanges, Log, H256 as RpcH256, U256 as RpcU256};
-use v1::helpers::{PollFilter, PollManager, limit_logs};
+use v1::helpers::{PollFilter, PollManager, limit_logs, max_logs};
 use v1::impls::eth::pending_logs;
 
 /// Eth filter rpc implementation.
@@ -36,6 +36,7 @@ pub struct EthFilterClient<C, M> where
 	client: Weak<C>,
 	miner: Weak<M>,
 	polls: Mutex<PollManager<PollFilter>>,
+	max_logs: Option<usize>,
 }
 
 impl<C, M> EthFilterClient<C, M> where
@@ -48,9 +49,16 @@ impl<C, M> EthFilterClient<C, M> where
 			client: Arc::downgrade(client),
 			miner: Arc::downgrade(miner),
 			polls: Mutex::new(PollManager::new()),
+			max_logs: None,
 		}
 	}
 
+	/// Limits the number of logs returned by a single filter query.
+	pub fn with_max_logs(mut self, max: usize) -> Self {
+		self.max_logs = Some(max);
+		self
+	}
+
 	fn active(&self) -> Result<(), Error> {
 		// TODO: only call every 30s at most.
 		take_weak!(self.client).keep_alive();
@@ -167,7 +175,7 @@ impl<C, M> EthFilter for EthFilterClient<C, M>
 						logs.extend(new_pending_logs);
 					}
 
-					let logs = limit_logs(logs, filter.limit);
+					let logs = limit_logs(logs, max_logs(filter.limit, self.max_logs));
 
 					// save the number of the next block as a first block from which
 					// we want to get logs
@@ -197,7 +205,7 @@ impl<C, M> EthFilter for EthFilterClient<C, M>
 					logs.extend(pending_logs(&*take_weak!(self.miner), best_block, &filter));
 				}
 
-				let logs = limit_logs(logs, filter.limit);
+				let logs = limit_logs(logs, max_logs(filter.limit, self.max_logs));
 
 				Ok(logs)
 			},
@@ -32,11 +32,13 @@ mod parity;
 mod parity_accounts;
 mod parity_set;
 mod personal;
+mod private;
 mod signer;
 mod signing;
 mod signing_unsafe;
 mod rpc;
 mod traces;
+mod txpool;
 mod web3;
 
 pub use self::web3::Web3Client;
@@ -47,8 +49,10 @@ pub use self::parity::ParityClient;
 pub use self::parity_accounts::ParityAccountsClient;
 pub use self::parity_set::ParitySetClient;
 pub use self::personal::PersonalClient;
+pub use self::private::PrivateClient;
 pub use self::signer::SignerClient;
 pub use self::signing::SigningQueueClient;
 pub use self::signing_unsafe::SigningUnsafeClient;
 pub use self::traces::TracesClient;
+pub use self::txpool::TxPoolClient;
 pub use self::rpc::RpcClient;
@@ -19,30 +19,43 @@ use std::sync::{Arc, Weak};
 use std::str::FromStr;
 use std::collections::BTreeMap;
 
-use util::{RotatingLogger, Address};
+use util::{RotatingLogger, Address, Uint, U256 as EthU256};
+use rlp::{UntrustedRlp, View};
 use util::misc::version_data;
 
 use crypto::ecies;
 use ethkey::{Brain, Generator};
 use ethstore::random_phrase;
-use ethsync::{SyncProvider, ManageNetwork};
+use ethsync::{SyncProvider, ManageNetwork, SyncState};
 use ethcore::miner::MinerService;
-use ethcore::client::{MiningBlockChainClient};
+use ethcore::client::{MiningBlockChainClient, BlockID, TransactionID};
+use ethcore::views::HeaderView;
 use ethcore::mode::Mode;
+use ethcore::snapshot::{SnapshotService, RestorationStatus};
 use ethcore::account_provider::AccountProvider;
+use ethcore::transaction::SignedTransaction;
+use ethcore::error::TransactionError;
 
 use jsonrpc_core::Error;
 use v1::traits::Parity;
 use v1::types::{
 	Bytes, U256, H160, H256, H512,
-	Peers, Transaction, RpcSettings, Histogram,
-	TransactionStats, LocalTransactionStatus,
-	BlockNumber,
+	Peers, Transaction, RpcSettings, Histogram, BlockReward, ChainSpec, PowCacheStatus, ValidatorUptime,
+	TransactionStats, LocalTransactionStatus, NonceGap,
+	BlockNumber, Health, SyncProgress, StageProgress, DbColumnStats, MemoryProfile, BlockChainCacheStats,
+	CallRequest, SimulationResult, DecodedTransaction, Index, LogProof,
 };
-use v1::helpers::{errors, SigningQueue, SignerService, NetworkSettings};
-use v1::helpers::dispatch::DEFAULT_MAC;
+use v1::helpers::{errors, SigningQueue, SignerService, NetworkSettings, CallRequest as CRequest};
+use v1::helpers::health::NodeHealth;
+use v1::helpers::sync_progress::ProgressTracker;
+use v1::helpers::dispatch::{DEFAULT_MAC, fake_sign_call};
 use v1::helpers::auto_args::Trailing;
 
+/// Maximal number of transactions `parity_simulateTransactions` executes in a single call.
+const MAX_SIMULATED_TRANSACTIONS: usize = 100;
+/// Maximal number of transactions returned by `parity_transactionsBySender`.
+const MAX_SENDER_TRANSACTIONS: usize = 1000;
+
 /// Parity implementation.
 pub struct ParityClient<C, M, S: ?Sized> where
 	C: MiningBlockChainClient,
@@ -52,6 +65,7 @@ pub struct ParityClient<C, M, S: ?Sized> where
 	client: Weak<C>,
 	miner: Weak<M>,
 	sync: Weak<S>,
+	snapshot: Weak<SnapshotService>,
 	net: Weak<ManageNetwork>,
 	accounts: Weak<AccountProvider>,
 	logger: Arc<RotatingLogger>,
@@ -59,6 +73,9 @@ pub struct ParityClient<C, M, S: ?Sized> where
 	signer: Option<Arc<SignerService>>,
 	dapps_interface: Option<String>,
 	dapps_port: Option<u16>,
+	health: Arc<NodeHealth>,
+	progress: ProgressTracker,
+	gas_cap: Option<EthU256>,
 }
 
 impl<C, M, S: ?Sized> ParityClient<C, M, S> where
@@ -71,6 +88,7 @@ impl<C, M, S: ?Sized> ParityClient<C, M, S> where
 		client: &Arc<C>,
 		miner: &Arc<M>,
 		sync: &Arc<S>,
+		snapshot: &Arc<SnapshotService>,
 		net: &Arc<ManageNetwork>,
 		store: &Arc<AccountProvider>,
 		logger: Arc<RotatingLogger>,
@@ -78,11 +96,13 @@ impl<C, M, S: ?Sized> ParityClient<C, M, S> where
 		signer: Option<Arc<SignerService>>,
 		dapps_interface: Option<String>,
 		dapps_port: Option<u16>,
+		health: Arc<NodeHealth>,
 	) -> Self {
 		ParityClient {
 			client: Arc::downgrade(client),
 			miner: Arc::downgrade(miner),
 			sync: Arc::downgrade(sync),
+			snapshot: Arc::downgrade(snapshot),
 			net: Arc::downgrade(net),
 			accounts: Arc::downgrade(store),
 			logger: logger,
@@ -90,14 +110,28 @@ impl<C, M, S: ?Sized> ParityClient<C, M, S> where
 			signer: signer,
 			dapps_interface: dapps_interface,
 			dapps_port: dapps_port,
+			health: health,
+			progress: ProgressTracker::default(),
+			gas_cap: None,
 		}
 	}
 
+	/// Refuse calls and simulated transactions requesting more gas than `gas_cap`, if given.
+	pub fn with_gas_cap(mut self, gas_cap: Option<EthU256>) -> Self {
+		self.gas_cap = gas_cap;
+		self
+	}
+
 	fn active(&self) -> Result<(), Error> {
 		// TODO: only call every 30s at most.
 		take_weak!(self.client).keep_alive();
 		Ok(())
 	}
+
+	fn sign_call(&self, request: CRequest) -> Result<SignedTransaction, Error> {
+		let (client, miner) = (take_weak!(self.client), take_weak!(self.miner));
+		fake_sign_call(&*client, &*miner, request, self.gas_cap)
+	}
 }
 
 impl<C, M, S: ?Sized> Parity for ParityClient<C, M, S> where
@@ -214,6 +248,42 @@ impl<C, M, S: ?Sized> Parity for ParityClient<C, M, S> where
 		take_weak!(self.client).gas_price_histogram(100, 10).ok_or_else(errors::not_enough_data).map(Into::into)
 	}
 
+	fn block_reward(&self, num: BlockNumber) -> Result<Option<BlockReward>, Error> {
+		try!(self.active());
+		Ok(take_weak!(self.client).block_rewards(num.into()).map(Into::into))
+	}
+
+	fn chain_spec(&self) -> Result<ChainSpec, Error> {
+		try!(self.active());
+
+		let client = take_weak!(self.client);
+		Ok(ChainSpec::new(client.chain_spec_info(), &client.latest_schedule()))
+	}
+
+	fn pow_cache_status(&self) -> Result<Option<PowCacheStatus>, Error> {
+		try!(self.active());
+
+		Ok(take_weak!(self.client).pow_cache_status().map(Into::into))
+	}
+
+	fn validator_uptime(&self) -> Result<Option<Vec<ValidatorUptime>>, Error> {
+		try!(self.active());
+
+		Ok(take_weak!(self.client).validator_uptime().map(|u| u.into_iter().map(Into::into).collect()))
+	}
+
+	fn finalized_block(&self) -> Result<Option<H256>, Error> {
+		try!(self.active());
+
+		Ok(take_weak!(self.client).block_hash(BlockID::Finalized).map(Into::into))
+	}
+
+	fn log_proof(&self, hash: H256, log_index: Index) -> Result<Option<LogProof>, Error> {
+		try!(self.active());
+
+		Ok(take_weak!(self.client).log_proof(TransactionID::Hash(hash.into()), log_index.value()).map(Into::into))
+	}
+
 	fn unsigned_transactions_count(&self) -> Result<usize, Error> {
 		try!(self.active());
 
@@ -286,6 +356,12 @@ impl<C, M, S: ?Sized> Parity for ParityClient<C, M, S> where
 		)
 	}
 
+	fn nonce_gaps(&self) -> Result<Vec<NonceGap>, Error> {
+		try!(self.active());
+
+		Ok(take_weak!(self.miner).nonce_gaps().into_iter().map(Into::into).collect())
+	}
+
 	fn signer_port(&self) -> Result<u16, Error> {
 		try!(self.active());
 
@@ -353,4 +429,188 @@ impl<C, M, S: ?Sized> Parity for ParityClient<C, M, S> where
 			(format!("0x{}", a.hex()), m)
 		}).collect())
 	}
+
+	fn node_health(&self) -> Result<Health, Error> {
+		try!(self.active());
+
+		Ok(self.health.health())
+	}
+
+	fn sync_status(&self) -> Result<SyncProgress, Error> {
+		try!(self.active());
+
+		let status = take_weak!(self.sync).status();
+		let chain_info = take_weak!(self.client).chain_info();
+		let restoration = take_weak!(self.snapshot).status();
+		let mut stages = Vec::new();
+		let ancient = {
+			let mut stage = |name: &'static str, done: u64, total: u64| {
+				stages.push(StageProgress {
+					stage: name.into(),
+					done: done,
+					total: total,
+					eta: self.progress.eta(name, done, total),
+				});
+			};
+
+			if let RestorationStatus::Ongoing { state_chunks, block_chunks, state_chunks_done, block_chunks_done } = restoration {
+				stage("warpState", state_chunks_done as u64, state_chunks as u64);
+				stage("warpBlocks", block_chunks_done as u64, block_chunks as u64);
+			}
+
+			let highest_block = status.highest_block_number.unwrap_or(status.start_block_number);
+			if highest_block > chain_info.best_block_number {
+				stage("blocks", chain_info.best_block_number, highest_block);
+			}
+
+			match (chain_info.ancient_block_number, chain_info.first_block_number) {
+				(Some(ancient), Some(first)) => {
+					stage("ancientBlocks", ancient, first);
+					true
+				},
+				_ => false,
+			}
+		};
+
+		let current = match status.state {
+			SyncState::WaitingPeers => "waitingPeers",
+			SyncState::SnapshotManifest => "warpManifest",
+			SyncState::SnapshotData | SyncState::SnapshotWaiting => "warpState",
+			SyncState::Blocks | SyncState::Waiting | SyncState::NewBlocks => "blocks",
+			SyncState::Idle if ancient => "ancientBlocks",
+			SyncState::Idle => "idle",
+		};
+
+		Ok(SyncProgress {
+			stage: current.into(),
+			stages: stages,
+			peers: status.num_peers,
+			active_peers: status.num_active_peers,
+		})
+	}
+
+	fn db_stats(&self) -> Result<Vec<DbColumnStats>, Error> {
+		try!(self.active());
+
+		Ok(take_weak!(self.client).db_stats().into_iter().map(Into::into).collect())
+	}
+
+	fn memory_profile(&self) -> Result<MemoryProfile, Error> {
+		try!(self.active());
+
+		let client = take_weak!(self.client).memory_usage();
+		let transaction_queue = take_weak!(self.miner).status().transactions_mem_used;
+		let sync = take_weak!(self.sync).status().mem_used;
+
+		Ok(MemoryProfile {
+			block_queue: client.block_queue,
+			blockchain_cache: client.blockchain_cache,
+			state_cache: client.state_cache,
+			trace_cache: client.trace_cache,
+			transaction_queue: transaction_queue,
+			sync: sync,
+			total: client.total() + transaction_queue + sync,
+		})
+	}
+
+	fn cache_stats(&self) -> Result<BlockChainCacheStats, Error> {
+		try!(self.active());
+
+		Ok(take_weak!(self.client).blockchain_cache_stats().into())
+	}
+
+	fn simulate_transactions(&self, requests: Vec<CallRequest>, block_number: Trailing<BlockNumber>) -> Result<Vec<SimulationResult>, Error> {
+		try!(self.active());
+
+		let block = match block_number.0 {
+			BlockNumber::Pending => return Err(errors::unimplemented(Some("Simulation on top of the pending block is not supported.".into()))),
+			num => num.into(),
+		};
+		if requests.len() > MAX_SIMULATED_TRANSACTIONS {
+			return Err(errors::invalid_params("Transactions", format!("At most {} transactions can be simulated at once.", MAX_SIMULATED_TRANSACTIONS)));
+		}
+		let transactions = try!(requests.into_iter()
+			.map(|request| self.sign_call(CallRequest::into(request)))
+			.collect::<Result<Vec<_>, _>>());
+
+		take_weak!(self.client).call_many(transactions, block, Default::default())
+			.map(|results| results.into_iter().map(Into::into).collect())
+			.map_err(errors::from_call_error)
+	}
+
+	fn transactions_by_sender(&self, sender: H160, from: BlockNumber, to: BlockNumber) -> Result<Vec<Transaction>, Error> {
+		try!(self.active());
+
+		let client = take_weak!(self.client);
+		let number = |num: BlockNumber| client.block_header(num.into())
+			.map(|header| HeaderView::new(&header).number())
+			.ok_or_else(|| errors::invalid_params("Block", "Unknown block"));
+		let (from, to) = (try!(number(from)), try!(number(to)));
+
+		let transactions = try!(client.transactions_by_sender(&sender.into(), from, to, MAX_SENDER_TRANSACTIONS + 1)
+			.ok_or_else(errors::sender_index_disabled));
+		if transactions.len() > MAX_SENDER_TRANSACTIONS {
+			return Err(errors::too_many_transactions(MAX_SENDER_TRANSACTIONS));
+		}
+		Ok(transactions.into_iter().map(Transaction::from).collect())
+	}
+
+	fn decode_raw_transaction(&self, raw: Bytes) -> Result<DecodedTransaction, Error> {
+		try!(self.active());
+
+		let raw = raw.to_vec();
+		let t: SignedTransaction = try!(UntrustedRlp::new(&raw).as_val().map_err(errors::from_rlp_error));
+		let client = take_weak!(self.client);
+		let miner = take_weak!(self.miner);
+		let schedule = client.latest_schedule();
+		let intrinsic_gas = t.gas_required(&schedule);
+		let minimal_gas: EthU256 = intrinsic_gas.into();
+
+		let mut problems = Vec::new();
+		if !t.signature().is_low_s() {
+			problems.push("Signature has a high S value.".to_owned());
+		}
+		if t.network_id().is_some() && t.network_id() != client.signing_network_id() {
+			problems.push(errors::transaction_message(TransactionError::InvalidNetworkId));
+		}
+		if t.gas < minimal_gas {
+			problems.push(errors::transaction_message(TransactionError::InsufficientGas {
+				minimal: minimal_gas,
+				got: t.gas,
+			}));
+		}
+		let gas_limit = HeaderView::new(&client.best_block_header()).gas_limit();
+		if t.gas > gas_limit {
+			problems.push(errors::transaction_message(TransactionError::GasLimitExceeded {
+				limit: gas_limit,
+				got: t.gas,
+			}));
+		}
+		if t.gas_price < miner.minimal_gas_price() {
+			problems.push(errors::transaction_message(TransactionError::InsufficientGasPrice {
+				minimal: miner.minimal_gas_price(),
+				got: t.gas_price,
+			}));
+		}
+
+		match t.sender() {
+			Ok(sender) => {
+				if t.nonce < client.latest_nonce(&sender) {
+					problems.push(errors::transaction_message(TransactionError::Old));
+				}
+				let balance = client.latest_balance(&sender);
+				let (gas_cost, overflow_1) = t.gas.overflowing_mul(t.gas_price);
+				let (cost, overflow_2) = gas_cost.overflowing_add(t.value);
+				if overflow_1 || overflow_2 || cost > balance {
+					problems.push(errors::transaction_message(TransactionError::InsufficientBalance {
+						balance: balance,
+						cost: cost,
+					}));
+				}
+			},
+			Err(e) => problems.push(format!("Invalid signature: {}.", e)),
+		}
+
+		Ok(DecodedTransaction::new(&t, intrinsic_gas, problems))
+	}
 }
@@ -19,13 +19,12 @@ use std::sync::{Arc, Weak};
 use std::collections::BTreeMap;
 use util::{Address};
 
-use ethkey::{Brain, Generator};
 use ethcore::account_provider::AccountProvider;
 use ethcore::client::MiningBlockChainClient;
 
 use jsonrpc_core::{Value, Error, to_value};
 use v1::traits::ParityAccounts;
-use v1::types::{H160 as RpcH160, H256 as RpcH256};
+use v1::types::{H160 as RpcH160, H256 as RpcH256, AccountPolicy};
 use v1::helpers::errors;
 
 /// Account management (personal) rpc implementation.
@@ -61,6 +60,7 @@ impl<C: 'static> ParityAccounts for ParityAccountsClient<C> where C: MiningBlock
 			let m = map![
 				"name".to_owned() => to_value(&v.name),
 				"meta".to_owned() => to_value(&v.meta),
+				"tags".to_owned() => to_value(&store.account_tags(&a)),
 				"uuid".to_owned() => if let &Some(ref uuid) = &v.uuid {
 					to_value(uuid)
 				} else {
@@ -75,7 +75,7 @@ impl<C: 'static> ParityAccounts for ParityAccountsClient<C> where C: MiningBlock
 		try!(self.active());
 		let store = take_weak!(self.accounts);
 
-		store.insert_account(*Brain::new(phrase).generate().unwrap().secret(), &pass)
+		store.new_account_from_phrase(&phrase, &pass)
 			.map(Into::into)
 			.map_err(|e| errors::account("Could not create account.", e))
 	}
@@ -148,8 +148,35 @@ impl<C: 'static> ParityAccounts for ParityAccountsClient<C> where C: MiningBlock
 		Ok(true)
 	}
 
-	fn set_account_visibility(&self, _address: RpcH160, _dapp: RpcH256, _visible: bool) -> Result<bool, Error> {
-		Ok(false)
+	fn set_account_tags(&self, addr: RpcH160, tags: Vec<String>) -> Result<bool, Error> {
+		try!(self.active());
+		take_weak!(self.accounts).set_account_tags(addr.into(), tags);
+		Ok(true)
+	}
+
+	fn set_dapps_addresses(&self, dapp: String, addresses: Vec<RpcH160>) -> Result<bool, Error> {
+		try!(self.active());
+		take_weak!(self.accounts).set_dapps_addresses(dapp, addresses.into_iter().map(Into::into).collect());
+		Ok(true)
+	}
+
+	fn dapps_addresses(&self, dapp: String) -> Result<Vec<RpcH160>, Error> {
+		try!(self.active());
+		take_weak!(self.accounts)
+			.dapps_addresses(&dapp)
+			.map(|accounts| accounts.into_iter().map(Into::into).collect())
+			.map_err(|e| errors::account("Could not fetch accounts.", e))
+	}
+
+	fn set_account_policy(&self, addr: RpcH160, policy: Option<AccountPolicy>) -> Result<bool, Error> {
+		try!(self.active());
+		take_weak!(self.accounts).set_account_policy(addr.into(), policy.map(Into::into));
+		Ok(true)
+	}
+
+	fn account_policy(&self, addr: RpcH160) -> Result<Option<AccountPolicy>, Error> {
+		try!(self.active());
+		Ok(take_weak!(self.accounts).account_policy(&addr.into()).map(Into::into))
 	}
 
 	fn import_geth_accounts(&self, addresses: Vec<RpcH160>) -> Result<Vec<RpcH160>, Error> {
@@ -17,6 +17,7 @@
 /// Parity-specific rpc interface for operations altering the settings.
 use std::{fs, io};
 use std::sync::{Arc, Weak, mpsc};
+use std::time::Duration;
 
 use ethcore::miner::MinerService;
 use ethcore::client::MiningBlockChainClient;
@@ -28,8 +29,9 @@ use util::{Mutex, sha3};
 use jsonrpc_core::Error;
 use v1::helpers::auto_args::Ready;
 use v1::helpers::errors;
+use v1::helpers::access_policy::AccessPolicy;
 use v1::traits::ParitySet;
-use v1::types::{Bytes, H160, H256, U256};
+use v1::types::{Bytes, H160, H256, H512, U256};
 
 /// Parity-specific rpc interface for operations altering the settings.
 pub struct ParitySetClient<C, M, F=FetchClient> where
@@ -40,6 +42,7 @@ pub struct ParitySetClient<C, M, F=FetchClient> where
 	client: Weak<C>,
 	miner: Weak<M>,
 	net: Weak<ManageNetwork>,
+	access_policy: Arc<AccessPolicy>,
 	fetch: Mutex<F>,
 }
 
@@ -48,8 +51,8 @@ impl<C, M> ParitySetClient<C, M, FetchClient> where
 	M: MinerService
 {
 	/// Creates new `ParitySetClient` with default `FetchClient`.
-	pub fn new(client: &Arc<C>, miner: &Arc<M>, net: &Arc<ManageNetwork>) -> Self {
-		Self::with_fetch(client, miner, net)
+	pub fn new(client: &Arc<C>, miner: &Arc<M>, net: &Arc<ManageNetwork>, access_policy: &Arc<AccessPolicy>) -> Self {
+		Self::with_fetch(client, miner, net, access_policy)
 	}
 }
 
@@ -59,11 +62,12 @@ impl<C, M, F> ParitySetClient<C, M, F> where
 	F: Fetch,
 {
 	/// Creates new `ParitySetClient` with default `FetchClient`.
-	pub fn with_fetch(client: &Arc<C>, miner: &Arc<M>, net: &Arc<ManageNetwork>) -> Self {
+	pub fn with_fetch(client: &Arc<C>, miner: &Arc<M>, net: &Arc<ManageNetwork>, access_policy: &Arc<AccessPolicy>) -> Self {
 		ParitySetClient {
 			client: Arc::downgrade(client),
 			miner: Arc::downgrade(miner),
 			net: Arc::downgrade(net),
+			access_policy: access_policy.clone(),
 			fetch: Mutex::new(F::default()),
 		}
 	}
@@ -139,6 +143,15 @@ impl<C, M, F> ParitySet for ParitySetClient<C, M, F> where
 		}
 	}
 
+	fn add_boot_node(&self, node: String) -> Result<bool, Error> {
+		try!(self.active());
+
+		match take_weak!(self.net).add_boot_node(node) {
+			Ok(()) => Ok(true),
+			Err(e) => Err(errors::invalid_params("Node address", e)),
+		}
+	}
+
 	fn remove_reserved_peer(&self, peer: String) -> Result<bool, Error> {
 		try!(self.active());
 
@@ -172,6 +185,14 @@ impl<C, M, F> ParitySet for ParitySetClient<C, M, F> where
 		Ok(true)
 	}
 
+	fn rotate_node_key(&self, grace_period: u64) -> Result<H512, Error> {
+		try!(self.active());
+
+		take_weak!(self.net).rotate_node_key(grace_period)
+			.map(Into::into)
+			.map_err(|e| errors::internal("Node key rotation failed", e))
+	}
+
 	fn set_mode(&self, mode: String) -> Result<bool, Error> {
 		take_weak!(self.client).set_mode(match mode.as_str() {
 			"offline" => Mode::Off,
@@ -183,6 +204,39 @@ impl<C, M, F> ParitySet for ParitySetClient<C, M, F> where
 		Ok(true)
 	}
 
+	fn set_cors(&self, cors: Option<Vec<String>>) -> Result<bool, Error> {
+		try!(self.active());
+
+		self.access_policy.set_cors(cors);
+		Ok(true)
+	}
+
+	fn dev_mine(&self) -> Result<H256, Error> {
+		try!(self.active());
+
+		take_weak!(self.miner).force_seal(&*take_weak!(self.client))
+			.map(Into::into)
+			.ok_or_else(errors::cannot_seal)
+	}
+
+	fn dev_set_block_interval(&self, seconds: u64) -> Result<bool, Error> {
+		try!(self.active());
+
+		let interval = match seconds {
+			0 => None,
+			s => Some(Duration::from_secs(s)),
+		};
+		take_weak!(self.miner).set_seal_interval(interval);
+		Ok(true)
+	}
+
+	fn db_compact(&self) -> Result<bool, Error> {
+		try!(self.active());
+
+		try!(take_weak!(self.client).compact_db().map_err(|e| errors::internal("Database compaction failed", e)));
+		Ok(true)
+	}
+
 	fn hash_content(&self, ready: Ready<H256>, url: String) {
 		let res = self.active();
 
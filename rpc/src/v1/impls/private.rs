@@ -0,0 +1,76 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Private transactions rpc implementation.
+
+use std::sync::{Arc, Weak};
+use rlp::UntrustedRlp;
+use jsonrpc_core::Error;
+use ethcore::client::MiningBlockChainClient;
+use ethcore::miner::MinerService;
+use ethcore::private_transactions::Provider;
+use ethcore::transaction::SignedTransaction;
+use v1::helpers::errors;
+use v1::traits::Private;
+use v1::types::{Bytes, H256, PrivateTransaction, PrivateTransactionReceipt};
+
+/// Private transactions rpc implementation.
+pub struct PrivateClient<C, M> where C: MiningBlockChainClient, M: MinerService {
+	provider: Option<Arc<Provider>>,
+	client: Weak<C>,
+	miner: Weak<M>,
+}
+
+impl<C, M> PrivateClient<C, M> where C: MiningBlockChainClient, M: MinerService {
+	/// Creates new `PrivateClient`. Without a provider all methods return an error.
+	pub fn new(provider: Option<Arc<Provider>>, client: &Arc<C>, miner: &Arc<M>) -> Self {
+		PrivateClient {
+			provider: provider,
+			client: Arc::downgrade(client),
+			miner: Arc::downgrade(miner),
+		}
+	}
+
+	fn provider(&self) -> Result<&Provider, Error> {
+		self.provider.as_ref().map(|p| &**p).ok_or_else(errors::private_transactions_disabled)
+	}
+}
+
+impl<C, M> Private for PrivateClient<C, M> where C: MiningBlockChainClient + 'static, M: MinerService + 'static {
+	fn send_transaction(&self, raw: Bytes) -> Result<PrivateTransactionReceipt, Error> {
+		let provider = try!(self.provider());
+		let raw = raw.to_vec();
+		let transaction: SignedTransaction = try!(UntrustedRlp::new(&raw).as_val().map_err(errors::from_rlp_error));
+
+		provider.send_transaction(&*take_weak!(self.client), &*take_weak!(self.miner), transaction)
+			.map(Into::into)
+			.map_err(errors::from_private_transaction_error)
+	}
+
+	fn import_transaction(&self, raw: Bytes) -> Result<H256, Error> {
+		let provider = try!(self.provider());
+		provider.import_transaction(&*take_weak!(self.client), &raw.to_vec())
+			.map(Into::into)
+			.map_err(errors::from_private_transaction_error)
+	}
+
+	fn transaction(&self, hash: H256) -> Result<Option<PrivateTransaction>, Error> {
+		let provider = try!(self.provider());
+		provider.transaction(&hash.into())
+			.map(|t| t.map(Into::into))
+			.map_err(errors::from_private_transaction_error)
+	}
+}
@@ -96,7 +96,7 @@ impl<C: 'static, M: 'static> Signer for SignerClient<C, M> where C: MiningBlockC
 				_ => {},
 			}
 			// Execute
-			let result = dispatch::execute(&*client, &*miner, &*accounts, payload, Some(pass));
+			let result = dispatch::execute_confirmed(&*client, &*miner, &*accounts, payload, pass);
 			if let Ok(ref response) = result {
 				signer.request_confirmed(id, Ok(response.clone()));
 			}
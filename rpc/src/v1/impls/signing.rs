@@ -36,6 +36,7 @@ use v1::types::{
 	Either as RpcEither,
 	RichRawTransaction as RpcRichRawTransaction,
 	TransactionRequest as RpcTransactionRequest,
+	UnsignedTransaction as RpcUnsignedTransaction,
 	ConfirmationPayload as RpcConfirmationPayload,
 	ConfirmationResponse as RpcConfirmationResponse
 };
@@ -98,7 +99,8 @@ impl<C, M> SigningQueueClient<C, M> where
 		let accounts = take_weak!(self.accounts);
 
 		let sender = payload.sender();
-		if accounts.is_unlocked(sender) {
+		// requests violating the account policy always go to the signer
+		if accounts.is_unlocked(sender) && dispatch::check_policy(&*accounts, &payload).is_ok() {
 			return dispatch::execute(&*client, &*miner, &*accounts, payload, None).map(DispatchResult::Value);
 		}
 
@@ -162,6 +164,21 @@ impl<C: 'static, M: 'static> ParitySigning for SigningQueueClient<C, M> where
 		res
 	}
 
+	fn compose_transaction(&self, request: RpcTransactionRequest) -> Result<RpcUnsignedTransaction, Error> {
+		try!(self.active());
+		Ok(dispatch::compose_transaction(&*take_weak!(self.client), &*take_weak!(self.miner), request.into()))
+	}
+
+	fn combine_transaction(&self, unsigned: RpcUnsignedTransaction, signature: RpcH520) -> Result<RpcRichRawTransaction, Error> {
+		try!(self.active());
+		dispatch::combine_transaction(unsigned, signature).map(Into::into)
+	}
+
+	fn submit_signed_transaction(&self, unsigned: RpcUnsignedTransaction, signature: RpcH520) -> Result<RpcH256, Error> {
+		try!(self.active());
+		dispatch::submit_transaction(&*take_weak!(self.client), &*take_weak!(self.miner), unsigned, signature).map(Into::into)
+	}
+
 	fn decrypt_message(&self, ready: Ready<RpcBytes>, address: RpcH160, data: RpcBytes) {
 		let res = self.active()
 			.and_then(|_| self.dispatch(RpcConfirmationPayload::Decrypt((address, data).into())));
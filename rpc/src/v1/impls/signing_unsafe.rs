@@ -33,6 +33,7 @@ use v1::types::{
 	Either as RpcEither,
 	RichRawTransaction as RpcRichRawTransaction,
 	TransactionRequest as RpcTransactionRequest,
+	UnsignedTransaction as RpcUnsignedTransaction,
 	ConfirmationPayload as RpcConfirmationPayload,
 	ConfirmationResponse as RpcConfirmationResponse,
 };
@@ -138,4 +139,19 @@ impl<C: 'static, M: 'static> ParitySigning for SigningUnsafeClient<C, M> where
 		// We don't support this in non-signer mode.
 		Err(errors::signer_disabled())
 	}
+
+	fn compose_transaction(&self, request: RpcTransactionRequest) -> Result<RpcUnsignedTransaction, Error> {
+		try!(self.active());
+		Ok(dispatch::compose_transaction(&*take_weak!(self.client), &*take_weak!(self.miner), request.into()))
+	}
+
+	fn combine_transaction(&self, unsigned: RpcUnsignedTransaction, signature: RpcH520) -> Result<RpcRichRawTransaction, Error> {
+		try!(self.active());
+		dispatch::combine_transaction(unsigned, signature).map(Into::into)
+	}
+
+	fn submit_signed_transaction(&self, unsigned: RpcUnsignedTransaction, signature: RpcH520) -> Result<RpcH256, Error> {
+		try!(self.active());
+		dispatch::submit_transaction(&*take_weak!(self.client), &*take_weak!(self.miner), unsigned, signature).map(Into::into)
+	}
 }
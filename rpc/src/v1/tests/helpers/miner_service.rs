@@ -16,6 +16,7 @@
 
 //! Test implementation of miner service.
 
+use std::time::Duration;
 use util::{Address, H256, Bytes, U256, FixedHash, Uint};
 use util::standard::*;
 use ethcore::error::{Error, CallError};
@@ -24,7 +25,7 @@ use ethcore::block::{ClosedBlock, IsBlock};
 use ethcore::header::BlockNumber;
 use ethcore::transaction::SignedTransaction;
 use ethcore::receipt::{Receipt, RichReceipt};
-use ethcore::miner::{MinerService, MinerStatus, TransactionImportResult, LocalTransactionStatus};
+use ethcore::miner::{MinerService, MinerStatus, TransactionImportResult, LocalTransactionStatus, NonceGap};
 
 /// Test miner service.
 pub struct TestMinerService {
@@ -34,12 +35,18 @@ pub struct TestMinerService {
 	pub latest_closed_block: Mutex<Option<ClosedBlock>>,
 	/// Pre-existed pending transactions
 	pub pending_transactions: Mutex<HashMap<H256, SignedTransaction>>,
+	/// Pre-existed future transactions
+	pub future_transactions: Mutex<Vec<SignedTransaction>>,
+	/// Pre-existed nonce gaps
+	pub nonce_gaps: Mutex<Vec<NonceGap>>,
 	/// Pre-existed local transactions
 	pub local_transactions: Mutex<BTreeMap<H256, LocalTransactionStatus>>,
 	/// Pre-existed pending receipts
 	pub pending_receipts: Mutex<BTreeMap<H256, Receipt>>,
 	/// Last nonces.
 	pub last_nonces: RwLock<HashMap<Address, U256>>,
+	/// Interval of sealing empty blocks.
+	pub seal_interval: RwLock<Option<Duration>>,
 
 	min_gas_price: RwLock<U256>,
 	gas_range_target: RwLock<(U256, U256)>,
@@ -55,9 +62,12 @@ impl Default for TestMinerService {
 			imported_transactions: Mutex::new(Vec::new()),
 			latest_closed_block: Mutex::new(None),
 			pending_transactions: Mutex::new(HashMap::new()),
+			future_transactions: Mutex::new(Vec::new()),
+			nonce_gaps: Mutex::new(Vec::new()),
 			local_transactions: Mutex::new(BTreeMap::new()),
 			pending_receipts: Mutex::new(BTreeMap::new()),
 			last_nonces: RwLock::new(HashMap::new()),
+			seal_interval: RwLock::new(None),
 			min_gas_price: RwLock::new(U256::from(20_000_000)),
 			gas_range_target: RwLock::new((U256::from(12345), U256::from(54321))),
 			author: RwLock::new(Address::zero()),
@@ -75,7 +85,8 @@ impl MinerService for TestMinerService {
 		MinerStatus {
 			transactions_in_pending_queue: 0,
 			transactions_in_future_queue: 0,
-			transactions_in_pending_block: 1
+			transactions_in_pending_block: 1,
+			transactions_mem_used: 0,
 		}
 	}
 
@@ -198,6 +209,14 @@ impl MinerService for TestMinerService {
 		self.pending_transactions.lock().values().cloned().collect()
 	}
 
+	fn future_transactions(&self) -> Vec<SignedTransaction> {
+		self.future_transactions.lock().clone()
+	}
+
+	fn nonce_gaps(&self) -> Vec<NonceGap> {
+		self.nonce_gaps.lock().clone()
+	}
+
 	fn local_transactions(&self) -> BTreeMap<H256, LocalTransactionStatus> {
 		self.local_transactions.lock().iter().map(|(hash, stats)| (*hash, stats.clone())).collect()
 	}
@@ -234,6 +253,14 @@ impl MinerService for TestMinerService {
 		false
 	}
 
+	fn force_seal(&self, _chain: &MiningBlockChainClient) -> Option<H256> {
+		None
+	}
+
+	fn set_seal_interval(&self, interval: Option<Duration>) {
+		*self.seal_interval.write() = interval;
+	}
+
 	/// Submit `seal` as a valid solution for the header of `pow_hash`.
 	/// Will check the seal, but not actually insert the block into the chain.
 	fn submit_seal(&self, _chain: &MiningBlockChainClient, _pow_hash: H256, _seal: Vec<Bytes>) -> Result<(), Error> {
@@ -18,7 +18,7 @@
 
 use std::collections::BTreeMap;
 use util::{H256, RwLock};
-use ethsync::{SyncProvider, SyncStatus, SyncState, PeerInfo, TransactionStats};
+use ethsync::{SyncProvider, SyncStatus, SyncState, PeerInfo, TransactionStats, PeerPropagationStats};
 
 /// TestSyncProvider config.
 pub struct Config {
@@ -53,6 +53,8 @@ impl TestSyncProvider {
 				num_snapshot_chunks: 0,
 				snapshot_chunks_done: 0,
 				last_imported_old_block_number: None,
+				receipts_cache_hits: 0,
+				receipts_cache_misses: 0,
 			}),
 		}
 	}
@@ -80,7 +82,14 @@ impl SyncProvider for TestSyncProvider {
 				local_address: "127.0.0.1:8888".to_owned(),
 				eth_version: 62,
 				eth_difficulty: Some(40.into()),
-				eth_head: 50.into()
+				eth_head: 50.into(),
+				propagation: PeerPropagationStats {
+					blocks_first: 3,
+					blocks_total: 10,
+					average_block_delay_ms: 250,
+					transactions_first: 7,
+					transactions_total: 20,
+				},
 			},
 			PeerInfo {
 				id: None,
@@ -90,7 +99,8 @@ impl SyncProvider for TestSyncProvider {
 				local_address: "127.0.0.1:3333".to_owned(),
 				eth_version: 64,
 				eth_difficulty: None,
-				eth_head: 60.into()
+				eth_head: 60.into(),
+				propagation: Default::default(),
 			}
 		]
 	}
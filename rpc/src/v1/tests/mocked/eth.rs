@@ -28,6 +28,7 @@ use ethcore::client::{TestBlockChainClient, EachBlockWith, Executed, Transaction
 use ethcore::log_entry::{LocalizedLogEntry, LogEntry};
 use ethcore::receipt::LocalizedReceipt;
 use ethcore::transaction::{Transaction, Action};
+use ethcore::trace::TraceError;
 use ethcore::miner::{ExternalMiner, MinerService};
 use ethsync::SyncState;
 
@@ -351,6 +352,25 @@ fn rpc_eth_gas_price() {
 	assert_eq!(EthTester::default().io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_eth_fee_history() {
+	let tester = EthTester::default();
+	tester.client.add_blocks(3, EachBlockWith::Nothing);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_feeHistory", "params": ["0x2", "latest", [10, 90]], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"gasUsedRatio":[0.0,0.0],"oldestBlock":"0x2","reward":[["0x0","0x0"],["0x0","0x0"]]},"id":1}"#;
+
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_eth_fee_history_invalid_percentiles() {
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_feeHistory", "params": ["0x1", "latest", [90, 10]], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: rewardPercentiles","data":"\"Percentiles must be increasing values between 0 and 100.\""},"id":1}"#;
+
+	assert_eq!(EthTester::default().io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_eth_accounts() {
 	let tester = EthTester::default();
@@ -369,6 +389,24 @@ fn rpc_eth_accounts() {
 	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_eth_accounts_visible_to_dapp() {
+	use v1::helpers::request_log::with_origin;
+
+	let tester = EthTester::default();
+	let address = tester.accounts_provider.new_account("").unwrap();
+	tester.accounts_provider.new_account("").unwrap();
+	tester.accounts_provider.set_dapps_addresses("wallet".into(), vec![address]);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_accounts", "params": [], "id": 1}"#;
+	let response = format!(r#"{{"jsonrpc":"2.0","result":["0x{:?}"],"id":1}}"#, address);
+
+	let result = with_origin(Some("http://wallet.parity:8080".into()), || tester.io.handle_request_sync(request));
+	assert_eq!(result, Some(response));
+	let result = with_origin(Some("http://other.parity".into()), || tester.io.handle_request_sync(request)).unwrap();
+	assert_eq!(result.matches("0x").count(), 2);
+}
+
 #[test]
 fn rpc_eth_block_number() {
 	let tester = EthTester::default();
@@ -559,6 +597,7 @@ fn rpc_eth_call_latest() {
 		logs: vec![],
 		contracts_created: vec![],
 		output: vec![0x12, 0x34, 0xff],
+		exception: None,
 		trace: vec![],
 		vm_trace: None,
 		state_diff: None,
@@ -594,6 +633,7 @@ fn rpc_eth_call() {
 		logs: vec![],
 		contracts_created: vec![],
 		output: vec![0x12, 0x34, 0xff],
+		exception: None,
 		trace: vec![],
 		vm_trace: None,
 		state_diff: None,
@@ -618,6 +658,121 @@ fn rpc_eth_call() {
 	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_eth_call_with_state_overrides() {
+	let tester = EthTester::default();
+	tester.client.set_execution_result(Ok(Executed {
+		gas: U256::zero(),
+		gas_used: U256::from(0xff30),
+		refunded: U256::from(0x5),
+		cumulative_gas_used: U256::zero(),
+		logs: vec![],
+		contracts_created: vec![],
+		output: vec![0x12, 0x34, 0xff],
+		exception: None,
+		trace: vec![],
+		vm_trace: None,
+		state_diff: None,
+	}));
+
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_call",
+		"params": [{
+			"from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567"
+		},
+		"latest",
+		{
+			"0xd46e8dd67c5d32be8058bb8eb970870f07244567": {
+				"balance": "0x1",
+				"code": "0x6000"
+			}
+		}],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x1234ff","id":1}"#;
+
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_eth_call_rejects_state_overrides_for_pending() {
+	let tester = EthTester::default();
+
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_call",
+		"params": [{
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567"
+		},
+		"pending",
+		{
+			"0xd46e8dd67c5d32be8058bb8eb970870f07244567": { "code": "0x6000" }
+		}],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"This request is not implemented yet. Please create an issue on Github repo.","data":"State overrides are not supported for the pending block."},"id":1}"#;
+
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_eth_call_returns_vm_exception() {
+	let tester = EthTester::default();
+	tester.client.set_execution_result(Ok(Executed {
+		gas: U256::from(0x76c0),
+		gas_used: U256::from(0x76c0),
+		refunded: U256::zero(),
+		cumulative_gas_used: U256::zero(),
+		logs: vec![],
+		contracts_created: vec![],
+		output: vec![0x12, 0x34, 0xff],
+		exception: Some(TraceError::OutOfGas),
+		trace: vec![],
+		vm_trace: None,
+		state_diff: None,
+	}));
+
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_call",
+		"params": [{
+			"from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"gas": "0x76c0"
+		},
+		"latest"],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32015,"message":"VM execution error: Out of gas.","data":{"error":"Out of gas","output":"0x1234ff"}},"id":1}"#;
+
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_eth_call_rejects_gas_above_cap() {
+	let tester = EthTester::new_with_options(EthClientOptions {
+		gas_cap: Some(U256::from(0x5000)),
+		..Default::default()
+	});
+
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_call",
+		"params": [{
+			"from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"gas": "0x76c0"
+		},
+		"latest"],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32016,"message":"Requested gas exceeds the limit for calls on this node (20480).","data":null},"id":1}"#;
+
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_eth_call_default_block() {
 	let tester = EthTester::default();
@@ -629,6 +784,7 @@ fn rpc_eth_call_default_block() {
 		logs: vec![],
 		contracts_created: vec![],
 		output: vec![0x12, 0x34, 0xff],
+		exception: None,
 		trace: vec![],
 		vm_trace: None,
 		state_diff: None,
@@ -663,6 +819,7 @@ fn rpc_eth_estimate_gas() {
 		logs: vec![],
 		contracts_created: vec![],
 		output: vec![0x12, 0x34, 0xff],
+		exception: None,
 		trace: vec![],
 		vm_trace: None,
 		state_diff: None,
@@ -698,6 +855,7 @@ fn rpc_eth_estimate_gas_default_block() {
 		logs: vec![],
 		contracts_created: vec![],
 		output: vec![0x12, 0x34, 0xff],
+		exception: None,
 		trace: vec![],
 		vm_trace: None,
 		state_diff: None,
@@ -1045,6 +1203,8 @@ fn rpc_get_work_should_not_return_block_number() {
 	let eth_tester = EthTester::new_with_options(EthClientOptions {
 		allow_pending_receipt_query: true,
 		send_block_number_in_get_work: false,
+		max_logs: None,
+		gas_cap: None,
 	});
 	eth_tester.miner.set_author(Address::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap());
 
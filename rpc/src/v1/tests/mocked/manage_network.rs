@@ -14,7 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethsync::{ManageNetwork, NetworkConfiguration};
+use ethsync::{ManageNetwork, NetworkConfiguration, is_valid_node_url};
+use util::H512;
 
 pub struct TestManageNetwork;
 
@@ -24,7 +25,11 @@ impl ManageNetwork for TestManageNetwork {
 	fn deny_unreserved_peers(&self) { }
 	fn remove_reserved_peer(&self, _peer: String) -> Result<(), String> { Ok(()) }
 	fn add_reserved_peer(&self, _peer: String) -> Result<(), String> { Ok(()) }
+	fn add_boot_node(&self, node: String) -> Result<(), String> {
+		if is_valid_node_url(&node) { Ok(()) } else { Err("Invalid node address".into()) }
+	}
 	fn start_network(&self) {}
 	fn stop_network(&self) {}
 	fn network_config(&self) -> NetworkConfiguration { NetworkConfiguration::new_local() }
+	fn rotate_node_key(&self, _grace_period: u64) -> Result<H512, String> { Ok(H512::from(1)) }
 }
@@ -21,10 +21,13 @@ mod eth;
 mod net;
 mod web3;
 mod personal;
+mod private;
 mod parity;
 mod parity_accounts;
 mod parity_set;
+mod public_node;
 mod rpc;
 mod signer;
 mod signing;
+mod txpool;
 mod manage_network;
@@ -19,28 +19,40 @@ use util::log::RotatingLogger;
 use util::Address;
 use ethsync::ManageNetwork;
 use ethcore::account_provider::AccountProvider;
-use ethcore::client::{TestBlockChainClient};
+use ethcore::client::{TestBlockChainClient, Executed};
 use ethcore::miner::LocalTransactionStatus;
 use ethstore::ethkey::{Generator, Random};
 
 use jsonrpc_core::IoHandler;
 use v1::{Parity, ParityClient};
 use v1::helpers::{SignerService, NetworkSettings};
-use v1::tests::helpers::{TestSyncProvider, Config, TestMinerService};
+use v1::helpers::health::{NodeHealth, SyncStatus};
+use util::ntp::TimeChecker;
+use ethcore::snapshot::SnapshotService;
+use v1::tests::helpers::{TestSyncProvider, Config, TestMinerService, TestSnapshotService};
 use super::manage_network::TestManageNetwork;
 
+struct TestSyncStatus;
+
+impl SyncStatus for TestSyncStatus {
+	fn is_major_importing(&self) -> bool { false }
+	fn peers(&self) -> (usize, usize) { (120, 25) }
+}
+
 pub type TestParityClient = ParityClient<TestBlockChainClient, TestMinerService, TestSyncProvider>;
 
 pub struct Dependencies {
 	pub miner: Arc<TestMinerService>,
 	pub client: Arc<TestBlockChainClient>,
 	pub sync: Arc<TestSyncProvider>,
+	pub snapshot: Arc<TestSnapshotService>,
 	pub logger: Arc<RotatingLogger>,
 	pub settings: Arc<NetworkSettings>,
 	pub network: Arc<ManageNetwork>,
 	pub accounts: Arc<AccountProvider>,
 	pub dapps_interface: Option<String>,
 	pub dapps_port: Option<u16>,
+	pub health: Arc<NodeHealth>,
 }
 
 impl Dependencies {
@@ -52,6 +64,7 @@ impl Dependencies {
 				network_id: 3,
 				num_peers: 120,
 			})),
+			snapshot: Arc::new(TestSnapshotService::new()),
 			logger: Arc::new(RotatingLogger::new("rpc=trace".to_owned())),
 			settings: Arc::new(NetworkSettings {
 				name: "mynode".to_owned(),
@@ -65,14 +78,17 @@ impl Dependencies {
 			accounts: Arc::new(AccountProvider::transient_provider()),
 			dapps_interface: Some("127.0.0.1".into()),
 			dapps_port: Some(18080),
+			health: Arc::new(NodeHealth::new(Arc::new(TestSyncStatus), Arc::new(TimeChecker::new("127.0.0.1:9".into())))),
 		}
 	}
 
 	pub fn client(&self, signer: Option<Arc<SignerService>>) -> TestParityClient {
+		let snapshot: Arc<SnapshotService> = self.snapshot.clone();
 		ParityClient::new(
 			&self.client,
 			&self.miner,
 			&self.sync,
+			&snapshot,
 			&self.network,
 			&self.accounts,
 			self.logger.clone(),
@@ -80,6 +96,7 @@ impl Dependencies {
 			signer,
 			self.dapps_interface.clone(),
 			self.dapps_port,
+			self.health.clone(),
 		)
 	}
 
@@ -107,6 +124,75 @@ fn rpc_parity_extra_data() {
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_block_reward() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_blockReward", "params": ["latest"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"author":{"address":"0x0000000000000000000000000000000000000000","amount":"0x0"},"total":"0x0","uncles":[]},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_blockReward", "params": ["0x10"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_chain_spec() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_chainSpec", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"engine":"NullEngine","networkId":"0x2","params":{},"schedule":{"balanceGas":"0x190","callGas":"0x2bc","createGas":"0x7d00","extcodesizeGas":"0x2bc","killEmpty":true,"maxCodeSize":"0x6000","maxDepth":"0x400","noEmpty":true,"sloadGas":"0xc8","sstoreRefundGas":"0x3a98","sstoreResetGas":"0x1388","sstoreSetGas":"0x4e20","suicideGas":"0x1388","txCreateGas":"0xcf08","txDataNonZeroGas":"0x44","txDataZeroGas":"0x4","txGas":"0x5208"},"transitions":{},"validators":null},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_pow_cache_status() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_powCacheStatus", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_validator_uptime() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_validatorUptime", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_finalized_block() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_finalizedBlock", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_log_proof() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_logProof", "params": ["0x0000000000000000000000000000000000000000000000000000000000000001", "0x0"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_parity_default_extra_data() {
 	use util::misc;
@@ -199,9 +285,11 @@ fn rpc_parity_net_peers() {
 	let response = "{\"jsonrpc\":\"2.0\",\"result\":{\"active\":0,\"connected\":120,\"max\":50,\"peers\":[{\"caps\":[\"eth/62\",\"eth/63\"],\
 \"id\":\"node1\",\"name\":\"Parity/1\",\"network\":{\"localAddress\":\"127.0.0.1:8888\",\"remoteAddress\":\"127.0.0.1:7777\"}\
 ,\"protocols\":{\"eth\":{\"difficulty\":\"0x28\",\"head\":\"0000000000000000000000000000000000000000000000000000000000000032\"\
+,\"propagation\":{\"averageBlockDelay\":250,\"blocksFirst\":3,\"blocksTotal\":10,\"transactionsFirst\":7,\"transactionsTotal\":20}\
 ,\"version\":62}}},{\"caps\":[\"eth/63\",\"eth/64\"],\"id\":null,\"name\":\"Parity/2\",\"network\":{\"localAddress\":\
 \"127.0.0.1:3333\",\"remoteAddress\":\"Handshake\"},\"protocols\":{\"eth\":{\"difficulty\":null,\"head\":\
-\"000000000000000000000000000000000000000000000000000000000000003c\",\"version\":64}}}]},\"id\":1}";
+\"000000000000000000000000000000000000000000000000000000000000003c\",\"propagation\":{\"averageBlockDelay\":0,\"blocksFirst\":0,\
+\"blocksTotal\":0,\"transactionsFirst\":0,\"transactionsTotal\":0},\"version\":64}}}]},\"id\":1}";
 
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
@@ -381,3 +469,220 @@ fn rpc_parity_local_transactions() {
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_nonce_gaps() {
+	use std::time::Duration;
+	use ethcore::miner::NonceGap;
+
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+	deps.miner.nonce_gaps.lock().push(NonceGap {
+		sender: 5.into(),
+		next_nonce: 1.into(),
+		missing: 2.into(),
+		queued: vec![3.into(), 4.into()],
+		waiting: Duration::from_secs(30),
+	});
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_nonceGaps", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[{"missing":"0x2","nextNonce":"0x1","queued":["0x3","0x4"],"sender":"0x0000000000000000000000000000000000000005","waiting":30}],"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+
+#[test]
+fn rpc_parity_list_accounts_without_fat_db() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_listAccounts", "params": [10, null], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_list_storage_keys_without_fat_db() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_listStorageKeys", "params": ["0x0000000000000000000000000000000000000005", 10, null], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_sync_status_idle() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_syncStatus", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"activePeers":0,"peers":120,"stage":"idle","stages":[]},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_sync_status_warping() {
+	use ethcore::snapshot::RestorationStatus;
+	use ethsync::SyncState;
+
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+	deps.snapshot.set_status(RestorationStatus::Ongoing {
+		state_chunks: 40,
+		block_chunks: 10,
+		state_chunks_done: 15,
+		block_chunks_done: 2,
+	});
+	{
+		let mut status = deps.sync.status.write();
+		status.state = SyncState::SnapshotData;
+		status.num_active_peers = 5;
+	}
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_syncStatus", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"activePeers":5,"peers":120,"stage":"warpState","stages":[{"done":15,"eta":null,"stage":"warpState","total":40},{"done":2,"eta":null,"stage":"warpBlocks","total":10}]},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_db_stats() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_dbStats", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[],"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_memory_profile() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_memoryProfile", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"blockQueue":0,"blockchainCache":0,"stateCache":0,"sync":0,"total":0,"traceCache":0,"transactionQueue":0},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_cache_stats() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let empty = r#"{"budget":0,"hits":0,"misses":0,"size":0}"#;
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_cacheStats", "params": [], "id": 1}"#;
+	let response = format!(r#"{{"jsonrpc":"2.0","result":{{"bodies":{0},"details":{0},"headers":{0},"receipts":{0}}},"id":1}}"#, empty);
+
+	assert_eq!(io.handle_request_sync(request), Some(response));
+}
+
+#[test]
+fn rpc_parity_transactions_by_sender_disabled() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_transactionsBySender", "params": ["0x0000000000000000000000000000000000000001", "earliest", "latest"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32037,"message":"Sender index is disabled. Start Parity with --sender-index to enable it.","data":null},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_simulate_transactions() {
+	use util::U256;
+
+	let deps = Dependencies::new();
+	deps.client.set_execution_result(Ok(Executed {
+		gas: U256::zero(),
+		gas_used: U256::from(0xff30),
+		refunded: U256::zero(),
+		cumulative_gas_used: U256::zero(),
+		logs: vec![],
+		contracts_created: vec![],
+		output: vec![0x12, 0x34, 0xff],
+		exception: None,
+		trace: vec![],
+		vm_trace: None,
+		state_diff: None,
+	}));
+	let io = deps.default_client();
+
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "parity_simulateTransactions",
+		"params": [[
+			{ "from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155", "to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567", "value": "0x1" },
+			{ "from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155", "to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567", "data": "0x12" }
+		], "latest"],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[{"error":null,"gasUsed":"0xff30","logs":[],"output":"0x1234ff"},{"error":null,"gasUsed":"0xff30","logs":[],"output":"0x1234ff"}],"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_simulate_transactions_respects_gas_cap() {
+	let deps = Dependencies::new();
+	let io = IoHandler::new();
+	io.add_delegate(deps.client(None).with_gas_cap(Some(100_000.into())).to_delegate());
+
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "parity_simulateTransactions",
+		"params": [[
+			{ "from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155", "to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567", "gas": "0x186a1" }
+		], "latest"],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32016,"message":"Requested gas exceeds the limit for calls on this node (100000).","data":null},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_decode_raw_transaction() {
+	use rlp;
+	use util::{ToPretty, U256};
+	use ethcore::transaction::{Transaction, Action};
+
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let key = Random.generate().unwrap();
+	let t = Transaction {
+		nonce: U256::zero(),
+		gas_price: U256::from(0x9184e72a000u64),
+		gas: U256::from(20_000),
+		action: Action::Call(5.into()),
+		value: U256::from(1),
+		data: vec![],
+	}.sign(key.secret(), None);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_decodeRawTransaction", "params": ["0x"#.to_owned()
+		+ &rlp::encode(&t).to_vec().to_hex() + r#""], "id": 1}"#;
+	let res = io.handle_request_sync(&request).unwrap();
+
+	assert!(res.contains(&format!(r#""from":"0x{:?}""#, key.address())), "{}", res);
+	assert!(res.contains(r#""intrinsicGas":"0x5208""#), "{}", res);
+	assert!(res.contains("Transaction gas is too low."), "{}", res);
+	assert!(res.contains("Insufficient funds."), "{}", res);
+}
+
+#[test]
+fn rpc_parity_decode_invalid_raw_transaction() {
+	let deps = Dependencies::new();
+	let io = deps.default_client();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_decodeRawTransaction", "params": ["0x0102"], "id": 1}"#;
+	let res = io.handle_request_sync(request).unwrap();
+
+	assert!(res.contains("Invalid RLP."), "{}", res);
+}
@@ -70,7 +70,7 @@ fn should_be_able_to_get_account_info() {
 
 	let request = r#"{"jsonrpc": "2.0", "method": "parity_accountsInfo", "params": [], "id": 1}"#;
 	let res = tester.io.handle_request_sync(request);
-	let response = format!("{{\"jsonrpc\":\"2.0\",\"result\":{{\"0x{}\":{{\"meta\":\"{{foo: 69}}\",\"name\":\"Test\",\"uuid\":\"{}\"}}}},\"id\":1}}", address.hex(), uuid);
+	let response = format!("{{\"jsonrpc\":\"2.0\",\"result\":{{\"0x{}\":{{\"meta\":\"{{foo: 69}}\",\"name\":\"Test\",\"tags\":[],\"uuid\":\"{}\"}}}},\"id\":1}}", address.hex(), uuid);
 	assert_eq!(res, Some(response));
 }
 
@@ -91,7 +91,7 @@ fn should_be_able_to_set_name() {
 
 	let request = r#"{"jsonrpc": "2.0", "method": "parity_accountsInfo", "params": [], "id": 1}"#;
 	let res = tester.io.handle_request_sync(request);
-	let response = format!("{{\"jsonrpc\":\"2.0\",\"result\":{{\"0x{}\":{{\"meta\":\"{{}}\",\"name\":\"Test\",\"uuid\":\"{}\"}}}},\"id\":1}}", address.hex(), uuid);
+	let response = format!("{{\"jsonrpc\":\"2.0\",\"result\":{{\"0x{}\":{{\"meta\":\"{{}}\",\"name\":\"Test\",\"tags\":[],\"uuid\":\"{}\"}}}},\"id\":1}}", address.hex(), uuid);
 	assert_eq!(res, Some(response));
 }
 
@@ -112,7 +112,7 @@ fn should_be_able_to_set_meta() {
 
 	let request = r#"{"jsonrpc": "2.0", "method": "parity_accountsInfo", "params": [], "id": 1}"#;
 	let res = tester.io.handle_request_sync(request);
-	let response = format!("{{\"jsonrpc\":\"2.0\",\"result\":{{\"0x{}\":{{\"meta\":\"{{foo: 69}}\",\"name\":\"\",\"uuid\":\"{}\"}}}},\"id\":1}}", address.hex(), uuid);
+	let response = format!("{{\"jsonrpc\":\"2.0\",\"result\":{{\"0x{}\":{{\"meta\":\"{{foo: 69}}\",\"name\":\"\",\"tags\":[],\"uuid\":\"{}\"}}}},\"id\":1}}", address.hex(), uuid);
 	assert_eq!(res, Some(response));
 }
 
@@ -138,3 +138,53 @@ fn should_be_able_to_kill_account() {
 	assert_eq!(accounts.len(), 0);
 }
 
+
+#[test]
+fn should_be_able_to_set_tags() {
+	let tester = setup();
+	tester.accounts.new_account("").unwrap();
+	let address = tester.accounts.accounts().unwrap()[0];
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "parity_setAccountTags", "params": ["0x{}", ["savings", "cold"]], "id": 1}}"#, address.hex());
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	let res = tester.io.handle_request_sync(&request);
+	assert_eq!(res, Some(response.into()));
+
+	assert_eq!(tester.accounts.account_tags(&address), vec!["savings".to_owned(), "cold".to_owned()]);
+}
+
+#[test]
+fn should_be_able_to_set_dapps_addresses() {
+	let tester = setup();
+	tester.accounts.new_account("").unwrap();
+	tester.accounts.new_account("").unwrap();
+	let address = tester.accounts.accounts().unwrap()[0];
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "parity_setDappsAddresses", "params": ["wallet", ["0x{}"]], "id": 1}}"#, address.hex());
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	let res = tester.io.handle_request_sync(&request);
+	assert_eq!(res, Some(response.into()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_getDappsAddresses", "params": ["wallet"], "id": 1}"#;
+	let response = format!(r#"{{"jsonrpc":"2.0","result":["0x{}"],"id":1}}"#, address.hex());
+	let res = tester.io.handle_request_sync(request);
+	assert_eq!(res, Some(response));
+}
+
+#[test]
+fn should_be_able_to_set_account_policy() {
+	let tester = setup();
+	tester.accounts.new_account("").unwrap();
+	let address = tester.accounts.accounts().unwrap()[0];
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "parity_setAccountPolicy", "params": ["0x{}", {{"maxDailyValue": "0x64", "allowedRecipients": null, "requireConfirmation": false}}], "id": 1}}"#, address.hex());
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	let res = tester.io.handle_request_sync(&request);
+	assert_eq!(res, Some(response.into()));
+	assert!(tester.accounts.check_transaction(&address, None, &101.into()).is_err());
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "parity_accountPolicy", "params": ["0x{}"], "id": 1}}"#, address.hex());
+	let response = r#"{"jsonrpc":"2.0","result":{"allowedRecipients":null,"maxDailyValue":"0x64","requireConfirmation":false},"id":1}"#;
+	let res = tester.io.handle_request_sync(&request);
+	assert_eq!(res, Some(response.into()));
+}
@@ -16,6 +16,7 @@
 
 use std::sync::Arc;
 use std::str::FromStr;
+use std::time::Duration;
 use rustc_serialize::hex::FromHex;
 use util::{U256, Address};
 
@@ -25,6 +26,7 @@ use ethsync::ManageNetwork;
 
 use jsonrpc_core::IoHandler;
 use v1::{ParitySet, ParitySetClient};
+use v1::access_policy::AccessPolicy;
 use v1::tests::helpers::{TestMinerService, TestFetch};
 use super::manage_network::TestManageNetwork;
 
@@ -43,7 +45,7 @@ fn network_service() -> Arc<TestManageNetwork> {
 pub type TestParitySetClient = ParitySetClient<TestBlockChainClient, TestMinerService, TestFetch>;
 
 fn parity_set_client(client: &Arc<TestBlockChainClient>, miner: &Arc<TestMinerService>, net: &Arc<TestManageNetwork>) -> TestParitySetClient {
-	ParitySetClient::with_fetch(client, miner, &(net.clone() as Arc<ManageNetwork>))
+	ParitySetClient::with_fetch(client, miner, &(net.clone() as Arc<ManageNetwork>), &Arc::new(AccessPolicy::new(None, None)))
 }
 
 #[test]
@@ -135,3 +137,94 @@ fn rpc_parity_set_hash_content() {
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+
+#[test]
+fn rpc_parity_db_compact() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(parity_set_client(&client, &miner, &network).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_dbCompact", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_add_bootnode() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(parity_set_client(&client, &miner, &network).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_addBootnode", "params":["enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_addBootnode", "params":["enode://invalid"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: Node address","data":"\"Invalid node address\""},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_rotate_node_key() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(parity_set_client(&client, &miner, &network).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_rotateNodeKey", "params":[3600], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001","id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_dev_mine() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(parity_set_client(&client, &miner, &network).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_devMine", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32004,"message":"Unable to seal a block. Sealing on demand is only possible with engines which seal internally (e.g. --chain dev).","data":null},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_dev_set_block_interval() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(parity_set_client(&client, &miner, &network).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_devSetBlockInterval", "params":[5], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(*miner.seal_interval.read(), Some(Duration::from_secs(5)));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_devSetBlockInterval", "params":[0], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(*miner.seal_interval.read(), None);
+}
+
+#[test]
+fn rpc_parity_set_cors() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let policy = Arc::new(AccessPolicy::new(None, None));
+	let io = IoHandler::new();
+	io.add_delegate(ParitySetClient::<_, _, TestFetch>::with_fetch(&client, &miner, &(network as Arc<ManageNetwork>), &policy).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setCors", "params":[["http://*.ethcore.io"]], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(policy.cors_header("/", Some("http://wallet.ethcore.io")), Some("http://wallet.ethcore.io".into()));
+}
@@ -0,0 +1,153 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use rustc_serialize::hex::ToHex;
+use serde_json;
+use ethkey::{Random, Generator, Secret};
+use devtools::RandomTempPath;
+use jsonrpc_core::IoHandler;
+use util::{Address, U256};
+use ethcore::account_provider::AccountProvider;
+use ethcore::client::{TestBlockChainClient, Executed};
+use ethcore::private_transactions::{Provider, ProviderConfig};
+use ethcore::transaction::{Action, Transaction};
+use v1::{Private, PrivateClient};
+use v1::tests::helpers::TestMinerService;
+
+struct PrivateTester {
+	validator: Address,
+	sender: Secret,
+	miner: Arc<TestMinerService>,
+	io: IoHandler,
+	// the handler only holds a weak pointer to the client.
+	_client: Arc<TestBlockChainClient>,
+	_path: RandomTempPath,
+}
+
+fn setup(enabled: bool) -> PrivateTester {
+	let accounts = Arc::new(AccountProvider::transient_provider());
+	let (validator, public) = accounts.new_account_and_public("").unwrap();
+	accounts.unlock_account_permanently(validator, "".into()).unwrap();
+	let sender = Random.generate().unwrap().secret().clone();
+	accounts.insert_account(sender.clone(), "").unwrap();
+	let path = RandomTempPath::new();
+	let provider = match enabled {
+		true => Some(Arc::new(Provider::new(ProviderConfig {
+			account: validator,
+			validators: vec![public],
+		}, accounts, path.as_path()).unwrap())),
+		false => None,
+	};
+
+	let client = Arc::new(TestBlockChainClient::new());
+	client.set_execution_result(Ok(Executed {
+		gas: U256::zero(),
+		gas_used: U256::from(0xff30),
+		refunded: U256::zero(),
+		cumulative_gas_used: U256::zero(),
+		logs: vec![],
+		contracts_created: vec![],
+		output: vec![],
+		exception: None,
+		trace: vec![],
+		vm_trace: None,
+		state_diff: None,
+	}));
+	let miner = Arc::new(TestMinerService::default());
+
+	let io = IoHandler::new();
+	io.add_delegate(PrivateClient::new(provider, &client, &miner).to_delegate());
+
+	PrivateTester {
+		validator: validator,
+		sender: sender,
+		miner: miner,
+		io: io,
+		_client: client,
+		_path: path,
+	}
+}
+
+fn private_call(sender: &Secret) -> String {
+	let t = Transaction {
+		nonce: U256::zero(),
+		gas_price: U256::from(0x9184e72a000u64),
+		gas: U256::from(0x76c0),
+		action: Action::Call(Address::from(5)),
+		value: U256::zero(),
+		data: vec![0x12, 0x34],
+	}.sign(sender, None);
+	::rlp::encode(&t).to_hex()
+}
+
+fn request(io: &IoHandler, method: &str, param: &str) -> serde_json::Value {
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "{}", "params": ["{}"], "id": 1}}"#, method, param);
+	serde_json::from_str(&io.handle_request_sync(&request).unwrap()).unwrap()
+}
+
+fn field(response: &serde_json::Value, path: &str) -> String {
+	response.lookup(path).and_then(serde_json::Value::as_str).unwrap().to_owned()
+}
+
+#[test]
+fn rpc_private_disabled() {
+	let tester = setup(false);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "private_sendTransaction", "params": ["0x"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32038,"message":"Private transactions are disabled. Start Parity with --private-tx-account to enable them.","data":null},"id":1}"#;
+
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_private_send_and_import_transaction() {
+	let tester = setup(true);
+
+	let receipt = request(&tester.io, "private_sendTransaction", &format!("0x{}", private_call(&tester.sender)));
+	let hash = field(&receipt, "result.hash");
+
+	// only the commitment is sent publicly, signed by the node account.
+	{
+		let imported = tester.miner.imported_transactions.lock();
+		assert_eq!(imported.len(), 1);
+		assert_eq!(imported[0].sender().unwrap(), tester.validator);
+		assert_eq!(imported[0].action, Action::Call(Address::from(5)));
+		assert_eq!(imported[0].data.len(), 4 + 32);
+		assert_eq!(imported[0].gas, U256::from(100_000));
+		assert_eq!(format!("0x{:?}", imported[0].hash()), field(&receipt, "result.publicTransactionHash"));
+	}
+
+	let private = request(&tester.io, "private_transaction", &hash);
+	assert_eq!(field(&private, "result.contract"), "0x0000000000000000000000000000000000000005");
+	assert!(private.lookup("result.transaction").is_none());
+	let validators = private.lookup("result.validators").and_then(serde_json::Value::as_array).unwrap();
+	assert_eq!(validators.len(), 1);
+	assert_eq!(validators[0].as_str().unwrap(), format!("0x{:?}", tester.validator));
+
+	let import = request(&tester.io, "private_importTransaction", &field(&private, "result.encrypted"));
+	assert_eq!(field(&import, "result"), hash);
+}
+
+#[test]
+fn rpc_private_rejects_unknown_sender() {
+	let tester = setup(true);
+
+	let response = request(&tester.io, "private_sendTransaction", &format!("0x{}", private_call(Random.generate().unwrap().secret())));
+
+	assert_eq!(response.lookup("error.code").and_then(serde_json::Value::as_i64), Some(-32065));
+	assert_eq!(tester.miner.imported_transactions.lock().len(), 0);
+}
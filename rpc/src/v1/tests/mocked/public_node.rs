@@ -0,0 +1,70 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use ethsync::SyncProvider;
+use jsonrpc_core::IoHandler;
+use v1::{Parity, PublicNodeMiddleware};
+use super::parity::Dependencies;
+use Extendable;
+
+fn public_io() -> IoHandler {
+	let deps = Dependencies::new();
+	let sync: Arc<SyncProvider> = deps.sync.clone();
+	let io = PublicNodeMiddleware::new(IoHandler::new(), &sync, &deps.network);
+	io.add_delegate(deps.client(None).to_delegate());
+	io.into_inner()
+}
+
+#[test]
+fn rpc_public_node_disables_operator_methods() {
+	let io = public_io();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_nodeName", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32036,"message":"Method disabled on public nodes.","data":null},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_public_node_disables_node_management() {
+	let io = public_io();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_mode", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32036,"message":"Method disabled on public nodes.","data":null},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_public_node_disables_signing() {
+	let io = public_io();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_sendTransaction", "params":[{}], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32036,"message":"Method disabled on public nodes.","data":null},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_public_node_hides_peer_details() {
+	let io = public_io();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_netPeers", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"active":0,"connected":120,"max":50,"peers":[]},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
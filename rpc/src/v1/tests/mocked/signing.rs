@@ -27,7 +27,7 @@ use v1::tests::helpers::TestMinerService;
 use v1::tests::mocked::parity;
 
 use util::{Address, FixedHash, Uint, U256, H256, ToPretty};
-use ethcore::account_provider::AccountProvider;
+use ethcore::account_provider::{AccountProvider, AccountPolicy};
 use ethcore::client::TestBlockChainClient;
 use ethcore::transaction::{Transaction, Action};
 use ethstore::ethkey::{Generator, Random};
@@ -204,6 +204,34 @@ fn should_sign_if_account_is_unlocked() {
 	assert_eq!(tester.signer.requests().len(), 0);
 }
 
+#[test]
+fn should_add_sign_to_queue_if_policy_requires_confirmation() {
+	// given
+	let tester = eth_signing();
+	let acc = tester.accounts.new_account("test").unwrap();
+	tester.accounts.unlock_account_permanently(acc, "test".into()).unwrap();
+	tester.accounts.set_account_policy(acc, Some(AccountPolicy {
+		require_confirmation: true,
+		..Default::default()
+	}));
+
+	// when
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "parity_postSign",
+		"params": [
+			""#.to_owned() + format!("0x{:?}", acc).as_ref() + r#"",
+			"0x0000000000000000000000000000000000000000000000000000000000000005"
+		],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x1","id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 1);
+}
+
 #[test]
 fn should_add_transaction_to_queue() {
 	// given
@@ -395,3 +423,75 @@ fn should_add_decryption_to_the_queue() {
 		assert_eq!(res, response.to_owned());
 	}));
 }
+
+#[test]
+fn should_compose_and_submit_transaction_signed_offline() {
+	// given
+	let tester = eth_signing();
+	let acc = tester.accounts.new_account("test").unwrap();
+	let t = Transaction {
+		nonce: U256::zero(),
+		gas_price: U256::from(0x9184e72a000u64),
+		gas: U256::from(0x76c0),
+		action: Action::Call(Address::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap()),
+		value: U256::from(0x9184e72au64),
+		data: vec![]
+	};
+	let unsigned = r#"{"data":"0x","from":""#.to_owned() + format!("0x{:?}", acc).as_ref()
+		+ r#"","gas":"0x76c0","gasPrice":"0x9184e72a000","hash":""# + format!("0x{:?}", t.hash(None)).as_ref()
+		+ r#"","networkId":null,"nonce":"0x0","to":"0xd46e8dd67c5d32be8058bb8eb970870f07244567","value":"0x9184e72a"}"#;
+
+	// when
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "parity_composeTransaction",
+		"params": [{
+			"from": ""#.to_owned() + format!("0x{:?}", acc).as_ref() + r#"",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"gas": "0x76c0",
+			"gasPrice": "0x9184e72a000",
+			"value": "0x9184e72a"
+		}],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"#.to_owned() + &unsigned + r#","id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response));
+
+	// sign offline and submit
+	let signature = tester.accounts.sign(acc, Some("test".into()), t.hash(None)).unwrap();
+	let signed = t.with_signature(signature, None);
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_submitSignedTransaction", "params": ["#.to_owned()
+		+ &unsigned + r#", ""# + format!("0x{}", signature).as_ref() + r#""], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":""#.to_owned() + format!("0x{:?}", signed.hash()).as_ref() + r#"","id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response));
+	assert_eq!(tester.signer.requests().len(), 0);
+}
+
+#[test]
+fn should_refuse_offline_signature_of_other_account() {
+	// given
+	let tester = eth_signing();
+	let acc = tester.accounts.new_account("test").unwrap();
+	let other = Random.generate().unwrap();
+	let t = Transaction {
+		nonce: U256::zero(),
+		gas_price: U256::from(0x9184e72a000u64),
+		gas: U256::from(0x76c0),
+		action: Action::Create,
+		value: U256::zero(),
+		data: vec![]
+	};
+	let signature = ::ethkey::sign(other.secret(), &t.hash(None)).unwrap();
+
+	// when
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_combineTransaction", "params": [{"data":"0x","from":""#.to_owned()
+		+ format!("0x{:?}", acc).as_ref() + r#"","gas":"0x76c0","gasPrice":"0x9184e72a000","hash":""#
+		+ format!("0x{:?}", t.hash(None)).as_ref() + r#"","networkId":null,"nonce":"0x0","to":null,"value":"0x0"}, ""#
+		+ format!("0x{}", signature).as_ref() + r#""], "id": 1}"#;
+	let res = tester.io.handle_request_sync(&request).unwrap();
+
+	// then
+	assert!(res.contains("\"error\""), "{}", res);
+}
@@ -0,0 +1,78 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use ethcore::transaction::{Transaction, Action};
+use jsonrpc_core::IoHandler;
+use v1::{TxPool, TxPoolClient};
+use v1::tests::helpers::TestMinerService;
+
+fn miner_service() -> Arc<TestMinerService> {
+	let miner = TestMinerService::default();
+	let tx = |nonce: u64| Transaction {
+		nonce: nonce.into(),
+		gas_price: 20.into(),
+		gas: 21000.into(),
+		action: Action::Call(5.into()),
+		value: 100.into(),
+		data: vec![],
+	}.fake_sign(1.into());
+
+	let pending = tx(0);
+	miner.pending_transactions.lock().insert(pending.hash(), pending);
+	miner.future_transactions.lock().push(tx(2));
+	Arc::new(miner)
+}
+
+fn setup(miner: &Arc<TestMinerService>) -> IoHandler {
+	let io = IoHandler::new();
+	io.add_delegate(TxPoolClient::new(miner).to_delegate());
+	io
+}
+
+#[test]
+fn rpc_txpool_inspect() {
+	let miner = miner_service();
+	let io = setup(&miner);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "txpool_inspect", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"pending":{"0x0000000000000000000000000000000000000001":{"0":"0x0000000000000000000000000000000000000005: 100 wei + 21000 gas × 20 wei"}},"queued":{"0x0000000000000000000000000000000000000001":{"2":"0x0000000000000000000000000000000000000005: 100 wei + 21000 gas × 20 wei"}}},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_txpool_content() {
+	let miner = miner_service();
+	let io = setup(&miner);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "txpool_content", "params": [], "id": 1}"#;
+	let response = io.handle_request_sync(request).unwrap();
+
+	assert!(response.contains(r#""pending":{"0x0000000000000000000000000000000000000001":{"0":{"#));
+	assert!(response.contains(r#""queued":{"0x0000000000000000000000000000000000000001":{"2":{"#));
+}
+
+#[test]
+fn rpc_txpool_status() {
+	let miner = Arc::new(TestMinerService::default());
+	let io = setup(&miner);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "txpool_status", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"pending":"0x0","queued":"0x0"},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
@@ -18,8 +18,8 @@
 use jsonrpc_core::Error;
 
 use v1::types::{RichBlock, BlockNumber, Bytes, CallRequest, Filter, FilterChanges, Index};
-use v1::types::{Log, Receipt, SyncStatus, Transaction, Work};
-use v1::types::{H64, H160, H256, U256};
+use v1::types::{Log, Receipt, SyncStatus, Transaction, Work, FeeHistory};
+use v1::types::{H64, H160, H256, U256, StateOverride};
 
 use v1::helpers::auto_args::{Trailing, Wrap};
 
@@ -50,6 +50,11 @@ build_rpc_trait! {
 		#[rpc(name = "eth_gasPrice")]
 		fn gas_price(&self) -> Result<U256, Error>;
 
+		/// Returns gas used ratios and gas prices paid at given percentiles of gas used
+		/// for a number of blocks ending with the given one.
+		#[rpc(name = "eth_feeHistory")]
+		fn fee_history(&self, U256, BlockNumber, Trailing<Vec<f64>>) -> Result<FeeHistory, Error>;
+
 		/// Returns accounts list.
 		#[rpc(name = "eth_accounts")]
 		fn accounts(&self) -> Result<Vec<H160>, Error>;
@@ -106,9 +111,9 @@ build_rpc_trait! {
 		#[rpc(name = "eth_submitTransaction")]
 		fn submit_transaction(&self, Bytes) -> Result<H256, Error>;
 
-		/// Call contract, returning the output data.
+		/// Call contract, returning the output data. Account state may be replaced for the call with the optional overrides.
 		#[rpc(name = "eth_call")]
-		fn call(&self, CallRequest, Trailing<BlockNumber>) -> Result<Bytes, Error>;
+		fn call(&self, CallRequest, Trailing<BlockNumber>, Trailing<StateOverride>) -> Result<Bytes, Error>;
 
 		/// Estimate gas needed for execution of given contract.
 		#[rpc(name = "eth_estimateGas")]
@@ -25,8 +25,10 @@ pub mod parity_accounts;
 pub mod parity_set;
 pub mod parity_signing;
 pub mod personal;
+pub mod private;
 pub mod signer;
 pub mod traces;
+pub mod txpool;
 pub mod rpc;
 
 pub use self::web3::Web3;
@@ -38,7 +40,9 @@ pub use self::parity_accounts::ParityAccounts;
 pub use self::parity_set::ParitySet;
 pub use self::parity_signing::ParitySigning;
 pub use self::personal::Personal;
+pub use self::private::Private;
 pub use self::signer::Signer;
 pub use self::traces::Traces;
+pub use self::txpool::TxPool;
 pub use self::rpc::Rpc;
 
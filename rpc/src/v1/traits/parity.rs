@@ -21,9 +21,10 @@ use std::collections::BTreeMap;
 use v1::helpers::auto_args::{Wrap, Trailing};
 use v1::types::{
 	H160, H256, H512, U256, Bytes,
-	Peers, Transaction, RpcSettings, Histogram,
-	TransactionStats, LocalTransactionStatus,
-	BlockNumber
+	Peers, Transaction, RpcSettings, Histogram, BlockReward, ChainSpec, PowCacheStatus, ValidatorUptime,
+	TransactionStats, LocalTransactionStatus, NonceGap,
+	BlockNumber, Health, SyncProgress, DbColumnStats, MemoryProfile, BlockChainCacheStats,
+	CallRequest, SimulationResult, DecodedTransaction, Index, LogProof,
 };
 
 build_rpc_trait! {
@@ -85,6 +86,34 @@ build_rpc_trait! {
 		#[rpc(name = "parity_gasPriceHistogram")]
 		fn gas_price_histogram(&self) -> Result<Histogram, Error>;
 
+		/// Returns author and uncle rewards bestowed by the consensus engine on given block.
+		#[rpc(name = "parity_blockReward")]
+		fn block_reward(&self, BlockNumber) -> Result<Option<BlockReward>, Error>;
+
+		/// Returns consensus engine, fork transitions, gas schedule and validators of the active chain.
+		#[rpc(name = "parity_chainSpec")]
+		fn chain_spec(&self) -> Result<ChainSpec, Error>;
+
+		/// Returns status of the ethash verification cache, including generation progress.
+		/// Returns null if the engine does not use proof-of-work.
+		#[rpc(name = "parity_powCacheStatus")]
+		fn pow_cache_status(&self) -> Result<Option<PowCacheStatus>, Error>;
+
+		/// Returns assigned and missed slots of each validator over the recent steps of the engine.
+		/// Returns null if the engine does not track validator liveness.
+		#[rpc(name = "parity_validatorUptime")]
+		fn validator_uptime(&self) -> Result<Option<Vec<ValidatorUptime>>, Error>;
+
+		/// Returns hash of the latest block finalized by the consensus engine; the block itself can be
+		/// queried with the "finalized" block tag. Returns null if no block is final.
+		#[rpc(name = "parity_finalizedBlock")]
+		fn finalized_block(&self) -> Result<Option<H256>, Error>;
+
+		/// Returns a self-contained proof of the log at given index within the receipt of the transaction
+		/// with given hash, for relaying to other chains. Returns null if there is no such log.
+		#[rpc(name = "parity_logProof")]
+		fn log_proof(&self, H256, Index) -> Result<Option<LogProof>, Error>;
+
 		/// Returns number of unsigned transactions waiting in the signer queue (if signer enabled)
 		/// Returns error when signer is disabled
 		#[rpc(name = "parity_unsignedTransactionsCount")]
@@ -102,12 +131,13 @@ build_rpc_trait! {
 		#[rpc(name = "parity_registryAddress")]
 		fn registry_address(&self) -> Result<Option<H160>, Error>;
 
-		/// Returns all addresses if Fat DB is enabled (`--fat-db`), or null if not.
+		/// Returns up to `count` addresses (first parameter) following the given one
+		/// if Fat DB is enabled (`--fat-db`), or null if not.
 		#[rpc(name = "parity_listAccounts")]
 		fn list_accounts(&self, u64, Option<H160>, Trailing<BlockNumber>) -> Result<Option<Vec<H160>>, Error>;
 
-		/// Returns all storage keys of the given address (first parameter) if Fat DB is enabled (`--fat-db`),
-		/// or null if not.
+		/// Returns up to `count` storage keys of the given address (first parameter) following the given key
+		/// if Fat DB is enabled (`--fat-db`), or null if not.
 		#[rpc(name = "parity_listStorageKeys")]
 		fn list_storage_keys(&self, H160, u64, Option<H256>, Trailing<BlockNumber>) -> Result<Option<Vec<H256>>, Error>;
 
@@ -128,6 +158,10 @@ build_rpc_trait! {
 		#[rpc(name = "parity_localTransactions")]
 		fn local_transactions(&self) -> Result<BTreeMap<H256, LocalTransactionStatus>, Error>;
 
+		/// Returns senders whose queued transactions are blocked by a nonce gap.
+		#[rpc(name = "parity_nonceGaps")]
+		fn nonce_gaps(&self) -> Result<Vec<NonceGap>, Error>;
+
 		/// Returns current Trusted Signer port or an error if signer is disabled.
 		#[rpc(name = "parity_signerPort")]
 		fn signer_port(&self) -> Result<u16, Error>;
@@ -155,5 +189,39 @@ build_rpc_trait! {
 		/// Returns accounts information.
 		#[rpc(name = "parity_accounts")]
 		fn accounts(&self) -> Result<BTreeMap<String, BTreeMap<String, String>>, Error>;
+
+		/// Returns node's health report: sync status, peer count adequacy and clock drift.
+		#[rpc(name = "parity_nodeHealth")]
+		fn node_health(&self) -> Result<Health, Error>;
+
+		/// Returns detailed sync progress: current stage, per-stage progress with ETA and active peers.
+		#[rpc(name = "parity_syncStatus")]
+		fn sync_status(&self) -> Result<SyncProgress, Error>;
+
+		/// Returns estimated number of keys and size of every database column.
+		#[rpc(name = "parity_dbStats")]
+		fn db_stats(&self) -> Result<Vec<DbColumnStats>, Error>;
+
+		/// Returns estimated heap usage of the node's caches and queues, in bytes.
+		#[rpc(name = "parity_memoryProfile")]
+		fn memory_profile(&self) -> Result<MemoryProfile, Error>;
+
+		/// Returns hit and miss counts, sizes and budgets of the blockchain caches.
+		#[rpc(name = "parity_cacheStats")]
+		fn cache_stats(&self) -> Result<BlockChainCacheStats, Error>;
+
+		/// Executes given calls one after another on top of the state of given block, returning gas used, output and logs of each.
+		#[rpc(name = "parity_simulateTransactions")]
+		fn simulate_transactions(&self, Vec<CallRequest>, Trailing<BlockNumber>) -> Result<Vec<SimulationResult>, Error>;
+
+		/// Returns canonical transactions sent by given address in the given (inclusive) block range.
+		/// Requires the sender index to be enabled. Fails if the range holds more than 1000 transactions.
+		#[rpc(name = "parity_transactionsBySender")]
+		fn transactions_by_sender(&self, H160, BlockNumber, BlockNumber) -> Result<Vec<Transaction>, Error>;
+
+		/// Decodes a signed raw transaction without importing it.
+		/// Returns its fields, the recovered sender and reasons why it would be rejected.
+		#[rpc(name = "parity_decodeRawTransaction")]
+		fn decode_raw_transaction(&self, Bytes) -> Result<DecodedTransaction, Error>;
 	}
 }
@@ -19,7 +19,7 @@ use std::collections::BTreeMap;
 use jsonrpc_core::{Value, Error};
 
 use v1::helpers::auto_args::Wrap;
-use v1::types::{H160, H256};
+use v1::types::{H160, H256, AccountPolicy};
 
 build_rpc_trait! {
 	/// Personal Parity rpc interface.
@@ -66,9 +66,26 @@ build_rpc_trait! {
 		#[rpc(name = "parity_setAccountMeta")]
 		fn set_account_meta(&self, H160, String) -> Result<bool, Error>;
 
-		/// Returns accounts information.
-		#[rpc(name = "parity_setAccountVisiblity")]
-		fn set_account_visibility(&self, H160, H256, bool) -> Result<bool, Error>;
+		/// Set an account's tags.
+		#[rpc(name = "parity_setAccountTags")]
+		fn set_account_tags(&self, H160, Vec<String>) -> Result<bool, Error>;
+
+		/// Sets accounts visible to particular dapp.
+		/// Arguments: `dapp_id`, `addresses`.
+		#[rpc(name = "parity_setDappsAddresses")]
+		fn set_dapps_addresses(&self, String, Vec<H160>) -> Result<bool, Error>;
+
+		/// Returns accounts visible to particular dapp.
+		#[rpc(name = "parity_getDappsAddresses")]
+		fn dapps_addresses(&self, String) -> Result<Vec<H160>, Error>;
+
+		/// Sets or removes (with `null`) signing policy of an account.
+		#[rpc(name = "parity_setAccountPolicy")]
+		fn set_account_policy(&self, H160, Option<AccountPolicy>) -> Result<bool, Error>;
+
+		/// Returns signing policy of an account.
+		#[rpc(name = "parity_accountPolicy")]
+		fn account_policy(&self, H160) -> Result<Option<AccountPolicy>, Error>;
 
 		/// Imports a number of Geth accounts, with the list provided as the argument.
 		#[rpc(name = "parity_importGethAccounts")]
@@ -19,7 +19,7 @@
 use jsonrpc_core::Error;
 
 use v1::helpers::auto_args::{Wrap, WrapAsync, Ready};
-use v1::types::{Bytes, H160, H256, U256};
+use v1::types::{Bytes, H160, H256, H512, U256};
 
 build_rpc_trait! {
 	/// Parity-specific rpc interface for operations altering the settings.
@@ -56,6 +56,10 @@ build_rpc_trait! {
 		#[rpc(name = "parity_addReservedPeer")]
 		fn add_reserved_peer(&self, String) -> Result<bool, Error>;
 
+		/// Add a boot node in addition to the ones from the chain spec.
+		#[rpc(name = "parity_addBootnode")]
+		fn add_boot_node(&self, String) -> Result<bool, Error>;
+
 		/// Remove a reserved peer.
 		#[rpc(name = "parity_removeReservedPeer")]
 		fn remove_reserved_peer(&self, String) -> Result<bool, Error>;
@@ -80,10 +84,35 @@ build_rpc_trait! {
 		#[rpc(name = "parity_stopNetwork")]
 		fn stop_network(&self) -> Result<bool, Error>;
 
+		/// Replace the node key with a new one, used after the next restart. Discovery keeps
+		/// answering for the current identity during the given number of seconds.
+		/// Returns the new node id.
+		#[rpc(name = "parity_rotateNodeKey")]
+		fn rotate_node_key(&self, u64) -> Result<H512, Error>;
+
 		/// Set the mode. Argument must be one of: "active", "passive", "dark", "offline".
 		#[rpc(name = "parity_setMode")]
 		fn set_mode(&self, String) -> Result<bool, Error>;
 
+		/// Set CORS domains allowed by the HTTP JSON-RPC server. `null` disables CORS headers.
+		/// Domains may contain `*` wildcards.
+		#[rpc(name = "parity_setCors")]
+		fn set_cors(&self, Option<Vec<String>>) -> Result<bool, Error>;
+
+		/// Seal and import a new block right away, even without transactions. Returns its hash.
+		/// Only available with engines which seal internally (e.g. `--chain dev`).
+		#[rpc(name = "parity_devMine")]
+		fn dev_mine(&self) -> Result<H256, Error>;
+
+		/// Seal a block every given number of seconds, even without transactions. `0` disables it.
+		/// Only used with engines which seal internally (e.g. `--chain dev`).
+		#[rpc(name = "parity_devSetBlockInterval")]
+		fn dev_set_block_interval(&self, u64) -> Result<bool, Error>;
+
+		/// Start flushing and compacting the database in the background.
+		#[rpc(name = "parity_dbCompact")]
+		fn db_compact(&self) -> Result<bool, Error>;
+
 		/// Hash a file content under given URL.
 		#[rpc(async, name = "parity_hashContent")]
 		fn hash_content(&self, Ready<H256>, String);
@@ -18,7 +18,7 @@
 use jsonrpc_core::Error;
 
 use v1::helpers::auto_args::{Wrap, WrapAsync, Ready};
-use v1::types::{U256, H160, H256, Bytes, ConfirmationResponse, TransactionRequest, Either};
+use v1::types::{U256, H160, H256, H520, Bytes, ConfirmationResponse, TransactionRequest, Either, UnsignedTransaction, RichRawTransaction};
 
 build_rpc_trait! {
 	/// Signing methods implementation.
@@ -38,6 +38,19 @@ build_rpc_trait! {
 		#[rpc(name = "parity_checkRequest")]
 		fn check_request(&self, U256) -> Result<Option<ConfirmationResponse>, Error>;
 
+		/// Fills in the transaction for signing offline, without signing it.
+		/// The `hash` field of the result has to be signed with the key of `from`.
+		#[rpc(name = "parity_composeTransaction")]
+		fn compose_transaction(&self, TransactionRequest) -> Result<UnsignedTransaction, Error>;
+
+		/// Attaches a signature of the composed transaction's hash and returns the signed transaction.
+		#[rpc(name = "parity_combineTransaction")]
+		fn combine_transaction(&self, UnsignedTransaction, H520) -> Result<RichRawTransaction, Error>;
+
+		/// Attaches a signature of the composed transaction's hash and submits the transaction.
+		#[rpc(name = "parity_submitSignedTransaction")]
+		fn submit_signed_transaction(&self, UnsignedTransaction, H520) -> Result<H256, Error>;
+
 		/// Decrypt some ECIES-encrypted message.
 		/// First parameter is the address with which it is encrypted, second is the ciphertext.
 		#[rpc(async, name = "parity_decryptMessage")]
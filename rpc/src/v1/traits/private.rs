@@ -0,0 +1,42 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Private transactions rpc interface.
+
+use jsonrpc_core::Error;
+
+use v1::helpers::auto_args::Wrap;
+use v1::types::{Bytes, H256, PrivateTransaction, PrivateTransactionReceipt};
+
+build_rpc_trait! {
+	/// Private transactions rpc interface.
+	pub trait Private {
+		/// Executes a signed call to a private contract off-chain, encrypts it to the validators
+		/// and sends the public transaction committing to the resulting contract state.
+		/// The call must be signed by an account of this node.
+		#[rpc(name = "private_sendTransaction")]
+		fn send_transaction(&self, Bytes) -> Result<PrivateTransactionReceipt, Error>;
+
+		/// Imports an encrypted private transaction sent to this node as a validator.
+		/// Returns the hash of the private transaction.
+		#[rpc(name = "private_importTransaction")]
+		fn import_transaction(&self, Bytes) -> Result<H256, Error>;
+
+		/// Returns a private transaction sent or imported by this node, in its encrypted form.
+		#[rpc(name = "private_transaction")]
+		fn transaction(&self, H256) -> Result<Option<PrivateTransaction>, Error>;
+	}
+}
@@ -0,0 +1,39 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction pool inspection rpc interface.
+
+use jsonrpc_core::Error;
+
+use v1::helpers::auto_args::Wrap;
+use v1::types::{Transaction, TxPoolContent, TxPoolStatus};
+
+build_rpc_trait! {
+	/// Transaction pool inspection rpc interface, compatible with the `txpool_` namespace.
+	pub trait TxPool {
+		/// Returns pending and queued transactions grouped by sender and nonce.
+		#[rpc(name = "txpool_content")]
+		fn content(&self) -> Result<TxPoolContent<Transaction>, Error>;
+
+		/// Returns number of pending and queued transactions.
+		#[rpc(name = "txpool_status")]
+		fn status(&self) -> Result<TxPoolStatus, Error>;
+
+		/// Returns short summaries of pending and queued transactions grouped by sender and nonce.
+		#[rpc(name = "txpool_inspect")]
+		fn inspect(&self) -> Result<TxPoolContent<String>, Error>;
+	}
+}
@@ -0,0 +1,59 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity. If not, see <http://www.gnu.org/licenses/>.
+
+//! Account signing policy.
+
+use ethcore::account_provider::AccountPolicy as EthAccountPolicy;
+use v1::types::{H160, U256};
+
+/// Rules checked before anything is signed with an account.
+/// Requests violating them have to be confirmed in the signer.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccountPolicy {
+	/// Maximal value sent per day.
+	#[serde(rename="maxDailyValue")]
+	pub max_daily_value: Option<U256>,
+	/// Allowed recipients, any recipient is allowed if not set.
+	#[serde(rename="allowedRecipients")]
+	pub allowed_recipients: Option<Vec<H160>>,
+	/// Whether every request has to be confirmed.
+	#[serde(rename="requireConfirmation")]
+	pub require_confirmation: bool,
+}
+
+impl From<EthAccountPolicy> for AccountPolicy {
+	fn from(p: EthAccountPolicy) -> Self {
+		AccountPolicy {
+			max_daily_value: p.max_daily_value.map(Into::into),
+			allowed_recipients: p.allowed_recipients.map(|r| {
+				let mut r: Vec<H160> = r.into_iter().map(Into::into).collect();
+				r.sort();
+				r
+			}),
+			require_confirmation: p.require_confirmation,
+		}
+	}
+}
+
+impl Into<EthAccountPolicy> for AccountPolicy {
+	fn into(self) -> EthAccountPolicy {
+		EthAccountPolicy {
+			max_daily_value: self.max_daily_value.map(Into::into),
+			allowed_recipients: self.allowed_recipients.map(|r| r.into_iter().map(Into::into).collect()),
+			require_confirmation: self.require_confirmation,
+		}
+	}
+}
@@ -29,6 +29,8 @@ pub enum BlockNumber {
 	Earliest,
 	/// Pending block (being mined)
 	Pending,
+	/// Latest block finalized by the consensus engine
+	Finalized,
 }
 
 impl Default for BlockNumber {
@@ -54,6 +56,7 @@ impl Visitor for BlockNumberVisitor {
 			"latest" => Ok(BlockNumber::Latest),
 			"earliest" => Ok(BlockNumber::Earliest),
 			"pending" => Ok(BlockNumber::Pending),
+			"finalized" => Ok(BlockNumber::Finalized),
 			_ if value.starts_with("0x") => u64::from_str_radix(&value[2..], 16).map(BlockNumber::Num).map_err(|_| Error::custom("invalid block number")),
 			_ => value.parse::<u64>().map(BlockNumber::Num).map_err(|_| Error::custom("invalid block number"))
 		}
@@ -71,6 +74,7 @@ impl Into<BlockID> for BlockNumber {
 			BlockNumber::Earliest => BlockID::Earliest,
 			BlockNumber::Latest => BlockID::Latest,
 			BlockNumber::Pending => BlockID::Pending,
+			BlockNumber::Finalized => BlockID::Finalized,
 		}
 	}
 }
@@ -83,9 +87,9 @@ mod tests {
 
 	#[test]
 	fn block_number_deserialization() {
-		let s = r#"["0xa", "10", "latest", "earliest", "pending"]"#;
+		let s = r#"["0xa", "10", "latest", "earliest", "pending", "finalized"]"#;
 		let deserialized: Vec<BlockNumber> = serde_json::from_str(s).unwrap();
-		assert_eq!(deserialized, vec![BlockNumber::Num(10), BlockNumber::Num(10), BlockNumber::Latest, BlockNumber::Earliest, BlockNumber::Pending])
+		assert_eq!(deserialized, vec![BlockNumber::Num(10), BlockNumber::Num(10), BlockNumber::Latest, BlockNumber::Earliest, BlockNumber::Pending, BlockNumber::Finalized])
 	}
 
 	#[test]
@@ -94,6 +98,7 @@ mod tests {
 		assert_eq!(BlockID::Earliest, BlockNumber::Earliest.into());
 		assert_eq!(BlockID::Latest, BlockNumber::Latest.into());
 		assert_eq!(BlockID::Pending, BlockNumber::Pending.into());
+		assert_eq!(BlockID::Finalized, BlockNumber::Finalized.into());
 	}
 }
 
@@ -0,0 +1,59 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity. If not, see <http://www.gnu.org/licenses/>.
+
+//! Block rewards.
+
+use ethcore::block_rewards::{BlockRewards as EthBlockRewards, Reward as EthReward};
+use v1::types::{H160, U256};
+
+/// Reward bestowed on a single account.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Reward {
+	/// Rewarded account.
+	pub address: H160,
+	/// Reward amount.
+	pub amount: U256,
+}
+
+impl From<EthReward> for Reward {
+	fn from(r: EthReward) -> Self {
+		Reward {
+			address: r.address.into(),
+			amount: r.amount.into(),
+		}
+	}
+}
+
+/// Rewards bestowed on closing a block.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BlockReward {
+	/// Reward of the block author, including rewards for included uncles.
+	pub author: Reward,
+	/// Rewards of included uncles' authors.
+	pub uncles: Vec<Reward>,
+	/// Total amount issued with the block.
+	pub total: U256,
+}
+
+impl From<EthBlockRewards> for BlockReward {
+	fn from(r: EthBlockRewards) -> Self {
+		BlockReward {
+			total: r.total().into(),
+			author: r.author.into(),
+			uncles: r.uncles.into_iter().map(Into::into).collect(),
+		}
+	}
+}
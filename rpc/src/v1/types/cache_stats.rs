@@ -0,0 +1,67 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Blockchain cache statistics.
+
+use ethcore::cache_stats;
+
+/// Usage statistics of a single cache.
+#[derive(Debug, Default, PartialEq, Clone, Serialize)]
+pub struct CacheStats {
+	/// Number of lookups served from the cache.
+	pub hits: u64,
+	/// Number of lookups which had to read the database.
+	pub misses: u64,
+	/// Current size, in bytes.
+	pub size: usize,
+	/// Part of the total budget currently assigned to the cache, in bytes.
+	pub budget: usize,
+}
+
+impl From<cache_stats::CacheStats> for CacheStats {
+	fn from(c: cache_stats::CacheStats) -> Self {
+		CacheStats {
+			hits: c.hits,
+			misses: c.misses,
+			size: c.size,
+			budget: c.budget,
+		}
+	}
+}
+
+/// Usage statistics of the blockchain caches.
+#[derive(Debug, Default, PartialEq, Clone, Serialize)]
+pub struct BlockChainCacheStats {
+	/// Block headers.
+	pub headers: CacheStats,
+	/// Block bodies.
+	pub bodies: CacheStats,
+	/// Block receipts.
+	pub receipts: CacheStats,
+	/// Block details, hashes, transaction addresses and blooms.
+	pub details: CacheStats,
+}
+
+impl From<cache_stats::BlockChainCacheStats> for BlockChainCacheStats {
+	fn from(c: cache_stats::BlockChainCacheStats) -> Self {
+		BlockChainCacheStats {
+			headers: c.headers.into(),
+			bodies: c.bodies.into(),
+			receipts: c.receipts.into(),
+			details: c.details.into(),
+		}
+	}
+}
@@ -0,0 +1,134 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity. If not, see <http://www.gnu.org/licenses/>.
+
+//! Active chain specification.
+
+use std::collections::BTreeMap;
+use ethcore::chain_spec_info::ChainSpecInfo;
+use ethcore::evm::Schedule;
+use v1::types::{H160, U256};
+
+/// Gas costs and limits of the EVM schedule.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct GasSchedule {
+	/// Base cost of a transaction.
+	#[serde(rename="txGas")]
+	pub tx_gas: U256,
+	/// Base cost of a contract creation transaction.
+	#[serde(rename="txCreateGas")]
+	pub tx_create_gas: U256,
+	/// Cost of a zero byte of transaction data.
+	#[serde(rename="txDataZeroGas")]
+	pub tx_data_zero_gas: U256,
+	/// Cost of a non-zero byte of transaction data.
+	#[serde(rename="txDataNonZeroGas")]
+	pub tx_data_non_zero_gas: U256,
+	/// Cost of `SLOAD`.
+	#[serde(rename="sloadGas")]
+	pub sload_gas: U256,
+	/// Cost of setting a storage slot from zero.
+	#[serde(rename="sstoreSetGas")]
+	pub sstore_set_gas: U256,
+	/// Cost of altering a storage slot.
+	#[serde(rename="sstoreResetGas")]
+	pub sstore_reset_gas: U256,
+	/// Refund for clearing a storage slot.
+	#[serde(rename="sstoreRefundGas")]
+	pub sstore_refund_gas: U256,
+	/// Cost of `*CALL*` opcodes.
+	#[serde(rename="callGas")]
+	pub call_gas: U256,
+	/// Cost of `CREATE`.
+	#[serde(rename="createGas")]
+	pub create_gas: U256,
+	/// Cost of `BALANCE`.
+	#[serde(rename="balanceGas")]
+	pub balance_gas: U256,
+	/// Cost of `EXTCODESIZE`.
+	#[serde(rename="extcodesizeGas")]
+	pub extcodesize_gas: U256,
+	/// Cost of `SUICIDE`.
+	#[serde(rename="suicideGas")]
+	pub suicide_gas: U256,
+	/// Maximal size of deployed contract code.
+	#[serde(rename="maxCodeSize")]
+	pub max_code_size: U256,
+	/// Maximal call depth.
+	#[serde(rename="maxDepth")]
+	pub max_depth: U256,
+	/// Whether empty accounts are never created (EIP-161).
+	#[serde(rename="noEmpty")]
+	pub no_empty: bool,
+	/// Whether touched empty accounts are removed (EIP-161).
+	#[serde(rename="killEmpty")]
+	pub kill_empty: bool,
+}
+
+impl<'a> From<&'a Schedule> for GasSchedule {
+	fn from(s: &'a Schedule) -> Self {
+		GasSchedule {
+			tx_gas: s.tx_gas.into(),
+			tx_create_gas: s.tx_create_gas.into(),
+			tx_data_zero_gas: s.tx_data_zero_gas.into(),
+			tx_data_non_zero_gas: s.tx_data_non_zero_gas.into(),
+			sload_gas: s.sload_gas.into(),
+			sstore_set_gas: s.sstore_set_gas.into(),
+			sstore_reset_gas: s.sstore_reset_gas.into(),
+			sstore_refund_gas: s.sstore_refund_gas.into(),
+			call_gas: s.call_gas.into(),
+			create_gas: s.create_gas.into(),
+			balance_gas: s.balance_gas.into(),
+			extcodesize_gas: s.extcodesize_gas.into(),
+			suicide_gas: s.suicide_gas.into(),
+			max_code_size: s.create_data_limit.into(),
+			max_depth: s.max_depth.into(),
+			no_empty: s.no_empty,
+			kill_empty: s.kill_empty,
+		}
+	}
+}
+
+/// Consensus-related parameters of the active chain specification.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ChainSpec {
+	/// Name of the consensus engine.
+	pub engine: String,
+	/// Network ID.
+	#[serde(rename="networkId")]
+	pub network_id: U256,
+	/// Block numbers from which rule changes take effect, keyed by spec parameter name.
+	pub transitions: BTreeMap<String, U256>,
+	/// Accounts allowed to seal blocks if the engine uses a fixed validator set.
+	pub validators: Option<Vec<H160>>,
+	/// Additional engine-specific parameters.
+	pub params: BTreeMap<String, String>,
+	/// EVM schedule in effect for the next block.
+	pub schedule: GasSchedule,
+}
+
+impl ChainSpec {
+	/// Creates new `ChainSpec` from chain spec summary and current schedule.
+	pub fn new(info: ChainSpecInfo, schedule: &Schedule) -> Self {
+		ChainSpec {
+			engine: info.engine,
+			network_id: info.network_id.into(),
+			transitions: info.transitions.into_iter().map(|(name, block)| (name, block.into())).collect(),
+			validators: info.validators.map(|v| v.into_iter().map(Into::into).collect()),
+			params: info.params,
+			schedule: schedule.into(),
+		}
+	}
+}
@@ -0,0 +1,82 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity. If not, see <http://www.gnu.org/licenses/>.
+
+//! Decoded raw transaction.
+
+use ethcore::transaction::{Action, SignedTransaction};
+use v1::types::{Bytes, H160, H256, U256};
+
+/// Fields of a raw transaction along with problems which would prevent its import.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct DecodedTransaction {
+	/// Hash
+	pub hash: H256,
+	/// Nonce
+	pub nonce: U256,
+	/// Sender recovered from the signature, if valid
+	pub from: Option<H160>,
+	/// Recipient
+	pub to: Option<H160>,
+	/// Transfered value
+	pub value: U256,
+	/// Gas Price
+	#[serde(rename="gasPrice")]
+	pub gas_price: U256,
+	/// Gas
+	pub gas: U256,
+	/// Gas required before any code is executed
+	#[serde(rename="intrinsicGas")]
+	pub intrinsic_gas: U256,
+	/// Data
+	pub input: Bytes,
+	/// Network id used for replay protection
+	#[serde(rename="networkId")]
+	pub network_id: Option<u8>,
+	/// The V field of the signature.
+	pub v: u8,
+	/// The R field of the signature.
+	pub r: H256,
+	/// The S field of the signature.
+	pub s: H256,
+	/// Reasons why the transaction would be rejected, empty if it looks valid.
+	pub errors: Vec<String>,
+}
+
+impl DecodedTransaction {
+	/// Creates new `DecodedTransaction`.
+	pub fn new(t: &SignedTransaction, intrinsic_gas: u64, errors: Vec<String>) -> Self {
+		let signature = t.signature();
+		DecodedTransaction {
+			hash: t.hash().into(),
+			nonce: t.nonce.into(),
+			from: t.sender().ok().map(Into::into),
+			to: match t.action {
+				Action::Create => None,
+				Action::Call(ref address) => Some(address.clone().into()),
+			},
+			value: t.value.into(),
+			gas_price: t.gas_price.into(),
+			gas: t.gas.into(),
+			intrinsic_gas: intrinsic_gas.into(),
+			input: t.data.clone().into(),
+			network_id: t.network_id(),
+			v: signature.v(),
+			r: signature.r().into(),
+			s: signature.s().into(),
+			errors: errors,
+		}
+	}
+}
@@ -0,0 +1,32 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity. If not, see <http://www.gnu.org/licenses/>.
+
+//! Block fee statistics.
+
+use v1::types::U256;
+
+/// Gas usage and paid gas prices over a range of blocks.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct FeeHistory {
+	/// Number of the oldest block in the range.
+	#[serde(rename="oldestBlock")]
+	pub oldest_block: U256,
+	/// Ratio of gas used to gas limit for each block.
+	#[serde(rename="gasUsedRatio")]
+	pub gas_used_ratio: Vec<f64>,
+	/// Gas prices at the requested percentiles of gas used, for each block.
+	pub reward: Vec<Vec<U256>>,
+}
@@ -0,0 +1,73 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Node health information.
+
+use serde::Serialize;
+
+/// Status of a single health check.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub enum HealthStatus {
+	/// Everything is fine.
+	#[serde(rename="ok")]
+	Ok,
+	/// Node works, but the check should be looked into.
+	#[serde(rename="needsAttention")]
+	NeedsAttention,
+	/// Node should not be serving requests.
+	#[serde(rename="bad")]
+	Bad,
+}
+
+/// Result of a single health check.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct HealthInfo<T: Serialize> {
+	/// Status of the check.
+	pub status: HealthStatus,
+	/// Human-readable explanation of the status.
+	pub message: String,
+	/// Measured value.
+	pub details: T,
+}
+
+/// Peer count details.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct PeersDetails {
+	/// Number of connected peers.
+	pub connected: usize,
+	/// Minimal number of peers the node tries to maintain.
+	pub min: usize,
+}
+
+/// Node health.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Health {
+	/// Peer count adequacy.
+	pub peers: HealthInfo<PeersDetails>,
+	/// Whether the node is fully synced (`details` is `true` when it is syncing).
+	pub sync: HealthInfo<bool>,
+	/// Local clock drift in milliseconds.
+	pub time: HealthInfo<i64>,
+}
+
+impl Health {
+	/// Returns true if none of the checks is `Bad`.
+	pub fn is_healthy(&self) -> bool {
+		self.peers.status != HealthStatus::Bad &&
+			self.sync.status != HealthStatus::Bad &&
+			self.time.status != HealthStatus::Bad
+	}
+}
@@ -0,0 +1,74 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Log proof.
+
+use util::Hashable;
+use ethcore::log_proof::LogProof as EthcoreLogProof;
+use v1::types::{Bytes, H256, U256};
+
+/// Proof of a log entry against the header of the block containing it.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct LogProof {
+	/// Hash of the block containing the log.
+	#[serde(rename="blockHash")]
+	pub block_hash: H256,
+	/// Encoded block header.
+	pub header: Bytes,
+	/// Receipts trie nodes on the path from the receipts root to the receipt.
+	#[serde(rename="receiptProof")]
+	pub receipt_proof: Vec<Bytes>,
+	/// Index of the transaction in the block.
+	#[serde(rename="transactionIndex")]
+	pub transaction_index: U256,
+	/// Index of the log in the transaction receipt.
+	#[serde(rename="logIndex")]
+	pub log_index: U256,
+	/// Canonical encoding of the whole proof, as consumed by relays.
+	pub rlp: Bytes,
+}
+
+impl From<EthcoreLogProof> for LogProof {
+	fn from(p: EthcoreLogProof) -> Self {
+		LogProof {
+			block_hash: p.header.sha3().into(),
+			rlp: ::rlp::encode(&p).to_vec().into(),
+			header: p.header.into(),
+			receipt_proof: p.receipt_proof.into_iter().map(Into::into).collect(),
+			transaction_index: p.transaction_index.into(),
+			log_index: p.log_index.into(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use ethcore::log_proof::LogProof as EthcoreLogProof;
+	use super::LogProof;
+
+	#[test]
+	fn should_serialize_log_proof() {
+		let proof = LogProof::from(EthcoreLogProof {
+			header: vec![0xc0],
+			receipt_proof: vec![vec![0x12, 0x34]],
+			transaction_index: 1,
+			log_index: 2,
+		});
+		let serialized = serde_json::to_string(&proof).unwrap();
+		assert_eq!(serialized, r#"{"blockHash":"0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347","header":"0xc0","receiptProof":["0x1234"],"transactionIndex":"0x1","logIndex":"0x2","rlp":"0xc7c0c38212340102"}"#);
+	}
+}
@@ -0,0 +1,41 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Memory usage of the node.
+
+/// Estimated heap usage of the node's caches and queues, in bytes.
+#[derive(Debug, Default, PartialEq, Clone, Serialize)]
+pub struct MemoryProfile {
+	/// Block verification queue.
+	#[serde(rename="blockQueue")]
+	pub block_queue: usize,
+	/// Blockchain caches.
+	#[serde(rename="blockchainCache")]
+	pub blockchain_cache: usize,
+	/// State database caches.
+	#[serde(rename="stateCache")]
+	pub state_cache: usize,
+	/// Trace database cache.
+	#[serde(rename="traceCache")]
+	pub trace_cache: usize,
+	/// Transaction queue.
+	#[serde(rename="transactionQueue")]
+	pub transaction_queue: usize,
+	/// Block downloaders and peer tables of the eth and light protocols.
+	pub sync: usize,
+	/// Sum of the above.
+	pub total: usize,
+}
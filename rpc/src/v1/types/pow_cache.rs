@@ -0,0 +1,63 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity. If not, see <http://www.gnu.org/licenses/>.
+
+//! Proof-of-work cache status.
+
+use ethcore::pow_cache_status::PowCacheStatus as EthcorePowCacheStatus;
+
+/// Status of the ethash verification cache, for miners waiting on its generation.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PowCacheStatus {
+	/// Epoch of the most recently used cache.
+	#[serde(rename="currentEpoch")]
+	pub current_epoch: Option<u64>,
+	/// Epoch of the cache being generated, if any.
+	#[serde(rename="generatingEpoch")]
+	pub generating_epoch: Option<u64>,
+	/// Generation progress of that cache, in percent.
+	pub progress: u8,
+	/// Epochs which have a cache stored on disk.
+	#[serde(rename="cachedEpochs")]
+	pub cached_epochs: Vec<u64>,
+}
+
+impl From<EthcorePowCacheStatus> for PowCacheStatus {
+	fn from(s: EthcorePowCacheStatus) -> Self {
+		PowCacheStatus {
+			current_epoch: s.current_epoch,
+			generating_epoch: s.generating_epoch,
+			progress: s.progress,
+			cached_epochs: s.cached_epochs,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::PowCacheStatus;
+
+	#[test]
+	fn should_serialize_pow_cache_status() {
+		let status = PowCacheStatus {
+			current_epoch: Some(98),
+			generating_epoch: Some(99),
+			progress: 42,
+			cached_epochs: vec![97, 98],
+		};
+		assert_eq!(serde_json::to_string(&status).unwrap(), r#"{"currentEpoch":98,"generatingEpoch":99,"progress":42,"cachedEpochs":[97,98]}"#);
+	}
+}
@@ -0,0 +1,76 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Private transactions.
+
+use ethcore::private_transactions::{Receipt, StoredTransaction};
+use v1::types::{Bytes, H160, H256};
+
+/// Result of sending a private transaction.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PrivateTransactionReceipt {
+	/// Hash of the private transaction.
+	pub hash: H256,
+	/// Commitment to the private contract storage after the call.
+	pub commitment: H256,
+	/// Hash of the public commitment transaction.
+	#[serde(rename="publicTransactionHash")]
+	pub public_transaction_hash: H256,
+}
+
+impl From<Receipt> for PrivateTransactionReceipt {
+	fn from(r: Receipt) -> Self {
+		PrivateTransactionReceipt {
+			hash: r.hash.into(),
+			commitment: r.commitment.into(),
+			public_transaction_hash: r.public_transaction.into(),
+		}
+	}
+}
+
+/// Private transaction known to the node.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PrivateTransaction {
+	/// Hash of the private transaction.
+	pub hash: H256,
+	/// Private contract called by the transaction.
+	pub contract: H160,
+	/// Commitment to the private contract storage the call was executed on.
+	pub prior: H256,
+	/// Commitment to the private contract storage after the call.
+	pub commitment: H256,
+	/// Validators able to decrypt the transaction.
+	pub validators: Vec<H160>,
+	/// Hash of the public commitment transaction if it was sent by this node.
+	#[serde(rename="publicTransactionHash")]
+	pub public_transaction_hash: Option<H256>,
+	/// Encrypted private transaction, as imported by the validators.
+	pub encrypted: Bytes,
+}
+
+impl From<StoredTransaction> for PrivateTransaction {
+	fn from(t: StoredTransaction) -> Self {
+		PrivateTransaction {
+			hash: t.private.hash().into(),
+			contract: t.private.contract.into(),
+			prior: t.private.prior.into(),
+			commitment: t.private.commitment.into(),
+			validators: t.private.payload.recipients().into_iter().map(Into::into).collect(),
+			encrypted: ::rlp::encode(&t.private).to_vec().into(),
+			public_transaction_hash: t.public_transaction.map(Into::into),
+		}
+	}
+}
@@ -0,0 +1,62 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::client::Executed;
+use v1::types::{Bytes, Log, U256};
+
+/// Result of a single simulated call.
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct SimulationResult {
+	/// Gas used
+	#[serde(rename="gasUsed")]
+	pub gas_used: U256,
+	/// Output
+	pub output: Bytes,
+	/// Logs
+	pub logs: Vec<Log>,
+	/// VM exception which terminated the execution, if any
+	pub error: Option<String>,
+}
+
+impl From<Executed> for SimulationResult {
+	fn from(e: Executed) -> Self {
+		SimulationResult {
+			gas_used: e.gas_used.into(),
+			output: e.output.into(),
+			logs: e.logs.into_iter().map(Into::into).collect(),
+			error: e.exception.map(|e| format!("{}", e)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use v1::types::{Bytes, U256};
+	use super::SimulationResult;
+
+	#[test]
+	fn simulation_result_serialize() {
+		let result = SimulationResult {
+			gas_used: U256::from(21000u64),
+			output: Bytes::new(vec![0x12]),
+			logs: vec![],
+			error: Some("Out of gas".into()),
+		};
+		let serialized = serde_json::to_string(&result).unwrap();
+		assert_eq!(serialized, r#"{"gasUsed":"0x5208","output":"0x12","logs":[],"error":"Out of gas"}"#);
+	}
+}
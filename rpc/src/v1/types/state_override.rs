@@ -0,0 +1,72 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use ethcore::client::AccountOverride as EthAccountOverride;
+use v1::types::{Bytes, H160, H256, U256};
+
+/// Account state replaced for the duration of a call.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountOverride {
+	/// Balance
+	pub balance: Option<U256>,
+	/// Code
+	pub code: Option<Bytes>,
+	/// Storage slots to set
+	pub storage: Option<BTreeMap<H256, H256>>,
+}
+
+impl Into<EthAccountOverride> for AccountOverride {
+	fn into(self) -> EthAccountOverride {
+		EthAccountOverride {
+			balance: self.balance.map(Into::into),
+			code: self.code.map(Into::into),
+			storage: self.storage.map_or_else(BTreeMap::new, |storage| storage.into_iter()
+				.map(|(key, value)| (key.into(), value.into()))
+				.collect()),
+		}
+	}
+}
+
+/// Account overrides keyed by address.
+pub type StateOverride = BTreeMap<H160, AccountOverride>;
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use v1::types::{H160, H256, U256};
+	use super::StateOverride;
+
+	#[test]
+	fn state_override_deserialize() {
+		let s = r#"{
+			"0x0000000000000000000000000000000000000001": {
+				"balance": "0x10",
+				"code": "0x6000",
+				"storage": {
+					"0x0000000000000000000000000000000000000000000000000000000000000001": "0x0000000000000000000000000000000000000000000000000000000000000002"
+				}
+			}
+		}"#;
+		let deserialized: StateOverride = serde_json::from_str(s).unwrap();
+		let account = deserialized.get(&H160::from(1u64)).unwrap();
+
+		assert_eq!(account.balance, Some(U256::from(0x10u64)));
+		assert_eq!(account.code, Some(vec![0x60, 0x00].into()));
+		assert_eq!(account.storage.as_ref().unwrap().get(&H256::from(1u64)), Some(&H256::from(2u64)));
+	}
+}
@@ -15,7 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::BTreeMap;
-use ethsync::{PeerInfo as SyncPeerInfo, TransactionStats as SyncTransactionStats};
+use ethsync::{PeerInfo as SyncPeerInfo, TransactionStats as SyncTransactionStats, PeerPropagationStats};
 use serde::{Serialize, Serializer};
 use v1::types::{U256, H512};
 
@@ -97,6 +97,28 @@ pub struct PeerEthereumProtocolInfo {
 	pub difficulty: Option<U256>,
 	/// SHA3 of peer best block hash
 	pub head: String,
+	/// How early the peer delivers new blocks and transactions
+	pub propagation: PeerPropagationInfo,
+}
+
+/// Arrival statistics of new blocks and transactions from a peer
+#[derive(Default, Debug, Serialize)]
+pub struct PeerPropagationInfo {
+	/// Number of blocks announced by this peer before any other peer
+	#[serde(rename="blocksFirst")]
+	pub blocks_first: u64,
+	/// Number of blocks announced by this peer
+	#[serde(rename="blocksTotal")]
+	pub blocks_total: u64,
+	/// Average delay (in ms) behind the first peer for the remaining blocks
+	#[serde(rename="averageBlockDelay")]
+	pub average_block_delay: u64,
+	/// Number of transactions sent by this peer before any other peer
+	#[serde(rename="transactionsFirst")]
+	pub transactions_first: u64,
+	/// Number of transactions sent by this peer
+	#[serde(rename="transactionsTotal")]
+	pub transactions_total: u64,
 }
 
 /// Sync status
@@ -118,6 +140,33 @@ impl Serialize for SyncStatus {
 	}
 }
 
+/// Progress of a single sync stage.
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct StageProgress {
+	/// Stage name
+	pub stage: String,
+	/// Amount of work done (chunks or blocks)
+	pub done: u64,
+	/// Total amount of work
+	pub total: u64,
+	/// Estimated number of seconds until the stage completes, if known
+	pub eta: Option<u64>,
+}
+
+/// Detailed sync progress.
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct SyncProgress {
+	/// Current stage: `waitingPeers`, `warpManifest`, `warpState`, `blocks`, `ancientBlocks` or `idle`
+	pub stage: String,
+	/// Progress of all stages which are still in progress
+	pub stages: Vec<StageProgress>,
+	/// Number of connected peers
+	pub peers: usize,
+	/// Number of peers blocks or chunks are currently downloaded from
+	#[serde(rename="activePeers")]
+	pub active_peers: usize,
+}
+
 /// Propagation statistics for pending transaction.
 #[derive(Default, Debug, Serialize)]
 pub struct TransactionStats {
@@ -144,12 +193,25 @@ impl From<SyncPeerInfo> for PeerInfo {
 					version: p.eth_version,
 					difficulty: p.eth_difficulty.map(|d| d.into()),
 					head: p.eth_head.hex(),
+					propagation: p.propagation.into(),
 				})
 			},
 		}
 	}
 }
 
+impl From<PeerPropagationStats> for PeerPropagationInfo {
+	fn from(s: PeerPropagationStats) -> Self {
+		PeerPropagationInfo {
+			blocks_first: s.blocks_first,
+			blocks_total: s.blocks_total,
+			average_block_delay: s.average_block_delay_ms,
+			transactions_first: s.transactions_first,
+			transactions_total: s.transactions_total,
+		}
+	}
+}
+
 impl From<SyncTransactionStats> for TransactionStats {
 	fn from(s: SyncTransactionStats) -> Self {
 		TransactionStats {
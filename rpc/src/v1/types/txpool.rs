@@ -0,0 +1,131 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity. If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction pool content in the format of the `txpool_` namespace.
+
+use std::collections::BTreeMap;
+use serde::Serialize;
+use ethcore::miner;
+use ethcore::transaction::{SignedTransaction, Action};
+use v1::types::{H160, U256};
+
+/// Transactions grouped by sender address and nonce (as decimal string).
+pub type TxPoolBucket<T> = BTreeMap<String, BTreeMap<String, T>>;
+
+/// Contents of the transaction pool.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct TxPoolContent<T: Serialize> {
+	/// Transactions ready to be included in a block.
+	pub pending: TxPoolBucket<T>,
+	/// Transactions waiting for a nonce gap to be filled.
+	pub queued: TxPoolBucket<T>,
+}
+
+impl<T: Serialize> TxPoolContent<T> {
+	/// Groups pending and queued transactions using `f` to describe each one of them.
+	pub fn new<F>(pending: Vec<SignedTransaction>, queued: Vec<SignedTransaction>, f: F) -> Self where F: Fn(SignedTransaction) -> T {
+		TxPoolContent {
+			pending: bucket(pending, &f),
+			queued: bucket(queued, &f),
+		}
+	}
+}
+
+impl TxPoolContent<String> {
+	/// Groups pending and queued transactions describing each one with a short summary.
+	pub fn summaries(pending: Vec<SignedTransaction>, queued: Vec<SignedTransaction>) -> Self {
+		Self::new(pending, queued, |tx| summary(&tx))
+	}
+}
+
+fn bucket<T, F>(transactions: Vec<SignedTransaction>, f: &F) -> TxPoolBucket<T> where F: Fn(SignedTransaction) -> T {
+	let mut bucket = TxPoolBucket::new();
+	for tx in transactions {
+		let sender = match tx.sender() {
+			Ok(sender) => format!("0x{:?}", sender),
+			Err(_) => continue,
+		};
+		let nonce = format!("{}", tx.nonce);
+		bucket.entry(sender).or_insert_with(BTreeMap::new).insert(nonce, f(tx));
+	}
+	bucket
+}
+
+/// Number of transactions in the pool.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct TxPoolStatus {
+	/// Transactions ready to be included in a block.
+	pub pending: U256,
+	/// Transactions waiting for a nonce gap to be filled.
+	pub queued: U256,
+}
+
+/// Transactions of a single sender waiting for a missing nonce.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct NonceGap {
+	/// Sender of the waiting transactions.
+	pub sender: H160,
+	/// Nonce of the transaction which has to be sent to unblock the sender.
+	#[serde(rename="nextNonce")]
+	pub next_nonce: U256,
+	/// Number of nonces missing below the highest queued one.
+	pub missing: U256,
+	/// Nonces of the waiting transactions.
+	pub queued: Vec<U256>,
+	/// Number of seconds the oldest transaction has been waiting.
+	pub waiting: u64,
+}
+
+impl From<miner::NonceGap> for NonceGap {
+	fn from(gap: miner::NonceGap) -> Self {
+		NonceGap {
+			sender: gap.sender.into(),
+			next_nonce: gap.next_nonce.into(),
+			missing: gap.missing.into(),
+			queued: gap.queued.into_iter().map(Into::into).collect(),
+			waiting: gap.waiting.as_secs(),
+		}
+	}
+}
+
+/// Short textual summary of a transaction.
+fn summary(tx: &SignedTransaction) -> String {
+	let to = match tx.action {
+		Action::Create => "contract creation".to_owned(),
+		Action::Call(ref address) => format!("0x{:?}", address),
+	};
+	format!("{}: {} wei + {} gas × {} wei", to, tx.value, tx.gas, tx.gas_price)
+}
+
+#[cfg(test)]
+mod tests {
+	use ethcore::transaction::{Transaction, Action};
+	use super::summary;
+
+	#[test]
+	fn should_summarize_transaction() {
+		let tx = Transaction {
+			nonce: 1.into(),
+			gas_price: 20.into(),
+			gas: 21000.into(),
+			action: Action::Call(5.into()),
+			value: 100.into(),
+			data: vec![],
+		}.fake_sign(1.into());
+
+		assert_eq!(summary(&tx), "0x0000000000000000000000000000000000000005: 100 wei + 21000 gas × 20 wei");
+	}
+}
@@ -0,0 +1,107 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity. If not, see <http://www.gnu.org/licenses/>.
+
+//! Unsigned transaction exported for offline signing.
+
+use util::Address;
+use ethcore::transaction::{Action, Transaction as EthTransaction};
+use v1::types::{Bytes, H160, H256, U256};
+
+/// Transaction with all fields filled in, ready to be signed elsewhere.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UnsignedTransaction {
+	/// Expected sender
+	pub from: H160,
+	/// Recipient
+	pub to: Option<H160>,
+	/// Nonce
+	pub nonce: U256,
+	/// Gas Price
+	#[serde(rename="gasPrice")]
+	pub gas_price: U256,
+	/// Gas
+	pub gas: U256,
+	/// Transfered value
+	pub value: U256,
+	/// Data
+	pub data: Bytes,
+	/// Network id used for replay protection (EIP155)
+	#[serde(rename="networkId")]
+	pub network_id: Option<u8>,
+	/// Hash which has to be signed
+	pub hash: H256,
+}
+
+impl UnsignedTransaction {
+	/// Creates new `UnsignedTransaction` to be signed by `from`.
+	pub fn new(from: Address, t: EthTransaction, network_id: Option<u8>) -> Self {
+		UnsignedTransaction {
+			from: from.into(),
+			to: match t.action {
+				Action::Create => None,
+				Action::Call(ref to) => Some(to.clone().into()),
+			},
+			hash: t.hash(network_id).into(),
+			nonce: t.nonce.into(),
+			gas_price: t.gas_price.into(),
+			gas: t.gas.into(),
+			value: t.value.into(),
+			data: t.data.into(),
+			network_id: network_id,
+		}
+	}
+}
+
+impl Into<EthTransaction> for UnsignedTransaction {
+	fn into(self) -> EthTransaction {
+		EthTransaction {
+			nonce: self.nonce.into(),
+			action: self.to.map_or(Action::Create, |to| Action::Call(to.into())),
+			gas_price: self.gas_price.into(),
+			gas: self.gas.into(),
+			value: self.value.into(),
+			data: self.data.into(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use ethcore::transaction::{Action, Transaction};
+	use super::UnsignedTransaction;
+
+	#[test]
+	fn should_roundtrip_unsigned_transaction() {
+		let t = Transaction {
+			nonce: 1.into(),
+			action: Action::Call(5.into()),
+			gas_price: 2.into(),
+			gas: 21_000.into(),
+			value: 10.into(),
+			data: vec![],
+		};
+		let unsigned = UnsignedTransaction::new(6.into(), t.clone(), Some(3));
+		let serialized = serde_json::to_string(&unsigned).unwrap();
+		let deserialized: UnsignedTransaction = serde_json::from_str(&serialized).unwrap();
+		assert_eq!(deserialized, unsigned);
+		assert_eq!(deserialized.hash, t.hash(Some(3)).into());
+
+		let t2: Transaction = deserialized.into();
+		assert_eq!(t2, t);
+	}
+}
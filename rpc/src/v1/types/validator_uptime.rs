@@ -0,0 +1,58 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity. If not, see <http://www.gnu.org/licenses/>.
+
+//! Validator liveness statistics.
+
+use v1::types::H160;
+use ethcore::validator_uptime::ValidatorUptime as EthcoreValidatorUptime;
+
+/// Slots a validator was assigned within the recent tracking window and how many it missed.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ValidatorUptime {
+	/// Validator address.
+	pub address: H160,
+	/// Number of slots assigned to the validator.
+	pub slots: u64,
+	/// Number of those slots for which no block was produced.
+	pub missed: u64,
+}
+
+impl From<EthcoreValidatorUptime> for ValidatorUptime {
+	fn from(u: EthcoreValidatorUptime) -> Self {
+		ValidatorUptime {
+			address: u.address.into(),
+			slots: u.slots,
+			missed: u.missed,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use v1::types::H160;
+	use super::ValidatorUptime;
+
+	#[test]
+	fn should_serialize_validator_uptime() {
+		let uptime = ValidatorUptime {
+			address: H160::from(5),
+			slots: 10,
+			missed: 2,
+		};
+		assert_eq!(serde_json::to_string(&uptime).unwrap(), r#"{"address":"0x0000000000000000000000000000000000000005","slots":10,"missed":2}"#);
+	}
+}
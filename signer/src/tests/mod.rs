@@ -43,10 +43,14 @@ impl DerefMut for GuardedAuthCodes {
 }
 
 pub fn serve() -> (Server, usize, GuardedAuthCodes) {
+	serve_with(|builder| builder)
+}
+
+pub fn serve_with<F>(configure: F) -> (Server, usize, GuardedAuthCodes) where F: FnOnce(ServerBuilder) -> ServerBuilder {
 	let mut path = RandomTempPath::new();
 	path.panic_on_drop_failure = false;
 	let queue = Arc::new(ConfirmationsQueue::default());
-	let builder = ServerBuilder::new(queue, path.to_path_buf());
+	let builder = configure(ServerBuilder::new(queue, path.to_path_buf()));
 	let port = 35000 + rand::random::<usize>() % 10000;
 	let res = builder.start(format!("127.0.0.1:{}", port).parse().unwrap()).unwrap();
 
@@ -247,3 +251,44 @@ fn should_allow_initial_connection_but_only_once() {
 	http_client::assert_security_headers_present(&response2.headers, None);
 }
 
+#[test]
+fn should_reject_disallowed_origin_without_ui() {
+	// given
+	let (server, port, _) = serve_with(|builder| builder.allowed_origins(vec!["http://*.ethcore.io".into()]).serve_ui(false));
+
+	// when
+	let response = request(server,
+		&format!("\
+			GET / HTTP/1.1\r\n\
+			Host: 127.0.0.1:{}\r\n\
+			Origin: http://evil.io\r\n\
+			Connection: close\r\n\
+			\r\n\
+			{{}}
+		", port)
+	);
+
+	// then
+	assert_eq!(response.status, "HTTP/1.1 403 FORBIDDEN".to_owned());
+}
+
+#[test]
+fn should_not_serve_ui_when_disabled() {
+	// given
+	let (server, port, _) = serve_with(|builder| builder.allowed_origins(vec!["http://*.ethcore.io".into()]).serve_ui(false));
+
+	// when
+	let response = request(server,
+		&format!("\
+			GET / HTTP/1.1\r\n\
+			Host: 127.0.0.1:{}\r\n\
+			Origin: http://wallet.ethcore.io\r\n\
+			Connection: close\r\n\
+			\r\n\
+			{{}}
+		", port)
+	);
+
+	// then
+	assert_eq!(response.status, "HTTP/1.1 404 NOT FOUND".to_owned());
+}
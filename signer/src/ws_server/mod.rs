@@ -30,6 +30,8 @@ use rpc::{Extendable, ConfirmationsQueue};
 
 mod session;
 
+pub use self::session::Options;
+
 /// Signer startup error
 #[derive(Debug)]
 pub enum ServerError {
@@ -53,7 +55,7 @@ pub struct ServerBuilder {
 	queue: Arc<ConfirmationsQueue>,
 	handler: Arc<IoHandler>,
 	authcodes_path: PathBuf,
-	skip_origin_validation: bool,
+	options: Options,
 }
 
 impl Extendable for ServerBuilder {
@@ -69,21 +71,41 @@ impl ServerBuilder {
 			queue: queue,
 			handler: Arc::new(IoHandler::new()),
 			authcodes_path: authcodes_path,
-			skip_origin_validation: false,
+			options: Options::default(),
 		}
 	}
 
 	/// If set to `true` server will not verify Origin of incoming requests.
 	/// Not recommended. Use only for development.
 	pub fn skip_origin_validation(mut self, skip: bool) -> Self {
-		self.skip_origin_validation = skip;
+		self.options.skip_origin_validation = skip;
+		self
+	}
+
+	/// Accept connections only from given origins (patterns may contain `*` wildcards)
+	/// or from clients which don't send an `Origin` header at all.
+	/// Connections still have to be authorized with a token.
+	pub fn allowed_origins(mut self, origins: Vec<String>) -> Self {
+		self.options.allowed_origins = Some(origins);
+		self
+	}
+
+	/// If set to `false` only `WebSocket` connections are accepted and Trusted Signer UI is not served.
+	pub fn serve_ui(mut self, serve: bool) -> Self {
+		self.options.serve_ui = serve;
+		self
+	}
+
+	/// Limit the number of requests awaiting response on a single connection.
+	pub fn max_pending_requests(mut self, max: usize) -> Self {
+		self.options.max_pending_requests = Some(max);
 		self
 	}
 
 	/// Starts a new `WebSocket` server in separate thread.
 	/// Returns a `Server` handle which closes the server when droped.
 	pub fn start(self, addr: SocketAddr) -> Result<Server, ServerError> {
-		Server::start(addr, self.handler, self.queue, self.authcodes_path, self.skip_origin_validation)
+		Server::start(addr, self.handler, self.queue, self.authcodes_path, self.options)
 	}
 }
 
@@ -104,7 +126,7 @@ impl Server {
 
 	/// Starts a new `WebSocket` server in separate thread.
 	/// Returns a `Server` handle which closes the server when droped.
-	fn start(addr: SocketAddr, handler: Arc<IoHandler>, queue: Arc<ConfirmationsQueue>, authcodes_path: PathBuf, skip_origin_validation: bool) -> Result<Server, ServerError> {
+	fn start(addr: SocketAddr, handler: Arc<IoHandler>, queue: Arc<ConfirmationsQueue>, authcodes_path: PathBuf, options: Options) -> Result<Server, ServerError> {
 		let config = {
 			let mut config = ws::Settings::default();
 			// accept only handshakes beginning with GET
@@ -117,7 +139,7 @@ impl Server {
 		// Create WebSocket
 		let origin = format!("{}", addr);
 		let ws = try!(ws::Builder::new().with_settings(config).build(
-			session::Factory::new(handler, origin, authcodes_path, skip_origin_validation)
+			session::Factory::new(handler, origin, authcodes_path, options)
 		));
 
 		let panic_handler = PanicHandler::new_in_arc();
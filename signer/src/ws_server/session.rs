@@ -20,8 +20,10 @@ use ws;
 use authcode_store::AuthCodes;
 use std::path::{PathBuf, Path};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::str::FromStr;
 use jsonrpc_core::IoHandler;
+use rpc::v1::{access_policy, request_log};
 use util::{H256, Mutex, version};
 
 #[cfg(feature = "parity-ui")]
@@ -82,6 +84,19 @@ fn origin_is_allowed(self_origin: &str, header: Option<&[u8]>) -> bool {
 	}
 }
 
+/// Origins allowed to connect to a server with a custom origin list.
+/// Clients which are not browsers don't send an `Origin` and rely on the authorization token only.
+fn origin_matches(self_origin: &str, allowed: &[String], header: Option<&[u8]>) -> bool {
+	let origin = match header.map(|h| String::from_utf8(h.to_owned())) {
+		None => return true,
+		Some(Ok(origin)) => origin,
+		Some(Err(_)) => return false,
+	};
+
+	origin == format!("http://{}", self_origin) ||
+		allowed.iter().any(|pattern| access_policy::matches(pattern, &origin))
+}
+
 fn auth_is_valid(codes_path: &Path, protocols: ws::Result<Vec<&str>>) -> bool {
 	match protocols {
 		Ok(ref protocols) if protocols.len() == 1 => {
@@ -129,13 +144,41 @@ fn add_headers(mut response: ws::Response, mime: &str) -> ws::Response {
 	response
 }
 
+const TOO_MANY_REQUESTS: &'static str = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Too many pending requests on this connection."},"id":null}"#;
+
+/// Connection options.
+#[derive(Debug, Clone)]
+pub struct Options {
+	/// Skip validation of `Origin` and `Host` headers.
+	pub skip_origin_validation: bool,
+	/// Origin patterns allowed instead of the signer ones; `None` for Trusted Signer rules.
+	pub allowed_origins: Option<Vec<String>>,
+	/// Serve Trusted Signer UI files.
+	pub serve_ui: bool,
+	/// Maximal number of requests awaiting response on a single connection.
+	pub max_pending_requests: Option<usize>,
+}
+
+impl Default for Options {
+	fn default() -> Self {
+		Options {
+			skip_origin_validation: false,
+			allowed_origins: None,
+			serve_ui: true,
+			max_pending_requests: None,
+		}
+	}
+}
+
 pub struct Session {
 	out: Arc<Mutex<ws::Sender>>,
-	skip_origin_validation: bool,
+	options: Options,
 	self_origin: String,
 	authcodes_path: PathBuf,
 	handler: Arc<IoHandler>,
 	file_handler: Arc<ui::Handler>,
+	pending: Arc<AtomicUsize>,
+	origin: Option<String>,
 }
 
 impl ws::Handler for Session {
@@ -150,13 +193,19 @@ impl ws::Handler for Session {
 		// Styles file is allowed for error pages to display nicely.
 		let is_styles_file = resource == "/styles.css";
 
+		self.origin = req.header("origin").or_else(|| req.header("Origin"))
+			.and_then(|x| String::from_utf8(x.clone()).ok());
+
 		// Check request origin and host header.
-		if !self.skip_origin_validation {
+		if !self.options.skip_origin_validation {
 			let origin = req.header("origin").or_else(|| req.header("Origin")).map(|x| &x[..]);
 			let host = req.header("host").or_else(|| req.header("Host")).map(|x| &x[..]);
 
-			let is_valid = origin_is_allowed(&self.self_origin, origin) || (origin.is_none() && origin_is_allowed(&self.self_origin, host));
-			let is_valid = is_styles_file || is_valid;
+			let is_valid = match self.options.allowed_origins {
+				Some(ref allowed) => origin_matches(&self.self_origin, allowed, origin),
+				None => origin_is_allowed(&self.self_origin, origin) || (origin.is_none() && origin_is_allowed(&self.self_origin, host)),
+			};
+			let is_valid = (self.options.serve_ui && is_styles_file) || is_valid;
 
 			if !is_valid {
 				warn!(target: "signer", "Blocked connection to Signer API from untrusted origin.");
@@ -195,6 +244,10 @@ impl ws::Handler for Session {
 			});
 		}
 
+		if !self.options.serve_ui {
+			return Ok(error(ErrorType::NotFound, "Not found", "Only WebSocket connections are accepted.", None));
+		}
+
 		debug!(target: "signer", "Requesting resource: {:?}", resource);
 		// Otherwise try to serve a page.
 		Ok(self.file_handler.handle(resource)
@@ -208,9 +261,21 @@ impl ws::Handler for Session {
 
 	fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
 		let req = try!(msg.as_text());
-		if let Some(async) = self.handler.handle_request(req) {
+
+		if let Some(max) = self.options.max_pending_requests {
+			if self.pending.load(Ordering::SeqCst) >= max {
+				debug!(target: "signer", "Rejecting request, {} requests pending.", max);
+				return self.out.lock().send(TOO_MANY_REQUESTS);
+			}
+		}
+
+		let handler = &self.handler;
+		if let Some(async) = request_log::with_origin(self.origin.clone(), || handler.handle_request(req)) {
 			let out = self.out.clone();
+			let pending = self.pending.clone();
+			pending.fetch_add(1, Ordering::SeqCst);
 			async.on_result(move |result| {
+				pending.fetch_sub(1, Ordering::SeqCst);
 				let res = out.lock().send(result);
 				if let Err(e) = res {
 					warn!(target: "signer", "Error while sending response: {:?}", e);
@@ -223,17 +288,17 @@ impl ws::Handler for Session {
 
 pub struct Factory {
 	handler: Arc<IoHandler>,
-	skip_origin_validation: bool,
+	options: Options,
 	self_origin: String,
 	authcodes_path: PathBuf,
 	file_handler: Arc<ui::Handler>,
 }
 
 impl Factory {
-	pub fn new(handler: Arc<IoHandler>, self_origin: String, authcodes_path: PathBuf, skip_origin_validation: bool) -> Self {
+	pub fn new(handler: Arc<IoHandler>, self_origin: String, authcodes_path: PathBuf, options: Options) -> Self {
 		Factory {
 			handler: handler,
-			skip_origin_validation: skip_origin_validation,
+			options: options,
 			self_origin: self_origin,
 			authcodes_path: authcodes_path,
 			file_handler: Arc::new(ui::Handler::default()),
@@ -248,10 +313,12 @@ impl ws::Factory for Factory {
 		Session {
 			out: Arc::new(Mutex::new(sender)),
 			handler: self.handler.clone(),
-			skip_origin_validation: self.skip_origin_validation,
+			options: self.options.clone(),
 			self_origin: self.self_origin.clone(),
 			authcodes_path: self.authcodes_path.clone(),
 			file_handler: self.file_handler.clone(),
+			pending: Arc::new(AtomicUsize::new(0)),
+			origin: None,
 		}
 	}
 }
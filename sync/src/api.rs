@@ -17,14 +17,17 @@
 use std::sync::Arc;
 use std::collections::{HashMap, BTreeMap};
 use std::io;
+use std::path::Path;
 use util::Bytes;
 use network::{NetworkProtocolHandler, NetworkService, NetworkContext, PeerId, ProtocolId,
 	NetworkConfiguration as BasicNetworkConfiguration, NonReservedPeerMode, NetworkError,
-	AllowIP as NetworkAllowIP};
+	AllowIP as NetworkAllowIP, ConnectionFilter, Proxy, node_key};
 use util::{U256, H256, H512};
 use io::{TimerToken};
 use ethcore::client::{BlockChainClient, ChainNotify};
+use ethcore::engines::Engine;
 use ethcore::snapshot::SnapshotService;
+use ethcore::private_transactions::Provider as PrivateTxProvider;
 use ethcore::header::BlockNumber;
 use sync_io::NetSyncIo;
 use chain::{ChainSync, SyncStatus};
@@ -33,6 +36,8 @@ use ipc::{BinaryConvertable, BinaryConvertError, IpcConfig};
 use std::str::FromStr;
 use parking_lot::RwLock;
 use chain::{ETH_PACKET_COUNT, SNAPSHOT_SYNC_PACKET_COUNT};
+use light::net::{self as light_net, LightProtocol, Params as LightParams, FlowParams, CostTable, NetworkId as LightNetworkId};
+use light::provider::{Provider as LightProvider, Composite as LightComposite};
 
 pub const WARP_SYNC_PROTOCOL_ID: ProtocolId = *b"par";
 
@@ -51,6 +56,8 @@ pub struct SyncConfig {
 	pub fork_block: Option<(BlockNumber, H256)>,
 	/// Enable snapshot sync
 	pub warp_sync: bool,
+	/// Delay local transactions and send them to a few random peers first.
+	pub tx_diffusion: bool,
 }
 
 impl Default for SyncConfig {
@@ -62,11 +69,24 @@ impl Default for SyncConfig {
 			subprotocol_name: *b"eth",
 			fork_block: None,
 			warp_sync: false,
+			tx_diffusion: false,
 		}
 	}
 }
 
 binary_fixed_size!(SyncConfig);
+
+/// Serving of light clients over the les protocol.
+pub struct LightServing {
+	/// Source of the data light clients request.
+	pub provider: Arc<LightProvider>,
+	/// Consensus engine of the chain, checks seals of headers announced by light peers.
+	pub engine: Arc<Engine>,
+	/// Request buffer limit offered to light clients.
+	pub buffer_limit: u64,
+	/// Request buffer recharge per second offered to light clients.
+	pub buffer_recharge: u64,
+}
 binary_fixed_size!(SyncStatus);
 
 /// Current sync status
@@ -110,6 +130,23 @@ pub struct PeerInfo {
 	pub eth_head: H256,
 	/// Peer total difficulty if known
 	pub eth_difficulty: Option<U256>,
+	/// How early the peer delivers new blocks and transactions
+	pub propagation: PeerPropagationStats,
+}
+
+/// Arrival statistics of new blocks and transactions from a single peer
+#[derive(Debug, Default, PartialEq, Clone, Binary)]
+pub struct PeerPropagationStats {
+	/// Number of blocks announced by this peer before any other peer
+	pub blocks_first: u64,
+	/// Number of blocks announced by this peer
+	pub blocks_total: u64,
+	/// Average delay (in ms) behind the first peer for blocks not announced first
+	pub average_block_delay_ms: u64,
+	/// Number of transactions sent by this peer before any other peer
+	pub transactions_first: u64,
+	/// Number of transactions sent by this peer
+	pub transactions_total: u64,
 }
 
 /// Ethereum network protocol handler
@@ -120,15 +157,37 @@ pub struct EthSync {
 	handler: Arc<SyncProtocolHandler>,
 	/// The main subprotocol name
 	subprotocol_name: [u8; 3],
+	/// Light protocol handler, registered alongside the eth and warp protocols if light clients are served.
+	light_proto: Option<Arc<LightProtocol>>,
 	/// Configuration
 	config: NetworkConfiguration,
 }
 
 impl EthSync {
-	/// Creates and register protocol with the network service
-	pub fn new(config: SyncConfig, chain: Arc<BlockChainClient>, snapshot_service: Arc<SnapshotService>, network_config: NetworkConfiguration) -> Result<Arc<EthSync>, NetworkError> {
-		let chain_sync = ChainSync::new(config, &*chain);
-		let service = try!(NetworkService::new(try!(network_config.clone().into_basic())));
+	/// Creates and register protocol with the network service.
+	/// Sessions are only established with nodes allowed by `connection_filter`, if given.
+	/// Light clients are served as configured by `light_serving`, if given.
+	/// Private transactions are verified and distributed by `private_tx`, if given.
+	pub fn new(config: SyncConfig, chain: Arc<BlockChainClient>, snapshot_service: Arc<SnapshotService>, network_config: NetworkConfiguration, connection_filter: Option<Arc<ConnectionFilter>>, light_serving: Option<LightServing>, private_tx: Option<Arc<PrivateTxProvider>>) -> Result<Arc<EthSync>, NetworkError> {
+		let mut chain_sync = ChainSync::new(config, &*chain);
+		if let Some(provider) = private_tx {
+			chain_sync.set_private_tx_provider(provider);
+		}
+		let light_proto = light_serving.and_then(|serving| match LightNetworkId::from_raw(config.network_id as u32) {
+			Some(network_id) => Some(Arc::new(LightProtocol::new(Box::new(LightComposite::new(serving.provider)), LightParams {
+				network_id: network_id,
+				flow_params: FlowParams::new(serving.buffer_limit.into(), CostTable::default(), serving.buffer_recharge.into()),
+				capabilities: Default::default(),
+				checkpoints: chain.chain_spec_info().checkpoints,
+				limits: Default::default(),
+				engine: Some(serving.engine),
+			}))),
+			None => {
+				warn!("Light clients are not supported on network {}.", config.network_id);
+				None
+			},
+		});
+		let service = try!(NetworkService::new(try!(network_config.clone().into_basic()), connection_filter));
 		let sync = Arc::new(EthSync{
 			network: service,
 			handler: Arc::new(SyncProtocolHandler {
@@ -138,6 +197,7 @@ impl EthSync {
 				overlay: RwLock::new(HashMap::new()),
 			}),
 			subprotocol_name: config.subprotocol_name,
+			light_proto: light_proto,
 			config: network_config,
 		});
 
@@ -149,7 +209,11 @@ impl EthSync {
 impl SyncProvider for EthSync {
 	/// Get sync status
 	fn status(&self) -> SyncStatus {
-		self.handler.sync.write().status()
+		let mut status = self.handler.sync.write().status();
+		if let Some(ref light_proto) = self.light_proto {
+			status.mem_used += light_proto.mem_used();
+		}
+		status
 	}
 
 	/// Get sync peers
@@ -213,7 +277,9 @@ impl NetworkProtocolHandler for SyncProtocolHandler {
 	fn timeout(&self, io: &NetworkContext, _timer: TimerToken) {
 		self.sync.write().maintain_peers(&mut NetSyncIo::new(io, &*self.chain, &*self.snapshot_service, &self.overlay));
 		self.sync.write().maintain_sync(&mut NetSyncIo::new(io, &*self.chain, &*self.snapshot_service, &self.overlay));
+		self.sync.write().propagate_pending_head(&mut NetSyncIo::new(io, &*self.chain, &*self.snapshot_service, &self.overlay));
 		self.sync.write().propagate_new_transactions(&mut NetSyncIo::new(io, &*self.chain, &*self.snapshot_service, &self.overlay));
+		self.sync.write().propagate_private_transactions(&mut NetSyncIo::new(io, &*self.chain, &*self.snapshot_service, &self.overlay));
 	}
 }
 
@@ -247,8 +313,13 @@ impl ChainNotify for EthSync {
 		self.network.register_protocol(self.handler.clone(), self.subprotocol_name, ETH_PACKET_COUNT, &[62u8, 63u8])
 			.unwrap_or_else(|e| warn!("Error registering ethereum protocol: {:?}", e));
 		// register the warp sync subprotocol
-		self.network.register_protocol(self.handler.clone(), WARP_SYNC_PROTOCOL_ID, SNAPSHOT_SYNC_PACKET_COUNT, &[1u8])
+		self.network.register_protocol(self.handler.clone(), WARP_SYNC_PROTOCOL_ID, SNAPSHOT_SYNC_PACKET_COUNT, &[1u8, 2u8])
 			.unwrap_or_else(|e| warn!("Error registering snapshot sync protocol: {:?}", e));
+		// register the light protocol if light clients are served
+		if let Some(ref light_proto) = self.light_proto {
+			self.network.register_protocol(light_proto.clone(), light_net::PROTOCOL_ID, light_net::PACKET_COUNT, light_net::PROTOCOL_VERSIONS)
+				.unwrap_or_else(|e| warn!("Error registering light protocol: {:?}", e));
+		}
 	}
 
 	fn stop(&self) {
@@ -270,12 +341,17 @@ pub trait ManageNetwork : Send + Sync {
 	fn remove_reserved_peer(&self, peer: String) -> Result<(), String>;
 	/// Add reserved peer
 	fn add_reserved_peer(&self, peer: String) -> Result<(), String>;
+	/// Add a boot node in addition to the ones from the chain spec
+	fn add_boot_node(&self, node: String) -> Result<(), String>;
 	/// Start network
 	fn start_network(&self);
 	/// Stop network
 	fn stop_network(&self);
 	/// Query the current configuration of the network
 	fn network_config(&self) -> NetworkConfiguration;
+	/// Replace the node key, effective after a restart, answering discovery for the current
+	/// one during `grace_period` seconds. Returns the new node id.
+	fn rotate_node_key(&self, grace_period: u64) -> Result<H512, String>;
 }
 
 
@@ -297,6 +373,10 @@ impl ManageNetwork for EthSync {
 		self.network.add_reserved_peer(&peer).map_err(|e| format!("{:?}", e))
 	}
 
+	fn add_boot_node(&self, node: String) -> Result<(), String> {
+		self.network.add_boot_node(&node).map_err(|e| format!("{:?}", e))
+	}
+
 	fn start_network(&self) {
 		self.start();
 	}
@@ -312,6 +392,16 @@ impl ManageNetwork for EthSync {
 	fn network_config(&self) -> NetworkConfiguration {
 		NetworkConfiguration::from(self.network.config().clone())
 	}
+
+	fn rotate_node_key(&self, grace_period: u64) -> Result<H512, String> {
+		let config = self.network.config();
+		if config.use_secret.is_some() {
+			return Err("The node key is given in the configuration and can't be rotated".into());
+		}
+		let path = try!(config.config_path.clone().ok_or_else(|| "Network configuration directory is not set".to_owned()));
+		let key = try!(node_key::rotate_random(Path::new(&path), grace_period).map_err(|e| format!("Unable to rotate node key: {}", e)));
+		Ok(key.public().clone())
+	}
 }
 
 /// IP fiter
@@ -346,6 +436,10 @@ pub struct NetworkConfiguration {
 	pub net_config_path: Option<String>,
 	/// IP address to listen for incoming connections. Listen to all connections by default
 	pub listen_address: Option<String>,
+	/// Additional addresses to listen for incoming connections on.
+	pub extra_listen_addresses: Vec<String>,
+	/// Prefer IPv6 addresses when selecting the public address to advertise.
+	pub prefer_ipv6: bool,
 	/// IP address to advertise. Detected automatically if none.
 	pub public_address: Option<String>,
 	/// Port for UDP connections, same as TCP by default
@@ -372,6 +466,14 @@ pub struct NetworkConfiguration {
 	pub allow_non_reserved: bool,
 	/// IP Filtering
 	pub allow_ips: AllowIP,
+	/// Re-establish sessions older than this many seconds to renew their keys.
+	pub session_rekey_interval: Option<u64>,
+	/// Proxy for outbound connections, `socks5://IP:PORT` or `http://IP:PORT`.
+	pub proxy: Option<String>,
+	/// Maximum number of peers from the same IP address.
+	pub max_peers_per_ip: Option<u32>,
+	/// Maximum number of peers from the same /24 (IPv4) or /64 (IPv6) subnet.
+	pub max_peers_per_subnet: Option<u32>,
 }
 
 impl NetworkConfiguration {
@@ -390,15 +492,27 @@ impl NetworkConfiguration {
 		if let Some(ref addr) = self.public_address {
 			try!(SocketAddr::from_str(&addr));
 		}
+		for addr in &self.extra_listen_addresses {
+			try!(SocketAddr::from_str(addr));
+		}
+		if let Some(ref proxy) = self.proxy {
+			try!(Proxy::from_str(proxy));
+		}
 		Ok(())
 	}
 
 	pub fn into_basic(self) -> Result<BasicNetworkConfiguration, AddrParseError> {
+		let mut extra_listen_addresses = Vec::new();
+		for addr in &self.extra_listen_addresses {
+			extra_listen_addresses.push(try!(SocketAddr::from_str(addr)));
+		}
 
 		Ok(BasicNetworkConfiguration {
 			config_path: self.config_path,
 			net_config_path: self.net_config_path,
 			listen_address: match self.listen_address { None => None, Some(addr) => Some(try!(SocketAddr::from_str(&addr))) },
+			extra_listen_addresses: extra_listen_addresses,
+			prefer_ipv6: self.prefer_ipv6,
 			public_address:  match self.public_address { None => None, Some(addr) => Some(try!(SocketAddr::from_str(&addr))) },
 			udp_port: self.udp_port,
 			nat_enabled: self.nat_enabled,
@@ -416,6 +530,10 @@ impl NetworkConfiguration {
 				AllowIP::Public => NetworkAllowIP::Public,
 			},
 			non_reserved_mode: if self.allow_non_reserved { NonReservedPeerMode::Accept } else { NonReservedPeerMode::Deny },
+			session_rekey_interval: self.session_rekey_interval,
+			proxy: match self.proxy { None => None, Some(proxy) => Some(try!(Proxy::from_str(&proxy))) },
+			max_peers_per_ip: self.max_peers_per_ip,
+			max_peers_per_subnet: self.max_peers_per_subnet,
 		})
 	}
 }
@@ -426,6 +544,8 @@ impl From<BasicNetworkConfiguration> for NetworkConfiguration {
 			config_path: other.config_path,
 			net_config_path: other.net_config_path,
 			listen_address: other.listen_address.and_then(|addr| Some(format!("{}", addr))),
+			extra_listen_addresses: other.extra_listen_addresses.iter().map(|addr| format!("{}", addr)).collect(),
+			prefer_ipv6: other.prefer_ipv6,
 			public_address: other.public_address.and_then(|addr| Some(format!("{}", addr))),
 			udp_port: other.udp_port,
 			nat_enabled: other.nat_enabled,
@@ -443,6 +563,10 @@ impl From<BasicNetworkConfiguration> for NetworkConfiguration {
 				NetworkAllowIP::Public => AllowIP::Public,
 			},
 			allow_non_reserved: match other.non_reserved_mode { NonReservedPeerMode::Accept => true, _ => false } ,
+			session_rekey_interval: other.session_rekey_interval,
+			proxy: other.proxy.map(|proxy| format!("{}", proxy)),
+			max_peers_per_ip: other.max_peers_per_ip,
+			max_peers_per_subnet: other.max_peers_per_subnet,
 		}
 	}
 }
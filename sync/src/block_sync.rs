@@ -32,9 +32,13 @@ const MAX_HEADERS_TO_REQUEST: usize = 128;
 const MAX_BODIES_TO_REQUEST: usize = 64;
 const MAX_RECEPITS_TO_REQUEST: usize = 128;
 const SUBCHAIN_SIZE: u64 = 256;
+// Distance between subchain heads. Subchains overlap by one header so that they can be linked.
+const SUBCHAIN_SPACING: u64 = MAX_HEADERS_TO_REQUEST as u64 - 1;
 const MAX_ROUND_PARENTS: usize = 32;
 const MAX_PARALLEL_SUBCHAIN_DOWNLOAD: usize = 5;
 const MAX_REORG_BLOCKS: u64 = 20;
+// Minimal part of the full request size given to the slowest peers
+const MIN_REQUEST_SHARE: f64 = 0.125;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 /// Downloader state
@@ -101,6 +105,8 @@ pub struct BlockDownloader {
 	download_receipts: bool,
 	/// Sync up to the block with this hash.
 	target_hash: Option<H256>,
+	/// Whether the next data request should be for receipts, so that bodies and receipts are downloaded side by side.
+	receipts_turn: bool,
 }
 
 impl BlockDownloader {
@@ -116,6 +122,7 @@ impl BlockDownloader {
 			round_parents: VecDeque::new(),
 			download_receipts: sync_receipts,
 			target_hash: None,
+			receipts_turn: false,
 		}
 	}
 
@@ -197,6 +204,7 @@ impl BlockDownloader {
 
 		let mut headers = Vec::new();
 		let mut hashes = Vec::new();
+		let mut numbers = Vec::new();
 		let mut valid_response = item_count == 0; //empty response is valid
 		let mut any_known = false;
 		for i in 0..item_count {
@@ -233,6 +241,7 @@ impl BlockDownloader {
 					}
 					headers.push(hdr.as_raw().to_vec());
 					hashes.push(hash);
+					numbers.push(number);
 				},
 				BlockStatus::Bad => {
 					return Err(BlockDownloaderImportError::Invalid);
@@ -240,6 +249,7 @@ impl BlockDownloader {
 				BlockStatus::Unknown => {
 					headers.push(hdr.as_raw().to_vec());
 					hashes.push(hash);
+					numbers.push(number);
 				}
 			}
 		}
@@ -253,7 +263,10 @@ impl BlockDownloader {
 		match self.state {
 			State::ChainHead => {
 				if !headers.is_empty() {
-					// TODO: validate heads better. E.g. check that there is enough distance between blocks.
+					if !is_valid_skeleton(&numbers, SUBCHAIN_SPACING) {
+						trace!(target: "sync", "Invalid subchain heads: {:?}", numbers);
+						return Err(BlockDownloaderImportError::Invalid);
+					}
 					trace!(target: "sync", "Received {} subchain heads, proceeding to download", headers.len());
 					self.blocks.reset_to(hashes);
 					self.state = State::Blocks;
@@ -373,12 +386,13 @@ impl BlockDownloader {
 	}
 
 	/// Find some headers or blocks to download for a peer.
-	pub fn request_blocks(&mut self, io: &mut SyncIo, num_active_peers: usize) -> Option<BlockRequest> {
+	/// `share` is the part of the full request size the peer is trusted with, based on its download rate.
+	pub fn request_blocks(&mut self, io: &mut SyncIo, num_active_peers: usize, share: f64) -> Option<BlockRequest> {
 		match self.state {
 			State::Idle => {
 				self.start_sync_round(io);
 				if self.state == State::ChainHead {
-					return self.request_blocks(io, num_active_peers);
+					return self.request_blocks(io, num_active_peers, share);
 				}
 			},
 			State::ChainHead => {
@@ -390,25 +404,28 @@ impl BlockDownloader {
 					return Some(BlockRequest::Headers {
 						start: self.last_imported_hash.clone(),
 						count: SUBCHAIN_SIZE,
-						skip: (MAX_HEADERS_TO_REQUEST - 2) as u64,
+						skip: SUBCHAIN_SPACING - 1,
 					});
 				}
 			},
 			State::Blocks => {
-				// check to see if we need to download any block bodies first
-				let needed_bodies = self.blocks.needed_bodies(MAX_BODIES_TO_REQUEST, false);
+				// alternate between bodies and receipts so that different peers serve them in parallel
+				let receipts_first = self.download_receipts && self.receipts_turn;
+				self.receipts_turn = !self.receipts_turn;
+				if receipts_first {
+					if let Some(request) = self.request_receipts(share) {
+						return Some(request);
+					}
+				}
+				let needed_bodies = self.blocks.needed_bodies(request_size(MAX_BODIES_TO_REQUEST, share), false);
 				if !needed_bodies.is_empty() {
 					return Some(BlockRequest::Bodies {
 						hashes: needed_bodies,
 					});
 				}
-
-				if self.download_receipts {
-					let needed_receipts = self.blocks.needed_receipts(MAX_RECEPITS_TO_REQUEST, false);
-					if !needed_receipts.is_empty() {
-						return Some(BlockRequest::Receipts {
-							hashes: needed_receipts,
-						});
+				if !receipts_first {
+					if let Some(request) = self.request_receipts(share) {
+						return Some(request);
 					}
 				}
 
@@ -426,6 +443,20 @@ impl BlockDownloader {
 		None
 	}
 
+	fn request_receipts(&mut self, share: f64) -> Option<BlockRequest> {
+		if !self.download_receipts {
+			return None;
+		}
+		let needed_receipts = self.blocks.needed_receipts(request_size(MAX_RECEPITS_TO_REQUEST, share), false);
+		if needed_receipts.is_empty() {
+			None
+		} else {
+			Some(BlockRequest::Receipts {
+				hashes: needed_receipts,
+			})
+		}
+	}
+
 	/// Checks if there are blocks fully downloaded that can be imported into the blockchain and does the import.
 	pub fn collect_blocks(&mut self, io: &mut SyncIo, allow_out_of_order: bool) -> Result<(), BlockDownloaderImportError> {
 		let mut bad = false;
@@ -509,4 +540,38 @@ impl BlockDownloader {
 	}
 }
 
-//TODO: module tests
+/// Number of items to request out of `max` for a peer trusted with `share` of a full request.
+fn request_size(max: usize, share: f64) -> usize {
+	let share = if share < MIN_REQUEST_SHARE { MIN_REQUEST_SHARE } else if share > 1.0 { 1.0 } else { share };
+	::std::cmp::max((max as f64 * share) as usize, 1)
+}
+
+/// Check that subchain heads ascend in steps of `spacing`. Heads we already have may be left out.
+fn is_valid_skeleton(numbers: &[BlockNumber], spacing: BlockNumber) -> bool {
+	numbers.windows(2).all(|w| w[1] > w[0] && (w[1] - w[0]) % spacing == 0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{request_size, is_valid_skeleton, MAX_BODIES_TO_REQUEST};
+
+	#[test]
+	fn validates_skeleton_spacing() {
+		assert!(is_valid_skeleton(&[10], 127));
+		assert!(is_valid_skeleton(&[10, 137, 264], 127));
+		// a known head in between was skipped
+		assert!(is_valid_skeleton(&[10, 264], 127));
+		assert!(!is_valid_skeleton(&[10, 136, 264], 127));
+		assert!(!is_valid_skeleton(&[137, 10], 127));
+		assert!(!is_valid_skeleton(&[10, 10], 127));
+	}
+
+	#[test]
+	fn scales_request_size_with_share() {
+		assert_eq!(request_size(MAX_BODIES_TO_REQUEST, 1.0), MAX_BODIES_TO_REQUEST);
+		assert_eq!(request_size(MAX_BODIES_TO_REQUEST, 2.0), MAX_BODIES_TO_REQUEST);
+		assert_eq!(request_size(MAX_BODIES_TO_REQUEST, 0.5), MAX_BODIES_TO_REQUEST / 2);
+		assert_eq!(request_size(MAX_BODIES_TO_REQUEST, 0.0), MAX_BODIES_TO_REQUEST / 8);
+		assert_eq!(request_size(1, 0.0), 1);
+	}
+}
@@ -0,0 +1,109 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Protocol capabilities negotiated with each peer.
+//!
+//! A session may carry several subprotocols at once: `eth/62` or `eth/63`,
+//! the warp sync `par/1` protocol, which extends `eth/63` with snapshot packets
+//! (`par/2` adds private transactions),
+//! and the light `les/1` protocol, served by its own handler.
+
+use network::{PeerId, ProtocolId};
+use light::net::PROTOCOL_ID as LIGHT_PROTOCOL_ID;
+use api::WARP_SYNC_PROTOCOL_ID;
+use sync_io::SyncIo;
+
+/// Protocol version a packet was introduced in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requirement {
+	/// Given `eth` version or later. Any `par` version implies the latest `eth` packets.
+	Eth(u8),
+	/// Given `par` version or later.
+	Par(u8),
+}
+
+/// Versions of each subprotocol negotiated with a peer, `0` if not spoken.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+	/// `eth` protocol version.
+	pub eth: u8,
+	/// Warp sync protocol version.
+	pub par: u8,
+	/// Light protocol version.
+	pub les: u8,
+}
+
+impl Capabilities {
+	/// Read the versions negotiated with given peer. `eth_protocol` is the configured name of the `eth` protocol.
+	pub fn from_io(io: &SyncIo, peer: PeerId, eth_protocol: &ProtocolId) -> Self {
+		Capabilities {
+			eth: io.protocol_version(eth_protocol, peer),
+			par: io.protocol_version(&WARP_SYNC_PROTOCOL_ID, peer),
+			les: io.protocol_version(&LIGHT_PROTOCOL_ID, peer),
+		}
+	}
+
+	/// Whether the peer uses the warp sync protocol for block sync.
+	pub fn is_warp(&self) -> bool {
+		self.par != 0
+	}
+
+	/// Version announced in the status packet: the warp sync one if spoken.
+	pub fn status_version(&self) -> u8 {
+		match self.is_warp() {
+			true => self.par,
+			false => self.eth,
+		}
+	}
+
+	/// Whether packets introduced with given requirement may be exchanged with the peer.
+	pub fn allows(&self, requirement: Requirement) -> bool {
+		match requirement {
+			Requirement::Eth(version) => self.is_warp() || (self.eth != 0 && self.eth >= version),
+			Requirement::Par(version) => self.par != 0 && self.par >= version,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Capabilities, Requirement};
+
+	#[test]
+	fn eth_packets_follow_negotiated_version() {
+		let eth62 = Capabilities { eth: 62, par: 0, les: 0 };
+		assert!(eth62.allows(Requirement::Eth(62)));
+		assert!(!eth62.allows(Requirement::Eth(63)));
+		assert!(!eth62.allows(Requirement::Par(1)));
+		assert_eq!(eth62.status_version(), 62);
+
+		let eth63 = Capabilities { eth: 63, par: 0, les: 1 };
+		assert!(eth63.allows(Requirement::Eth(63)));
+		assert!(!eth63.allows(Requirement::Par(1)));
+	}
+
+	#[test]
+	fn warp_peers_speak_all_eth_packets() {
+		let par = Capabilities { eth: 62, par: 1, les: 0 };
+		assert!(par.is_warp());
+		assert!(par.allows(Requirement::Eth(63)));
+		assert!(par.allows(Requirement::Par(1)));
+		assert!(!par.allows(Requirement::Par(2)));
+		assert_eq!(par.status_version(), 1);
+
+		assert!(!Capabilities::default().allows(Requirement::Eth(62)));
+	}
+}
@@ -96,7 +96,10 @@ use ethcore::views::{HeaderView};
 use ethcore::header::{BlockNumber, Header as BlockHeader};
 use ethcore::client::{BlockChainClient, BlockStatus, BlockID, BlockChainInfo, BlockImportError};
 use ethcore::error::*;
+use ethcore::transaction::SignedTransaction;
 use ethcore::snapshot::{ManifestData, RestorationStatus};
+use ethcore::private_transactions::{Provider as PrivateTxProvider, Error as PrivateTxError};
+use ethcore::request::{self, HashOrNumber, Response};
 use sync_io::SyncIo;
 use time;
 use super::SyncConfig;
@@ -105,6 +108,12 @@ use snapshot::{Snapshot, ChunkType};
 use rand::{thread_rng, Rng};
 use api::{PeerInfo as PeerInfoDigest, WARP_SYNC_PROTOCOL_ID};
 use transactions_stats::{TransactionsStats, Stats as TransactionStats};
+use propagation_stats::{PropagationStats, PeerArrivals};
+use misbehavior::{Misbehavior, Offence};
+use fork_id::{ForkFilter, ForkId};
+use receipts_cache::ReceiptsCache;
+use lru_cache::LruCache;
+use capabilities::{Capabilities, Requirement};
 
 known_heap_size!(0, PeerInfo);
 
@@ -113,10 +122,11 @@ type PacketDecodeError = DecoderError;
 const PROTOCOL_VERSION_63: u8 = 63;
 const PROTOCOL_VERSION_62: u8 = 62;
 const PROTOCOL_VERSION_1: u8 = 1;
+const PROTOCOL_VERSION_2: u8 = 2;
 const MAX_BODIES_TO_SEND: usize = 256;
 const MAX_HEADERS_TO_SEND: usize = 512;
 const MAX_NODE_DATA_TO_SEND: usize = 1024;
-const MAX_RECEIPTS_TO_SEND: usize = 1024;
+const MAX_RECEIPTS_BYTES_TO_SEND: usize = 2 * 1024 * 1024;
 const MAX_RECEIPTS_HEADERS_TO_SEND: usize = 256;
 const MIN_PEERS_PROPAGATION: usize = 4;
 const MAX_PEERS_PROPAGATION: usize = 128;
@@ -128,6 +138,16 @@ const MAX_TRANSACTION_SIZE: usize = 300*1024;
 // Min number of blocks to be behind for a snapshot sync
 const SNAPSHOT_RESTORE_THRESHOLD: BlockNumber = 100000;
 const SNAPSHOT_MIN_PEERS: usize = 3;
+// Weight of the latest response in the peer download rate average
+const DOWNLOAD_RATE_WEIGHT: f64 = 0.3;
+// Minimum time between two head announcements sent to the same peer
+const MIN_HEAD_ANNOUNCE_INTERVAL_NS: u64 = 500_000_000;
+/// Maximal random delay before the first propagation of a local transaction.
+const MAX_TX_DIFFUSION_DELAY_MS: u64 = 5_000;
+/// Number of peers a local transaction is sent to on its first propagation.
+const TX_DIFFUSION_PEERS: usize = 2;
+/// Number of recently seen private transactions remembered to avoid relaying them twice.
+const MAX_KNOWN_PRIVATE_TRANSACTIONS: usize = 4096;
 
 const STATUS_PACKET: u8 = 0x00;
 const NEW_BLOCK_HASHES_PACKET: u8 = 0x01;
@@ -149,8 +169,52 @@ const GET_SNAPSHOT_MANIFEST_PACKET: u8 = 0x11;
 const SNAPSHOT_MANIFEST_PACKET: u8 = 0x12;
 const GET_SNAPSHOT_DATA_PACKET: u8 = 0x13;
 const SNAPSHOT_DATA_PACKET: u8 = 0x14;
+const PRIVATE_TRANSACTION_PACKET: u8 = 0x15;
+
+pub const SNAPSHOT_SYNC_PACKET_COUNT: u8 = 0x16;
+
+/// How an incoming packet is processed.
+enum Handler {
+	/// Request answered from the chain without taking the sync lock.
+	Serve(fn(&SyncIo, &UntrustedRlp, PeerId) -> RlpResponseResult),
+	/// Receipts request answered through the shared receipts cache.
+	ServeReceipts,
+	/// Packet updating the sync state.
+	Handle(fn(&mut ChainSync, &mut SyncIo, PeerId, &UntrustedRlp) -> Result<(), PacketDecodeError>),
+}
+
+/// Entry of the packet dispatch table.
+struct Packet {
+	id: PacketId,
+	name: &'static str,
+	/// Protocol version the packet was introduced in.
+	requires: Requirement,
+	handler: Handler,
+}
 
-pub const SNAPSHOT_SYNC_PACKET_COUNT: u8 = 0x15;
+/// Packets of the `eth` and `par` protocols. Packets not listed are ignored.
+static PACKETS: &'static [Packet] = &[
+	Packet { id: STATUS_PACKET, name: "Status", requires: Requirement::Eth(PROTOCOL_VERSION_62), handler: Handler::Handle(ChainSync::on_peer_status) },
+	Packet { id: NEW_BLOCK_HASHES_PACKET, name: "NewBlockHashes", requires: Requirement::Eth(PROTOCOL_VERSION_62), handler: Handler::Handle(ChainSync::on_peer_new_hashes) },
+	Packet { id: TRANSACTIONS_PACKET, name: "Transactions", requires: Requirement::Eth(PROTOCOL_VERSION_62), handler: Handler::Handle(ChainSync::on_peer_transactions) },
+	Packet { id: GET_BLOCK_HEADERS_PACKET, name: "GetBlockHeaders", requires: Requirement::Eth(PROTOCOL_VERSION_62), handler: Handler::Serve(ChainSync::return_block_headers) },
+	Packet { id: BLOCK_HEADERS_PACKET, name: "BlockHeaders", requires: Requirement::Eth(PROTOCOL_VERSION_62), handler: Handler::Handle(ChainSync::on_peer_block_headers) },
+	Packet { id: GET_BLOCK_BODIES_PACKET, name: "GetBlockBodies", requires: Requirement::Eth(PROTOCOL_VERSION_62), handler: Handler::Serve(ChainSync::return_block_bodies) },
+	Packet { id: BLOCK_BODIES_PACKET, name: "BlockBodies", requires: Requirement::Eth(PROTOCOL_VERSION_62), handler: Handler::Handle(ChainSync::on_peer_block_bodies) },
+	Packet { id: NEW_BLOCK_PACKET, name: "NewBlock", requires: Requirement::Eth(PROTOCOL_VERSION_62), handler: Handler::Handle(ChainSync::on_peer_new_block) },
+	Packet { id: GET_NODE_DATA_PACKET, name: "GetNodeData", requires: Requirement::Eth(PROTOCOL_VERSION_63), handler: Handler::Serve(ChainSync::return_node_data) },
+	Packet { id: GET_RECEIPTS_PACKET, name: "GetReceipts", requires: Requirement::Eth(PROTOCOL_VERSION_63), handler: Handler::ServeReceipts },
+	Packet { id: RECEIPTS_PACKET, name: "Receipts", requires: Requirement::Eth(PROTOCOL_VERSION_63), handler: Handler::Handle(ChainSync::on_peer_block_receipts) },
+	Packet { id: GET_SNAPSHOT_MANIFEST_PACKET, name: "GetSnapshotManifest", requires: Requirement::Par(PROTOCOL_VERSION_1), handler: Handler::Serve(ChainSync::return_snapshot_manifest) },
+	Packet { id: SNAPSHOT_MANIFEST_PACKET, name: "SnapshotManifest", requires: Requirement::Par(PROTOCOL_VERSION_1), handler: Handler::Handle(ChainSync::on_snapshot_manifest) },
+	Packet { id: GET_SNAPSHOT_DATA_PACKET, name: "GetSnapshotData", requires: Requirement::Par(PROTOCOL_VERSION_1), handler: Handler::Serve(ChainSync::return_snapshot_data) },
+	Packet { id: SNAPSHOT_DATA_PACKET, name: "SnapshotData", requires: Requirement::Par(PROTOCOL_VERSION_1), handler: Handler::Handle(ChainSync::on_snapshot_data) },
+	Packet { id: PRIVATE_TRANSACTION_PACKET, name: "PrivateTransaction", requires: Requirement::Par(PROTOCOL_VERSION_2), handler: Handler::Handle(ChainSync::on_private_transaction) },
+];
+
+fn find_packet(id: PacketId) -> Option<&'static Packet> {
+	PACKETS.iter().find(|p| p.id == id)
+}
 
 const MAX_SNAPSHOT_CHUNKS_DOWNLOAD_AHEAD: usize = 3;
 
@@ -215,6 +279,10 @@ pub struct SyncStatus {
 	pub snapshot_chunks_done: usize,
 	/// Last fully downloaded and imported ancient block number (if any).
 	pub last_imported_old_block_number: Option<BlockNumber>,
+	/// Receipt sets requested by peers and served from the cache.
+	pub receipts_cache_hits: u64,
+	/// Receipt sets requested by peers and loaded from the database.
+	pub receipts_cache_misses: u64,
 }
 
 impl SyncStatus {
@@ -225,6 +293,14 @@ impl SyncStatus {
 			|| self.state == SyncState::SnapshotWaiting
 	}
 
+	/// Percentage of requested receipt sets served from the cache, if any were requested.
+	pub fn receipts_cache_hit_rate(&self) -> Option<u64> {
+		match self.receipts_cache_hits + self.receipts_cache_misses {
+			0 => None,
+			total => Some(self.receipts_cache_hits * 100 / total),
+		}
+	}
+
 	/// Returns max no of peers to display in informants
 	pub fn current_max_peers(&self, min_peers: u32, max_peers: u32) -> u32 {
 		if self.num_peers as u32 > min_peers {
@@ -300,6 +376,14 @@ struct PeerInfo {
 	snapshot_number: Option<BlockNumber>,
 	/// Block set requested
 	block_set: Option<BlockSet>,
+	/// How early this peer delivers new blocks and transactions
+	arrivals: PeerArrivals,
+	/// Moving average of items (headers, bodies or receipts) per second received from this peer
+	download_rate: f64,
+	/// Time (ns) of the last head announcement sent to this peer
+	last_head_announce: u64,
+	/// Protocol versions negotiated with the peer, recorded at handshake
+	capabilities: Capabilities,
 }
 
 impl PeerInfo {
@@ -311,6 +395,16 @@ impl PeerInfo {
 		self.confirmation != ForkConfirmation::Unconfirmed && !self.expired
 	}
 
+	/// Update download rate with a response of `items` received at `now` (ns) for the last request.
+	fn record_response(&mut self, items: usize, now: u64) {
+		let elapsed = max(now.saturating_sub(self.ask_time), 1_000_000) as f64 / 1_000_000_000f64;
+		let rate = items as f64 / elapsed;
+		self.download_rate = match self.download_rate == 0.0 {
+			true => rate,
+			false => self.download_rate * (1.0 - DOWNLOAD_RATE_WEIGHT) + rate * DOWNLOAD_RATE_WEIGHT,
+		};
+	}
+
 	fn reset_asking(&mut self) {
 		self.asking_blocks.clear();
 		self.asking_hash = None;
@@ -342,8 +436,14 @@ pub struct ChainSync {
 	last_sent_block_number: BlockNumber,
 	/// Network ID
 	network_id: usize,
+	/// Name of the `eth` subprotocol
+	eth_protocol: ProtocolId,
 	/// Optional fork block to check
 	fork_block: Option<(BlockNumber, H256)>,
+	/// Fork identifiers of the local chain
+	fork_filter: ForkFilter,
+	/// Trusted block hashes from the chain spec
+	checkpoints: BTreeMap<BlockNumber, H256>,
 	/// Snapshot downloader.
 	snapshot: Snapshot,
 	/// Connected peers pending Status message.
@@ -353,8 +453,24 @@ pub struct ChainSync {
 	sync_start_time: Option<u64>,
 	/// Transactions propagation statistics
 	transactions_stats: TransactionsStats,
+	/// Arrival statistics of new blocks and transactions
+	propagation_stats: PropagationStats,
+	/// Misbehavior scores of nodes serving invalid data
+	misbehavior: Misbehavior,
 	/// Enable ancient block downloading
 	download_old_blocks: bool,
+	/// Diffuse local transactions instead of broadcasting them right away
+	tx_diffusion: bool,
+	/// Local transactions waiting for their first propagation (with release timestamp) or already diffused (`None`)
+	local_transactions: H256FastMap<Option<u64>>,
+	/// Recently served block receipts. Shared so that requests can be served without the sync lock.
+	receipts_cache: Arc<Mutex<ReceiptsCache>>,
+	/// Verifies and keeps private transactions, if enabled.
+	private_tx: Option<Arc<PrivateTxProvider>>,
+	/// Hashes of recently seen private transactions, least recently seen evicted first
+	known_private_transactions: LruCache<H256, ()>,
+	/// Private transactions received from peers waiting to be relayed, with the peer they came from
+	relayed_private_transactions: Vec<(PeerId, Bytes)>,
 }
 
 type RlpResponseResult = Result<Option<(PacketId, RlpStream)>, PacketDecodeError>;
@@ -363,6 +479,7 @@ impl ChainSync {
 	/// Create a new instance of syncing strategy.
 	pub fn new(config: SyncConfig, chain: &BlockChainClient) -> ChainSync {
 		let chain_info = chain.chain_info();
+		let spec_info = chain.chain_spec_info();
 		let mut sync = ChainSync {
 			state: if config.warp_sync { SyncState::WaitingPeers } else { SyncState::Idle },
 			starting_block: chain.chain_info().best_block_number,
@@ -374,19 +491,39 @@ impl ChainSync {
 			old_blocks: None,
 			last_sent_block_number: 0,
 			network_id: config.network_id,
+			eth_protocol: config.subprotocol_name,
 			fork_block: config.fork_block,
+			fork_filter: ForkFilter::new(chain_info.genesis_hash, spec_info.transitions.into_iter().map(|(_, n)| n)),
+			checkpoints: spec_info.checkpoints,
 			download_old_blocks: config.download_old_blocks,
 			snapshot: Snapshot::new(),
 			sync_start_time: None,
 			transactions_stats: TransactionsStats::default(),
+			propagation_stats: PropagationStats::default(),
+			misbehavior: Misbehavior::default(),
+			tx_diffusion: config.tx_diffusion,
+			local_transactions: H256FastMap::default(),
+			receipts_cache: Arc::new(Mutex::new(ReceiptsCache::default())),
+			private_tx: None,
+			known_private_transactions: LruCache::new(MAX_KNOWN_PRIVATE_TRANSACTIONS),
+			relayed_private_transactions: Vec::new(),
 		};
 		sync.update_targets(chain);
 		sync
 	}
 
+	/// Verify private transactions received from peers with `provider` and distribute the ones it sends.
+	pub fn set_private_tx_provider(&mut self, provider: Arc<PrivateTxProvider>) {
+		self.private_tx = Some(provider);
+	}
+
 	/// Returns synchonization status
 	pub fn status(&self) -> SyncStatus {
 		let last_imported_number = self.new_blocks.last_imported_block_number();
+		let (receipts_cache_hits, receipts_cache_misses) = {
+			let cache = self.receipts_cache.lock();
+			(cache.hits(), cache.misses())
+		};
 		SyncStatus {
 			state: self.state.clone(),
 			protocol_version: PROTOCOL_VERSION_63,
@@ -401,6 +538,8 @@ impl ChainSync {
 			num_active_peers: self.peers.values().filter(|p| p.is_allowed() && p.asking != PeerAsking::Nothing).count(),
 			num_snapshot_chunks: self.snapshot.total_chunks(),
 			snapshot_chunks_done: self.snapshot.done_chunks(),
+			receipts_cache_hits: receipts_cache_hits,
+			receipts_cache_misses: receipts_cache_misses,
 			mem_used:
 				self.new_blocks.heap_size()
 				+ self.old_blocks.as_ref().map_or(0, |d| d.heap_size())
@@ -422,6 +561,7 @@ impl ChainSync {
 						eth_version: peer_data.protocol_version as u32,
 						eth_difficulty: peer_data.difficulty,
 						eth_head: peer_data.latest_hash,
+						propagation: (&peer_data.arrivals).into(),
 				})
 			)
 			.collect()
@@ -481,7 +621,7 @@ impl ChainSync {
 		let our_best_block = io.chain().chain_info().best_block_number;
 		let fork_block = self.fork_block.as_ref().map(|&(n, _)| n).unwrap_or(0);
 
-		let (best_hash, max_peers, snapshot_peers) = {
+		let selected = {
 			//collect snapshot infos from peers
 			let snapshots = self.peers.iter()
 				.filter(|&(_, p)| p.is_allowed() && p.snapshot_number.map_or(false, |sn|
@@ -489,31 +629,24 @@ impl ChainSync {
 					sn > fork_block &&
 					self.highest_block.map_or(true, |highest| highest >= sn && (highest - sn) <= SNAPSHOT_RESTORE_THRESHOLD)
 				))
-				.filter_map(|(p, peer)| peer.snapshot_hash.map(|hash| (p, hash.clone())));
-
-			let mut snapshot_peers = HashMap::new();
-			let mut max_peers: usize = 0;
-			let mut best_hash = None;
-			for (p, hash) in snapshots {
-				let peers = snapshot_peers.entry(hash).or_insert_with(Vec::new);
-				peers.push(*p);
-				if peers.len() > max_peers {
-					max_peers = peers.len();
-					best_hash = Some(hash);
-				}
-			}
-			(best_hash, max_peers, snapshot_peers)
+				.filter_map(|(p, peer)| match (peer.snapshot_hash, peer.snapshot_number) {
+					(Some(hash), Some(number)) => Some((*p, hash, number)),
+					_ => None,
+				})
+				.filter(|&(_, hash, _)| !self.snapshot.is_known_bad(&hash));
+
+			select_snapshot(snapshots)
 		};
 
 		let timeout = self.sync_start_time.map_or(false, |t| ((time::precise_time_ns() - t) / 1_000_000_000) > WAIT_PEERS_TIMEOUT_SEC);
 
-		if let (Some(hash), Some(peers)) = (best_hash, best_hash.map_or(None, |h| snapshot_peers.get(&h))) {
-			if max_peers >= SNAPSHOT_MIN_PEERS {
+		if let Some((hash, peers, confirmed)) = selected {
+			if confirmed {
 				trace!(target: "sync", "Starting confirmed snapshot sync {:?} with {:?}", hash, peers);
-				self.start_snapshot_sync(io, peers);
+				self.start_snapshot_sync(io, &peers);
 			} else if timeout {
 				trace!(target: "sync", "Starting unconfirmed snapshot sync {:?} with {:?}", hash, peers);
-				self.start_snapshot_sync(io, peers);
+				self.start_snapshot_sync(io, &peers);
 			}
 		} else if timeout {
 			trace!(target: "sync", "No snapshots found, starting full sync");
@@ -562,7 +695,8 @@ impl ChainSync {
 	fn on_peer_status(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
 		self.handshaking_peers.remove(&peer_id);
 		let protocol_version: u8 = try!(r.val_at(0));
-		let warp_protocol = io.protocol_version(&WARP_SYNC_PROTOCOL_ID, peer_id) != 0;
+		let capabilities = self.capabilities(io, peer_id);
+		let warp_protocol = capabilities.is_warp();
 		let peer = PeerInfo {
 			protocol_version: protocol_version,
 			network_id: try!(r.val_at(1)),
@@ -575,11 +709,15 @@ impl ChainSync {
 			ask_time: 0,
 			last_sent_transactions: HashSet::new(),
 			expired: false,
-			confirmation: if self.fork_block.is_none() { ForkConfirmation::Confirmed } else { ForkConfirmation::Unconfirmed },
+			confirmation: if self.confirmation_block().is_none() { ForkConfirmation::Confirmed } else { ForkConfirmation::Unconfirmed },
 			asking_snapshot_data: None,
 			snapshot_hash: if warp_protocol { Some(try!(r.val_at(5))) } else { None },
 			snapshot_number: if warp_protocol { Some(try!(r.val_at(6))) } else { None },
 			block_set: None,
+			arrivals: PeerArrivals::default(),
+			download_rate: 0.0,
+			last_head_announce: 0,
+			capabilities: capabilities,
 		};
 
 		if self.sync_start_time.is_none() {
@@ -608,16 +746,29 @@ impl ChainSync {
 			trace!(target: "sync", "Peer {} network id mismatch (ours: {}, theirs: {})", peer_id, self.network_id, peer.network_id);
 			return Ok(());
 		}
-		if (warp_protocol && peer.protocol_version != PROTOCOL_VERSION_1) || (!warp_protocol && peer.protocol_version != PROTOCOL_VERSION_63 && peer.protocol_version != PROTOCOL_VERSION_62) {
+		// fork identifier is only sent by warp peers which support it: the eth status has a fixed layout.
+		if warp_protocol && r.item_count() > 7 {
+			let fork_id: ForkId = try!(r.val_at(7));
+			if let Err(e) = self.fork_filter.validate(chain_info.best_block_number, &fork_id) {
+				io.disable_peer(peer_id);
+				trace!(target: "sync", "Peer {} fork id mismatch ({:?}, ours: {:?}, theirs: {:?})", peer_id, e, self.fork_filter.current(chain_info.best_block_number), fork_id);
+				return Ok(());
+			}
+		}
+		let supported = match warp_protocol {
+			true => peer.protocol_version == PROTOCOL_VERSION_1 || peer.protocol_version == PROTOCOL_VERSION_2,
+			false => peer.protocol_version == PROTOCOL_VERSION_63 || peer.protocol_version == PROTOCOL_VERSION_62,
+		};
+		if !supported {
 			io.disable_peer(peer_id);
-			trace!(target: "sync", "Peer {} unsupported eth protocol ({})", peer_id, peer.protocol_version);
+			trace!(target: "sync", "Peer {} unsupported eth protocol ({}, negotiated {:?})", peer_id, peer.protocol_version, capabilities);
 			return Ok(());
 		}
 
 		self.peers.insert(peer_id.clone(), peer);
 		self.active_peers.insert(peer_id.clone());
 		debug!(target: "sync", "Connected {}:{}", peer_id, io.peer_info(peer_id));
-		if let Some((fork_block, _)) = self.fork_block {
+		if let Some((fork_block, _)) = self.confirmation_block() {
 			self.request_fork_header_by_number(io, peer_id, fork_block);
 		} else {
 			self.sync_peer(io, peer_id, false);
@@ -628,11 +779,12 @@ impl ChainSync {
 	#[cfg_attr(feature="dev", allow(cyclomatic_complexity, needless_borrow))]
 	/// Called by peer once it has new block headers during sync
 	fn on_peer_block_headers(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		let confirmation_block = self.confirmation_block();
 		let confirmed = match self.peers.get_mut(&peer_id) {
 			Some(ref mut peer) if peer.asking == PeerAsking::ForkHeader => {
 				peer.asking = PeerAsking::Nothing;
 				let item_count = r.item_count();
-				let (fork_number, fork_hash) = self.fork_block.expect("ForkHeader request is sent only fork block is Some; qed").clone();
+				let (fork_number, fork_hash) = confirmation_block.expect("ForkHeader request is sent only if there is a block to confirm; qed");
 				if item_count == 0 || item_count != 1 {
 					trace!(target: "sync", "{}: Chain is too short to confirm the block", peer_id);
 					peer.confirmation = ForkConfirmation::TooShort;
@@ -669,6 +821,7 @@ impl ChainSync {
 			return Ok(());
 		}
 		let item_count = r.item_count();
+		self.record_response(peer_id, item_count);
 		trace!(target: "sync", "{} -> BlockHeaders ({} entries), state = {:?}, set = {:?}", peer_id, item_count, self.state, block_set);
 		if (self.state == SyncState::Idle || self.state == SyncState::WaitingPeers) && self.old_blocks.is_none() {
 			trace!(target: "sync", "Ignored unexpected block headers");
@@ -680,6 +833,13 @@ impl ChainSync {
 			self.continue_sync(io);
 			return Ok(());
 		}
+		if let Some(number) = try!(self.conflicting_checkpoint(r)) {
+			debug!(target: "sync", "{}: Header #{} contradicts a checkpoint", peer_id, number);
+			self.misbehaved(io, peer_id, Offence::CheckpointMismatch);
+			self.deactivate_peer(io, peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
 
 		let result =  {
 			let mut downloader = match block_set {
@@ -703,7 +863,7 @@ impl ChainSync {
 				self.deactivate_peer(io, peer_id);
 			},
 			Err(DownloaderImportError::Invalid) => {
-				io.disable_peer(peer_id);
+				self.misbehaved(io, peer_id, Offence::InvalidData);
 				self.deactivate_peer(io, peer_id);
 				self.continue_sync(io);
 				return Ok(());
@@ -737,6 +897,7 @@ impl ChainSync {
 			return Ok(());
 		}
 		let item_count = r.item_count();
+		self.record_response(peer_id, item_count);
 		trace!(target: "sync", "{} -> BlockBodies ({} entries), set = {:?}", peer_id, item_count, block_set);
 		if item_count == 0 {
 			self.deactivate_peer(io, peer_id);
@@ -763,7 +924,7 @@ impl ChainSync {
 
 			match result {
 				Err(DownloaderImportError::Invalid) => {
-					io.disable_peer(peer_id);
+					self.misbehaved(io, peer_id, Offence::InvalidData);
 					self.deactivate_peer(io, peer_id);
 					self.continue_sync(io);
 					return Ok(());
@@ -791,6 +952,7 @@ impl ChainSync {
 			return Ok(());
 		}
 		let item_count = r.item_count();
+		self.record_response(peer_id, item_count);
 		trace!(target: "sync", "{} -> BlockReceipts ({} entries)", peer_id, item_count);
 		if item_count == 0 {
 			self.deactivate_peer(io, peer_id);
@@ -817,7 +979,7 @@ impl ChainSync {
 
 			match result {
 				Err(DownloaderImportError::Invalid) => {
-					io.disable_peer(peer_id);
+					self.misbehaved(io, peer_id, Offence::InvalidData);
 					self.deactivate_peer(io, peer_id);
 					self.continue_sync(io);
 					return Ok(());
@@ -847,6 +1009,11 @@ impl ChainSync {
 		let h = header_rlp.as_raw().sha3();
 		trace!(target: "sync", "{} -> NewBlock ({})", peer_id, h);
 		let header: BlockHeader = try!(header_rlp.as_val());
+		if self.checkpoints.get(&header.number()).map_or(false, |hash| *hash != h) {
+			debug!(target: "sync", "{}: New block #{} contradicts a checkpoint", peer_id, header.number());
+			self.misbehaved(io, peer_id, Offence::CheckpointMismatch);
+			return Ok(());
+		}
 		if header.number() > self.highest_block.unwrap_or(0) {
 			self.highest_block = Some(header.number());
 		}
@@ -854,6 +1021,7 @@ impl ChainSync {
 		{
 			if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
 				peer.latest_hash = header.hash();
+				self.propagation_stats.block_arrived(header.hash(), peer_id, time::precise_time_ns() / 1_000_000, &mut peer.arrivals);
 			}
 		}
 		let last_imported_number = self.new_blocks.last_imported_block_number();
@@ -879,7 +1047,7 @@ impl ChainSync {
 			},
 			Err(e) => {
 				debug!(target: "sync", "Bad new block {:?} : {:?}", h, e);
-				io.disable_peer(peer_id);
+				self.misbehaved(io, peer_id, Offence::BadBlock);
 			}
 		};
 		if unknown {
@@ -920,12 +1088,16 @@ impl ChainSync {
 		let mut max_height: BlockNumber = 0;
 		let mut new_hashes = Vec::new();
 		let last_imported_number = self.new_blocks.last_imported_block_number();
+		let now_ms = time::precise_time_ns() / 1_000_000;
 		for (rh, rn) in hashes {
 			let hash = try!(rh);
 			let number = try!(rn);
 			if number > self.highest_block.unwrap_or(0) {
 				self.highest_block = Some(number);
 			}
+			if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
+				self.propagation_stats.block_arrived(hash.clone(), peer_id, now_ms, &mut peer.arrivals);
+			}
 			if self.new_blocks.is_downloading(&hash) {
 				continue;
 			}
@@ -953,7 +1125,7 @@ impl ChainSync {
 				},
 				BlockStatus::Bad => {
 					debug!(target: "sync", "Bad new block hash {:?}", hash);
-					io.disable_peer(peer_id);
+					self.misbehaved(io, peer_id, Offence::BadBlock);
 					return Ok(());
 				}
 			}
@@ -984,13 +1156,33 @@ impl ChainSync {
 		let manifest = match ManifestData::from_rlp(manifest_rlp.as_raw()) {
 			Err(e) => {
 				trace!(target: "sync", "{}: Ignored bad manifest: {:?}", peer_id, e);
-				io.disconnect_peer(peer_id);
+				self.misbehaved(io, peer_id, Offence::BadManifest);
 				self.continue_sync(io);
 				return Ok(());
 			}
 			Ok(manifest) => manifest,
 		};
-		self.snapshot.reset_to(&manifest, &manifest_rlp.as_raw().sha3());
+
+		// the manifest must be the one the peer advertised, which was voted for.
+		let manifest_hash = manifest_rlp.as_raw().sha3();
+		let advertised = self.peers.get(&peer_id).map(|p| (p.snapshot_hash, p.snapshot_number));
+		if advertised != Some((Some(manifest_hash), Some(manifest.block_number))) {
+			trace!(target: "sync", "{}: Ignored manifest {} not matching the advertised snapshot", peer_id, manifest_hash);
+			self.misbehaved(io, peer_id, Offence::BadManifest);
+			self.continue_sync(io);
+			return Ok(());
+		}
+		// snapshots in a format we can't restore are not an offence, just not usable.
+		if let Err(e) = ::ethcore::snapshot::check_version(manifest.version) {
+			trace!(target: "sync", "{}: Ignored manifest: {}", peer_id, e);
+			if let Some(peer) = self.peers.get_mut(&peer_id) {
+				peer.snapshot_hash = None;
+				peer.snapshot_number = None;
+			}
+			self.continue_sync(io);
+			return Ok(());
+		}
+		self.snapshot.reset_to(&manifest, &manifest_hash);
 		io.snapshot_service().begin_restore(manifest);
 		self.state = SyncState::SnapshotData;
 
@@ -1016,19 +1208,27 @@ impl ChainSync {
 
 		// check service status
 		match io.snapshot_service().status() {
-			RestorationStatus::Inactive | RestorationStatus::Failed => {
+			RestorationStatus::Inactive => {
 				trace!(target: "sync", "{}: Snapshot restoration aborted", peer_id);
 				self.state = SyncState::WaitingPeers;
 				self.snapshot.clear();
 				self.continue_sync(io);
 				return Ok(());
 			},
+			RestorationStatus::Failed => {
+				trace!(target: "sync", "{}: Snapshot restoration failed", peer_id);
+				self.state = SyncState::WaitingPeers;
+				self.snapshot.note_failed();
+				self.continue_sync(io);
+				return Ok(());
+			},
 			RestorationStatus::Ongoing { .. } => {
 				trace!(target: "sync", "{}: Snapshot restoration is ongoing", peer_id);
 			},
 		}
 
 		let snapshot_data: Bytes = try!(r.val_at(0));
+		let requested = self.peers.get(&peer_id).and_then(|p| p.asking_snapshot_data.clone());
 		match self.snapshot.validate_chunk(&snapshot_data) {
 			Ok(ChunkType::Block(hash)) => {
 				trace!(target: "sync", "{}: Processing block chunk", peer_id);
@@ -1038,6 +1238,12 @@ impl ChainSync {
 				trace!(target: "sync", "{}: Processing state chunk", peer_id);
 				io.snapshot_service().restore_state_chunk(hash, snapshot_data);
 			}
+			Err(()) if requested != Some(snapshot_data.sha3()) => {
+				trace!(target: "sync", "{}: Got snapshot chunk which was not requested", peer_id);
+				self.misbehaved(io, peer_id, Offence::BadChunk);
+				self.continue_sync(io);
+				return Ok(());
+			}
 			Err(()) => {
 				trace!(target: "sync", "{}: Got bad snapshot chunk", peer_id);
 				io.disconnect_peer(peer_id);
@@ -1073,6 +1279,13 @@ impl ChainSync {
 	/// Called when a new peer is connected
 	pub fn on_peer_connected(&mut self, io: &mut SyncIo, peer: PeerId) {
 		trace!(target: "sync", "== Connected {}: {}", peer, io.peer_info(peer));
+		if let Some(id) = io.peer_session_info(peer).and_then(|info| info.id) {
+			if self.misbehavior.is_banned(&id, time::precise_time_ns()) {
+				trace!(target: "sync", "Refusing banned peer {}", peer);
+				io.disconnect_peer(peer);
+				return;
+			}
+		}
 		if let Err(e) = self.send_status(io, peer) {
 			debug!(target:"sync", "Error sending status request: {:?}", e);
 			io.disable_peer(peer);
@@ -1083,13 +1296,16 @@ impl ChainSync {
 
 	/// Resume downloading
 	fn continue_sync(&mut self, io: &mut SyncIo) {
-		let mut peers: Vec<(PeerId, U256, u8)> = self.peers.iter().filter_map(|(k, p)|
-			if p.can_sync() { Some((*k, p.difficulty.unwrap_or_else(U256::zero), p.protocol_version)) } else { None }).collect();
-		thread_rng().shuffle(&mut peers); //TODO: sort by rating
-		// prefer peers with higher protocol version
-		peers.sort_by(|&(_, _, ref v1), &(_, _, ref v2)| v1.cmp(v2));
+		let mut peers: Vec<(PeerId, U256, u8, f64)> = self.peers.iter().filter_map(|(k, p)|
+			if p.can_sync() { Some((*k, p.difficulty.unwrap_or_else(U256::zero), p.protocol_version, p.download_rate)) } else { None }).collect();
+		thread_rng().shuffle(&mut peers);
+		// prefer peers with higher protocol version, then the ones that deliver data faster
+		peers.sort_by(|&(_, _, ref v1, ref r1), &(_, _, ref v2, ref r2)| match v1.cmp(v2) {
+			Ordering::Equal => r2.partial_cmp(r1).unwrap_or(Ordering::Equal),
+			ordering => ordering,
+		});
 		trace!(target: "sync", "Syncing with {}/{} peers", self.active_peers.len(), peers.len());
-		for (p, _, _) in peers {
+		for (p, _, _, _) in peers {
 			if self.active_peers.contains(&p) {
 				self.sync_peer(io, p, false);
 			}
@@ -1141,6 +1357,7 @@ impl ChainSync {
 		let chain_info = io.chain().chain_info();
 		let syncing_difficulty = chain_info.pending_total_difficulty;
 		let num_active_peers = self.peers.values().filter(|p| p.asking != PeerAsking::Nothing).count();
+		let share = self.download_share(peer_id);
 
 		let higher_difficulty = peer_difficulty.map_or(true, |pd| pd > syncing_difficulty);
 		if force || self.state == SyncState::NewBlocks || higher_difficulty || self.old_blocks.is_some() {
@@ -1159,7 +1376,7 @@ impl ChainSync {
 					if !have_latest && (higher_difficulty || force || self.state == SyncState::NewBlocks) {
 						// check if got new blocks to download
 						trace!(target: "sync", "Syncing with {}, force={}, td={:?}, our td={}, state={:?}", peer_id, force, peer_difficulty, syncing_difficulty, self.state);
-						if let Some(request) = self.new_blocks.request_blocks(io, num_active_peers) {
+						if let Some(request) = self.new_blocks.request_blocks(io, num_active_peers, share) {
 							self.request_blocks(io, peer_id, request, BlockSet::NewBlocks);
 							if self.state == SyncState::Idle {
 								self.state = SyncState::Blocks;
@@ -1168,7 +1385,7 @@ impl ChainSync {
 						}
 					}
 
-					if let Some(request) = self.old_blocks.as_mut().and_then(|d| d.request_blocks(io, num_active_peers)) {
+					if let Some(request) = self.old_blocks.as_mut().and_then(|d| d.request_blocks(io, num_active_peers, share)) {
 						self.request_blocks(io, peer_id, request, BlockSet::OldBlocks);
 						return;
 					}
@@ -1191,6 +1408,18 @@ impl ChainSync {
 		}
 	}
 
+	/// Part of a full block data request given to a peer: its download rate relative to the fastest peer.
+	/// Peers that have not been rated yet get full requests.
+	fn download_share(&self, peer_id: PeerId) -> f64 {
+		let rate = self.peers.get(&peer_id).map_or(0.0, |p| p.download_rate);
+		let best = self.peers.values().fold(0.0, |best: f64, p| best.max(p.download_rate));
+		if rate == 0.0 || best == 0.0 {
+			1.0
+		} else {
+			rate / best
+		}
+	}
+
 	/// Perofrm block download request`
 	fn request_blocks(&mut self, io: &mut SyncIo, peer_id: PeerId, request: BlockRequest, block_set: BlockSet) {
 		match request {
@@ -1276,12 +1505,13 @@ impl ChainSync {
 	#[cfg_attr(feature="dev", allow(too_many_arguments))]
 	fn request_headers_by_hash(&mut self, sync: &mut SyncIo, peer_id: PeerId, h: &H256, count: u64, skip: u64, reverse: bool, set: BlockSet) {
 		trace!(target: "sync", "{} <- GetBlockHeaders: {} entries starting from {}, set = {:?}", peer_id, count, h, set);
-		let mut rlp = RlpStream::new_list(4);
-		rlp.append(h);
-		rlp.append(&count);
-		rlp.append(&skip);
-		rlp.append(&if reverse {1u32} else {0u32});
-		self.send_request(sync, peer_id, PeerAsking::BlockHeaders, GET_BLOCK_HEADERS_PACKET, rlp.out());
+		let req = request::Headers {
+			block: HashOrNumber::Hash(h.clone()),
+			max: count as usize,
+			skip: skip as usize,
+			reverse: reverse,
+		};
+		self.send_request(sync, peer_id, PeerAsking::BlockHeaders, GET_BLOCK_HEADERS_PACKET, encode(&req).to_vec());
 		let peer = self.peers.get_mut(&peer_id).expect("peer_id may originate either from on_packet, where it is already validated or from enumerating self.peers. qed");
 		peer.asking_hash = Some(h.clone());
 		peer.block_set = Some(set);
@@ -1291,12 +1521,13 @@ impl ChainSync {
 	#[cfg_attr(feature="dev", allow(too_many_arguments))]
 	fn request_fork_header_by_number(&mut self, sync: &mut SyncIo, peer_id: PeerId, n: BlockNumber) {
 		trace!(target: "sync", "{} <- GetForkHeader: at {}", peer_id, n);
-		let mut rlp = RlpStream::new_list(4);
-		rlp.append(&n);
-		rlp.append(&1u32);
-		rlp.append(&0u32);
-		rlp.append(&0u32);
-		self.send_request(sync, peer_id, PeerAsking::ForkHeader, GET_BLOCK_HEADERS_PACKET, rlp.out());
+		let req = request::Headers {
+			block: HashOrNumber::Number(n),
+			max: 1,
+			skip: 0,
+			reverse: false,
+		};
+		self.send_request(sync, peer_id, PeerAsking::ForkHeader, GET_BLOCK_HEADERS_PACKET, encode(&req).to_vec());
 	}
 
 	/// Request snapshot manifest from a peer.
@@ -1316,30 +1547,61 @@ impl ChainSync {
 
 	/// Request block bodies from a peer
 	fn request_bodies(&mut self, sync: &mut SyncIo, peer_id: PeerId, hashes: Vec<H256>, set: BlockSet) {
-		let mut rlp = RlpStream::new_list(hashes.len());
 		trace!(target: "sync", "{} <- GetBlockBodies: {} entries starting from {:?}, set = {:?}", peer_id, hashes.len(), hashes.first(), set);
-		for h in &hashes {
-			rlp.append(&h.clone());
-		}
-		self.send_request(sync, peer_id, PeerAsking::BlockBodies, GET_BLOCK_BODIES_PACKET, rlp.out());
+		let req = request::Bodies { block_hashes: hashes };
+		self.send_request(sync, peer_id, PeerAsking::BlockBodies, GET_BLOCK_BODIES_PACKET, encode(&req).to_vec());
 		let peer = self.peers.get_mut(&peer_id).expect("peer_id may originate either from on_packet, where it is already validated or from enumerating self.peers. qed");
-		peer.asking_blocks = hashes;
+		peer.asking_blocks = req.block_hashes;
 		peer.block_set = Some(set);
 	}
 
 	/// Request block receipts from a peer
 	fn request_receipts(&mut self, sync: &mut SyncIo, peer_id: PeerId, hashes: Vec<H256>, set: BlockSet) {
-		let mut rlp = RlpStream::new_list(hashes.len());
 		trace!(target: "sync", "{} <- GetBlockReceipts: {} entries starting from {:?}, set = {:?}", peer_id, hashes.len(), hashes.first(), set);
-		for h in &hashes {
-			rlp.append(&h.clone());
-		}
-		self.send_request(sync, peer_id, PeerAsking::BlockReceipts, GET_RECEIPTS_PACKET, rlp.out());
+		let req = request::Receipts { block_hashes: hashes };
+		self.send_request(sync, peer_id, PeerAsking::BlockReceipts, GET_RECEIPTS_PACKET, encode(&req).to_vec());
 		let peer = self.peers.get_mut(&peer_id).expect("peer_id may originate either from on_packet, where it is already validated or from enumerating self.peers. qed");
-		peer.asking_blocks = hashes;
+		peer.asking_blocks = req.block_hashes;
 		peer.block_set = Some(set);
 	}
 
+	/// Block which peers are asked for to confirm they are on our chain:
+	/// the fork block if configured, the latest checkpoint otherwise.
+	fn confirmation_block(&self) -> Option<(BlockNumber, H256)> {
+		self.fork_block.or_else(|| self.checkpoints.iter().next_back().map(|(n, h)| (*n, h.clone())))
+	}
+
+	/// Returns the number of the first header which contradicts a checkpoint.
+	fn conflicting_checkpoint(&self, headers: &UntrustedRlp) -> Result<Option<BlockNumber>, PacketDecodeError> {
+		if self.checkpoints.is_empty() {
+			return Ok(None);
+		}
+		for header in headers.iter() {
+			let number: BlockNumber = try!(header.val_at(8));
+			if self.checkpoints.get(&number).map_or(false, |hash| *hash != header.as_raw().sha3()) {
+				return Ok(Some(number));
+			}
+		}
+		Ok(None)
+	}
+
+	/// Record an offence of the peer and disable it.
+	fn misbehaved(&mut self, io: &mut SyncIo, peer_id: PeerId, offence: Offence) {
+		if let Some(id) = io.peer_session_info(peer_id).and_then(|info| info.id) {
+			if self.misbehavior.report(id, offence, time::precise_time_ns()) {
+				debug!(target: "sync", "Banning peer {} for serving invalid data", peer_id);
+			}
+		}
+		io.disable_peer(peer_id);
+	}
+
+	/// Update peer download rate after a response to a block data request.
+	fn record_response(&mut self, peer_id: PeerId, items: usize) {
+		if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
+			peer.record_response(items, time::precise_time_ns());
+		}
+	}
+
 	/// Reset peer status after request is complete.
 	fn reset_peer_asking(&mut self, peer_id: PeerId, asking: PeerAsking) -> bool {
 		if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
@@ -1400,12 +1662,16 @@ impl ChainSync {
 		trace!(target: "sync", "{} -> Transactions ({} entries)", peer_id, item_count);
 		item_count = min(item_count, MAX_TX_TO_IMPORT);
 		let mut transactions = Vec::with_capacity(item_count);
+		let now_ms = time::precise_time_ns() / 1_000_000;
 		for i in 0 .. item_count {
 			let rlp = try!(r.at(i));
 			if rlp.as_raw().len() > MAX_TRANSACTION_SIZE {
 				debug!("Skipped oversized transaction of {} bytes", rlp.as_raw().len());
 				continue;
 			}
+			if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
+				self.propagation_stats.transaction_arrived(rlp.as_raw().sha3(), peer_id, now_ms, &mut peer.arrivals);
+			}
 			let tx = rlp.as_raw().to_vec();
 			transactions.push(tx);
 		}
@@ -1413,12 +1679,50 @@ impl ChainSync {
 		Ok(())
 	}
 
+	/// Called when peer sends us a private transaction. Verified transactions, and the ones
+	/// this node is not a validator for, are relayed to other peers.
+	fn on_private_transaction(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		let provider = match self.private_tx {
+			Some(ref provider) => provider.clone(),
+			None => {
+				trace!(target: "sync", "{} Ignoring private transaction, private transactions are disabled", peer_id);
+				return Ok(());
+			}
+		};
+		if !self.peers.get(&peer_id).map_or(false, |p| p.can_sync()) {
+			trace!(target: "sync", "{} Ignoring private transaction from unconfirmed/unknown peer", peer_id);
+			return Ok(());
+		}
+		if r.as_raw().len() > MAX_TRANSACTION_SIZE {
+			debug!(target: "sync", "{} Skipped oversized private transaction of {} bytes", peer_id, r.as_raw().len());
+			return Ok(());
+		}
+
+		let hash = r.as_raw().sha3();
+		if self.known_private_transactions.insert(hash.clone(), ()).is_some() {
+			return Ok(());
+		}
+		// already imported or sent by this node, and so relayed before.
+		if let Ok(Some(_)) = provider.transaction(&hash) {
+			trace!(target: "sync", "{} Ignoring known private transaction {}", peer_id, hash);
+			return Ok(());
+		}
+		trace!(target: "sync", "{} -> PrivateTransaction {}", peer_id, hash);
+		match provider.import_transaction(io.chain(), r.as_raw()) {
+			Ok(_) | Err(PrivateTxError::NotPermitted(_)) => self.relayed_private_transactions.push((peer_id, r.as_raw().to_vec())),
+			Err(PrivateTxError::Decoder(e)) => return Err(e),
+			Err(e) => debug!(target: "sync", "{} Rejected private transaction {}: {}", peer_id, hash, e),
+		}
+		Ok(())
+	}
+
 	/// Send Status message
 	fn send_status(&mut self, io: &mut SyncIo, peer: PeerId) -> Result<(), NetworkError> {
-		let warp_protocol = io.protocol_version(&WARP_SYNC_PROTOCOL_ID, peer) != 0;
-		let protocol = if warp_protocol { PROTOCOL_VERSION_1 } else { io.eth_protocol_version(peer) };
+		let capabilities = self.capabilities(io, peer);
+		let warp_protocol = capabilities.is_warp();
+		let protocol = capabilities.status_version();
 		trace!(target: "sync", "Sending status to {}, protocol version {}", peer, protocol);
-		let mut packet = RlpStream::new_list(if warp_protocol { 7 } else { 5 });
+		let mut packet = RlpStream::new_list(if warp_protocol { 8 } else { 5 });
 		let chain = io.chain().chain_info();
 		packet.append(&(protocol as u32));
 		packet.append(&self.network_id);
@@ -1431,41 +1735,39 @@ impl ChainSync {
 			let manifest_hash = manifest.map_or(H256::new(), |m| m.into_rlp().sha3());
 			packet.append(&manifest_hash);
 			packet.append(&block_number);
+			packet.append(&self.fork_filter.current(chain.best_block_number));
 		}
 		io.respond(STATUS_PACKET, packet.out())
 	}
 
 	/// Respond to GetBlockHeaders request
 	fn return_block_headers(io: &SyncIo, r: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
-		// Packet layout:
-		// [ block: { P , B_32 }, maxHeaders: P, skip: P, reverse: P in { 0 , 1 } ]
-		let max_headers: usize = try!(r.val_at(1));
-		let skip: usize = try!(r.val_at(2));
-		let reverse: bool = try!(r.val_at(3));
+		let req: request::Headers = try!(r.as_val());
+		let (max_headers, skip, reverse) = (req.max, req.skip, req.reverse);
 		let last = io.chain().chain_info().best_block_number;
-		let number = if try!(r.at(0)).size() == 32 {
-			// id is a hash
-			let hash: H256 = try!(r.val_at(0));
-			trace!(target: "sync", "{} -> GetBlockHeaders (hash: {}, max: {}, skip: {}, reverse:{})", peer_id, hash, max_headers, skip, reverse);
-			match io.chain().block_header(BlockID::Hash(hash)) {
-				Some(hdr) => {
-					let number = From::from(HeaderView::new(&hdr).number());
-					debug_assert_eq!(HeaderView::new(&hdr).sha3(), hash);
-					if max_headers == 1 || io.chain().block_hash(BlockID::Number(number)) != Some(hash) {
-						// Non canonical header or single header requested
-						// TODO: handle single-step reverse hashchains of non-canon hashes
-						trace!(target:"sync", "Returning single header: {:?}", hash);
-						let mut rlp = RlpStream::new_list(1);
-						rlp.append_raw(&hdr, 1);
-						return Ok(Some((BLOCK_HEADERS_PACKET, rlp)));
+		let number = match req.block {
+			HashOrNumber::Hash(hash) => {
+				trace!(target: "sync", "{} -> GetBlockHeaders (hash: {}, max: {}, skip: {}, reverse:{})", peer_id, hash, max_headers, skip, reverse);
+				match io.chain().block_header(BlockID::Hash(hash)) {
+					Some(hdr) => {
+						let number = From::from(HeaderView::new(&hdr).number());
+						debug_assert_eq!(HeaderView::new(&hdr).sha3(), hash);
+						if max_headers == 1 || io.chain().block_hash(BlockID::Number(number)) != Some(hash) {
+							// Non canonical header or single header requested
+							// TODO: handle single-step reverse hashchains of non-canon hashes
+							trace!(target:"sync", "Returning single header: {:?}", hash);
+							let response = request::BlockHeaders { headers: vec![hdr] };
+							return Ok(Some((BLOCK_HEADERS_PACKET, response.eth_encode())));
+						}
+						number
 					}
-					number
+					None => return Ok(Some((BLOCK_HEADERS_PACKET, request::BlockHeaders::default().eth_encode()))) //no such header, return nothing
 				}
-				None => return Ok(Some((BLOCK_HEADERS_PACKET, RlpStream::new_list(0)))) //no such header, return nothing
 			}
-		} else {
-			trace!(target: "sync", "{} -> GetBlockHeaders (number: {}, max: {}, skip: {}, reverse:{})", peer_id, try!(r.val_at::<BlockNumber>(0)), max_headers, skip, reverse);
-			try!(r.val_at(0))
+			HashOrNumber::Number(number) => {
+				trace!(target: "sync", "{} -> GetBlockHeaders (number: {}, max: {}, skip: {}, reverse:{})", peer_id, number, max_headers, skip, reverse);
+				number
+			}
 		};
 
 		let mut number = if reverse {
@@ -1474,19 +1776,16 @@ impl ChainSync {
 			max(0, number)
 		};
 		let max_count = min(MAX_HEADERS_TO_SEND, max_headers);
-		let mut count = 0;
-		let mut data = Bytes::new();
+		let mut response = request::BlockHeaders::default();
 		let inc = (skip + 1) as BlockNumber;
 		let overlay = io.chain_overlay().read();
 
-		while number <= last && count < max_count {
+		while number <= last && response.headers.len() < max_count {
 			if let Some(hdr) = overlay.get(&number) {
 				trace!(target: "sync", "{}: Returning cached fork header", peer_id);
-				data.extend_from_slice(hdr);
-				count += 1;
-			} else if let Some(mut hdr) = io.chain().block_header(BlockID::Number(number)) {
-				data.append(&mut hdr);
-				count += 1;
+				response.headers.push(hdr.clone());
+			} else if let Some(hdr) = io.chain().block_header(BlockID::Number(number)) {
+				response.headers.push(hdr);
 			} else {
 				// No required block.
 				break;
@@ -1501,32 +1800,23 @@ impl ChainSync {
 				number += inc;
 			}
 		}
-		let mut rlp = RlpStream::new_list(count as usize);
-		rlp.append_raw(&data, count as usize);
-		trace!(target: "sync", "{} -> GetBlockHeaders: returned {} entries", peer_id, count);
-		Ok(Some((BLOCK_HEADERS_PACKET, rlp)))
+		trace!(target: "sync", "{} -> GetBlockHeaders: returned {} entries", peer_id, response.headers.len());
+		Ok(Some((BLOCK_HEADERS_PACKET, response.eth_encode())))
 	}
 
 	/// Respond to GetBlockBodies request
 	fn return_block_bodies(io: &SyncIo, r: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
-		let mut count = r.item_count();
-		if count == 0 {
+		let mut req: request::Bodies = try!(r.as_val());
+		if req.block_hashes.is_empty() {
 			debug!(target: "sync", "Empty GetBlockBodies request, ignoring.");
 			return Ok(None);
 		}
-		count = min(count, MAX_BODIES_TO_SEND);
-		let mut added = 0usize;
-		let mut data = Bytes::new();
-		for i in 0..count {
-			if let Some(mut hdr) = io.chain().block_body(BlockID::Hash(try!(r.val_at::<H256>(i)))) {
-				data.append(&mut hdr);
-				added += 1;
-			}
-		}
-		let mut rlp = RlpStream::new_list(added);
-		rlp.append_raw(&data, added);
-		trace!(target: "sync", "{} -> GetBlockBodies: returned {} entries", peer_id, added);
-		Ok(Some((BLOCK_BODIES_PACKET, rlp)))
+		req.block_hashes.truncate(MAX_BODIES_TO_SEND);
+		let response = request::BlockBodies {
+			bodies: req.block_hashes.into_iter().filter_map(|hash| io.chain().block_body(BlockID::Hash(hash))).collect(),
+		};
+		trace!(target: "sync", "{} -> GetBlockBodies: returned {} entries", peer_id, response.bodies.len());
+		Ok(Some((BLOCK_BODIES_PACKET, response.eth_encode())))
 	}
 
 	/// Respond to GetNodeData request
@@ -1554,28 +1844,32 @@ impl ChainSync {
 		Ok(Some((NODE_DATA_PACKET, rlp)))
 	}
 
-	fn return_receipts(io: &SyncIo, rlp: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
-		let mut count = rlp.item_count();
-		trace!(target: "sync", "{} -> GetReceipts: {} entries", peer_id, count);
-		if count == 0 {
+	fn return_receipts(io: &SyncIo, rlp: &UntrustedRlp, peer_id: PeerId, cache: &Mutex<ReceiptsCache>) -> RlpResponseResult {
+		let mut req: request::Receipts = try!(rlp.as_val());
+		trace!(target: "sync", "{} -> GetReceipts: {} entries", peer_id, req.block_hashes.len());
+		if req.block_hashes.is_empty() {
 			debug!(target: "sync", "Empty GetReceipts request, ignoring.");
 			return Ok(None);
 		}
-		count = min(count, MAX_RECEIPTS_HEADERS_TO_SEND);
-		let mut added_headers = 0usize;
+		req.block_hashes.truncate(MAX_RECEIPTS_HEADERS_TO_SEND);
 		let mut added_receipts = 0usize;
-		let mut data = Bytes::new();
-		for i in 0..count {
-			if let Some(mut receipts_bytes) = io.chain().block_receipts(&try!(rlp.val_at::<H256>(i))) {
-				data.append(&mut receipts_bytes);
+		let mut response = request::BlockReceipts::default();
+		for hash in req.block_hashes {
+			let cached = cache.lock().get(&hash);
+			let receipts_bytes = match cached {
+				Some(receipts_bytes) => Some(receipts_bytes),
+				None => io.chain().block_receipts(&hash).map(|receipts_bytes| {
+					cache.lock().insert(hash, receipts_bytes.clone());
+					receipts_bytes
+				}),
+			};
+			if let Some(receipts_bytes) = receipts_bytes {
 				added_receipts += receipts_bytes.len();
-				added_headers += 1;
-				if added_receipts > MAX_RECEIPTS_TO_SEND { break; }
+				response.receipts.push(receipts_bytes);
+				if added_receipts > MAX_RECEIPTS_BYTES_TO_SEND { break; }
 			}
 		}
-		let mut rlp_result = RlpStream::new_list(added_headers);
-		rlp_result.append_raw(&data, added_headers);
-		Ok(Some((RECEIPTS_PACKET, rlp_result)))
+		Ok(Some((RECEIPTS_PACKET, response.eth_encode())))
 	}
 
 	/// Respond to GetSnapshotManifest request
@@ -1637,36 +1931,33 @@ impl ChainSync {
 
 	/// Dispatch incoming requests and responses
 	pub fn dispatch_packet(sync: &RwLock<ChainSync>, io: &mut SyncIo, peer: PeerId, packet_id: u8, data: &[u8]) {
-		let rlp = UntrustedRlp::new(data);
-		let result = match packet_id {
-			GET_BLOCK_BODIES_PACKET => ChainSync::return_rlp(io, &rlp, peer,
-				ChainSync::return_block_bodies,
-				|e| format!("Error sending block bodies: {:?}", e)),
-
-			GET_BLOCK_HEADERS_PACKET => ChainSync::return_rlp(io, &rlp, peer,
-				ChainSync::return_block_headers,
-				|e| format!("Error sending block headers: {:?}", e)),
-
-			GET_RECEIPTS_PACKET => ChainSync::return_rlp(io, &rlp, peer,
-				ChainSync::return_receipts,
-				|e| format!("Error sending receipts: {:?}", e)),
-
-			GET_NODE_DATA_PACKET => ChainSync::return_rlp(io, &rlp, peer,
-				ChainSync::return_node_data,
-				|e| format!("Error sending nodes: {:?}", e)),
-
-			GET_SNAPSHOT_MANIFEST_PACKET => ChainSync::return_rlp(io, &rlp, peer,
-				ChainSync::return_snapshot_manifest,
-				|e| format!("Error sending snapshot manifest: {:?}", e)),
-
-			GET_SNAPSHOT_DATA_PACKET => ChainSync::return_rlp(io, &rlp, peer,
-				ChainSync::return_snapshot_data,
-				|e| format!("Error sending snapshot data: {:?}", e)),
+		let packet = match find_packet(packet_id) {
+			Some(packet) => packet,
+			None => {
+				debug!(target: "sync", "{}: Unknown packet {}", peer, packet_id);
+				return;
+			}
+		};
+		let (capabilities, receipts_cache) = {
+			let sync = sync.read();
+			(sync.capabilities(io, peer), sync.receipts_cache.clone())
+		};
+		if !capabilities.allows(packet.requires) {
+			debug!(target: "sync", "{}: {} packet not supported by negotiated protocols {:?}", peer, packet.name, capabilities);
+			return;
+		}
 
-			_ => {
+		let rlp = UntrustedRlp::new(data);
+		let result = match packet.handler {
+			Handler::Serve(serve) => ChainSync::return_rlp(io, &rlp, peer, serve,
+				|e| format!("Error answering {}: {:?}", packet.name, e)),
+			Handler::ServeReceipts => ChainSync::return_rlp(io, &rlp, peer,
+				|io, rlp, peer| ChainSync::return_receipts(io, rlp, peer, &receipts_cache),
+				|e| format!("Error answering {}: {:?}", packet.name, e)),
+			Handler::Handle(_) => {
 				sync.write().on_packet(io, peer, packet_id, data);
 				Ok(())
-			}
+			},
 		};
 		result.unwrap_or_else(|e| {
 			debug!(target:"sync", "{} -> Malformed packet {} : {}", peer, packet_id, e);
@@ -1679,16 +1970,8 @@ impl ChainSync {
 			return;
 		}
 		let rlp = UntrustedRlp::new(data);
-		let result = match packet_id {
-			STATUS_PACKET => self.on_peer_status(io, peer, &rlp),
-			TRANSACTIONS_PACKET => self.on_peer_transactions(io, peer, &rlp),
-			BLOCK_HEADERS_PACKET => self.on_peer_block_headers(io, peer, &rlp),
-			BLOCK_BODIES_PACKET => self.on_peer_block_bodies(io, peer, &rlp),
-			RECEIPTS_PACKET => self.on_peer_block_receipts(io, peer, &rlp),
-			NEW_BLOCK_PACKET => self.on_peer_new_block(io, peer, &rlp),
-			NEW_BLOCK_HASHES_PACKET => self.on_peer_new_hashes(io, peer, &rlp),
-			SNAPSHOT_MANIFEST_PACKET => self.on_snapshot_manifest(io, peer, &rlp),
-			SNAPSHOT_DATA_PACKET => self.on_snapshot_data(io, peer, &rlp),
+		let result = match find_packet(packet_id).map(|p| &p.handler) {
+			Some(&Handler::Handle(handle)) => handle(self, io, peer, &rlp),
 			_ => {
 				debug!(target: "sync", "{}: Unknown packet {}", peer, packet_id);
 				Ok(())
@@ -1699,9 +1982,18 @@ impl ChainSync {
 		})
 	}
 
+	/// Protocol versions negotiated with given peer. Read from the session until the handshake is done.
+	fn capabilities(&self, io: &SyncIo, peer: PeerId) -> Capabilities {
+		match self.peers.get(&peer) {
+			Some(info) => info.capabilities,
+			None => Capabilities::from_io(io, peer, &self.eth_protocol),
+		}
+	}
+
 	#[cfg_attr(feature="dev", allow(match_same_arms))]
 	pub fn maintain_peers(&mut self, io: &mut SyncIo) {
 		let tick = time::precise_time_ns();
+		self.misbehavior.collect_garbage(tick);
 		let mut aborting = Vec::new();
 		for (peer_id, peer) in &self.peers {
 			let elapsed = (tick - peer.ask_time) / 1_000_000_000;
@@ -1753,9 +2045,9 @@ impl ChainSync {
 					}
 				},
 				RestorationStatus::Failed => {
-					trace!(target: "sync", "Snapshot restoration aborted");
+					trace!(target: "sync", "Snapshot restoration failed");
 					self.state = SyncState::WaitingPeers;
-					self.snapshot.clear();
+					self.snapshot.note_failed();
 					self.continue_sync(io);
 				},
 			}
@@ -1860,14 +2152,24 @@ impl ChainSync {
 	fn propagate_new_hashes(&mut self, chain_info: &BlockChainInfo, io: &mut SyncIo, peers: &[PeerId]) -> usize {
 		trace!(target: "sync", "Sending NewHashes to {:?}", peers);
 		let mut sent = 0;
+		let now = time::precise_time_ns();
 		let last_parent = HeaderView::new(&io.chain().block_header(BlockID::Hash(chain_info.best_block_hash.clone()))
 			.expect("Best block always exists")).parent_hash();
 		for peer_id in peers {
-			sent += match ChainSync::create_new_hashes_rlp(io.chain(), &last_parent, &chain_info.best_block_hash) {
+			// announce every block since the peer's known head at once, if it is close enough.
+			let from = self.peers.get(peer_id)
+				.and_then(|peer| io.chain().block_header(BlockID::Hash(peer.latest_hash.clone())).map(|h| (peer.latest_hash.clone(), HeaderView::new(&h).number())))
+				.and_then(|(hash, number)| match chain_info.best_block_number.saturating_sub(number) <= MAX_PEER_LAG_PROPAGATION {
+					true => Some(hash),
+					false => None,
+				})
+				.unwrap_or_else(|| last_parent.clone());
+			sent += match ChainSync::create_new_hashes_rlp(io.chain(), &from, &chain_info.best_block_hash) {
 				Some(rlp) => {
 					{
 						if let Some(ref mut peer) = self.peers.get_mut(peer_id) {
 							peer.latest_hash = chain_info.best_block_hash.clone();
+							peer.last_head_announce = now;
 						}
 					}
 					self.send_packet(io, *peer_id, NEW_BLOCK_HASHES_PACKET, rlp);
@@ -1886,11 +2188,24 @@ impl ChainSync {
 			return 0;
 		}
 
-		let transactions = io.chain().pending_transactions();
+		let mut transactions = io.chain().pending_transactions();
 		if transactions.is_empty() {
 			return 0;
 		}
 
+		// Clear old transactions from stats
+		let pending_hashes = transactions.iter().map(|tx| tx.hash()).collect::<HashSet<H256>>();
+		self.transactions_stats.retain(&pending_hashes);
+
+		// Hold back local transactions until they are diffused
+		let diffused = match self.tx_diffusion {
+			true => self.select_diffused_transactions(io, &pending_hashes, &mut transactions),
+			false => Vec::new(),
+		};
+		if transactions.is_empty() {
+			return self.diffuse_transactions(io, diffused);
+		}
+
 		let all_transactions_hashes = transactions.iter().map(|tx| tx.hash()).collect::<HashSet<H256>>();
 		let all_transactions_rlp = {
 			let mut packet = RlpStream::new_list(transactions.len());
@@ -1898,9 +2213,6 @@ impl ChainSync {
 			packet.out()
 		};
 
-		// Clear old transactions from stats
-		self.transactions_stats.retain(&all_transactions_hashes);
-
 		// sqrt(x)/x scaled to max u32
 		let fraction = (self.peers.len() as f64).powf(-0.5).mul(u32::max_value() as f64).round() as u32;
 		let small = self.peers.len() < MIN_PEERS_PROPAGATION;
@@ -1955,14 +2267,133 @@ impl ChainSync {
 
 			trace!(target: "sync", "Sent up to {} transactions to {} peers.", transactions.len(), sent);
 		}
+		sent + self.diffuse_transactions(io, diffused)
+	}
+
+	/// Sends private transactions created by this node or received from peers to the
+	/// peers supporting them. Returns the number of packets sent.
+	pub fn propagate_private_transactions(&mut self, io: &mut SyncIo) -> usize {
+		let mut transactions = self.relayed_private_transactions.drain(..).map(|(peer, rlp)| (Some(peer), rlp)).collect::<Vec<_>>();
+		if let Some(ref provider) = self.private_tx {
+			transactions.extend(provider.drain_outgoing().into_iter().map(|rlp| (None, rlp)));
+		}
+		if transactions.is_empty() {
+			return 0;
+		}
+
+		let peers = self.peers.iter()
+			.filter(|&(_, peer)| peer.can_sync() && peer.capabilities.allows(Requirement::Par(PROTOCOL_VERSION_2)))
+			.map(|(peer_id, _)| *peer_id)
+			.collect::<Vec<_>>();
+		let mut sent = 0;
+		for (origin, rlp) in transactions {
+			self.known_private_transactions.insert(rlp.sha3(), ());
+			for peer_id in peers.iter().filter(|peer_id| Some(**peer_id) != origin) {
+				if let Err(e) = io.send_protocol(WARP_SYNC_PROTOCOL_ID, *peer_id, PRIVATE_TRANSACTION_PACKET, rlp.clone()) {
+					debug!(target: "sync", "Error sending private transaction: {:?}", e);
+					io.disable_peer(*peer_id);
+				}
+				sent += 1;
+			}
+		}
+		trace!(target: "sync", "Sent {} private transaction packets.", sent);
 		sent
 	}
 
+	/// Schedules first propagation of new local transactions after a random delay.
+	/// Removes local transactions from `transactions` until they have been diffused
+	/// and returns the ones which are due for diffusion now.
+	fn select_diffused_transactions(&mut self, io: &SyncIo, pending: &HashSet<H256>, transactions: &mut Vec<SignedTransaction>) -> Vec<SignedTransaction> {
+		let now = time::precise_time_ns();
+		let stale = self.local_transactions.keys().filter(|hash| !pending.contains(hash)).cloned().collect::<Vec<_>>();
+		for hash in stale {
+			self.local_transactions.remove(&hash);
+		}
+		for hash in io.chain().local_transactions() {
+			if pending.contains(&hash) && !self.local_transactions.contains_key(&hash) {
+				let delay_ms = ::rand::random::<u64>() % (MAX_TX_DIFFUSION_DELAY_MS + 1);
+				self.local_transactions.insert(hash, Some(now + delay_ms * 1_000_000));
+			}
+		}
+
+		let mut diffused = Vec::new();
+		let mut rest = Vec::with_capacity(transactions.len());
+		for tx in transactions.drain(..) {
+			let hash = tx.hash();
+			match self.local_transactions.get(&hash).cloned() {
+				Some(Some(release)) if release > now => {},
+				Some(Some(_)) => {
+					self.local_transactions.insert(hash, None);
+					diffused.push(tx);
+				},
+				_ => rest.push(tx),
+			}
+		}
+		*transactions = rest;
+		diffused
+	}
+
+	/// Sends transactions to a few random peers only.
+	fn diffuse_transactions(&mut self, io: &mut SyncIo, transactions: Vec<SignedTransaction>) -> usize {
+		if transactions.is_empty() {
+			return 0;
+		}
+
+		let mut peers = self.peers.keys().cloned().collect::<Vec<_>>();
+		::rand::thread_rng().shuffle(&mut peers);
+		peers.truncate(TX_DIFFUSION_PEERS);
+
+		let block_number = io.chain().chain_info().best_block_number;
+		let rlp = {
+			let mut packet = RlpStream::new_list(transactions.len());
+			for tx in &transactions { packet.append(tx); }
+			packet.out()
+		};
+		for peer_id in &peers {
+			if let Some(peer_info) = self.peers.get_mut(peer_id) {
+				let id = io.peer_session_info(*peer_id).and_then(|info| info.id);
+				for tx in &transactions {
+					peer_info.last_sent_transactions.insert(tx.hash());
+					self.transactions_stats.propagated(tx.hash(), id, block_number);
+				}
+			}
+			self.send_packet(io, *peer_id, TRANSACTIONS_PACKET, rlp.clone());
+		}
+
+		trace!(target: "sync", "Diffused {} local transactions to {} peers.", transactions.len(), peers.len());
+		peers.len()
+	}
+
+	/// Lagging peers which were not sent a head announcement within the last `MIN_HEAD_ANNOUNCE_INTERVAL_NS`.
+	fn get_announceable_peers(&mut self, chain_info: &BlockChainInfo, io: &SyncIo, now: u64) -> Vec<PeerId> {
+		let peers = self.get_lagging_peers(chain_info, io);
+		peers.into_iter()
+			.filter(|id| self.peers.get(id).map_or(false, |p| now.saturating_sub(p.last_head_announce) >= MIN_HEAD_ANNOUNCE_INTERVAL_NS))
+			.collect()
+	}
+
+	/// Announce the current head to lagging peers whose announcement was held back by the rate limit.
+	/// Consecutive heads imported in the meantime are squashed into a single `NewBlockHashes` message.
+	pub fn propagate_pending_head(&mut self, io: &mut SyncIo) -> usize {
+		if !io.is_chain_queue_empty() {
+			return 0;
+		}
+		let chain_info = io.chain().chain_info();
+		let peers = self.get_announceable_peers(&chain_info, io, time::precise_time_ns());
+		if peers.is_empty() {
+			return 0;
+		}
+		let hashes = self.propagate_new_hashes(&chain_info, io, &peers);
+		trace!(target: "sync", "Sent deferred head announcement to {} peers.", hashes);
+		hashes
+	}
+
 	fn propagate_latest_blocks(&mut self, io: &mut SyncIo, sealed: &[H256]) {
 		let chain_info = io.chain().chain_info();
 		if (((chain_info.best_block_number as i64) - (self.last_sent_block_number as i64)).abs() as BlockNumber) < MAX_PEER_LAG_PROPAGATION {
-			let mut peers = self.get_lagging_peers(&chain_info, io);
 			if sealed.is_empty() {
+				// peers announced to recently get the new head squashed with the following ones later.
+				let mut peers = self.get_announceable_peers(&chain_info, io, time::precise_time_ns());
 				let hashes = self.propagate_new_hashes(&chain_info, io, &peers);
 				peers = self.select_random_lagging_peers(&peers);
 				let blocks = self.propagate_blocks(&chain_info, io, sealed, &peers);
@@ -1970,12 +2401,14 @@ impl ChainSync {
 					trace!(target: "sync", "Sent latest {} blocks and {} hashes to peers.", blocks, hashes);
 				}
 			} else {
+				// the full block is an announcement in itself, no need to follow it with the hash.
+				let peers = self.get_lagging_peers(&chain_info, io);
 				self.propagate_blocks(&chain_info, io, sealed, &peers);
-				self.propagate_new_hashes(&chain_info, io, &peers);
 				trace!(target: "sync", "Sent sealed block to all peers");
 			};
 		}
 		self.propagate_new_transactions(io);
+		self.propagate_private_transactions(io);
 		self.last_sent_block_number = chain_info.best_block_number;
 	}
 
@@ -1997,12 +2430,32 @@ impl ChainSync {
 	}
 }
 
+/// Pick a snapshot to restore given `(peer, manifest hash, block number)` of the snapshots
+/// advertised by peers. This is the highest snapshot advertised by at least `SNAPSHOT_MIN_PEERS`
+/// peers, or if there is none, the one advertised by the most peers.
+/// Returns the manifest hash, peers advertising it and whether it is confirmed by enough peers.
+fn select_snapshot<I>(snapshots: I) -> Option<(H256, Vec<PeerId>, bool)> where I: IntoIterator<Item=(PeerId, H256, BlockNumber)> {
+	let mut votes: HashMap<(H256, BlockNumber), Vec<PeerId>> = HashMap::new();
+	for (peer, hash, number) in snapshots {
+		votes.entry((hash, number)).or_insert_with(Vec::new).push(peer);
+	}
+
+	let confirmed = votes.iter()
+		.filter(|&(_, peers)| peers.len() >= SNAPSHOT_MIN_PEERS)
+		.max_by_key(|&(&(_, number), peers)| (number, peers.len()))
+		.map(|(&(hash, _), peers)| (hash, peers.clone(), true));
+
+	confirmed.or_else(|| votes.iter()
+		.max_by_key(|&(&(_, number), peers)| (peers.len(), number))
+		.map(|(&(hash, _), peers)| (hash, peers.clone(), false)))
+}
+
 #[cfg(test)]
 mod tests {
 	use std::collections::{HashSet, VecDeque};
 	use tests::helpers::*;
 	use tests::snapshot::TestSnapshotService;
-	use util::{U256, RwLock};
+	use util::{U256, RwLock, Mutex};
 	use util::sha3::Hashable;
 	use util::hash::{H256, FixedHash};
 	use util::bytes::Bytes;
@@ -2010,6 +2463,9 @@ mod tests {
 	use super::*;
 	use ::SyncConfig;
 	use super::{PeerInfo, PeerAsking};
+	use propagation_stats::PeerArrivals;
+	use receipts_cache::ReceiptsCache;
+	use capabilities::{Capabilities, Requirement};
 	use ethcore::views::BlockView;
 	use ethcore::header::*;
 	use ethcore::client::*;
@@ -2054,6 +2510,39 @@ mod tests {
 		rlp.out()
 	}
 
+	#[test]
+	fn selects_highest_confirmed_snapshot() {
+		let (a, b, c) = (H256::from(1u64), H256::from(2u64), H256::from(3u64));
+		let snapshots = vec![
+			(1, a, 100), (2, a, 100), (3, a, 100), (4, a, 100),
+			(5, b, 200), (6, b, 200), (7, b, 200),
+			// lies about the block number of `b`.
+			(8, b, 300),
+			(9, c, 400),
+		];
+		assert_eq!(super::select_snapshot(snapshots), Some((b, vec![5, 6, 7], true)));
+	}
+
+	#[test]
+	fn selects_most_advertised_unconfirmed_snapshot() {
+		let (a, b) = (H256::from(1u64), H256::from(2u64));
+		let snapshots = vec![(1, a, 100), (2, a, 100), (3, b, 200)];
+		assert_eq!(super::select_snapshot(snapshots), Some((a, vec![1, 2], false)));
+		assert_eq!(super::select_snapshot(Vec::new()), None);
+	}
+
+	#[test]
+	fn packet_table_is_within_protocol_ranges() {
+		let mut ids = HashSet::new();
+		for packet in super::PACKETS {
+			assert!(ids.insert(packet.id), "duplicate packet {}", packet.name);
+			match packet.requires {
+				Requirement::Eth(_) => assert!(packet.id < super::ETH_PACKET_COUNT),
+				Requirement::Par(_) => assert!(packet.id >= super::ETH_PACKET_COUNT && packet.id < super::SNAPSHOT_SYNC_PACKET_COUNT),
+			}
+		}
+	}
+
 	#[test]
 	fn return_receipts_empty() {
 		let mut client = TestBlockChainClient::new();
@@ -2061,7 +2550,7 @@ mod tests {
 		let ss = TestSnapshotService::new();
 		let io = TestIo::new(&mut client, &ss, &mut queue, None);
 
-		let result = ChainSync::return_receipts(&io, &UntrustedRlp::new(&[0xc0]), 0);
+		let result = ChainSync::return_receipts(&io, &UntrustedRlp::new(&[0xc0]), 0, &Mutex::new(ReceiptsCache::default()));
 
 		assert!(result.is_ok());
 	}
@@ -2082,7 +2571,7 @@ mod tests {
 
 		let receipts_request = receipt_list.out();
 		// it returns rlp ONLY for hashes started with "f"
-		let result = ChainSync::return_receipts(&io, &UntrustedRlp::new(&receipts_request.clone()), 0);
+		let result = ChainSync::return_receipts(&io, &UntrustedRlp::new(&receipts_request.clone()), 0, &sync.receipts_cache);
 
 		assert!(result.is_ok());
 		let rlp_result = result.unwrap();
@@ -2090,10 +2579,14 @@ mod tests {
 
 		// the length of two rlp-encoded receipts
 		assert_eq!(603, rlp_result.unwrap().1.out().len());
+		assert_eq!((sync.status().receipts_cache_hits, sync.status().receipts_cache_misses), (0, 4));
 
 		io.sender = Some(2usize);
-		ChainSync::dispatch_packet(&RwLock::new(sync), &mut io, 0usize, super::GET_RECEIPTS_PACKET, &receipts_request);
+		let sync = RwLock::new(sync);
+		ChainSync::dispatch_packet(&sync, &mut io, 0usize, super::GET_RECEIPTS_PACKET, &receipts_request);
 		assert_eq!(1, io.queue.len());
+		// the found receipt sets are served from the cache the second time.
+		assert_eq!((sync.read().status().receipts_cache_hits, sync.read().status().receipts_cache_misses), (2, 6));
 	}
 
 	#[test]
@@ -2213,10 +2706,64 @@ mod tests {
 				snapshot_hash: None,
 				asking_snapshot_data: None,
 				block_set: None,
+				arrivals: PeerArrivals::default(),
+				download_rate: 0.0,
+				last_head_announce: 0,
+				capabilities: Capabilities { eth: 63, par: 1, les: 0 },
 			});
 		sync
 	}
 
+	#[test]
+	fn detects_headers_contradicting_checkpoints() {
+		let client = TestBlockChainClient::new();
+		let mut sync = dummy_sync_with_peer(H256::new(), &client);
+		let blocks: Vec<Bytes> = (1..4).map(|n| get_dummy_block(n, H256::zero())).collect();
+		let mut rlp = RlpStream::new_list(blocks.len());
+		for block in &blocks {
+			rlp.append_raw(&Rlp::new(block).at(0).as_raw(), 1);
+		}
+		let headers = rlp.out();
+		let headers = UntrustedRlp::new(&headers);
+
+		assert_eq!(sync.conflicting_checkpoint(&headers), Ok(None));
+		sync.checkpoints.insert(2, BlockView::new(&blocks[1]).header().hash());
+		assert_eq!(sync.conflicting_checkpoint(&headers), Ok(None));
+		assert_eq!(sync.confirmation_block(), Some((2, BlockView::new(&blocks[1]).header().hash())));
+		sync.checkpoints.insert(3, H256::from(1));
+		assert_eq!(sync.conflicting_checkpoint(&headers), Ok(Some(3)));
+	}
+
+	#[test]
+	fn rates_peer_downloads() {
+		let client = TestBlockChainClient::new();
+		let mut sync = dummy_sync_with_peer(H256::new(), &client);
+		let peer = sync.peers.get_mut(&0).unwrap();
+
+		// 100 items in a second.
+		peer.record_response(100, 1_000_000_000);
+		assert_eq!(peer.download_rate, 100.0);
+
+		// empty response lowers the rating.
+		peer.ask_time = 1_000_000_000;
+		peer.record_response(0, 2_000_000_000);
+		assert!((peer.download_rate - 70.0).abs() < 1e-9);
+	}
+
+	#[test]
+	fn gives_smaller_requests_to_slower_peers() {
+		let client = TestBlockChainClient::new();
+		let mut sync = dummy_sync_with_peer(H256::new(), &client);
+		assert_eq!(sync.download_share(0), 1.0);
+
+		let mut slow = sync.peers.get(&0).unwrap().clone();
+		slow.download_rate = 25.0;
+		sync.peers.get_mut(&0).unwrap().download_rate = 100.0;
+		sync.peers.insert(1, slow);
+		assert_eq!(sync.download_share(0), 1.0);
+		assert_eq!(sync.download_share(1), 0.25);
+	}
+
 	#[test]
 	fn finds_lagging_peers() {
 		let mut client = TestBlockChainClient::new();
@@ -2270,6 +2817,42 @@ mod tests {
 		assert_eq!(0x01, io.queue[0].packet_id);
 	}
 
+	#[test]
+	fn squashes_head_announcements() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		let mut queue = VecDeque::new();
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(5), &client);
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+
+		// first event just sets the marker
+		sync.chain_new_blocks(&mut io, &[], &[], &[], &[], &[]);
+		sync.chain_new_blocks(&mut io, &[], &[], &[], &[], &[]);
+		let sent = io.queue.len();
+		assert!(sent > 0);
+
+		// heads imported right after the announcement are held back
+		io.chain.add_blocks(3, EachBlockWith::Nothing);
+		sync.chain_new_blocks(&mut io, &[], &[], &[], &[], &[]);
+		assert_eq!(sent, io.queue.len());
+
+		let chain_info = io.chain.chain_info();
+		assert!(sync.get_announceable_peers(&chain_info, &io, time::precise_time_ns()).is_empty());
+		let peers = sync.get_announceable_peers(&chain_info, &io, time::precise_time_ns() + MIN_HEAD_ANNOUNCE_INTERVAL_NS);
+		assert_eq!(peers, vec![0]);
+
+		// and then announced together in a single message
+		assert_eq!(1, sync.propagate_new_hashes(&chain_info, &mut io, &peers));
+		assert_eq!(sent + 1, io.queue.len());
+		let packet = io.queue.back().unwrap();
+		assert_eq!(0x01, packet.packet_id);
+		let rlp = UntrustedRlp::new(&packet.data);
+		assert!(rlp.item_count() >= 3);
+		let last: H256 = rlp.at(rlp.item_count() - 1).unwrap().val_at(0).unwrap();
+		assert_eq!(chain_info.best_block_hash, last);
+	}
+
 	#[test]
 	fn sends_latest_block_to_lagging_peer() {
 		let mut client = TestBlockChainClient::new();
@@ -2338,6 +2921,31 @@ mod tests {
 		assert_eq!(0x02, io.queue[0].packet_id);
 	}
 
+	#[test]
+	fn diffuses_local_transactions() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		let hash = client.insert_local_transaction_to_queue();
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+		sync.tx_diffusion = true;
+		sync.local_transactions.insert(hash, Some(u64::max_value()));
+		let mut queue = VecDeque::new();
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+
+		// Held back until the release time
+		let peer_count = sync.propagate_new_transactions(&mut io);
+		sync.local_transactions.insert(hash, Some(0));
+		let peer_count2 = sync.propagate_new_transactions(&mut io);
+		let peer_count3 = sync.propagate_new_transactions(&mut io);
+
+		assert_eq!(0, peer_count);
+		assert_eq!(1, peer_count2);
+		assert_eq!(0, peer_count3);
+		assert_eq!(1, io.queue.len());
+		assert_eq!(sync.local_transactions.get(&hash), Some(&None));
+	}
+
 	#[test]
 	fn propagates_new_transactions_after_new_block() {
 		let mut client = TestBlockChainClient::new();
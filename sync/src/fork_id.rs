@@ -0,0 +1,182 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Fork identifiers exchanged during the handshake.
+//!
+//! A fork identifier is a checksum of the genesis hash and all fork blocks passed
+//! so far, together with the number of the next scheduled fork. It allows
+//! disconnecting peers with an incompatible fork configuration before any blocks
+//! are downloaded from them.
+
+use util::{H32, H256, Hashable, FixedHash};
+use rlp::*;
+use ethcore::header::BlockNumber;
+
+/// Fork identifier of a chain at some block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForkId {
+	/// Checksum of the genesis hash and the passed fork blocks.
+	pub hash: H32,
+	/// Next scheduled fork block or 0 if none is known.
+	pub next: BlockNumber,
+}
+
+impl Encodable for ForkId {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(2);
+		s.append(&self.hash);
+		s.append(&self.next);
+	}
+}
+
+impl Decodable for ForkId {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		Ok(ForkId {
+			hash: try!(d.val_at(0)),
+			next: try!(d.val_at(1)),
+		})
+	}
+}
+
+/// Reason for rejecting a remote fork identifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForkIdError {
+	/// Remote node is on our chain but is missing a fork we have already passed.
+	RemoteStale,
+	/// Fork configurations differ.
+	Incompatible,
+}
+
+/// Validates remote fork identifiers against the local fork configuration.
+#[derive(Debug, Clone)]
+pub struct ForkFilter {
+	forks: Vec<BlockNumber>,
+	hashes: Vec<H32>,
+}
+
+impl ForkFilter {
+	/// Create a filter for the chain with given genesis hash and fork blocks.
+	pub fn new<I>(genesis: H256, forks: I) -> Self where I: IntoIterator<Item=BlockNumber> {
+		let mut forks: Vec<_> = forks.into_iter().filter(|n| *n != 0).collect();
+		forks.sort();
+		forks.dedup();
+
+		let mut data = genesis.to_vec();
+		let mut hashes = vec![checksum(&data)];
+		for fork in &forks {
+			data.extend((0..8u64).rev().map(|i| (*fork >> (i * 8)) as u8));
+			hashes.push(checksum(&data));
+		}
+
+		ForkFilter {
+			forks: forks,
+			hashes: hashes,
+		}
+	}
+
+	fn passed(&self, head: BlockNumber) -> usize {
+		self.forks.iter().take_while(|fork| **fork <= head).count()
+	}
+
+	/// Fork identifier at the given head block.
+	pub fn current(&self, head: BlockNumber) -> ForkId {
+		let passed = self.passed(head);
+		ForkId {
+			hash: self.hashes[passed],
+			next: self.forks.get(passed).cloned().unwrap_or(0),
+		}
+	}
+
+	/// Check whether a peer announcing `remote` is compatible with us at the given head block.
+	pub fn validate(&self, head: BlockNumber, remote: &ForkId) -> Result<(), ForkIdError> {
+		let passed = self.passed(head);
+		match self.hashes.iter().position(|hash| *hash == remote.hash) {
+			// same forks passed; reject if remote schedules a fork we have already passed without it.
+			Some(index) if index == passed => match remote.next {
+				0 => Ok(()),
+				next if next <= head => Err(ForkIdError::Incompatible),
+				_ => Ok(()),
+			},
+			// remote is behind; its next fork must be the one we know of.
+			Some(index) if index < passed => match remote.next == self.forks[index] {
+				true => Ok(()),
+				false => Err(ForkIdError::RemoteStale),
+			},
+			// remote is ahead, we might still be syncing.
+			Some(_) => Ok(()),
+			None => Err(ForkIdError::Incompatible),
+		}
+	}
+}
+
+fn checksum(data: &[u8]) -> H32 {
+	H32::from_slice(&data.sha3()[..4])
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{ForkFilter, ForkId, ForkIdError};
+	use util::{H256, H32};
+	use rlp::{encode, decode};
+
+	fn filter() -> ForkFilter {
+		ForkFilter::new(H256::from(1), vec![20, 10, 0, 10])
+	}
+
+	#[test]
+	fn should_compute_current_fork_id() {
+		let filter = filter();
+		assert_eq!(filter.current(0).next, 10);
+		assert_eq!(filter.current(9), filter.current(0));
+		assert_eq!(filter.current(10).next, 20);
+		assert!(filter.current(10).hash != filter.current(9).hash);
+		assert_eq!(filter.current(25).next, 0);
+	}
+
+	#[test]
+	fn should_accept_compatible_peers() {
+		let filter = filter();
+		// same state
+		assert_eq!(filter.validate(15, &filter.current(15)), Ok(()));
+		// remote not aware of any further forks
+		assert_eq!(filter.validate(15, &ForkId { next: 0, ..filter.current(15) }), Ok(()));
+		// remote behind, but knows our next fork
+		assert_eq!(filter.validate(25, &filter.current(5)), Ok(()));
+		// remote ahead
+		assert_eq!(filter.validate(5, &filter.current(25)), Ok(()));
+	}
+
+	#[test]
+	fn should_reject_incompatible_peers() {
+		let filter = filter();
+		// remote passed a fork we don't know of
+		assert_eq!(filter.validate(15, &ForkId { hash: H32::from(0xdeadbeefu64), next: 0 }), Err(ForkIdError::Incompatible));
+		// remote behind and scheduled a different fork
+		assert_eq!(filter.validate(25, &ForkId { next: 12, ..filter.current(5) }), Err(ForkIdError::RemoteStale));
+		// remote schedules a fork we have passed without it
+		assert_eq!(filter.validate(25, &ForkId { next: 22, ..filter.current(25) }), Err(ForkIdError::Incompatible));
+		// different genesis
+		let other = ForkFilter::new(H256::from(2), vec![10, 20]);
+		assert_eq!(filter.validate(5, &other.current(5)), Err(ForkIdError::Incompatible));
+	}
+
+	#[test]
+	fn should_roundtrip_rlp() {
+		let id = filter().current(10);
+		assert_eq!(decode::<ForkId>(&encode(&id)), id);
+	}
+}
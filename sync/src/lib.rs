@@ -36,6 +36,8 @@ extern crate rand;
 extern crate semver;
 extern crate parking_lot;
 extern crate rlp;
+extern crate lru_cache;
+extern crate ethcore_light as light;
 
 #[macro_use]
 extern crate log;
@@ -52,6 +54,11 @@ mod block_sync;
 mod sync_io;
 mod snapshot;
 mod transactions_stats;
+mod propagation_stats;
+mod misbehavior;
+mod fork_id;
+mod receipts_cache;
+mod capabilities;
 
 #[cfg(test)]
 mod tests;
@@ -61,10 +68,12 @@ mod api {
 	include!(concat!(env!("OUT_DIR"), "/api.rs"));
 }
 
-pub use api::{EthSync, SyncProvider, SyncClient, NetworkManagerClient, ManageNetwork, SyncConfig,
-	ServiceConfiguration, NetworkConfiguration, PeerInfo, AllowIP, TransactionStats};
+pub use api::{EthSync, SyncProvider, SyncClient, NetworkManagerClient, ManageNetwork, SyncConfig, LightServing,
+	ServiceConfiguration, NetworkConfiguration, PeerInfo, AllowIP, TransactionStats, PeerPropagationStats};
 pub use chain::{SyncStatus, SyncState};
-pub use network::{is_valid_node_url, NonReservedPeerMode, NetworkError};
+pub use light::provider::{Provider as LightProvider, ClientProvider as LightClientProvider};
+pub use network::node_key;
+pub use network::{is_valid_node_url, NonReservedPeerMode, NetworkError, ConnectionFilter, ConnectionDirection, Proxy};
 
 /// IPC interfaces
 #[cfg(feature="ipc")]
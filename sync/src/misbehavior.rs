@@ -0,0 +1,172 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Misbehavior scores of peers serving invalid data.
+//!
+//! Every offence adds to the node's score, which halves every `HALF_LIFE_SEC`.
+//! Nodes whose score reaches `BAN_THRESHOLD` are refused for `BAN_DURATION_SEC`.
+
+use std::collections::HashMap;
+use util::H512;
+
+type NodeId = H512;
+
+/// Seconds after which a misbehavior score halves.
+const HALF_LIFE_SEC: f64 = 600.0;
+/// Score at which a node is banned.
+const BAN_THRESHOLD: f64 = 3.0;
+/// Duration of a ban.
+const BAN_DURATION_SEC: u64 = 1800;
+/// Scores below this value are forgotten.
+const FORGET_THRESHOLD: f64 = 0.01;
+
+/// Severity of an offence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Offence {
+	/// Served data which does not match the request (e.g. bodies not matching headers).
+	InvalidData,
+	/// Served a block which failed verification (e.g. invalid PoW).
+	BadBlock,
+	/// Served a snapshot manifest other than the one it advertised. Honest peers do so
+	/// when they produce a new snapshot in between, so it weighs little.
+	BadManifest,
+	/// Served a snapshot chunk other than the one requested.
+	BadChunk,
+	/// Served a block header which contradicts a trusted checkpoint.
+	CheckpointMismatch,
+}
+
+impl Offence {
+	fn score(&self) -> f64 {
+		match *self {
+			Offence::InvalidData => 1.0,
+			Offence::BadBlock => 2.0,
+			Offence::BadManifest => 1.0,
+			Offence::BadChunk => 2.0,
+			Offence::CheckpointMismatch => BAN_THRESHOLD,
+		}
+	}
+}
+
+#[derive(Debug)]
+struct Score {
+	value: f64,
+	updated: u64,
+}
+
+impl Score {
+	fn decayed(&self, now: u64) -> f64 {
+		let elapsed = now.saturating_sub(self.updated) as f64 / 1_000_000_000f64;
+		self.value * (0.5f64).powf(elapsed / HALF_LIFE_SEC)
+	}
+}
+
+/// Misbehavior scores and bans of nodes. Times are in nanoseconds.
+#[derive(Debug, Default)]
+pub struct Misbehavior {
+	scores: HashMap<NodeId, Score>,
+	banned: HashMap<NodeId, u64>,
+}
+
+impl Misbehavior {
+	/// Records an offence. Returns true if the node got banned.
+	pub fn report(&mut self, id: NodeId, offence: Offence, now: u64) -> bool {
+		let value = {
+			let score = self.scores.entry(id).or_insert(Score { value: 0.0, updated: now });
+			score.value = score.decayed(now) + offence.score();
+			score.updated = now;
+			score.value
+		};
+
+		if value >= BAN_THRESHOLD {
+			self.scores.remove(&id);
+			self.banned.insert(id, now + BAN_DURATION_SEC * 1_000_000_000);
+			true
+		} else {
+			false
+		}
+	}
+
+	/// Returns true if the node is currently banned.
+	pub fn is_banned(&self, id: &NodeId, now: u64) -> bool {
+		self.banned.get(id).map_or(false, |until| *until > now)
+	}
+
+	/// Removes expired bans and negligible scores.
+	pub fn collect_garbage(&mut self, now: u64) {
+		let expired: Vec<_> = self.banned.iter().filter(|&(_, until)| *until <= now).map(|(id, _)| *id).collect();
+		for id in expired {
+			self.banned.remove(&id);
+		}
+
+		let forgotten: Vec<_> = self.scores.iter().filter(|&(_, score)| score.decayed(now) < FORGET_THRESHOLD).map(|(id, _)| *id).collect();
+		for id in forgotten {
+			self.scores.remove(&id);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Misbehavior, Offence, HALF_LIFE_SEC, BAN_DURATION_SEC};
+
+	const SEC: u64 = 1_000_000_000;
+
+	#[test]
+	fn should_ban_after_repeated_offences() {
+		let mut misbehavior = Misbehavior::default();
+		let id = 1.into();
+
+		assert!(!misbehavior.report(id, Offence::InvalidData, 0));
+		assert!(!misbehavior.report(id, Offence::InvalidData, SEC));
+		assert!(misbehavior.report(id, Offence::InvalidData, 2 * SEC));
+		assert!(misbehavior.is_banned(&id, 3 * SEC));
+		assert!(!misbehavior.is_banned(&id, 3 * SEC + BAN_DURATION_SEC * SEC));
+	}
+
+	#[test]
+	fn should_not_ban_for_single_manifest_mismatch() {
+		let mut misbehavior = Misbehavior::default();
+		let id = 1.into();
+
+		assert!(!misbehavior.report(id, Offence::BadManifest, 0));
+		assert!(misbehavior.report(id, Offence::BadChunk, 0));
+	}
+
+	#[test]
+	fn should_decay_scores() {
+		let mut misbehavior = Misbehavior::default();
+		let id = 1.into();
+		let half_life = HALF_LIFE_SEC as u64 * SEC;
+
+		misbehavior.report(id, Offence::BadBlock, 0);
+		// score decayed to 1.0 before the next offence.
+		assert!(!misbehavior.report(id, Offence::InvalidData, half_life));
+		assert!(misbehavior.report(id, Offence::InvalidData, half_life));
+	}
+
+	#[test]
+	fn should_forget_old_scores_and_bans() {
+		let mut misbehavior = Misbehavior::default();
+		let id = 1.into();
+		misbehavior.report(id, Offence::BadBlock, 0);
+		misbehavior.report(id, Offence::BadBlock, 0);
+
+		misbehavior.collect_garbage(BAN_DURATION_SEC * SEC);
+		assert!(misbehavior.banned.is_empty());
+		assert!(misbehavior.scores.is_empty());
+	}
+}
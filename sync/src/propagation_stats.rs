@@ -0,0 +1,202 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-peer statistics of how early new blocks and transactions arrive
+//! compared to other peers.
+
+use std::collections::{HashSet, VecDeque};
+use api::PeerPropagationStats;
+use network::PeerId;
+use util::H256;
+use util::hash::H256FastMap;
+
+/// Number of recent block hashes remembered for arrival comparison.
+const BLOCKS_TO_REMEMBER: usize = 1024;
+/// Number of recent transaction hashes remembered for arrival comparison.
+const TRANSACTIONS_TO_REMEMBER: usize = 16384;
+
+/// Arrival statistics of a single peer.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct PeerArrivals {
+	/// Blocks this peer announced before any other peer.
+	blocks_first: u64,
+	/// All blocks announced by this peer.
+	blocks_total: u64,
+	/// Sum of delays (in ms) of blocks announced after another peer.
+	blocks_delay_ms: u64,
+	/// Transactions this peer sent before any other peer.
+	transactions_first: u64,
+	/// All transactions sent by this peer.
+	transactions_total: u64,
+}
+
+impl<'a> From<&'a PeerArrivals> for PeerPropagationStats {
+	fn from(other: &'a PeerArrivals) -> Self {
+		let late = other.blocks_total - other.blocks_first;
+		PeerPropagationStats {
+			blocks_first: other.blocks_first,
+			blocks_total: other.blocks_total,
+			average_block_delay_ms: if late == 0 { 0 } else { other.blocks_delay_ms / late },
+			transactions_first: other.transactions_first,
+			transactions_total: other.transactions_total,
+		}
+	}
+}
+
+#[derive(Debug, PartialEq)]
+enum Arrival {
+	/// No other peer delivered the item before.
+	First,
+	/// Delivered the given number of milliseconds after the first peer.
+	Later(u64),
+	/// Already delivered by the same peer.
+	Repeated,
+}
+
+/// Bounded record of when an item was first seen and which peers delivered it.
+struct FirstSeen {
+	limit: usize,
+	order: VecDeque<H256>,
+	seen: H256FastMap<(u64, HashSet<PeerId>)>,
+}
+
+impl FirstSeen {
+	fn new(limit: usize) -> Self {
+		FirstSeen {
+			limit: limit,
+			order: VecDeque::new(),
+			seen: Default::default(),
+		}
+	}
+
+	fn arrived(&mut self, hash: H256, peer: PeerId, now_ms: u64) -> Arrival {
+		if let Some(&mut (first_seen, ref mut peers)) = self.seen.get_mut(&hash) {
+			return match peers.insert(peer) {
+				true => Arrival::Later(now_ms.saturating_sub(first_seen)),
+				false => Arrival::Repeated,
+			};
+		}
+
+		if self.order.len() == self.limit {
+			if let Some(oldest) = self.order.pop_front() {
+				self.seen.remove(&oldest);
+			}
+		}
+		let mut peers = HashSet::new();
+		peers.insert(peer);
+		self.seen.insert(hash, (now_ms, peers));
+		self.order.push_back(hash);
+		Arrival::First
+	}
+}
+
+/// Tracks arrivals of new blocks and transactions across peers.
+pub struct PropagationStats {
+	blocks: FirstSeen,
+	transactions: FirstSeen,
+}
+
+impl Default for PropagationStats {
+	fn default() -> Self {
+		PropagationStats {
+			blocks: FirstSeen::new(BLOCKS_TO_REMEMBER),
+			transactions: FirstSeen::new(TRANSACTIONS_TO_REMEMBER),
+		}
+	}
+}
+
+impl PropagationStats {
+	/// Records a new block announced by given peer.
+	pub fn block_arrived(&mut self, hash: H256, peer: PeerId, now_ms: u64, arrivals: &mut PeerArrivals) {
+		match self.blocks.arrived(hash, peer, now_ms) {
+			Arrival::First => {
+				arrivals.blocks_first += 1;
+				arrivals.blocks_total += 1;
+			},
+			Arrival::Later(delay) => {
+				arrivals.blocks_total += 1;
+				arrivals.blocks_delay_ms += delay;
+			},
+			Arrival::Repeated => {},
+		}
+	}
+
+	/// Records a transaction sent by given peer.
+	pub fn transaction_arrived(&mut self, hash: H256, peer: PeerId, now_ms: u64, arrivals: &mut PeerArrivals) {
+		match self.transactions.arrived(hash, peer, now_ms) {
+			Arrival::First => {
+				arrivals.transactions_first += 1;
+				arrivals.transactions_total += 1;
+			},
+			Arrival::Later(_) => arrivals.transactions_total += 1,
+			Arrival::Repeated => {},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use api::PeerPropagationStats;
+	use super::{FirstSeen, Arrival, PropagationStats, PeerArrivals};
+
+	#[test]
+	fn should_report_first_later_and_repeated_arrivals() {
+		let mut seen = FirstSeen::new(2);
+
+		assert_eq!(seen.arrived(1.into(), 1, 100), Arrival::First);
+		assert_eq!(seen.arrived(1.into(), 2, 150), Arrival::Later(50));
+		assert_eq!(seen.arrived(1.into(), 2, 170), Arrival::Repeated);
+	}
+
+	#[test]
+	fn should_forget_oldest_hashes() {
+		let mut seen = FirstSeen::new(2);
+		seen.arrived(1.into(), 1, 100);
+		seen.arrived(2.into(), 1, 100);
+		seen.arrived(3.into(), 1, 100);
+
+		assert_eq!(seen.arrived(1.into(), 2, 200), Arrival::First);
+	}
+
+	#[test]
+	fn should_compute_peer_stats() {
+		let mut stats = PropagationStats::default();
+		let mut fast = PeerArrivals::default();
+		let mut slow = PeerArrivals::default();
+
+		stats.block_arrived(1.into(), 1, 1000, &mut fast);
+		stats.block_arrived(1.into(), 2, 1200, &mut slow);
+		stats.block_arrived(2.into(), 1, 2000, &mut fast);
+		stats.block_arrived(2.into(), 2, 2400, &mut slow);
+		stats.transaction_arrived(3.into(), 2, 2500, &mut slow);
+		stats.transaction_arrived(3.into(), 1, 2600, &mut fast);
+
+		assert_eq!(PeerPropagationStats::from(&fast), PeerPropagationStats {
+			blocks_first: 2,
+			blocks_total: 2,
+			average_block_delay_ms: 0,
+			transactions_first: 0,
+			transactions_total: 1,
+		});
+		assert_eq!(PeerPropagationStats::from(&slow), PeerPropagationStats {
+			blocks_first: 0,
+			blocks_total: 2,
+			average_block_delay_ms: 300,
+			transactions_first: 1,
+			transactions_total: 1,
+		});
+	}
+}
@@ -0,0 +1,149 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Cache of recently served block receipts.
+//!
+//! Fast-syncing peers request receipts of the same blocks from many nodes and
+//! often repeat their requests. Receipts of a block never change, so encoded
+//! receipt sets are kept by block hash without invalidation.
+
+use lru_cache::LruCache;
+use util::{Bytes, H256};
+
+/// Maximum number of receipt sets kept.
+const MAX_ENTRIES: usize = 4096;
+/// Maximum total size of the kept receipt sets, in bytes.
+const MAX_SIZE: usize = 32 * 1024 * 1024;
+
+/// LRU of encoded receipt sets by block hash, bounded by entry count and total size.
+pub struct ReceiptsCache {
+	cache: LruCache<H256, Bytes>,
+	size: usize,
+	max_entries: usize,
+	max_size: usize,
+	hits: u64,
+	misses: u64,
+}
+
+impl Default for ReceiptsCache {
+	fn default() -> Self {
+		ReceiptsCache::new(MAX_ENTRIES, MAX_SIZE)
+	}
+}
+
+impl ReceiptsCache {
+	/// Create a cache holding up to `max_entries` receipt sets of `max_size` bytes in total.
+	pub fn new(max_entries: usize, max_size: usize) -> Self {
+		ReceiptsCache {
+			// evicted manually, so that the total size stays accounted for.
+			cache: LruCache::new(usize::max_value()),
+			size: 0,
+			max_entries: max_entries,
+			max_size: max_size,
+			hits: 0,
+			misses: 0,
+		}
+	}
+
+	/// Encoded receipts of given block, if cached. Counts towards the hit rate.
+	pub fn get(&mut self, hash: &H256) -> Option<Bytes> {
+		match self.cache.get_mut(hash) {
+			Some(receipts) => {
+				self.hits += 1;
+				Some(receipts.clone())
+			},
+			None => {
+				self.misses += 1;
+				None
+			},
+		}
+	}
+
+	/// Keep encoded receipts of given block, evicting the least recently used sets if needed.
+	pub fn insert(&mut self, hash: H256, receipts: Bytes) {
+		if receipts.len() > self.max_size {
+			return;
+		}
+
+		self.size += receipts.len();
+		if let Some(old) = self.cache.insert(hash, receipts) {
+			self.size -= old.len();
+		}
+		while self.size > self.max_size || self.cache.len() > self.max_entries {
+			match self.cache.remove_lru() {
+				Some((_, evicted)) => self.size -= evicted.len(),
+				None => break,
+			}
+		}
+	}
+
+	/// Number of requested receipt sets served from the cache.
+	pub fn hits(&self) -> u64 {
+		self.hits
+	}
+
+	/// Number of requested receipt sets which were not cached.
+	pub fn misses(&self) -> u64 {
+		self.misses
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::ReceiptsCache;
+
+	#[test]
+	fn should_count_hits_and_misses() {
+		let mut cache = ReceiptsCache::default();
+
+		assert_eq!(cache.get(&1.into()), None);
+		cache.insert(1.into(), vec![0xc0]);
+		assert_eq!(cache.get(&1.into()), Some(vec![0xc0]));
+		assert_eq!(cache.get(&2.into()), None);
+
+		assert_eq!(cache.hits(), 1);
+		assert_eq!(cache.misses(), 2);
+	}
+
+	#[test]
+	fn should_evict_least_recently_used_over_size() {
+		let mut cache = ReceiptsCache::new(16, 10);
+		cache.insert(1.into(), vec![0; 4]);
+		cache.insert(2.into(), vec![0; 4]);
+		assert!(cache.get(&1.into()).is_some());
+		cache.insert(3.into(), vec![0; 4]);
+
+		assert!(cache.get(&1.into()).is_some());
+		assert!(cache.get(&2.into()).is_none());
+		assert!(cache.get(&3.into()).is_some());
+
+		cache.insert(4.into(), vec![0; 11]);
+		assert!(cache.get(&4.into()).is_none());
+	}
+
+	#[test]
+	fn should_account_for_size_when_evicting_over_count() {
+		let mut cache = ReceiptsCache::new(2, 10);
+		cache.insert(1.into(), vec![0; 4]);
+		cache.insert(2.into(), vec![0; 4]);
+		cache.insert(3.into(), vec![0; 4]);
+
+		// the evicted set no longer counts towards the size limit.
+		assert!(cache.get(&1.into()).is_none());
+		assert!(cache.get(&2.into()).is_some());
+		assert!(cache.get(&3.into()).is_some());
+	}
+}
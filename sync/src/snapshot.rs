@@ -31,6 +31,7 @@ pub struct Snapshot {
 	downloading_chunks: HashSet<H256>,
 	completed_chunks: HashSet<H256>,
 	snapshot_hash: Option<H256>,
+	bad_hashes: HashSet<H256>,
 }
 
 impl Snapshot {
@@ -42,6 +43,7 @@ impl Snapshot {
 			downloading_chunks: HashSet::new(),
 			completed_chunks: HashSet::new(),
 			snapshot_hash: None,
+			bad_hashes: HashSet::new(),
 		}
 	}
 
@@ -54,6 +56,19 @@ impl Snapshot {
 		self.snapshot_hash = None;
 	}
 
+	/// Clear everything, remembering the current snapshot as one which failed to restore.
+	pub fn note_failed(&mut self) {
+		if let Some(hash) = self.snapshot_hash {
+			self.bad_hashes.insert(hash);
+		}
+		self.clear();
+	}
+
+	/// Check if a snapshot with given manifest hash failed to restore before.
+	pub fn is_known_bad(&self, hash: &H256) -> bool {
+		self.bad_hashes.contains(hash)
+	}
+
 	/// Reset collection for a manifest RLP
 	pub fn reset_to(&mut self, manifest: &ManifestData, hash: &H256) {
 		self.clear();
@@ -139,6 +154,7 @@ mod test {
 		let state_chunks: Vec<Bytes> = (0..20).map(|_| H256::random().to_vec()).collect();
 		let block_chunks: Vec<Bytes> = (0..20).map(|_| H256::random().to_vec()).collect();
 		let manifest = ManifestData {
+			version: 2,
 			state_hashes: state_chunks.iter().map(|data| data.sha3()).collect(),
 			block_hashes: block_chunks.iter().map(|data| data.sha3()).collect(),
 			state_root: H256::new(),
@@ -160,6 +176,17 @@ mod test {
 		assert!(is_empty(&snapshot));
 	}
 
+	#[test]
+	fn remember_failed() {
+		let mut snapshot = Snapshot::new();
+		let (manifest, mhash, _, _,) = test_manifest();
+		snapshot.reset_to(&manifest, &mhash);
+		assert!(!snapshot.is_known_bad(&mhash));
+		snapshot.note_failed();
+		assert!(is_empty(&snapshot));
+		assert!(snapshot.is_known_bad(&mhash));
+	}
+
 	#[test]
 	fn validate_chunks() {
 		let mut snapshot = Snapshot::new();
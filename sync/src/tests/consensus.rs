@@ -0,0 +1,101 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use util::*;
+use ethcore::account_provider::AccountProvider;
+use ethcore::client::BlockChainClient;
+use ethcore::miner::MinerService;
+use ethcore::spec::Spec;
+use super::helpers::*;
+use SyncConfig;
+
+// `TestAuthorityRound` with steps long enough to only be advanced by the test.
+const AUTHORITY_ROUND_SPEC: &'static str = r#"{
+	"name": "TestAuthorityRoundManualSteps",
+	"engine": {
+		"AuthorityRound": {
+			"params": {
+				"gasLimitBoundDivisor": "0x0400",
+				"stepDuration": "100000000",
+				"authorities" : [
+					"0x7d577a597b2742b498cb5cf0c26cdcd726d39e6e",
+					"0x82a978b3f5962a5b0957d9ee9eef472ee55b42f1"
+				]
+			}
+		}
+	},
+	"params": {
+		"accountStartNonce": "0x0",
+		"maximumExtraDataSize": "0x20",
+		"minGasLimit": "0x1388",
+		"networkID" : "0x69"
+	},
+	"genesis": {
+		"seal": {
+			"generic": {
+				"fields": 2,
+				"rlp": "0x200"
+			}
+		},
+		"difficulty": "0x20000",
+		"author": "0x0000000000000000000000000000000000000000",
+		"timestamp": "0x00",
+		"parentHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+		"extraData": "0x",
+		"gasLimit": "0x2fefd8"
+	},
+	"accounts": {
+		"0000000000000000000000000000000000000001": { "balance": "1", "nonce": "1048576", "builtin": { "name": "ecrecover", "pricing": { "linear": { "base": 3000, "word": 0 } } } },
+		"0000000000000000000000000000000000000002": { "balance": "1", "nonce": "1048576", "builtin": { "name": "sha256", "pricing": { "linear": { "base": 60, "word": 12 } } } },
+		"0000000000000000000000000000000000000003": { "balance": "1", "nonce": "1048576", "builtin": { "name": "ripemd160", "pricing": { "linear": { "base": 600, "word": 120 } } } },
+		"0000000000000000000000000000000000000004": { "balance": "1", "nonce": "1048576", "builtin": { "name": "identity", "pricing": { "linear": { "base": 15, "word": 3 } } } }
+	}
+}"#;
+
+fn authority_round_spec() -> Spec {
+	Spec::load(AUTHORITY_ROUND_SPEC.as_bytes()).expect("spec is valid; qed")
+}
+
+#[test]
+fn authority_round() {
+	::env_logger::init().ok();
+	let ap = Arc::new(AccountProvider::transient_provider());
+	let s0 = ap.insert_account("1".sha3(), "").unwrap();
+	ap.unlock_account_permanently(s0, "".into()).unwrap();
+	let s1 = ap.insert_account("2".sha3(), "").unwrap();
+	ap.unlock_account_permanently(s1, "".into()).unwrap();
+
+	let mut net = TestNet::with_spec(2, SyncConfig::default(), authority_round_spec, Some(ap));
+	net.peer(0).chain.miner().set_author(s0);
+	net.peer(1).chain.miner().set_author(s1);
+	net.sync();
+
+	for _ in 0..6 {
+		// exactly one authority proposes in each step.
+		let proposers: Vec<_> = (0..2).filter(|&i| net.seal_and_propagate(i)).collect();
+		assert_eq!(proposers.len(), 1);
+		// a proposer seals only once per step.
+		assert!(!net.seal_and_propagate(proposers[0]));
+
+		while !net.done() {
+			net.sync_step();
+		}
+		assert!(net.is_consensus());
+		net.step_engines();
+	}
+	assert_eq!(net.peer(0).chain.chain_info().best_block_number, 6);
+	assert_eq!(net.peer(1).chain.chain_info().best_block_number, 6);
+}
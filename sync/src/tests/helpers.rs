@@ -17,16 +17,49 @@
 use util::*;
 use network::*;
 use tests::snapshot::*;
-use ethcore::client::{TestBlockChainClient, BlockChainClient};
+use util::kvdb::in_memory;
+use io::IoChannel;
+use ethcore::account_provider::AccountProvider;
+use ethcore::client::{TestBlockChainClient, BlockChainClient, Client, ClientConfig};
+use ethcore::db::NUM_COLUMNS;
 use ethcore::header::BlockNumber;
+use ethcore::miner::{Miner, MinerService};
 use ethcore::snapshot::SnapshotService;
+use ethcore::spec::Spec;
 use sync_io::SyncIo;
 use api::WARP_SYNC_PROTOCOL_ID;
+use light::net::PROTOCOL_ID as LIGHT_PROTOCOL_ID;
 use chain::ChainSync;
 use ::SyncConfig;
 
-pub struct TestIo<'p> {
-	pub chain: &'p mut TestBlockChainClient,
+/// Chain backing a test peer.
+pub trait TestChain {
+	/// The chain as seen by the sync.
+	fn client(&self) -> &BlockChainClient;
+
+	/// Import all the blocks queued so far.
+	fn flush(&self) {}
+}
+
+impl TestChain for TestBlockChainClient {
+	fn client(&self) -> &BlockChainClient {
+		self
+	}
+}
+
+impl TestChain for Arc<Client> {
+	fn client(&self) -> &BlockChainClient {
+		&**self
+	}
+
+	fn flush(&self) {
+		self.flush_queue();
+		self.import_verified_blocks();
+	}
+}
+
+pub struct TestIo<'p, C> where C: TestChain + 'p {
+	pub chain: &'p mut C,
 	pub snapshot_service: &'p TestSnapshotService,
 	pub queue: &'p mut VecDeque<TestPacket>,
 	pub sender: Option<PeerId>,
@@ -34,8 +67,8 @@ pub struct TestIo<'p> {
 	overlay: RwLock<HashMap<BlockNumber, Bytes>>,
 }
 
-impl<'p> TestIo<'p> {
-	pub fn new(chain: &'p mut TestBlockChainClient, ss: &'p TestSnapshotService, queue: &'p mut VecDeque<TestPacket>, sender: Option<PeerId>) -> TestIo<'p> {
+impl<'p, C> TestIo<'p, C> where C: TestChain + 'p {
+	pub fn new(chain: &'p mut C, ss: &'p TestSnapshotService, queue: &'p mut VecDeque<TestPacket>, sender: Option<PeerId>) -> TestIo<'p, C> {
 		TestIo {
 			chain: chain,
 			snapshot_service: ss,
@@ -47,7 +80,7 @@ impl<'p> TestIo<'p> {
 	}
 }
 
-impl<'p> SyncIo for TestIo<'p> {
+impl<'p, C> SyncIo for TestIo<'p, C> where C: TestChain + 'p {
 	fn disable_peer(&mut self, peer_id: PeerId) {
 		self.disconnect_peer(peer_id);
 	}
@@ -83,7 +116,7 @@ impl<'p> SyncIo for TestIo<'p> {
 	}
 
 	fn chain(&self) -> &BlockChainClient {
-		self.chain
+		self.chain.client()
 	}
 
 	fn snapshot_service(&self) -> &SnapshotService {
@@ -99,7 +132,13 @@ impl<'p> SyncIo for TestIo<'p> {
 	}
 
 	fn protocol_version(&self, protocol: &ProtocolId, peer_id: PeerId) -> u8 {
-		if protocol == &WARP_SYNC_PROTOCOL_ID { 1 } else { self.eth_protocol_version(peer_id) }
+		if protocol == &WARP_SYNC_PROTOCOL_ID {
+			1
+		} else if protocol == &LIGHT_PROTOCOL_ID {
+			0
+		} else {
+			self.eth_protocol_version(peer_id)
+		}
 	}
 
 	fn chain_overlay(&self) -> &RwLock<HashMap<BlockNumber, Bytes>> {
@@ -113,20 +152,20 @@ pub struct TestPacket {
 	pub recipient: PeerId,
 }
 
-pub struct TestPeer {
-	pub chain: TestBlockChainClient,
+pub struct TestPeer<C = TestBlockChainClient> {
+	pub chain: C,
 	pub snapshot_service: Arc<TestSnapshotService>,
 	pub sync: RwLock<ChainSync>,
 	pub queue: VecDeque<TestPacket>,
 }
 
-pub struct TestNet {
-	pub peers: Vec<TestPeer>,
+pub struct TestNet<C = TestBlockChainClient> {
+	pub peers: Vec<TestPeer<C>>,
 	pub started: bool,
 	pub disconnect_events: Vec<(PeerId, PeerId)>, //disconnected (initiated by, to)
 }
 
-impl TestNet {
+impl TestNet<TestBlockChainClient> {
 	pub fn new(n: usize) -> TestNet {
 		Self::new_with_config(n, SyncConfig::default())
 	}
@@ -156,12 +195,71 @@ impl TestNet {
 		}
 		net
 	}
+}
+
+impl TestNet<Arc<Client>> {
+	/// Creates a network of `n` peers running full clients with the engine of the spec.
+	/// Clients use in-memory databases and seal only when told to, e.g. with `Miner::force_seal`.
+	/// Engines with timed steps should be advanced with `step_engines`, with the spec's step
+	/// duration long enough for their own timers not to interfere.
+	pub fn with_spec<F>(n: usize, config: SyncConfig, spec_factory: F, accounts: Option<Arc<AccountProvider>>) -> Self
+		where F: Fn() -> Spec
+	{
+		let mut net = TestNet {
+			peers: Vec::new(),
+			started: false,
+			disconnect_events: Vec::new(),
+		};
+		for _ in 0..n {
+			let spec = spec_factory();
+			let db = Arc::new(in_memory(NUM_COLUMNS.unwrap_or(0)));
+			let miner = Arc::new(Miner::with_spec_and_accounts(&spec, accounts.clone()));
+			let chain = Client::with_db(ClientConfig::default(), &spec, db, miner, IoChannel::disconnected())
+				.expect("in-memory database can always be opened; qed");
+			let sync = ChainSync::new(config.clone(), &*chain);
+			net.peers.push(TestPeer {
+				sync: RwLock::new(sync),
+				snapshot_service: Arc::new(TestSnapshotService::new()),
+				chain: chain,
+				queue: VecDeque::new(),
+			});
+		}
+		net
+	}
+
+	/// Advance the engines of all peers to their next step.
+	pub fn step_engines(&self) {
+		for peer in &self.peers {
+			peer.chain.engine().step();
+		}
+	}
+
+	/// Let the peer seal a block with its own engine and announce it.
+	/// Returns `false` if the engine refused to seal, e.g. the peer is not the step's proposer.
+	pub fn seal_and_propagate(&mut self, peer_id: usize) -> bool {
+		let peer = self.peer_mut(peer_id);
+		let sealed = match peer.chain.miner().force_seal(&*peer.chain) {
+			Some(hash) => hash,
+			None => return false,
+		};
+		peer.chain.flush();
+		peer.sync.write().chain_new_blocks(&mut TestIo::new(&mut peer.chain, &peer.snapshot_service, &mut peer.queue, None), &[], &[], &[], &[], &[sealed]);
+		true
+	}
+
+	/// Whether all the peers agree on the best block.
+	pub fn is_consensus(&self) -> bool {
+		let best = self.peers[0].chain.chain_info().best_block_hash;
+		self.peers.iter().all(|p| p.chain.chain_info().best_block_hash == best)
+	}
+}
 
-	pub fn peer(&self, i: usize) -> &TestPeer {
+impl<C> TestNet<C> where C: TestChain {
+	pub fn peer(&self, i: usize) -> &TestPeer<C> {
 		&self.peers[i]
 	}
 
-	pub fn peer_mut(&mut self, i: usize) -> &mut TestPeer {
+	pub fn peer_mut(&mut self, i: usize) -> &mut TestPeer<C> {
 		&mut self.peers[i]
 	}
 
@@ -170,7 +268,7 @@ impl TestNet {
 			for client in 0..self.peers.len() {
 				if peer != client {
 					let mut p = &mut self.peers[peer];
-					p.sync.write().update_targets(&p.chain);
+					p.sync.write().update_targets(p.chain.client());
 					p.sync.write().on_peer_connected(&mut TestIo::new(&mut p.chain, &p.snapshot_service, &mut p.queue, Some(client as PeerId)), client as PeerId);
 				}
 			}
@@ -188,6 +286,7 @@ impl TestNet {
 						ChainSync::dispatch_packet(&p.sync, &mut io, peer as PeerId, packet.packet_id, &packet.data);
 						io.to_disconnect
 					};
+					p.chain.flush();
 					for d in &to_disconnect {
 						// notify this that disconnecting peers are disconnecting
 						let mut io = TestIo::new(&mut p.chain, &p.snapshot_service, &mut p.queue, Some(*d));
@@ -0,0 +1,323 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+use util::*;
+use network::{PeerId, PacketId, NetworkError};
+use ethcore::client::{BlockChainClient, Client};
+use ethcore::header::Header;
+use ethcore::receipt::Receipt;
+use ethcore::spec::Spec;
+use ethcore::transaction::Transaction;
+use light::net::{LightProtocol, Params, FlowParams, CostTable, NetworkId, IoContext, Handler, PeerLimits};
+use light::provider::{Provider, ClientProvider, Composite};
+use light::request::{self, HashOrNumber, Request};
+use rlp::{RlpStream, Stream};
+use super::helpers::*;
+use SyncConfig;
+
+const SERVER: PeerId = 0;
+const LIGHT_PEER: PeerId = 2;
+
+const SEND_TRANSACTIONS: PacketId = 0x0c;
+
+/// Light protocol I/O of one side of a session, collecting the packets sent.
+struct LightIo {
+	remote: PeerId,
+	sent: Mutex<Vec<(PacketId, Bytes)>>,
+}
+
+impl LightIo {
+	fn new(remote: PeerId) -> Self {
+		LightIo {
+			remote: remote,
+			sent: Mutex::new(Vec::new()),
+		}
+	}
+
+	/// Hand the packets sent so far over to the other side.
+	fn deliver(&self, to: &LightProtocol, to_io: &LightIo) {
+		let packets: Vec<_> = self.sent.lock().drain(..).collect();
+		for (packet_id, data) in packets {
+			to.handle_packet(to_io, &to_io.remote, packet_id, &data);
+		}
+	}
+}
+
+impl IoContext for LightIo {
+	fn send(&self, peer: PeerId, packet_id: PacketId, packet_body: Vec<u8>) -> Result<(), NetworkError> {
+		assert_eq!(peer, self.remote);
+		self.sent.lock().push((packet_id, packet_body));
+		Ok(())
+	}
+
+	fn respond(&self, packet_id: PacketId, packet_body: Vec<u8>) -> Result<(), NetworkError> {
+		self.sent.lock().push((packet_id, packet_body));
+		Ok(())
+	}
+
+	fn disconnect_peer(&self, peer: PeerId) {
+		panic!("Unexpected disconnect of {}", peer);
+	}
+
+	fn disable_peer(&self, peer: PeerId) {
+		panic!("Unexpected disabling of {}", peer);
+	}
+}
+
+#[derive(Default)]
+struct HeadersReceived(Mutex<Vec<Header>>);
+
+impl Handler for HeadersReceived {
+	fn on_block_headers(&self, _peer: PeerId, _req_id: usize, headers: &[Header]) {
+		self.0.lock().extend(headers.iter().cloned());
+	}
+}
+
+#[derive(Default)]
+struct ReceiptsReceived(Mutex<Vec<Vec<Receipt>>>);
+
+impl Handler for ReceiptsReceived {
+	fn on_receipts(&self, _peer: PeerId, _req_id: usize, receipts: &[Vec<Receipt>]) {
+		self.0.lock().extend(receipts.iter().cloned());
+	}
+}
+
+#[derive(Default)]
+struct StateReceived {
+	proofs: Mutex<Vec<Vec<Bytes>>>,
+	codes: Mutex<Vec<Bytes>>,
+}
+
+impl Handler for StateReceived {
+	fn on_state_proofs(&self, _peer: PeerId, _req_id: usize, proofs: &[Vec<Bytes>]) {
+		self.proofs.lock().extend(proofs.iter().cloned());
+	}
+
+	fn on_code(&self, _peer: PeerId, _req_id: usize, codes: &[Bytes]) {
+		self.codes.lock().extend(codes.iter().cloned());
+	}
+}
+
+#[derive(Default)]
+struct HeaderProofsReceived(Mutex<Vec<usize>>);
+
+impl Handler for HeaderProofsReceived {
+	fn on_header_proofs(&self, _peer: PeerId, _req_id: usize, proofs: &[(Header, Vec<Bytes>)]) {
+		self.0.lock().push(proofs.len());
+	}
+}
+
+#[derive(Default)]
+struct TimedOut(Mutex<Vec<usize>>);
+
+impl Handler for TimedOut {
+	fn on_timeout(&self, _peer: PeerId, expired: &[usize]) {
+		self.0.lock().extend_from_slice(expired);
+	}
+}
+
+fn light_protocol(chain: Arc<Client>) -> LightProtocol {
+	light_protocol_with_limits(chain, Default::default())
+}
+
+fn light_protocol_with_limits(chain: Arc<Client>, limits: PeerLimits) -> LightProtocol {
+	LightProtocol::new(Box::new(Composite::new(Arc::new(ClientProvider::new(chain)) as Arc<Provider>)), Params {
+		network_id: NetworkId::Mainnet,
+		flow_params: FlowParams::new(50_000_000.into(), CostTable::default(), 1_000_000.into()),
+		capabilities: Default::default(),
+		checkpoints: Default::default(),
+		limits: limits,
+		engine: None,
+	})
+}
+
+#[test]
+fn serves_eth_and_les_peers_side_by_side() {
+	::env_logger::init().ok();
+	let mut net = TestNet::with_spec(2, SyncConfig::default(), Spec::new_instant, None);
+	for _ in 0..3 {
+		assert!(net.seal_and_propagate(0));
+	}
+	let light_chain = TestNet::with_spec(1, SyncConfig::default(), Spec::new_instant, None).peers.remove(0).chain;
+
+	// the full peer's session speaks eth and the light peer's speaks les, to the same server.
+	let server = light_protocol(net.peer(0).chain.clone());
+	let mut light_peer = light_protocol(light_chain);
+	let received = Arc::new(HeadersReceived::default());
+	light_peer.add_handler(received.clone());
+
+	let server_io = LightIo::new(LIGHT_PEER);
+	let light_io = LightIo::new(SERVER);
+	server.on_connect(&LIGHT_PEER, &server_io);
+	light_peer.on_connect(&SERVER, &light_io);
+	net.start();
+
+	server_io.deliver(&light_peer, &light_io);
+	light_io.deliver(&server, &server_io);
+	assert_eq!(server.peers(), vec![LIGHT_PEER]);
+	assert_eq!(light_peer.peers(), vec![SERVER]);
+
+	let headers = Request::Headers(request::Headers { block: HashOrNumber::Number(0), max: 4, skip: 0, reverse: false });
+	assert_eq!(light_peer.request(&light_io, headers).unwrap().0, SERVER);
+	net.sync_steps(1);
+	light_io.deliver(&server, &server_io);
+	server_io.deliver(&light_peer, &light_io);
+
+	while !net.done() {
+		net.sync_step();
+	}
+
+	assert_eq!(net.peer(1).chain.chain_info().best_block_number, 3);
+	let received: Vec<_> = received.0.lock().iter().map(|header| header.number()).collect();
+	assert_eq!(received, vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn gives_up_on_unanswered_requests() {
+	let net = TestNet::with_spec(2, SyncConfig::default(), Spec::new_instant, None);
+	let server = light_protocol(net.peer(0).chain.clone());
+	let mut light_peer = light_protocol_with_limits(net.peer(1).chain.clone(), PeerLimits {
+		request_timeout: Duration::from_millis(0),
+		..Default::default()
+	});
+	let timed_out = Arc::new(TimedOut::default());
+	light_peer.add_handler(timed_out.clone());
+
+	let server_io = LightIo::new(LIGHT_PEER);
+	let light_io = LightIo::new(SERVER);
+	server.on_connect(&LIGHT_PEER, &server_io);
+	light_peer.on_connect(&SERVER, &light_io);
+	server_io.deliver(&light_peer, &light_io);
+	light_io.deliver(&server, &server_io);
+
+	let headers = Request::Headers(request::Headers { block: HashOrNumber::Number(0), max: 1, skip: 0, reverse: false });
+	let req_id = light_peer.request(&light_io, headers).unwrap().1;
+	light_peer.tick(&light_io);
+	assert_eq!(*timed_out.0.lock(), vec![req_id]);
+
+	light_peer.tick(&light_io);
+	assert_eq!(*timed_out.0.lock(), vec![req_id]);
+}
+
+/// Exchange status packets between the server and the light peer.
+fn handshake(server: &LightProtocol, server_io: &LightIo, light_peer: &LightProtocol, light_io: &LightIo) {
+	server.on_connect(&LIGHT_PEER, server_io);
+	light_peer.on_connect(&SERVER, light_io);
+	server_io.deliver(light_peer, light_io);
+	light_io.deliver(server, server_io);
+}
+
+#[test]
+fn accepts_relayed_transactions() {
+	let net = TestNet::with_spec(2, SyncConfig::default(), Spec::new_instant, None);
+	let server = light_protocol(net.peer(0).chain.clone());
+	let light_peer = light_protocol(net.peer(1).chain.clone());
+	let server_io = LightIo::new(LIGHT_PEER);
+	let light_io = LightIo::new(SERVER);
+	handshake(&server, &server_io, &light_peer, &light_io);
+
+	let tx = Transaction::default().fake_sign(Address::from(1));
+	let mut stream = RlpStream::new_list(1);
+	stream.append(&tx);
+
+	// queued without punishing the peer, and not sent back to it.
+	server.handle_packet(&server_io, &LIGHT_PEER, SEND_TRANSACTIONS, &stream.out());
+	server.propagate_transactions(&server_io);
+	assert!(server_io.sent.lock().is_empty());
+}
+
+#[test]
+fn serves_receipts() {
+	let mut net = TestNet::with_spec(2, SyncConfig::default(), Spec::new_instant, None);
+	assert!(net.seal_and_propagate(0));
+	let best_hash = net.peer(0).chain.chain_info().best_block_hash;
+
+	let server = light_protocol(net.peer(0).chain.clone());
+	let mut light_peer = light_protocol(net.peer(1).chain.clone());
+	let received = Arc::new(ReceiptsReceived::default());
+	light_peer.add_handler(received.clone());
+
+	let server_io = LightIo::new(LIGHT_PEER);
+	let light_io = LightIo::new(SERVER);
+	handshake(&server, &server_io, &light_peer, &light_io);
+
+	let receipts = Request::Receipts(request::Receipts { block_hashes: vec![best_hash] });
+	light_peer.request(&light_io, receipts).unwrap();
+	light_io.deliver(&server, &server_io);
+	server_io.deliver(&light_peer, &light_io);
+
+	assert_eq!(*received.0.lock(), vec![Vec::new()]);
+}
+
+#[test]
+fn serves_state_proofs_and_code() {
+	let net = TestNet::with_spec(2, SyncConfig::default(), Spec::new_instant, None);
+	let genesis_hash = net.peer(0).chain.chain_info().genesis_hash;
+
+	let server = light_protocol(net.peer(0).chain.clone());
+	let mut light_peer = light_protocol(net.peer(1).chain.clone());
+	let received = Arc::new(StateReceived::default());
+	light_peer.add_handler(received.clone());
+
+	let server_io = LightIo::new(LIGHT_PEER);
+	let light_io = LightIo::new(SERVER);
+	handshake(&server, &server_io, &light_peer, &light_io);
+
+	// a builtin account: proved against the genesis state root, and without code.
+	let account_key = Address::from(1).sha3();
+	let proofs = Request::StateProofs(request::StateProofs { requests: vec![request::StateProof {
+		block: genesis_hash,
+		key1: account_key,
+		key2: None,
+		from_level: 0,
+	}] });
+	let codes = Request::Codes(request::ContractCodes { code_requests: vec![(genesis_hash, account_key)] });
+	light_peer.request(&light_io, proofs).unwrap();
+	light_peer.request(&light_io, codes).unwrap();
+	light_io.deliver(&server, &server_io);
+	server_io.deliver(&light_peer, &light_io);
+
+	let proofs = received.proofs.lock();
+	assert_eq!(proofs.len(), 1);
+	assert!(!proofs[0].is_empty());
+	assert_eq!(*received.codes.lock(), vec![Bytes::new()]);
+}
+
+#[test]
+fn answers_header_proofs_out_of_range() {
+	let net = TestNet::with_spec(2, SyncConfig::default(), Spec::new_instant, None);
+	let server = light_protocol(net.peer(0).chain.clone());
+	let mut light_peer = light_protocol(net.peer(1).chain.clone());
+	let received = Arc::new(HeaderProofsReceived::default());
+	light_peer.add_handler(received.clone());
+
+	let server_io = LightIo::new(LIGHT_PEER);
+	let light_io = LightIo::new(SERVER);
+	handshake(&server, &server_io, &light_peer, &light_io);
+
+	// the first CHT isn't complete yet, so there is nothing to prove.
+	let proofs = Request::HeaderProofs(request::HeaderProofs { requests: vec![request::HeaderProof {
+		cht_number: 0,
+		block_number: 0,
+		from_level: 0,
+	}] });
+	light_peer.request(&light_io, proofs).unwrap();
+	light_io.deliver(&server, &server_io);
+	server_io.deliver(&light_peer, &light_io);
+
+	assert_eq!(*received.0.lock(), vec![0]);
+}
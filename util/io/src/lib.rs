@@ -139,7 +139,10 @@ pub use panics::{PanicHandler, MayPanic, OnPanicListener, ForwardPanic};
 #[cfg(test)]
 mod tests {
 
-	use std::sync::Arc;
+	use std::sync::{Arc, Mutex};
+	use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+	use std::thread;
+	use std::time::Duration;
 	use super::*;
 
 	struct MyHandler;
@@ -169,4 +172,72 @@ mod tests {
 		service.register_handler(Arc::new(MyHandler)).unwrap();
 	}
 
+	struct CountingHandler(Arc<AtomicUsize>);
+
+	impl IoHandler<MyMessage> for CountingHandler {
+		fn message(&self, _io: &IoContext<MyMessage>, message: &MyMessage) {
+			self.0.fetch_add(message.data as usize, Ordering::SeqCst);
+		}
+	}
+
+	#[test]
+	fn test_priority_message_delivered() {
+		let count = Arc::new(AtomicUsize::new(0));
+		let service = IoService::<MyMessage>::start().expect("Error creating network service");
+		service.register_handler(Arc::new(CountingHandler(count.clone()))).unwrap();
+		service.channel().send(MyMessage { data: 1 }).unwrap();
+		service.channel().send_priority(MyMessage { data: 2 }).unwrap();
+
+		for _ in 0..100 {
+			if count.load(Ordering::SeqCst) == 3 { break; }
+			thread::sleep(Duration::from_millis(10));
+		}
+		assert_eq!(count.load(Ordering::SeqCst), 3);
+	}
+
+	/// Blocks on messages with data 1 until released, records the order of the others.
+	struct BlockingHandler {
+		release: Arc<AtomicBool>,
+		handled: Arc<Mutex<Vec<u32>>>,
+	}
+
+	impl IoHandler<MyMessage> for BlockingHandler {
+		fn message(&self, _io: &IoContext<MyMessage>, message: &MyMessage) {
+			if message.data == 1 {
+				while !self.release.load(Ordering::SeqCst) {
+					thread::sleep(Duration::from_millis(1));
+				}
+			}
+			self.handled.lock().unwrap().push(message.data);
+		}
+	}
+
+	#[test]
+	fn test_priority_message_preempts_busy_workers() {
+		let release = Arc::new(AtomicBool::new(false));
+		let handled = Arc::new(Mutex::new(Vec::new()));
+		let service = IoService::<MyMessage>::start().expect("Error creating network service");
+		service.register_handler(Arc::new(BlockingHandler { release: release.clone(), handled: handled.clone() })).unwrap();
+
+		// keep every worker busy, then queue more work behind them.
+		for _ in 0..8 {
+			service.channel().send(MyMessage { data: 1 }).unwrap();
+		}
+		service.channel().send(MyMessage { data: 3 }).unwrap();
+		service.channel().send_priority(MyMessage { data: 2 }).unwrap();
+
+		for _ in 0..100 {
+			if !handled.lock().unwrap().is_empty() { break; }
+			thread::sleep(Duration::from_millis(10));
+		}
+		assert_eq!(*handled.lock().unwrap(), vec![2]);
+
+		release.store(true, Ordering::SeqCst);
+		for _ in 0..100 {
+			if handled.lock().unwrap().len() == 10 { break; }
+			thread::sleep(Duration::from_millis(10));
+		}
+		assert_eq!(handled.lock().unwrap().len(), 10);
+	}
+
 }
@@ -57,6 +57,7 @@ pub enum IoMessage<Message> where Message: Send + Clone + Sized {
 		token: TimerToken,
 		delay: u64,
 		once: bool,
+		priority: bool,
 	},
 	RemoveTimer {
 		handler_id: HandlerId,
@@ -75,7 +76,9 @@ pub enum IoMessage<Message> where Message: Send + Clone + Sized {
 		token: StreamToken,
 	},
 	/// Broadcast a message across all protocol handlers.
-	UserMessage(Message)
+	UserMessage(Message),
+	/// Broadcast a message across all protocol handlers ahead of any other queued work.
+	PriorityMessage(Message),
 }
 
 /// IO access point. This is passed to all IO handlers and provides an interface to the IO subsystem.
@@ -100,6 +103,7 @@ impl<Message> IoContext<Message> where Message: Send + Clone + Sync + 'static {
 			delay: ms,
 			handler_id: self.handler,
 			once: false,
+			priority: false,
 		}));
 		Ok(())
 	}
@@ -111,6 +115,20 @@ impl<Message> IoContext<Message> where Message: Send + Clone + Sync + 'static {
 			delay: ms,
 			handler_id: self.handler,
 			once: true,
+			priority: false,
+		}));
+		Ok(())
+	}
+
+	/// Register a new IO timer once. The timeout is handled ahead of any other queued work,
+	/// which makes it suitable for time-critical events like consensus steps.
+	pub fn register_priority_timer_once(&self, token: TimerToken, ms: u64) -> Result<(), IoError> {
+		try!(self.channel.send_io(IoMessage::AddTimer {
+			token: token,
+			delay: ms,
+			handler_id: self.handler,
+			once: true,
+			priority: true,
 		}));
 		Ok(())
 	}
@@ -177,6 +195,7 @@ struct UserTimer {
 	delay: u64,
 	timeout: Timeout,
 	once: bool,
+	priority: bool,
 }
 
 /// Root IO handler. Manages user handlers, messages and IO timers.
@@ -185,6 +204,7 @@ pub struct IoManager<Message> where Message: Send + Sync {
 	handlers: Arc<RwLock<Slab<Arc<IoHandler<Message>>, HandlerId>>>,
 	workers: Vec<Worker>,
 	worker_channel: chase_lev::Worker<Work<Message>>,
+	priority_channel: chase_lev::Worker<Work<Message>>,
 	work_ready: Arc<SCondvar>,
 }
 
@@ -196,30 +216,61 @@ impl<Message> IoManager<Message> where Message: Send + Sync + Clone + 'static {
 		handlers: Arc<RwLock<Slab<Arc<IoHandler<Message>>, HandlerId>>>
 	) -> Result<(), IoError> {
 		let (worker, stealer) = chase_lev::deque();
+		let (priority_worker, priority_stealer) = chase_lev::deque();
 		let num_workers = 4;
 		let work_ready_mutex =  Arc::new(SMutex::new(()));
 		let work_ready = Arc::new(SCondvar::new());
-		let workers = (0..num_workers).map(|i|
+		let mut workers: Vec<_> = (0..num_workers).map(|i|
 			Worker::new(
 				i,
-				stealer.clone(),
+				Some(stealer.clone()),
+				priority_stealer.clone(),
 				IoChannel::new(event_loop.channel(), Arc::downgrade(&handlers)),
 				work_ready.clone(),
 				work_ready_mutex.clone(),
 				panic_handler.clone(),
 			)
 		).collect();
+		// priority work must not wait for bulk work to finish on a busy worker.
+		workers.push(Worker::new(
+			num_workers,
+			None,
+			priority_stealer.clone(),
+			IoChannel::new(event_loop.channel(), Arc::downgrade(&handlers)),
+			work_ready.clone(),
+			work_ready_mutex.clone(),
+			panic_handler.clone(),
+		));
 
 		let mut io = IoManager {
 			timers: Arc::new(RwLock::new(HashMap::new())),
 			handlers: handlers,
 			worker_channel: worker,
+			priority_channel: priority_worker,
 			workers: workers,
 			work_ready: work_ready,
 		};
 		try!(event_loop.run(&mut io));
 		Ok(())
 	}
+
+	fn push_work(&self, work: Work<Message>, priority: bool) {
+		match priority {
+			true => self.priority_channel.push(work),
+			false => self.worker_channel.push(work),
+		}
+	}
+
+	fn broadcast(&self, data: Message, priority: bool) {
+		//TODO: better way to iterate the slab
+		for id in 0 .. MAX_HANDLERS {
+			if let Some(h) = self.handlers.read().get(id) {
+				let handler = h.clone();
+				self.push_work(Work { work_type: WorkType::Message(data.clone()), token: 0, handler: handler, handler_id: id }, priority);
+			}
+		}
+		self.work_ready.notify_all();
+	}
 }
 
 impl<Message> Handler for IoManager<Message> where Message: Send + Clone + Sync + 'static {
@@ -257,7 +308,7 @@ impl<Message> Handler for IoManager<Message> where Message: Send + Clone + Sync
 				} else {
 					event_loop.timeout(token, Duration::from_millis(timer.delay)).expect("Error re-registering user timer");
 				}
-				self.worker_channel.push(Work { work_type: WorkType::Timeout, token: token_id, handler: handler.clone(), handler_id: handler_index });
+				self.push_work(Work { work_type: WorkType::Timeout, token: token_id, handler: handler.clone(), handler_id: handler_index }, timer.priority);
 				self.work_ready.notify_all();
 			}
 		}
@@ -284,10 +335,10 @@ impl<Message> Handler for IoManager<Message> where Message: Send + Clone + Sync
 					event_loop.clear_timeout(&timer.timeout);
 				}
 			},
-			IoMessage::AddTimer { handler_id, token, delay, once } => {
+			IoMessage::AddTimer { handler_id, token, delay, once, priority } => {
 				let timer_id = token + handler_id * TOKENS_PER_HANDLER;
 				let timeout = event_loop.timeout(Token(timer_id), Duration::from_millis(delay)).expect("Error registering user timer");
-				self.timers.write().insert(timer_id, UserTimer { delay: delay, timeout: timeout, once: once });
+				self.timers.write().insert(timer_id, UserTimer { delay: delay, timeout: timeout, once: once, priority: priority });
 			},
 			IoMessage::RemoveTimer { handler_id, token } => {
 				let timer_id = token + handler_id * TOKENS_PER_HANDLER;
@@ -315,16 +366,8 @@ impl<Message> Handler for IoManager<Message> where Message: Send + Clone + Sync
 					handler.update_stream(token, Token(token + handler_id * TOKENS_PER_HANDLER), event_loop);
 				}
 			},
-			IoMessage::UserMessage(data) => {
-				//TODO: better way to iterate the slab
-				for id in 0 .. MAX_HANDLERS {
-					if let Some(h) = self.handlers.read().get(id) {
-						let handler = h.clone();
-						self.worker_channel.push(Work { work_type: WorkType::Message(data.clone()), token: 0, handler: handler, handler_id: id });
-					}
-				}
-				self.work_ready.notify_all();
-			}
+			IoMessage::UserMessage(data) => self.broadcast(data, false),
+			IoMessage::PriorityMessage(data) => self.broadcast(data, true),
 		}
 	}
 }
@@ -354,6 +397,14 @@ impl<Message> IoChannel<Message> where Message: Send + Clone + Sync + 'static {
 		Ok(())
 	}
 
+	/// Send a message through the channel. Handlers receive it ahead of any other queued work.
+	pub fn send_priority(&self, message: Message) -> Result<(), IoError> {
+		if let Some(ref channel) = self.channel {
+			try!(channel.send(IoMessage::PriorityMessage(message)));
+		}
+		Ok(())
+	}
+
 	/// Send a message through the channel and handle it synchronously
 	pub fn send_sync(&self, message: Message) -> Result<(), IoError> {
 		if let Some(handlers) = self.handlers.upgrade() {
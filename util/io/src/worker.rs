@@ -59,9 +59,10 @@ pub struct Worker {
 }
 
 impl Worker {
-	/// Creates a new worker instance.
+	/// Creates a new worker instance. A worker without `stealer` only does priority work.
 	pub fn new<Message>(index: usize,
-						stealer: chase_lev::Stealer<Work<Message>>,
+						stealer: Option<chase_lev::Stealer<Work<Message>>>,
+						priority_stealer: chase_lev::Stealer<Work<Message>>,
 						channel: IoChannel<Message>,
 						wait: Arc<SCondvar>,
 						wait_mutex: Arc<SMutex<()>>,
@@ -79,14 +80,15 @@ impl Worker {
 			move || {
 				LOCAL_STACK_SIZE.with(|val| val.set(STACK_SIZE));
 				panic_handler.catch_panic(move || {
-					Worker::work_loop(stealer, channel.clone(), wait, wait_mutex.clone(), deleting)
+					Worker::work_loop(stealer, priority_stealer, channel.clone(), wait, wait_mutex.clone(), deleting)
 				}).expect("Error starting panic handler")
 			})
 			.expect("Error creating worker thread"));
 		worker
 	}
 
-	fn work_loop<Message>(stealer: chase_lev::Stealer<Work<Message>>,
+	fn work_loop<Message>(stealer: Option<chase_lev::Stealer<Work<Message>>>,
+						priority_stealer: chase_lev::Stealer<Work<Message>>,
 						channel: IoChannel<Message>, wait: Arc<SCondvar>,
 						wait_mutex: Arc<SMutex<()>>,
 						deleting: Arc<AtomicBool>)
@@ -101,8 +103,13 @@ impl Worker {
 			}
 
 			while !deleting.load(AtomicOrdering::Acquire) {
-				match stealer.steal() {
-					chase_lev::Steal::Data(work) => Worker::do_work(work, channel.clone()),
+				// drain priority work before picking up anything else.
+				if let chase_lev::Steal::Data(work) = priority_stealer.steal() {
+					Worker::do_work(work, channel.clone());
+					continue;
+				}
+				match stealer.as_ref().map(|s| s.steal()) {
+					Some(chase_lev::Steal::Data(work)) => Worker::do_work(work, channel.clone()),
 					_ => break,
 				}
 			}
@@ -274,6 +274,61 @@ enum EncryptedConnectionState {
 	Payload,
 }
 
+/// Session secrets derived from a completed handshake.
+/// https://github.com/ethereum/devp2p/blob/master/rlpx.md#secrets
+pub struct Secrets {
+	/// Frame encryption key
+	pub aes_secret: H256,
+	/// MAC encryption key
+	pub mac_secret: H256,
+	/// Initial egress MAC state
+	pub egress_mac: Keccak,
+	/// Initial ingress MAC state
+	pub ingress_mac: Keccak,
+}
+
+impl Secrets {
+	/// Derive session secrets from the ephemeral key agreement and nonces of the handshake.
+	pub fn new(handshake: &Handshake) -> Result<Secrets, NetworkError> {
+		let shared = try!(crypto::ecdh::agree(handshake.ecdhe.secret(), &handshake.remote_ephemeral));
+		let mut nonce_material = H512::new();
+		if handshake.originated {
+			handshake.remote_nonce.copy_to(&mut nonce_material[0..32]);
+			handshake.nonce.copy_to(&mut nonce_material[32..64]);
+		}
+		else {
+			handshake.nonce.copy_to(&mut nonce_material[0..32]);
+			handshake.remote_nonce.copy_to(&mut nonce_material[32..64]);
+		}
+		let mut key_material = H512::new();
+		shared.copy_to(&mut key_material[0..32]);
+		nonce_material.sha3_into(&mut key_material[32..64]);
+		key_material.sha3().copy_to(&mut key_material[32..64]);
+		key_material.sha3().copy_to(&mut key_material[32..64]);
+		let aes_secret = H256::from_slice(&key_material[32..64]);
+
+		key_material.sha3().copy_to(&mut key_material[32..64]);
+		let mac_secret = H256::from_slice(&key_material[32..64]);
+
+		let mut egress_mac = Keccak::new_keccak256();
+		let mut mac_material = &mac_secret ^ &handshake.remote_nonce;
+		egress_mac.update(&mac_material);
+		egress_mac.update(if handshake.originated { &handshake.auth_cipher } else { &handshake.ack_cipher });
+
+		let mut ingress_mac = Keccak::new_keccak256();
+		mac_material = &mac_secret ^ &handshake.nonce;
+		ingress_mac.update(&mac_material);
+		ingress_mac.update(if handshake.originated { &handshake.ack_cipher } else { &handshake.auth_cipher });
+
+		Ok(Secrets {
+			aes_secret: aes_secret,
+			mac_secret: mac_secret,
+			egress_mac: egress_mac,
+			ingress_mac: ingress_mac,
+		})
+	}
+}
+
 /// Connection implementing `RLPx` framing
 /// https://github.com/ethereum/devp2p/blob/master/rlpx.md#framing
 pub struct EncryptedConnection {
@@ -300,39 +355,13 @@ pub struct EncryptedConnection {
 impl EncryptedConnection {
 	/// Create an encrypted connection out of the handshake.
 	pub fn new(handshake: &mut Handshake) -> Result<EncryptedConnection, NetworkError> {
-		let shared = try!(crypto::ecdh::agree(handshake.ecdhe.secret(), &handshake.remote_ephemeral));
-		let mut nonce_material = H512::new();
-		if handshake.originated {
-			handshake.remote_nonce.copy_to(&mut nonce_material[0..32]);
-			handshake.nonce.copy_to(&mut nonce_material[32..64]);
-		}
-		else {
-			handshake.nonce.copy_to(&mut nonce_material[0..32]);
-			handshake.remote_nonce.copy_to(&mut nonce_material[32..64]);
-		}
-		let mut key_material = H512::new();
-		shared.copy_to(&mut key_material[0..32]);
-		nonce_material.sha3_into(&mut key_material[32..64]);
-		key_material.sha3().copy_to(&mut key_material[32..64]);
-		key_material.sha3().copy_to(&mut key_material[32..64]);
+		let secrets = try!(Secrets::new(handshake));
 
 		let iv = vec![0u8; 16];
-		let encoder = CtrMode::new(AesSafe256Encryptor::new(&key_material[32..64]), iv);
+		let encoder = CtrMode::new(AesSafe256Encryptor::new(&secrets.aes_secret), iv);
 		let iv = vec![0u8; 16];
-		let decoder = CtrMode::new(AesSafe256Encryptor::new(&key_material[32..64]), iv);
-
-		key_material.sha3().copy_to(&mut key_material[32..64]);
-		let mac_encoder = EcbEncryptor::new(AesSafe256Encryptor::new(&key_material[32..64]), NoPadding);
-
-		let mut egress_mac = Keccak::new_keccak256();
-		let mut mac_material = &H256::from_slice(&key_material[32..64]) ^ &handshake.remote_nonce;
-		egress_mac.update(&mac_material);
-		egress_mac.update(if handshake.originated { &handshake.auth_cipher } else { &handshake.ack_cipher });
-
-		let mut ingress_mac = Keccak::new_keccak256();
-		mac_material = &H256::from_slice(&key_material[32..64]) ^ &handshake.nonce;
-		ingress_mac.update(&mac_material);
-		ingress_mac.update(if handshake.originated { &handshake.ack_cipher } else { &handshake.auth_cipher });
+		let decoder = CtrMode::new(AesSafe256Encryptor::new(&secrets.aes_secret), iv);
+		let mac_encoder = EcbEncryptor::new(AesSafe256Encryptor::new(&secrets.mac_secret), NoPadding);
 
 		let old_connection = try!(handshake.connection.try_clone());
 		let connection = ::std::mem::replace(&mut handshake.connection, old_connection);
@@ -341,8 +370,8 @@ impl EncryptedConnection {
 			encoder: encoder,
 			decoder: decoder,
 			mac_encoder: mac_encoder,
-			egress_mac: egress_mac,
-			ingress_mac: ingress_mac,
+			egress_mac: secrets.egress_mac,
+			ingress_mac: secrets.ingress_mac,
 			read_state: EncryptedConnectionState::Header,
 			protocol_id: 0,
 			payload_len: 0
@@ -89,7 +89,11 @@ pub struct Discovery {
 	id: NodeId,
 	id_hash: H256,
 	secret: Secret,
+	/// Key replaced by a rotation, answered until the given unix time.
+	previous: Option<(NodeId, Secret, u64)>,
 	public_endpoint: NodeEndpoint,
+	/// Public endpoints of the additional listeners, announced to nodes of their address family.
+	extra_public_endpoints: Vec<NodeEndpoint>,
 	udp_socket: UdpSocket,
 	token: StreamToken,
 	discovery_round: u16,
@@ -114,7 +118,9 @@ impl Discovery {
 			id: key.public().clone(),
 			id_hash: key.public().sha3(),
 			secret: key.secret().clone(),
+			previous: None,
 			public_endpoint: public,
+			extra_public_endpoints: Vec::new(),
 			token: token,
 			discovery_round: 0,
 			discovery_id: NodeId::new(),
@@ -128,6 +134,27 @@ impl Discovery {
 		}
 	}
 
+	/// Keep answering pings and node lookups for a previous node key until `until` (unix time, in seconds).
+	pub fn set_previous_key(&mut self, key: &KeyPair, until: u64) {
+		self.previous = Some((key.public().clone(), key.secret().clone(), until));
+	}
+
+	/// Set the public endpoints of the additional listeners.
+	pub fn set_extra_endpoints(&mut self, endpoints: Vec<NodeEndpoint>) {
+		self.extra_public_endpoints = endpoints;
+	}
+
+	/// Endpoint to announce to a node at `address`: the main one unless it is of another
+	/// address family and one of the additional endpoints matches.
+	fn announced_endpoint(&self, address: &SocketAddr) -> &NodeEndpoint {
+		if same_family(&self.public_endpoint.address, address) {
+			return &self.public_endpoint;
+		}
+		self.extra_public_endpoints.iter()
+			.find(|e| same_family(&e.address, address))
+			.unwrap_or(&self.public_endpoint)
+	}
+
 	/// Add a new node to discovery table. Pings the node.
 	pub fn add_node(&mut self, e: NodeEntry) {
 		if self.is_allowed(&e) {
@@ -246,13 +273,31 @@ impl Discovery {
 	fn ping(&mut self, node: &NodeEndpoint) {
 		let mut rlp = RlpStream::new_list(3);
 		rlp.append(&PROTOCOL_VERSION);
-		self.public_endpoint.to_rlp_list(&mut rlp);
+		self.announced_endpoint(&node.address).to_rlp_list(&mut rlp);
 		node.to_rlp_list(&mut rlp);
 		trace!(target: "discovery", "Sent Ping to {:?}", &node);
 		self.send_packet(PACKET_PING, &node.udp_address(), &rlp.drain());
 	}
 
 	fn send_packet(&mut self, packet_id: u8, address: &SocketAddr, payload: &[u8]) {
+		let secret = self.secret.clone();
+		self.send_signed_packet(&secret, packet_id, address, payload);
+	}
+
+	/// Sends a response, also signed with the previous node key while it is still answered.
+	fn reply(&mut self, packet_id: u8, address: &SocketAddr, payload: &[u8]) {
+		self.send_packet(packet_id, address, payload);
+		let previous = match self.previous {
+			Some((_, ref secret, until)) if until > time::get_time().sec as u64 => Some(secret.clone()),
+			_ => None,
+		};
+		match previous {
+			Some(secret) => self.send_signed_packet(&secret, packet_id, address, payload),
+			None => self.previous = None,
+		}
+	}
+
+	fn send_signed_packet(&mut self, secret: &Secret, packet_id: u8, address: &SocketAddr, payload: &[u8]) {
 		let mut rlp = RlpStream::new();
 		rlp.append_raw(&[packet_id], 1);
 		let source = Rlp::new(payload);
@@ -265,7 +310,7 @@ impl Discovery {
 
 		let bytes = rlp.drain();
 		let hash = bytes.as_ref().sha3();
-		let signature = match sign(&self.secret, &hash) {
+		let signature = match sign(secret, &hash) {
 			Ok(s) => s,
 			Err(_) => {
 				warn!("Error signing UDP packet");
@@ -400,7 +445,8 @@ impl Discovery {
 	}
 
 	fn is_allowed(&self, entry: &NodeEntry) -> bool {
-		entry.endpoint.is_allowed(self.allow_ips) && entry.id != self.id
+		entry.endpoint.is_allowed(self.allow_ips) && entry.id != self.id &&
+			self.previous.as_ref().map_or(true, |&(ref id, _, _)| *id != entry.id)
 	}
 
 	fn on_ping(&mut self, rlp: &UntrustedRlp, node: &NodeId, from: &SocketAddr) -> Result<Option<TableUpdates>, NetworkError> {
@@ -423,7 +469,7 @@ impl Discovery {
 		let mut response = RlpStream::new_list(2);
 		dest.to_rlp_list(&mut response);
 		response.append(&hash);
-		self.send_packet(PACKET_PONG, from, &response.drain());
+		self.reply(PACKET_PONG, from, &response.drain());
 
 		Ok(Some(TableUpdates { added: added_map, removed: HashSet::new() }))
 	}
@@ -456,7 +502,7 @@ impl Discovery {
 		}
 		let mut packets = Discovery::prepare_neighbours_packets(&nearest);
 		for p in packets.drain(..) {
-			self.send_packet(PACKET_NEIGHBOURS, from, &p);
+			self.reply(PACKET_NEIGHBOURS, from, &p);
 		}
 		trace!(target: "discovery", "Sent {} Neighbours to {:?}", nearest.len(), &from);
 		Ok(None)
@@ -552,6 +598,13 @@ impl Discovery {
 	}
 }
 
+fn same_family(a: &SocketAddr, b: &SocketAddr) -> bool {
+	match (*a, *b) {
+		(SocketAddr::V4(_), SocketAddr::V4(_)) | (SocketAddr::V6(_), SocketAddr::V6(_)) => true,
+		_ => false,
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -582,6 +635,21 @@ mod tests {
 		assert!(packets.last().unwrap().len() > 0);
 	}
 
+	#[test]
+	fn announces_endpoint_of_the_peer_family() {
+		let key = Random.generate().unwrap();
+		let ep = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40446").unwrap(), udp_port: 40446 };
+		let ep6 = NodeEndpoint { address: SocketAddr::from_str("[2001:db8::1]:40447").unwrap(), udp_port: 40446 };
+		let mut discovery = Discovery::new(&key, ep.address.clone(), ep.clone(), 0, AllowIP::All);
+		let v4_peer = SocketAddr::from_str("10.0.0.1:30303").unwrap();
+		let v6_peer = SocketAddr::from_str("[2001:db8::2]:30303").unwrap();
+		assert_eq!(discovery.announced_endpoint(&v6_peer).address, ep.address);
+
+		discovery.set_extra_endpoints(vec![ep6.clone()]);
+		assert_eq!(discovery.announced_endpoint(&v4_peer).address, ep.address);
+		assert_eq!(discovery.announced_endpoint(&v6_peer).address, ep6.address);
+	}
+
 	#[test]
 	fn discovery() {
 		let key1 = Random.generate().unwrap();
@@ -646,6 +714,33 @@ mod tests {
 		assert_eq!(nearest.len(), 16)
 	}
 
+	#[test]
+	fn answers_for_previous_key() {
+		let key = Random.generate().unwrap();
+		let previous = Random.generate().unwrap();
+		let ep = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40449").unwrap(), udp_port: 40449 };
+		let mut discovery = Discovery::new(&key, ep.address.clone(), ep.clone(), 0, AllowIP::All);
+		discovery.set_previous_key(&previous, time::get_time().sec as u64 + 60);
+
+		let other_key = Random.generate().unwrap();
+		let other_ep = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40450").unwrap(), udp_port: 40450 };
+		let mut other = Discovery::new(&other_key, other_ep.address.clone(), other_ep.clone(), 0, AllowIP::All);
+		other.ping(&ep);
+		let ping = other.send_queue.pop_front().unwrap();
+
+		assert!(discovery.on_packet(&ping.payload, other_ep.address.clone()).is_ok());
+		let signers: Vec<NodeId> = discovery.send_queue.iter()
+			.map(|d| recover(&H520::from_slice(&d.payload[32..(32 + 65)]).into(), &(&d.payload[(32 + 65)..]).sha3()).unwrap())
+			.collect();
+		assert_eq!(signers, vec![key.public().clone(), previous.public().clone()]);
+
+		// not answered after the grace period.
+		discovery.send_queue.clear();
+		discovery.set_previous_key(&previous, 0);
+		assert!(discovery.on_packet(&ping.payload, other_ep.address.clone()).is_ok());
+		assert_eq!(discovery.send_queue.len(), 1);
+	}
+
 	#[test]
 	fn packets() {
 		let key = Random.generate().unwrap();
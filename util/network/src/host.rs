@@ -14,16 +14,15 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::net::{SocketAddr, SocketAddrV4, Ipv4Addr};
+use std::net::{self, SocketAddr, SocketAddrV4, Ipv4Addr, IpAddr};
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering as AtomicOrdering};
 use std::ops::*;
 use std::cmp::min;
-use std::path::{Path, PathBuf};
-use std::io::{Read, Write};
-use std::fs;
+use std::path::Path;
+use std::thread;
 use ethkey::{KeyPair, Secret, Random, Generator};
 use mio::*;
 use mio::deprecated::{EventLoop};
@@ -31,22 +30,28 @@ use mio::tcp::*;
 use util::hash::*;
 use util::Hashable;
 use util::version;
+use util::metrics::metrics;
 use rlp::*;
 use session::{Session, SessionInfo, SessionData};
 use error::*;
 use io::*;
 use {NetworkProtocolHandler, NonReservedPeerMode, AllowIP, PROTOCOL_VERSION};
+use connection_filter::{ConnectionFilter, ConnectionDirection};
 use node_table::*;
 use stats::NetworkStats;
+use proxy::Proxy;
+use ip_limits::SessionAddresses;
 use discovery::{Discovery, TableUpdates, NodeEntry};
-use ip_utils::{map_external_address, select_public_address};
-use util::path::restrict_permissions_owner;
+use node_key;
+use ip_utils::{map_external_address, select_public_address, select_public_address_of_family, SocketAddrExt};
 use parking_lot::{Mutex, RwLock};
 
 type Slab<T> = ::slab::Slab<T, usize>;
 
 const MAX_SESSIONS: usize = 1024 + MAX_HANDSHAKES;
 const MAX_HANDSHAKES: usize = 1024;
+/// Maximal number of proxy handshakes running at the same time, each on its own thread.
+const MAX_PROXY_HANDSHAKES: usize = 16;
 
 const DEFAULT_PORT: u16 = 30303;
 
@@ -57,6 +62,9 @@ const DISCOVERY: usize = SYS_TIMER + 3;
 const DISCOVERY_REFRESH: usize = SYS_TIMER + 4;
 const DISCOVERY_ROUND: usize = SYS_TIMER + 5;
 const NODE_TABLE: usize = SYS_TIMER + 6;
+const FIRST_EXTRA_TCP_ACCEPT: usize = SYS_TIMER + 7;
+const LAST_EXTRA_TCP_ACCEPT: usize = FIRST_EXTRA_TCP_ACCEPT + MAX_EXTRA_LISTENERS - 1;
+const MAX_EXTRA_LISTENERS: usize = 16;
 const FIRST_SESSION: usize = 0;
 const LAST_SESSION: usize = FIRST_SESSION + MAX_SESSIONS - 1;
 const USER_TIMER: usize = LAST_SESSION + 256;
@@ -77,6 +85,10 @@ pub struct NetworkConfiguration {
 	pub net_config_path: Option<String>,
 	/// IP address to listen for incoming connections. Listen to all connections by default
 	pub listen_address: Option<SocketAddr>,
+	/// Additional addresses to listen for incoming connections on, e.g. an IPv6 address on a dual-stack host.
+	pub extra_listen_addresses: Vec<SocketAddr>,
+	/// Prefer IPv6 addresses when selecting the public address to advertise.
+	pub prefer_ipv6: bool,
 	/// IP address to advertise. Detected automatically if none.
 	pub public_address: Option<SocketAddr>,
 	/// Port for UDP connections, same as TCP by default
@@ -103,6 +115,15 @@ pub struct NetworkConfiguration {
 	pub non_reserved_mode: NonReservedPeerMode,
 	/// IP filter
 	pub allow_ips: AllowIP,
+	/// Close sessions older than this many seconds so that they are re-established with fresh ephemeral keys.
+	/// Sessions are closed one at a time and sessions with reserved peers are kept.
+	pub session_rekey_interval: Option<u64>,
+	/// Proxy for outbound TCP connections. Discovery traffic is not proxied.
+	pub proxy: Option<Proxy>,
+	/// Maximum number of peers connected from the same IP address. Reserved peers are exempt.
+	pub max_peers_per_ip: Option<u32>,
+	/// Maximum number of peers connected from the same /24 (IPv4) or /64 (IPv6) subnet. Reserved peers are exempt.
+	pub max_peers_per_subnet: Option<u32>,
 }
 
 impl Default for NetworkConfiguration {
@@ -118,6 +139,8 @@ impl NetworkConfiguration {
 			config_path: None,
 			net_config_path: None,
 			listen_address: None,
+			extra_listen_addresses: Vec::new(),
+			prefer_ipv6: false,
 			public_address: None,
 			udp_port: None,
 			nat_enabled: true,
@@ -131,6 +154,10 @@ impl NetworkConfiguration {
 			allow_ips: AllowIP::All,
 			reserved_nodes: Vec::new(),
 			non_reserved_mode: NonReservedPeerMode::Accept,
+			session_rekey_interval: None,
+			proxy: None,
+			max_peers_per_ip: None,
+			max_peers_per_subnet: None,
 		}
 	}
 
@@ -186,6 +213,8 @@ pub enum NetworkIoMessage {
 	DisablePeer(PeerId),
 	/// Network has been started with the host as the given enode.
 	NetworkStarted(String),
+	/// A connection through the proxy has been established.
+	ProxyConnected,
 }
 
 /// Local (temporary) peer session ID.
@@ -334,6 +363,10 @@ pub struct HostInfo {
 	pub local_endpoint: NodeEndpoint,
 	/// Public address + discovery port
 	pub public_endpoint: Option<NodeEndpoint>,
+	/// Local endpoints of the additional listeners
+	pub extra_local_endpoints: Vec<NodeEndpoint>,
+	/// Public endpoints reachable through the additional listeners
+	pub extra_public_endpoints: Vec<NodeEndpoint>,
 }
 
 impl HostInfo {
@@ -366,6 +399,7 @@ struct ProtocolTimer {
 pub struct Host {
 	pub info: RwLock<HostInfo>,
 	tcp_listener: Mutex<TcpListener>,
+	extra_listeners: Vec<Mutex<TcpListener>>,
 	sessions: Arc<RwLock<Slab<SharedSession>>>,
 	discovery: Mutex<Option<Discovery>>,
 	nodes: RwLock<NodeTable>,
@@ -375,12 +409,16 @@ pub struct Host {
 	stats: Arc<NetworkStats>,
 	reserved_nodes: RwLock<HashSet<NodeId>>,
 	num_sessions: AtomicUsize,
+	session_addresses: RwLock<SessionAddresses>,
 	stopping: AtomicBool,
+	filter: Option<Arc<ConnectionFilter>>,
+	/// Outbound connections through the proxy, `None` while the proxy handshake is running.
+	proxy_connections: Arc<Mutex<HashMap<NodeId, Option<net::TcpStream>>>>,
 }
 
 impl Host {
 	/// Create a new instance
-	pub fn new(mut config: NetworkConfiguration, stats: Arc<NetworkStats>) -> Result<Host, NetworkError> {
+	pub fn new(mut config: NetworkConfiguration, stats: Arc<NetworkStats>, filter: Option<Arc<ConnectionFilter>>) -> Result<Host, NetworkError> {
 		trace!(target: "host", "Creating new Host object");
 
 		let mut listen_address = match config.listen_address {
@@ -408,11 +446,24 @@ impl Host {
 		let udp_port = config.udp_port.unwrap_or(listen_address.port());
 		let local_endpoint = NodeEndpoint { address: listen_address, udp_port: udp_port };
 
+		if config.extra_listen_addresses.len() > MAX_EXTRA_LISTENERS {
+			warn!(target: "network", "Only the first {} additional listen addresses are used", MAX_EXTRA_LISTENERS);
+		}
+		let mut extra_listeners = Vec::new();
+		let mut extra_local_endpoints = Vec::new();
+		for address in config.extra_listen_addresses.iter().take(MAX_EXTRA_LISTENERS) {
+			let listener = try!(TcpListener::bind(address));
+			let address = SocketAddr::new(address.ip(), try!(listener.local_addr()).port());
+			// discovery only runs on the main UDP socket.
+			extra_local_endpoints.push(NodeEndpoint { address: address, udp_port: udp_port });
+			extra_listeners.push(Mutex::new(listener));
+		}
+
 		let boot_nodes = config.boot_nodes.clone();
 		let reserved_nodes = config.reserved_nodes.clone();
 		config.max_handshakes = min(config.max_handshakes, MAX_HANDSHAKES as u32);
 
-		let mut host = Host {
+		let host = Host {
 			info: RwLock::new(HostInfo {
 				keys: keys,
 				config: config,
@@ -422,9 +473,12 @@ impl Host {
 				capabilities: Vec::new(),
 				public_endpoint: None,
 				local_endpoint: local_endpoint,
+				extra_local_endpoints: extra_local_endpoints,
+				extra_public_endpoints: Vec::new(),
 			}),
 			discovery: Mutex::new(None),
 			tcp_listener: Mutex::new(tcp_listener),
+			extra_listeners: extra_listeners,
 			sessions: Arc::new(RwLock::new(Slab::new_starting_at(FIRST_SESSION, MAX_SESSIONS))),
 			nodes: RwLock::new(NodeTable::new(path)),
 			handlers: RwLock::new(HashMap::new()),
@@ -433,11 +487,16 @@ impl Host {
 			stats: stats,
 			reserved_nodes: RwLock::new(HashSet::new()),
 			num_sessions: AtomicUsize::new(0),
+			session_addresses: RwLock::new(SessionAddresses::default()),
 			stopping: AtomicBool::new(false),
+			filter: filter,
+			proxy_connections: Arc::new(Mutex::new(HashMap::new())),
 		};
 
 		for n in boot_nodes {
-			host.add_node(&n);
+			if let Err(e) = host.add_node(&n) {
+				debug!(target: "network", "Could not add node {}: {:?}", n, e);
+			}
 		}
 
 		for n in reserved_nodes {
@@ -448,18 +507,20 @@ impl Host {
 		Ok(host)
 	}
 
-	pub fn add_node(&mut self, id: &str) {
-		match Node::from_str(id) {
-			Err(e) => { debug!(target: "network", "Could not add node {}: {:?}", id, e); },
-			Ok(n) => {
-				let entry = NodeEntry { endpoint: n.endpoint.clone(), id: n.id.clone() };
+	/// Add a node to the node table and discovery, e.g. a boot node.
+	pub fn add_node(&self, id: &str) -> Result<(), NetworkError> {
+		let n = try!(Node::from_str(id));
+		let entry = NodeEntry { endpoint: n.endpoint.clone(), id: n.id.clone() };
 
-				self.nodes.write().add_node(n);
-				if let Some(ref mut discovery) = *self.discovery.lock() {
-					discovery.add_node(entry);
-				}
-			}
+		self.nodes.write().add_node(n);
+		if let Some(ref mut discovery) = *self.discovery.lock() {
+			discovery.add_node(entry);
 		}
+		Ok(())
+	}
+
+	fn connection_allowed(&self, own_id: &NodeId, id: &NodeId, direction: ConnectionDirection) -> bool {
+		self.filter.as_ref().map_or(true, |f| f.connection_allowed(own_id, id, direction))
 	}
 
 	pub fn add_reserved_node(&self, id: &str) -> Result<(), NetworkError> {
@@ -521,6 +582,15 @@ impl Host {
 		self.info.read().public_endpoint.as_ref().map(|e| format!("{}", Node::new(self.info.read().id().clone(), e.clone())))
 	}
 
+	/// Returns enode URLs for all the public endpoints, starting with the one advertised in discovery.
+	pub fn external_urls(&self) -> Vec<String> {
+		let info = self.info.read();
+		info.public_endpoint.iter()
+			.chain(info.extra_public_endpoints.iter())
+			.map(|e| format!("{}", Node::new(info.id().clone(), e.clone())))
+			.collect()
+	}
+
 	pub fn local_url(&self) -> String {
 		let r = format!("{}", Node::new(self.info.read().id().clone(), self.info.read().local_endpoint.clone()));
 		println!("{}", r);
@@ -550,9 +620,10 @@ impl Host {
 		let local_endpoint = self.info.read().local_endpoint.clone();
 		let public_address = self.info.read().config.public_address.clone();
 		let allow_ips = self.info.read().config.allow_ips;
+		let prefer_ipv6 = self.info.read().config.prefer_ipv6;
 		let public_endpoint = match public_address {
 			None => {
-				let public_address = select_public_address(local_endpoint.address.port());
+				let public_address = select_public_address(local_endpoint.address.port(), prefer_ipv6);
 				let public_endpoint = NodeEndpoint { address: public_address, udp_port: local_endpoint.udp_port };
 				if self.info.read().config.nat_enabled {
 					match map_external_address(&local_endpoint) {
@@ -569,19 +640,65 @@ impl Host {
 			Some(addr) => NodeEndpoint { address: addr, udp_port: local_endpoint.udp_port }
 		};
 
-		self.info.write().public_endpoint = Some(public_endpoint.clone());
+		let extra_public_endpoints = {
+			let info = self.info.read();
+			let mut endpoints: Vec<NodeEndpoint> = Vec::new();
+			for local in &info.extra_local_endpoints {
+				let address = match local.address.ip() {
+					IpAddr::V4(ref ip) if ip.is_unspecified_s() => select_public_address_of_family(local.address.port(), false),
+					IpAddr::V6(ref ip) if ip.is_unspecified_s() => select_public_address_of_family(local.address.port(), true),
+					_ => Some(local.address),
+				};
+				if let Some(address) = address {
+					if address != public_endpoint.address && !endpoints.iter().any(|e| e.address == address) {
+						endpoints.push(NodeEndpoint { address: address, udp_port: public_endpoint.udp_port });
+					}
+				}
+			}
+			endpoints
+		};
+
+		{
+			let mut info = self.info.write();
+			info.public_endpoint = Some(public_endpoint.clone());
+			info.extra_public_endpoints = extra_public_endpoints;
+		}
 
-		if let Some(url) = self.external_url() {
-			io.message(NetworkIoMessage::NetworkStarted(url)).unwrap_or_else(|e| warn!("Error sending IO notification: {:?}", e));
+		// Discovery announces each endpoint to nodes of its address family.
+		let urls = self.external_urls();
+		if let Some(url) = urls.first() {
+			io.message(NetworkIoMessage::NetworkStarted(url.clone())).unwrap_or_else(|e| warn!("Error sending IO notification: {:?}", e));
+		}
+		for url in urls.iter().skip(1) {
+			info!(target: "network", "Additional node URL: {}", url);
 		}
 
 		// Initialize discovery.
 		let discovery = {
 			let info = self.info.read();
-			if info.config.discovery_enabled && info.config.non_reserved_mode == NonReservedPeerMode::Accept {
+			if info.config.discovery_enabled && info.config.proxy.is_some() {
+				info!(target: "network", "Node discovery disabled: it uses UDP, which cannot be routed through the proxy.");
+			}
+			if info.config.discovery_enabled && info.config.proxy.is_none() && info.config.non_reserved_mode == NonReservedPeerMode::Accept {
 				let mut udp_addr = local_endpoint.address.clone();
 				udp_addr.set_port(local_endpoint.udp_port);
-				Some(Discovery::new(&info.keys, udp_addr, public_endpoint, DISCOVERY, allow_ips))
+				let mut discovery = Discovery::new(&info.keys, udp_addr, public_endpoint, DISCOVERY, allow_ips);
+				discovery.set_extra_endpoints(info.extra_public_endpoints.clone());
+				// keep answering for the identity replaced by the last rotation.
+				let previous = match (&info.config.use_secret, &info.config.config_path) {
+					(&None, &Some(ref path)) => node_key::load_previous(Path::new(path)),
+					_ => None,
+				};
+				if let Some((secret, until)) = previous {
+					match KeyPair::from_secret(secret) {
+						Ok(key) => {
+							info!(target: "network", "Answering discovery for previous node id {} until {}", key.public(), until);
+							discovery.set_previous_key(&key, until);
+						},
+						Err(e) => warn!(target: "network", "Invalid previous node key: {:?}", e),
+					}
+				}
+				Some(discovery)
 			} else { None }
 		};
 
@@ -595,11 +712,15 @@ impl Host {
 		}
 		try!(io.register_timer(NODE_TABLE, NODE_TABLE_TIMEOUT));
 		try!(io.register_stream(TCP_ACCEPT));
+		for i in 0..self.extra_listeners.len() {
+			try!(io.register_stream(FIRST_EXTRA_TCP_ACCEPT + i));
+		}
 		Ok(())
 	}
 
 	fn maintain_network(&self, io: &IoContext<NetworkIoMessage>) {
 		self.keep_alive(io);
+		self.rotate_session_keys(io);
 		self.connect_peers(io);
 	}
 
@@ -612,7 +733,7 @@ impl Host {
 	}
 
 	fn connecting_to(&self, id: &NodeId) -> bool {
-		self.sessions.read().iter().any(|e| e.lock().id() == Some(id))
+		self.proxy_connections.lock().contains_key(id) || self.sessions.read().iter().any(|e| e.lock().id() == Some(id))
 	}
 
 	fn handshake_count(&self) -> usize {
@@ -635,6 +756,42 @@ impl Host {
 		}
 	}
 
+	/// `RLPx` has no in-session rekeying, so sessions past the rekey interval are closed
+	/// and re-established by `connect_peers` with a new handshake.
+	/// Only the oldest expired session is closed each round, so that peers are not all dropped at once.
+	/// Reserved peers are never dropped.
+	fn rotate_session_keys(&self, io: &IoContext<NetworkIoMessage>) {
+		let interval = match self.info.read().config.session_rekey_interval {
+			Some(interval) => interval,
+			None => return,
+		};
+		let reserved = self.reserved_nodes.read().clone();
+		let mut oldest: Option<(u64, SharedSession)> = None;
+		for e in self.sessions.read().iter() {
+			let s = e.lock();
+			if !s.is_ready() || s.id().map_or(false, |id| reserved.contains(id)) {
+				continue;
+			}
+			match s.key_age_sec() {
+				Some(age) if age >= interval && oldest.as_ref().map_or(true, |&(oldest_age, _)| age > oldest_age) => {
+					oldest = Some((age, e.clone()));
+				}
+				_ => {},
+			}
+		}
+		if let Some((_, e)) = oldest {
+			let p = {
+				let mut s = e.lock();
+				s.disconnect(io, DisconnectReason::DisconnectRequested);
+				s.token()
+			};
+			trace!(target: "network", "Session keys expired: {}", p);
+			self.stats.inc_rekeys();
+			metrics().counter("parity_network_session_rekeys_total", "Sessions closed to renew their keys.", &[]).inc();
+			self.kill_connection(p, io, false);
+		}
+	}
+
 	fn connect_peers(&self, io: &IoContext<NetworkIoMessage>) {
 		let (min_peers, mut pin, max_handshakes, allow_ips, self_id) = {
 			let info = self.info.read();
@@ -647,7 +804,7 @@ impl Host {
 		};
 
 		let session_count = self.session_count();
-		let reserved_nodes = self.reserved_nodes.read();
+		let reserved_nodes = self.reserved_nodes.read().iter().cloned().collect::<Vec<_>>();
 		if session_count >= min_peers as usize + reserved_nodes.len() {
 			// check if all pinned nodes are connected.
 			if reserved_nodes.iter().all(|n| self.have_session(n) && self.connecting_to(n)) {
@@ -666,7 +823,7 @@ impl Host {
 
 		// iterate over all nodes, reserved ones coming first.
 		// if we are pinned to only reserved nodes, ignore all others.
-		let nodes = reserved_nodes.iter().cloned().chain(if !pin {
+		let nodes = reserved_nodes.into_iter().chain(if !pin {
 			self.nodes.read().nodes(allow_ips)
 		} else {
 			Vec::new()
@@ -674,7 +831,7 @@ impl Host {
 
 		let max_handshakes_per_round = max_handshakes / 2;
 		let mut started: usize = 0;
-		for id in nodes.filter(|id| !self.have_session(id) && !self.connecting_to(id) && *id != self_id)
+		for id in nodes.filter(|id| !self.have_session(id) && !self.connecting_to(id) && *id != self_id && self.connection_allowed(&self_id, id, ConnectionDirection::Outbound))
 			.take(min(max_handshakes_per_round, max_handshakes - handshake_count)) {
 			self.connect_peer(&id, io);
 			started += 1;
@@ -706,6 +863,11 @@ impl Host {
 					return;
 				}
 			};
+			let proxy = self.info.read().config.proxy.clone();
+			if let Some(proxy) = proxy {
+				self.connect_through_proxy(proxy, id.clone(), address, io);
+				return;
+			}
 			match TcpStream::connect(&address) {
 				Ok(socket) => socket,
 				Err(e) => {
@@ -719,6 +881,55 @@ impl Host {
 		}
 	}
 
+	/// Run the blocking proxy handshake on its own thread. The event loop is notified
+	/// with `ProxyConnected` once the connection is ready to be handed over.
+	fn connect_through_proxy(&self, proxy: Proxy, id: NodeId, address: SocketAddr, io: &IoContext<NetworkIoMessage>) {
+		let mut connections = self.proxy_connections.lock();
+		if connections.len() >= MAX_PROXY_HANDSHAKES {
+			trace!(target: "network", "Aborted connect. Too many proxy handshakes.");
+			return;
+		}
+		connections.insert(id.clone(), None);
+
+		let pending = self.proxy_connections.clone();
+		let channel = io.channel();
+		let node = id.clone();
+		let spawned = thread::Builder::new().name("proxy-connect".into()).spawn(move || {
+			match proxy.connect(&address) {
+				Ok(stream) => {
+					pending.lock().insert(node, Some(stream));
+					channel.send(NetworkIoMessage::ProxyConnected).unwrap_or_else(|e| warn!("Error sending IO notification: {:?}", e));
+				},
+				Err(e) => {
+					debug!(target: "network", "Can't connect to address {:?} through {}: {:?}", address, proxy, e);
+					pending.lock().remove(&node);
+				},
+			}
+		});
+		if let Err(e) = spawned {
+			debug!(target: "network", "Can't start proxy handshake: {:?}", e);
+			connections.remove(&id);
+		}
+	}
+
+	/// Create sessions for the connections established through the proxy.
+	fn create_proxied_connections(&self, io: &IoContext<NetworkIoMessage>) {
+		let ready = {
+			let mut connections = self.proxy_connections.lock();
+			let ids = connections.iter().filter(|&(_, stream)| stream.is_some()).map(|(id, _)| id.clone()).collect::<Vec<_>>();
+			ids.into_iter().filter_map(|id| connections.remove(&id).and_then(|stream| stream).map(|stream| (id, stream))).collect::<Vec<_>>()
+		};
+		for (id, stream) in ready {
+			if self.have_session(&id) {
+				continue;
+			}
+			let result = TcpStream::from_stream(stream).map_err(From::from).and_then(|socket| self.create_connection(socket, Some(&id), io));
+			if let Err(e) = result {
+				debug!(target: "network", "Can't create connection: {:?}", e);
+			}
+		}
+	}
+
 	#[cfg_attr(feature="dev", allow(block_in_if_condition_stmt))]
 	fn create_connection(&self, socket: TcpStream, id: Option<&NodeId>, io: &IoContext<NetworkIoMessage>) -> Result<(), NetworkError> {
 		let nonce = self.info.write().next_nonce();
@@ -743,10 +954,10 @@ impl Host {
 		}
 	}
 
-	fn accept(&self, io: &IoContext<NetworkIoMessage>) {
+	fn accept(&self, listener: &Mutex<TcpListener>, io: &IoContext<NetworkIoMessage>) {
 		trace!(target: "network", "Accepting incoming connection");
 		loop {
-			let socket = match self.tcp_listener.lock().accept() {
+			let socket = match listener.lock().accept() {
 				Ok((sock, _addr)) => sock,
 				Err(e) => {
 					debug!(target: "network", "Error accepting connection: {:?}", e);
@@ -816,6 +1027,15 @@ impl Host {
 							(info.config.min_peers as usize, max_peers as usize, info.config.non_reserved_mode == NonReservedPeerMode::Deny)
 						};
 
+						let id = s.id().expect("Ready session always has id").clone();
+						let direction = if s.info.originated { ConnectionDirection::Outbound } else { ConnectionDirection::Inbound };
+						if !self.connection_allowed(self.info.read().id(), &id, direction) {
+							trace!(target: "network", "Connection with {:?} not allowed by the filter", id);
+							s.disconnect(io, DisconnectReason::UnexpectedIdentity);
+							return;
+						}
+						self.nodes.write().note_success(&id);
+
 						// Check for the session limit. session_counts accounts for the new session.
 						if reserved_only ||
 							(s.info.originated && session_count > min_peers) ||
@@ -827,6 +1047,27 @@ impl Host {
 							}
 						}
 
+						// Check for the per-IP and per-subnet limits. Outbound sessions may go through
+						// a proxy, so the dialed address is used for them.
+						let address = if s.info.originated {
+							self.nodes.read().get(&id).map(|node| node.endpoint.address)
+						} else {
+							s.remote_addr().ok()
+						};
+						if let Some(address) = address {
+							let (max_per_ip, max_per_subnet) = {
+								let info = self.info.read();
+								(info.config.max_peers_per_ip, info.config.max_peers_per_subnet)
+							};
+							let mut addresses = self.session_addresses.write();
+							if !addresses.allows(&address.ip(), max_per_ip, max_per_subnet) && !self.reserved_nodes.read().contains(&id) {
+								trace!(target: "network", "Too many peers from the address of {:?} ({})", id, address);
+								s.disconnect(io, DisconnectReason::TooManyPeers);
+								return;
+							}
+							addresses.insert(token, address.ip());
+						}
+
 						// Add it to the node table
 						if !s.info.originated {
 							if let Ok(address) = s.remote_addr() {
@@ -865,6 +1106,7 @@ impl Host {
 		let handlers = self.handlers.read();
 		for p in ready_data {
 			self.stats.inc_sessions();
+			metrics().counter("parity_network_sessions_total", "Protocol sessions established.", &[]).inc();
 			let reserved = self.reserved_nodes.read();
 			if let Some(h) = handlers.get(&p).clone() {
 				h.connected(&NetworkContext::new(io, p, session.clone(), self.sessions.clone(), &reserved), &token);
@@ -896,6 +1138,7 @@ impl Host {
 				if !s.expired() {
 					if s.is_ready() {
 						self.num_sessions.fetch_sub(1, AtomicOrdering::SeqCst);
+						self.session_addresses.write().remove(token);
 						for (p, _) in self.handlers.read().iter() {
 							if s.have_capability(*p)  {
 								to_disconnect.push(*p);
@@ -986,7 +1229,12 @@ impl IoHandler<NetworkIoMessage> for Host {
 					self.update_nodes(io, node_changes);
 				}
 			},
-			TCP_ACCEPT => self.accept(io),
+			TCP_ACCEPT => self.accept(&self.tcp_listener, io),
+			FIRST_EXTRA_TCP_ACCEPT ... LAST_EXTRA_TCP_ACCEPT => {
+				if let Some(listener) = self.extra_listeners.get(stream - FIRST_EXTRA_TCP_ACCEPT) {
+					self.accept(listener, io);
+				}
+			},
 			_ => panic!("Received unknown readable token"),
 		}
 	}
@@ -1096,6 +1344,7 @@ impl IoHandler<NetworkIoMessage> for Host {
 			},
 			NetworkIoMessage::InitPublicInterface =>
 				self.init_public_interface(io).unwrap_or_else(|e| warn!("Error initializing public interface: {:?}", e)),
+			NetworkIoMessage::ProxyConnected => self.create_proxied_connections(io),
 			_ => {}	// ignore others.
 		}
 	}
@@ -1110,6 +1359,11 @@ impl IoHandler<NetworkIoMessage> for Host {
 			}
 			DISCOVERY => self.discovery.lock().as_ref().and_then(|d| d.register_socket(event_loop).ok()).expect("Error registering discovery socket"),
 			TCP_ACCEPT => event_loop.register(&*self.tcp_listener.lock(), Token(TCP_ACCEPT), Ready::all(), PollOpt::edge()).expect("Error registering stream"),
+			FIRST_EXTRA_TCP_ACCEPT ... LAST_EXTRA_TCP_ACCEPT => {
+				if let Some(listener) = self.extra_listeners.get(stream - FIRST_EXTRA_TCP_ACCEPT) {
+					event_loop.register(&*listener.lock(), reg, Ready::all(), PollOpt::edge()).expect("Error registering stream");
+				}
+			},
 			_ => warn!("Unexpected stream registration")
 		}
 	}
@@ -1138,56 +1392,31 @@ impl IoHandler<NetworkIoMessage> for Host {
 			}
 			DISCOVERY => self.discovery.lock().as_ref().and_then(|d| d.update_registration(event_loop).ok()).expect("Error reregistering discovery socket"),
 			TCP_ACCEPT => event_loop.reregister(&*self.tcp_listener.lock(), Token(TCP_ACCEPT), Ready::all(), PollOpt::edge()).expect("Error reregistering stream"),
+			FIRST_EXTRA_TCP_ACCEPT ... LAST_EXTRA_TCP_ACCEPT => {
+				if let Some(listener) = self.extra_listeners.get(stream - FIRST_EXTRA_TCP_ACCEPT) {
+					event_loop.reregister(&*listener.lock(), reg, Ready::all(), PollOpt::edge()).expect("Error reregistering stream");
+				}
+			},
 			_ => warn!("Unexpected stream update")
 		}
 	}
 }
 
 fn save_key(path: &Path, key: &Secret) {
-	let mut path_buf = PathBuf::from(path);
-	if let Err(e) = fs::create_dir_all(path_buf.as_path()) {
-		warn!("Error creating key directory: {:?}", e);
-		return;
-	};
-	path_buf.push("key");
-	let path = path_buf.as_path();
-	let mut file = match fs::File::create(&path) {
-		Ok(file) => file,
-		Err(e) => {
-			warn!("Error creating key file: {:?}", e);
-			return;
-		}
-	};
-	if let Err(e) = restrict_permissions_owner(path) {
-		warn!(target: "network", "Failed to modify permissions of the file (chmod: {})", e);
-	}
-	if let Err(e) = file.write(&key.hex().into_bytes()) {
+	if let Err(e) = node_key::save(path, key) {
 		warn!("Error writing key file: {:?}", e);
 	}
 }
 
 fn load_key(path: &Path) -> Option<Secret> {
-	let mut path_buf = PathBuf::from(path);
-	path_buf.push("key");
-	let mut file = match fs::File::open(path_buf.as_path()) {
-		Ok(file) => file,
-		Err(e) => {
+	match node_key::load(path) {
+		Ok(key) => Some(key),
+		Err(ref e) if e.kind() == ::std::io::ErrorKind::NotFound => {
 			debug!("Error opening key file: {:?}", e);
-			return None;
-		}
-	};
-	let mut buf = String::new();
-	match file.read_to_string(&mut buf) {
-		Ok(_) => {},
+			None
+		},
 		Err(e) => {
 			warn!("Error reading key file: {:?}", e);
-			return None;
-		}
-	}
-	match Secret::from_str(&buf) {
-		Ok(key) => Some(key),
-		Err(e) => {
-			warn!("Error parsing key file: {:?}", e);
 			None
 		}
 	}
@@ -1209,6 +1438,6 @@ fn host_client_url() {
 	let mut config = NetworkConfiguration::new_local();
 	let key = "6f7b0d801bc7b5ce7bbd930b84fd0369b3eb25d09be58d64ba811091046f3aa2".into();
 	config.use_secret = Some(key);
-	let host: Host = Host::new(config, Arc::new(NetworkStats::new())).unwrap();
+	let host: Host = Host::new(config, Arc::new(NetworkStats::new()), None).unwrap();
 	assert!(host.local_url().starts_with("enode://101b3ef5a4ea7a1c7928e24c4c75fd053c235d7b80c22ae5c03d145d0ac7396e2a4ffff9adee3133a7b05044a5cee08115fd65145e5165d646bde371010d803c@"));
 }
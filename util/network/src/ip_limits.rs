@@ -0,0 +1,100 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Limits on the number of peers sharing an IP address or subnet.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use io::StreamToken;
+
+/// Returns the subnet of an address: /24 for IPv4 and /64 for IPv6.
+/// IPv4-mapped IPv6 addresses are treated as IPv4.
+pub fn subnet(ip: &IpAddr) -> IpAddr {
+	match *ip {
+		IpAddr::V4(ref ip) => v4_subnet(ip),
+		IpAddr::V6(ref ip) => match ip.to_ipv4() {
+			Some(ref ip) if ip.octets()[0] != 0 => v4_subnet(ip),
+			_ => {
+				let s = ip.segments();
+				IpAddr::V6(Ipv6Addr::new(s[0], s[1], s[2], s[3], 0, 0, 0, 0))
+			},
+		},
+	}
+}
+
+fn v4_subnet(ip: &Ipv4Addr) -> IpAddr {
+	let o = ip.octets();
+	IpAddr::V4(Ipv4Addr::new(o[0], o[1], o[2], 0))
+}
+
+/// Remote addresses of the established sessions.
+#[derive(Default)]
+pub struct SessionAddresses {
+	addresses: HashMap<StreamToken, IpAddr>,
+}
+
+impl SessionAddresses {
+	/// Records the address of an established session.
+	pub fn insert(&mut self, token: StreamToken, ip: IpAddr) {
+		self.addresses.insert(token, ip);
+	}
+
+	/// Forgets a closed session.
+	pub fn remove(&mut self, token: StreamToken) {
+		self.addresses.remove(&token);
+	}
+
+	/// Returns true if another session from `ip` stays within the given limits.
+	pub fn allows(&self, ip: &IpAddr, max_per_ip: Option<u32>, max_per_subnet: Option<u32>) -> bool {
+		let subnet = subnet(ip);
+		let same_ip = self.addresses.values().filter(|a| *a == ip).count() as u32;
+		let same_subnet = self.addresses.values().filter(|a| self::subnet(a) == subnet).count() as u32;
+		max_per_ip.map_or(true, |max| same_ip < max) && max_per_subnet.map_or(true, |max| same_subnet < max)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::IpAddr;
+	use super::{subnet, SessionAddresses};
+
+	fn ip(s: &str) -> IpAddr {
+		s.parse().unwrap()
+	}
+
+	#[test]
+	fn should_compute_subnet() {
+		assert_eq!(subnet(&ip("10.1.2.3")), ip("10.1.2.0"));
+		assert_eq!(subnet(&ip("2001:db8:1:2:3:4:5:6")), ip("2001:db8:1:2::"));
+		assert_eq!(subnet(&ip("::ffff:10.1.2.3")), ip("10.1.2.0"));
+	}
+
+	#[test]
+	fn should_limit_peers_per_ip_and_subnet() {
+		let mut addresses = SessionAddresses::default();
+		addresses.insert(0, ip("10.1.2.3"));
+		addresses.insert(1, ip("10.1.2.4"));
+
+		assert!(addresses.allows(&ip("10.1.2.3"), None, None));
+		assert!(!addresses.allows(&ip("10.1.2.3"), Some(1), None));
+		assert!(addresses.allows(&ip("10.1.2.5"), Some(1), None));
+		assert!(!addresses.allows(&ip("10.1.2.5"), Some(1), Some(2)));
+		assert!(addresses.allows(&ip("10.1.3.5"), Some(1), Some(2)));
+
+		addresses.remove(0);
+		assert!(addresses.allows(&ip("10.1.2.3"), Some(1), Some(2)));
+	}
+}
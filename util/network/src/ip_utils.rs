@@ -152,33 +152,49 @@ fn get_if_addrs() -> io::Result<Vec<IpAddr>> {
 	Ok(Vec::new())
 }
 
-/// Select the best available public address
-pub fn select_public_address(port: u16) -> SocketAddr {
+/// Select the first usable interface address of the given family.
+fn select_address_of_family(list: &[IpAddr], port: u16, ipv6: bool) -> Option<SocketAddr> {
+	for addr in list { //TODO: use better criteria than just the first in the list
+		match *addr {
+			IpAddr::V4(a) if !ipv6 && !a.is_unspecified_s() && !a.is_loopback() && !a.is_link_local() => {
+				return Some(SocketAddr::V4(SocketAddrV4::new(a, port)));
+			},
+			IpAddr::V6(a) if ipv6 && !a.is_unspecified_s() && !a.is_loopback() => {
+				return Some(SocketAddr::V6(SocketAddrV6::new(a, port, 0, 0)));
+			},
+			_ => {},
+		}
+	}
+	None
+}
+
+/// Select an address from the list, trying the preferred family first.
+fn select_address(list: &[IpAddr], port: u16, prefer_ipv6: bool) -> SocketAddr {
+	select_address_of_family(list, port, prefer_ipv6)
+		.or_else(|| select_address_of_family(list, port, !prefer_ipv6))
+		.unwrap_or_else(|| SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), port)))
+}
+
+/// Select a public interface address, preferring IPv4 bindings unless `prefer_ipv6` is set.
+pub fn select_public_address(port: u16, prefer_ipv6: bool) -> SocketAddr {
 	match get_if_addrs() {
-		Ok(list) => {
-			//prefer IPV4 bindings
-			for addr in &list { //TODO: use better criteria than just the first in the list
-				match *addr {
-					IpAddr::V4(a) if !a.is_unspecified_s() && !a.is_loopback() && !a.is_link_local() => {
-						return SocketAddr::V4(SocketAddrV4::new(a, port));
-					},
-					_ => {},
-				}
-			}
-			for addr in list {
-				match addr {
-					IpAddr::V6(a) if !a.is_unspecified_s() && !a.is_loopback() => {
-						return SocketAddr::V6(SocketAddrV6::new(a, port, 0, 0));
-					},
-					_ => {},
-				}
-			}
-		},
+		Ok(list) => return select_address(&list, port, prefer_ipv6),
 		Err(e) => debug!("Error listing public interfaces: {:?}", e)
 	}
 	SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), port))
 }
 
+/// Select a public interface address of the given family, if there is one.
+pub fn select_public_address_of_family(port: u16, ipv6: bool) -> Option<SocketAddr> {
+	match get_if_addrs() {
+		Ok(list) => select_address_of_family(&list, port, ipv6),
+		Err(e) => {
+			debug!("Error listing public interfaces: {:?}", e);
+			None
+		}
+	}
+}
+
 pub fn map_external_address(local: &NodeEndpoint) -> Option<NodeEndpoint> {
 	if let SocketAddr::V4(ref local_addr) = local.address {
 		match search_gateway_from_timeout(local_addr.ip().clone(), Duration::new(5, 0)) {
@@ -214,14 +230,28 @@ pub fn map_external_address(local: &NodeEndpoint) -> Option<NodeEndpoint> {
 
 #[test]
 fn can_select_public_address() {
-	let pub_address = select_public_address(40477);
+	let pub_address = select_public_address(40477, false);
 	assert!(pub_address.port() == 40477);
 }
 
+#[test]
+fn select_address_respects_family_preference() {
+	let v4 = IpAddr::V4(Ipv4Addr::new(192, 168, 0, 2));
+	let v6 = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+	let loopback = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+	let list = [loopback, v6, v4];
+
+	assert_eq!(select_address(&list, 30303, false), SocketAddr::new(v4, 30303));
+	assert_eq!(select_address(&list, 30303, true), SocketAddr::new(v6, 30303));
+	// falls back to the other family.
+	assert_eq!(select_address(&[loopback, v4], 30303, true), SocketAddr::new(v4, 30303));
+	assert_eq!(select_address_of_family(&[loopback, v4], 30303, true), None);
+}
+
 #[ignore]
 #[test]
 fn can_map_external_address_or_fail() {
-	let pub_address = select_public_address(40478);
+	let pub_address = select_public_address(40478, false);
 	let _ = map_external_address(&NodeEndpoint { address: pub_address, udp_port: 40478 });
 }
 
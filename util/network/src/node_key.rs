@@ -0,0 +1,161 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Node key storage and identity rotation.
+//!
+//! The node key is kept in the `key` file of the network configuration directory.
+//! After a rotation the previous key is kept in `key.previous` along with the time
+//! until which discovery keeps answering for it, so that peers can learn the new identity.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use ethkey::{KeyPair, Secret, Random, Generator};
+use time;
+use util::path::restrict_permissions_owner;
+
+const KEY_FILE: &'static str = "key";
+const PREVIOUS_KEY_FILE: &'static str = "key.previous";
+
+fn invalid_data<E: ::std::fmt::Debug>(e: E) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e))
+}
+
+fn read(path: &Path) -> io::Result<String> {
+	let mut content = String::new();
+	try!(try!(fs::File::open(path)).read_to_string(&mut content));
+	Ok(content)
+}
+
+fn write(path: &Path, content: &str) -> io::Result<()> {
+	if let Some(dir) = path.parent() {
+		try!(fs::create_dir_all(dir));
+	}
+	let mut file = try!(fs::File::create(path));
+	if let Err(e) = restrict_permissions_owner(path) {
+		warn!(target: "network", "Failed to modify permissions of the file (chmod: {})", e);
+	}
+	file.write_all(content.as_bytes())
+}
+
+/// Parses a secret in hex, ignoring surrounding whitespace.
+pub fn parse_secret(s: &str) -> io::Result<Secret> {
+	let s = s.trim();
+	let s = if s.starts_with("0x") { &s[2..] } else { s };
+	let secret = try!(Secret::from_str(s).map_err(invalid_data));
+	// make sure it is a valid key
+	try!(KeyPair::from_secret(secret.clone()).map_err(invalid_data));
+	Ok(secret)
+}
+
+/// Reads the node key stored in the network configuration directory.
+pub fn load(dir: &Path) -> io::Result<Secret> {
+	parse_secret(&try!(read(&dir.join(KEY_FILE))))
+}
+
+/// Writes the node key to the network configuration directory.
+pub fn save(dir: &Path, key: &Secret) -> io::Result<()> {
+	write(&dir.join(KEY_FILE), &key.hex())
+}
+
+/// Reads the previous node key if its grace period has not ended yet.
+/// Returns the key and the end of the grace period (unix time, in seconds).
+pub fn load_previous(dir: &Path) -> Option<(Secret, u64)> {
+	let path: PathBuf = dir.join(PREVIOUS_KEY_FILE);
+	let content = match read(&path) {
+		Ok(content) => content,
+		Err(_) => return None,
+	};
+	let mut lines = content.lines();
+	let parsed = match (lines.next().map(parse_secret), lines.next().map(|l| l.trim().parse::<u64>())) {
+		(Some(Ok(secret)), Some(Ok(until))) => Some((secret, until)),
+		_ => {
+			warn!(target: "network", "Invalid previous node key file: {}", path.display());
+			None
+		},
+	};
+	parsed.and_then(|(secret, until)| if until > time::get_time().sec as u64 {
+		Some((secret, until))
+	} else {
+		let _ = fs::remove_file(&path);
+		None
+	})
+}
+
+/// Replaces the node key with `new`, keeping the current key answered in discovery
+/// for `grace_period` seconds.
+pub fn rotate(dir: &Path, new: &Secret, grace_period: u64) -> io::Result<()> {
+	if let Ok(current) = load(dir) {
+		let until = time::get_time().sec as u64 + grace_period;
+		try!(write(&dir.join(PREVIOUS_KEY_FILE), &format!("{}\n{}\n", current.hex(), until)));
+	}
+	save(dir, new)
+}
+
+/// Replaces the node key with a newly generated one, as `rotate` does. Returns the new key.
+pub fn rotate_random(dir: &Path, grace_period: u64) -> io::Result<KeyPair> {
+	let key = try!(Random.generate().map_err(invalid_data));
+	try!(rotate(dir, key.secret(), grace_period));
+	Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+	use devtools::RandomTempPath;
+	use ethkey::{Random, Generator};
+	use super::{load, save, load_previous, rotate, rotate_random, parse_secret};
+
+	#[test]
+	fn should_rotate_key() {
+		let temp = RandomTempPath::create_dir();
+		let old = Random.generate().unwrap();
+		let new = Random.generate().unwrap();
+		save(temp.as_path(), old.secret()).unwrap();
+		assert!(load_previous(temp.as_path()).is_none());
+
+		rotate(temp.as_path(), new.secret(), 60).unwrap();
+		assert_eq!(&load(temp.as_path()).unwrap(), new.secret());
+		assert_eq!(&load_previous(temp.as_path()).unwrap().0, old.secret());
+	}
+
+	#[test]
+	fn should_rotate_to_generated_key() {
+		let temp = RandomTempPath::create_dir();
+		let old = Random.generate().unwrap();
+		save(temp.as_path(), old.secret()).unwrap();
+
+		let new = rotate_random(temp.as_path(), 60).unwrap();
+		assert_eq!(&load(temp.as_path()).unwrap(), new.secret());
+		assert_eq!(&load_previous(temp.as_path()).unwrap().0, old.secret());
+	}
+
+	#[test]
+	fn should_forget_previous_key_after_grace_period() {
+		let temp = RandomTempPath::create_dir();
+		let old = Random.generate().unwrap();
+		save(temp.as_path(), old.secret()).unwrap();
+		rotate(temp.as_path(), Random.generate().unwrap().secret(), 0).unwrap();
+		assert!(load_previous(temp.as_path()).is_none());
+	}
+
+	#[test]
+	fn should_parse_secret() {
+		let s = "0x6f7b0d801bc7b5ce7bbd930b84fd0369b3eb25d09be58d64ba811091046f3aa2\n";
+		assert!(parse_secret(s).is_ok());
+		assert!(parse_secret("0x00").is_err());
+	}
+}
@@ -20,6 +20,7 @@ use std::net::{SocketAddr, ToSocketAddrs, SocketAddrV4, SocketAddrV6, Ipv4Addr,
 use std::hash::{Hash, Hasher};
 use std::str::{FromStr};
 use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
 use std::path::{PathBuf};
 use std::fmt;
@@ -124,6 +125,9 @@ impl FromStr for NodeEndpoint {
 	}
 }
 
+/// Nodes we had a session with in this many seconds are tried before any others.
+const RECENT_CONTACT_SECS: u64 = 24 * 60 * 60;
+
 #[derive(PartialEq, Eq, Copy, Clone)]
 pub enum PeerType {
 	_Required,
@@ -136,6 +140,8 @@ pub struct Node {
 	pub peer_type: PeerType,
 	pub failures: u32,
 	pub last_attempted: Option<Tm>,
+	/// UNIX time of the last successfully established session.
+	pub last_contact: Option<u64>,
 }
 
 impl Node {
@@ -146,6 +152,7 @@ impl Node {
 			peer_type: PeerType::Optional,
 			failures: 0,
 			last_attempted: None,
+			last_contact: None,
 		}
 	}
 }
@@ -177,6 +184,7 @@ impl FromStr for Node {
 			peer_type: PeerType::Optional,
 			last_attempted: None,
 			failures: 0,
+			last_contact: None,
 		})
 	}
 }
@@ -212,16 +220,28 @@ impl NodeTable {
 
 	/// Add a node to table
 	pub fn add_node(&mut self, mut node: Node) {
-		// preserve failure counter
-		let failures = self.nodes.get(&node.id).map_or(0, |n| n.failures);
-		node.failures = failures;
+		// preserve failure counter and liveness
+		if let Some(existing) = self.nodes.get(&node.id) {
+			node.failures = existing.failures;
+			node.last_contact = existing.last_contact;
+		}
 		self.nodes.insert(node.id.clone(), node);
 	}
 
-	/// Returns node ids sorted by number of failures
+	/// Returns node ids, recently contacted nodes first (most recent first), then the rest sorted by number of failures
 	pub fn nodes(&self, filter: AllowIP) -> Vec<NodeId> {
+		self.nodes_at(filter, ::time::get_time().sec as u64)
+	}
+
+	fn nodes_at(&self, filter: AllowIP, now: u64) -> Vec<NodeId> {
+		let recent = |n: &Node| n.last_contact.and_then(|t| if now.saturating_sub(t) <= RECENT_CONTACT_SECS { Some(t) } else { None });
 		let mut refs: Vec<&Node> = self.nodes.values().filter(|n| !self.useless_nodes.contains(&n.id) && n.endpoint.is_allowed(filter)).collect();
-		refs.sort_by(|a, b| a.failures.cmp(&b.failures));
+		refs.sort_by(|a, b| match (recent(a), recent(b)) {
+			(Some(a), Some(b)) => b.cmp(&a),
+			(Some(_), None) => Ordering::Less,
+			(None, Some(_)) => Ordering::Greater,
+			(None, None) => a.failures.cmp(&b.failures),
+		});
 		refs.iter().map(|n| n.id.clone()).collect()
 	}
 
@@ -231,6 +251,11 @@ impl NodeTable {
 		self.nodes.values().map(|n| NodeEntry { endpoint: n.endpoint.clone(), id: n.id.clone() }).collect()
 	}
 
+	/// Get particular node
+	pub fn get(&self, id: &NodeId) -> Option<&Node> {
+		self.nodes.get(id)
+	}
+
 	/// Get particular node
 	pub fn get_mut(&mut self, id: &NodeId) -> Option<&mut Node> {
 		self.nodes.get_mut(id)
@@ -256,6 +281,14 @@ impl NodeTable {
 		}
 	}
 
+	/// Record a successfully established session with a node.
+	pub fn note_success(&mut self, id: &NodeId) {
+		if let Some(node) = self.nodes.get_mut(id) {
+			node.failures = 0;
+			node.last_contact = Some(::time::get_time().sec as u64);
+		}
+	}
+
 	/// Mark as useless, no furter attempts to connect until next call to `clear_useless`.
 	pub fn mark_as_useless(&mut self, id: &NodeId) {
 		self.useless_nodes.insert(id.clone());
@@ -281,7 +314,8 @@ impl NodeTable {
 			let node_ids = self.nodes(AllowIP::All);
 			for i in 0 .. node_ids.len() {
 				let node = self.nodes.get(&node_ids[i]).expect("self.nodes() only returns node IDs from self.nodes");
-				json.push_str(&format!("\t{{ \"url\": \"{}\", \"failures\": {} }}{}\n", node, node.failures, if i == node_ids.len() - 1 {""} else {","}))
+				let last_contact = node.last_contact.map_or(String::new(), |t| format!(", \"last_contact\": {}", t));
+				json.push_str(&format!("\t{{ \"url\": \"{}\", \"failures\": {}{} }}{}\n", node, node.failures, last_contact, if i == node_ids.len() - 1 {""} else {","}))
 			}
 			json.push_str("]\n");
 			json.push_str("}");
@@ -332,6 +366,7 @@ impl NodeTable {
 							if let Some(failures) = n.get("failures").and_then(|f| f.as_u64()) {
 								node.failures = failures as u32;
 							}
+							node.last_contact = n.get("last_contact").and_then(|t| t.as_u64());
 							nodes.insert(node.id.clone(), node);
 						}
 					}
@@ -434,4 +469,38 @@ mod tests {
 			assert_eq!(r[1][..], id2[..]);
 		}
 	}
+
+	#[test]
+	fn table_prefers_recently_contacted_nodes() {
+		let temp_path = RandomTempPath::create_dir();
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node3 = Node::from_str("enode://c979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = node1.id.clone();
+		let id2 = node2.id.clone();
+		let id3 = node3.id.clone();
+		{
+			let mut table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			table.add_node(node1);
+			table.add_node(node2);
+			table.add_node(node3);
+			table.note_failure(&id3);
+			table.note_success(&id3);
+			table.get_mut(&id2).unwrap().last_contact = Some(1000);
+		}
+
+		let mut table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+		assert_eq!(table.get_mut(&id3).unwrap().failures, 0);
+		assert!(table.get_mut(&id3).unwrap().last_contact.is_some());
+		// node 3 was contacted just now, node 2 a long time ago.
+		let r = table.nodes(AllowIP::All);
+		assert_eq!(r[0][..], id3[..]);
+
+		// within the recency window the most recent contact comes first.
+		table.get_mut(&id3).unwrap().last_contact = Some(1500);
+		let r = table.nodes_at(AllowIP::All, 2000);
+		assert_eq!(r[0][..], id3[..]);
+		assert_eq!(r[1][..], id2[..]);
+		assert_eq!(r[2][..], id1[..]);
+	}
 }
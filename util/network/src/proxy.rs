@@ -0,0 +1,242 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Outbound peer connections through a SOCKS5 or HTTP `CONNECT` proxy.
+//!
+//! The proxy handshake is performed on a blocking socket, on a thread of its own,
+//! and the socket is then handed over to the event loop. Node discovery is not
+//! proxied, it is disabled when a proxy is used.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{self, SocketAddr, AddrParseError};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Timeout of a single read or write during the proxy handshake.
+const HANDSHAKE_TIMEOUT_MS: u64 = 10_000;
+/// Maximal size of the HTTP proxy response headers.
+const MAX_HTTP_RESPONSE: usize = 4096;
+
+const SOCKS_VERSION: u8 = 5;
+const SOCKS_NO_AUTH: u8 = 0;
+const SOCKS_CMD_CONNECT: u8 = 1;
+const SOCKS_ATYP_IPV4: u8 = 1;
+const SOCKS_ATYP_DOMAIN: u8 = 3;
+const SOCKS_ATYP_IPV6: u8 = 4;
+
+/// Proxy used for outbound peer connections.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Proxy {
+	/// SOCKS5 proxy without authentication, e.g. Tor.
+	Socks5(SocketAddr),
+	/// HTTP proxy supporting the `CONNECT` method.
+	Http(SocketAddr),
+}
+
+impl FromStr for Proxy {
+	type Err = AddrParseError;
+
+	/// Parses `socks5://IP:PORT` or `http://IP:PORT`. Addresses without a scheme are SOCKS5 proxies.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if s.starts_with("http://") {
+			Ok(Proxy::Http(try!(s[7..].trim_right_matches('/').parse())))
+		} else if s.starts_with("socks5://") {
+			Ok(Proxy::Socks5(try!(s[9..].trim_right_matches('/').parse())))
+		} else {
+			Ok(Proxy::Socks5(try!(s.parse())))
+		}
+	}
+}
+
+impl fmt::Display for Proxy {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Proxy::Socks5(ref addr) => write!(f, "socks5://{}", addr),
+			Proxy::Http(ref addr) => write!(f, "http://{}", addr),
+		}
+	}
+}
+
+impl Proxy {
+	/// Address of the proxy.
+	pub fn address(&self) -> &SocketAddr {
+		match *self {
+			Proxy::Socks5(ref addr) | Proxy::Http(ref addr) => addr,
+		}
+	}
+
+	/// Opens a connection to `target` through the proxy.
+	pub fn connect(&self, target: &SocketAddr) -> io::Result<net::TcpStream> {
+		let mut stream = try!(net::TcpStream::connect(self.address()));
+		let timeout = Some(Duration::from_millis(HANDSHAKE_TIMEOUT_MS));
+		try!(stream.set_read_timeout(timeout));
+		try!(stream.set_write_timeout(timeout));
+		match *self {
+			Proxy::Socks5(_) => try!(socks5_handshake(&mut stream, target)),
+			Proxy::Http(_) => try!(http_handshake(&mut stream, target)),
+		}
+		try!(stream.set_read_timeout(None));
+		try!(stream.set_write_timeout(None));
+		Ok(stream)
+	}
+}
+
+fn proxy_error(msg: String) -> io::Error {
+	io::Error::new(io::ErrorKind::Other, msg)
+}
+
+fn socks5_handshake<S: Read + Write>(stream: &mut S, target: &SocketAddr) -> io::Result<()> {
+	try!(stream.write_all(&[SOCKS_VERSION, 1, SOCKS_NO_AUTH]));
+	let mut method = [0u8; 2];
+	try!(stream.read_exact(&mut method));
+	if method != [SOCKS_VERSION, SOCKS_NO_AUTH] {
+		return Err(proxy_error("SOCKS5 proxy requires an unsupported authentication method".into()));
+	}
+
+	let mut request = vec![SOCKS_VERSION, SOCKS_CMD_CONNECT, 0];
+	match *target {
+		SocketAddr::V4(ref addr) => {
+			request.push(SOCKS_ATYP_IPV4);
+			request.extend_from_slice(&addr.ip().octets());
+		},
+		SocketAddr::V6(ref addr) => {
+			request.push(SOCKS_ATYP_IPV6);
+			request.extend_from_slice(&addr.ip().octets());
+		},
+	}
+	request.push((target.port() >> 8) as u8);
+	request.push(target.port() as u8);
+	try!(stream.write_all(&request));
+
+	let mut reply = [0u8; 4];
+	try!(stream.read_exact(&mut reply));
+	if reply[0] != SOCKS_VERSION || reply[1] != 0 {
+		return Err(proxy_error(format!("SOCKS5 proxy refused the connection to {} (reply {})", target, reply[1])));
+	}
+	// skip the bound address and port.
+	let len = match reply[3] {
+		SOCKS_ATYP_IPV4 => 4,
+		SOCKS_ATYP_IPV6 => 16,
+		SOCKS_ATYP_DOMAIN => {
+			let mut len = [0u8; 1];
+			try!(stream.read_exact(&mut len));
+			len[0] as usize
+		},
+		atyp => return Err(proxy_error(format!("SOCKS5 proxy replied with unknown address type {}", atyp))),
+	};
+	let mut bound = vec![0u8; len + 2];
+	stream.read_exact(&mut bound)
+}
+
+fn http_handshake<S: Read + Write>(stream: &mut S, target: &SocketAddr) -> io::Result<()> {
+	try!(write!(stream, "CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n\r\n", target));
+
+	// read byte by byte so that nothing past the headers is consumed.
+	let mut response = Vec::new();
+	let mut byte = [0u8; 1];
+	while !response.ends_with(b"\r\n\r\n") {
+		if response.len() >= MAX_HTTP_RESPONSE {
+			return Err(proxy_error("HTTP proxy response is too long".into()));
+		}
+		try!(stream.read_exact(&mut byte));
+		response.push(byte[0]);
+	}
+
+	let response = String::from_utf8_lossy(&response);
+	let status = response.lines().next().unwrap_or("");
+	match status.split_whitespace().nth(1) {
+		Some("200") => Ok(()),
+		_ => Err(proxy_error(format!("HTTP proxy refused the connection to {}: {}", target, status))),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::{self, Cursor, Read, Write};
+	use super::{Proxy, socks5_handshake, http_handshake};
+
+	struct MockStream {
+		input: Cursor<Vec<u8>>,
+		output: Vec<u8>,
+	}
+
+	impl MockStream {
+		fn new(input: &[u8]) -> Self {
+			MockStream {
+				input: Cursor::new(input.to_vec()),
+				output: Vec::new(),
+			}
+		}
+	}
+
+	impl Read for MockStream {
+		fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+			self.input.read(buf)
+		}
+	}
+
+	impl Write for MockStream {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.output.write(buf)
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn should_parse_proxy() {
+		assert_eq!("127.0.0.1:9050".parse(), Ok(Proxy::Socks5("127.0.0.1:9050".parse().unwrap())));
+		assert_eq!("socks5://127.0.0.1:9050".parse(), Ok(Proxy::Socks5("127.0.0.1:9050".parse().unwrap())));
+		assert_eq!("http://[::1]:3128/".parse(), Ok(Proxy::Http("[::1]:3128".parse().unwrap())));
+		assert!("ftp://127.0.0.1:21".parse::<Proxy>().is_err());
+		assert_eq!(format!("{}", Proxy::Http("[::1]:3128".parse().unwrap())), "http://[::1]:3128");
+	}
+
+	#[test]
+	fn should_connect_through_socks5() {
+		let mut stream = MockStream::new(&[5, 0, 5, 0, 0, 1, 10, 0, 0, 1, 0x76, 0x5f]);
+		socks5_handshake(&mut stream, &"1.2.3.4:30303".parse().unwrap()).unwrap();
+		assert_eq!(stream.output, vec![5, 1, 0, 5, 1, 0, 1, 1, 2, 3, 4, 0x76, 0x5f]);
+		// whole reply consumed.
+		assert_eq!(stream.input.position(), 12);
+	}
+
+	#[test]
+	fn should_fail_when_socks5_refuses() {
+		let mut stream = MockStream::new(&[5, 0, 5, 5, 0, 1, 0, 0, 0, 0, 0, 0]);
+		assert!(socks5_handshake(&mut stream, &"1.2.3.4:30303".parse().unwrap()).is_err());
+
+		let mut stream = MockStream::new(&[5, 0xff]);
+		assert!(socks5_handshake(&mut stream, &"1.2.3.4:30303".parse().unwrap()).is_err());
+	}
+
+	#[test]
+	fn should_connect_through_http() {
+		let mut stream = MockStream::new(b"HTTP/1.1 200 Connection established\r\n\r\nrest");
+		http_handshake(&mut stream, &"[::1]:30303".parse().unwrap()).unwrap();
+		assert_eq!(stream.output, b"CONNECT [::1]:30303 HTTP/1.1\r\nHost: [::1]:30303\r\n\r\n".to_vec());
+		// data past the headers is left for the session.
+		let mut rest = String::new();
+		stream.read_to_string(&mut rest).unwrap();
+		assert_eq!(rest, "rest");
+
+		let mut stream = MockStream::new(b"HTTP/1.1 403 Forbidden\r\n\r\n");
+		assert!(http_handshake(&mut stream, &"1.2.3.4:30303".parse().unwrap()).is_err());
+	}
+}
@@ -17,10 +17,13 @@
 use {NetworkProtocolHandler, NetworkConfiguration, NonReservedPeerMode};
 use error::NetworkError;
 use host::{Host, NetworkContext, NetworkIoMessage, ProtocolId};
+use connection_filter::ConnectionFilter;
+use node_table::Node;
 use stats::NetworkStats;
 use io::*;
 use parking_lot::RwLock;
 use std::sync::Arc;
+use std::str::FromStr;
 use ansi_term::Colour;
 
 struct HostHandler {
@@ -49,11 +52,12 @@ pub struct NetworkService {
 	panic_handler: Arc<PanicHandler>,
 	host_handler: Arc<HostHandler>,
 	config: NetworkConfiguration,
+	filter: Option<Arc<ConnectionFilter>>,
 }
 
 impl NetworkService {
 	/// Starts IO event loop
-	pub fn new(config: NetworkConfiguration) -> Result<NetworkService, NetworkError> {
+	pub fn new(config: NetworkConfiguration, filter: Option<Arc<ConnectionFilter>>) -> Result<NetworkService, NetworkError> {
 		let host_handler = Arc::new(HostHandler { public_url: RwLock::new(None) });
 		let panic_handler = PanicHandler::new_in_arc();
 		let io_service = try!(IoService::<NetworkIoMessage>::start());
@@ -69,6 +73,7 @@ impl NetworkService {
 			host: RwLock::new(None),
 			config: config,
 			host_handler: host_handler,
+			filter: filter,
 		})
 	}
 
@@ -109,6 +114,12 @@ impl NetworkService {
 		host.as_ref().and_then(|h| h.external_url())
 	}
 
+	/// Returns external urls of all the public endpoints.
+	pub fn external_urls(&self) -> Vec<String> {
+		let host = self.host.read();
+		host.as_ref().map_or_else(Vec::new, |h| h.external_urls())
+	}
+
 	/// Returns external url if available.
 	pub fn local_url(&self) -> Option<String> {
 		let host = self.host.read();
@@ -119,7 +130,7 @@ impl NetworkService {
 	pub fn start(&self) -> Result<(), NetworkError> {
 		let mut host = self.host.write();
 		if host.is_none() {
-			let h = Arc::new(try!(Host::new(self.config.clone(), self.stats.clone())));
+			let h = Arc::new(try!(Host::new(self.config.clone(), self.stats.clone(), self.filter.clone())));
 			try!(self.io_service.register_handler(h.clone()));
 			*host = Some(h);
 		}
@@ -142,6 +153,17 @@ impl NetworkService {
 		Ok(())
 	}
 
+	/// Add a boot node at runtime. The node is validated even if the network is not running.
+	pub fn add_boot_node(&self, node: &str) -> Result<(), NetworkError> {
+		try!(Node::from_str(node));
+		let host = self.host.read();
+		if let Some(ref host) = *host {
+			host.add_node(node)
+		} else {
+			Ok(())
+		}
+	}
+
 	/// Try to add a reserved peer.
 	pub fn add_reserved_peer(&self, peer: &str) -> Result<(), NetworkError> {
 		let host = self.host.read();
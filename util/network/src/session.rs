@@ -48,6 +48,8 @@ pub struct Session {
 	expired: bool,
 	ping_time_ns: u64,
 	pong_time_ns: Option<u64>,
+	/// Time the session keys were established.
+	established_ns: Option<u64>,
 	state: State,
 }
 
@@ -185,6 +187,7 @@ impl Session {
 			},
 			ping_time_ns: 0,
 			pong_time_ns: None,
+			established_ns: None,
 			expired: false,
 		})
 	}
@@ -198,6 +201,7 @@ impl Session {
 			panic!("Unexpected state");
 		};
 		self.state = State::Session(connection);
+		self.established_ns = Some(time::precise_time_ns());
 		try!(self.write_hello(io, host));
 		try!(self.send_ping(io));
 		Ok(())
@@ -233,6 +237,11 @@ impl Session {
 		}
 	}
 
+	/// Number of seconds since the session keys were established. `None` while the handshake is in progress.
+	pub fn key_age_sec(&self) -> Option<u64> {
+		self.established_ns.map(|t| (time::precise_time_ns() - t) / 1000_000_000)
+	}
+
 	/// Check if this session is over and there is nothing to be sent.
 	pub fn done(&self) -> bool {
 		self.expired() && !self.connection().is_sending()
@@ -26,6 +26,8 @@ pub struct NetworkStats {
 	send: AtomicUsize,
 	/// Total number of sessions created
 	sessions: AtomicUsize,
+	/// Total number of sessions closed to renew their keys
+	rekeys: AtomicUsize,
 }
 
 impl NetworkStats {
@@ -47,6 +49,12 @@ impl NetworkStats {
 		self.sessions.fetch_add(1, Ordering::Relaxed);
 	}
 
+	/// Increase number of sessions closed to renew their keys.
+	#[inline]
+	pub fn inc_rekeys(&self) {
+		self.rekeys.fetch_add(1, Ordering::Relaxed);
+	}
+
 	/// Get bytes sent.
 	#[inline]
 	pub fn send(&self) -> usize {
@@ -65,12 +73,19 @@ impl NetworkStats {
 		self.sessions.load(Ordering::Relaxed)
 	}
 
+	/// Get total number of sessions closed to renew their keys.
+	#[inline]
+	pub fn rekeys(&self) -> usize {
+		self.rekeys.load(Ordering::Relaxed)
+	}
+
 	/// Create a new empty instance.
 	pub fn new() -> NetworkStats {
 		NetworkStats {
 			recv: AtomicUsize::new(0),
 			send: AtomicUsize::new(0),
 			sessions: AtomicUsize::new(0),
+			rekeys: AtomicUsize::new(0),
 		}
 	}
 }
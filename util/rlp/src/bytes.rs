@@ -22,7 +22,7 @@ use std::fmt;
 use std::cmp::Ordering;
 use std::error::Error as StdError;
 use bigint::uint::{Uint, U128, U256};
-use bigint::hash::{H64, H128, H160, H256, H512, H520, H2048};
+use bigint::hash::{H32, H64, H128, H160, H256, H512, H520, H2048};
 use elastic_array::*;
 
 /// Vector like object
@@ -157,6 +157,7 @@ macro_rules! impl_hash_to_bytes {
 	}
 }
 
+impl_hash_to_bytes!(H32);
 impl_hash_to_bytes!(H64);
 impl_hash_to_bytes!(H128);
 impl_hash_to_bytes!(H160);
@@ -282,6 +283,7 @@ macro_rules! impl_hash_from_bytes {
 	}
 }
 
+impl_hash_from_bytes!(H32, 4);
 impl_hash_from_bytes!(H64, 8);
 impl_hash_from_bytes!(H128, 16);
 impl_hash_from_bytes!(H160, 20);
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-//! Key-Value store abstraction with `RocksDB` backend.
+//! Key-Value store abstraction with `RocksDB` and in-memory backends.
 
 use std::io::ErrorKind;
 use common::*;
@@ -34,6 +34,10 @@ use std::fs::File;
 
 const DB_BACKGROUND_FLUSHES: i32 = 2;
 const DB_BACKGROUND_COMPACTIONS: i32 = 2;
+/// Default limit of the total size of write-ahead logs, in bytes.
+const DB_DEFAULT_WAL_SIZE: u64 = 64 * 1024 * 1024;
+/// Default block cache size (in MiB) of columns without a specific one.
+const DB_DEFAULT_CACHE_SIZE: usize = 2;
 
 /// Write transaction. Batches a sequence of put/delete operations for efficiency.
 pub struct DBTransaction {
@@ -59,7 +63,7 @@ enum DBOp {
 
 impl DBTransaction {
 	/// Create new transaction.
-	pub fn new(_db: &Database) -> DBTransaction {
+	pub fn new() -> DBTransaction {
 		DBTransaction {
 			ops: Vec::with_capacity(256),
 		}
@@ -116,6 +120,209 @@ enum KeyState {
 	Delete,
 }
 
+/// Generic key-value database.
+///
+/// Writes are done through `DBTransaction`s. Buffered writes are visible to `get` immediately,
+/// but are only persisted on `flush`. Iteration and prefix lookups see flushed data only.
+pub trait KeyValueDB: Sync + Send {
+	/// Helper to create a new transaction.
+	fn transaction(&self) -> DBTransaction { DBTransaction::new() }
+
+	/// Get a value by key.
+	fn get(&self, col: Option<u32>, key: &[u8]) -> Result<Option<DBValue>, String>;
+
+	/// Get values of several keys. Implementations may read them in a single batch.
+	fn get_many(&self, col: Option<u32>, keys: &[&[u8]]) -> Result<Vec<Option<DBValue>>, String> {
+		keys.iter().map(|key| self.get(col, key)).collect()
+	}
+
+	/// Get a value by partial key. Only works for flushed data.
+	fn get_by_prefix(&self, col: Option<u32>, prefix: &[u8]) -> Option<Box<[u8]>>;
+
+	/// Write a transaction of changes to the buffer.
+	fn write_buffered(&self, transaction: DBTransaction);
+
+	/// Write a transaction of changes to the backing store.
+	fn write(&self, transaction: DBTransaction) -> Result<(), String> {
+		self.write_buffered(transaction);
+		self.flush()
+	}
+
+	/// Flush all buffered data.
+	fn flush(&self) -> Result<(), String>;
+
+	/// Iterate over flushed data for a given column.
+	fn iter<'a>(&'a self, col: Option<u32>) -> Box<Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a>;
+
+	/// Iterate over flushed data for a given column, in key order, over keys starting with given prefix.
+	fn iter_from_prefix<'a>(&'a self, col: Option<u32>, prefix: &'a [u8]) -> Box<Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a>;
+
+	/// Attempt to replace this database with a new one located at the given path.
+	fn restore(&self, new_db: &str) -> Result<(), UtilError>;
+
+	/// Flush and compact all columns.
+	fn compact(&self) -> Result<(), String> { self.flush() }
+
+	/// Estimated number of keys and size of every column.
+	fn column_stats(&self) -> Vec<ColumnStats>;
+}
+
+/// Estimated statistics of a single database column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats {
+	/// Column index.
+	pub column: Option<u32>,
+	/// Estimated number of keys.
+	pub estimated_keys: u64,
+	/// Estimated size of live data, in bytes.
+	pub estimated_size: u64,
+}
+
+/// A key-value database fulfilling the `KeyValueDB` trait, living in memory.
+/// This is generally intended for tests and is not particularly optimized.
+pub struct InMemory {
+	columns: RwLock<HashMap<Option<u32>, BTreeMap<Vec<u8>, DBValue>>>,
+}
+
+/// Create an in-memory database with the given number of columns.
+/// Columns will be indexable by 0..`num_cols`
+pub fn in_memory(num_cols: u32) -> InMemory {
+	let mut cols = HashMap::new();
+	cols.insert(None, BTreeMap::new());
+
+	for idx in 0..num_cols {
+		cols.insert(Some(idx), BTreeMap::new());
+	}
+
+	InMemory {
+		columns: RwLock::new(cols)
+	}
+}
+
+impl KeyValueDB for InMemory {
+	fn get(&self, col: Option<u32>, key: &[u8]) -> Result<Option<DBValue>, String> {
+		let columns = self.columns.read();
+		match columns.get(&col) {
+			None => Err(format!("No such column family: {:?}", col)),
+			Some(map) => Ok(map.get(key).cloned()),
+		}
+	}
+
+	fn get_by_prefix(&self, col: Option<u32>, prefix: &[u8]) -> Option<Box<[u8]>> {
+		let columns = self.columns.read();
+		match columns.get(&col) {
+			None => None,
+			Some(map) => map.iter()
+				.find(|&(ref k, _)| k.starts_with(prefix))
+				.map(|(_, v)| v.to_vec().into_boxed_slice())
+		}
+	}
+
+	fn write_buffered(&self, transaction: DBTransaction) {
+		let mut columns = self.columns.write();
+		let ops = transaction.ops;
+		for op in ops {
+			match op {
+				DBOp::Insert { col, key, value } => {
+					if let Some(col) = columns.get_mut(&col) {
+						col.insert(key.to_vec(), value);
+					}
+				},
+				DBOp::InsertCompressed { col, key, value } => {
+					if let Some(col) = columns.get_mut(&col) {
+						let compressed = UntrustedRlp::new(&value).compress(RlpType::Blocks);
+						col.insert(key.to_vec(), DBValue::from_slice(&compressed));
+					}
+				},
+				DBOp::Delete { col, key } => {
+					if let Some(col) = columns.get_mut(&col) {
+						col.remove(&*key);
+					}
+				},
+			}
+		}
+	}
+
+	fn flush(&self) -> Result<(), String> { Ok(()) }
+
+	fn iter<'a>(&'a self, col: Option<u32>) -> Box<Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a> {
+		match self.columns.read().get(&col) {
+			Some(map) => Box::new( // TODO: worth optimizing at all?
+				map.iter()
+					.map(|(k, v)| (k.clone().into_boxed_slice(), v.to_vec().into_boxed_slice()))
+					.collect::<Vec<_>>()
+					.into_iter()
+			),
+			None => Box::new(None.into_iter()),
+		}
+	}
+
+	fn iter_from_prefix<'a>(&'a self, col: Option<u32>, prefix: &'a [u8]) -> Box<Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a> {
+		match self.columns.read().get(&col) {
+			Some(map) => Box::new(
+				map.iter()
+					.filter(|&(k, _)| k.starts_with(prefix))
+					.map(|(k, v)| (k.clone().into_boxed_slice(), v.to_vec().into_boxed_slice()))
+					.collect::<Vec<_>>()
+					.into_iter()
+			),
+			None => Box::new(None.into_iter()),
+		}
+	}
+
+	fn restore(&self, _new_db: &str) -> Result<(), UtilError> {
+		Err(UtilError::SimpleString("Attempted to restore in-memory database".into()))
+	}
+
+	fn column_stats(&self) -> Vec<ColumnStats> {
+		let mut stats: Vec<_> = self.columns.read().iter()
+			.map(|(col, map)| ColumnStats {
+				column: *col,
+				estimated_keys: map.len() as u64,
+				estimated_size: map.iter().map(|(k, v)| (k.len() + v.len()) as u64).sum(),
+			})
+			.collect();
+		stats.sort_by_key(|s| s.column);
+		stats
+	}
+}
+
+/// Compaction style of the database.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CompactionStyle {
+	/// Leveled compaction; lower space amplification, more write amplification.
+	Level,
+	/// Universal compaction; less write amplification, needs more free space.
+	Universal,
+}
+
+impl Default for CompactionStyle {
+	fn default() -> Self {
+		CompactionStyle::Universal
+	}
+}
+
+impl str::FromStr for CompactionStyle {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"level" => Ok(CompactionStyle::Level),
+			"universal" => Ok(CompactionStyle::Universal),
+			_ => Err("Invalid compaction style given. Expected level/universal.".into()),
+		}
+	}
+}
+
+impl CompactionStyle {
+	fn to_rocksdb(&self) -> DBCompactionStyle {
+		match *self {
+			CompactionStyle::Level => DBCompactionStyle::DBLevelCompaction,
+			CompactionStyle::Universal => DBCompactionStyle::DBUniversalCompaction,
+		}
+	}
+}
+
 /// Compaction profile for the database settings
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct CompactionProfile {
@@ -213,12 +420,18 @@ pub struct DatabaseConfig {
 	pub max_open_files: i32,
 	/// Cache sizes (in MiB) for specific columns.
 	pub cache_sizes: HashMap<Option<u32>, usize>,
+	/// Block cache size (in MiB) of columns without a specific one.
+	pub default_cache_size: usize,
 	/// Compaction profile
 	pub compaction: CompactionProfile,
+	/// Compaction style
+	pub compaction_style: CompactionStyle,
 	/// Set number of columns
 	pub columns: Option<u32>,
 	/// Should we keep WAL enabled?
 	pub wal: bool,
+	/// Limit of the total size of write-ahead logs, in bytes.
+	pub max_wal_size: u64,
 }
 
 impl DatabaseConfig {
@@ -240,10 +453,13 @@ impl Default for DatabaseConfig {
 	fn default() -> DatabaseConfig {
 		DatabaseConfig {
 			cache_sizes: HashMap::new(),
+			default_cache_size: DB_DEFAULT_CACHE_SIZE,
 			max_open_files: 512,
 			compaction: CompactionProfile::default(),
+			compaction_style: CompactionStyle::default(),
 			columns: None,
 			wal: true,
+			max_wal_size: DB_DEFAULT_WAL_SIZE,
 		}
 	}
 }
@@ -290,14 +506,11 @@ impl Database {
 
 	/// Open database file. Creates if it does not exist.
 	pub fn open(config: &DatabaseConfig, path: &str) -> Result<Database, String> {
-		// default cache size for columns not specified.
-		const DEFAULT_CACHE: usize = 2;
-
 		let mut opts = Options::new();
 		if let Some(rate_limit) = config.compaction.write_rate_limit {
 			try!(opts.set_parsed_options(&format!("rate_limiter_bytes_per_sec={}", rate_limit)));
 		}
-		try!(opts.set_parsed_options(&format!("max_total_wal_size={}", 64 * 1024 * 1024)));
+		try!(opts.set_parsed_options(&format!("max_total_wal_size={}", config.max_wal_size)));
 		try!(opts.set_parsed_options("verify_checksums_in_compaction=0"));
 		opts.set_max_open_files(config.max_open_files);
 		opts.create_if_missing(true);
@@ -307,7 +520,7 @@ impl Database {
 		opts.set_max_background_compactions(DB_BACKGROUND_COMPACTIONS);
 
 		// compaction settings
-		opts.set_compaction_style(DBCompactionStyle::DBUniversalCompaction);
+		opts.set_compaction_style(config.compaction_style.to_rocksdb());
 		opts.set_target_file_size_base(config.compaction.initial_file_size);
 		opts.set_target_file_size_multiplier(config.compaction.file_size_multiplier);
 
@@ -317,14 +530,14 @@ impl Database {
 
 		for col in 0 .. config.columns.unwrap_or(0) {
 			let mut opts = Options::new();
-			opts.set_compaction_style(DBCompactionStyle::DBUniversalCompaction);
+			opts.set_compaction_style(config.compaction_style.to_rocksdb());
 			opts.set_target_file_size_base(config.compaction.initial_file_size);
 			opts.set_target_file_size_multiplier(config.compaction.file_size_multiplier);
 
 			let col_opt = config.columns.map(|_| col);
 
 			{
-				let cache_size = config.cache_sizes.get(&col_opt).cloned().unwrap_or(DEFAULT_CACHE);
+				let cache_size = config.cache_sizes.get(&col_opt).cloned().unwrap_or(config.default_cache_size);
 				let mut block_opts = BlockBasedOptions::new();
 				// all goes to read cache.
 				block_opts.set_cache(Cache::new(cache_size * 1024 * 1024));
@@ -395,7 +608,7 @@ impl Database {
 
 	/// Creates new transaction for this database.
 	pub fn transaction(&self) -> DBTransaction {
-		DBTransaction::new(self)
+		DBTransaction::new()
 	}
 
 
@@ -539,6 +752,24 @@ impl Database {
 		}
 	}
 
+	/// Get values of several keys, taking the database and overlay locks only once.
+	pub fn get_many(&self, col: Option<u32>, keys: &[&[u8]]) -> Result<Vec<Option<DBValue>>, String> {
+		match *self.db.read() {
+			Some(DBAndColumns { ref db, ref cfs }) => {
+				let overlay = &self.overlay.read()[Self::to_overlay_column(col)];
+				let flushing = &self.flushing.read()[Self::to_overlay_column(col)];
+				keys.iter().map(|key| match overlay.get(*key).or_else(|| flushing.get(*key)) {
+					Some(&KeyState::Insert(ref value)) | Some(&KeyState::InsertCompressed(ref value)) => Ok(Some(value.clone())),
+					Some(&KeyState::Delete) => Ok(None),
+					None => col.map_or_else(
+						|| db.get_opt(key, &self.read_opts).map(|r| r.map(|v| DBValue::from_slice(&v))),
+						|c| db.get_cf_opt(cfs[c as usize], key, &self.read_opts).map(|r| r.map(|v| DBValue::from_slice(&v)))),
+				}).collect()
+			},
+			None => Ok(keys.iter().map(|_| None).collect()),
+		}
+	}
+
 	/// Get value by partial key. Prefix size should match configured prefix size. Only searches flushed values.
 	// TODO: support prefix seek for unflushed data
 	pub fn get_by_prefix(&self, col: Option<u32>, prefix: &[u8]) -> Option<Box<[u8]>> {
@@ -570,6 +801,57 @@ impl Database {
 		}
 	}
 
+	/// Get database iterator for flushed data, starting from given key.
+	pub fn iter_from_prefix(&self, col: Option<u32>, prefix: &[u8]) -> Option<DatabaseIterator> {
+		match *self.db.read() {
+			Some(DBAndColumns { ref db, ref cfs }) => {
+				let iter = col.map_or_else(|| db.iterator_opt(IteratorMode::From(prefix, Direction::Forward), &self.read_opts),
+					|c| db.iterator_cf_opt(cfs[c as usize], IteratorMode::From(prefix, Direction::Forward), &self.read_opts)
+						.expect("iterator params are valid; qed"));
+				Some(DatabaseIterator { iter: iter })
+			},
+			None => None,
+		}
+	}
+
+	/// Flush buffered data and compact all columns.
+	pub fn compact(&self) -> Result<(), String> {
+		try!(self.flush());
+		match *self.db.read() {
+			Some(DBAndColumns { ref db, ref cfs }) => {
+				if cfs.is_empty() {
+					db.compact_range(None, None);
+				}
+				for cf in cfs {
+					db.compact_range_cf(*cf, None, None);
+				}
+				Ok(())
+			},
+			None => Err("Database is closed".into()),
+		}
+	}
+
+	/// Estimated number of keys and size of every column.
+	pub fn column_stats(&self) -> Vec<ColumnStats> {
+		fn stat(value: Result<Option<u64>, String>) -> u64 {
+			value.ok().and_then(|v| v).unwrap_or(0)
+		}
+
+		match *self.db.read() {
+			Some(DBAndColumns { ref db, ref cfs }) if cfs.is_empty() => vec![ColumnStats {
+				column: None,
+				estimated_keys: stat(db.property_int_value("rocksdb.estimate-num-keys")),
+				estimated_size: stat(db.property_int_value("rocksdb.estimate-live-data-size")),
+			}],
+			Some(DBAndColumns { ref db, ref cfs }) => cfs.iter().enumerate().map(|(i, cf)| ColumnStats {
+				column: Some(i as u32),
+				estimated_keys: stat(db.property_int_value_cf(*cf, "rocksdb.estimate-num-keys")),
+				estimated_size: stat(db.property_int_value_cf(*cf, "rocksdb.estimate-live-data-size")),
+			}).collect(),
+			None => Vec::new(),
+		}
+	}
+
 	/// Close the database
 	fn close(&self) {
 		*self.db.write() = None;
@@ -619,6 +901,56 @@ impl Database {
 	}
 }
 
+// duplicate declaration of methods here to avoid trait import in certain existing cases
+// at time of addition.
+impl KeyValueDB for Database {
+	fn get(&self, col: Option<u32>, key: &[u8]) -> Result<Option<DBValue>, String> {
+		Database::get(self, col, key)
+	}
+
+	fn get_many(&self, col: Option<u32>, keys: &[&[u8]]) -> Result<Vec<Option<DBValue>>, String> {
+		Database::get_many(self, col, keys)
+	}
+
+	fn get_by_prefix(&self, col: Option<u32>, prefix: &[u8]) -> Option<Box<[u8]>> {
+		Database::get_by_prefix(self, col, prefix)
+	}
+
+	fn write_buffered(&self, transaction: DBTransaction) {
+		Database::write_buffered(self, transaction)
+	}
+
+	fn write(&self, transaction: DBTransaction) -> Result<(), String> {
+		Database::write(self, transaction)
+	}
+
+	fn flush(&self) -> Result<(), String> {
+		Database::flush(self)
+	}
+
+	fn iter<'a>(&'a self, col: Option<u32>) -> Box<Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a> {
+		let unboxed = Database::iter(self, col);
+		Box::new(unboxed)
+	}
+
+	fn iter_from_prefix<'a>(&'a self, col: Option<u32>, prefix: &'a [u8]) -> Box<Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a> {
+		let unboxed = Database::iter_from_prefix(self, col, prefix);
+		Box::new(unboxed.into_iter().flat_map(|inner| inner).take_while(move |&(ref k, _)| k.starts_with(prefix)))
+	}
+
+	fn restore(&self, new_db: &str) -> Result<(), UtilError> {
+		Database::restore(self, new_db)
+	}
+
+	fn compact(&self) -> Result<(), String> {
+		Database::compact(self)
+	}
+
+	fn column_stats(&self) -> Vec<ColumnStats> {
+		Database::column_stats(self)
+	}
+}
+
 impl Drop for Database {
 	fn drop(&mut self) {
 		// write all buffered changes if we can.
@@ -674,6 +1006,10 @@ mod tests {
 		assert_eq!(&*db.get_by_prefix(None, &key3).unwrap(), b"elephant");
 		assert_eq!(&*db.get_by_prefix(None, &key2).unwrap(), b"dog");
 
+		let prefixed: Vec<_> = KeyValueDB::iter_from_prefix(&db, None, &key2[0..1]).collect();
+		assert_eq!(prefixed.len(), 1);
+		assert_eq!(&*prefixed[0].1, b"dog");
+
 		let mut transaction = db.transaction();
 		transaction.put(None, &key1, b"horse");
 		transaction.delete(None, &key3);
@@ -681,11 +1017,48 @@ mod tests {
 		assert!(db.get(None, &key3).unwrap().is_none());
 		assert_eq!(&*db.get(None, &key1).unwrap().unwrap(), b"horse");
 
+		let values = db.get_many(None, &[&key1[..], &key2[..], &key3[..]]).unwrap();
+		assert_eq!(&**values[0].as_ref().unwrap(), b"horse");
+		assert_eq!(&**values[1].as_ref().unwrap(), b"dog");
+		assert!(values[2].is_none());
+
 		db.flush().unwrap();
 		assert!(db.get(None, &key3).unwrap().is_none());
 		assert_eq!(&*db.get(None, &key1).unwrap().unwrap(), b"horse");
 	}
 
+	#[test]
+	fn in_memory_kvdb() {
+		let db = in_memory(1);
+		let key1 = H256::from_str("02c69be41d0b7e40352fc85be1cd65eb03d40ef8427a0ca4596b1ead9a00e9fc").unwrap();
+		let key2 = H256::from_str("03c69be41d0b7e40352fc85be1cd65eb03d40ef8427a0ca4596b1ead9a00e9fc").unwrap();
+
+		let mut batch = db.transaction();
+		batch.put(Some(0), &key1, b"cat");
+		batch.put(Some(0), &key2, b"dog");
+		db.write(batch).unwrap();
+
+		assert_eq!(&*db.get(Some(0), &key1).unwrap().unwrap(), b"cat");
+		assert!(db.get(None, &key1).unwrap().is_none());
+		assert!(db.get(Some(1), &key1).is_err());
+		assert_eq!(&*db.get_by_prefix(Some(0), &key2[0..12]).unwrap(), b"dog");
+
+		let contents: Vec<_> = db.iter(Some(0)).collect();
+		assert_eq!(contents.len(), 2);
+		assert_eq!(&*contents[0].0, &*key1);
+		assert_eq!(&*contents[1].1, b"dog");
+
+		let mut batch = db.transaction();
+		batch.delete(Some(0), &key1);
+		db.write_buffered(batch);
+		assert!(db.get(Some(0), &key1).unwrap().is_none());
+		assert!(db.restore("/nonexistent").is_err());
+
+		let stats = db.column_stats();
+		assert_eq!(stats.len(), 2);
+		assert_eq!(stats[1], ColumnStats { column: Some(0), estimated_keys: 1, estimated_size: 35 });
+	}
+
 	#[test]
 	fn kvdb() {
 		let path = RandomTempPath::create_dir();
@@ -145,6 +145,8 @@ pub mod log;
 pub mod path;
 pub mod snappy;
 pub mod stats;
+pub mod metrics;
+pub mod ntp;
 pub mod cache;
 mod timer;
 
@@ -0,0 +1,314 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Process-wide metrics registry.
+//!
+//! Counters, gauges and histograms are registered lazily by name (and optional labels)
+//! and can be rendered in the Prometheus text exposition format.
+
+use std::fmt::Write;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, AtomicIsize, Ordering};
+use std::time::Duration;
+use parking_lot::{Mutex, RwLock};
+
+/// Default histogram buckets, in seconds.
+pub const DEFAULT_BUCKETS: &'static [f64] = &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Monotonically increasing counter.
+#[derive(Debug, Default)]
+pub struct Counter(AtomicUsize);
+
+impl Counter {
+	/// Increment by one.
+	pub fn inc(&self) {
+		self.add(1);
+	}
+
+	/// Increment by `n`.
+	pub fn add(&self, n: usize) {
+		self.0.fetch_add(n, Ordering::Relaxed);
+	}
+
+	/// Current value.
+	pub fn get(&self) -> usize {
+		self.0.load(Ordering::Relaxed)
+	}
+}
+
+/// Value which may go up and down.
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicIsize);
+
+impl Gauge {
+	/// Set to given value.
+	pub fn set(&self, v: isize) {
+		self.0.store(v, Ordering::Relaxed);
+	}
+
+	/// Increment by one.
+	pub fn inc(&self) {
+		self.0.fetch_add(1, Ordering::Relaxed);
+	}
+
+	/// Decrement by one.
+	pub fn dec(&self) {
+		self.0.fetch_sub(1, Ordering::Relaxed);
+	}
+
+	/// Current value.
+	pub fn get(&self) -> isize {
+		self.0.load(Ordering::Relaxed)
+	}
+}
+
+#[derive(Debug)]
+struct HistogramInner {
+	counts: Vec<u64>,
+	sum: f64,
+	count: u64,
+}
+
+/// Cumulative histogram of observed values.
+#[derive(Debug)]
+pub struct Histogram {
+	bounds: Vec<f64>,
+	inner: Mutex<HistogramInner>,
+}
+
+impl Histogram {
+	/// Create a new histogram with given upper bucket bounds (sorted ascending).
+	pub fn new(bounds: &[f64]) -> Self {
+		Histogram {
+			bounds: bounds.to_vec(),
+			inner: Mutex::new(HistogramInner {
+				counts: vec![0; bounds.len()],
+				sum: 0.0,
+				count: 0,
+			}),
+		}
+	}
+
+	/// Record an observation.
+	pub fn observe(&self, v: f64) {
+		let mut inner = self.inner.lock();
+		if let Some(pos) = self.bounds.iter().position(|b| v <= *b) {
+			inner.counts[pos] += 1;
+		}
+		inner.sum += v;
+		inner.count += 1;
+	}
+
+	/// Record a duration, in seconds.
+	pub fn observe_duration(&self, d: Duration) {
+		self.observe(d.as_secs() as f64 + d.subsec_nanos() as f64 / 1_000_000_000f64);
+	}
+
+	/// Total number of observations.
+	pub fn count(&self) -> u64 {
+		self.inner.lock().count
+	}
+
+	/// Sum of all observations.
+	pub fn sum(&self) -> f64 {
+		self.inner.lock().sum
+	}
+}
+
+#[derive(Debug, Clone)]
+enum Metric {
+	Counter(Arc<Counter>),
+	Gauge(Arc<Gauge>),
+	Histogram(Arc<Histogram>),
+}
+
+impl Metric {
+	fn kind(&self) -> &'static str {
+		match *self {
+			Metric::Counter(_) => "counter",
+			Metric::Gauge(_) => "gauge",
+			Metric::Histogram(_) => "histogram",
+		}
+	}
+}
+
+#[derive(Debug)]
+struct Family {
+	help: String,
+	series: BTreeMap<String, Metric>,
+}
+
+/// Collection of named metric families.
+#[derive(Debug, Default)]
+pub struct Metrics {
+	families: RwLock<BTreeMap<String, Family>>,
+}
+
+fn format_labels(labels: &[(&str, &str)]) -> String {
+	if labels.is_empty() {
+		return String::new();
+	}
+	let inner: Vec<String> = labels.iter()
+		.map(|&(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\"")))
+		.collect();
+	format!("{{{}}}", inner.join(","))
+}
+
+impl Metrics {
+	fn get_or_insert<F>(&self, name: &str, help: &str, labels: &[(&str, &str)], create: F) -> Metric where F: FnOnce() -> Metric {
+		let labels = format_labels(labels);
+		if let Some(m) = self.families.read().get(name).and_then(|f| f.series.get(&labels)) {
+			return m.clone();
+		}
+
+		let mut families = self.families.write();
+		let family = families.entry(name.to_owned()).or_insert_with(|| Family {
+			help: help.to_owned(),
+			series: BTreeMap::new(),
+		});
+		family.series.entry(labels).or_insert_with(create).clone()
+	}
+
+	/// Get or register a counter.
+	pub fn counter(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Arc<Counter> {
+		match self.get_or_insert(name, help, labels, || Metric::Counter(Default::default())) {
+			Metric::Counter(c) => c,
+			_ => panic!("metric {} registered with a different type", name),
+		}
+	}
+
+	/// Get or register a gauge.
+	pub fn gauge(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Arc<Gauge> {
+		match self.get_or_insert(name, help, labels, || Metric::Gauge(Default::default())) {
+			Metric::Gauge(g) => g,
+			_ => panic!("metric {} registered with a different type", name),
+		}
+	}
+
+	/// Get or register a histogram. Buckets are only used on first registration.
+	pub fn histogram(&self, name: &str, help: &str, labels: &[(&str, &str)], buckets: &[f64]) -> Arc<Histogram> {
+		match self.get_or_insert(name, help, labels, || Metric::Histogram(Arc::new(Histogram::new(buckets)))) {
+			Metric::Histogram(h) => h,
+			_ => panic!("metric {} registered with a different type", name),
+		}
+	}
+
+	/// Render all metrics in the Prometheus text exposition format.
+	pub fn render(&self) -> String {
+		let mut out = String::new();
+		for (name, family) in self.families.read().iter() {
+			let kind = match family.series.values().next() {
+				Some(m) => m.kind(),
+				None => continue,
+			};
+			let _ = writeln!(out, "# HELP {} {}", name, family.help);
+			let _ = writeln!(out, "# TYPE {} {}", name, kind);
+			for (labels, metric) in &family.series {
+				match *metric {
+					Metric::Counter(ref c) => { let _ = writeln!(out, "{}{} {}", name, labels, c.get()); },
+					Metric::Gauge(ref g) => { let _ = writeln!(out, "{}{} {}", name, labels, g.get()); },
+					Metric::Histogram(ref h) => {
+						let inner = h.inner.lock();
+						let mut cumulative = 0;
+						for (bound, count) in h.bounds.iter().zip(inner.counts.iter()) {
+							cumulative += *count;
+							let _ = writeln!(out, "{}_bucket{} {}", name, with_le(labels, &bound.to_string()), cumulative);
+						}
+						let _ = writeln!(out, "{}_bucket{} {}", name, with_le(labels, "+Inf"), inner.count);
+						let _ = writeln!(out, "{}_sum{} {}", name, labels, inner.sum);
+						let _ = writeln!(out, "{}_count{} {}", name, labels, inner.count);
+					},
+				}
+			}
+		}
+		out
+	}
+}
+
+fn with_le(labels: &str, le: &str) -> String {
+	if labels.is_empty() {
+		format!("{{le=\"{}\"}}", le)
+	} else {
+		format!("{},le=\"{}\"}}", &labels[..labels.len() - 1], le)
+	}
+}
+
+lazy_static! {
+	static ref METRICS: Metrics = Metrics::default();
+}
+
+/// Process-wide metrics registry.
+pub fn metrics() -> &'static Metrics {
+	&METRICS
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use super::Metrics;
+
+	#[test]
+	fn should_reuse_registered_metric() {
+		let metrics = Metrics::default();
+		metrics.counter("test_total", "Test.", &[]).inc();
+		metrics.counter("test_total", "Test.", &[]).add(2);
+		assert_eq!(metrics.counter("test_total", "Test.", &[]).get(), 3);
+	}
+
+	#[test]
+	fn should_observe_durations_in_seconds() {
+		let metrics = Metrics::default();
+		let h = metrics.histogram("duration", "Duration.", &[], &[1.0]);
+		h.observe_duration(Duration::from_millis(1500));
+		assert_eq!(h.sum(), 1.5);
+	}
+
+	#[test]
+	fn should_render_counters_and_gauges() {
+		let metrics = Metrics::default();
+		metrics.counter("requests_total", "Requests served.", &[("kind", "headers")]).add(5);
+		metrics.gauge("peers", "Connected peers.", &[]).set(7);
+
+		assert_eq!(metrics.render(), "\
+# HELP peers Connected peers.
+# TYPE peers gauge
+peers 7
+# HELP requests_total Requests served.
+# TYPE requests_total counter
+requests_total{kind=\"headers\"} 5
+");
+	}
+
+	#[test]
+	fn should_render_cumulative_histogram() {
+		let metrics = Metrics::default();
+		let h = metrics.histogram("latency", "Latency.", &[("method", "eth_call")], &[0.1, 1.0]);
+		h.observe(0.05);
+		h.observe(0.5);
+		h.observe(2.0);
+
+		assert_eq!(metrics.render(), "\
+# HELP latency Latency.
+# TYPE latency histogram
+latency_bucket{method=\"eth_call\",le=\"0.1\"} 1
+latency_bucket{method=\"eth_call\",le=\"1\"} 2
+latency_bucket{method=\"eth_call\",le=\"+Inf\"} 3
+latency_sum{method=\"eth_call\"} 2.55
+latency_count{method=\"eth_call\"} 3
+");
+	}
+}
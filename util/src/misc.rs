@@ -32,15 +32,20 @@ pub enum Filth {
 	Dirty,
 }
 
+/// Get the platform identifier (e.g. `x86_64-linux-gnu`) for this build.
+pub fn platform() -> String {
+	let env = Target::env();
+	let env_dash = if env.is_empty() { "" } else { "-" };
+	format!("{}-{}{}{}", Target::arch(), Target::os(), env_dash, env)
+}
+
 /// Get the standard version string for this software.
 pub fn version() -> String {
 	let sha3 = short_sha();
 	let sha3_dash = if sha3.is_empty() { "" } else { "-" };
 	let commit_date = commit_date().replace("-", "");
 	let date_dash = if commit_date.is_empty() { "" } else { "-" };
-	let env = Target::env();
-	let env_dash = if env.is_empty() { "" } else { "-" };
-	format!("Parity/v{}-unstable{}{}{}{}/{}-{}{}{}/rustc{}", env!("CARGO_PKG_VERSION"), sha3_dash, sha3, date_dash, commit_date, Target::arch(), Target::os(), env_dash, env, rustc_version())
+	format!("Parity/v{}-unstable{}{}{}{}/{}/rustc{}", env!("CARGO_PKG_VERSION"), sha3_dash, sha3, date_dash, commit_date, platform(), rustc_version())
 }
 
 /// Get the standard version data for this software.
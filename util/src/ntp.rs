@@ -0,0 +1,149 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Minimal SNTP client for measuring the offset of the local clock.
+
+use std::io;
+use std::net::{UdpSocket, ToSocketAddrs};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use parking_lot::RwLock;
+
+/// Seconds between the NTP epoch (1900) and the UNIX epoch (1970).
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+const PACKET_SIZE: usize = 48;
+
+/// Clock drift (in ms) above which users should be warned.
+pub const DRIFT_WARNING_THRESHOLD_MS: i64 = 500;
+
+/// Timeout of a single NTP query.
+const QUERY_TIMEOUT_MS: u64 = 1_000;
+
+/// Milliseconds since the NTP epoch for the given system time.
+fn to_ntp_millis(time: SystemTime) -> i64 {
+	let since_unix = time.duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+	((since_unix.as_secs() + NTP_UNIX_OFFSET) * 1000 + since_unix.subsec_nanos() as u64 / 1_000_000) as i64
+}
+
+/// Read a 64-bit NTP timestamp as milliseconds since the NTP epoch.
+fn read_timestamp(buf: &[u8]) -> i64 {
+	let secs = buf[..4].iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+	let frac = buf[4..8].iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+	(secs * 1000 + (frac * 1000 >> 32)) as i64
+}
+
+/// Compute the clock offset from the four NTP timestamps (all in milliseconds):
+/// client send, server receive, server transmit and client receive.
+pub fn offset(t0: i64, t1: i64, t2: i64, t3: i64) -> i64 {
+	((t1 - t0) + (t2 - t3)) / 2
+}
+
+/// Query an NTP server (`host:port`) and return the offset of the local clock
+/// in milliseconds. A positive value means the local clock is behind the server.
+pub fn query_offset<A: ToSocketAddrs>(server: A, timeout: Duration) -> io::Result<i64> {
+	let socket = try!(UdpSocket::bind("0.0.0.0:0"));
+	try!(socket.set_read_timeout(Some(timeout)));
+	try!(socket.set_write_timeout(Some(timeout)));
+
+	let mut request = [0u8; PACKET_SIZE];
+	// LI = 0, VN = 3, Mode = 3 (client)
+	request[0] = 0x1b;
+
+	let t0 = to_ntp_millis(SystemTime::now());
+	try!(socket.send_to(&request, server));
+
+	let mut response = [0u8; PACKET_SIZE];
+	let (len, _) = try!(socket.recv_from(&mut response));
+	let t3 = to_ntp_millis(SystemTime::now());
+
+	if len < PACKET_SIZE || response[0] & 0x07 != 4 {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid NTP response"));
+	}
+
+	let t1 = read_timestamp(&response[32..40]);
+	let t2 = read_timestamp(&response[40..48]);
+	Ok(offset(t0, t1, t2, t3))
+}
+
+/// Periodically updated measurement of the local clock drift.
+pub struct TimeChecker {
+	server: String,
+	last: RwLock<Option<Result<i64, String>>>,
+}
+
+impl TimeChecker {
+	/// Creates new `TimeChecker` querying given NTP server (`host:port`).
+	pub fn new(server: String) -> Self {
+		TimeChecker {
+			server: server,
+			last: RwLock::new(None),
+		}
+	}
+
+	/// NTP server used for the measurements.
+	pub fn server(&self) -> &str {
+		&self.server
+	}
+
+	/// Query the NTP server and store the result.
+	pub fn update(&self) -> Result<i64, String> {
+		let result = query_offset(&*self.server, Duration::from_millis(QUERY_TIMEOUT_MS))
+			.map_err(|e| format!("{}: {}", self.server, e));
+		*self.last.write() = Some(result.clone());
+		result
+	}
+
+	/// Result of the last measurement, if any.
+	pub fn last(&self) -> Option<Result<i64, String>> {
+		self.last.read().clone()
+	}
+
+	/// Result of the last measurement, querying the server if there was none yet.
+	pub fn drift(&self) -> Result<i64, String> {
+		match self.last() {
+			Some(result) => result,
+			None => self.update(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{offset, read_timestamp, TimeChecker};
+
+	#[test]
+	fn should_compute_offset() {
+		// local clock 100ms behind, 20ms round trip.
+		assert_eq!(offset(1000, 1110, 1110, 1020), 100);
+		// local clock 50ms ahead.
+		assert_eq!(offset(1000, 960, 962, 1012), -45);
+	}
+
+	#[test]
+	fn should_read_timestamp() {
+		// 1 second and a half.
+		assert_eq!(read_timestamp(&[0, 0, 0, 1, 0x80, 0, 0, 0]), 1500);
+	}
+
+	#[test]
+	fn should_store_last_measurement() {
+		// nothing listens on the discard port, so the query fails.
+		let checker = TimeChecker::new("127.0.0.1:9".into());
+		assert!(checker.last().is_none());
+		assert!(checker.update().is_err());
+		assert!(checker.last().unwrap().is_err());
+	}
+}
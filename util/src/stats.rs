@@ -59,11 +59,32 @@ impl Histogram {
 	}
 }
 
+/// Values at given percentiles (between 0 and 100) of a corpus of `(value, weight)` pairs sorted by value.
+/// Each value is counted `weight` times. Zeros are returned for a weightless corpus.
+pub fn weighted_percentiles(corpus: &[(U256, u64)], percentiles: &[f64]) -> Vec<U256> {
+	let total = corpus.iter().fold(0u64, |acc, &(_, weight)| acc + weight);
+	if total == 0 {
+		return vec![U256::zero(); percentiles.len()];
+	}
+
+	percentiles.iter().map(|percentile| {
+		let threshold = total as f64 * percentile / 100.0;
+		let mut sum = 0;
+		for &(value, weight) in corpus {
+			sum += weight;
+			if sum as f64 >= threshold {
+				return value;
+			}
+		}
+		corpus.last().expect("total weight is non-zero, so corpus is not empty; qed").0
+	}).collect()
+}
+
 
 #[cfg(test)]
 mod tests {
 	use bigint::uint::U256;
-	use super::Histogram;
+	use super::{Histogram, weighted_percentiles};
 
 	#[test]
 	fn check_histogram() {
@@ -100,4 +121,17 @@ mod tests {
 	fn none_when_too_few_data() {
 		assert!(Histogram::new(slice_into![], 1).is_none());
 	}
+
+	#[test]
+	fn percentiles_are_weighted() {
+		let corpus = vec![(U256::from(1), 10), (U256::from(2), 80), (U256::from(3), 10)];
+		let expected: Vec<U256> = vec_into![1, 1, 2, 2, 3, 3];
+		assert_eq!(weighted_percentiles(&corpus, &[0.0, 10.0, 11.0, 50.0, 95.0, 100.0]), expected);
+	}
+
+	#[test]
+	fn percentiles_of_weightless_corpus_are_zero() {
+		assert_eq!(weighted_percentiles(&[(U256::from(5), 0)], &[50.0]), vec![U256::zero()]);
+		assert_eq!(weighted_percentiles(&[], &[]), Vec::<U256>::new());
+	}
 }